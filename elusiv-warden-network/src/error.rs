@@ -1,7 +1,14 @@
 use solana_program::program_error::ProgramError;
 use std::fmt;
 
-#[derive(Copy, Clone, Debug)]
+/// Offset of all [`ElusivWardenNetworkError`] codes
+///
+/// # Notes
+///
+/// The main program's `ElusivError` codes and the [`elusiv_types::TokenError`] codes (`100..`) are both located below this offset.
+pub const WARDEN_NETWORK_ERROR_OFFSET: u32 = 0x1000;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum ElusivWardenNetworkError {
     InvalidInstructionData = 0x01,
     InvalidSigner = 0x02,
@@ -14,7 +21,13 @@ pub enum ElusivWardenNetworkError {
     Overflow = 0x08,
     Underflow = 0x09,
 
-    /// Placeholder, [`elusiv_types::token::TokenError`] uses 0x1xx error codes
+    WardenAlreadyRegistered = 0x0a,
+    InvalidWardenID = 0x0b,
+    NotAWarden = 0x0c,
+    StatsYearMismatch = 0x0d,
+    UntrackableInstruction = 0x0e,
+
+    /// Placeholder, [`elusiv_types::TokenError`] uses 0x1xx error codes
     TokenError = 0x100,
 
     // APA inception errors
@@ -25,14 +38,107 @@ pub enum ElusivWardenNetworkError {
     WardenAlreadyConfirmed = 0x204,
 }
 
+impl ElusivWardenNetworkError {
+    pub fn code(&self) -> u32 {
+        WARDEN_NETWORK_ERROR_OFFSET + *self as u32
+    }
+}
+
 impl From<ElusivWardenNetworkError> for ProgramError {
     fn from(e: ElusivWardenNetworkError) -> Self {
-        ProgramError::Custom(e as u32)
+        ProgramError::Custom(e.code())
     }
 }
 
 impl fmt::Display for ElusivWardenNetworkError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", *self as u32)
+        write!(f, "{}", self.code())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use elusiv_types::TokenError;
+
+    const ERRORS: [ElusivWardenNetworkError; 20] = [
+        ElusivWardenNetworkError::InvalidInstructionData,
+        ElusivWardenNetworkError::InvalidSigner,
+        ElusivWardenNetworkError::WardenRegistrationError,
+        ElusivWardenNetworkError::ProposalError,
+        ElusivWardenNetworkError::VotingError,
+        ElusivWardenNetworkError::StatsError,
+        ElusivWardenNetworkError::TimestampError,
+        ElusivWardenNetworkError::Overflow,
+        ElusivWardenNetworkError::Underflow,
+        ElusivWardenNetworkError::WardenAlreadyRegistered,
+        ElusivWardenNetworkError::InvalidWardenID,
+        ElusivWardenNetworkError::NotAWarden,
+        ElusivWardenNetworkError::StatsYearMismatch,
+        ElusivWardenNetworkError::UntrackableInstruction,
+        ElusivWardenNetworkError::TokenError,
+        ElusivWardenNetworkError::ApplicationAlreadyComplete,
+        ElusivWardenNetworkError::InvalidConfirmationMessage,
+        ElusivWardenNetworkError::SignerAndWardenIdMismatch,
+        ElusivWardenNetworkError::NotInConfirmationPhase,
+        ElusivWardenNetworkError::WardenAlreadyConfirmed,
+    ];
+
+    #[test]
+    fn test_error_codes() {
+        assert_eq!(
+            ProgramError::from(ElusivWardenNetworkError::InvalidInstructionData),
+            ProgramError::Custom(0x1001)
+        );
+        assert_eq!(
+            ProgramError::from(ElusivWardenNetworkError::WardenAlreadyRegistered),
+            ProgramError::Custom(0x100a)
+        );
+        assert_eq!(
+            ProgramError::from(ElusivWardenNetworkError::InvalidWardenID),
+            ProgramError::Custom(0x100b)
+        );
+        assert_eq!(
+            ProgramError::from(ElusivWardenNetworkError::NotAWarden),
+            ProgramError::Custom(0x100c)
+        );
+        assert_eq!(
+            ProgramError::from(ElusivWardenNetworkError::StatsYearMismatch),
+            ProgramError::Custom(0x100d)
+        );
+        assert_eq!(
+            ProgramError::from(ElusivWardenNetworkError::UntrackableInstruction),
+            ProgramError::Custom(0x100e)
+        );
+        assert_eq!(
+            ProgramError::from(ElusivWardenNetworkError::WardenAlreadyConfirmed),
+            ProgramError::Custom(0x1204)
+        );
+        assert_eq!(
+            ElusivWardenNetworkError::NotAWarden.to_string(),
+            0x100c.to_string()
+        );
+    }
+
+    #[test]
+    fn test_error_code_ranges() {
+        // Codes are unique
+        for (i, a) in ERRORS.iter().enumerate() {
+            for b in &ERRORS[i + 1..] {
+                assert_ne!(a.code(), b.code());
+            }
+        }
+
+        // No collision with the `TokenError` codes (and the main program's codes below them)
+        let max_token_error_code = match ProgramError::from(TokenError::Overflow) {
+            ProgramError::Custom(code) => code,
+            _ => unreachable!(),
+        };
+        assert!(max_token_error_code < WARDEN_NETWORK_ERROR_OFFSET);
+
+        for e in ERRORS {
+            assert!(e.code() > WARDEN_NETWORK_ERROR_OFFSET);
+            assert!(e.code() < 2 * WARDEN_NETWORK_ERROR_OFFSET);
+        }
     }
 }
@@ -123,6 +123,7 @@ pub enum ElusivWardenNetworkInstruction {
     TrackBasicWardenStats {
         year: u16,
         can_fail: bool,
+        strict: bool,
     },
 
     // -------- APA --------
@@ -15,6 +15,7 @@ use elusiv_utils::{
 };
 use solana_program::account_info::AccountInfo;
 use solana_program::entrypoint::ProgramResult;
+use solana_program::instruction::Instruction;
 use solana_program::program_error::ProgramError;
 use solana_program::pubkey::Pubkey;
 use solana_program::sysvar::instructions;
@@ -29,7 +30,20 @@ pub fn register_basic_warden<'a, 'b>(
     warden_id: ElusivWardenID,
     config: ElusivBasicWardenConfig,
 ) -> ProgramResult {
-    guard!(config.key == *warden.key, ProgramError::InvalidArgument);
+    guard!(
+        config.key == *warden.key,
+        ElusivWardenNetworkError::NotAWarden
+    );
+    guard!(
+        warden_id == wardens_account.get_next_warden_id(),
+        ElusivWardenNetworkError::InvalidWardenID
+    );
+
+    // `warden_map_account` prevents duplicate registrations
+    guard!(
+        warden_map_account.get_unsafe().lamports() == 0,
+        ElusivWardenNetworkError::WardenAlreadyRegistered
+    );
 
     basic_network_account.try_add_member(
         warden_id,
@@ -50,10 +64,6 @@ pub fn register_basic_warden<'a, 'b>(
         join_timestamp: current_timestamp,
     };
 
-    guard!(
-        warden_id == wardens_account.get_next_warden_id(),
-        ProgramError::InvalidArgument
-    );
     wardens_account.set_next_warden_id(
         &warden_id
             .checked_add(1)
@@ -105,7 +115,7 @@ pub fn update_basic_warden_state(
     let mut basic_warden = warden_account.get_warden();
     guard!(
         *warden.key == basic_warden.config.key,
-        ProgramError::MissingRequiredSignature
+        ElusivWardenNetworkError::NotAWarden
     );
 
     // `activation_timestamp` is used to track all `is_active` changes
@@ -130,7 +140,7 @@ pub fn update_basic_warden_lut(
     let mut basic_warden = warden_account.get_warden();
     guard!(
         *warden.key == basic_warden.config.key,
-        ProgramError::MissingRequiredSignature
+        ElusivWardenNetworkError::NotAWarden
     );
 
     basic_warden.lut = *lut_account.key;
@@ -276,21 +286,56 @@ pub struct TrackableElusivInstruction {
 pub const TRACKABLE_ELUSIV_INSTRUCTIONS: [TrackableElusivInstruction; 3] = [
     // FinalizeBaseCommitmentHash
     TrackableElusivInstruction {
-        instruction_id: 2,
+        instruction_id: elusiv_interface::FINALIZE_BASE_COMMITMENT_HASH_INDEX,
         warden_index: 0,
     },
     // FinalizeVerificationTransferLamports
     TrackableElusivInstruction {
-        instruction_id: 13,
+        instruction_id: elusiv_interface::FINALIZE_VERIFICATION_TRANSFER_LAMPORTS_INDEX,
         warden_index: 1,
     },
     // FinalizeVerificationTransferToken
     TrackableElusivInstruction {
-        instruction_id: 14,
+        instruction_id: elusiv_interface::FINALIZE_VERIFICATION_TRANSFER_TOKEN_INDEX,
         warden_index: 3,
     },
 ];
 
+/// Returns true, if `ix` is a trackable Elusiv instruction with `warden` as the fee payer
+fn is_strictly_attributable(ix: &Instruction, warden: &Pubkey) -> bool {
+    if ix.program_id != ELUSIV_PROGRAM_ID {
+        return false;
+    }
+
+    let is_trackable = match ix.data.first() {
+        Some(ix_byte) => TRACKABLE_ELUSIV_INSTRUCTIONS
+            .iter()
+            .any(|i| i.instruction_id == *ix_byte),
+        None => false,
+    };
+
+    match ix.accounts.get(elusiv_interface::FEE_PAYER_ACCOUNT_INDEX) {
+        Some(fee_payer) => is_trackable && fee_payer.pubkey == *warden,
+        None => false,
+    }
+}
+
+/// Counts all instructions of the current transaction that are strictly attributable to `warden`
+fn strictly_attributable_instructions_count(
+    warden: &Pubkey,
+    instructions_account: &AccountInfo,
+) -> u32 {
+    let mut count = 0;
+    let mut index = 0;
+    while let Ok(ix) = instructions::load_instruction_at_checked(index, instructions_account) {
+        if is_strictly_attributable(&ix, warden) {
+            count += 1;
+        }
+        index += 1;
+    }
+    count
+}
+
 pub fn track_basic_warden_stats(
     warden: &AccountInfo,
     stats_account: &mut BasicWardenStatsAccount,
@@ -298,10 +343,16 @@ pub fn track_basic_warden_stats(
 
     year: u16,
     can_fail: bool,
+    strict: bool,
 ) -> ProgramResult {
-    if let Err(err) =
-        track_basic_warden_stats_inner(warden, stats_account, instructions_account, year)
-    {
+    if let Err(err) = track_basic_warden_stats_inner(
+        warden,
+        stats_account,
+        instructions_account,
+        year,
+        strict,
+        current_timestamp()?,
+    ) {
         if can_fail {
             return Err(err);
         } else {
@@ -319,18 +370,32 @@ fn track_basic_warden_stats_inner(
     instructions_account: &AccountInfo,
 
     year: u16,
+    strict: bool,
+    current_timestamp: u64,
 ) -> ProgramResult {
-    let current_timestamp = current_timestamp()?;
     let (day, y) = unix_timestamp_to_day_and_year(current_timestamp)
         .ok_or(ElusivWardenNetworkError::TimestampError)?;
 
-    guard!(y == year, ElusivWardenNetworkError::StatsError);
+    guard!(y == year, ElusivWardenNetworkError::StatsYearMismatch);
 
     guard!(
         stats_account.get_year() == year,
-        ElusivWardenNetworkError::StatsError
+        ElusivWardenNetworkError::StatsYearMismatch
     );
 
+    // Strict mode: only instructions of the transaction that have been paid by the warden are tracked
+    if strict {
+        let count = strictly_attributable_instructions_count(warden.key, instructions_account);
+        guard!(count > 0, ElusivWardenNetworkError::UntrackableInstruction);
+
+        for _ in 0..count {
+            stats_account.set_store(stats_account.get_store().inc(day)?);
+        }
+        stats_account.set_last_activity_timestamp(&current_timestamp);
+
+        return Ok(());
+    }
+
     let index = instructions::load_current_index_checked(instructions_account)?;
     let previous_ix = instructions::load_instruction_at_checked(
         index
@@ -350,15 +415,204 @@ fn track_basic_warden_stats_inner(
         );
         guard!(
             previous_ix.program_id == ELUSIV_PROGRAM_ID,
-            ProgramError::IncorrectProgramId
+            ElusivWardenNetworkError::UntrackableInstruction
         );
 
         stats_account.set_store(stats_account.get_store().inc(day)?);
     } else {
-        return Err(ElusivWardenNetworkError::StatsError.into());
+        return Err(ElusivWardenNetworkError::UntrackableInstruction.into());
     }
 
     stats_account.set_last_activity_timestamp(&current_timestamp);
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use elusiv_types::{ProgramAccount, SizedAccount};
+    use solana_program::instruction::AccountMeta;
+
+    macro_rules! account_info {
+        ($id: ident, $pk: expr, $lamports: expr) => {
+            let pk = $pk;
+            let mut lamports = $lamports;
+            let mut data = vec![];
+            let $id = AccountInfo::new(
+                &pk,
+                true,
+                true,
+                &mut lamports,
+                &mut data,
+                &crate::ID,
+                false,
+                0,
+            );
+        };
+    }
+
+    macro_rules! zero_account {
+        (mut $id: ident, $ty: ty) => {
+            let mut data = vec![0; <$ty>::SIZE];
+            let mut $id = <$ty>::new(&mut data).unwrap();
+        };
+    }
+
+    fn warden_config(key: Pubkey) -> ElusivBasicWardenConfig {
+        let mut data = vec![0; BasicWardenAccount::SIZE];
+        let mut config = BasicWardenAccount::new(&mut data)
+            .unwrap()
+            .get_warden()
+            .config;
+        config.key = key;
+        config
+    }
+
+    #[test]
+    fn test_register_basic_warden_errors() {
+        account_info!(warden, Pubkey::new_unique(), 0);
+        account_info!(warden_account, Pubkey::new_unique(), 0);
+        account_info!(free_map_account, Pubkey::new_unique(), 0);
+        account_info!(used_map_account, Pubkey::new_unique(), 1);
+        zero_account!(mut wardens_account, WardensAccount);
+        zero_account!(mut basic_network_account, BasicWardenNetworkAccount);
+        wardens_account.set_next_warden_id(&1);
+
+        macro_rules! register {
+            ($warden_id: expr, $config: expr, $map_account: ident) => {
+                register_basic_warden(
+                    &warden,
+                    UnverifiedAccountInfo::new(&warden_account),
+                    UnverifiedAccountInfo::new(&$map_account),
+                    &mut wardens_account,
+                    &mut basic_network_account,
+                    $warden_id,
+                    $config,
+                )
+            };
+        }
+
+        assert_eq!(
+            register!(1, warden_config(Pubkey::new_unique()), free_map_account),
+            Err(ProgramError::Custom(0x100c))
+        );
+        assert_eq!(
+            register!(0, warden_config(*warden.key), free_map_account),
+            Err(ElusivWardenNetworkError::InvalidWardenID.into())
+        );
+        assert_eq!(
+            register!(1, warden_config(*warden.key), used_map_account),
+            Err(ElusivWardenNetworkError::WardenAlreadyRegistered.into())
+        );
+    }
+
+    #[test]
+    fn test_update_basic_warden_errors() {
+        account_info!(signer, Pubkey::new_unique(), 0);
+        account_info!(lut_account, Pubkey::new_unique(), 0);
+        zero_account!(mut warden_account, BasicWardenAccount);
+
+        assert_eq!(
+            update_basic_warden_state(&signer, &mut warden_account, 0, true),
+            Err(ElusivWardenNetworkError::NotAWarden.into())
+        );
+        assert_eq!(
+            update_basic_warden_lut(&signer, &mut warden_account, &lut_account, 0),
+            Err(ElusivWardenNetworkError::NotAWarden.into())
+        );
+    }
+
+    #[test]
+    fn test_track_basic_warden_stats_year_mismatch() {
+        account_info!(warden, Pubkey::new_unique(), 0);
+        account_info!(instructions_account, instructions::ID, 0);
+        zero_account!(mut stats_account, BasicWardenStatsAccount);
+        stats_account.set_year(&2001);
+
+        // 2000-01-01
+        let timestamp = 946684800;
+
+        assert_eq!(
+            track_basic_warden_stats_inner(
+                &warden,
+                &mut stats_account,
+                &instructions_account,
+                2001,
+                false,
+                timestamp
+            ),
+            Err(ProgramError::Custom(0x100d))
+        );
+
+        assert_eq!(
+            track_basic_warden_stats_inner(
+                &warden,
+                &mut stats_account,
+                &instructions_account,
+                2000,
+                false,
+                timestamp
+            ),
+            Err(ElusivWardenNetworkError::StatsYearMismatch.into())
+        );
+    }
+
+    #[test]
+    fn test_is_strictly_attributable() {
+        let warden = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        let instruction = |program_id: Pubkey, data: &[u8], fee_payer: Pubkey| {
+            Instruction::new_with_bytes(
+                program_id,
+                data,
+                vec![
+                    AccountMeta::new(fee_payer, true),
+                    AccountMeta::new(warden, false),
+                ],
+            )
+        };
+
+        for ix in TRACKABLE_ELUSIV_INSTRUCTIONS {
+            let data = [ix.instruction_id];
+
+            // Matching fee payer
+            assert!(is_strictly_attributable(
+                &instruction(ELUSIV_PROGRAM_ID, &data, warden),
+                &warden
+            ));
+
+            // Non-matching fee payer
+            assert!(!is_strictly_attributable(
+                &instruction(ELUSIV_PROGRAM_ID, &data, other),
+                &warden
+            ));
+
+            // Other program
+            assert!(!is_strictly_attributable(
+                &instruction(other, &data, warden),
+                &warden
+            ));
+        }
+
+        // Non-trackable instruction
+        assert!(!is_strictly_attributable(
+            &instruction(ELUSIV_PROGRAM_ID, &[0], warden),
+            &warden
+        ));
+
+        // Missing data and accounts
+        assert!(!is_strictly_attributable(
+            &instruction(ELUSIV_PROGRAM_ID, &[], warden),
+            &warden
+        ));
+        assert!(!is_strictly_attributable(
+            &Instruction::new_with_bytes(
+                ELUSIV_PROGRAM_ID,
+                &[elusiv_interface::FINALIZE_BASE_COMMITMENT_HASH_INDEX],
+                vec![]
+            ),
+            &warden
+        ));
+    }
+}
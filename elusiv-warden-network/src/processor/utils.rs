@@ -1,3 +1,4 @@
+use crate::error::ElusivWardenNetworkError;
 use solana_program::{clock::Clock, program_error::ProgramError, sysvar::Sysvar};
 
 pub fn current_timestamp() -> Result<u64, ProgramError> {
@@ -8,7 +9,8 @@ pub fn current_timestamp() -> Result<u64, ProgramError> {
 pub fn get_day_and_year() -> Result<(u32, u16), ProgramError> {
     let clock = Clock::get()?;
     let timestamp = clock.unix_timestamp.try_into().unwrap();
-    unix_timestamp_to_day_and_year(timestamp).ok_or(ProgramError::UnsupportedSysvar)
+    unix_timestamp_to_day_and_year(timestamp)
+        .ok_or_else(|| ElusivWardenNetworkError::TimestampError.into())
 }
 
 const TWO_K_EPOCH: u64 = 946_684_800;
@@ -243,6 +243,7 @@ const FULL_QUOTE_SIZE: usize = 1116;
 /// An SGX quote.
 /// See [the remote attestation crate](https://github.com/elusiv-privacy/rust-sgx-remote-attestation)
 #[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, Clone)]
+#[cfg_attr(any(test, feature = "elusiv-client"), derive(Debug))]
 pub struct Quote(pub [u8; FULL_QUOTE_SIZE]);
 
 /// The first half of an SGX quote.
@@ -250,6 +251,7 @@ pub struct Quote(pub [u8; FULL_QUOTE_SIZE]);
 ///
 /// See also [`QuoteEnd`]
 #[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, Clone)]
+#[cfg_attr(any(test, feature = "elusiv-client"), derive(Debug))]
 pub struct QuoteStart(pub [u8; HALF_QUOTE_SIZE]);
 
 impl QuoteStart {
@@ -272,6 +274,7 @@ impl QuoteStart {
 ///
 /// See also [`QuoteStart`]
 #[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, Clone)]
+#[cfg_attr(any(test, feature = "elusiv-client"), derive(Debug))]
 pub struct QuoteEnd(pub [u8; HALF_QUOTE_SIZE]);
 
 #[elusiv_account]
@@ -284,3 +287,23 @@ pub struct ApaWardenAccount {
     pub network_member_index: u32,
     // pub latest_quote: Quote,
 }
+
+#[cfg(all(test, feature = "debug-display"))]
+mod tests {
+    use super::*;
+    use elusiv_types::{ProgramAccount, SizedAccount};
+
+    #[test]
+    fn test_dump_basic_warden_stats_account() {
+        let mut data = vec![0; BasicWardenStatsAccount::SIZE];
+        let mut account = BasicWardenStatsAccount::new(&mut data).unwrap();
+        account.set_year(&2023);
+        account.set_last_activity_timestamp(&1672531200);
+
+        let dump = dump_basic_warden_stats_account(&data);
+        assert!(dump.starts_with("BasicWardenStatsAccount {"));
+        assert!(dump.contains("year: 2023,"));
+        assert!(dump.contains("last_activity_timestamp: 1672531200,"));
+        assert!(dump.contains("migrate: WardenStatistics {"));
+    }
+}
@@ -359,7 +359,7 @@ impl IxFailsWith for ElusivProgramTest {
         self.ix_fails_with_instruction_error(
             ix,
             signers,
-            InstructionError::Custom(expected_error as u32),
+            InstructionError::Custom(expected_error.code()),
         )
         .await
     }
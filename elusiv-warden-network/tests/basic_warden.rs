@@ -356,6 +356,7 @@ async fn test_track_stats() {
                 ElusivWardenNetworkInstruction::track_basic_warden_stats_instruction(
                     year,
                     true,
+                    false,
                     UserAccount(warden.pubkey),
                 ),
             ],
@@ -374,6 +375,7 @@ async fn test_track_stats() {
                 ElusivWardenNetworkInstruction::track_basic_warden_stats_instruction(
                     year,
                     true,
+                    false,
                     UserAccount(warden.pubkey),
                 ),
             ],
@@ -392,6 +394,7 @@ async fn test_track_stats() {
                 ElusivWardenNetworkInstruction::track_basic_warden_stats_instruction(
                     year,
                     true,
+                    false,
                     UserAccount(warden.pubkey),
                 ),
             ],
@@ -409,6 +412,7 @@ async fn test_track_stats() {
             ElusivWardenNetworkInstruction::track_basic_warden_stats_instruction(
                 year,
                 true,
+                false,
                 UserAccount(warden.pubkey),
             ),
         ])
@@ -420,6 +424,7 @@ async fn test_track_stats() {
             ElusivWardenNetworkInstruction::track_basic_warden_stats_instruction(
                 year,
                 false,
+                false,
                 UserAccount(warden.pubkey),
             ),
         ];
@@ -433,6 +438,7 @@ async fn test_track_stats() {
                 ElusivWardenNetworkInstruction::track_basic_warden_stats_instruction(
                     year,
                     true,
+                    false,
                     UserAccount(warden.pubkey),
                 ),
             ],
@@ -441,3 +447,84 @@ async fn test_track_stats() {
         .await;
     }
 }
+
+#[tokio::test]
+async fn test_track_stats_strict() {
+    let mut test = start_test_with_setup().await;
+
+    let mut warden = Actor::new(&mut test).await;
+    register_warden(&mut test, &mut warden).await;
+
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let year = unix_timestamp_to_day_and_year(timestamp).unwrap().1;
+
+    test.ix_should_succeed_simple(
+        ElusivWardenNetworkInstruction::open_basic_warden_stats_account_instruction(
+            year,
+            UserAccount(warden.pubkey),
+            WritableSignerAccount(test.payer()),
+        ),
+    )
+    .await;
+
+    let track_ix = ElusivWardenNetworkInstruction::track_basic_warden_stats_instruction(
+        year,
+        true,
+        true,
+        UserAccount(warden.pubkey),
+    );
+
+    for ix in TRACKABLE_ELUSIV_INSTRUCTIONS {
+        let instruction = |fee_payer: Pubkey| {
+            let mut accounts = vec![AccountMeta::new(fee_payer, true)];
+            for _ in 0..ix.warden_index {
+                accounts.push(AccountMeta::new(Pubkey::new_unique(), false));
+            }
+            Instruction::new_with_bytes(ELUSIV_PROGRAM_ID, &[ix.instruction_id], accounts)
+        };
+        let other_instruction = Instruction::new_with_bytes(
+            OTHER_PROGRAM_ID,
+            &[ix.instruction_id],
+            vec![AccountMeta::new(warden.pubkey, true)],
+        );
+
+        // Non-matching fee payer
+        let fee_payer = Actor::new(&mut test).await;
+        test.tx_should_fail(
+            &[instruction(fee_payer.pubkey), track_ix.clone()],
+            &[&fee_payer.keypair],
+        )
+        .await;
+
+        // Matching fee payer, but not an Elusiv instruction
+        test.tx_should_fail(
+            &[other_instruction.clone(), track_ix.clone()],
+            &[&warden.keypair],
+        )
+        .await;
+
+        // Matching fee payer (the tracked instruction does not need to precede the tracking instruction)
+        test.tx_should_succeed(
+            &[
+                instruction(warden.pubkey),
+                other_instruction.clone(),
+                track_ix.clone(),
+            ],
+            &[&warden.keypair],
+        )
+        .await;
+
+        test.tx_should_succeed(
+            &[
+                instruction(warden.pubkey),
+                instruction(fee_payer.pubkey),
+                track_ix.clone(),
+            ],
+            &[&warden.keypair, &fee_payer.keypair],
+        )
+        .await;
+    }
+}
@@ -190,6 +190,64 @@ pub fn compute_commitment_hash_partial(
     Ok(())
 }
 
+/// Deterministic nullifier-hash, commitment and root triple for tests
+///
+/// # Notes
+///
+/// - `nullifier_hash = poseidon(nullifier, 0)` with `nullifier = seed`,
+/// - `commitment = poseidon(poseidon(nullifier, 1), seed)`,
+/// - `root` is the root of an otherwise empty MT with `commitment` as the first leaf.
+///
+/// `nullifier_hash`, `commitment` and `root` are in the public input representation (no mr-form).
+#[cfg(feature = "test-support")]
+#[derive(Clone, Debug, PartialEq)]
+pub struct CommitmentTestVector {
+    pub nullifier_hash: crate::types::RawU256,
+    pub commitment: crate::types::RawU256,
+    pub root: crate::types::RawU256,
+
+    /// The nodes from the commitment (`path[0]`) up to the root (`path[MT_HEIGHT]`) in mr-form
+    pub path: Vec<crate::types::U256>,
+}
+
+#[cfg(feature = "test-support")]
+impl CommitmentTestVector {
+    pub fn new(seed: u64) -> Self {
+        use crate::fields::{fr_to_u256_le, fr_to_u256_le_repr, u256_to_fr_skip_mr, u64_to_scalar};
+        use crate::state::storage::EMPTY_TREE;
+        use crate::types::RawU256;
+        use ark_ff::Zero;
+        use poseidon_hash::full_poseidon2_hash;
+
+        let nullifier = u64_to_scalar(seed);
+        let nullifier_hash = full_poseidon2_hash(nullifier, ark_bn254::Fr::zero());
+        let base_commitment = full_poseidon2_hash(nullifier, u64_to_scalar(1));
+        let commitment = full_poseidon2_hash(base_commitment, u64_to_scalar(seed));
+
+        let mut node = commitment;
+        let mut path = vec![fr_to_u256_le(&node)];
+        for empty_sibling in EMPTY_TREE.iter().take(MT_HEIGHT) {
+            node = full_poseidon2_hash(node, u256_to_fr_skip_mr(empty_sibling));
+            path.push(fr_to_u256_le(&node));
+        }
+
+        CommitmentTestVector {
+            nullifier_hash: RawU256::new(fr_to_u256_le_repr(&nullifier_hash)),
+            commitment: RawU256::new(fr_to_u256_le_repr(&commitment)),
+            root: RawU256::new(fr_to_u256_le_repr(&node)),
+            path,
+        }
+    }
+
+    /// Inserts the commitment as the first leaf into an empty active MT
+    pub fn insert_into(&self, storage: &mut crate::state::storage::StorageAccount) {
+        storage.set_next_commitment_ptr(&1);
+        for (i, node) in self.path.iter().enumerate() {
+            storage.set_node(node, 0, MT_HEIGHT - i);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -327,4 +385,15 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_commitment_test_vector() {
+        assert_eq!(CommitmentTestVector::new(1), CommitmentTestVector::new(1));
+        assert_ne!(CommitmentTestVector::new(1), CommitmentTestVector::new(2));
+
+        let vector = CommitmentTestVector::new(0);
+        assert_eq!(vector.path.len(), MT_HEIGHT + 1);
+        assert_eq!(vector.commitment.reduce(), vector.path[0]);
+        assert_eq!(vector.root.reduce(), vector.path[MT_HEIGHT]);
+    }
 }
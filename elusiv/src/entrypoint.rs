@@ -36,6 +36,9 @@ pub fn process_instruction(
 
     match instruction::ElusivInstruction::deserialize(&mut &instruction_data[..]) {
         Ok(instruction) => {
+            #[cfg(feature = "devnet-tools")]
+            crate::processor::check_failure_injection(accounts, &instruction)?;
+
             instruction::ElusivInstruction::process(program_id, accounts, instruction)
         }
         Err(_) => Err(ProgramError::InvalidInstructionData),
@@ -59,6 +59,51 @@ pub enum ElusivError {
     // Accounts
     ChildAccountAlreadyExists,
     ChildAccouttDoesNotExists,
+
+    // Proof (appended to keep the existing error codes stable)
+    VerificationNotTimedOut,
+    InvalidEncryptedMemo,
+
+    // Nullifier
+    NullifierTreeFull,
+
+    // Proof
+    TooManyDuplicateVerifications,
+    MemoTooLarge,
+    DuplicateVerificationGracePeriod,
+
+    // Accounts
+    IndexOutOfBounds,
+    InsufficientRentFunds,
+
+    // Merkle tree
+    NonCanonicalMerkleRoot,
+
+    // Commitment
+    DuplicateOutputCommitment,
+
+    // Proof
+    InvalidVerificationTimeout,
+
+    // Governance
+    CategoryPaused,
+
+    // VKey
+    InvalidVKeySourceDigest,
+
+    // Accounts
+    ForeignAccountOwner,
+    CompromisedTokenAccount,
+
+    // Tokens
+    TokenPaused,
+
+    // Proof
+    AmbiguousMemo,
+    AmountInsteadOfTotalAmount,
+    PrepareInputsInstructionsMismatch,
+    ClockUnavailable,
+    InvalidTimestampPruningBits,
 }
 
 #[cfg(not(tarpaulin_include))]
@@ -84,4 +129,10 @@ mod test {
     fn test_sdk_error_codes() {
         assert_eq!(ProgramError::Custom(105), TokenError::PriceError.into());
     }
+
+    #[test]
+    fn test_error_code_range() {
+        // The last code is located below the `TokenError` codes (and the warden-network codes starting at 0x1000)
+        assert!((ElusivError::InvalidTimestampPruningBits as u32) < 100);
+    }
 }
@@ -25,7 +25,12 @@ macro_rules! u64_array {
 }
 
 /// Bn254 base field modulus: `q = 21888242871839275222246405745257275088696311157297823662689037894645226208583` in non-mr-form
-//const BASE_MODULUS_RAW: BigInteger256 = BigInteger256([0x3c208c16d87cfd47, 0x97816a916871ca8d, 0xb85045b68181585d, 0x30644e72e131a029]);
+const BASE_MODULUS_RAW: BigInteger256 = BigInteger256([
+    0x3c208c16d87cfd47,
+    0x97816a916871ca8d,
+    0xb85045b68181585d,
+    0x30644e72e131a029,
+]);
 
 /// Bn254 scalar field modulus: `r = 21888242871839275222246405745257275088548364400416034343698204186575808495617` in non-mr-form
 pub const SCALAR_MODULUS_RAW: BigInteger256 = BigInteger256([
@@ -50,6 +55,35 @@ pub fn is_element_scalar_field(e: BigInteger256) -> bool {
     e < SCALAR_MODULUS_RAW
 }
 
+/// Checks whether a base field element has a canonical representation (in mr-form)
+fn is_element_base_field(e: &Fq) -> bool {
+    e.0 < BASE_MODULUS_RAW
+}
+
+/// Checks whether a G1 point is a valid (non-zero) element of the prime order subgroup
+///
+/// # Notes
+///
+/// The cofactor of the Bn254 G1 is 1, so every point on the curve is also in the prime order subgroup.
+pub fn is_g1_valid(p: &G1Affine) -> bool {
+    !p.infinity && is_element_base_field(&p.x) && is_element_base_field(&p.y) && p.is_on_curve()
+}
+
+/// Checks whether a G2 point is a valid (non-zero) point on the twist
+///
+/// # Notes
+///
+/// This check is bounded to canonical coordinates and the twist equation.
+/// A full subgroup check requires a scalar multiplication in Fq2 (~10m CUs), which does not fit into a single transaction.
+pub fn is_g2_valid(p: &G2Affine) -> bool {
+    !p.infinity
+        && is_element_base_field(&p.x.c0)
+        && is_element_base_field(&p.x.c1)
+        && is_element_base_field(&p.y.c0)
+        && is_element_base_field(&p.y.c1)
+        && p.is_on_curve()
+}
+
 /// [`BigInteger256`] efficiently from LE buffer
 /// - to increase efficiency callers should always assert that $v.len() >= $o + 32 (https://www.reddit.com/r/rust/comments/6anp0d/suggestion_for_a_new_rustc_optimization/dhfzp93/)
 fn le_u256(slice: &[u8]) -> BigInteger256 {
@@ -436,7 +470,9 @@ pub fn u256_from_str_skip_mr(str: &str) -> U256 {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ark_ff::BigInteger;
+    use ark_bn254::g2::Parameters as G2Parameters;
+    use ark_ec::{models::SWModelParameters, AffineCurve, ProjectiveCurve};
+    use ark_ff::{BigInteger, Field, SquareRootField, Zero};
 
     macro_rules! test_ser_de {
         ($ty: ty, $v: expr) => {
@@ -479,6 +515,89 @@ mod tests {
         assert!(is_element_scalar_field(BigInteger256::from(0)));
     }
 
+    #[test]
+    fn test_base_mod() {
+        let mut q = Fq::from_str(
+            "21888242871839275222246405745257275088696311157297823662689037894645226208582",
+        )
+        .unwrap()
+        .into_repr();
+        q.add_nocarry(&BigInteger256::from(1));
+
+        assert_eq!(BASE_MODULUS_RAW, q);
+    }
+
+    #[test]
+    fn test_is_g1_valid() {
+        let g = G1Affine::prime_subgroup_generator();
+        assert!(is_g1_valid(&g));
+        assert!(is_g1_valid(&g.mul(Fr::from(123456789u64)).into_affine()));
+
+        // Bn254 G1 has cofactor 1 (every point on the curve is in the subgroup)
+        assert!(g
+            .mul(Fr::from(987654321u64))
+            .into_affine()
+            .is_in_correct_subgroup_assuming_on_curve());
+
+        // Off-curve
+        assert!(!is_g1_valid(&G1Affine::new(g.x, g.y + Fq::one(), false)));
+        assert!(!is_g1_valid(&G1Affine::new(g.x + Fq::one(), g.y, false)));
+        assert!(!is_g1_valid(&G1Affine::new(Fq::zero(), Fq::zero(), false)));
+
+        // Infinity
+        assert!(!is_g1_valid(&G1Affine::zero()));
+        assert!(!is_g1_valid(&G1Affine::new(g.x, g.y, true)));
+
+        // Non-canonical coordinates
+        let mut x = g.x.0;
+        x.add_nocarry(&BASE_MODULUS_RAW);
+        assert!(!is_g1_valid(&G1Affine::new(Fq::new(x), g.y, false)));
+    }
+
+    #[test]
+    fn test_is_g2_valid() {
+        let g = G2Affine::prime_subgroup_generator();
+        assert!(is_g2_valid(&g));
+        assert!(is_g2_valid(&g.mul(Fr::from(123456789u64)).into_affine()));
+
+        // Off-curve
+        assert!(!is_g2_valid(&G2Affine::new(g.x, g.y + Fq2::one(), false)));
+        assert!(!is_g2_valid(&G2Affine::new(
+            Fq2::new(g.x.c0 + Fq::one(), g.x.c1),
+            g.y,
+            false
+        )));
+        assert!(!is_g2_valid(&G2Affine::new(
+            Fq2::zero(),
+            Fq2::zero(),
+            false
+        )));
+
+        // Infinity
+        assert!(!is_g2_valid(&G2Affine::zero()));
+
+        // Non-canonical coordinates
+        let mut c1 = g.y.c1.0;
+        c1.add_nocarry(&BASE_MODULUS_RAW);
+        assert!(!is_g2_valid(&G2Affine::new(
+            g.x,
+            Fq2::new(g.y.c0, Fq::new(c1)),
+            false
+        )));
+
+        // Points on the twist outside of the prime order subgroup pass the bounded check
+        let wrong_subgroup = (1..)
+            .find_map(|i| {
+                let x = Fq2::new(Fq::from(i as u64), Fq::zero());
+                let y = (x.square() * x + G2Parameters::COEFF_B).sqrt()?;
+                Some(G2Affine::new(x, y, false))
+            })
+            .unwrap();
+        assert!(wrong_subgroup.is_on_curve());
+        assert!(!wrong_subgroup.is_in_correct_subgroup_assuming_on_curve());
+        assert!(is_g2_valid(&wrong_subgroup));
+    }
+
     #[test]
     fn test_ser_de_big_integer_256() {
         test_ser_de!(Wrap<BigInteger256>, Wrap(BigInteger256::from(123456789)));
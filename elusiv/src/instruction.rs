@@ -4,31 +4,45 @@ use super::processor;
 use super::processor::BaseCommitmentHashRequest;
 use crate::macros::*;
 use crate::processor::{FinalizeSendData, ProofRequest, VKeyAccountDataPacket, MAX_MT_COUNT};
+use crate::proof::verifier::ComputationDescriptor;
 use crate::state::{
     commitment::{
         BaseCommitmentBufferAccount, BaseCommitmentHashingAccount, CommitmentBufferAccount,
         CommitmentHashingAccount, CommitmentQueueAccount,
     },
     fee::{FeeAccount, ProgramFee},
+    genesis::GenesisParamsAccount,
     governor::{FeeCollectorAccount, GovernorAccount, PoolAccount},
     metadata::{CommitmentMetadata, MetadataAccount, MetadataQueueAccount},
-    nullifier::NullifierAccount,
-    proof::VerificationAccount,
+    nullifier::{NullifierAccount, NullifierMergeAccount},
+    proof::{VerificationAccount, VerificationIndexAccount},
     storage::StorageAccount,
+    token_registry::{RegisteredToken, TokenRegistryAccount},
     vkey::VKeyAccount,
 };
-use crate::types::Proof;
+#[cfg(feature = "devnet-tools")]
+use crate::state::{
+    failure_injection::FailureInjectionAccount,
+    test_token::{TestMintAccount, TestPriceAccount},
+};
+use crate::types::{Proof, U256};
 use borsh::{BorshDeserialize, BorshSerialize};
 use elusiv_types::{AccountRepr, ElusivOption};
 use solana_program::{pubkey::Pubkey, system_program, sysvar::instructions};
 
+#[cfg(feature = "elusiv-client")]
+use crate::state::{
+    governor::NetworkFeeAccount, owner_rotation::OwnerRotationAccount,
+    vkey::ComputationDescriptorAccount,
+};
+
 #[cfg(feature = "elusiv-client")]
 pub use elusiv_types::accounts::{
     SignerAccount, UserAccount, WritableSignerAccount, WritableUserAccount,
 };
 
 #[repr(u8)]
-#[derive(BorshDeserialize, BorshSerialize, ElusivInstruction)]
+#[derive(BorshDeserialize, BorshSerialize, ElusivInstruction, EnumVariantIndex)]
 #[allow(clippy::large_enum_variant)]
 pub enum ElusivInstruction {
     // -------- Base commitment hashing --------
@@ -49,6 +63,8 @@ pub enum ElusivInstruction {
     #[pda(buffer, BaseCommitmentBufferAccount, { writable })]
     #[acc(token_program)] // if `token_id = 0` { `system_program` } else { `token_program` }
     #[sys(system_program, key = system_program::ID)]
+    #[pda(genesis_params, GenesisParamsAccount, { find_pda, account_info })]
+    #[pda(token_registry, TokenRegistryAccount, { account_info, find_pda })]
     StoreBaseCommitment {
         hash_account_index: u32,
         hash_account_bump: u8,
@@ -73,8 +89,16 @@ pub enum ElusivInstruction {
     // -------- Commitment hashing --------
     /// Hashes commitments in a new MT-root
     #[pda(commitment_hashing_account, CommitmentHashingAccount, { writable })]
+    #[pda(commitment_hash_queue, CommitmentQueueAccount, { writable })]
+    #[pda(metadata_queue, MetadataQueueAccount, { writable })]
+    #[pda(fee, FeeAccount, pda_offset = Some(fee_version))]
+    #[pda(pool, PoolAccount, { writable, account_info })]
+    #[pda(fee_collector, FeeCollectorAccount, { writable, account_info })]
     #[pda(storage_account, StorageAccount, { include_child_accounts })]
-    InitCommitmentHashSetup { insertion_can_fail: bool },
+    InitCommitmentHashSetup {
+        insertion_can_fail: bool,
+        fee_version: u32,
+    },
 
     #[pda(commitment_hash_queue, CommitmentQueueAccount, { writable })]
     #[pda(metadata_queue, MetadataQueueAccount, { writable })]
@@ -94,22 +118,28 @@ pub enum ElusivInstruction {
 
     // -------- Proof Verification --------
     /// Proof verification initialization
+    /// - unused `tree_indices` (more indices than roots) need to be set to the index of the next MT (active MT index + 1)
     #[acc(fee_payer, { writable, signer })]
-    #[pda(verification_account, VerificationAccount, pda_pubkey = fee_payer.pubkey(), pda_offset = Some(verification_account_index.into()), { writable, account_info, find_pda })]
+    #[pda(verification_account, VerificationAccount, pda_pubkey = fee_payer.pubkey(), pda_offset = Some(VerificationAccount::pda_offset(verification_account_index, verification_account_generation)), { writable, account_info, find_pda })]
+    #[pda(verification_index_account, VerificationIndexAccount, pda_pubkey = fee_payer.pubkey(), { writable, account_info, find_pda })]
     #[pda(vkey_account, VKeyAccount, pda_offset = Some(vkey_id))]
     #[acc(nullifier_duplicate_account, { writable })]
     #[sys(system_program, key = system_program::ID, { ignore })]
     #[acc(identifier_account)]
+    #[pda(governor, GovernorAccount)]
     #[pda(storage_account, StorageAccount)]
     #[pda(buffer, CommitmentBufferAccount, { writable })]
+    #[pda(genesis_params, GenesisParamsAccount, { find_pda, account_info })]
     #[pda(nullifier_account0, NullifierAccount, pda_offset = Some(tree_indices[0]), { include_child_accounts })]
     #[pda(nullifier_account1, NullifierAccount, pda_offset = Some(tree_indices[1]), { include_child_accounts })]
     InitVerification {
         verification_account_index: u8,
+        verification_account_generation: u32,
         vkey_id: u32,
         tree_indices: [u32; MAX_MT_COUNT],
         request: ProofRequest,
         skip_nullifier_pda: bool,
+        timeout: ElusivOption<u64>,
     },
 
     #[acc(fee_payer, { writable, signer })]
@@ -121,25 +151,33 @@ pub enum ElusivInstruction {
     #[acc(sol_price_account)]
     #[acc(token_price_account)]
     #[pda(governor, GovernorAccount)]
-    #[pda(verification_account, VerificationAccount, pda_pubkey = fee_payer.pubkey(), pda_offset = Some(verification_account_index.into()), { writable })]
+    #[pda(token_registry, TokenRegistryAccount, { account_info, find_pda })]
+    #[pda(verification_account, VerificationAccount, pda_pubkey = fee_payer.pubkey(), pda_offset = Some(VerificationAccount::pda_offset(verification_account_index, verification_account_generation)), { writable })]
     #[acc(token_program)] // if `token_id = 0` { `system_program` } else { `token_program` }
     #[sys(system_program, key = system_program::ID)]
-    InitVerificationTransferFee { verification_account_index: u8 },
+    InitVerificationTransferFee {
+        verification_account_index: u8,
+        verification_account_generation: u32,
+    },
 
     #[acc(fee_payer, { signer })]
-    #[pda(verification_account, VerificationAccount, pda_pubkey = fee_payer.pubkey(), pda_offset = Some(verification_account_index.into()), { writable })]
+    #[pda(verification_account, VerificationAccount, pda_pubkey = fee_payer.pubkey(), pda_offset = Some(VerificationAccount::pda_offset(verification_account_index, verification_account_generation)), { writable })]
     InitVerificationProof {
         verification_account_index: u8,
+        verification_account_generation: u32,
         proof: Proof,
     },
 
     /// Proof verification computation
     #[acc(original_fee_payer, { ignore })]
-    #[pda(verification_account, VerificationAccount, pda_pubkey = original_fee_payer.pubkey(), pda_offset = Some(verification_account_index.into()), { writable })]
+    #[pda(verification_account, VerificationAccount, pda_pubkey = original_fee_payer.pubkey(), pda_offset = Some(VerificationAccount::pda_offset(verification_account_index, verification_account_generation)), { writable })]
     #[pda(vkey_account, VKeyAccount, pda_offset = Some(vkey_id), { include_child_accounts })]
+    #[pda(computation_descriptor_account, ComputationDescriptorAccount, pda_offset = Some(vkey_id), { skip_pda_verification, account_info })]
     #[sys(instructions_account, key = instructions::ID)]
+    #[pda(governor, GovernorAccount)]
     ComputeVerification {
         verification_account_index: u8,
+        verification_account_generation: u32,
         vkey_id: u32,
     },
 
@@ -149,20 +187,25 @@ pub enum ElusivInstruction {
     #[acc(transaction_reference_account)]
     #[acc(original_fee_payer, { ignore })]
     #[pda(commitment_hash_queue, CommitmentQueueAccount, { writable })]
-    #[pda(verification_account, VerificationAccount, pda_pubkey = original_fee_payer.pubkey(), pda_offset = Some(verification_account_index.into()), { writable })]
+    #[pda(verification_account, VerificationAccount, pda_pubkey = original_fee_payer.pubkey(), pda_offset = Some(VerificationAccount::pda_offset(verification_account_index, verification_account_generation)), { writable })]
     #[pda(storage_account, StorageAccount)]
     #[pda(buffer, CommitmentBufferAccount, { writable })]
+    #[pda(governor, GovernorAccount)]
     #[sys(instructions_account, key = instructions::ID)]
     FinalizeVerificationSend {
         verification_account_index: u8,
+        verification_account_generation: u32,
         data: FinalizeSendData,
         uses_memo: bool,
     },
 
     #[acc(original_fee_payer, { ignore })]
-    #[pda(verification_account, VerificationAccount, pda_pubkey = original_fee_payer.pubkey(), pda_offset = Some(verification_account_index.into()), { writable })]
+    #[pda(verification_account, VerificationAccount, pda_pubkey = original_fee_payer.pubkey(), pda_offset = Some(VerificationAccount::pda_offset(verification_account_index, verification_account_generation)), { writable })]
     #[pda(nullifier_account, NullifierAccount, pda_offset = Some(verification_account.get_tree_indices(0)), { writable, include_child_accounts, skip_abi })]
-    FinalizeVerificationInsertNullifier { verification_account_index: u8 },
+    FinalizeVerificationInsertNullifier {
+        verification_account_index: u8,
+        verification_account_generation: u32,
+    },
 
     #[acc(original_fee_payer, { signer, writable })]
     #[acc(recipient, { writable })]
@@ -171,16 +214,23 @@ pub enum ElusivInstruction {
     #[acc(optional_fee_collector, { account_info, writable })]
     #[pda(commitment_hash_queue, CommitmentQueueAccount, { writable })]
     #[pda(metadata_queue, MetadataQueueAccount, { writable })]
-    #[pda(verification_account, VerificationAccount, pda_pubkey = original_fee_payer.pubkey(), pda_offset = Some(verification_account_index.into()), { writable, account_info })]
+    #[pda(verification_account, VerificationAccount, pda_pubkey = original_fee_payer.pubkey(), pda_offset = Some(VerificationAccount::pda_offset(verification_account_index, verification_account_generation)), { writable, account_info })]
     #[acc(nullifier_duplicate_account, { writable, owned })]
     #[sys(system_program, key = system_program::ID, { ignore })]
     #[sys(instructions_account, key = instructions::ID)]
-    FinalizeVerificationTransferLamports { verification_account_index: u8 },
+    #[pda(governor, GovernorAccount)]
+    #[acc(rent_recipient, { writable })]
+    #[acc(network_fee_account, { writable, owned })]
+    #[pda(verification_index_account, VerificationIndexAccount, pda_pubkey = original_fee_payer.pubkey(), { writable, account_info, find_pda })]
+    FinalizeVerificationTransferLamports {
+        verification_account_index: u8,
+        verification_account_generation: u32,
+    },
 
     #[acc(original_fee_payer, { signer, writable })]
     #[acc(original_fee_payer_account, { writable })]
     #[acc(recipient, { writable })]
-    #[acc(recipient_wallet)]
+    #[acc(recipient_wallet, { writable })]
     #[pda(pool, PoolAccount, { account_info, writable })]
     #[acc(pool_account, { writable })]
     #[pda(fee_collector, FeeCollectorAccount, { account_info, writable })]
@@ -188,19 +238,27 @@ pub enum ElusivInstruction {
     #[acc(optional_fee_collector, { account_info, writable })]
     #[pda(commitment_hash_queue, CommitmentQueueAccount, { writable })]
     #[pda(metadata_queue, MetadataQueueAccount, { writable })]
-    #[pda(verification_account, VerificationAccount, pda_pubkey = original_fee_payer.pubkey(), pda_offset = Some(verification_account_index.into()), { writable, account_info })]
+    #[pda(verification_account, VerificationAccount, pda_pubkey = original_fee_payer.pubkey(), pda_offset = Some(VerificationAccount::pda_offset(verification_account_index, verification_account_generation)), { writable, account_info })]
     #[acc(nullifier_duplicate_account, { writable, owned })]
     #[sys(a_token_program, key = spl_associated_token_account::ID, { ignore })]
     #[sys(token_program, key = spl_token::ID)]
     #[sys(system_program, key = system_program::ID, { ignore })]
     #[acc(mint_account)]
     #[sys(instructions_account, key = instructions::ID)]
-    FinalizeVerificationTransferToken { verification_account_index: u8 },
+    #[pda(governor, GovernorAccount)]
+    #[acc(rent_recipient, { writable })]
+    #[acc(network_fee_account, { writable, owned })]
+    #[pda(verification_index_account, VerificationIndexAccount, pda_pubkey = original_fee_payer.pubkey(), { writable, account_info, find_pda })]
+    FinalizeVerificationTransferToken {
+        verification_account_index: u8,
+        verification_account_generation: u32,
+    },
 
     // -------- Verifying key management --------
     #[acc(signer, { writable, signer })]
     #[pda(vkey_account, VKeyAccount, pda_offset = Some(vkey_id), { writable, account_info, find_pda })]
     #[sys(system_program, key = system_program::ID, { ignore })]
+    #[pda(governor, GovernorAccount)]
     CreateVkeyAccount {
         vkey_id: u32,
         public_inputs_count: u32,
@@ -210,16 +268,19 @@ pub enum ElusivInstruction {
     #[acc(signer, { signer })]
     #[pda(vkey_account, VKeyAccount, pda_offset = Some(vkey_id), { writable })]
     #[acc(vkey_binary_data_account, { writable })]
+    #[pda(governor, GovernorAccount)]
     CreateNewVkeyVersion { vkey_id: u32 },
 
     #[acc(signer, { signer, writable })]
     #[pda(vkey_account, VKeyAccount, pda_offset = Some(vkey_id), { writable })]
     #[acc(old_vkey_binary_data_account, { writable })]
     #[sys(system_program, key = system_program::ID)]
+    #[pda(governor, GovernorAccount)]
     UpdateVkeyVersion { vkey_id: u32 },
 
     #[acc(signer, { signer })]
     #[pda(vkey_account, VKeyAccount, pda_offset = Some(vkey_id), { writable, include_child_accounts })]
+    #[pda(governor, GovernorAccount)]
     SetVkeyData {
         vkey_id: u32,
         data_position: u32,
@@ -228,10 +289,15 @@ pub enum ElusivInstruction {
 
     #[acc(signer, { signer })]
     #[pda(vkey_account, VKeyAccount, pda_offset = Some(vkey_id), { writable })]
-    FreezeVkey { vkey_id: u32 },
+    #[pda(governor, GovernorAccount)]
+    FreezeVkey {
+        vkey_id: u32,
+        expected_source_digest: U256,
+    },
 
     #[acc(signer, { signer })]
     #[pda(vkey_account, VKeyAccount, pda_offset = Some(vkey_id), { writable })]
+    #[pda(governor, GovernorAccount)]
     ChangeVkeyAuthority { vkey_id: u32, authority: Pubkey },
 
     // -------- MT management --------
@@ -299,6 +365,227 @@ pub enum ElusivInstruction {
         program_fee: ProgramFee,
     },
 
+    // -------- Appended instructions --------
+    // New instructions are only appended (before the cfg-gated tail) to keep the discriminants of existing instructions stable
+    /// Publishes a new owner encryption pubkey for `identifier`
+    #[acc(identifier, { signer, writable })]
+    #[pda(owner_rotation_account, OwnerRotationAccount, pda_pubkey = identifier.pubkey(), { writable, account_info, skip_pda_verification })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    RotateOwner {
+        encryption_pubkey: U256,
+        rotation_slot: u64,
+    },
+
+    /// Closes a proof verification that has not been computed before the timeout
+    #[acc(original_fee_payer, { writable })]
+    #[pda(pool, PoolAccount, { account_info, writable })]
+    #[acc(pool_account, { writable })]
+    #[pda(fee_collector, FeeCollectorAccount, { account_info, writable })]
+    #[acc(fee_collector_account, { writable })]
+    #[pda(verification_account, VerificationAccount, pda_pubkey = original_fee_payer.pubkey(), pda_offset = Some(VerificationAccount::pda_offset(verification_account_index, verification_account_generation)), { writable, account_info })]
+    #[pda(verification_index_account, VerificationIndexAccount, pda_pubkey = original_fee_payer.pubkey(), { writable, account_info, find_pda })]
+    #[acc(nullifier_duplicate_account, { writable, owned })]
+    #[pda(buffer, CommitmentBufferAccount, { writable })]
+    #[acc(token_program)] // if `token_id = 0` { `system_program` } else { `token_program` }
+    CloseTimedOutVerification {
+        verification_account_index: u8,
+        verification_account_generation: u32,
+    },
+
+    /// Aborts a proof verification before it's proof has been setup
+    #[acc(original_fee_payer, { writable, signer })]
+    #[pda(pool, PoolAccount, { account_info, writable })]
+    #[acc(pool_account, { writable })]
+    #[pda(fee_collector, FeeCollectorAccount, { account_info, writable })]
+    #[acc(fee_collector_account, { writable })]
+    #[pda(verification_account, VerificationAccount, pda_pubkey = original_fee_payer.pubkey(), pda_offset = Some(VerificationAccount::pda_offset(verification_account_index, verification_account_generation)), { writable, account_info })]
+    #[pda(verification_index_account, VerificationIndexAccount, pda_pubkey = original_fee_payer.pubkey(), { writable, account_info, find_pda })]
+    #[acc(nullifier_duplicate_account, { writable, owned })]
+    #[pda(buffer, CommitmentBufferAccount, { writable })]
+    #[acc(token_program)] // if `token_id = 0` { `system_program` } else { `token_program` }
+    AbortVerification {
+        verification_account_index: u8,
+        verification_account_generation: u32,
+    },
+
+    #[acc(authority, { signer })]
+    #[acc(payer, { writable, signer })]
+    #[pda(vkey_account, VKeyAccount, pda_offset = Some(vkey_id))]
+    #[pda(computation_descriptor_account, ComputationDescriptorAccount, pda_offset = Some(vkey_id), { writable, skip_pda_verification, account_info })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    #[pda(governor, GovernorAccount)]
+    RegisterComputationDescriptor {
+        vkey_id: u32,
+        descriptor: ComputationDescriptor,
+    },
+
+    /// Merges the nullifier-hashes of a closed MT into another closed MT (resumable)
+    #[acc(authority, { signer })]
+    #[acc(payer, { writable, signer })]
+    #[pda(storage_account, StorageAccount)]
+    #[pda(merge_account, NullifierMergeAccount, pda_offset = Some(source_mt_index), { writable, account_info, find_pda })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    #[pda(source_nullifier_account, NullifierAccount, pda_offset = Some(source_mt_index), { include_child_accounts })]
+    #[pda(destination_nullifier_account, NullifierAccount, pda_offset = Some(destination_mt_index), { writable, include_child_accounts })]
+    MergeNullifierTrees {
+        source_mt_index: u32,
+        destination_mt_index: u32,
+    },
+
+    /// Verifies all child-accounts of the storage-account and a nullifier-account once
+    #[pda(storage_account, StorageAccount, { writable, include_child_accounts })]
+    #[pda(nullifier_account, NullifierAccount, pda_offset = Some(tree_index), { writable, include_child_accounts })]
+    WarmTreeAccounts { tree_index: u32 },
+
+    #[acc(authority, { signer })]
+    #[acc(payer, { writable, signer })]
+    #[pda(genesis_params, GenesisParamsAccount, { writable, skip_pda_verification, account_info })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    SetupGenesisParams {
+        zero_commitment: U256,
+        zero_base_commitment: U256,
+    },
+
+    #[acc(authority, { signer })]
+    #[pda(governor, GovernorAccount, { writable })]
+    SetAllowLegacyFeePayerTokenAccount { allow: bool },
+
+    #[acc(authority, { signer })]
+    #[pda(governor, GovernorAccount, { writable })]
+    SetMaxDuplicateVerifications { max_duplicate_verifications: u8 },
+
+    #[acc(authority, { signer })]
+    #[pda(governor, GovernorAccount, { writable })]
+    SetDuplicateGracePeriod { duplicate_grace_period: u64 },
+
+    #[acc(authority, { signer })]
+    #[pda(governor, GovernorAccount, { writable })]
+    SetRentRecipient {
+        rent_recipient: ElusivOption<Pubkey>,
+    },
+
+    #[acc(authority, { signer })]
+    #[pda(governor, GovernorAccount, { writable })]
+    SetFailureBond { failure_bond: u64 },
+
+    #[acc(authority, { signer })]
+    #[pda(governor, GovernorAccount, { writable })]
+    SetVerificationTimeoutBounds {
+        min_verification_timeout: u64,
+        max_verification_timeout: u64,
+    },
+
+    #[acc(authority, { signer })]
+    #[pda(governor, GovernorAccount, { writable })]
+    SetPausedCategories { paused_categories: u32 },
+
+    #[acc(authority, { signer })]
+    #[pda(governor, GovernorAccount, { writable })]
+    SetEnforceSingleMemo { enforce_single_memo: bool },
+
+    #[acc(authority, { signer })]
+    #[pda(governor, GovernorAccount, { writable })]
+    SetMemoProgramIds { memo_program_ids: Vec<Pubkey> },
+
+    #[acc(authority, { signer })]
+    #[pda(governor, GovernorAccount, { writable })]
+    SetEnforceSystemOwnedLamportsRecipient {
+        enforce_system_owned_lamports_recipient: bool,
+    },
+
+    #[acc(authority, { signer })]
+    #[acc(payer, { writable, signer })]
+    #[pda(token_registry, TokenRegistryAccount, { writable, account_info, find_pda })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    RegisterToken {
+        token_id: u16,
+        token: RegisteredToken,
+    },
+
+    #[acc(authority, { signer })]
+    #[acc(payer, { writable, signer })]
+    #[pda(token_registry, TokenRegistryAccount, { writable, account_info, find_pda })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    SetTokenPaused { token_id: u16, paused: bool },
+
+    #[pda(governor, GovernorAccount)]
+    GovernorHead,
+
+    /// Logs a [`crate::processor::TreasurySnapshot`]
+    ///
+    /// # Note
+    ///
+    /// The remaining accounts are the pool and fee-collector token-accounts for each of the `token_ids`.
+    #[pda(pool, PoolAccount, { account_info })]
+    #[pda(fee_collector, FeeCollectorAccount, { account_info })]
+    #[pda(token_registry, TokenRegistryAccount, { account_info, find_pda })]
+    #[acc(token_accounts, { remaining })]
+    SnapshotTreasury { token_ids: Vec<u16> },
+
+    #[acc(authority, { signer })]
+    #[pda(governor, GovernorAccount, { writable })]
+    RegisterCommonLut { lut_address: Pubkey },
+
+    #[pda(governor, GovernorAccount)]
+    #[acc(lut_account)]
+    VerifyCommonLut,
+
+    #[pda(storage_account, StorageAccount)]
+    VerifyRootIndex,
+
+    /// Logs a [`crate::processor::ProgramTokenAccountAudit`] if the pool and fee-collector token-accounts are not compromised
+    #[pda(pool, PoolAccount, { account_info })]
+    #[pda(fee_collector, FeeCollectorAccount, { account_info })]
+    #[acc(pool_account)]
+    #[acc(fee_collector_account)]
+    #[pda(token_registry, TokenRegistryAccount, { account_info, find_pda })]
+    AuditProgramTokenAccount { token_id: u16 },
+
+    /// Returns a [`crate::processor::TokenList`] (as return data)
+    #[pda(token_registry, TokenRegistryAccount, { account_info, find_pda })]
+    #[pda(governor, GovernorAccount)]
+    ListTokens,
+
+    /// Logs the [`crate::processor::OpenVerifications`] of `fee_payer`
+    #[acc(fee_payer)]
+    #[pda(verification_index_account, VerificationIndexAccount, pda_pubkey = fee_payer.pubkey())]
+    LogOpenVerifications,
+
+    /// Opens the [`NetworkFeeAccount`] of `token_id` (network fees of finalizations are only accumulated once it is open)
+    #[acc(payer, { writable, signer })]
+    #[pda(network_fee_account, NetworkFeeAccount, pda_offset = Some(token_id.into()), { writable, skip_pda_verification, account_info })]
+    #[pda(token_registry, TokenRegistryAccount, { account_info, find_pda })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    OpenNetworkFeeAccount { token_id: u16 },
+
+    /// Closes a proof verification that has stalled in the computation (callable by anyone, `caller` receives the rent)
+    #[acc(caller, { writable, signer })]
+    #[acc(original_fee_payer, { writable })]
+    #[pda(pool, PoolAccount, { account_info, writable })]
+    #[acc(pool_account, { writable })]
+    #[pda(fee_collector, FeeCollectorAccount, { account_info, writable })]
+    #[acc(fee_collector_account, { writable })]
+    #[pda(verification_account, VerificationAccount, pda_pubkey = original_fee_payer.pubkey(), pda_offset = Some(VerificationAccount::pda_offset(verification_account_index, verification_account_generation)), { writable, account_info })]
+    #[pda(verification_index_account, VerificationIndexAccount, pda_pubkey = original_fee_payer.pubkey(), { writable, account_info, find_pda })]
+    #[acc(nullifier_duplicate_account, { writable, owned })]
+    #[pda(buffer, CommitmentBufferAccount, { writable })]
+    #[acc(token_program)] // if `token_id = 0` { `system_program` } else { `token_program` }
+    CleanupStalledVerification {
+        verification_account_index: u8,
+        verification_account_generation: u32,
+    },
+
+    /// Grows a [`GovernorAccount`] opened by a previous program version to the current size
+    #[acc(authority, { signer })]
+    #[acc(payer, { writable, signer })]
+    #[pda(governor, GovernorAccount, { writable, account_info })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    ReallocGovernorAccount,
+
+    #[acc(authority, { signer })]
+    #[pda(governor, GovernorAccount, { writable })]
+    SetTimestampPruningBits { timestamp_pruning_bits: u8 },
+
     #[cfg(not(feature = "mainnet"))]
     #[acc(payer, { signer })]
     #[acc(recipient, { writable })]
@@ -306,6 +593,45 @@ pub enum ElusivInstruction {
     #[sys(system_program, key = system_program::ID, { ignore })]
     CloseProgramAccount,
 
+    #[cfg(feature = "devnet-tools")]
+    #[acc(payer, { writable, signer })]
+    #[pda(mint, TestMintAccount, pda_offset = Some(token_id.into()), { writable, account_info, find_pda })]
+    #[sys(token_program, key = spl_token::ID)]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    CreateTestMint { token_id: u16 },
+
+    #[cfg(feature = "devnet-tools")]
+    #[pda(mint, TestMintAccount, pda_offset = Some(token_id.into()), { writable, account_info, find_pda })]
+    #[acc(recipient, { writable })]
+    #[sys(token_program, key = spl_token::ID)]
+    MintTestTokens { token_id: u16, amount: u64 },
+
+    #[cfg(feature = "devnet-tools")]
+    #[acc(payer, { writable, signer })]
+    #[pda(price_account, TestPriceAccount, pda_offset = Some(token_id.into()), { writable, account_info, find_pda })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    SetTestPrice {
+        token_id: u16,
+        price: i64,
+        conf: u64,
+        expo: i32,
+    },
+
+    #[cfg(feature = "devnet-tools")]
+    #[acc(payer, { writable, signer })]
+    #[pda(failure_injection_account, FailureInjectionAccount, { writable, account_info, find_pda })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    OpenFailureInjectionAccount,
+
+    #[cfg(feature = "devnet-tools")]
+    #[acc(authority, { signer })]
+    #[pda(failure_injection, FailureInjectionAccount, { writable })]
+    SetFailureInjection {
+        force_queue_full: bool,
+        force_price_stale: bool,
+        force_compute_error_at_round: ElusivOption<u32>,
+    },
+
     #[acc(payer, { writable, signer })]
     #[pda(buffer, CommitmentBufferAccount, { writable, skip_pda_verification, account_info })]
     #[pda(metadata_queue, MetadataQueueAccount, { writable, skip_pda_verification, account_info })]
@@ -354,10 +680,12 @@ impl ElusivInstruction {
 
     pub fn init_verification_transfer_fee_sol_instruction(
         verification_account_index: u8,
+        verification_account_generation: u32,
         warden: Pubkey,
     ) -> solana_program::instruction::Instruction {
         ElusivInstruction::init_verification_transfer_fee_instruction(
             verification_account_index,
+            verification_account_generation,
             WritableSignerAccount(warden),
             WritableUserAccount(warden),
             WritableUserAccount(PoolAccount::find(None).0),
@@ -370,6 +698,7 @@ impl ElusivInstruction {
 
     pub fn init_verification_transfer_fee_token_instruction(
         verification_account_index: u8,
+        verification_account_generation: u32,
         token_id: u16,
         warden: Pubkey,
         warden_account: Pubkey,
@@ -380,6 +709,7 @@ impl ElusivInstruction {
 
         ElusivInstruction::init_verification_transfer_fee_instruction(
             verification_account_index,
+            verification_account_generation,
             WritableSignerAccount(warden),
             WritableUserAccount(warden_account),
             WritableUserAccount(pool_account),
@@ -389,6 +719,112 @@ impl ElusivInstruction {
             UserAccount(spl_token::id()),
         )
     }
+
+    /// Returns the writable `pool`, `pool_account`, `fee_collector` and `fee_collector_account` metas (in this order) for `token_id`
+    ///
+    /// # Note
+    ///
+    /// For Lamports the PDAs are their own token-accounts, otherwise the associated-token-accounts of the PDAs are used.
+    pub fn pool_and_fee_collector_account_metas(
+        token_id: u16,
+    ) -> Result<
+        Vec<solana_program::instruction::AccountMeta>,
+        solana_program::program_error::ProgramError,
+    > {
+        use crate::processor::program_token_account_address;
+        use solana_program::instruction::AccountMeta;
+
+        let pool = PoolAccount::find(None).0;
+        let fee_collector = FeeCollectorAccount::find(None).0;
+        let (pool_account, fee_collector_account) = if token_id == 0 {
+            (pool, fee_collector)
+        } else {
+            (
+                program_token_account_address::<PoolAccount>(token_id, None)?,
+                program_token_account_address::<FeeCollectorAccount>(token_id, None)?,
+            )
+        };
+
+        Ok(vec![
+            AccountMeta::new(pool, false),
+            AccountMeta::new(pool_account, false),
+            AccountMeta::new(fee_collector, false),
+            AccountMeta::new(fee_collector_account, false),
+        ])
+    }
+
+    /// Returns the (deduplicated) writable accounts of the finalize-transfer transaction of `request`
+    ///
+    /// # Note
+    ///
+    /// - Intended for transaction simulation and priority-fee estimation.
+    /// - For tokens, `recipient` is the recipient's token-account (`recipient_wallet` is ignored for Lamports).
+    /// - `rent_recipient` is the [`GovernorAccount`]'s `rent_recipient` (or `original_fee_payer` if none is set).
+    pub fn finalize_writable_accounts(
+        request: &ProofRequest,
+        original_fee_payer: Pubkey,
+        verification_account_index: u8,
+        verification_account_generation: u32,
+        recipient: Pubkey,
+        recipient_wallet: Pubkey,
+        rent_recipient: Pubkey,
+    ) -> Result<Vec<Pubkey>, solana_program::program_error::ProgramError> {
+        use crate::token::elusiv_token;
+        use crate::types::PublicInputs;
+
+        let join_split = match request {
+            ProofRequest::Send(public_inputs) => public_inputs.join_split_inputs(),
+            ProofRequest::Migrate(public_inputs) => public_inputs.join_split_inputs(),
+        };
+        let token_id = join_split.token_id;
+        let optional_fee_collector = join_split.optional_fee.collector;
+        let nullifier_duplicate_account = join_split.associated_nullifier_duplicate_pda_pubkey();
+        let network_fee_account = NetworkFeeAccount::find(Some(token_id.into())).0;
+
+        let ix = if token_id == 0 {
+            ElusivInstruction::finalize_verification_transfer_lamports_instruction(
+                verification_account_index,
+                verification_account_generation,
+                WritableSignerAccount(original_fee_payer),
+                WritableUserAccount(recipient),
+                WritableUserAccount(optional_fee_collector),
+                WritableUserAccount(nullifier_duplicate_account),
+                WritableUserAccount(rent_recipient),
+                WritableUserAccount(network_fee_account),
+            )
+        } else {
+            let mint = elusiv_token(token_id)?.mint;
+            let accounts = ElusivInstruction::pool_and_fee_collector_account_metas(token_id)?;
+
+            ElusivInstruction::finalize_verification_transfer_token_instruction(
+                verification_account_index,
+                verification_account_generation,
+                WritableSignerAccount(original_fee_payer),
+                WritableUserAccount(spl_associated_token_account::get_associated_token_address(
+                    &original_fee_payer,
+                    &mint,
+                )),
+                WritableUserAccount(recipient),
+                WritableUserAccount(recipient_wallet),
+                WritableUserAccount(accounts[1].pubkey),
+                WritableUserAccount(accounts[3].pubkey),
+                WritableUserAccount(optional_fee_collector),
+                WritableUserAccount(nullifier_duplicate_account),
+                UserAccount(mint),
+                WritableUserAccount(rent_recipient),
+                WritableUserAccount(network_fee_account),
+            )
+        };
+
+        let mut writable_accounts: Vec<Pubkey> = Vec::new();
+        for account in ix.accounts.iter().filter(|account| account.is_writable) {
+            if !writable_accounts.contains(&account.pubkey) {
+                writable_accounts.push(account.pubkey);
+            }
+        }
+
+        Ok(writable_accounts)
+    }
 }
 
 #[cfg(test)]
@@ -424,5 +860,374 @@ mod tests {
             14,
             ElusivInstruction::FINALIZE_VERIFICATION_TRANSFER_TOKEN_INDEX
         );
+
+        assert_eq!(
+            elusiv_interface::FINALIZE_BASE_COMMITMENT_HASH_INDEX,
+            ElusivInstruction::FINALIZE_BASE_COMMITMENT_HASH_INDEX
+        );
+        assert_eq!(
+            elusiv_interface::FINALIZE_VERIFICATION_TRANSFER_LAMPORTS_INDEX,
+            ElusivInstruction::FINALIZE_VERIFICATION_TRANSFER_LAMPORTS_INDEX
+        );
+        assert_eq!(
+            elusiv_interface::FINALIZE_VERIFICATION_TRANSFER_TOKEN_INDEX,
+            ElusivInstruction::FINALIZE_VERIFICATION_TRANSFER_TOKEN_INDEX
+        );
+    }
+
+    #[test]
+    fn test_elusiv_instruction_kind() {
+        let instruction = ElusivInstruction::FinalizeVerificationSend {
+            verification_account_index: 0,
+            verification_account_generation: 0,
+            data: FinalizeSendData::default(),
+            uses_memo: false,
+        };
+        let data = instruction.try_to_vec().unwrap();
+
+        assert_eq!(data[0], ElusivInstruction::FINALIZE_VERIFICATION_SEND_INDEX);
+        assert_eq!(
+            ElusivInstructionKind::try_from(data[0]),
+            Ok(ElusivInstructionKind::FinalizeVerificationSend)
+        );
+        assert_eq!(
+            instruction.kind(),
+            ElusivInstructionKind::FinalizeVerificationSend
+        );
+
+        // `Nop` is the last instruction
+        assert_eq!(
+            ElusivInstruction::NOP_INDEX as usize,
+            ElusivInstruction::VARIANT_COUNT - 1
+        );
+        assert_eq!(
+            ElusivInstructionKind::try_from(ElusivInstruction::VARIANT_COUNT as u8),
+            Err(ElusivInstruction::VARIANT_COUNT as u8)
+        );
+    }
+
+    #[test]
+    fn test_instruction_indices_are_stable() {
+        // The discriminants of already deployed instructions must never change (clients and off-chain tooling rely on them)
+        for (index, expected) in [
+            (ElusivInstruction::STORE_BASE_COMMITMENT_INDEX, 0),
+            (ElusivInstruction::COMPUTE_BASE_COMMITMENT_HASH_INDEX, 1),
+            (ElusivInstruction::FINALIZE_BASE_COMMITMENT_HASH_INDEX, 2),
+            (ElusivInstruction::INIT_COMMITMENT_HASH_SETUP_INDEX, 3),
+            (ElusivInstruction::INIT_COMMITMENT_HASH_INDEX, 4),
+            (ElusivInstruction::COMPUTE_COMMITMENT_HASH_INDEX, 5),
+            (ElusivInstruction::FINALIZE_COMMITMENT_HASH_INDEX, 6),
+            (ElusivInstruction::INIT_VERIFICATION_INDEX, 7),
+            (ElusivInstruction::INIT_VERIFICATION_TRANSFER_FEE_INDEX, 8),
+            (ElusivInstruction::INIT_VERIFICATION_PROOF_INDEX, 9),
+            (ElusivInstruction::COMPUTE_VERIFICATION_INDEX, 10),
+            (ElusivInstruction::FINALIZE_VERIFICATION_SEND_INDEX, 11),
+            (
+                ElusivInstruction::FINALIZE_VERIFICATION_INSERT_NULLIFIER_INDEX,
+                12,
+            ),
+            (
+                ElusivInstruction::FINALIZE_VERIFICATION_TRANSFER_LAMPORTS_INDEX,
+                13,
+            ),
+            (
+                ElusivInstruction::FINALIZE_VERIFICATION_TRANSFER_TOKEN_INDEX,
+                14,
+            ),
+            (ElusivInstruction::CREATE_VKEY_ACCOUNT_INDEX, 15),
+            (ElusivInstruction::CREATE_NEW_VKEY_VERSION_INDEX, 16),
+            (ElusivInstruction::UPDATE_VKEY_VERSION_INDEX, 17),
+            (ElusivInstruction::SET_VKEY_DATA_INDEX, 18),
+            (ElusivInstruction::FREEZE_VKEY_INDEX, 19),
+            (ElusivInstruction::CHANGE_VKEY_AUTHORITY_INDEX, 20),
+            (ElusivInstruction::RESET_ACTIVE_MERKLE_TREE_INDEX, 21),
+            (ElusivInstruction::ARCHIVE_CLOSED_MERKLE_TREE_INDEX, 22),
+            (ElusivInstruction::OPEN_SINGLE_INSTANCE_ACCOUNTS_INDEX, 23),
+            (ElusivInstruction::OPEN_NULLIFIER_ACCOUNT_INDEX, 24),
+            (ElusivInstruction::ENABLE_STORAGE_CHILD_ACCOUNT_INDEX, 25),
+            (ElusivInstruction::ENABLE_NULLIFIER_CHILD_ACCOUNT_INDEX, 26),
+            (ElusivInstruction::ENABLE_METADATA_CHILD_ACCOUNT_INDEX, 27),
+            (ElusivInstruction::SETUP_GOVERNOR_ACCOUNT_INDEX, 28),
+            (ElusivInstruction::UPGRADE_GOVERNOR_STATE_INDEX, 29),
+            (ElusivInstruction::INIT_NEW_FEE_VERSION_INDEX, 30),
+        ] {
+            assert_eq!(index, expected);
+        }
+    }
+
+    #[test]
+    fn test_fee_payer_account_index() {
+        // The original fee payer needs to be the first account for the Warden-Network strict stats tracking
+        let fee_payer = Pubkey::new_unique();
+        let index = elusiv_interface::FEE_PAYER_ACCOUNT_INDEX;
+
+        let ix = ElusivInstruction::finalize_base_commitment_hash_instruction(
+            0,
+            0,
+            WritableUserAccount(fee_payer),
+        );
+        assert_eq!(ix.accounts[index].pubkey, fee_payer);
+
+        let ix = ElusivInstruction::finalize_verification_transfer_lamports_instruction(
+            0,
+            0,
+            WritableSignerAccount(fee_payer),
+            WritableUserAccount(Pubkey::new_unique()),
+            WritableUserAccount(Pubkey::new_unique()),
+            WritableUserAccount(Pubkey::new_unique()),
+            WritableUserAccount(Pubkey::new_unique()),
+            WritableUserAccount(Pubkey::new_unique()),
+        );
+        assert_eq!(ix.accounts[index].pubkey, fee_payer);
+
+        let ix = ElusivInstruction::finalize_verification_transfer_token_instruction(
+            0,
+            0,
+            WritableSignerAccount(fee_payer),
+            WritableUserAccount(Pubkey::new_unique()),
+            WritableUserAccount(Pubkey::new_unique()),
+            WritableUserAccount(Pubkey::new_unique()),
+            WritableUserAccount(Pubkey::new_unique()),
+            WritableUserAccount(Pubkey::new_unique()),
+            WritableUserAccount(Pubkey::new_unique()),
+            WritableUserAccount(Pubkey::new_unique()),
+            UserAccount(Pubkey::new_unique()),
+            WritableUserAccount(Pubkey::new_unique()),
+            WritableUserAccount(Pubkey::new_unique()),
+        );
+        assert_eq!(ix.accounts[index].pubkey, fee_payer);
+    }
+
+    #[test]
+    fn test_pool_and_fee_collector_account_metas() {
+        use crate::token::{elusiv_token, LAMPORTS_TOKEN_ID, USDC_TOKEN_ID, USDT_TOKEN_ID};
+
+        for token_id in [LAMPORTS_TOKEN_ID, USDC_TOKEN_ID, USDT_TOKEN_ID] {
+            let metas = ElusivInstruction::pool_and_fee_collector_account_metas(token_id).unwrap();
+            assert_eq!(metas.len(), 4);
+            assert!(metas.iter().all(|meta| meta.is_writable && !meta.is_signer));
+
+            // Same positions and flags as in a finalize transaction
+            let ix = ElusivInstruction::finalize_verification_transfer_token_instruction(
+                0,
+                0,
+                WritableSignerAccount(Pubkey::new_unique()),
+                WritableUserAccount(Pubkey::new_unique()),
+                WritableUserAccount(Pubkey::new_unique()),
+                WritableUserAccount(Pubkey::new_unique()),
+                WritableUserAccount(metas[1].pubkey),
+                WritableUserAccount(metas[3].pubkey),
+                WritableUserAccount(Pubkey::new_unique()),
+                WritableUserAccount(Pubkey::new_unique()),
+                UserAccount(Pubkey::new_unique()),
+                WritableUserAccount(Pubkey::new_unique()),
+                WritableUserAccount(Pubkey::new_unique()),
+            );
+            assert_eq!(ix.accounts[4..8], metas);
+
+            // Matches the on-chain token-account verification
+            assert_eq!(metas[0].pubkey, PoolAccount::find(None).0);
+            assert_eq!(metas[2].pubkey, FeeCollectorAccount::find(None).0);
+            for (pda, token_account) in [(&metas[0], &metas[1]), (&metas[2], &metas[3])] {
+                let expected = if token_id == LAMPORTS_TOKEN_ID {
+                    pda.pubkey
+                } else {
+                    spl_associated_token_account::get_associated_token_address(
+                        &pda.pubkey,
+                        &elusiv_token(token_id).unwrap().mint,
+                    )
+                };
+                assert_eq!(token_account.pubkey, expected);
+            }
+        }
+
+        assert!(ElusivInstruction::pool_and_fee_collector_account_metas(u16::MAX).is_err());
+    }
+
+    #[test]
+    fn test_finalize_writable_accounts() {
+        use crate::processor::ProofRequest;
+        use crate::state::commitment::CommitmentQueueAccount;
+        use crate::token::{elusiv_token, LAMPORTS_TOKEN_ID, USDC_TOKEN_ID};
+        use crate::types::{
+            InputCommitment, JoinSplitPublicInputs, OptionalFee, RawU256, SendPublicInputs,
+        };
+        use std::collections::HashSet;
+
+        let fee_payer = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let recipient_wallet = Pubkey::new_unique();
+        let optional_fee_collector = Pubkey::new_unique();
+
+        for token_id in [LAMPORTS_TOKEN_ID, USDC_TOKEN_ID] {
+            let public_inputs = SendPublicInputs {
+                join_split: JoinSplitPublicInputs {
+                    input_commitments: vec![InputCommitment {
+                        root: Some(RawU256::new([1; 32])),
+                        nullifier_hash: RawU256::new([2; 32]),
+                    }],
+                    output_commitment: RawU256::new([3; 32]),
+                    recent_commitment_index: 0,
+                    fee_version: 0,
+                    amount: 1_000,
+                    fee: 0,
+                    optional_fee: OptionalFee {
+                        collector: optional_fee_collector,
+                        amount: 1,
+                    },
+                    token_id,
+                    metadata: CommitmentMetadata::default(),
+                },
+                recipient_is_associated_token_account: false,
+                solana_pay_transfer: false,
+                hashed_inputs: [0; 32],
+                recipient_is_program_owned: false,
+                view_tag: 0,
+                current_time: 0,
+            };
+            let nullifier_duplicate_account = public_inputs
+                .join_split
+                .associated_nullifier_duplicate_pda_pubkey();
+            let verification_account = VerificationAccount::find_with_pubkey(
+                fee_payer,
+                Some(VerificationAccount::pda_offset(1, 2)),
+            )
+            .0;
+
+            let mut expected = vec![
+                fee_payer,
+                recipient,
+                PoolAccount::find(None).0,
+                FeeCollectorAccount::find(None).0,
+                optional_fee_collector,
+                CommitmentQueueAccount::find(None).0,
+                MetadataQueueAccount::find(None).0,
+                verification_account,
+                nullifier_duplicate_account,
+                NetworkFeeAccount::find(Some(token_id.into())).0,
+                VerificationIndexAccount::find_with_pubkey(fee_payer, None).0,
+            ];
+            if token_id != LAMPORTS_TOKEN_ID {
+                let mint = elusiv_token(token_id).unwrap().mint;
+                let accounts =
+                    ElusivInstruction::pool_and_fee_collector_account_metas(token_id).unwrap();
+
+                expected.push(spl_associated_token_account::get_associated_token_address(
+                    &fee_payer, &mint,
+                ));
+                expected.push(recipient_wallet);
+                expected.push(accounts[1].pubkey);
+                expected.push(accounts[3].pubkey);
+            }
+
+            let writable_accounts = ElusivInstruction::finalize_writable_accounts(
+                &ProofRequest::Send(public_inputs),
+                fee_payer,
+                1,
+                2,
+                recipient,
+                recipient_wallet,
+                fee_payer,
+            )
+            .unwrap();
+
+            // No duplicates (the fee-payer is also the rent-recipient)
+            assert_eq!(
+                writable_accounts.iter().collect::<HashSet<_>>().len(),
+                writable_accounts.len()
+            );
+            assert_eq!(
+                writable_accounts.into_iter().collect::<HashSet<_>>(),
+                expected.into_iter().collect::<HashSet<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_accounts() {
+        use crate::state::storage::StorageChildAccount;
+        use elusiv_types::accounts::{
+            AccountSnapshot, ParentAccount, SizedAccount, ValidationError,
+        };
+        use std::collections::HashMap;
+
+        let child_accounts: Vec<Pubkey> = (0..StorageAccount::COUNT)
+            .map(|_| Pubkey::new_unique())
+            .collect();
+        let storage_accounts = || {
+            child_accounts
+                .iter()
+                .map(|pubkey| WritableUserAccount(*pubkey))
+                .collect::<Vec<_>>()
+        };
+
+        let slots = ElusivInstruction::finalize_commitment_hash_account_slots(&storage_accounts());
+        assert_eq!(slots.len(), 2 + StorageAccount::COUNT);
+        assert_eq!(slots[0].min_size, CommitmentHashingAccount::SIZE);
+        assert_eq!(slots[1].min_size, StorageAccount::SIZE);
+        assert_eq!(slots[2].min_size, StorageChildAccount::SIZE);
+        assert!(slots.iter().all(|slot| slot.must_be_writable));
+
+        let mut rpc_snapshot: HashMap<Pubkey, AccountSnapshot> = slots
+            .iter()
+            .map(|slot| {
+                (
+                    slot.pubkey,
+                    AccountSnapshot {
+                        owner: crate::ID,
+                        data_len: slot.min_size,
+                        executable: false,
+                    },
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            ElusivInstruction::finalize_commitment_hash_validate_accounts(
+                &storage_accounts(),
+                &rpc_snapshot
+            ),
+            Ok(())
+        );
+
+        // Undersized child-account
+        rpc_snapshot.get_mut(&child_accounts[1]).unwrap().data_len -= 1;
+        assert_eq!(
+            ElusivInstruction::finalize_commitment_hash_validate_accounts(
+                &storage_accounts(),
+                &rpc_snapshot
+            ),
+            Err(ValidationError::AccountTooSmall {
+                pubkey: child_accounts[1],
+                min_size: StorageChildAccount::SIZE,
+                size: StorageChildAccount::SIZE - 1,
+            })
+        );
+        rpc_snapshot.get_mut(&child_accounts[1]).unwrap().data_len += 1;
+
+        // Wrong owner
+        let wrong_owner = Pubkey::new_unique();
+        rpc_snapshot.get_mut(&slots[0].pubkey).unwrap().owner = wrong_owner;
+        assert_eq!(
+            ElusivInstruction::finalize_commitment_hash_validate_accounts(
+                &storage_accounts(),
+                &rpc_snapshot
+            ),
+            Err(ValidationError::InvalidOwner {
+                pubkey: slots[0].pubkey,
+                expected: crate::ID,
+                actual: wrong_owner,
+            })
+        );
+
+        // Missing account
+        rpc_snapshot.remove(&slots[0].pubkey);
+        assert_eq!(
+            ElusivInstruction::finalize_commitment_hash_validate_accounts(
+                &storage_accounts(),
+                &rpc_snapshot
+            ),
+            Err(ValidationError::MissingAccount(slots[0].pubkey))
+        );
     }
 }
@@ -0,0 +1,209 @@
+//! Client-side planning of join-splits for amounts that can't be covered by a single join-split
+//!
+//! A join-split consumes at most [`JOIN_SPLIT_MAX_N_ARITY`] input commitments (notes).
+//! If the largest notes are not sufficient, notes are first consolidated with merges (join-splits with `amount = 0`) whose output commitments are then used as inputs for the following join-splits.
+
+use crate::types::JOIN_SPLIT_MAX_N_ARITY;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum JoinSplitPlanError {
+    /// The notes are not sufficient for covering the amount and all fees
+    InsufficientFunds,
+
+    /// The amounts exceed `u64::MAX`
+    Overflow,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum JoinSplitInput {
+    /// An available note (index into the provided note amounts)
+    Note(usize),
+
+    /// The output commitment of a previous join-split in the plan
+    Output(usize),
+}
+
+/// The shape of a single join-split (the values required for building its [`crate::types::JoinSplitPublicInputs`])
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct JoinSplitShape {
+    pub inputs: Vec<JoinSplitInput>,
+    pub amount: u64,
+    pub fee: u64,
+
+    /// Amount of the output commitment (consolidated note for merges, remainder otherwise)
+    pub output_amount: u64,
+}
+
+impl JoinSplitShape {
+    pub fn is_merge(&self) -> bool {
+        self.amount == 0
+    }
+}
+
+/// Plans the sequence of join-splits required for sending `amount` using the notes with `note_amounts`
+///
+/// # Notes
+///
+/// - `fee` is the fee paid for each join-split.
+/// - The largest notes are merged first, which results in the minimum number of join-splits.
+/// - The last join-split sends `amount`, all previous ones are merges.
+pub fn plan_join_splits(
+    note_amounts: &[u64],
+    amount: u64,
+    fee: u64,
+) -> Result<Vec<JoinSplitShape>, JoinSplitPlanError> {
+    let required = amount
+        .checked_add(fee)
+        .ok_or(JoinSplitPlanError::Overflow)?;
+    let sum = |notes: &[(JoinSplitInput, u64)]| {
+        notes
+            .iter()
+            .try_fold(0u64, |acc, (_, a)| acc.checked_add(*a))
+    };
+
+    let mut notes: Vec<(JoinSplitInput, u64)> = note_amounts
+        .iter()
+        .enumerate()
+        .map(|(i, a)| (JoinSplitInput::Note(i), *a))
+        .collect();
+    let mut plan = Vec::new();
+
+    loop {
+        notes.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+        let count = std::cmp::min(notes.len(), JOIN_SPLIT_MAX_N_ARITY);
+        let max_input_amount = sum(&notes[..count]).ok_or(JoinSplitPlanError::Overflow)?;
+
+        if max_input_amount >= required {
+            // Uses the minimum number of inputs
+            let mut input_amount = 0;
+            let mut inputs = Vec::new();
+            for (input, a) in &notes {
+                if input_amount >= required && !inputs.is_empty() {
+                    break;
+                }
+                input_amount += a;
+                inputs.push(*input);
+            }
+
+            plan.push(JoinSplitShape {
+                inputs,
+                amount,
+                fee,
+                output_amount: input_amount - required,
+            });
+
+            return Ok(plan);
+        }
+
+        // Merging does not reduce the number of notes anymore
+        if notes.len() <= JOIN_SPLIT_MAX_N_ARITY {
+            return Err(JoinSplitPlanError::InsufficientFunds);
+        }
+
+        let merged: Vec<_> = notes.drain(..JOIN_SPLIT_MAX_N_ARITY).collect();
+        let output_amount = max_input_amount
+            .checked_sub(fee)
+            .ok_or(JoinSplitPlanError::InsufficientFunds)?;
+
+        plan.push(JoinSplitShape {
+            inputs: merged.iter().map(|(input, _)| *input).collect(),
+            amount: 0,
+            fee,
+            output_amount,
+        });
+        notes.push((JoinSplitInput::Output(plan.len() - 1), output_amount));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use JoinSplitInput::{Note, Output};
+
+    #[test]
+    fn test_plan_join_splits_single() {
+        assert_eq!(
+            plan_join_splits(&[5, 10, 3], 12, 1).unwrap(),
+            vec![JoinSplitShape {
+                inputs: vec![Note(1), Note(0)],
+                amount: 12,
+                fee: 1,
+                output_amount: 2,
+            }]
+        );
+
+        // Exact amount with all notes
+        assert_eq!(
+            plan_join_splits(&[1, 2, 3, 4], 9, 1).unwrap(),
+            vec![JoinSplitShape {
+                inputs: vec![Note(3), Note(2), Note(1), Note(0)],
+                amount: 9,
+                fee: 1,
+                output_amount: 0,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_plan_join_splits_multi() {
+        let plan = plan_join_splits(&[10; 7], 55, 1).unwrap();
+        assert_eq!(
+            plan,
+            vec![
+                JoinSplitShape {
+                    inputs: vec![Note(0), Note(1), Note(2), Note(3)],
+                    amount: 0,
+                    fee: 1,
+                    output_amount: 39,
+                },
+                JoinSplitShape {
+                    inputs: vec![Output(0), Note(4), Note(5)],
+                    amount: 55,
+                    fee: 1,
+                    output_amount: 3,
+                },
+            ]
+        );
+        assert!(plan[0].is_merge());
+        assert!(!plan[1].is_merge());
+
+        // Multiple merges (each consolidating three additional notes)
+        let plan = plan_join_splits(&[10; 13], 120, 1).unwrap();
+        assert_eq!(plan.len(), 4);
+        assert!(plan[..3].iter().all(|j| j.is_merge()));
+        assert_eq!(plan[1].inputs[0], Output(0));
+        assert_eq!(plan[2].inputs[0], Output(1));
+        assert_eq!(plan[3].inputs[0], Output(2));
+        assert_eq!(plan[3].output_amount, 130 - 120 - 4);
+
+        // Every input is used once
+        let inputs: Vec<_> = plan.iter().flat_map(|j| j.inputs.clone()).collect();
+        assert_eq!(inputs.len(), 13 + 3);
+        assert_eq!(inputs.iter().collect::<HashSet<_>>().len(), inputs.len());
+    }
+
+    #[test]
+    fn test_plan_join_splits_insufficient_funds() {
+        assert_eq!(
+            plan_join_splits(&[], 1, 0),
+            Err(JoinSplitPlanError::InsufficientFunds)
+        );
+        assert_eq!(
+            plan_join_splits(&[5, 5], 10, 1),
+            Err(JoinSplitPlanError::InsufficientFunds)
+        );
+
+        // Sufficient funds in total, but not after paying the merge fees
+        assert_eq!(
+            plan_join_splits(&[10; 5], 49, 1),
+            Err(JoinSplitPlanError::InsufficientFunds)
+        );
+
+        assert_eq!(
+            plan_join_splits(&[1], u64::MAX, 1),
+            Err(JoinSplitPlanError::Overflow)
+        );
+    }
+}
@@ -7,12 +7,18 @@ pub mod entrypoint;
 mod error;
 pub mod fields;
 pub mod instruction;
+#[cfg(feature = "elusiv-client")]
+pub mod join_split_plan;
 mod macros;
 pub mod map;
 pub mod processor;
 pub mod proof;
 pub mod state;
+#[cfg(feature = "test-vectors")]
+pub mod test_vectors;
 pub mod token;
+#[cfg(feature = "elusiv-client")]
+pub mod transaction_plan;
 pub mod types;
 
 pub use elusiv_computation;
@@ -26,3 +32,6 @@ extern crate static_assertions;
 compile_error!(
     "The 'devnet' and 'mainnet' features are mutually exclusive and cannot be used together."
 );
+
+#[cfg(all(feature = "devnet-tools", feature = "mainnet"))]
+compile_error!("The 'devnet-tools' feature cannot be used in mainnet builds.");
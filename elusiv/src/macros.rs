@@ -110,6 +110,21 @@ macro_rules! test_pda_account_info {
     };
 }
 
+/// Creates a zeroed [`elusiv_types::PDAAccount`] (storing the bump of `$offset`) for testing
+///
+/// # Usage
+///
+/// `zero_pda_account_info!($id: ident, $ty: ty, $offset: expr)`
+#[cfg(test)]
+macro_rules! zero_pda_account_info {
+    ($id: ident, $ty: ty, $offset: expr) => {
+        let (pk, bump) = <$ty as elusiv_types::PDAAccount>::find($offset);
+        let mut data = vec![0; <$ty as elusiv_types::SizedAccount>::SIZE];
+        data[0] = bump;
+        crate::macros::account_info!($id, pk, data)
+    };
+}
+
 /// Creates a program-token-account for a specific [`elusiv_types::PDAAccount`] and a token-id
 ///
 /// # Usage
@@ -120,7 +135,14 @@ macro_rules! program_token_account_info {
     ($id: ident, $pda_ty: ty, $token_id: expr) => {
         let pk =
             crate::processor::program_token_account_address::<$pda_ty>($token_id, None).unwrap();
-        crate::macros::account_info!($id, pk, vec![], spl_token::id(), false)
+        let owner = <$pda_ty as elusiv_types::PDAAccount>::find(None).0;
+        crate::macros::account_info!(
+            $id,
+            pk,
+            crate::token::spl_token_account_data_with_owner($token_id, &owner),
+            spl_token::id(),
+            false
+        )
     };
 }
 
@@ -130,8 +152,13 @@ macro_rules! program_token_account_info {
 ///
 /// - `zero_program_account!($id: ident, $ty: ty)`
 /// - mutable instance: `zero_program_account!(mut $id: ident, $ty: ty)`
+/// - mutable instance with trailing data: `zero_program_account!(mut $id: ident, $ty: ty, $size: expr)`
 #[cfg(test)]
 macro_rules! zero_program_account {
+    (mut $id: ident, $ty: ty, $size: expr) => {
+        let mut data = vec![0; $size];
+        let mut $id = <$ty as elusiv_types::ProgramAccount>::new(&mut data).unwrap();
+    };
     (mut $id: ident, $ty: ty) => {
         let mut data = vec![0; <$ty as elusiv_types::SizedAccount>::SIZE];
         let mut $id = <$ty as elusiv_types::ProgramAccount>::new(&mut data).unwrap();
@@ -177,8 +204,28 @@ macro_rules! parent_account {
     };
 }
 
+/// Logs a labeled compute-unit checkpoint (only with the `cu-trace` feature, no-op otherwise)
+///
+/// # Note
+///
+/// The label is logged as `cu-checkpoint: $label`, followed by the remaining compute units.
+///
+/// # Usage
+///
+/// `cu_checkpoint!($label: literal)`
+macro_rules! cu_checkpoint {
+    ($label: literal) => {
+        #[cfg(feature = "cu-trace")]
+        {
+            solana_program::msg!(concat!("cu-checkpoint: ", $label));
+            solana_program::log::sol_log_compute_units();
+        }
+    };
+}
+
 #[cfg(test)]
 pub(crate) use account_info;
+pub(crate) use cu_checkpoint;
 #[cfg(test)]
 pub(crate) use parent_account;
 #[cfg(test)]
@@ -192,4 +239,6 @@ pub(crate) use test_account_info;
 #[cfg(test)]
 pub(crate) use test_pda_account_info;
 #[cfg(test)]
+pub(crate) use zero_pda_account_info;
+#[cfg(test)]
 pub(crate) use zero_program_account;
@@ -414,6 +414,10 @@ impl<'a, K: ElusivMapKey, V: ElusivMapValue, const CAPACITY: usize> ElusivMap<'a
         self.value(&ptr)
     }
 
+    pub fn len(&mut self) -> u32 {
+        self.len.get()
+    }
+
     pub fn is_empty(&mut self) -> bool {
         self.len.get() == 0
     }
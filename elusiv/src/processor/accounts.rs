@@ -2,28 +2,47 @@ use super::utils::*;
 use crate::bytes::{is_zero, BorshSerDeSized, ElusivOption};
 use crate::commitment::DEFAULT_COMMITMENT_BATCHING_RATE;
 use crate::error::ElusivError;
+use crate::fields::{is_element_scalar_field, u256_to_big_uint};
 use crate::macros::*;
 use crate::state::commitment::{
     BaseCommitmentBufferAccount, CommitmentBufferAccount, CommitmentHashingAccount,
     CommitmentQueue, CommitmentQueueAccount,
 };
+use crate::state::genesis::{GenesisParams, GenesisParamsAccount};
 use crate::state::metadata::{MetadataAccount, MetadataQueueAccount};
 use crate::state::queue::RingQueue;
 use crate::state::{
     fee::{FeeAccount, ProgramFee},
-    governor::{FeeCollectorAccount, GovernorAccount, PoolAccount},
-    nullifier::{NullifierAccount, NullifierChildAccount},
+    governor::{
+        FeeCollectorAccount, GovernorAccount, GovernorHead, NetworkFeeAccount, PoolAccount,
+        DEFAULT_TIMESTAMP_PRUNING_BITS, MAX_MEMO_PROGRAM_IDS, MAX_TIMESTAMP_PRUNING_BITS,
+        PAUSABLE_CATEGORIES, SPL_MEMO_PROGRAM_ID,
+    },
+    nullifier::{NullifierAccount, NullifierChildAccount, NullifierMergeAccount},
     queue::Queue,
     storage::{StorageAccount, MT_COMMITMENT_COUNT},
+    token_registry::{
+        registered_elusiv_token, RegisteredToken, TokenRegistryAccount, TOKEN_REGISTRY_CAPACITY,
+    },
 };
+use crate::token::{elusiv_token, TokenError, TokenID, SPL_TOKEN_COUNT};
+use crate::types::U256;
 use crate::{bytes::usize_as_u32_safe, map::ElusivMap};
+use borsh::{BorshDeserialize, BorshSerialize};
 use elusiv_types::{
-    split_child_account_data_mut, ChildAccount, ChildAccountConfig, ParentAccount, SizedAccount,
-    UnverifiedAccountInfo,
+    split_child_account_data_mut, ChildAccount, ChildAccountConfig, PDAAccount, ParentAccount,
+    SizedAccount, UnverifiedAccountInfo,
 };
 use solana_program::{
-    account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError, rent::Rent,
-    sysvar::Sysvar,
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    program::set_return_data,
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_program,
+    sysvar::{instructions, Sysvar},
 };
 
 /// Opens one single instance [`elusiv_types::PDAAccount`], as long this PDA does not already exist
@@ -154,6 +173,51 @@ pub fn enable_metadata_child_account(
     )
 }
 
+/// Verifies the owner and size of all child-accounts of the [`StorageAccount`] and a [`NullifierAccount`] once
+///
+/// # Note
+///
+/// - Subsequent instructions skip the owner check of verified child-accounts (until a child-account's pubkey is changed).
+/// - All child-accounts need to be enabled and supplied.
+pub fn warm_tree_accounts(
+    storage_account: &mut StorageAccount,
+    nullifier_account: &mut NullifierAccount,
+
+    _tree_index: u32,
+) -> ProgramResult {
+    verify_child_accounts(storage_account)?;
+    verify_child_accounts(nullifier_account)
+}
+
+fn verify_child_accounts<'a, 'b, 't, P: ParentAccount<'a, 'b, 't>>(
+    parent_account: &mut P,
+) -> ProgramResult {
+    let mut bitmap = 0;
+    for child_index in 0..P::COUNT {
+        guard!(
+            parent_account.get_child_pubkey(child_index).is_some(),
+            ElusivError::ChildAccouttDoesNotExists
+        );
+
+        // Safety: only the account's metadata is accessed
+        let child_account = unsafe { parent_account.get_child_account_unsafe(child_index) }?;
+        guard!(
+            *child_account.owner == crate::ID,
+            ProgramError::IllegalOwner
+        );
+        guard!(
+            child_account.data_len() == <P::Child as SizedAccount>::SIZE,
+            ProgramError::InvalidAccountData
+        );
+
+        bitmap |= 1 << child_index;
+    }
+
+    parent_account.set_verified_children_bitmap(bitmap);
+
+    Ok(())
+}
+
 /// Closes the active MT and activates the next one
 ///
 /// # Notes
@@ -272,6 +336,114 @@ pub fn archive_closed_merkle_tree<'a>(
     panic!("N-SMT not implemented yet");
 }
 
+/// The maximum number of nullifier-hashes merged by a single [`merge_nullifier_trees`] instruction
+pub const NULLIFIER_MERGE_BATCH_SIZE: u32 = 16;
+
+/// Merges the nullifier-hashes of the closed MT `source_mt_index` into the closed MT `destination_mt_index`
+///
+/// # Note
+///
+/// - `authority` needs to be the program's keypair.
+/// - Each call merges up to [`NULLIFIER_MERGE_BATCH_SIZE`] nullifier-hashes, the progress is stored in the [`NullifierMergeAccount`].
+/// - Calls after the merge has finished have no effect.
+/// - A nullifier-hash of the source MT already contained in the destination MT results in an error.
+#[allow(clippy::too_many_arguments)]
+pub fn merge_nullifier_trees<'a, 'b, 'c, 'd>(
+    authority: &AccountInfo,
+    payer: &AccountInfo<'a>,
+    storage_account: &StorageAccount,
+    merge_account: &AccountInfo<'a>,
+    source_nullifier_account: &NullifierAccount<'b, 'c, 'd>,
+    destination_nullifier_account: &mut NullifierAccount<'b, 'c, 'd>,
+
+    source_mt_index: u32,
+    destination_mt_index: u32,
+) -> ProgramResult {
+    guard!(*authority.key == crate::ID, ElusivError::InvalidAccount);
+    guard!(
+        source_mt_index != destination_mt_index,
+        ElusivError::InvalidInstructionData
+    );
+
+    // Only closed MTs can be merged
+    let trees_count = storage_account.get_trees_count();
+    guard!(
+        source_mt_index < trees_count && destination_mt_index < trees_count,
+        ElusivError::InvalidInstructionData
+    );
+    guard!(
+        source_nullifier_account.is_moved_nullifier_empty()
+            && destination_nullifier_account.is_moved_nullifier_empty(),
+        ElusivError::InvalidAccountState
+    );
+
+    if merge_account.data_is_empty() {
+        open_pda_account_with_offset::<NullifierMergeAccount>(
+            &crate::id(),
+            payer,
+            merge_account,
+            source_mt_index,
+            None,
+        )?;
+    }
+
+    pda_account!(mut merge, NullifierMergeAccount, merge_account);
+
+    // The destination MT is bound with the first merged nullifier-hash
+    if merge.get_merged_count() == 0 {
+        merge.set_destination_mt_index(&destination_mt_index);
+    }
+    guard!(
+        merge.get_destination_mt_index() == destination_mt_index,
+        ElusivError::InvalidInstructionData
+    );
+
+    if merge.get_is_finished() {
+        return Ok(());
+    }
+
+    let mut child_account_index = merge.get_child_account_index() as usize;
+    let mut map_index = merge.get_map_index();
+    let mut merged_count = merge.get_merged_count();
+    let nullifier_hash_count = source_nullifier_account.get_nullifier_hash_count();
+
+    let mut batch_size = 0;
+    while batch_size < NULLIFIER_MERGE_BATCH_SIZE && merged_count < nullifier_hash_count {
+        if map_index >= source_nullifier_account.child_nullifier_hash_count(child_account_index)? {
+            child_account_index += 1;
+            map_index = 0;
+            continue;
+        }
+
+        let nullifier_hash =
+            source_nullifier_account.nullifier_hash_at(child_account_index, map_index)?;
+        guard!(
+            destination_nullifier_account.can_insert_nullifier_hash(nullifier_hash)?,
+            ElusivError::CouldNotInsertNullifier
+        );
+        destination_nullifier_account.try_insert_nullifier_hash(nullifier_hash)?;
+
+        while !destination_nullifier_account.is_moved_nullifier_empty() {
+            destination_nullifier_account.move_nullifier_hashes_to_next_account()?;
+        }
+
+        map_index += 1;
+        merged_count += 1;
+        batch_size += 1;
+    }
+
+    merge.set_child_account_index(&usize_as_u32_safe(child_account_index));
+    merge.set_map_index(&map_index);
+    merge.set_merged_count(&merged_count);
+    merge.set_is_finished(&(merged_count == nullifier_hash_count));
+
+    Ok(())
+}
+
+pub const DEFAULT_MAX_DUPLICATE_VERIFICATIONS: u8 = 2;
+pub const DEFAULT_MIN_VERIFICATION_TIMEOUT: u64 = 60 * 60;
+pub const DEFAULT_MAX_VERIFICATION_TIMEOUT: u64 = 60 * 60 * 24 * 7;
+
 /// Setup the [`GovernorAccount`] with the default values
 ///
 /// # Note
@@ -290,6 +462,78 @@ pub fn setup_governor_account<'b>(
 
     pda_account!(mut governor, GovernorAccount, governor_account.get_unsafe());
     governor.set_commitment_batching_rate(&usize_as_u32_safe(DEFAULT_COMMITMENT_BATCHING_RATE));
+    set_default_governor_values(&mut governor);
+
+    Ok(())
+}
+
+/// Sets the defaults of the [`GovernorAccount`] fields appended after the initial program version
+fn set_default_governor_values(governor: &mut GovernorAccount) {
+    governor.set_allow_legacy_fee_payer_token_account(&true);
+    governor.set_max_duplicate_verifications(&DEFAULT_MAX_DUPLICATE_VERIFICATIONS);
+    governor.set_min_verification_timeout(&DEFAULT_MIN_VERIFICATION_TIMEOUT);
+    governor.set_max_verification_timeout(&DEFAULT_MAX_VERIFICATION_TIMEOUT);
+    governor.set_memo_program_ids(0, &ElusivOption::Some(SPL_MEMO_PROGRAM_ID));
+    governor.set_timestamp_pruning_bits(&ElusivOption::Some(DEFAULT_TIMESTAMP_PRUNING_BITS));
+}
+
+/// Setup the [`GenesisParamsAccount`] (required for deployments with non-default Poseidon parameters)
+///
+/// # Note
+///
+/// - `authority` needs to be the program's keypair.
+/// - The genesis params can only be written once.
+pub fn setup_genesis_params<'b>(
+    authority: &AccountInfo,
+    payer: &AccountInfo<'b>,
+    genesis_params_account: UnverifiedAccountInfo<'_, 'b>,
+
+    zero_commitment: U256,
+    zero_base_commitment: U256,
+) -> ProgramResult {
+    guard!(*authority.key == crate::ID, ElusivError::InvalidAccount);
+
+    open_pda_account_without_offset::<GenesisParamsAccount>(
+        &crate::id(),
+        payer,
+        genesis_params_account.get_unsafe(),
+        None,
+    )?;
+
+    pda_account!(
+        mut genesis_params,
+        GenesisParamsAccount,
+        genesis_params_account.get_unsafe()
+    );
+    write_genesis_params(
+        &mut genesis_params,
+        &GenesisParams {
+            zero_commitment,
+            zero_base_commitment,
+        },
+    )
+}
+
+fn write_genesis_params(
+    genesis_params_account: &mut GenesisParamsAccount,
+    genesis_params: &GenesisParams,
+) -> ProgramResult {
+    guard!(
+        !genesis_params_account.get_is_setup(),
+        ElusivError::InvalidAccountState
+    );
+    guard!(
+        is_element_scalar_field(u256_to_big_uint(&genesis_params.zero_commitment)),
+        ElusivError::NonScalarValue
+    );
+    guard!(
+        is_element_scalar_field(u256_to_big_uint(&genesis_params.zero_base_commitment)),
+        ElusivError::NonScalarValue
+    );
+
+    genesis_params_account.set_zero_commitment(&genesis_params.zero_commitment);
+    genesis_params_account.set_zero_base_commitment(&genesis_params.zero_base_commitment);
+    genesis_params_account.set_is_setup(&true);
 
     Ok(())
 }
@@ -308,297 +552,2219 @@ pub fn upgrade_governor_state(
     // TODO: fee changes require empty queues
 }
 
-/// Setup a new [`FeeAccount`]
+/// Grows a [`GovernorAccount`] opened by a previous program version to [`GovernorAccount::SIZE`]
 ///
 /// # Note
 ///
-/// There is no way of upgrading the program fees atm.
-pub fn init_new_fee_version<'b>(
-    payer: &AccountInfo<'b>,
+/// - `authority` needs to be the program's keypair, `payer` funds the additional rent.
+/// - The appended fields are set to the same defaults as in [`setup_governor_account`].
+pub fn realloc_governor_account<'a>(
+    authority: &AccountInfo,
+    payer: &AccountInfo<'a>,
+    governor_account: &AccountInfo<'a>,
+) -> ProgramResult {
+    guard!(*authority.key == crate::ID, ElusivError::InvalidAccount);
+
+    realloc_account(payer, governor_account, GovernorAccount::SIZE)?;
+
+    pda_account!(mut governor, GovernorAccount, governor_account);
+    set_default_governor_values(&mut governor);
+
+    Ok(())
+}
+
+/// Enables or disables the acceptance of legacy (non-associated) fee-payer token-accounts
+///
+/// # Note
+///
+/// `authority` needs to be the program's keypair.
+pub fn set_allow_legacy_fee_payer_token_account(
+    authority: &AccountInfo,
     governor: &mut GovernorAccount,
-    mut new_fee_account: UnverifiedAccountInfo<'_, 'b>,
 
-    fee_version: u32,
-    program_fee: ProgramFee,
+    allow: bool,
 ) -> ProgramResult {
-    // Note: we have no upgrade-authroity check here since with the current setup it's impossible to have a fee version higher than zero, so will be added once that changes
-    guard!(
-        fee_version == governor.get_fee_version(),
-        ElusivError::InvalidFeeVersion
-    );
-    guard!(program_fee.is_valid(), ElusivError::InvalidInstructionData);
-
-    open_pda_account_with_offset::<FeeAccount>(
-        &crate::id(),
-        payer,
-        new_fee_account.get_unsafe_and_set_is_verified(),
-        fee_version,
-        None,
-    )?;
+    guard!(*authority.key == crate::ID, ElusivError::InvalidAccount);
 
-    pda_account!(mut fee_account, FeeAccount, new_fee_account.get_safe()?);
-    fee_account.set_program_fee(&program_fee);
-    governor.set_program_fee(&program_fee);
+    governor.set_allow_legacy_fee_payer_token_account(&allow);
 
     Ok(())
 }
 
-/// Closes a program owned account in devnet and localhost
+/// Sets the maximum number of additional verifications (`skip_nullifier_pda`) of the same nullifier-hashes
 ///
 /// # Note
 ///
-/// - `signer` needs to be the program's keypair
-/// - `recipient` receives the accounts Lamports
-#[cfg(not(feature = "mainnet"))]
-pub fn close_program_account<'a>(
-    signer: &AccountInfo,
-    recipient: &AccountInfo<'a>,
-    program_account: &AccountInfo<'a>,
+/// `authority` needs to be the program's keypair.
+pub fn set_max_duplicate_verifications(
+    authority: &AccountInfo,
+    governor: &mut GovernorAccount,
+
+    max_duplicate_verifications: u8,
 ) -> ProgramResult {
-    assert!(!cfg!(feature = "mainnet"));
-    assert_eq!(*signer.key, crate::ID);
+    guard!(*authority.key == crate::ID, ElusivError::InvalidAccount);
 
-    elusiv_utils::close_account(recipient, program_account)
+    governor.set_max_duplicate_verifications(&max_duplicate_verifications);
+
+    Ok(())
 }
 
-/// Verifies a single user-supplied [`ChildAccount`] and then saves it's pubkey in the `parent_account`
+/// Sets the number of slots after the last use of a [`crate::state::proof::NullifierDuplicateAccount`] before it can be reused
 ///
 /// # Note
 ///
-/// If `size` is manually supplied (not the default [`C::SIZE`] is used) [`elusiv_types::ChildAccountConfig::SIZE`] needs to be contained in the size.
-pub fn setup_child_account<'a, 'b, 't, P: ParentAccount<'a, 'b, 't>>(
-    parent_account: &mut P,
-    child_account: &AccountInfo,
-    child_index: usize,
-    check_zeroness: bool,
-    size: Option<usize>,
+/// `authority` needs to be the program's keypair.
+pub fn set_duplicate_grace_period(
+    authority: &AccountInfo,
+    governor: &mut GovernorAccount,
+
+    duplicate_grace_period: u64,
 ) -> ProgramResult {
-    if parent_account.get_child_pubkey(child_index).is_some() {
-        return Err(ElusivError::ChildAccountAlreadyExists.into());
-    }
+    guard!(*authority.key == crate::ID, ElusivError::InvalidAccount);
 
-    verify_extern_data_account(
-        child_account,
-        size.unwrap_or(<P::Child as SizedAccount>::SIZE),
-        check_zeroness,
-    )?;
-    parent_account.set_child_pubkey(child_index, ElusivOption::Some(*child_account.key));
-    P::Child::try_start_using_account(child_account)?;
+    governor.set_duplicate_grace_period(&duplicate_grace_period);
 
     Ok(())
 }
 
-fn reset_map_child_account<C: ChildAccount>(child_account: &AccountInfo) -> ProgramResult {
-    let data = &mut child_account.data.borrow_mut()[..];
-    let (_, inner_data) = split_child_account_data_mut(data)?;
-    let mut map = ElusivMap::<(), (), 1>::new(&mut inner_data[..ElusivMap::<(), (), 1>::SIZE]);
-    map.reset();
+/// Sets the Lamports escrowed by the fee-payer for each verification
+///
+/// # Note
+///
+/// `authority` needs to be the program's keypair.
+pub fn set_failure_bond(
+    authority: &AccountInfo,
+    governor: &mut GovernorAccount,
+
+    failure_bond: u64,
+) -> ProgramResult {
+    guard!(*authority.key == crate::ID, ElusivError::InvalidAccount);
+
+    governor.set_failure_bond(&failure_bond);
 
     Ok(())
 }
 
-/// Verifies that an account with `data_len` > 10 KiB (non PDA) is formatted correctly
-fn verify_extern_data_account(
-    account: &AccountInfo,
-    data_len: usize,
-    check_zeroness: bool,
+/// Sets the bounds of the verification-timeouts chosen by fee-payers
+///
+/// # Note
+///
+/// `authority` needs to be the program's keypair.
+pub fn set_verification_timeout_bounds(
+    authority: &AccountInfo,
+    governor: &mut GovernorAccount,
+
+    min_verification_timeout: u64,
+    max_verification_timeout: u64,
 ) -> ProgramResult {
+    guard!(*authority.key == crate::ID, ElusivError::InvalidAccount);
     guard!(
-        account.data_len() == data_len,
-        ProgramError::InvalidAccountData
+        min_verification_timeout <= max_verification_timeout,
+        ElusivError::InvalidVerificationTimeout
     );
 
-    guard!(
-        data_len >= ChildAccountConfig::SIZE,
-        ProgramError::InvalidAccountData
-    );
+    governor.set_min_verification_timeout(&min_verification_timeout);
+    governor.set_max_verification_timeout(&max_verification_timeout);
 
-    if check_zeroness {
-        guard!(
-            is_zero(&account.data.borrow()[..]),
-            ProgramError::InvalidAccountData
-        );
-    }
+    Ok(())
+}
 
-    // Check rent-exemption
-    if cfg!(test) {
-        // only unit-testing (since we have no ledger there)
-        guard!(
-            account.lamports() >= u32::MAX as u64,
-            ProgramError::AccountNotRentExempt
-        );
-    } else {
-        guard!(
-            account.lamports() >= Rent::get()?.minimum_balance(data_len),
-            ProgramError::AccountNotRentExempt
-        );
-    }
+/// Pauses all [`crate::state::governor::Category`]s set in `paused_categories` (and resumes all others)
+///
+/// # Note
+///
+/// [`crate::state::governor::Category::Governance`] can never be paused.
+pub fn set_paused_categories(
+    authority: &AccountInfo,
+    governor: &mut GovernorAccount,
 
-    // Check ownership
-    guard!(*account.owner == crate::id(), ProgramError::IllegalOwner);
+    paused_categories: u32,
+) -> ProgramResult {
+    guard!(*authority.key == crate::ID, ElusivError::InvalidAccount);
+    guard!(
+        paused_categories & !PAUSABLE_CATEGORIES == 0,
+        ElusivError::InvalidInstructionData
+    );
+
+    governor.set_paused_categories(&paused_categories);
 
     Ok(())
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::{
-        macros::account_info,
-        processor::CommitmentHashRequest,
-        state::{program_account::SizedAccount, queue::RingQueue, storage::StorageChildAccount},
-        types::U256,
-    };
-    use elusiv_types::ProgramAccount;
-    use solana_program::{pubkey::Pubkey, system_program};
+/// Enables or disables the rejection of finalize transactions with more than one memo instruction
+///
+/// # Note
+///
+/// `authority` needs to be the program's keypair.
+pub fn set_enforce_single_memo(
+    authority: &AccountInfo,
+    governor: &mut GovernorAccount,
 
-    #[test]
-    fn test_enable_storage_child_account() {
-        let mut data = vec![0; StorageAccount::SIZE];
-        let mut storage_account = StorageAccount::new(&mut data).unwrap();
+    enforce_single_memo: bool,
+) -> ProgramResult {
+    guard!(*authority.key == crate::ID, ElusivError::InvalidAccount);
 
-        // Account has invalid size
-        account_info!(
-            child_account,
-            Pubkey::new_unique(),
-            vec![0; StorageChildAccount::SIZE - 1]
-        );
-        assert_eq!(
-            enable_storage_child_account(&mut storage_account, &child_account, 0),
-            Err(ProgramError::InvalidAccountData)
-        );
+    governor.set_enforce_single_memo(&enforce_single_memo);
 
-        storage_account.set_child_pubkey(0, ElusivOption::Some(Pubkey::new_unique()));
+    Ok(())
+}
 
-        // Account has already been setup
-        account_info!(
+/// Enables or disables the rejection of lamports sends to program-owned recipients that are not flagged as such by the sender
+///
+/// # Note
+///
+/// `authority` needs to be the program's keypair.
+pub fn set_enforce_system_owned_lamports_recipient(
+    authority: &AccountInfo,
+    governor: &mut GovernorAccount,
+
+    enforce_system_owned_lamports_recipient: bool,
+) -> ProgramResult {
+    guard!(*authority.key == crate::ID, ElusivError::InvalidAccount);
+
+    governor.set_enforce_system_owned_lamports_recipient(&enforce_system_owned_lamports_recipient);
+
+    Ok(())
+}
+
+/// Sets the number of low bits ignored when validating asserted timestamps (see [`crate::processor::is_timestamp_valid`])
+///
+/// # Note
+///
+/// `authority` needs to be the program's keypair.
+pub fn set_timestamp_pruning_bits(
+    authority: &AccountInfo,
+    governor: &mut GovernorAccount,
+
+    timestamp_pruning_bits: u8,
+) -> ProgramResult {
+    guard!(*authority.key == crate::ID, ElusivError::InvalidAccount);
+    guard!(
+        timestamp_pruning_bits <= MAX_TIMESTAMP_PRUNING_BITS,
+        ElusivError::InvalidTimestampPruningBits
+    );
+
+    governor.set_timestamp_pruning_bits(&ElusivOption::Some(timestamp_pruning_bits));
+
+    Ok(())
+}
+
+/// Replaces the allow-list of memo program ids accepted in finalize transactions
+///
+/// # Note
+///
+/// `authority` needs to be the program's keypair.
+pub fn set_memo_program_ids(
+    authority: &AccountInfo,
+    governor: &mut GovernorAccount,
+
+    memo_program_ids: Vec<Pubkey>,
+) -> ProgramResult {
+    guard!(*authority.key == crate::ID, ElusivError::InvalidAccount);
+    guard!(
+        !memo_program_ids.is_empty() && memo_program_ids.len() <= MAX_MEMO_PROGRAM_IDS,
+        ElusivError::InvalidInstructionData
+    );
+
+    for i in 0..MAX_MEMO_PROGRAM_IDS {
+        governor.set_memo_program_ids(i, &memo_program_ids.get(i).copied().into());
+    }
+
+    Ok(())
+}
+
+/// Registers (or updates) the metadata of the token `token_id` in the [`TokenRegistryAccount`]
+///
+/// # Note
+///
+/// The [`TokenRegistryAccount`] is opened with the first registration.
+pub fn register_token<'a>(
+    authority: &AccountInfo,
+    payer: &AccountInfo<'a>,
+    token_registry_account: &AccountInfo<'a>,
+
+    token_id: TokenID,
+    token: RegisteredToken,
+) -> ProgramResult {
+    guard!(*authority.key == crate::ID, ElusivError::InvalidAccount);
+    guard!(token.min <= token.max, ElusivError::InvalidInstructionData);
+
+    if token_registry_account.data_is_empty() {
+        open_pda_account_without_offset::<TokenRegistryAccount>(
+            &crate::id(),
+            payer,
+            token_registry_account,
+            None,
+        )?;
+    }
+
+    pda_account!(
+        mut token_registry,
+        TokenRegistryAccount,
+        token_registry_account
+    );
+    token_registry.register_token(token_id, &token)
+}
+
+/// Pauses (or resumes) the token `token_id` without affecting any other tokens
+///
+/// # Note
+///
+/// The [`TokenRegistryAccount`] is opened if it does not exist yet.
+pub fn set_token_paused<'a>(
+    authority: &AccountInfo,
+    payer: &AccountInfo<'a>,
+    token_registry_account: &AccountInfo<'a>,
+
+    token_id: TokenID,
+    paused: bool,
+) -> ProgramResult {
+    guard!(*authority.key == crate::ID, ElusivError::InvalidAccount);
+
+    if token_registry_account.data_is_empty() {
+        open_pda_account_without_offset::<TokenRegistryAccount>(
+            &crate::id(),
+            payer,
+            token_registry_account,
+            None,
+        )?;
+    }
+
+    pda_account!(
+        mut token_registry,
+        TokenRegistryAccount,
+        token_registry_account
+    );
+    token_registry.set_token_paused(token_id, paused)
+}
+
+/// Opens the [`NetworkFeeAccount`] of the (supported) token `token_id`
+pub fn open_network_fee_account<'b>(
+    payer: &AccountInfo<'b>,
+    network_fee_account: UnverifiedAccountInfo<'_, 'b>,
+    token_registry_account: &AccountInfo,
+
+    token_id: TokenID,
+) -> ProgramResult {
+    registered_elusiv_token(token_registry_account, token_id)?;
+
+    open_pda_account_with_offset::<NetworkFeeAccount>(
+        &crate::id(),
+        payer,
+        network_fee_account.get_unsafe(),
+        token_id.into(),
+        None,
+    )
+}
+
+/// Sets the account receiving the rent of closed verification PDAs
+///
+/// # Note
+///
+/// `ElusivOption::None` restores the default beneficiaries (fee-payer or fee-collector).
+pub fn set_rent_recipient(
+    authority: &AccountInfo,
+    governor: &mut GovernorAccount,
+
+    rent_recipient: ElusivOption<Pubkey>,
+) -> ProgramResult {
+    guard!(*authority.key == crate::ID, ElusivError::InvalidAccount);
+
+    governor.set_rent_recipient(&rent_recipient);
+
+    Ok(())
+}
+
+/// Returns the [`GovernorHead`] as return data
+///
+/// # Note
+///
+/// Allows clients to read the fee-version and batching-rate atomically.
+pub fn governor_head(governor: &GovernorAccount) -> ProgramResult {
+    set_return_data(&GovernorHead::new(governor).try_to_vec()?);
+    Ok(())
+}
+
+/// Metadata and amount bounds of a single supported token
+#[derive(BorshDeserialize, BorshSerialize, PartialEq, Eq, Clone, Debug)]
+pub struct SupportedToken {
+    pub token_id: u16,
+    pub mint: Pubkey,
+    pub decimals: u8,
+    pub min: u64,
+    pub max: u64,
+}
+
+/// Returned by [`list_tokens`]
+#[derive(BorshDeserialize, BorshSerialize, PartialEq, Eq, Clone, Debug)]
+pub struct TokenList {
+    pub fee_version: u32,
+
+    /// The per-commitment-amount network fee in basis points (applies to all tokens)
+    pub base_commitment_network_fee: u64,
+
+    /// The per-join-split-amount network fee in basis points (applies to all tokens)
+    pub proof_network_fee: u64,
+
+    pub tokens: Vec<SupportedToken>,
+}
+
+/// Returns the [`TokenList`] as return data
+///
+/// # Note
+///
+/// Registered tokens (see [`register_token`]) override the static token metadata.
+pub fn list_tokens(
+    token_registry_account: &AccountInfo,
+    governor: &GovernorAccount,
+) -> ProgramResult {
+    set_return_data(&token_list(token_registry_account, governor)?.try_to_vec()?);
+    Ok(())
+}
+
+/// Reads all supported tokens from the (verified) [`TokenRegistryAccount`] and [`crate::token::TOKENS`]
+pub fn token_list(
+    token_registry_account: &AccountInfo,
+    governor: &GovernorAccount,
+) -> Result<TokenList, ProgramError> {
+    let max_token_id = std::cmp::max(SPL_TOKEN_COUNT, TOKEN_REGISTRY_CAPACITY);
+    let mut tokens = Vec::new();
+
+    for token_id in 0..=max_token_id as TokenID {
+        let token = match registered_elusiv_token(token_registry_account, token_id) {
+            Ok(token) => token,
+            Err(err) if err == TokenError::InvalidTokenID.into() => continue,
+            Err(err) => return Err(err),
+        };
+
+        tokens.push(SupportedToken {
+            token_id,
+            mint: token.mint,
+            decimals: token.decimals,
+            min: token.min,
+            max: token.max,
+        });
+    }
+
+    let fee = governor.get_program_fee();
+    Ok(TokenList {
+        fee_version: governor.get_fee_version(),
+        base_commitment_network_fee: fee.base_commitment_network_fee.0,
+        proof_network_fee: fee.proof_network_fee.0,
+        tokens,
+    })
+}
+
+/// Balance of the pool and fee-collector token-accounts for a single token
+#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Eq, Clone, Copy, Debug)]
+pub struct TreasuryTokenBalance {
+    pub token_id: u16,
+    pub pool: u64,
+    pub fee_collector: u64,
+}
+
+/// Logged by [`snapshot_treasury`]
+#[derive(BorshDeserialize, BorshSerialize, PartialEq, Eq, Clone, Debug)]
+pub struct TreasurySnapshot {
+    pub pool_lamports: u64,
+    pub fee_collector_lamports: u64,
+    pub token_balances: Vec<TreasuryTokenBalance>,
+}
+
+impl TreasurySnapshot {
+    pub fn log(&self) -> ProgramResult {
+        solana_program::log::sol_log_data(&[&self.try_to_vec()?]);
+        Ok(())
+    }
+}
+
+/// Logs the pool and fee-collector balances as a [`TreasurySnapshot`]
+///
+/// # Note
+///
+/// - `token_accounts` contains the pool and the fee-collector token-account for each of the `token_ids` (in this order).
+/// - Allows dashboards to read all balances with a single simulation.
+pub fn snapshot_treasury(
+    pool: &AccountInfo,
+    fee_collector: &AccountInfo,
+    token_registry_account: &AccountInfo,
+    token_accounts: &[AccountInfo],
+    token_ids: Vec<u16>,
+) -> ProgramResult {
+    treasury_snapshot(
+        pool,
+        fee_collector,
+        token_registry_account,
+        token_accounts,
+        &token_ids,
+    )?
+    .log()
+}
+
+/// Reads the [`TreasurySnapshot`] after verifying all token-accounts
+pub fn treasury_snapshot(
+    pool: &AccountInfo,
+    fee_collector: &AccountInfo,
+    token_registry_account: &AccountInfo,
+    token_accounts: &[AccountInfo],
+    token_ids: &[u16],
+) -> Result<TreasurySnapshot, ProgramError> {
+    guard!(
+        token_accounts.len() == token_ids.len() * 2,
+        ElusivError::InvalidInstructionData
+    );
+
+    let mut token_balances = Vec::with_capacity(token_ids.len());
+    for (token_id, accounts) in token_ids.iter().zip(token_accounts.chunks(2)) {
+        let (pool_token_account, fee_collector_token_account) = (&accounts[0], &accounts[1]);
+        let mint = registered_elusiv_token(token_registry_account, *token_id)?.mint;
+        verify_program_token_account(pool, pool_token_account, *token_id, &mint)?;
+        verify_program_token_account(fee_collector, fee_collector_token_account, *token_id, &mint)?;
+
+        token_balances.push(TreasuryTokenBalance {
+            token_id: *token_id,
+            pool: token_account_balance(pool_token_account, *token_id)?,
+            fee_collector: token_account_balance(fee_collector_token_account, *token_id)?,
+        });
+    }
+
+    Ok(TreasurySnapshot {
+        pool_lamports: pool.lamports(),
+        fee_collector_lamports: fee_collector.lamports(),
+        token_balances,
+    })
+}
+
+fn token_account_balance(token_account: &AccountInfo, token_id: u16) -> Result<u64, ProgramError> {
+    if token_id == 0 {
+        return Ok(token_account.lamports());
+    }
+
+    let data = &token_account.data.borrow()[..];
+    Ok(spl_token::state::Account::unpack(data)?.amount)
+}
+
+/// Program-id of the address-lookup-table program
+pub const ADDRESS_LOOKUP_TABLE_PROGRAM_ID: Pubkey = Pubkey::new_from_array([
+    2, 119, 166, 175, 151, 51, 155, 122, 200, 141, 24, 146, 201, 4, 70, 245, 0, 2, 48, 146, 102,
+    246, 46, 83, 193, 24, 36, 73, 130, 0, 0, 0,
+]);
+
+/// Size of the serialized `LookupTableMeta` (including the `ProgramState` discriminant)
+const LOOKUP_TABLE_META_SIZE: usize = 56;
+
+/// The static accounts that are expected to be contained in the common address-lookup-table
+pub fn common_lut_addresses() -> Result<Vec<Pubkey>, ProgramError> {
+    let mut addresses = vec![
+        PoolAccount::find(None).0,
+        FeeCollectorAccount::find(None).0,
+        GovernorAccount::find(None).0,
+        StorageAccount::find(None).0,
+        CommitmentQueueAccount::find(None).0,
+        MetadataQueueAccount::find(None).0,
+        CommitmentBufferAccount::find(None).0,
+        system_program::ID,
+        spl_token::ID,
+        spl_associated_token_account::ID,
+        instructions::ID,
+    ];
+
+    for token_id in 0..=SPL_TOKEN_COUNT as u16 {
+        let token = elusiv_token(token_id)?;
+        addresses.push(token.pyth_usd_price_key);
+        addresses.push(NetworkFeeAccount::find(Some(token_id.into())).0);
+
+        if token_id > 0 {
+            addresses.push(token.mint);
+            addresses.push(program_token_account_address::<PoolAccount>(
+                token_id, None,
+            )?);
+            addresses.push(program_token_account_address::<FeeCollectorAccount>(
+                token_id, None,
+            )?);
+        }
+    }
+
+    Ok(addresses)
+}
+
+/// Returns the addresses stored in an active address-lookup-table
+fn lookup_table_addresses(data: &[u8]) -> Option<Vec<Pubkey>> {
+    if data.len() < LOOKUP_TABLE_META_SIZE {
+        return None;
+    }
+
+    // `ProgramState::LookupTable`
+    if u32::from_le_bytes(data[..4].try_into().unwrap()) != 1 {
+        return None;
+    }
+
+    // Deactivated tables can not be used anymore
+    if u64::from_le_bytes(data[4..12].try_into().unwrap()) != u64::MAX {
+        return None;
+    }
+
+    let addresses = data[LOOKUP_TABLE_META_SIZE..].chunks_exact(32);
+    if !addresses.remainder().is_empty() {
+        return None;
+    }
+
+    Some(
+        addresses
+            .map(|k| Pubkey::new_from_array(k.try_into().unwrap()))
+            .collect(),
+    )
+}
+
+/// Registers the canonical address-lookup-table containing the [`common_lut_addresses`]
+///
+/// # Note
+///
+/// `authority` needs to be the program's keypair.
+pub fn register_common_lut(
+    authority: &AccountInfo,
+    governor: &mut GovernorAccount,
+
+    lut_address: Pubkey,
+) -> ProgramResult {
+    guard!(*authority.key == crate::ID, ElusivError::InvalidAccount);
+
+    governor.set_common_lut(&ElusivOption::Some(lut_address));
+
+    Ok(())
+}
+
+/// Verifies that the registered address-lookup-table is active and contains all [`common_lut_addresses`]
+pub fn verify_common_lut(governor: &GovernorAccount, lut_account: &AccountInfo) -> ProgramResult {
+    guard!(
+        governor.get_common_lut().option() == Some(*lut_account.key),
+        ElusivError::InvalidAccount
+    );
+    guard!(
+        *lut_account.owner == ADDRESS_LOOKUP_TABLE_PROGRAM_ID,
+        ElusivError::InvalidAccount
+    );
+
+    let addresses = lookup_table_addresses(&lut_account.data.borrow())
+        .ok_or(ElusivError::InvalidAccountState)?;
+
+    for expected in common_lut_addresses()? {
+        guard!(addresses.contains(&expected), ElusivError::MissingValue);
+    }
+
+    Ok(())
+}
+
+/// Verifies that the sorted root-index of the [`StorageAccount`] matches its root-history
+pub fn verify_root_index(storage_account: &StorageAccount) -> ProgramResult {
+    storage_account.verify_root_index()
+}
+
+/// Logged by [`audit_program_token_account`]
+#[derive(BorshDeserialize, BorshSerialize, PartialEq, Eq, Clone, Debug)]
+pub struct ProgramTokenAccountAudit {
+    pub token_id: u16,
+    pub pool_account: Pubkey,
+    pub fee_collector_account: Pubkey,
+}
+
+impl ProgramTokenAccountAudit {
+    pub fn log(&self) -> ProgramResult {
+        solana_program::log::sol_log_data(&[&self.try_to_vec()?]);
+        Ok(())
+    }
+}
+
+/// Runs the [`verify_program_token_account`] checks on the pool and fee-collector token-accounts of `token_id`
+///
+/// # Note
+///
+/// Permissionless, allows monitoring to detect delegates or close-authorities before funds are at risk.
+pub fn audit_program_token_account(
+    pool: &AccountInfo,
+    fee_collector: &AccountInfo,
+    pool_account: &AccountInfo,
+    fee_collector_account: &AccountInfo,
+    token_registry_account: &AccountInfo,
+
+    token_id: u16,
+) -> ProgramResult {
+    let mint = registered_elusiv_token(token_registry_account, token_id)?.mint;
+    verify_program_token_account(pool, pool_account, token_id, &mint)?;
+    verify_program_token_account(fee_collector, fee_collector_account, token_id, &mint)?;
+
+    ProgramTokenAccountAudit {
+        token_id,
+        pool_account: *pool_account.key,
+        fee_collector_account: *fee_collector_account.key,
+    }
+    .log()
+}
+
+/// Setup a new [`FeeAccount`]
+///
+/// # Note
+///
+/// There is no way of upgrading the program fees atm.
+pub fn init_new_fee_version<'b>(
+    payer: &AccountInfo<'b>,
+    governor: &mut GovernorAccount,
+    mut new_fee_account: UnverifiedAccountInfo<'_, 'b>,
+
+    fee_version: u32,
+    program_fee: ProgramFee,
+) -> ProgramResult {
+    // Note: we have no upgrade-authroity check here since with the current setup it's impossible to have a fee version higher than zero, so will be added once that changes
+    guard!(
+        fee_version == governor.get_fee_version(),
+        ElusivError::InvalidFeeVersion
+    );
+    guard!(program_fee.is_valid(), ElusivError::InvalidInstructionData);
+
+    open_pda_account_with_offset::<FeeAccount>(
+        &crate::id(),
+        payer,
+        new_fee_account.get_unsafe_and_set_is_verified(),
+        fee_version,
+        None,
+    )?;
+
+    pda_account!(mut fee_account, FeeAccount, new_fee_account.get_safe()?);
+    fee_account.set_program_fee(&program_fee);
+    governor.set_program_fee(&program_fee);
+
+    Ok(())
+}
+
+/// Closes a program owned account in devnet and localhost
+///
+/// # Note
+///
+/// - `signer` needs to be the program's keypair
+/// - `recipient` receives the accounts Lamports
+#[cfg(not(feature = "mainnet"))]
+pub fn close_program_account<'a>(
+    signer: &AccountInfo,
+    recipient: &AccountInfo<'a>,
+    program_account: &AccountInfo<'a>,
+) -> ProgramResult {
+    assert!(!cfg!(feature = "mainnet"));
+    assert_eq!(*signer.key, crate::ID);
+
+    elusiv_utils::close_account(recipient, program_account)
+}
+
+/// Verifies a single user-supplied [`ChildAccount`] and then saves it's pubkey in the `parent_account`
+///
+/// # Note
+///
+/// If `size` is manually supplied (not the default [`C::SIZE`] is used) [`elusiv_types::ChildAccountConfig::SIZE`] needs to be contained in the size.
+pub fn setup_child_account<'a, 'b, 't, P: ParentAccount<'a, 'b, 't>>(
+    parent_account: &mut P,
+    child_account: &AccountInfo,
+    child_index: usize,
+    check_zeroness: bool,
+    size: Option<usize>,
+) -> ProgramResult {
+    if parent_account.get_child_pubkey(child_index).is_some() {
+        return Err(ElusivError::ChildAccountAlreadyExists.into());
+    }
+
+    verify_extern_data_account(
+        child_account,
+        size.unwrap_or(<P::Child as SizedAccount>::SIZE),
+        check_zeroness,
+    )?;
+    parent_account.set_child_pubkey(child_index, ElusivOption::Some(*child_account.key));
+    P::Child::try_start_using_account(child_account)?;
+
+    Ok(())
+}
+
+fn reset_map_child_account<C: ChildAccount>(child_account: &AccountInfo) -> ProgramResult {
+    let data = &mut child_account.data.borrow_mut()[..];
+    let (_, inner_data) = split_child_account_data_mut(data)?;
+    let mut map = ElusivMap::<(), (), 1>::new(&mut inner_data[..ElusivMap::<(), (), 1>::SIZE]);
+    map.reset();
+
+    Ok(())
+}
+
+/// Verifies that an account with `data_len` > 10 KiB (non PDA) is formatted correctly
+fn verify_extern_data_account(
+    account: &AccountInfo,
+    data_len: usize,
+    check_zeroness: bool,
+) -> ProgramResult {
+    guard!(
+        account.data_len() == data_len,
+        ProgramError::InvalidAccountData
+    );
+
+    guard!(
+        data_len >= ChildAccountConfig::SIZE,
+        ProgramError::InvalidAccountData
+    );
+
+    if check_zeroness {
+        guard!(
+            is_zero(&account.data.borrow()[..]),
+            ProgramError::InvalidAccountData
+        );
+    }
+
+    // Check rent-exemption
+    if cfg!(test) {
+        // only unit-testing (since we have no ledger there)
+        guard!(
+            account.lamports() >= u32::MAX as u64,
+            ProgramError::AccountNotRentExempt
+        );
+    } else {
+        guard!(
+            account.lamports() >= Rent::get()?.minimum_balance(data_len),
+            ProgramError::AccountNotRentExempt
+        );
+    }
+
+    // Check ownership
+    guard!(*account.owner == crate::id(), ProgramError::IllegalOwner);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        macros::{account_info, program_token_account_info},
+        processor::CommitmentHashRequest,
+        state::{
+            fee::BasisPointFee,
+            governor::Category,
+            program_account::{PDAAccountData, SizedAccount},
+            queue::RingQueue,
+            storage::StorageChildAccount,
+            token_registry::{is_token_paused, registered_elusiv_token},
+        },
+        token::{
+            pyth_price_account_data, Lamports, Price, TokenError, TokenPrice, LAMPORTS_TOKEN_ID,
+            USDC_TOKEN_ID, USDT_TOKEN_ID,
+        },
+    };
+    use borsh::BorshDeserialize;
+    use elusiv_types::ProgramAccount;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_enable_storage_child_account() {
+        let mut data = vec![0; StorageAccount::SIZE];
+        let mut storage_account = StorageAccount::new(&mut data).unwrap();
+
+        // Account has invalid size
+        account_info!(
+            child_account,
+            Pubkey::new_unique(),
+            vec![0; StorageChildAccount::SIZE - 1]
+        );
+        assert_eq!(
+            enable_storage_child_account(&mut storage_account, &child_account, 0),
+            Err(ProgramError::InvalidAccountData)
+        );
+
+        storage_account.set_child_pubkey(0, ElusivOption::Some(Pubkey::new_unique()));
+
+        // Account has already been setup
+        account_info!(
+            child_account,
+            Pubkey::new_unique(),
+            vec![0; StorageChildAccount::SIZE]
+        );
+        assert_eq!(
+            enable_storage_child_account(&mut storage_account, &child_account, 0),
+            Err(ElusivError::ChildAccountAlreadyExists.into())
+        );
+
+        // Success at different index
+        assert_eq!(
+            enable_storage_child_account(&mut storage_account, &child_account, 3),
+            Ok(())
+        );
+        assert_eq!(child_account.data.borrow()[0], 1);
+
+        // Account already is use
+        assert_eq!(
+            enable_storage_child_account(&mut storage_account, &child_account, 1),
+            Err(ProgramError::AccountAlreadyInitialized)
+        );
+    }
+
+    #[test]
+    fn test_enable_nullifier_child_account() {
+        let mut data = vec![0; NullifierAccount::SIZE];
+        let mut nullifier_account = NullifierAccount::new(&mut data).unwrap();
+
+        // Account has invalid size
+        account_info!(
+            child_account,
+            Pubkey::new_unique(),
+            vec![0; NullifierChildAccount::SIZE - 1]
+        );
+        assert_eq!(
+            enable_nullifier_child_account(&mut nullifier_account, &child_account, 0, 0),
+            Err(ProgramError::InvalidAccountData)
+        );
+
+        nullifier_account.set_child_pubkey(0, ElusivOption::Some(Pubkey::new_unique()));
+
+        // Account has already been setup
+        account_info!(
             child_account,
             Pubkey::new_unique(),
-            vec![0; StorageChildAccount::SIZE]
+            vec![0; NullifierChildAccount::SIZE]
+        );
+        assert_eq!(
+            enable_nullifier_child_account(&mut nullifier_account, &child_account, 0, 0),
+            Err(ElusivError::ChildAccountAlreadyExists.into())
+        );
+
+        // Success at different index with
+        assert_eq!(
+            enable_nullifier_child_account(&mut nullifier_account, &child_account, 0, 3),
+            Ok(())
+        );
+        assert_eq!(child_account.data.borrow()[0], 1);
+
+        // Account already is use
+        assert_eq!(
+            enable_nullifier_child_account(&mut nullifier_account, &child_account, 0, 1),
+            Err(ProgramError::AccountAlreadyInitialized)
+        );
+    }
+
+    #[test]
+    fn test_is_mt_full() {
+        let mut data = vec![0; StorageAccount::SIZE];
+        let mut storage_account = StorageAccount::new(&mut data).unwrap();
+        storage_account.set_next_commitment_ptr(&(MT_COMMITMENT_COUNT as u32));
+
+        let mut q_data = vec![0; CommitmentQueueAccount::SIZE];
+        let mut queue = CommitmentQueueAccount::new(&mut q_data).unwrap();
+        let mut queue = CommitmentQueue::new(&mut queue);
+        queue
+            .enqueue(CommitmentHashRequest {
+                min_batching_rate: 1,
+                commitment: [0; 32],
+                fee_version: 0,
+                view_tag: 0,
+            })
+            .unwrap();
+        queue
+            .enqueue(CommitmentHashRequest {
+                min_batching_rate: 1,
+                commitment: [0; 32],
+                fee_version: 0,
+                view_tag: 0,
+            })
+            .unwrap();
+
+        assert!(is_mt_full(&storage_account, &queue).unwrap());
+
+        storage_account.set_next_commitment_ptr(&(MT_COMMITMENT_COUNT as u32 - 3));
+        assert!(!is_mt_full(&storage_account, &queue).unwrap());
+
+        storage_account.set_next_commitment_ptr(&(MT_COMMITMENT_COUNT as u32 - 2));
+        assert!(is_mt_full(&storage_account, &queue).unwrap());
+    }
+
+    #[test]
+    fn test_warm_tree_accounts() {
+        parent_account!(mut storage_account, StorageAccount);
+        parent_account!(mut nullifier_account, NullifierAccount);
+
+        // Child-accounts have not been enabled
+        assert_eq!(
+            warm_tree_accounts(&mut storage_account, &mut nullifier_account, 0),
+            Err(ElusivError::ChildAccouttDoesNotExists.into())
+        );
+
+        for i in 0..StorageAccount::COUNT {
+            let pubkey = *unsafe { storage_account.get_child_account_unsafe(i) }
+                .unwrap()
+                .key;
+            storage_account.set_child_pubkey(i, ElusivOption::Some(pubkey));
+        }
+        for i in 0..NullifierAccount::COUNT {
+            let pubkey = *unsafe { nullifier_account.get_child_account_unsafe(i) }
+                .unwrap()
+                .key;
+            nullifier_account.set_child_pubkey(i, ElusivOption::Some(pubkey));
+        }
+        assert!(!storage_account.is_child_verified(0));
+
+        assert_eq!(
+            warm_tree_accounts(&mut storage_account, &mut nullifier_account, 0),
+            Ok(())
+        );
+        assert!((0..StorageAccount::COUNT).all(|i| storage_account.is_child_verified(i)));
+        assert!((0..NullifierAccount::COUNT).all(|i| nullifier_account.is_child_verified(i)));
+        assert!(!storage_account.is_child_verified(StorageAccount::COUNT));
+
+        // Re-setting a child-account invalidates it's verification
+        storage_account.set_child_pubkey(1, ElusivOption::Some(Pubkey::new_unique()));
+        assert!(!storage_account.is_child_verified(1));
+        assert!(storage_account.is_child_verified(0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_archive_closed_merkle_tree() {
+        test_account_info!(payer, 0);
+        let mut data = vec![0; StorageAccount::SIZE];
+        let mut storage_account = StorageAccount::new(&mut data).unwrap();
+        let mut data = vec![0; NullifierAccount::SIZE];
+        let mut nullifier_account = NullifierAccount::new(&mut data).unwrap();
+        test_account_info!(archived_tree_account, 0);
+
+        archive_closed_merkle_tree(
+            &payer,
+            &mut storage_account,
+            &mut nullifier_account,
+            &archived_tree_account,
+            0,
+        )
+        .unwrap();
+    }
+
+    fn nullifier_hashes(range: std::ops::Range<u64>) -> Vec<U256> {
+        range.map(crate::fields::u64_to_u256_skip_mr).collect()
+    }
+
+    #[test]
+    fn test_merge_nullifier_trees() {
+        test_account_info!(authority, 0);
+        account_info!(program, crate::ID, vec![], crate::ID, true);
+        test_account_info!(payer, 0);
+        zero_program_account!(mut storage_account, StorageAccount);
+        storage_account.set_trees_count(&3);
+        account_info!(
+            merge_account,
+            NullifierMergeAccount::find(Some(0)).0,
+            vec![0; NullifierMergeAccount::SIZE]
+        );
+        parent_account!(mut source, NullifierAccount);
+        parent_account!(mut destination, NullifierAccount);
+
+        let source_hashes = nullifier_hashes(100..120);
+        let destination_hashes = nullifier_hashes(0..10);
+        for &nullifier_hash in &source_hashes {
+            source.try_insert_nullifier_hash(nullifier_hash).unwrap();
+        }
+        for &nullifier_hash in &destination_hashes {
+            destination
+                .try_insert_nullifier_hash(nullifier_hash)
+                .unwrap();
+        }
+
+        // Invalid authority
+        assert_eq!(
+            merge_nullifier_trees(
+                &authority,
+                &payer,
+                &storage_account,
+                &merge_account,
+                &source,
+                &mut destination,
+                0,
+                1
+            ),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        // Identical or active MTs
+        for (source_mt_index, destination_mt_index) in [(0, 0), (0, 3), (3, 1)] {
+            assert_eq!(
+                merge_nullifier_trees(
+                    &program,
+                    &payer,
+                    &storage_account,
+                    &merge_account,
+                    &source,
+                    &mut destination,
+                    source_mt_index,
+                    destination_mt_index
+                ),
+                Err(ElusivError::InvalidInstructionData.into())
+            );
+        }
+
+        // First batch
+        merge_nullifier_trees(
+            &program,
+            &payer,
+            &storage_account,
+            &merge_account,
+            &source,
+            &mut destination,
+            0,
+            1,
+        )
+        .unwrap();
+        assert_eq!(
+            destination.get_nullifier_hash_count(),
+            10 + NULLIFIER_MERGE_BATCH_SIZE
+        );
+
+        // Resumption with a different destination
+        assert_eq!(
+            merge_nullifier_trees(
+                &program,
+                &payer,
+                &storage_account,
+                &merge_account,
+                &source,
+                &mut destination,
+                0,
+                2
+            ),
+            Err(ElusivError::InvalidInstructionData.into())
+        );
+
+        // Second batch finishes the merge
+        merge_nullifier_trees(
+            &program,
+            &payer,
+            &storage_account,
+            &merge_account,
+            &source,
+            &mut destination,
+            0,
+            1,
+        )
+        .unwrap();
+        assert_eq!(destination.get_nullifier_hash_count(), 30);
+        {
+            let data = &mut merge_account.data.borrow_mut()[..];
+            let merge = NullifierMergeAccount::new(data).unwrap();
+            assert!(merge.get_is_finished());
+            assert_eq!(merge.get_merged_count(), 20);
+        }
+
+        // Calls after the merge has finished have no effect
+        merge_nullifier_trees(
+            &program,
+            &payer,
+            &storage_account,
+            &merge_account,
+            &source,
+            &mut destination,
+            0,
+            1,
+        )
+        .unwrap();
+        assert_eq!(destination.get_nullifier_hash_count(), 30);
+
+        for nullifier_hash in source_hashes.into_iter().chain(destination_hashes) {
+            assert!(!destination
+                .can_insert_nullifier_hash(nullifier_hash)
+                .unwrap());
+        }
+        assert!(destination
+            .can_insert_nullifier_hash(nullifier_hashes(200..201)[0])
+            .unwrap());
+    }
+
+    #[test]
+    fn test_merge_nullifier_trees_collision() {
+        account_info!(program, crate::ID, vec![], crate::ID, true);
+        test_account_info!(payer, 0);
+        zero_program_account!(mut storage_account, StorageAccount);
+        storage_account.set_trees_count(&2);
+        account_info!(
+            merge_account,
+            NullifierMergeAccount::find(Some(0)).0,
+            vec![0; NullifierMergeAccount::SIZE]
+        );
+        parent_account!(mut source, NullifierAccount);
+        parent_account!(mut destination, NullifierAccount);
+
+        for nullifier_hash in nullifier_hashes(0..5) {
+            source.try_insert_nullifier_hash(nullifier_hash).unwrap();
+        }
+        for nullifier_hash in nullifier_hashes(4..8) {
+            destination
+                .try_insert_nullifier_hash(nullifier_hash)
+                .unwrap();
+        }
+
+        assert_eq!(
+            merge_nullifier_trees(
+                &program,
+                &payer,
+                &storage_account,
+                &merge_account,
+                &source,
+                &mut destination,
+                0,
+                1
+            ),
+            Err(ElusivError::CouldNotInsertNullifier.into())
+        );
+
+        let data = &mut merge_account.data.borrow_mut()[..];
+        let merge = NullifierMergeAccount::new(data).unwrap();
+        assert!(!merge.get_is_finished());
+        assert_eq!(merge.get_merged_count(), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_upgrade_governor_state() {
+        test_account_info!(authority, 0);
+        zero_program_account!(mut governor_account, GovernorAccount);
+        zero_program_account!(commitment_queue, CommitmentQueueAccount);
+
+        upgrade_governor_state(&authority, &mut governor_account, &commitment_queue, 1, 1).unwrap();
+    }
+
+    #[test]
+    fn test_realloc_governor_account() -> ProgramResult {
+        test_account_info!(authority, 0);
+        account_info!(program, crate::ID, vec![], crate::ID, true);
+        test_account_info!(payer, 0);
+
+        // Governor of the initial program version (`fee_version`, `program_fee`, `commitment_batching_rate`, `program_version`)
+        let legacy_size =
+            PDAAccountData::SIZE + u32::SIZE + ProgramFee::SIZE + u32::SIZE + u32::SIZE;
+        let mut data = vec![0; legacy_size];
+        data[..PDAAccountData::SIZE].copy_from_slice(&[GovernorAccount::find(None).1, 0]);
+        data[PDAAccountData::SIZE..PDAAccountData::SIZE + u32::SIZE]
+            .copy_from_slice(&3u32.to_le_bytes());
+        account_info!(governor_account, GovernorAccount::find(None).0, data);
+
+        assert!(GovernorAccount::new(&mut governor_account.data.borrow_mut()[..]).is_err());
+
+        assert_eq!(
+            realloc_governor_account(&authority, &payer, &governor_account),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        realloc_governor_account(&program, &payer, &governor_account).unwrap();
+        assert_eq!(governor_account.data_len(), GovernorAccount::SIZE);
+
+        {
+            pda_account!(governor, GovernorAccount, governor_account);
+            assert_eq!(governor.get_fee_version(), 3);
+            assert!(governor.get_allow_legacy_fee_payer_token_account());
+            assert_eq!(
+                governor.get_max_verification_timeout(),
+                DEFAULT_MAX_VERIFICATION_TIMEOUT
+            );
+            assert_eq!(
+                governor.get_timestamp_pruning_bits(),
+                ElusivOption::Some(DEFAULT_TIMESTAMP_PRUNING_BITS)
+            );
+        }
+
+        // Already up to date
+        assert_eq!(
+            realloc_governor_account(&program, &payer, &governor_account),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_allow_legacy_fee_payer_token_account() {
+        test_account_info!(authority, 0);
+        account_info!(program, crate::ID, vec![], crate::ID, true);
+        zero_program_account!(mut governor_account, GovernorAccount);
+
+        assert_eq!(
+            set_allow_legacy_fee_payer_token_account(&authority, &mut governor_account, true),
+            Err(ElusivError::InvalidAccount.into())
+        );
+        assert!(!governor_account.get_allow_legacy_fee_payer_token_account());
+
+        set_allow_legacy_fee_payer_token_account(&program, &mut governor_account, true).unwrap();
+        assert!(governor_account.get_allow_legacy_fee_payer_token_account());
+
+        set_allow_legacy_fee_payer_token_account(&program, &mut governor_account, false).unwrap();
+        assert!(!governor_account.get_allow_legacy_fee_payer_token_account());
+    }
+
+    #[test]
+    fn test_set_max_duplicate_verifications() {
+        test_account_info!(authority, 0);
+        account_info!(program, crate::ID, vec![], crate::ID, true);
+        zero_program_account!(mut governor_account, GovernorAccount);
+
+        assert_eq!(
+            set_max_duplicate_verifications(&authority, &mut governor_account, 3),
+            Err(ElusivError::InvalidAccount.into())
+        );
+        assert_eq!(governor_account.get_max_duplicate_verifications(), 0);
+
+        set_max_duplicate_verifications(&program, &mut governor_account, 3).unwrap();
+        assert_eq!(governor_account.get_max_duplicate_verifications(), 3);
+    }
+
+    #[test]
+    fn test_set_duplicate_grace_period() {
+        test_account_info!(authority, 0);
+        account_info!(program, crate::ID, vec![], crate::ID, true);
+        zero_program_account!(mut governor_account, GovernorAccount);
+
+        assert_eq!(
+            set_duplicate_grace_period(&authority, &mut governor_account, 2),
+            Err(ElusivError::InvalidAccount.into())
+        );
+        assert_eq!(governor_account.get_duplicate_grace_period(), 0);
+
+        set_duplicate_grace_period(&program, &mut governor_account, 2).unwrap();
+        assert_eq!(governor_account.get_duplicate_grace_period(), 2);
+    }
+
+    #[test]
+    fn test_set_failure_bond() {
+        test_account_info!(authority, 0);
+        account_info!(program, crate::ID, vec![], crate::ID, true);
+        zero_program_account!(mut governor_account, GovernorAccount);
+
+        assert_eq!(
+            set_failure_bond(&authority, &mut governor_account, 1_000_000),
+            Err(ElusivError::InvalidAccount.into())
+        );
+        assert_eq!(governor_account.get_failure_bond(), 0);
+
+        set_failure_bond(&program, &mut governor_account, 1_000_000).unwrap();
+        assert_eq!(governor_account.get_failure_bond(), 1_000_000);
+    }
+
+    #[test]
+    fn test_set_verification_timeout_bounds() {
+        test_account_info!(authority, 0);
+        account_info!(program, crate::ID, vec![], crate::ID, true);
+        zero_program_account!(mut governor_account, GovernorAccount);
+
+        assert_eq!(
+            set_verification_timeout_bounds(&authority, &mut governor_account, 60, 120),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        // Minimum above maximum
+        assert_eq!(
+            set_verification_timeout_bounds(&program, &mut governor_account, 121, 120),
+            Err(ElusivError::InvalidVerificationTimeout.into())
+        );
+        assert_eq!(governor_account.get_max_verification_timeout(), 0);
+
+        set_verification_timeout_bounds(&program, &mut governor_account, 60, 120).unwrap();
+        assert_eq!(governor_account.get_min_verification_timeout(), 60);
+        assert_eq!(governor_account.get_max_verification_timeout(), 120);
+    }
+
+    #[test]
+    fn test_register_token() {
+        test_account_info!(authority, 0);
+        account_info!(program, crate::ID, vec![], crate::ID, true);
+        test_account_info!(payer, 0);
+        account_info!(
+            token_registry_account,
+            TokenRegistryAccount::find(None).0,
+            vec![0; TokenRegistryAccount::SIZE]
+        );
+
+        let token_id = SPL_TOKEN_COUNT as TokenID + 1;
+        let usdc = elusiv_token(USDC_TOKEN_ID).unwrap();
+        let token = RegisteredToken {
+            mint: Pubkey::new_unique(),
+            decimals: usdc.decimals,
+            price_base_exp: usdc.price_base_exp,
+            pyth_usd_price_key: Pubkey::new_unique(),
+            min: usdc.min,
+            max: usdc.max,
+        };
+
+        // Invalid authority
+        assert_eq!(
+            register_token(&authority, &payer, &token_registry_account, token_id, token),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        // Invalid amount bounds
+        assert_eq!(
+            register_token(
+                &program,
+                &payer,
+                &token_registry_account,
+                token_id,
+                RegisteredToken {
+                    min: 2,
+                    max: 1,
+                    ..token
+                }
+            ),
+            Err(ElusivError::InvalidInstructionData.into())
+        );
+
+        // Invalid token-id
+        assert_eq!(
+            register_token(&program, &payer, &token_registry_account, 0, token),
+            Err(TokenError::InvalidTokenID.into())
+        );
+
+        register_token(&program, &payer, &token_registry_account, token_id, token).unwrap();
+        let registered = registered_elusiv_token(&token_registry_account, token_id).unwrap();
+        assert_eq!(registered.mint, token.mint);
+
+        // Fee computation using the registered token (identical to USDC apart from the keys)
+        let sol_usd = Price {
+            price: 39,
+            conf: 0,
+            expo: 0,
+        };
+        let token_usd = Price {
+            price: 1,
+            conf: 0,
+            expo: 0,
+        };
+        let sol_usd_data = pyth_price_account_data(&sol_usd).unwrap();
+        let token_usd_data = pyth_price_account_data(&token_usd).unwrap();
+        account_info!(
+            sol_usd_account,
+            elusiv_token(LAMPORTS_TOKEN_ID).unwrap().pyth_usd_price_key,
+            sol_usd_data.clone()
+        );
+        account_info!(
+            token_usd_account,
+            token.pyth_usd_price_key,
+            token_usd_data.clone()
+        );
+        account_info!(usdc_usd_account, usdc.pyth_usd_price_key, token_usd_data);
+
+        // Unknown to the static token table
+        assert_eq!(
+            TokenPrice::new(&sol_usd_account, &token_usd_account, token_id).err(),
+            Some(TokenError::InvalidTokenID.into())
+        );
+
+        // The price account has to match the registered token
+        assert_eq!(
+            TokenPrice::new_with_token(&sol_usd_account, &usdc_usd_account, token_id, &registered)
+                .err(),
+            Some(TokenError::InvalidPriceAccount.into())
+        );
+
+        let price =
+            TokenPrice::new_with_token(&sol_usd_account, &token_usd_account, token_id, &registered)
+                .unwrap();
+        let usdc_price =
+            TokenPrice::new(&sol_usd_account, &usdc_usd_account, USDC_TOKEN_ID).unwrap();
+
+        let program_fee = ProgramFee {
+            lamports_per_tx: Lamports(5000),
+            base_commitment_network_fee: BasisPointFee(11),
+            proof_network_fee: BasisPointFee(100),
+            base_commitment_subvention: Lamports(33),
+            proof_subvention: Lamports(44),
+            warden_hash_tx_reward: Lamports(300),
+            warden_proof_reward: Lamports(555),
+            proof_base_tx_count: 0,
+        };
+        let fee = program_fee
+            .proof_verification_fee(3, 0, 1_000_000, token_id, &price)
+            .unwrap();
+        let usdc_fee = program_fee
+            .proof_verification_fee(3, 0, 1_000_000, USDC_TOKEN_ID, &usdc_price)
+            .unwrap();
+
+        assert_eq!(fee.token_id(), token_id);
+        assert!(fee.amount() > 0);
+        assert_eq!(fee.amount(), usdc_fee.amount());
+    }
+
+    #[test]
+    fn test_set_token_paused() {
+        test_account_info!(authority, 0);
+        account_info!(program, crate::ID, vec![], crate::ID, true);
+        test_account_info!(payer, 0);
+        account_info!(
+            token_registry_account,
+            TokenRegistryAccount::find(None).0,
+            vec![0; TokenRegistryAccount::SIZE],
+            crate::ID,
+            false
+        );
+
+        // Invalid authority
+        assert_eq!(
+            set_token_paused(
+                &authority,
+                &payer,
+                &token_registry_account,
+                USDC_TOKEN_ID,
+                true
+            ),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        // Invalid token-id
+        assert_eq!(
+            set_token_paused(
+                &program,
+                &payer,
+                &token_registry_account,
+                TOKEN_REGISTRY_CAPACITY as TokenID + 1,
+                true
+            ),
+            Err(TokenError::InvalidTokenID.into())
+        );
+
+        set_token_paused(
+            &program,
+            &payer,
+            &token_registry_account,
+            USDC_TOKEN_ID,
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            is_token_paused(&token_registry_account, USDC_TOKEN_ID),
+            Ok(true)
+        );
+        assert_eq!(
+            is_token_paused(&token_registry_account, USDT_TOKEN_ID),
+            Ok(false)
         );
         assert_eq!(
-            enable_storage_child_account(&mut storage_account, &child_account, 0),
-            Err(ElusivError::ChildAccountAlreadyExists.into())
+            is_token_paused(&token_registry_account, LAMPORTS_TOKEN_ID),
+            Ok(false)
         );
 
-        // Success at different index
+        set_token_paused(
+            &program,
+            &payer,
+            &token_registry_account,
+            USDC_TOKEN_ID,
+            false,
+        )
+        .unwrap();
         assert_eq!(
-            enable_storage_child_account(&mut storage_account, &child_account, 3),
+            is_token_paused(&token_registry_account, USDC_TOKEN_ID),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn test_open_network_fee_account() {
+        test_account_info!(payer, 0);
+        test_account_info!(network_fee_account, 0);
+        test_account_info!(token_registry_account, 0);
+
+        // Unsupported token
+        assert_eq!(
+            open_network_fee_account(
+                &payer,
+                UnverifiedAccountInfo::new(&network_fee_account),
+                &token_registry_account,
+                SPL_TOKEN_COUNT as TokenID + 1
+            ),
+            Err(TokenError::InvalidTokenID.into())
+        );
+    }
+
+    #[test]
+    fn test_set_paused_categories() {
+        test_account_info!(authority, 0);
+        account_info!(program, crate::ID, vec![], crate::ID, true);
+        zero_program_account!(mut governor_account, GovernorAccount);
+
+        assert_eq!(
+            set_paused_categories(&authority, &mut governor_account, Category::Deposit.bit()),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        // Governance is not pausable
+        for paused_categories in [Category::Governance.bit(), 1 << Category::ALL.len()] {
+            assert_eq!(
+                set_paused_categories(&program, &mut governor_account, paused_categories),
+                Err(ElusivError::InvalidInstructionData.into())
+            );
+        }
+
+        for paused in Category::ALL {
+            if !paused.is_pausable() {
+                continue;
+            }
+
+            set_paused_categories(&program, &mut governor_account, paused.bit()).unwrap();
+            for category in Category::ALL {
+                if category == paused {
+                    assert_eq!(
+                        governor_account.check_category(category),
+                        Err(ElusivError::CategoryPaused.into())
+                    );
+                } else {
+                    assert_eq!(governor_account.check_category(category), Ok(()));
+                }
+            }
+        }
+
+        // Unpause
+        set_paused_categories(&program, &mut governor_account, 0).unwrap();
+        for category in Category::ALL {
+            assert_eq!(governor_account.check_category(category), Ok(()));
+        }
+
+        set_paused_categories(&program, &mut governor_account, PAUSABLE_CATEGORIES).unwrap();
+        assert_eq!(
+            governor_account.check_category(Category::Governance),
             Ok(())
         );
-        assert_eq!(child_account.data.borrow()[0], 1);
+    }
+
+    #[test]
+    fn test_set_enforce_single_memo() {
+        test_account_info!(authority, 0);
+        account_info!(program, crate::ID, vec![], crate::ID, true);
+        zero_program_account!(mut governor_account, GovernorAccount);
 
-        // Account already is use
         assert_eq!(
-            enable_storage_child_account(&mut storage_account, &child_account, 1),
-            Err(ProgramError::AccountAlreadyInitialized)
+            set_enforce_single_memo(&authority, &mut governor_account, true),
+            Err(ElusivError::InvalidAccount.into())
         );
+        assert!(!governor_account.get_enforce_single_memo());
+
+        set_enforce_single_memo(&program, &mut governor_account, true).unwrap();
+        assert!(governor_account.get_enforce_single_memo());
+
+        set_enforce_single_memo(&program, &mut governor_account, false).unwrap();
+        assert!(!governor_account.get_enforce_single_memo());
     }
 
     #[test]
-    fn test_enable_nullifier_child_account() {
-        let mut data = vec![0; NullifierAccount::SIZE];
-        let mut nullifier_account = NullifierAccount::new(&mut data).unwrap();
+    fn test_set_enforce_system_owned_lamports_recipient() {
+        test_account_info!(authority, 0);
+        account_info!(program, crate::ID, vec![], crate::ID, true);
+        zero_program_account!(mut governor_account, GovernorAccount);
 
-        // Account has invalid size
-        account_info!(
-            child_account,
-            Pubkey::new_unique(),
-            vec![0; NullifierChildAccount::SIZE - 1]
+        assert_eq!(
+            set_enforce_system_owned_lamports_recipient(&authority, &mut governor_account, true),
+            Err(ElusivError::InvalidAccount.into())
         );
+        assert!(!governor_account.get_enforce_system_owned_lamports_recipient());
+
+        set_enforce_system_owned_lamports_recipient(&program, &mut governor_account, true).unwrap();
+        assert!(governor_account.get_enforce_system_owned_lamports_recipient());
+
+        set_enforce_system_owned_lamports_recipient(&program, &mut governor_account, false)
+            .unwrap();
+        assert!(!governor_account.get_enforce_system_owned_lamports_recipient());
+    }
+
+    #[test]
+    fn test_set_timestamp_pruning_bits() {
+        test_account_info!(authority, 0);
+        account_info!(program, crate::ID, vec![], crate::ID, true);
+        zero_program_account!(mut governor_account, GovernorAccount);
+
+        // Fallback for an unset value
         assert_eq!(
-            enable_nullifier_child_account(&mut nullifier_account, &child_account, 0, 0),
-            Err(ProgramError::InvalidAccountData)
+            governor_account.timestamp_pruning_bits(),
+            DEFAULT_TIMESTAMP_PRUNING_BITS
         );
 
-        nullifier_account.set_child_pubkey(0, ElusivOption::Some(Pubkey::new_unique()));
+        assert_eq!(
+            set_timestamp_pruning_bits(&authority, &mut governor_account, 8),
+            Err(ElusivError::InvalidAccount.into())
+        );
 
-        // Account has already been setup
-        account_info!(
-            child_account,
-            Pubkey::new_unique(),
-            vec![0; NullifierChildAccount::SIZE]
+        assert_eq!(
+            set_timestamp_pruning_bits(
+                &program,
+                &mut governor_account,
+                MAX_TIMESTAMP_PRUNING_BITS + 1
+            ),
+            Err(ElusivError::InvalidTimestampPruningBits.into())
         );
         assert_eq!(
-            enable_nullifier_child_account(&mut nullifier_account, &child_account, 0, 0),
-            Err(ElusivError::ChildAccountAlreadyExists.into())
+            governor_account.timestamp_pruning_bits(),
+            DEFAULT_TIMESTAMP_PRUNING_BITS
         );
 
-        // Success at different index with
+        for bits in [0, 8, MAX_TIMESTAMP_PRUNING_BITS] {
+            set_timestamp_pruning_bits(&program, &mut governor_account, bits).unwrap();
+            assert_eq!(governor_account.timestamp_pruning_bits(), bits);
+        }
+    }
+
+    #[test]
+    fn test_set_memo_program_ids() {
+        test_account_info!(authority, 0);
+        account_info!(program, crate::ID, vec![], crate::ID, true);
+        zero_program_account!(mut governor_account, GovernorAccount);
+        let memo_program_ids: Vec<Pubkey> = (0..=MAX_MEMO_PROGRAM_IDS)
+            .map(|_| Pubkey::new_unique())
+            .collect();
+
+        // Fallback for an empty allow-list
         assert_eq!(
-            enable_nullifier_child_account(&mut nullifier_account, &child_account, 0, 3),
-            Ok(())
+            governor_account.memo_program_ids(),
+            vec![SPL_MEMO_PROGRAM_ID]
         );
-        assert_eq!(child_account.data.borrow()[0], 1);
 
-        // Account already is use
         assert_eq!(
-            enable_nullifier_child_account(&mut nullifier_account, &child_account, 0, 1),
-            Err(ProgramError::AccountAlreadyInitialized)
+            set_memo_program_ids(
+                &authority,
+                &mut governor_account,
+                memo_program_ids[..1].to_vec()
+            ),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        for invalid in [vec![], memo_program_ids.clone()] {
+            assert_eq!(
+                set_memo_program_ids(&program, &mut governor_account, invalid),
+                Err(ElusivError::InvalidInstructionData.into())
+            );
+        }
+
+        set_memo_program_ids(
+            &program,
+            &mut governor_account,
+            memo_program_ids[..MAX_MEMO_PROGRAM_IDS].to_vec(),
+        )
+        .unwrap();
+        assert_eq!(
+            governor_account.memo_program_ids(),
+            memo_program_ids[..MAX_MEMO_PROGRAM_IDS]
         );
+
+        // Removed ids are cleared
+        set_memo_program_ids(
+            &program,
+            &mut governor_account,
+            memo_program_ids[..1].to_vec(),
+        )
+        .unwrap();
+        assert_eq!(governor_account.memo_program_ids(), memo_program_ids[..1]);
     }
 
     #[test]
-    fn test_is_mt_full() {
-        let mut data = vec![0; StorageAccount::SIZE];
-        let mut storage_account = StorageAccount::new(&mut data).unwrap();
-        storage_account.set_next_commitment_ptr(&(MT_COMMITMENT_COUNT as u32));
+    fn test_set_rent_recipient() {
+        test_account_info!(authority, 0);
+        account_info!(program, crate::ID, vec![], crate::ID, true);
+        zero_program_account!(mut governor_account, GovernorAccount);
+        let treasury = Pubkey::new_unique();
 
-        let mut q_data = vec![0; CommitmentQueueAccount::SIZE];
-        let mut queue = CommitmentQueueAccount::new(&mut q_data).unwrap();
-        let mut queue = CommitmentQueue::new(&mut queue);
-        queue
-            .enqueue(CommitmentHashRequest {
-                min_batching_rate: 1,
-                commitment: [0; 32],
-                fee_version: 0,
+        assert_eq!(
+            set_rent_recipient(
+                &authority,
+                &mut governor_account,
+                ElusivOption::Some(treasury)
+            ),
+            Err(ElusivError::InvalidAccount.into())
+        );
+        assert_eq!(governor_account.get_rent_recipient().option(), None);
+
+        set_rent_recipient(
+            &program,
+            &mut governor_account,
+            ElusivOption::Some(treasury),
+        )
+        .unwrap();
+        assert_eq!(
+            governor_account.get_rent_recipient().option(),
+            Some(treasury)
+        );
+
+        set_rent_recipient(&program, &mut governor_account, ElusivOption::None).unwrap();
+        assert_eq!(governor_account.get_rent_recipient().option(), None);
+    }
+
+    #[test]
+    fn test_governor_head() {
+        zero_program_account!(mut governor_account, GovernorAccount);
+        governor_account.set_fee_version(&3);
+        governor_account.set_commitment_batching_rate(&4);
+
+        assert_eq!(governor_head(&governor_account), Ok(()));
+
+        let head = GovernorHead::new(&governor_account);
+        assert_eq!(
+            head,
+            GovernorHead {
+                fee_version: governor_account.get_fee_version(),
+                commitment_batching_rate: governor_account.get_commitment_batching_rate(),
+            }
+        );
+        assert_eq!(
+            GovernorHead::try_from_slice(&head.try_to_vec().unwrap()).unwrap(),
+            GovernorHead {
+                fee_version: 3,
+                commitment_batching_rate: 4,
+            }
+        );
+    }
+
+    #[test]
+    fn test_list_tokens() {
+        zero_program_account!(mut governor, GovernorAccount);
+        governor.set_fee_version(&2);
+        governor.set_program_fee(&ProgramFee::new(1, 11, 22, 0, 0, 0, 0).unwrap());
+        let pk = TokenRegistryAccount::find(None).0;
+        let supported_token = |token_id: TokenID| {
+            let token = elusiv_token(token_id).unwrap();
+            SupportedToken {
+                token_id,
+                mint: token.mint,
+                decimals: token.decimals,
+                min: token.min,
+                max: token.max,
+            }
+        };
+
+        // Absent registry
+        account_info!(token_registry, pk, vec![]);
+        assert_eq!(list_tokens(&token_registry, &governor), Ok(()));
+        let list = token_list(&token_registry, &governor).unwrap();
+        assert_eq!(list.fee_version, 2);
+        assert_eq!(list.base_commitment_network_fee, 11);
+        assert_eq!(list.proof_network_fee, 22);
+        assert_eq!(list.tokens.len(), SPL_TOKEN_COUNT + 1);
+        assert_eq!(list.tokens[0], supported_token(LAMPORTS_TOKEN_ID));
+        assert_eq!(
+            list.tokens[USDC_TOKEN_ID as usize],
+            supported_token(USDC_TOKEN_ID)
+        );
+
+        // Registered tokens override the static metadata and extend the list
+        let token = RegisteredToken {
+            mint: Pubkey::new_unique(),
+            decimals: 3,
+            price_base_exp: 0,
+            pyth_usd_price_key: Pubkey::new_unique(),
+            min: 5,
+            max: 50,
+        };
+        let mut data = vec![0; TokenRegistryAccount::SIZE];
+        let mut registry = TokenRegistryAccount::new(&mut data).unwrap();
+        registry.register_token(USDC_TOKEN_ID, &token).unwrap();
+        for token_id in 1..=TOKEN_REGISTRY_CAPACITY as TokenID {
+            if token_id != USDC_TOKEN_ID {
+                registry.register_token(token_id, &token).unwrap();
+            }
+        }
+        account_info!(token_registry, pk, data, crate::ID, false);
+        let list = token_list(&token_registry, &governor).unwrap();
+        assert_eq!(list.tokens.len(), TOKEN_REGISTRY_CAPACITY + 1);
+        assert_eq!(list.tokens[0], supported_token(LAMPORTS_TOKEN_ID));
+        assert_eq!(
+            list.tokens[USDC_TOKEN_ID as usize],
+            SupportedToken {
+                token_id: USDC_TOKEN_ID,
+                mint: token.mint,
+                decimals: 3,
+                min: 5,
+                max: 50,
+            }
+        );
+
+        // A full registry still fits into the return data
+        assert!(list.try_to_vec().unwrap().len() <= solana_program::program::MAX_RETURN_DATA);
+    }
+
+    fn token_account_data(token_id: u16, amount: u64) -> Vec<u8> {
+        let account = spl_token::state::Account {
+            mint: elusiv_token(token_id).unwrap().mint,
+            amount,
+            state: spl_token::state::AccountState::Initialized,
+            ..Default::default()
+        };
+        let mut data = vec![0; spl_token::state::Account::LEN];
+        spl_token::state::Account::pack(account, &mut data[..]).unwrap();
+        data
+    }
+
+    #[test]
+    fn test_snapshot_treasury() {
+        let pool_pk = PoolAccount::find(None).0;
+        let fee_collector_pk = FeeCollectorAccount::find(None).0;
+        account_info!(pool, pool_pk, vec![]);
+        account_info!(fee_collector, fee_collector_pk, vec![]);
+        account_info!(token_registry, TokenRegistryAccount::find(None).0, vec![]);
+        **pool.try_borrow_mut_lamports().unwrap() = 1_000;
+        **fee_collector.try_borrow_mut_lamports().unwrap() = 200;
+
+        let pool_usdc_pk =
+            program_token_account_address::<PoolAccount>(USDC_TOKEN_ID, None).unwrap();
+        let fee_collector_usdc_pk =
+            program_token_account_address::<FeeCollectorAccount>(USDC_TOKEN_ID, None).unwrap();
+        let pool_usdt_pk =
+            program_token_account_address::<PoolAccount>(USDT_TOKEN_ID, None).unwrap();
+        let fee_collector_usdt_pk =
+            program_token_account_address::<FeeCollectorAccount>(USDT_TOKEN_ID, None).unwrap();
+
+        let accounts = [
+            (pool_usdc_pk, token_account_data(USDC_TOKEN_ID, 10)),
+            (fee_collector_usdc_pk, token_account_data(USDC_TOKEN_ID, 1)),
+            (pool_usdt_pk, token_account_data(USDT_TOKEN_ID, 20)),
+            (fee_collector_usdt_pk, token_account_data(USDT_TOKEN_ID, 2)),
+        ];
+        let mut lamports = vec![0; accounts.len()];
+        let mut data: Vec<Vec<u8>> = accounts.iter().map(|(_, d)| d.clone()).collect();
+        let token_accounts: Vec<AccountInfo> = accounts
+            .iter()
+            .zip(lamports.iter_mut())
+            .zip(data.iter_mut())
+            .map(|(((pk, _), lamports), data)| {
+                AccountInfo::new(pk, false, false, lamports, data, &spl_token::ID, false, 0)
             })
-            .unwrap();
-        queue
-            .enqueue(CommitmentHashRequest {
-                min_batching_rate: 1,
-                commitment: [0; 32],
-                fee_version: 0,
+            .collect();
+
+        // Lamports only
+        assert_eq!(
+            treasury_snapshot(&pool, &fee_collector, &token_registry, &[], &[]),
+            Ok(TreasurySnapshot {
+                pool_lamports: 1_000,
+                fee_collector_lamports: 200,
+                token_balances: vec![],
             })
-            .unwrap();
+        );
 
-        assert!(is_mt_full(&storage_account, &queue).unwrap());
+        // Lamports and two tokens
+        assert_eq!(
+            treasury_snapshot(
+                &pool,
+                &fee_collector,
+                &token_registry,
+                &token_accounts,
+                &[USDC_TOKEN_ID, USDT_TOKEN_ID]
+            ),
+            Ok(TreasurySnapshot {
+                pool_lamports: 1_000,
+                fee_collector_lamports: 200,
+                token_balances: vec![
+                    TreasuryTokenBalance {
+                        token_id: USDC_TOKEN_ID,
+                        pool: 10,
+                        fee_collector: 1,
+                    },
+                    TreasuryTokenBalance {
+                        token_id: USDT_TOKEN_ID,
+                        pool: 20,
+                        fee_collector: 2,
+                    },
+                ],
+            })
+        );
+        assert_eq!(
+            snapshot_treasury(
+                &pool,
+                &fee_collector,
+                &token_registry,
+                &token_accounts,
+                vec![USDC_TOKEN_ID, USDT_TOKEN_ID]
+            ),
+            Ok(())
+        );
 
-        storage_account.set_next_commitment_ptr(&(MT_COMMITMENT_COUNT as u32 - 3));
-        assert!(!is_mt_full(&storage_account, &queue).unwrap());
+        // Missing token-accounts
+        assert_eq!(
+            treasury_snapshot(
+                &pool,
+                &fee_collector,
+                &token_registry,
+                &token_accounts[..2],
+                &[USDC_TOKEN_ID, USDT_TOKEN_ID]
+            ),
+            Err(ElusivError::InvalidInstructionData.into())
+        );
 
-        storage_account.set_next_commitment_ptr(&(MT_COMMITMENT_COUNT as u32 - 2));
-        assert!(is_mt_full(&storage_account, &queue).unwrap());
+        // Wrong token-account (USDC account for USDT)
+        assert_eq!(
+            treasury_snapshot(
+                &pool,
+                &fee_collector,
+                &token_registry,
+                &[
+                    token_accounts[0].clone(),
+                    token_accounts[1].clone(),
+                    token_accounts[0].clone(),
+                    token_accounts[3].clone(),
+                ],
+                &[USDC_TOKEN_ID, USDT_TOKEN_ID]
+            ),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        // Swapped pool and fee-collector token-accounts
+        assert_eq!(
+            treasury_snapshot(
+                &pool,
+                &fee_collector,
+                &token_registry,
+                &[token_accounts[1].clone(), token_accounts[0].clone()],
+                &[USDC_TOKEN_ID]
+            ),
+            Err(ElusivError::InvalidAccount.into())
+        );
     }
 
     #[test]
-    #[should_panic]
-    fn test_archive_closed_merkle_tree() {
+    fn test_audit_program_token_account() {
+        account_info!(pool, PoolAccount::find(None).0, vec![]);
+        account_info!(fee_collector, FeeCollectorAccount::find(None).0, vec![]);
+        account_info!(token_registry, TokenRegistryAccount::find(None).0, vec![]);
+        program_token_account_info!(pool_usdc, PoolAccount, USDC_TOKEN_ID);
+        program_token_account_info!(fee_collector_usdc, FeeCollectorAccount, USDC_TOKEN_ID);
+
+        assert_eq!(
+            audit_program_token_account(
+                &pool,
+                &fee_collector,
+                &pool_usdc,
+                &fee_collector_usdc,
+                &token_registry,
+                USDC_TOKEN_ID
+            ),
+            Ok(())
+        );
+
+        // Lamports
+        assert_eq!(
+            audit_program_token_account(
+                &pool,
+                &fee_collector,
+                &pool,
+                &fee_collector,
+                &token_registry,
+                0
+            ),
+            Ok(())
+        );
+
+        // Pool token-account with a delegate
+        let mut account =
+            spl_token::state::Account::unpack(&token_account_data(USDC_TOKEN_ID, 10)).unwrap();
+        account.delegate = Some(Pubkey::new_unique()).into();
+        let mut data = vec![0; spl_token::state::Account::LEN];
+        spl_token::state::Account::pack(account, &mut data[..]).unwrap();
+        account_info!(
+            delegated_pool_usdc,
+            *pool_usdc.key,
+            data,
+            spl_token::ID,
+            false
+        );
+        assert_eq!(
+            audit_program_token_account(
+                &pool,
+                &fee_collector,
+                &delegated_pool_usdc,
+                &fee_collector_usdc,
+                &token_registry,
+                USDC_TOKEN_ID
+            ),
+            Err(ElusivError::CompromisedTokenAccount.into())
+        );
+    }
+
+    #[test]
+    fn test_setup_genesis_params() {
+        test_account_info!(authority, 0);
         test_account_info!(payer, 0);
-        let mut data = vec![0; StorageAccount::SIZE];
-        let mut storage_account = StorageAccount::new(&mut data).unwrap();
-        let mut data = vec![0; NullifierAccount::SIZE];
-        let mut nullifier_account = NullifierAccount::new(&mut data).unwrap();
-        test_account_info!(archived_tree_account, 0);
+        account_info!(
+            genesis_params_account,
+            GenesisParamsAccount::find(None).0,
+            vec![]
+        );
 
-        archive_closed_merkle_tree(
-            &payer,
-            &mut storage_account,
-            &mut nullifier_account,
-            &archived_tree_account,
-            0,
-        )
-        .unwrap();
+        assert_eq!(
+            setup_genesis_params(
+                &authority,
+                &payer,
+                UnverifiedAccountInfo::new(&genesis_params_account),
+                [1; 32],
+                [2; 32]
+            ),
+            Err(ElusivError::InvalidAccount.into())
+        );
     }
 
     #[test]
-    #[should_panic]
-    fn test_upgrade_governor_state() {
+    fn test_write_genesis_params() {
+        zero_program_account!(mut genesis_params_account, GenesisParamsAccount);
+        let genesis_params = GenesisParams {
+            zero_commitment: [1; 32],
+            zero_base_commitment: [2; 32],
+        };
+
+        // Non-scalar values
+        for invalid in [
+            GenesisParams {
+                zero_commitment: [255; 32],
+                ..genesis_params
+            },
+            GenesisParams {
+                zero_base_commitment: [255; 32],
+                ..genesis_params
+            },
+        ] {
+            assert_eq!(
+                write_genesis_params(&mut genesis_params_account, &invalid),
+                Err(ElusivError::NonScalarValue.into())
+            );
+        }
+
+        write_genesis_params(&mut genesis_params_account, &genesis_params).unwrap();
+        assert!(genesis_params_account.get_is_setup());
+        assert_eq!(genesis_params_account.get_zero_commitment(), [1; 32]);
+        assert_eq!(genesis_params_account.get_zero_base_commitment(), [2; 32]);
+
+        // Genesis params can only be written once
+        assert_eq!(
+            write_genesis_params(&mut genesis_params_account, &GenesisParams::default()),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+        assert_eq!(genesis_params_account.get_zero_commitment(), [1; 32]);
+    }
+
+    #[test]
+    fn test_address_lookup_table_program_id() {
+        assert_eq!(
+            ADDRESS_LOOKUP_TABLE_PROGRAM_ID,
+            Pubkey::from_str("AddressLookupTab1e1111111111111111111111111").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_register_common_lut() {
         test_account_info!(authority, 0);
+        account_info!(program, crate::ID, vec![], crate::ID, true);
         zero_program_account!(mut governor_account, GovernorAccount);
-        zero_program_account!(commitment_queue, CommitmentQueueAccount);
+        let lut_address = Pubkey::new_unique();
 
-        upgrade_governor_state(&authority, &mut governor_account, &commitment_queue, 1, 1).unwrap();
+        assert_eq!(
+            register_common_lut(&authority, &mut governor_account, lut_address),
+            Err(ElusivError::InvalidAccount.into())
+        );
+        assert_eq!(governor_account.get_common_lut().option(), None);
+
+        register_common_lut(&program, &mut governor_account, lut_address).unwrap();
+        assert_eq!(
+            governor_account.get_common_lut().option(),
+            Some(lut_address)
+        );
+    }
+
+    fn lookup_table_data(addresses: &[Pubkey], deactivation_slot: u64) -> Vec<u8> {
+        let mut data = vec![0; LOOKUP_TABLE_META_SIZE];
+        data[..4].copy_from_slice(&1u32.to_le_bytes());
+        data[4..12].copy_from_slice(&deactivation_slot.to_le_bytes());
+        for address in addresses {
+            data.extend(address.to_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn test_verify_common_lut() {
+        let lut_address = Pubkey::new_unique();
+        zero_program_account!(mut governor_account, GovernorAccount);
+
+        let mut addresses = common_lut_addresses().unwrap();
+        addresses.push(Pubkey::new_unique());
+
+        // LUT not registered
+        account_info!(
+            lut,
+            lut_address,
+            lookup_table_data(&addresses, u64::MAX),
+            ADDRESS_LOOKUP_TABLE_PROGRAM_ID,
+            false
+        );
+        assert_eq!(
+            verify_common_lut(&governor_account, &lut),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        governor_account.set_common_lut(&ElusivOption::Some(lut_address));
+        assert_eq!(verify_common_lut(&governor_account, &lut), Ok(()));
+
+        // Different LUT
+        account_info!(
+            lut,
+            Pubkey::new_unique(),
+            lookup_table_data(&addresses, u64::MAX),
+            ADDRESS_LOOKUP_TABLE_PROGRAM_ID,
+            false
+        );
+        assert_eq!(
+            verify_common_lut(&governor_account, &lut),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        // Invalid owner
+        account_info!(
+            lut,
+            lut_address,
+            lookup_table_data(&addresses, u64::MAX),
+            crate::ID,
+            false
+        );
+        assert_eq!(
+            verify_common_lut(&governor_account, &lut),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        // Deactivated LUT
+        account_info!(
+            lut,
+            lut_address,
+            lookup_table_data(&addresses, 1),
+            ADDRESS_LOOKUP_TABLE_PROGRAM_ID,
+            false
+        );
+        assert_eq!(
+            verify_common_lut(&governor_account, &lut),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+
+        // Invalid data size
+        let mut data = lookup_table_data(&addresses, u64::MAX);
+        data.pop();
+        account_info!(
+            lut,
+            lut_address,
+            data,
+            ADDRESS_LOOKUP_TABLE_PROGRAM_ID,
+            false
+        );
+        assert_eq!(
+            verify_common_lut(&governor_account, &lut),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+
+        // LUT missing a single required key
+        for i in 0..addresses.len() - 1 {
+            let mut addresses = addresses.clone();
+            addresses.remove(i);
+
+            account_info!(
+                lut,
+                lut_address,
+                lookup_table_data(&addresses, u64::MAX),
+                ADDRESS_LOOKUP_TABLE_PROGRAM_ID,
+                false
+            );
+            assert_eq!(
+                verify_common_lut(&governor_account, &lut),
+                Err(ElusivError::MissingValue.into())
+            );
+        }
     }
 
     #[test]
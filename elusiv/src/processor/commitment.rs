@@ -7,7 +7,7 @@ use crate::commitment::{
     BaseCommitmentHashComputation, MAX_HT_COMMITMENTS,
 };
 use crate::error::ElusivError;
-use crate::fields::{fr_to_u256_le, is_element_scalar_field, u256_to_big_uint, u256_to_fr_skip_mr};
+use crate::fields::{fr_to_u256_le, is_element_scalar_field, u256_to_big_uint};
 use crate::macros::{guard, pda_account, BorshSerDeSized};
 use crate::processor::utils::{
     transfer_lamports_from_pda_checked, transfer_token, transfer_token_from_pda,
@@ -17,24 +17,27 @@ use crate::state::commitment::{
     BaseCommitmentBufferAccount, BaseCommitmentHashingAccount, CommitmentHashingAccount,
     CommitmentQueue, CommitmentQueueAccount, COMMITMENT_BUFFER_LEN,
 };
+use crate::state::genesis::GenesisParams;
 use crate::state::governor::FeeCollectorAccount;
 use crate::state::metadata::{
     CommitmentMetadata, MetadataAccount, MetadataQueue, MetadataQueueAccount,
 };
 use crate::state::storage::{StorageAccount, MT_COMMITMENT_COUNT};
+use crate::state::token_registry::registered_elusiv_token;
 use crate::state::{
-    fee::FeeAccount,
-    governor::GovernorAccount,
+    fee::{FeeAccount, ProgramFee},
+    governor::{Category, GovernorAccount},
     queue::{Queue, RingQueue},
 };
-use crate::token::{Token, TokenPrice};
+use crate::token::{rounding, Token, TokenPrice};
 use crate::types::{RawU256, U256};
-use ark_bn254::Fr;
-use ark_ff::BigInteger256;
 use borsh::{BorshDeserialize, BorshSerialize};
 use elusiv_computation::PartialComputation;
 use elusiv_types::UnverifiedAccountInfo;
-use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError,
+    pubkey::Pubkey,
+};
 
 #[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Clone, Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
@@ -57,15 +60,16 @@ pub struct CommitmentHashRequest {
     pub commitment: U256,
     pub fee_version: u32,
     pub min_batching_rate: u32,
+
+    /// The view tag of the [`crate::types::SendPublicInputs`] (`0` for base-commitments)
+    pub view_tag: u8,
 }
 
 /// poseidon(0, 0)
-const ZERO_BASE_COMMITMENT: Fr = Fr::new(BigInteger256::new([
-    3162363550698150530,
-    9486080942857866267,
-    15374008727889305678,
-    621823773387469172,
-]));
+pub const ZERO_BASE_COMMITMENT_RAW: U256 = [
+    100, 72, 182, 70, 132, 238, 57, 168, 35, 213, 254, 95, 213, 36, 49, 220, 129, 228, 129, 123,
+    242, 195, 234, 60, 171, 158, 35, 158, 251, 245, 152, 32,
+];
 
 /// poseidon(poseidon(0, 0), 0) in mr-form
 pub const ZERO_COMMITMENT: U256 = [
@@ -113,15 +117,25 @@ pub fn store_base_commitment<'a, 'b>(
     base_commitment_buffer: &mut BaseCommitmentBufferAccount,
     token_program: &AccountInfo<'a>,
     system_program: &AccountInfo<'a>,
+    genesis_params: &AccountInfo,
+    token_registry_account: &AccountInfo,
 
     hash_account_index: u32,
     hash_account_bump: u8,
     request: BaseCommitmentHashRequest,
     metadata: CommitmentMetadata,
 ) -> ProgramResult {
+    governor.check_category(Category::Deposit)?;
+
     let token_id = request.token_id;
-    let amount = Token::new_checked(token_id, request.amount)?;
-    let price = TokenPrice::new(sol_usd_price_account, token_usd_price_account, token_id)?;
+    let token = registered_elusiv_token(token_registry_account, token_id)?;
+    let amount = Token::new_checked_with_token(token_id, request.amount, &token)?;
+    let price = TokenPrice::new_with_token(
+        sol_usd_price_account,
+        token_usd_price_account,
+        token_id,
+        &token,
+    )?;
 
     guard!(
         is_element_scalar_field(u256_to_big_uint(&request.base_commitment.skip_mr())),
@@ -140,7 +154,8 @@ pub fn store_base_commitment<'a, 'b>(
 
     // Zero-commitment cannot be inserted by user
     guard!(
-        u256_to_fr_skip_mr(&request.base_commitment.reduce()) != ZERO_BASE_COMMITMENT,
+        request.base_commitment.skip_mr()
+            != GenesisParams::from_account(genesis_params)?.zero_base_commitment,
         ElusivError::InvalidInstructionData
     );
 
@@ -154,19 +169,20 @@ pub fn store_base_commitment<'a, 'b>(
     );
 
     let fee = governor.get_program_fee();
-    let subvention = fee
-        .base_commitment_subvention
-        .into_token(&price, token_id)?;
+    let subvention =
+        fee.base_commitment_subvention
+            .into_token(&price, token_id, rounding::PAYOUT)?;
     let computation_fee = (fee.base_commitment_hash_computation_fee()
         + fee.commitment_hash_computation_fee(request.min_batching_rate))?;
-    let computation_fee_token = computation_fee.into_token(&price, token_id)?;
+    let computation_fee_token = computation_fee.into_token(&price, token_id, rounding::FEE)?;
     let network_fee = Token::new(
         token_id,
-        fee.base_commitment_network_fee.calc(amount.amount()),
+        fee.base_commitment_network_fee
+            .calc(amount.amount(), rounding::FEE),
     );
 
-    verify_program_token_account(pool, pool_account, token_id)?;
-    verify_program_token_account(fee_collector, fee_collector_account, token_id)?;
+    verify_program_token_account(pool, pool_account, token_id, &token.mint)?;
+    verify_program_token_account(fee_collector, fee_collector_account, token_id, &token.mint)?;
 
     // `sender` transfers `computation_fee_token` - `subvention` to `fee_payer` (token)
     transfer_token(
@@ -306,13 +322,38 @@ pub fn finalize_base_commitment_hash<'a>(
         hashing_account.get_metadata(),
         fee_version,
         hashing_account.get_min_batching_rate(),
-    )?;
+        0,
+    )?
+    .log()?;
 
     // Close hashing account
     hashing_account.set_is_active(&false);
     close_account(original_fee_payer, hashing_account_info)
 }
 
+/// Logged once a commitment has been enqueued, allows clients to estimate the hashing latency
+#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Clone, Debug)]
+pub struct CommitmentEnqueuedEvent {
+    pub commitment: U256,
+
+    /// Position of the commitment in the [`CommitmentQueue`] at enqueue time
+    pub queue_position: u32,
+    pub min_batching_rate: u32,
+
+    /// Estimated number of commitment hash transactions ahead of the commitment
+    pub hash_tx_count_ahead: u64,
+
+    /// Allows wallets to discard most commitments without trial-decryption
+    pub view_tag: u8,
+}
+
+impl CommitmentEnqueuedEvent {
+    pub fn log(&self) -> ProgramResult {
+        solana_program::log::sol_log_data(&[&self.try_to_vec()?]);
+        Ok(())
+    }
+}
+
 /// Enques a commitment and it's associated metadata into the corresponding queues
 pub fn enqueue_commitment(
     commitment_queue: &mut CommitmentQueue,
@@ -321,24 +362,78 @@ pub fn enqueue_commitment(
     metadata: CommitmentMetadata,
     fee_version: u32,
     min_batching_rate: u32,
-) -> ProgramResult {
+    view_tag: u8,
+) -> Result<CommitmentEnqueuedEvent, ProgramError> {
+    let queue_position = commitment_queue.len();
+
     commitment_queue.enqueue(CommitmentHashRequest {
         commitment,
         fee_version,
         min_batching_rate,
+        view_tag,
     })?;
+    metadata_queue.enqueue(metadata)?;
 
-    metadata_queue.enqueue(metadata)
+    Ok(CommitmentEnqueuedEvent {
+        commitment,
+        queue_position,
+        min_batching_rate,
+        hash_tx_count_ahead: ProgramFee::commitment_hash_tx_count_ahead(
+            queue_position,
+            min_batching_rate,
+        ),
+        view_tag,
+    })
+}
+
+/// The number of most recently inserted MT-leaves a queued commitment is compared with before its insertion
+pub const DUPLICATE_COMMITMENT_WINDOW: usize = 64;
+
+/// Logged once a duplicate commitment has been removed from the [`CommitmentQueue`] (instead of being inserted into the MT)
+#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Clone, Debug)]
+pub struct DuplicateCommitmentSkippedEvent {
+    pub commitment: U256,
+    pub fee_version: u32,
+
+    /// The commitment hash fee (in lamports) refunded from the pool to the fee collector
+    pub refunded_fee: u64,
+}
+
+impl DuplicateCommitmentSkippedEvent {
+    pub fn log(&self) -> ProgramResult {
+        solana_program::log::sol_log_data(&[&self.try_to_vec()?]);
+        Ok(())
+    }
 }
 
 /// Places the hash siblings into the hashing account
-pub fn init_commitment_hash_setup(
+///
+/// # Note
+///
+/// Leading commitments of the [`CommitmentQueue`] matching one of the last [`DUPLICATE_COMMITMENT_WINDOW`] MT-leaves are skipped beforehand.
+#[allow(clippy::too_many_arguments)]
+pub fn init_commitment_hash_setup<'a>(
     hashing_account: &mut CommitmentHashingAccount,
+    commitment_queue: &mut CommitmentQueueAccount,
+    metadata_queue: &mut MetadataQueueAccount,
+    fee: &FeeAccount,
+    pool: &AccountInfo<'a>,
+    fee_collector: &AccountInfo<'a>,
     storage_account: &StorageAccount,
 
     insertion_can_fail: bool,
+    fee_version: u32,
 ) -> ProgramResult {
-    match init_commitment_hash_setup_inner(hashing_account, storage_account) {
+    match init_commitment_hash_setup_inner(
+        hashing_account,
+        commitment_queue,
+        metadata_queue,
+        fee,
+        pool,
+        fee_collector,
+        storage_account,
+        fee_version,
+    ) {
         Ok(()) => Ok(()),
         Err(e) => {
             if insertion_can_fail {
@@ -351,21 +446,80 @@ pub fn init_commitment_hash_setup(
     }
 }
 
-fn init_commitment_hash_setup_inner(
+#[allow(clippy::too_many_arguments)]
+fn init_commitment_hash_setup_inner<'a>(
     hashing_account: &mut CommitmentHashingAccount,
+    commitment_queue: &mut CommitmentQueueAccount,
+    metadata_queue: &mut MetadataQueueAccount,
+    fee: &FeeAccount,
+    pool: &AccountInfo<'a>,
+    fee_collector: &AccountInfo<'a>,
     storage_account: &StorageAccount,
+    fee_version: u32,
 ) -> ProgramResult {
     guard!(
         !hashing_account.get_is_active(),
         ElusivError::ComputationIsNotYetFinished
     );
 
+    skip_duplicate_commitments(
+        commitment_queue,
+        metadata_queue,
+        fee,
+        pool,
+        fee_collector,
+        storage_account,
+        fee_version,
+    )?;
+
     let ordering = storage_account.get_next_commitment_ptr();
     let siblings = storage_account.get_mt_opening(ordering as usize)?;
 
     hashing_account.setup(ordering, &siblings)
 }
 
+/// Removes all leading commitments of the [`CommitmentQueue`] that have already been inserted (into the last [`DUPLICATE_COMMITMENT_WINDOW`] MT-leaves)
+/// - the commitment hash fee of a skipped commitment is refunded to the fee collector
+/// - requests of a different `fee_version` are not skipped
+fn skip_duplicate_commitments<'a>(
+    commitment_queue: &mut CommitmentQueueAccount,
+    metadata_queue: &mut MetadataQueueAccount,
+    fee: &FeeAccount,
+    pool: &AccountInfo<'a>,
+    fee_collector: &AccountInfo<'a>,
+    storage_account: &StorageAccount,
+    fee_version: u32,
+) -> ProgramResult {
+    let mut commitment_queue = CommitmentQueue::new(commitment_queue);
+    let mut metadata_queue = MetadataQueue::new(metadata_queue);
+
+    while let Ok(request) = commitment_queue.view_first() {
+        if request.fee_version != fee_version
+            || !storage_account
+                .is_recently_inserted(&request.commitment, DUPLICATE_COMMITMENT_WINDOW)?
+        {
+            break;
+        }
+
+        let refunded_fee = fee
+            .get_program_fee()
+            .commitment_hash_computation_fee(request.min_batching_rate);
+        transfer_lamports_from_pda_checked(pool, fee_collector, refunded_fee.0)?;
+
+        commitment_queue.remove(1)?;
+        metadata_queue.remove(1)?;
+
+        DuplicateCommitmentSkippedEvent {
+            commitment: request.commitment,
+            fee_version,
+            refunded_fee: refunded_fee.0,
+        }
+        .log()?;
+    }
+
+    Ok(())
+}
+
 /// Places the next batch from the commitment queue in the [`CommitmentHashingAccount`]
 pub fn init_commitment_hash(
     commitment_queue: &mut CommitmentQueueAccount,
@@ -409,12 +563,10 @@ fn init_commitment_hash_inner(
     );
 
     let mut commitment_queue = CommitmentQueue::new(commitment_queue);
-    let (batch, batching_rate) = commitment_queue.next_batch()?;
-    commitment_queue.remove(usize_as_u32_safe(batch.len()))?;
+    let (batch, batching_rate) = commitment_queue.dequeue_batch()?;
 
     let mut metadata_queue = MetadataQueue::new(metadata_queue);
-    for _ in 0..batch.len() {
-        let metadata = metadata_queue.dequeue_first()?;
+    for metadata in metadata_queue.dequeue_n(batch.len())? {
         metadata_account.add_commitment_metadata(&metadata)?;
     }
 
@@ -452,6 +604,7 @@ pub fn compute_commitment_hash<'a>(
         hashing_account.get_fee_version() == fee_version,
         ElusivError::InvalidFeeVersion
     );
+    hashing_account.attribute_fee_payer(fee_payer.key)?;
 
     compute_commitment_hash_partial(hashing_account)?;
 
@@ -462,6 +615,25 @@ pub fn compute_commitment_hash<'a>(
     )
 }
 
+/// Logged once a commitment batch has been inserted into the MT
+#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Clone, Debug)]
+pub struct CommitmentsInsertedEvent {
+    /// The fee-payer that computed all hashes of the batch
+    pub fee_payer: Pubkey,
+
+    /// The MT-index of the first commitment of the batch
+    pub ordering: u32,
+    pub commitment_count: u32,
+    pub fee_version: u32,
+}
+
+impl CommitmentsInsertedEvent {
+    pub fn log(&self) -> ProgramResult {
+        solana_program::log::sol_log_data(&[&self.try_to_vec()?]);
+        Ok(())
+    }
+}
+
 /// Requires `batching_rate + 1` calls
 pub fn finalize_commitment_hash(
     hashing_account: &mut CommitmentHashingAccount,
@@ -493,11 +665,19 @@ pub fn finalize_commitment_hash(
         ElusivError::NoRoomForCommitment
     );
 
-    hashing_account.update_mt(storage_account, finalization_ix);
+    hashing_account.update_mt(storage_account, finalization_ix)?;
     hashing_account.set_finalization_ix(&(finalization_ix + 1));
     if finalization_ix == batching_rate {
         hashing_account.set_is_active(&false);
         hashing_account.set_setup(&false);
+
+        CommitmentsInsertedEvent {
+            fee_payer: hashing_account.get_fee_payer().option().unwrap_or_default(),
+            ordering: hashing_account.get_ordering(),
+            commitment_count: usize_as_u32_safe(commitments_per_batch(batching_rate)),
+            fee_version: hashing_account.get_fee_version(),
+        }
+        .log()?;
     }
     Ok(())
 }
@@ -505,28 +685,42 @@ pub fn finalize_commitment_hash(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::bytes::ElusivOption;
     use crate::commitment::poseidon_hash::full_poseidon2_hash;
     use crate::fields::{
-        big_uint_to_u256, fr_to_u256_le_repr, u256_from_str_skip_mr, SCALAR_MODULUS_RAW,
+        big_uint_to_u256, fr_to_u256_le_repr, u256_from_str_skip_mr, u256_to_fr_skip_mr,
+        SCALAR_MODULUS_RAW,
     };
     use crate::macros::{
         account_info, parent_account, program_token_account_info, pyth_price_account_info,
         test_account_info, test_pda_account_info, zero_program_account,
     };
     use crate::processor::mutate;
-    use crate::state::governor::PoolAccount;
-    use crate::state::program_account::{PDAAccount, SizedAccount};
+    use crate::state::commitment::COMMITMENT_QUEUE_LEN;
+    use crate::state::genesis::GenesisParamsAccount;
+    use crate::state::governor::{PoolAccount, PAUSABLE_CATEGORIES};
+    use crate::state::program_account::{PDAAccount, ProgramAccount, SizedAccount};
     use crate::state::storage::{EMPTY_TREE, MT_HEIGHT};
+    use crate::state::token_registry::TokenRegistryAccount;
     use crate::token::{lamports_token, usdc_token, LAMPORTS_TOKEN_ID, USDC_TOKEN_ID};
+    use ark_bn254::Fr;
+    use ark_ff::BigInteger256;
     use ark_ff::Zero;
     use elusiv_types::tokens::Price;
     use elusiv_types::{BorshSerDeSized, TokenError};
     use solana_program::native_token::LAMPORTS_PER_SOL;
-    use solana_program::program_error::ProgramError;
     use solana_program::pubkey::Pubkey;
     use solana_program::system_program;
     use std::str::FromStr;
 
+    /// poseidon(0, 0)
+    const ZERO_BASE_COMMITMENT: Fr = Fr::new(BigInteger256::new([
+        3162363550698150530,
+        9486080942857866267,
+        15374008727889305678,
+        621823773387469172,
+    ]));
+
     #[test]
     fn test_zero_commitment() {
         assert_eq!(
@@ -550,6 +744,10 @@ mod tests {
             full_poseidon2_hash(Fr::zero(), Fr::zero()),
             ZERO_BASE_COMMITMENT
         );
+        assert_eq!(
+            fr_to_u256_le_repr(&ZERO_BASE_COMMITMENT),
+            ZERO_BASE_COMMITMENT_RAW
+        );
     }
 
     #[test]
@@ -582,6 +780,8 @@ mod tests {
         test_account_info!(fee_collector, 0);
         test_account_info!(any, 0);
         account_info!(sys, system_program::id(), vec![]);
+        account_info!(genesis, GenesisParamsAccount::find(None).0, vec![]);
+        account_info!(token_registry, TokenRegistryAccount::find(None).0, vec![]);
         account_info!(spl, spl_token::id(), vec![]);
         let (hasing_account_pubkey, bump) = BaseCommitmentHashingAccount::find(Some(0));
         account_info!(
@@ -684,6 +884,8 @@ mod tests {
                     &mut buffer,
                     &sys,
                     &sys,
+                    &genesis,
+                    &token_registry,
                     0,
                     bump,
                     request,
@@ -693,6 +895,44 @@ mod tests {
             );
         }
 
+        // Zero-commitment sourced from the genesis params
+        let mut genesis_data = vec![0; GenesisParamsAccount::SIZE];
+        let mut genesis_params = GenesisParamsAccount::new(&mut genesis_data).unwrap();
+        genesis_params.set_zero_base_commitment(&request.base_commitment.skip_mr());
+        genesis_params.set_is_setup(&true);
+        account_info!(
+            custom_genesis,
+            GenesisParamsAccount::find(None).0,
+            genesis_data
+        );
+        assert_eq!(
+            store_base_commitment(
+                &sender,
+                &sender,
+                &fee_payer,
+                &fee_payer,
+                &pool,
+                &pool,
+                &fee_collector,
+                &fee_collector,
+                &any,
+                &any,
+                &governor,
+                &storage,
+                UnverifiedAccountInfo::new(&hashing_acc),
+                &mut buffer,
+                &sys,
+                &sys,
+                &custom_genesis,
+                &token_registry,
+                0,
+                bump,
+                request.clone(),
+                metadata,
+            ),
+            Err(ElusivError::InvalidInstructionData.into())
+        );
+
         // Invalid pool_account
         assert_eq!(
             store_base_commitment(
@@ -712,6 +952,8 @@ mod tests {
                 &mut buffer,
                 &sys,
                 &sys,
+                &genesis,
+                &token_registry,
                 0,
                 bump,
                 request.clone(),
@@ -739,6 +981,8 @@ mod tests {
                 &mut buffer,
                 &sys,
                 &sys,
+                &genesis,
+                &token_registry,
                 0,
                 bump,
                 request.clone(),
@@ -766,6 +1010,8 @@ mod tests {
                 &mut buffer,
                 &spl,
                 &sys,
+                &genesis,
+                &token_registry,
                 0,
                 bump,
                 request.clone(),
@@ -793,6 +1039,8 @@ mod tests {
                 &mut buffer,
                 &sys,
                 &sys,
+                &genesis,
+                &token_registry,
                 1,
                 bump,
                 request.clone(),
@@ -820,6 +1068,8 @@ mod tests {
                 &mut buffer,
                 &sys,
                 &sys,
+                &genesis,
+                &token_registry,
                 0,
                 0,
                 request.clone(),
@@ -828,6 +1078,8 @@ mod tests {
             Err(ProgramError::InvalidSeeds)
         );
 
+        // Paused deposits
+        governor.set_paused_categories(&Category::Deposit.bit());
         assert_eq!(
             store_base_commitment(
                 &sender,
@@ -846,6 +1098,37 @@ mod tests {
                 &mut buffer,
                 &sys,
                 &sys,
+                &genesis,
+                &token_registry,
+                0,
+                bump,
+                request.clone(),
+                metadata,
+            ),
+            Err(ElusivError::CategoryPaused.into())
+        );
+        governor.set_paused_categories(&(PAUSABLE_CATEGORIES & !Category::Deposit.bit()));
+
+        assert_eq!(
+            store_base_commitment(
+                &sender,
+                &sender,
+                &fee_payer,
+                &fee_payer,
+                &pool,
+                &pool,
+                &fee_collector,
+                &fee_collector,
+                &any,
+                &any,
+                &governor,
+                &storage,
+                UnverifiedAccountInfo::new(&hashing_acc),
+                &mut buffer,
+                &sys,
+                &sys,
+                &genesis,
+                &token_registry,
                 0,
                 bump,
                 request.clone(),
@@ -873,6 +1156,8 @@ mod tests {
                 &mut buffer,
                 &sys,
                 &sys,
+                &genesis,
+                &token_registry,
                 0,
                 bump,
                 request,
@@ -896,6 +1181,8 @@ mod tests {
         program_token_account_info!(pool_token, PoolAccount, USDC_TOKEN_ID);
         program_token_account_info!(fee_c_token, FeeCollectorAccount, USDC_TOKEN_ID);
         account_info!(sys, system_program::id(), vec![]);
+        account_info!(genesis, GenesisParamsAccount::find(None).0, vec![]);
+        account_info!(token_registry, TokenRegistryAccount::find(None).0, vec![]);
         account_info!(spl, spl_token::id(), vec![]);
         let (hasing_account_pubkey, bump) = BaseCommitmentHashingAccount::find(Some(0));
         account_info!(
@@ -964,6 +1251,8 @@ mod tests {
                     &mut buffer,
                     &spl,
                     &sys,
+                    &genesis,
+                    &token_registry,
                     0,
                     bump,
                     request,
@@ -992,6 +1281,8 @@ mod tests {
                 &mut buffer,
                 &spl,
                 &sys,
+                &genesis,
+                &token_registry,
                 0,
                 bump,
                 request.clone(),
@@ -1019,6 +1310,8 @@ mod tests {
                 &mut buffer,
                 &spl,
                 &sys,
+                &genesis,
+                &token_registry,
                 0,
                 bump,
                 request.clone(),
@@ -1046,6 +1339,8 @@ mod tests {
                 &mut buffer,
                 &sys,
                 &sys,
+                &genesis,
+                &token_registry,
                 0,
                 bump,
                 request.clone(),
@@ -1073,6 +1368,8 @@ mod tests {
                 &mut buffer,
                 &spl,
                 &sys,
+                &genesis,
+                &token_registry,
                 1,
                 bump,
                 request.clone(),
@@ -1100,6 +1397,8 @@ mod tests {
                 &mut buffer,
                 &spl,
                 &sys,
+                &genesis,
+                &token_registry,
                 0,
                 bump,
                 request.clone(),
@@ -1127,6 +1426,8 @@ mod tests {
                 &mut buffer,
                 &spl,
                 &sys,
+                &genesis,
+                &token_registry,
                 0,
                 bump,
                 request.clone(),
@@ -1154,6 +1455,8 @@ mod tests {
                 &mut buffer,
                 &spl,
                 &sys,
+                &genesis,
+                &token_registry,
                 0,
                 bump,
                 request.clone(),
@@ -1181,6 +1484,8 @@ mod tests {
                 &mut buffer,
                 &spl,
                 &sys,
+                &genesis,
+                &token_registry,
                 0,
                 bump,
                 request.clone(),
@@ -1207,6 +1512,8 @@ mod tests {
                 &mut buffer,
                 &spl,
                 &sys,
+                &genesis,
+                &token_registry,
                 0,
                 bump,
                 request.clone(),
@@ -1234,6 +1541,8 @@ mod tests {
                 &mut buffer,
                 &spl,
                 &sys,
+                &genesis,
+                &token_registry,
                 0,
                 bump,
                 request,
@@ -1376,6 +1685,7 @@ mod tests {
                         commitment: [0; 32],
                         min_batching_rate: 0,
                         fee_version: 0,
+                        view_tag: 0,
                     })
                     .unwrap();
             }
@@ -1412,6 +1722,74 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_enqueue_commitment() {
+        zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
+        zero_program_account!(mut metadata_queue, MetadataQueueAccount);
+        let mut commitment_queue = CommitmentQueue::new(&mut commitment_queue);
+        let mut metadata_queue = MetadataQueue::new(&mut metadata_queue);
+
+        // Empty queue
+        let event = enqueue_commitment(
+            &mut commitment_queue,
+            &mut metadata_queue,
+            [1; 32],
+            CommitmentMetadata::default(),
+            0,
+            2,
+            7,
+        )
+        .unwrap();
+        assert_eq!(
+            event,
+            CommitmentEnqueuedEvent {
+                commitment: [1; 32],
+                queue_position: 0,
+                min_batching_rate: 2,
+                hash_tx_count_ahead: 0,
+                view_tag: 7,
+            }
+        );
+        assert_eq!(commitment_queue.view_first().unwrap().view_tag, 7);
+
+        // Half-full queue
+        for _ in 1..COMMITMENT_QUEUE_LEN / 2 {
+            enqueue_commitment(
+                &mut commitment_queue,
+                &mut metadata_queue,
+                [0; 32],
+                CommitmentMetadata::default(),
+                0,
+                0,
+                0,
+            )
+            .unwrap();
+        }
+
+        let event = enqueue_commitment(
+            &mut commitment_queue,
+            &mut metadata_queue,
+            [2; 32],
+            CommitmentMetadata::default(),
+            0,
+            2,
+            0,
+        )
+        .unwrap();
+
+        // 120 commitments ahead, with four commitments per batch
+        assert_eq!(COMMITMENT_QUEUE_LEN / 2, 120);
+        assert_eq!(event.queue_position, 120);
+        assert_eq!(
+            event.hash_tx_count_ahead,
+            30 * commitment_hash_computation_instructions(2).len() as u64
+        );
+        assert_eq!(
+            event.hash_tx_count_ahead,
+            ProgramFee::commitment_hash_tx_count_ahead(120, 2)
+        );
+    }
+
     #[test]
     fn test_init_commitment_hash_empty_queue() {
         parent_account!(storage_account, StorageAccount);
@@ -1419,8 +1797,22 @@ mod tests {
         zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
         zero_program_account!(mut metadata_queue, MetadataQueueAccount);
         zero_program_account!(mut hashing_account, CommitmentHashingAccount);
+        zero_program_account!(fee, FeeAccount);
+        test_pda_account_info!(pool, PoolAccount);
+        test_pda_account_info!(fee_c, FeeCollectorAccount);
 
-        init_commitment_hash_setup(&mut hashing_account, &storage_account, false).unwrap();
+        init_commitment_hash_setup(
+            &mut hashing_account,
+            &mut commitment_queue,
+            &mut metadata_queue,
+            &fee,
+            &pool,
+            &fee_c,
+            &storage_account,
+            false,
+            0,
+        )
+        .unwrap();
         assert_eq!(
             init_commitment_hash(
                 &mut commitment_queue,
@@ -1450,6 +1842,7 @@ mod tests {
                 CommitmentMetadata::default(),
                 0,
                 0,
+                0,
             )
             .unwrap();
         }
@@ -1475,6 +1868,9 @@ mod tests {
         zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
         zero_program_account!(mut metadata_queue, MetadataQueueAccount);
         zero_program_account!(mut hashing_account, CommitmentHashingAccount);
+        zero_program_account!(fee, FeeAccount);
+        test_pda_account_info!(pool, PoolAccount);
+        test_pda_account_info!(fee_c, FeeCollectorAccount);
 
         {
             let mut commitment_queue = CommitmentQueue::new(&mut commitment_queue);
@@ -1482,16 +1878,28 @@ mod tests {
             enqueue_commitment(
                 &mut commitment_queue,
                 &mut metadata_queue,
-                [0; 32],
+                [1; 32],
                 CommitmentMetadata::default(),
                 0,
                 0,
+                0,
             )
             .unwrap();
         }
 
         storage_account.set_next_commitment_ptr(&(MT_COMMITMENT_COUNT as u32));
-        init_commitment_hash_setup(&mut hashing_account, &storage_account, false).unwrap();
+        init_commitment_hash_setup(
+            &mut hashing_account,
+            &mut commitment_queue,
+            &mut metadata_queue,
+            &fee,
+            &pool,
+            &fee_c,
+            &storage_account,
+            false,
+            0,
+        )
+        .unwrap();
         assert_eq!(
             init_commitment_hash(
                 &mut commitment_queue,
@@ -1511,6 +1919,9 @@ mod tests {
         zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
         zero_program_account!(mut metadata_queue, MetadataQueueAccount);
         zero_program_account!(mut hashing_account, CommitmentHashingAccount);
+        zero_program_account!(fee, FeeAccount);
+        test_pda_account_info!(pool, PoolAccount);
+        test_pda_account_info!(fee_c, FeeCollectorAccount);
 
         {
             let mut commitment_queue = CommitmentQueue::new(&mut commitment_queue);
@@ -1522,11 +1933,23 @@ mod tests {
                 CommitmentMetadata::default(),
                 0,
                 1,
+                0,
             )
             .unwrap();
         }
 
-        init_commitment_hash_setup(&mut hashing_account, &storage_account, false).unwrap();
+        init_commitment_hash_setup(
+            &mut hashing_account,
+            &mut commitment_queue,
+            &mut metadata_queue,
+            &fee,
+            &pool,
+            &fee_c,
+            &storage_account,
+            false,
+            0,
+        )
+        .unwrap();
         assert_eq!(
             init_commitment_hash(
                 &mut commitment_queue,
@@ -1546,6 +1969,9 @@ mod tests {
         zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
         zero_program_account!(mut metadata_queue, MetadataQueueAccount);
         zero_program_account!(mut hashing_account, CommitmentHashingAccount);
+        zero_program_account!(fee, FeeAccount);
+        test_pda_account_info!(pool, PoolAccount);
+        test_pda_account_info!(fee_c, FeeCollectorAccount);
 
         {
             let mut commitment_queue = CommitmentQueue::new(&mut commitment_queue);
@@ -1554,17 +1980,29 @@ mod tests {
                 enqueue_commitment(
                     &mut commitment_queue,
                     &mut metadata_queue,
-                    [0; 32],
+                    [1; 32],
                     CommitmentMetadata::default(),
                     0,
                     1,
+                    0,
                 )
                 .unwrap();
             }
         }
 
         storage_account.set_next_commitment_ptr(&(MT_COMMITMENT_COUNT as u32 - 1));
-        init_commitment_hash_setup(&mut hashing_account, &storage_account, false).unwrap();
+        init_commitment_hash_setup(
+            &mut hashing_account,
+            &mut commitment_queue,
+            &mut metadata_queue,
+            &fee,
+            &pool,
+            &fee_c,
+            &storage_account,
+            false,
+            0,
+        )
+        .unwrap();
         assert_eq!(
             init_commitment_hash(
                 &mut commitment_queue,
@@ -1585,6 +2023,9 @@ mod tests {
         zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
         zero_program_account!(mut metadata_queue, MetadataQueueAccount);
         zero_program_account!(mut hashing_account, CommitmentHashingAccount);
+        zero_program_account!(fee, FeeAccount);
+        test_pda_account_info!(pool, PoolAccount);
+        test_pda_account_info!(fee_c, FeeCollectorAccount);
 
         let mut c_queue = CommitmentQueue::new(&mut commitment_queue);
         let mut m_queue = MetadataQueue::new(&mut metadata_queue);
@@ -1594,12 +2035,24 @@ mod tests {
                     commitment: [i; 32],
                     min_batching_rate: 2,
                     fee_version: 0,
+                    view_tag: 0,
                 })
                 .unwrap();
             m_queue.enqueue([i; CommitmentMetadata::SIZE]).unwrap();
         }
 
-        init_commitment_hash_setup(&mut hashing_account, &storage_account, false).unwrap();
+        init_commitment_hash_setup(
+            &mut hashing_account,
+            &mut commitment_queue,
+            &mut metadata_queue,
+            &fee,
+            &pool,
+            &fee_c,
+            &storage_account,
+            false,
+            0,
+        )
+        .unwrap();
         init_commitment_hash(
             &mut commitment_queue,
             &mut metadata_queue,
@@ -1622,20 +2075,194 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_init_commitment_hash_setup_skip_duplicate() {
+        parent_account!(mut storage_account, StorageAccount);
+        parent_account!(mut metadata_account, MetadataAccount);
+        zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
+        zero_program_account!(mut metadata_queue, MetadataQueueAccount);
+        zero_program_account!(mut hashing_account, CommitmentHashingAccount);
+        zero_program_account!(mut fee, FeeAccount);
+        test_pda_account_info!(pool, PoolAccount);
+        test_pda_account_info!(fee_c, FeeCollectorAccount);
+
+        let program_fee = ProgramFee::new(5000, 0, 0, 0, 0, 300, 0).unwrap();
+        fee.set_program_fee(&program_fee);
+
+        let commitment = u256_from_str_skip_mr("123456789");
+        let enqueue = |commitment_queue: &mut CommitmentQueueAccount,
+                       metadata_queue: &mut MetadataQueueAccount| {
+            enqueue_commitment(
+                &mut CommitmentQueue::new(commitment_queue),
+                &mut MetadataQueue::new(metadata_queue),
+                commitment,
+                CommitmentMetadata::default(),
+                0,
+                0,
+                0,
+            )
+            .unwrap();
+        };
+
+        // Insert the commitment
+        enqueue(&mut commitment_queue, &mut metadata_queue);
+        init_commitment_hash_setup(
+            &mut hashing_account,
+            &mut commitment_queue,
+            &mut metadata_queue,
+            &fee,
+            &pool,
+            &fee_c,
+            &storage_account,
+            false,
+            0,
+        )
+        .unwrap();
+        init_commitment_hash(
+            &mut commitment_queue,
+            &mut metadata_queue,
+            &mut hashing_account,
+            &mut metadata_account,
+            false,
+        )
+        .unwrap();
+        for _ in commitment_hash_computation_instructions(0) {
+            compute_commitment_hash_partial(&mut hashing_account).unwrap();
+        }
+        finalize_commitment_hash(&mut hashing_account, &mut storage_account).unwrap();
+
+        let root = storage_account.get_root().unwrap();
+        let mut expected_root = u256_to_fr_skip_mr(&commitment);
+        for empty_node in EMPTY_TREE.iter().take(MT_HEIGHT as usize) {
+            expected_root = full_poseidon2_hash(expected_root, u256_to_fr_skip_mr(empty_node));
+        }
+        assert_eq!(root, fr_to_u256_le(&expected_root));
+
+        // Enqueue the commitment a second time
+        enqueue(&mut commitment_queue, &mut metadata_queue);
+
+        let pool_lamports = pool.lamports();
+        let fee_c_lamports = fee_c.lamports();
+        init_commitment_hash_setup(
+            &mut hashing_account,
+            &mut commitment_queue,
+            &mut metadata_queue,
+            &fee,
+            &pool,
+            &fee_c,
+            &storage_account,
+            false,
+            0,
+        )
+        .unwrap();
+
+        // Duplicate is removed and the hash fee refunded
+        let refunded_fee = program_fee.commitment_hash_computation_fee(0).0;
+        assert!(refunded_fee > 0);
+        assert_eq!(pool.lamports(), pool_lamports - refunded_fee);
+        assert_eq!(fee_c.lamports(), fee_c_lamports + refunded_fee);
+        assert!(CommitmentQueue::new(&mut commitment_queue).is_empty());
+        assert!(MetadataQueue::new(&mut metadata_queue).is_empty());
+        assert_eq!(
+            init_commitment_hash(
+                &mut commitment_queue,
+                &mut metadata_queue,
+                &mut hashing_account,
+                &mut metadata_account,
+                false,
+            ),
+            Err(ElusivError::QueueIsEmpty.into())
+        );
+
+        // The MT contains a single copy of the commitment
+        assert_eq!(storage_account.get_next_commitment_ptr(), 1);
+        assert_eq!(storage_account.get_node(0, MT_HEIGHT as usize), commitment);
+        assert_eq!(
+            storage_account.get_node(1, MT_HEIGHT as usize),
+            EMPTY_TREE[0]
+        );
+        assert_eq!(storage_account.get_root().unwrap(), root);
+    }
+
+    #[test]
+    fn test_init_commitment_hash_setup_skip_duplicate_fee_version() {
+        parent_account!(mut storage_account, StorageAccount);
+        zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
+        zero_program_account!(mut metadata_queue, MetadataQueueAccount);
+        zero_program_account!(mut hashing_account, CommitmentHashingAccount);
+        zero_program_account!(fee, FeeAccount);
+        test_pda_account_info!(pool, PoolAccount);
+        test_pda_account_info!(fee_c, FeeCollectorAccount);
+
+        storage_account.set_node(&[1; 32], 0, MT_HEIGHT as usize);
+        storage_account.set_next_commitment_ptr(&1);
+
+        enqueue_commitment(
+            &mut CommitmentQueue::new(&mut commitment_queue),
+            &mut MetadataQueue::new(&mut metadata_queue),
+            [1; 32],
+            CommitmentMetadata::default(),
+            1,
+            0,
+            0,
+        )
+        .unwrap();
+
+        // Requests of a different fee-version are not skipped
+        init_commitment_hash_setup(
+            &mut hashing_account,
+            &mut commitment_queue,
+            &mut metadata_queue,
+            &fee,
+            &pool,
+            &fee_c,
+            &storage_account,
+            false,
+            0,
+        )
+        .unwrap();
+        assert_eq!(CommitmentQueue::new(&mut commitment_queue).len(), 1);
+    }
+
     #[test]
     fn test_init_commitment_hash_setup_insertion_can_fail() {
         parent_account!(storage_account, StorageAccount);
         zero_program_account!(mut hashing_account, CommitmentHashingAccount);
+        zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
+        zero_program_account!(mut metadata_queue, MetadataQueueAccount);
+        zero_program_account!(fee, FeeAccount);
+        test_pda_account_info!(pool, PoolAccount);
+        test_pda_account_info!(fee_c, FeeCollectorAccount);
 
         hashing_account.set_is_active(&true);
 
         assert_eq!(
-            init_commitment_hash_setup(&mut hashing_account, &storage_account, false),
+            init_commitment_hash_setup(
+                &mut hashing_account,
+                &mut commitment_queue,
+                &mut metadata_queue,
+                &fee,
+                &pool,
+                &fee_c,
+                &storage_account,
+                false,
+                0,
+            ),
             Err(ElusivError::ComputationIsNotYetFinished.into())
         );
 
         assert_eq!(
-            init_commitment_hash_setup(&mut hashing_account, &storage_account, true),
+            init_commitment_hash_setup(
+                &mut hashing_account,
+                &mut commitment_queue,
+                &mut metadata_queue,
+                &fee,
+                &pool,
+                &fee_c,
+                &storage_account,
+                true,
+                0,
+            ),
             Ok(())
         );
     }
@@ -1693,6 +2320,38 @@ mod tests {
         compute_commitment_hash(&fee_payer, &fee, &pool, &mut hashing_account, 0, 0).unwrap();
     }
 
+    #[test]
+    fn test_compute_commitment_hash_fee_payer() {
+        zero_program_account!(mut hashing_account, CommitmentHashingAccount);
+        zero_program_account!(fee, FeeAccount);
+        test_account_info!(pool, 0);
+        test_account_info!(fee_payer, 0);
+        test_account_info!(interloper, 0);
+
+        hashing_account.set_setup(&true);
+        hashing_account.reset(0, 0, &[[0; 32]]).unwrap();
+        assert_eq!(hashing_account.get_fee_payer(), ElusivOption::None);
+
+        compute_commitment_hash(&fee_payer, &fee, &pool, &mut hashing_account, 0, 0).unwrap();
+        assert_eq!(
+            hashing_account.get_fee_payer(),
+            ElusivOption::Some(*fee_payer.key)
+        );
+
+        // Compute step of a different signer
+        assert_eq!(
+            compute_commitment_hash(&interloper, &fee, &pool, &mut hashing_account, 0, 0),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        compute_commitment_hash(&fee_payer, &fee, &pool, &mut hashing_account, 0, 1).unwrap();
+
+        // The next batch can be computed by a different fee-payer
+        hashing_account.set_is_active(&false);
+        hashing_account.reset(0, 0, &[[0; 32]]).unwrap();
+        compute_commitment_hash(&interloper, &fee, &pool, &mut hashing_account, 0, 0).unwrap();
+    }
+
     #[test]
     fn test_finalize_commitment_hash() {
         parent_account!(mut storage_account, StorageAccount);
@@ -1745,7 +2404,7 @@ mod tests {
             let level_size = commitment_count >> level;
             for index in 0..level_size {
                 assert_eq!(
-                    storage_account.get_node(index, level as usize).unwrap(),
+                    storage_account.get_node(index, level as usize),
                     EMPTY_TREE[level_inv as usize]
                 );
             }
@@ -1768,7 +2427,7 @@ mod tests {
             let level = MT_HEIGHT - level_inv;
             for index in 0..level_size {
                 assert_ne!(
-                    storage_account.get_node(index, level as usize).unwrap(),
+                    storage_account.get_node(index, level as usize),
                     EMPTY_TREE[level_inv as usize]
                 );
             }
@@ -0,0 +1,280 @@
+use super::utils::*;
+use crate::bytes::ElusivOption;
+use crate::error::ElusivError;
+use crate::instruction::ElusivInstruction;
+use crate::macros::*;
+use crate::state::failure_injection::FailureInjectionAccount;
+use crate::state::proof::VerificationAccount;
+use crate::token::TokenError;
+use elusiv_types::{PDAAccount, SizedAccount};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult};
+
+/// Opens the [`FailureInjectionAccount`] with the `payer` as its authority
+///
+/// # Note
+///
+/// Only available in `devnet-tools` builds (which are not allowed on mainnet).
+pub fn open_failure_injection_account<'a>(
+    payer: &AccountInfo<'a>,
+    failure_injection_account: &AccountInfo<'a>,
+) -> ProgramResult {
+    open_pda_account_without_offset::<FailureInjectionAccount>(
+        &crate::id(),
+        payer,
+        failure_injection_account,
+        None,
+    )?;
+
+    pda_account!(
+        mut failure_injection,
+        FailureInjectionAccount,
+        failure_injection_account
+    );
+    failure_injection.set_authority(payer.key);
+
+    Ok(())
+}
+
+/// Sets the flags of the [`FailureInjectionAccount`]
+pub fn set_failure_injection(
+    authority: &AccountInfo,
+    failure_injection: &mut FailureInjectionAccount,
+
+    force_queue_full: bool,
+    force_price_stale: bool,
+    force_compute_error_at_round: ElusivOption<u32>,
+) -> ProgramResult {
+    guard!(
+        *authority.key == failure_injection.get_authority(),
+        ElusivError::InvalidAccount
+    );
+
+    failure_injection.set_force_queue_full(&force_queue_full);
+    failure_injection.set_force_price_stale(&force_price_stale);
+    failure_injection.set_force_compute_error_at_round(&force_compute_error_at_round);
+
+    Ok(())
+}
+
+/// Fails the `instruction` if a failure is injected for it (called before the regular processing)
+///
+/// # Note
+///
+/// Without the [`FailureInjectionAccount`] among the `accounts`, no failures are injected.
+pub fn check_failure_injection(
+    accounts: &[AccountInfo],
+    instruction: &ElusivInstruction,
+) -> ProgramResult {
+    let failure_injection_key = FailureInjectionAccount::find(None).0;
+    let failure_injection_account = match accounts
+        .iter()
+        .find(|account| *account.key == failure_injection_key && *account.owner == crate::ID)
+    {
+        Some(account) => account,
+        None => return Ok(()),
+    };
+    pda_account!(
+        failure_injection,
+        FailureInjectionAccount,
+        failure_injection_account
+    );
+
+    match instruction {
+        ElusivInstruction::FinalizeBaseCommitmentHash { .. } => {
+            guard!(
+                !failure_injection.get_force_queue_full(),
+                ElusivError::QueueIsFull
+            );
+        }
+        ElusivInstruction::StoreBaseCommitment { .. }
+        | ElusivInstruction::InitVerificationTransferFee { .. } => {
+            guard!(
+                !failure_injection.get_force_price_stale(),
+                TokenError::PriceError
+            );
+        }
+        ElusivInstruction::ComputeVerification { .. } => {
+            if let Some(round) = failure_injection
+                .get_force_compute_error_at_round()
+                .option()
+            {
+                // The verification-account is the second account of the instruction (its PDA is verified by the regular processing)
+                if let Some(verification_account) = accounts.get(1).filter(|account| {
+                    *account.owner == crate::ID && account.data_len() >= VerificationAccount::SIZE
+                }) {
+                    pda_account!(
+                        verification_account,
+                        VerificationAccount,
+                        verification_account
+                    );
+                    guard!(
+                        verification_account.get_round() != round,
+                        ElusivError::PartialComputationError
+                    );
+                }
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entrypoint::process_instruction;
+    use crate::macros::{account_info, test_account_info};
+    use borsh::BorshSerialize;
+    use elusiv_types::ProgramAccount;
+    use solana_program::pubkey::Pubkey;
+
+    fn failure_injection_data(
+        force_queue_full: bool,
+        force_price_stale: bool,
+        force_compute_error_at_round: Option<u32>,
+    ) -> Vec<u8> {
+        let mut data = vec![0; FailureInjectionAccount::SIZE];
+        let mut failure_injection = FailureInjectionAccount::new(&mut data).unwrap();
+        failure_injection.set_force_queue_full(&force_queue_full);
+        failure_injection.set_force_price_stale(&force_price_stale);
+        failure_injection
+            .set_force_compute_error_at_round(&ElusivOption::from(force_compute_error_at_round));
+        data
+    }
+
+    #[test]
+    fn test_set_failure_injection() {
+        let mut data = vec![0; FailureInjectionAccount::SIZE];
+        let mut failure_injection = FailureInjectionAccount::new(&mut data).unwrap();
+        let authority_pubkey = Pubkey::new_unique();
+        failure_injection.set_authority(&authority_pubkey);
+        account_info!(authority, authority_pubkey, true);
+        test_account_info!(any, 0);
+
+        // Invalid authority
+        assert_eq!(
+            set_failure_injection(&any, &mut failure_injection, true, true, Some(1).into()),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        set_failure_injection(
+            &authority,
+            &mut failure_injection,
+            true,
+            false,
+            Some(1).into(),
+        )
+        .unwrap();
+        assert!(failure_injection.get_force_queue_full());
+        assert!(!failure_injection.get_force_price_stale());
+        assert_eq!(
+            failure_injection
+                .get_force_compute_error_at_round()
+                .option(),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn test_inject_queue_full() {
+        let instruction = ElusivInstruction::FinalizeBaseCommitmentHash {
+            hash_account_index: 0,
+            fee_version: 0,
+        }
+        .try_to_vec()
+        .unwrap();
+        account_info!(
+            enabled,
+            FailureInjectionAccount::find(None).0,
+            failure_injection_data(true, false, None)
+        );
+        account_info!(
+            disabled,
+            FailureInjectionAccount::find(None).0,
+            failure_injection_data(false, true, Some(0))
+        );
+
+        assert_eq!(
+            process_instruction(&crate::ID, &[enabled], &instruction),
+            Err(ElusivError::QueueIsFull.into())
+        );
+        assert_ne!(
+            process_instruction(&crate::ID, &[disabled], &instruction),
+            Err(ElusivError::QueueIsFull.into())
+        );
+    }
+
+    #[test]
+    fn test_inject_price_stale() {
+        let instruction = ElusivInstruction::InitVerificationTransferFee {
+            verification_account_index: 0,
+            verification_account_generation: 0,
+        }
+        .try_to_vec()
+        .unwrap();
+        account_info!(
+            enabled,
+            FailureInjectionAccount::find(None).0,
+            failure_injection_data(false, true, None)
+        );
+        account_info!(
+            disabled,
+            FailureInjectionAccount::find(None).0,
+            failure_injection_data(true, false, Some(0))
+        );
+
+        assert_eq!(
+            process_instruction(&crate::ID, &[enabled], &instruction),
+            Err(TokenError::PriceError.into())
+        );
+        assert_ne!(
+            process_instruction(&crate::ID, &[disabled], &instruction),
+            Err(TokenError::PriceError.into())
+        );
+    }
+
+    #[test]
+    fn test_inject_compute_error_at_round() {
+        let instruction = ElusivInstruction::ComputeVerification {
+            verification_account_index: 0,
+            verification_account_generation: 0,
+            vkey_id: 0,
+        }
+        .try_to_vec()
+        .unwrap();
+
+        let mut data = vec![0; VerificationAccount::SIZE];
+        let mut verification_account = VerificationAccount::new(&mut data).unwrap();
+        verification_account.set_round(&3);
+        account_info!(verification_account, Pubkey::new_unique(), data);
+        test_account_info!(fee_payer, 0);
+        account_info!(
+            round_2,
+            FailureInjectionAccount::find(None).0,
+            failure_injection_data(true, true, Some(2))
+        );
+        account_info!(
+            round_3,
+            FailureInjectionAccount::find(None).0,
+            failure_injection_data(false, false, Some(3))
+        );
+
+        assert_eq!(
+            process_instruction(
+                &crate::ID,
+                &[fee_payer.clone(), verification_account.clone(), round_3],
+                &instruction
+            ),
+            Err(ElusivError::PartialComputationError.into())
+        );
+        assert_ne!(
+            process_instruction(
+                &crate::ID,
+                &[fee_payer, verification_account, round_2],
+                &instruction
+            ),
+            Err(ElusivError::PartialComputationError.into())
+        );
+    }
+}
@@ -1,11 +1,24 @@
 mod accounts;
 mod commitment;
+#[cfg(feature = "devnet-tools")]
+mod failure_injection;
+mod owner_rotation;
 mod proof;
+#[cfg(feature = "devnet-tools")]
+mod test_token;
 mod utils;
 mod vkey;
 
 pub use accounts::*;
 pub use commitment::*;
+#[cfg(feature = "devnet-tools")]
+pub use failure_injection::*;
+pub use owner_rotation::*;
 pub use proof::*;
+#[cfg(feature = "devnet-tools")]
+pub use test_token::*;
 pub use utils::{nop, program_token_account_address};
+
+#[cfg(test)]
+pub(crate) use utils::TestInstructionsSysvar;
 pub use vkey::*;
@@ -0,0 +1,194 @@
+use crate::error::ElusivError;
+use crate::macros::{guard, pda_account, BorshSerDeSized};
+use crate::state::owner_rotation::OwnerRotationAccount;
+use crate::types::U256;
+use borsh::{BorshDeserialize, BorshSerialize};
+use elusiv_types::{PDAAccount, UnverifiedAccountInfo};
+use elusiv_utils::open_pda_account_with_associated_pubkey;
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult};
+
+/// Logged once the owner of an identifier published a new encryption pubkey
+#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Clone, Debug)]
+pub struct OwnerRotatedEvent {
+    pub identifier: U256,
+    pub encryption_pubkey: U256,
+    pub rotation_slot: u64,
+    pub rotation_count: u32,
+}
+
+impl OwnerRotatedEvent {
+    pub fn log(&self) -> ProgramResult {
+        solana_program::log::sol_log_data(&[&self.try_to_vec()?]);
+        Ok(())
+    }
+}
+
+/// Publishes a new encryption pubkey for future commitments owned by `identifier`
+///
+/// # Notes
+///
+/// - The [`OwnerRotationAccount`] is opened with the first rotation (`identifier` pays the rent).
+/// - `rotation_slot` has to be strictly increasing.
+pub fn rotate_owner<'a, 'b>(
+    identifier: &'a AccountInfo<'b>,
+    owner_rotation_account: UnverifiedAccountInfo<'a, 'b>,
+
+    encryption_pubkey: U256,
+    rotation_slot: u64,
+) -> ProgramResult {
+    let owner_rotation_account = owner_rotation_account.get_unsafe();
+
+    guard!(identifier.is_signer, ElusivError::InvalidAccount);
+    guard!(
+        *owner_rotation_account.key
+            == OwnerRotationAccount::find_with_pubkey(*identifier.key, None).0,
+        ElusivError::InvalidAccount
+    );
+
+    if owner_rotation_account.data_is_empty() {
+        open_pda_account_with_associated_pubkey::<OwnerRotationAccount>(
+            &crate::id(),
+            identifier,
+            owner_rotation_account,
+            identifier.key,
+            None,
+            None,
+        )?;
+    }
+
+    pda_account!(
+        mut owner_rotation,
+        OwnerRotationAccount,
+        owner_rotation_account
+    );
+
+    let rotation_count = owner_rotation.get_rotation_count();
+    guard!(
+        rotation_count == 0 || rotation_slot > owner_rotation.get_rotation_slot(),
+        ElusivError::InvalidInstructionData
+    );
+    let rotation_count = rotation_count
+        .checked_add(1)
+        .ok_or(ElusivError::InvalidAccountState)?;
+
+    owner_rotation.set_encryption_pubkey(&encryption_pubkey);
+    owner_rotation.set_rotation_slot(&rotation_slot);
+    owner_rotation.set_rotation_count(&rotation_count);
+
+    OwnerRotatedEvent {
+        identifier: identifier.key.to_bytes(),
+        encryption_pubkey,
+        rotation_slot,
+        rotation_count,
+    }
+    .log()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::account_info;
+    use elusiv_types::{ProgramAccount, SizedAccount};
+    use solana_program::pubkey::Pubkey;
+
+    fn rotation_account_data(identifier: &Pubkey) -> Vec<u8> {
+        let mut data = vec![0; OwnerRotationAccount::SIZE];
+        data[0] = OwnerRotationAccount::find_with_pubkey(*identifier, None).1;
+        data
+    }
+
+    #[test]
+    fn test_rotate_owner() {
+        let identifier_pk = Pubkey::new_unique();
+        account_info!(
+            identifier,
+            identifier_pk,
+            vec![],
+            Pubkey::new_unique(),
+            true
+        );
+        let pk = OwnerRotationAccount::find_with_pubkey(identifier_pk, None).0;
+        account_info!(rotation_account, pk, rotation_account_data(&identifier_pk));
+
+        rotate_owner(
+            &identifier,
+            UnverifiedAccountInfo::new(&rotation_account),
+            [1; 32],
+            10,
+        )
+        .unwrap();
+
+        // Slot needs to increase
+        for slot in [0, 10] {
+            assert_eq!(
+                rotate_owner(
+                    &identifier,
+                    UnverifiedAccountInfo::new(&rotation_account),
+                    [2; 32],
+                    slot,
+                ),
+                Err(ElusivError::InvalidInstructionData.into())
+            );
+        }
+
+        rotate_owner(
+            &identifier,
+            UnverifiedAccountInfo::new(&rotation_account),
+            [2; 32],
+            11,
+        )
+        .unwrap();
+
+        let mut data = rotation_account.data.borrow_mut();
+        let rotation = OwnerRotationAccount::new(&mut data).unwrap();
+        assert_eq!(rotation.get_encryption_pubkey(), [2; 32]);
+        assert_eq!(rotation.get_rotation_slot(), 11);
+        assert_eq!(rotation.get_rotation_count(), 2);
+    }
+
+    #[test]
+    fn test_rotate_owner_unauthorized() {
+        let identifier_pk = Pubkey::new_unique();
+        let pk = OwnerRotationAccount::find_with_pubkey(identifier_pk, None).0;
+        account_info!(rotation_account, pk, rotation_account_data(&identifier_pk));
+
+        // Identifier is not a signer
+        account_info!(
+            identifier,
+            identifier_pk,
+            vec![],
+            Pubkey::new_unique(),
+            false
+        );
+        assert_eq!(
+            rotate_owner(
+                &identifier,
+                UnverifiedAccountInfo::new(&rotation_account),
+                [1; 32],
+                1,
+            ),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        // Different signer (rotation account of another identifier)
+        account_info!(
+            other,
+            Pubkey::new_unique(),
+            vec![],
+            Pubkey::new_unique(),
+            true
+        );
+        assert_eq!(
+            rotate_owner(
+                &other,
+                UnverifiedAccountInfo::new(&rotation_account),
+                [1; 32],
+                1,
+            ),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        let data = rotation_account.data.borrow();
+        assert!(data[1..].iter().all(|b| *b == 0));
+    }
+}
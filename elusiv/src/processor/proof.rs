@@ -1,42 +1,56 @@
-use super::utils::{DefaultInstructionsSysvar, InstructionsSysvar};
+use super::utils::{
+    ClockSysvar, DefaultClockSysvar, DefaultInstructionsSysvar, InstructionsSysvar,
+};
 use crate::buffer::RingBuffer;
 use crate::bytes::{usize_as_u32_safe, BorshSerDeSized, ElusivOption};
 use crate::error::ElusivError;
+use crate::fields::{is_element_scalar_field, is_g1_valid, is_g2_valid, u256_to_big_uint};
 use crate::instruction::ElusivInstruction;
-use crate::macros::{guard, pda_account, BorshSerDeSized, EnumVariantIndex};
+use crate::macros::{cu_checkpoint, guard, pda_account, BorshSerDeSized, EnumVariantIndex};
 use crate::processor::utils::{
-    close_account, create_associated_token_account, spl_token_account_rent,
+    close_account, create_associated_token_account, current_timestamp, spl_token_account_rent,
     system_program_account_rent, transfer_lamports_from_pda_checked, transfer_token,
-    transfer_token_from_pda, verify_program_token_account,
+    transfer_token_from_pda, verify_adoptable_pda_account, verify_program_token_account,
+};
+use crate::processor::{enqueue_commitment, verify_recent_commitment_index};
+use crate::proof::verifier::{
+    prepare_public_inputs_instructions, prepare_public_inputs_instructions_with_descriptor,
+    prepare_public_inputs_rounds, verify_partial, ComputationDescriptor, VerificationStep,
+};
+use crate::proof::vkey::{
+    is_built_in_vkey, MigrateUnaryVKey, SendQuadraVKey, VerifyingKey, VerifyingKeyInfo,
 };
-use crate::processor::{enqueue_commitment, verify_recent_commitment_index, ZERO_COMMITMENT_RAW};
-use crate::proof::verifier::{prepare_public_inputs_instructions, verify_partial};
-use crate::proof::vkey::{MigrateUnaryVKey, SendQuadraVKey, VerifyingKey, VerifyingKeyInfo};
 use crate::state::commitment::{CommitmentBufferAccount, CommitmentQueue, CommitmentQueueAccount};
-use crate::state::governor::{FeeCollectorAccount, GovernorAccount, PoolAccount};
+use crate::state::genesis::GenesisParams;
+use crate::state::governor::{
+    Category, FeeCollectorAccount, GovernorAccount, NetworkFeeAccount, PoolAccount,
+};
 use crate::state::metadata::{MetadataQueue, MetadataQueueAccount};
 use crate::state::nullifier::NullifierAccount;
 use crate::state::proof::{
-    NullifierDuplicateAccount, VerificationAccount, VerificationAccountData, VerificationState,
+    NullifierDuplicateAccount, RecordedPrice, VerificationAccount, VerificationAccountData,
+    VerificationIndexAccount, VerificationState,
 };
 use crate::state::queue::{Queue, RingQueue};
 use crate::state::storage::{StorageAccount, MT_COMMITMENT_COUNT};
-use crate::state::vkey::VKeyAccount;
+use crate::state::token_registry::{is_token_paused, registered_elusiv_token};
+use crate::state::vkey::{read_computation_descriptor, VKeyAccount};
 use crate::token::{
-    elusiv_token, verify_associated_token_account, verify_token_account, Lamports, Token,
-    TokenPrice,
+    rounding, verify_associated_token_account_with_mint, verify_token_account_owner_with_mint,
+    verify_token_account_with_mint, Lamports, Token, TokenID, TokenPrice, LAMPORTS_TOKEN_ID,
 };
 use crate::types::{
-    generate_hashed_inputs, InputCommitment, JoinSplitPublicInputs, MigratePublicInputs, Proof,
-    PublicInputs, RawU256, SendPublicInputs, JOIN_SPLIT_MAX_N_ARITY, U256,
+    generate_hashed_inputs, is_encrypted_memo_valid, JoinSplitPublicInputs, MigratePublicInputs,
+    Proof, PublicInputs, RawU256, SendPublicInputs, JOIN_SPLIT_MAX_N_ARITY, MAX_MEMO_SIZE, U256,
 };
 use borsh::{BorshDeserialize, BorshSerialize};
-use elusiv_types::ParentAccount;
-use elusiv_utils::open_pda_account_with_associated_pubkey;
+use elusiv_types::{PDAAccount, ParentAccount, UnverifiedAccountInfo};
+use elusiv_utils::{open_pda_account, open_pda_account_with_associated_pubkey, MATH_ERR};
 use solana_program::instruction::Instruction;
 use solana_program::program_error::ProgramError;
 use solana_program::pubkey::Pubkey;
 use solana_program::system_instruction;
+use solana_program::system_program;
 use solana_program::sysvar::instructions;
 use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult};
 use std::collections::HashSet;
@@ -76,6 +90,13 @@ impl ProofRequest {
         }
     }
 
+    pub fn view_tag(&self) -> u8 {
+        match self {
+            ProofRequest::Send(request) => request.view_tag,
+            _ => 0,
+        }
+    }
+
     pub fn vkey_id(&self) -> u32 {
         match self {
             ProofRequest::Send(_) => SendQuadraVKey::VKEY_ID,
@@ -84,33 +105,148 @@ impl ProofRequest {
     }
 }
 
+/// The total amount (in the request's token) a user loses to fees
+/// - the fee required by [`init_verification_transfer_fee`] (computation and network fees minus the subvention)
+/// - the optional fee
+/// - the rent of the recipient's associated-token-account (if `recipient_is_associated_token_account`)
+///
+/// # Note
+///
+/// If `join_split.fee` equals the required fee, the recipient receives `amount + join_split.fee - user_facing_fee`.
+/// The associated-token-account rent is computed using the default `Rent`.
+#[cfg(feature = "elusiv-client")]
+pub fn user_facing_fee(
+    request: &ProofRequest,
+    governor: &GovernorAccount,
+    price: &TokenPrice,
+    recipient_is_associated_token_account: bool,
+) -> Result<Token, ProgramError> {
+    use solana_program::{program_pack::Pack, rent::Rent};
+
+    let join_split = proof_request!(request, public_inputs, public_inputs.join_split_inputs());
+    let token_id = join_split.token_id;
+
+    let public_inputs_count = match request {
+        ProofRequest::Send(_) => SendQuadraVKey::public_inputs_count(),
+        ProofRequest::Migrate(_) => MigrateUnaryVKey::public_inputs_count(),
+    };
+    let input_preparation_tx_count = prepare_public_inputs_instructions(
+        &proof_request!(
+            request,
+            public_inputs,
+            public_inputs.public_signals_skip_mr()
+        ),
+        public_inputs_count,
+    )
+    .len();
+
+    let fee = governor.get_program_fee().proof_verification_fee(
+        input_preparation_tx_count,
+        governor.get_commitment_batching_rate(),
+        join_split.amount,
+        token_id,
+        price,
+    )?;
+    let optional_fee = Token::new(token_id, join_split.optional_fee.amount);
+
+    let associated_token_account_rent = if recipient_is_associated_token_account {
+        Lamports(Rent::default().minimum_balance(spl_token::state::Account::LEN)).into_token(
+            price,
+            token_id,
+            rounding::FEE,
+        )?
+    } else {
+        Token::new(token_id, 0)
+    };
+
+    Ok(((fee + optional_fee)? + associated_token_account_rent)?)
+}
+
 /// We only allow two distinct MTs in a join-split (merges can be used to reduce the amount of MTs)
 pub const MAX_MT_COUNT: usize = 2;
 
-/// The maximum [`PDAOffset`] for [`VerificationAccount`] for a single fee payer
+/// The number of concurrent [`VerificationAccount`]s (indices) of a single fee payer
 pub const RESERVED_VERIFICATION_ACCOUNT_IDS: u8 = 128;
 
 /// Initializes a new proof verification
 /// - subsequent calls of [`init_verification_transfer_fee`] and [`init_verification_proof`] required to start the computation
 /// - both need to be called by the same signer (-> the fee structure "enforces" [`init_verification_transfer_fee`] to be called in the same transaction)
+/// - the fee payer can choose a `timeout` within the [`GovernorAccount`] bounds (defaults to [`VERIFICATION_TIMEOUT`])
 #[allow(clippy::too_many_arguments)]
 pub fn init_verification<'a, 'b, 'c, 'd>(
     fee_payer: &AccountInfo<'a>,
     verification_account: &AccountInfo<'a>,
+    verification_index_account: &AccountInfo<'a>,
+    vkey_account: &VKeyAccount,
+    nullifier_duplicate_account: &AccountInfo<'a>,
+    _identifier_account: &AccountInfo,
+    governor: &GovernorAccount,
+    storage_account: &StorageAccount,
+    commitment_buffer: &mut CommitmentBufferAccount,
+    genesis_params: &AccountInfo,
+    nullifier_account0: &NullifierAccount<'b, 'c, 'd>,
+    nullifier_account1: &NullifierAccount<'b, 'c, 'd>,
+
+    verification_account_index: u8,
+    verification_account_generation: u32,
+    vkey_id: u32,
+    tree_indices: [u32; MAX_MT_COUNT],
+    request: ProofRequest,
+    skip_nullifier_pda: bool,
+    timeout: ElusivOption<u64>,
+) -> ProgramResult {
+    init_verification_with_clock(
+        fee_payer,
+        verification_account,
+        verification_index_account,
+        vkey_account,
+        nullifier_duplicate_account,
+        _identifier_account,
+        governor,
+        storage_account,
+        commitment_buffer,
+        genesis_params,
+        nullifier_account0,
+        nullifier_account1,
+        verification_account_index,
+        verification_account_generation,
+        vkey_id,
+        tree_indices,
+        request,
+        skip_nullifier_pda,
+        timeout,
+        &DefaultClockSysvar,
+    )
+}
+
+/// [`init_verification`] using the supplied `clock` (allows for injecting a deterministic clock in tests)
+#[allow(clippy::too_many_arguments)]
+fn init_verification_with_clock<'a, 'b, 'c, 'd, C: ClockSysvar>(
+    fee_payer: &AccountInfo<'a>,
+    verification_account: &AccountInfo<'a>,
+    verification_index_account: &AccountInfo<'a>,
     vkey_account: &VKeyAccount,
     nullifier_duplicate_account: &AccountInfo<'a>,
     _identifier_account: &AccountInfo,
+    governor: &GovernorAccount,
     storage_account: &StorageAccount,
     commitment_buffer: &mut CommitmentBufferAccount,
+    genesis_params: &AccountInfo,
     nullifier_account0: &NullifierAccount<'b, 'c, 'd>,
     nullifier_account1: &NullifierAccount<'b, 'c, 'd>,
 
     verification_account_index: u8,
+    verification_account_generation: u32,
     vkey_id: u32,
     tree_indices: [u32; MAX_MT_COUNT],
     request: ProofRequest,
     skip_nullifier_pda: bool,
+    timeout: ElusivOption<u64>,
+    clock: &C,
 ) -> ProgramResult {
+    cu_checkpoint!("init_verification: start");
+    governor.check_category(Category::InitVerification)?;
+
     let raw_public_inputs = proof_request!(&request, public_inputs, public_inputs.public_signals());
 
     // Verify that an immutable vkey is setup
@@ -118,10 +254,30 @@ pub fn init_verification<'a, 'b, 'c, 'd>(
 
     guard!(vkey_id == request.vkey_id(), ElusivError::InvalidAccount);
     guard!(
-        verification_account_index <= RESERVED_VERIFICATION_ACCOUNT_IDS,
+        proof_request!(
+            &request,
+            public_inputs,
+            public_inputs.matches_vkey(vkey_account)
+        ),
+        ElusivError::InvalidPublicInputs
+    );
+    guard!(
+        verification_account_index < RESERVED_VERIFICATION_ACCOUNT_IDS,
         ElusivError::InvalidAccount
     );
 
+    let timeout = match timeout.option() {
+        Some(timeout) => {
+            guard!(
+                timeout >= governor.get_min_verification_timeout()
+                    && timeout <= governor.get_max_verification_timeout(),
+                ElusivError::InvalidVerificationTimeout
+            );
+            timeout
+        }
+        None => VERIFICATION_TIMEOUT,
+    };
+
     let instructions = prepare_public_inputs_instructions(
         &proof_request!(
             &request,
@@ -130,6 +286,7 @@ pub fn init_verification<'a, 'b, 'c, 'd>(
         ),
         vkey_account.get_public_inputs_count() as usize,
     );
+    cu_checkpoint!("init_verification: prepare inputs instructions");
 
     // TODO: reject zero-commitment nullifier
     // TODO: add identifier_account verification
@@ -141,6 +298,14 @@ pub fn init_verification<'a, 'b, 'c, 'd>(
                 public_inputs.verify_additional_constraints(),
                 ElusivError::InvalidPublicInputs
             );
+            guard!(
+                is_timestamp_valid(
+                    public_inputs.current_time,
+                    clock.timestamp()?,
+                    governor.timestamp_pruning_bits()
+                ),
+                ElusivError::InvalidPublicInputs
+            );
 
             &public_inputs.join_split
         }
@@ -155,7 +320,9 @@ pub fn init_verification<'a, 'b, 'c, 'd>(
         storage_account,
         [nullifier_account0, nullifier_account1],
         &tree_indices,
+        &GenesisParams::from_account(genesis_params)?,
     )?;
+    cu_checkpoint!("init_verification: check public inputs");
 
     // Open [`NullifierDuplicateAccount`]
     // - this account is used to prevent two proof verifications (of the same nullifier-hashes) at the same time
@@ -171,7 +338,17 @@ pub fn init_verification<'a, 'b, 'c, 'd>(
         if nullifier_duplicate_account.lamports() == 0 {
             return Err(ElusivError::InvalidInstructionData.into());
         }
+
+        register_duplicate_verification(
+            nullifier_duplicate_account,
+            governor.get_max_duplicate_verifications(),
+            governor.get_duplicate_grace_period(),
+            clock.slot()?,
+        )?;
     } else {
+        // Anyone can fund the PDA in advance (e.g. to force the `skip_nullifier_pda` path), such an account is adopted
+        verify_adoptable_pda_account(nullifier_duplicate_account)?;
+
         open_pda_account_with_associated_pubkey::<NullifierDuplicateAccount>(
             &crate::id(),
             fee_payer,
@@ -180,21 +357,52 @@ pub fn init_verification<'a, 'b, 'c, 'd>(
             None,
             None,
         )?;
+
+        pda_account!(
+            mut nullifier_duplicate,
+            NullifierDuplicateAccount,
+            nullifier_duplicate_account
+        );
+        nullifier_duplicate.set_last_use_slot(&clock.slot()?);
+    }
+
+    cu_checkpoint!("init_verification: nullifier duplicate account");
+
+    // Add the output commitment into the commitment-buffer
+    commitment_buffer.try_insert(&join_split.output_commitment.reduce())?;
+
+    // Consume the current generation of the fee payer's `VerificationIndexAccount`
+    if verification_index_account.data_is_empty() {
+        open_pda_account_with_associated_pubkey::<VerificationIndexAccount>(
+            &crate::id(),
+            fee_payer,
+            verification_index_account,
+            fee_payer.key,
+            None,
+            None,
+        )?;
     }
+    pda_account!(
+        mut verification_index,
+        VerificationIndexAccount,
+        verification_index_account
+    );
+    verification_index.open_index(verification_account_index, verification_account_generation)?;
 
-    // Open `VerificationAccount`
-    open_pda_account_with_associated_pubkey::<VerificationAccount>(
+    // Open `VerificationAccount` (sized for the public inputs of the vkey)
+    open_pda_account::<VerificationAccount>(
         &crate::id(),
         fee_payer,
         verification_account,
-        fee_payer.key,
-        Some(verification_account_index as u32),
+        Some(*fee_payer.key),
+        Some(VerificationAccount::pda_offset(
+            verification_account_index,
+            verification_account_generation,
+        )),
         None,
+        VerificationAccount::account_size(raw_public_inputs.len()),
     )?;
 
-    // Add the output commitment into the commitment-buffer
-    commitment_buffer.try_insert(&join_split.output_commitment.reduce())?;
-
     pda_account!(
         mut verification_account,
         VerificationAccount,
@@ -209,7 +417,11 @@ pub fn init_verification<'a, 'b, 'c, 'd>(
         vkey_id,
         request,
         tree_indices,
-    )
+    )?;
+    verification_account.set_timeout(&timeout);
+    cu_checkpoint!("init_verification: verification account");
+
+    Ok(())
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -227,12 +439,16 @@ pub fn init_verification_transfer_fee<'a>(
     token_usd_price_account: &AccountInfo,
 
     governor: &GovernorAccount,
+    token_registry_account: &AccountInfo,
     verification_account: &mut VerificationAccount,
     token_program: &AccountInfo<'a>,
     system_program: &AccountInfo<'a>,
 
     _verification_account_index: u8,
+    _verification_account_generation: u32,
 ) -> ProgramResult {
+    governor.check_category(Category::InitVerification)?;
+
     guard!(
         verification_account.get_state() == VerificationState::None,
         ElusivError::InvalidAccountState
@@ -247,30 +463,71 @@ pub fn init_verification_transfer_fee<'a>(
     let request = verification_account.get_request();
     let join_split = proof_request!(&request, public_inputs, public_inputs.join_split_inputs());
 
+    let fee_version = request.fee_version();
     guard!(
-        request.fee_version() == governor.get_fee_version(),
+        fee_version == governor.get_fee_version(),
         ElusivError::InvalidFeeVersion
     );
     let token_id = join_split.token_id;
-    let price = TokenPrice::new(sol_usd_price_account, token_usd_price_account, token_id)?;
+    guard!(
+        !is_token_paused(token_registry_account, token_id)?,
+        ElusivError::TokenPaused
+    );
+
+    // Tokens registered after deployment are only known to the token registry
+    let token = registered_elusiv_token(token_registry_account, token_id)?;
+    let price = TokenPrice::new_with_token(
+        sol_usd_price_account,
+        token_usd_price_account,
+        token_id,
+        &token,
+    )?;
     let min_batching_rate = governor.get_commitment_batching_rate();
     let fee = governor.get_program_fee();
-    let subvention = fee.proof_subvention.into_token(&price, token_id)?;
+    let subvention = fee
+        .proof_subvention
+        .into_token(&price, token_id, rounding::PAYOUT)?;
     let input_preparation_tx_count =
         verification_account.get_prepare_inputs_instructions_count() as usize;
     let proof_verification_fee = fee
         .proof_verification_computation_fee(input_preparation_tx_count)
-        .into_token(&price, token_id)?;
+        .into_token(&price, token_id, rounding::FEE)?;
     let commitment_hash_fee = fee.commitment_hash_computation_fee(min_batching_rate);
-    let commitment_hash_fee_token = commitment_hash_fee.into_token(&price, token_id)?;
-    let network_fee = Token::new(token_id, fee.proof_network_fee.calc(join_split.amount));
+    let commitment_hash_fee_token =
+        commitment_hash_fee.into_token(&price, token_id, rounding::FEE)?;
+    let network_fee = Token::new(
+        token_id,
+        fee.proof_network_fee.calc(join_split.amount, rounding::FEE),
+    );
 
     let fee =
         (((commitment_hash_fee_token + proof_verification_fee)? + network_fee)? - subvention)?;
     guard!(join_split.fee >= fee.amount(), ElusivError::InvalidFee);
 
-    verify_program_token_account(pool, pool_account, token_id)?;
-    verify_program_token_account(fee_collector, fee_collector_account, token_id)?;
+    verify_program_token_account(pool, pool_account, token_id, &token.mint)?;
+    verify_program_token_account(fee_collector, fee_collector_account, token_id, &token.mint)?;
+
+    // The refund target has to be controlled by `fee_payer` (for Lamports `fee_payer` itself)
+    guard!(
+        verify_token_account_owner_with_mint(
+            fee_payer_token_account,
+            fee_payer.key,
+            token_id,
+            &token.mint
+        )?,
+        ElusivError::InvalidAccount
+    );
+
+    // Legacy (non-associated) token-accounts are only accepted while the governor allows it
+    guard!(
+        verify_associated_token_account_with_mint(
+            fee_payer.key,
+            fee_payer_token_account.key,
+            token_id,
+            &token.mint
+        )? || governor.get_allow_legacy_fee_payer_token_account(),
+        ElusivError::InvalidAccount
+    );
 
     let mut associated_token_account_rent = Lamports(0);
     let mut associated_token_account_rent_token = 0;
@@ -280,6 +537,13 @@ pub fn init_verification_transfer_fee<'a>(
             return Err(ElusivError::InvalidRecipient.into());
         }
 
+        // Program-owned recipients (e.g. multi-sig vault PDAs) never use the associated-token-account path
+        if public_inputs.recipient_is_associated_token_account
+            && public_inputs.recipient_is_program_owned
+        {
+            return Err(ElusivError::InvalidRecipient.into());
+        }
+
         // Enforce minimum-send-amount for Lamports (zero-amount is always allowed for merges)
         if token_id == 0 && public_inputs.join_split.amount != 0 {
             guard!(
@@ -293,7 +557,7 @@ pub fn init_verification_transfer_fee<'a>(
         if public_inputs.recipient_is_associated_token_account {
             associated_token_account_rent = spl_token_account_rent()?;
             associated_token_account_rent_token = associated_token_account_rent
-                .into_token(&price, token_id)?
+                .into_token(&price, token_id, rounding::FEE)?
                 .amount();
 
             guard!(
@@ -305,13 +569,15 @@ pub fn init_verification_transfer_fee<'a>(
         }
     }
 
-    // `fee_payer` transfers `commitment_hash_fee` (+ `associated_token_account_rent`)? to `pool` (lamports)
+    // `fee_payer` transfers `commitment_hash_fee` (+ `associated_token_account_rent`)? + `failure_bond` to `pool` (lamports)
+    let failure_bond = Lamports(governor.get_failure_bond());
     transfer_token(
         fee_payer,
         fee_payer,
         pool,
         system_program,
-        (commitment_hash_fee + associated_token_account_rent)?.into_token_strict(),
+        ((commitment_hash_fee + associated_token_account_rent)? + failure_bond)?
+            .into_token_strict(),
     )?;
 
     // `fee_collector` transfers `subvention` to `pool` (token)
@@ -325,13 +591,7 @@ pub fn init_verification_transfer_fee<'a>(
         None,
     )?;
 
-    // TODO: switch fee_payer_token_account to associated-token-account
-    guard!(
-        verify_token_account(fee_payer_token_account, token_id)?,
-        ElusivError::InvalidAccount
-    );
-
-    verification_account.set_other_data(&VerificationAccountData {
+    let data = VerificationAccountData {
         fee_payer: RawU256::new(fee_payer.key.to_bytes()),
         fee_payer_account: RawU256::new(fee_payer_token_account.key.to_bytes()),
         recipient_wallet: ElusivOption::None,
@@ -344,7 +604,15 @@ pub fn init_verification_transfer_fee<'a>(
         commitment_hash_fee_token: commitment_hash_fee_token.amount(),
         proof_verification_fee: proof_verification_fee.amount(),
         associated_token_account_rent: associated_token_account_rent_token,
-    });
+        failure_bond,
+        fee_version,
+        sol_usd_price: RecordedPrice::new(&price.lamports_usd, price.lamports_usd_publish_slot),
+        token_usd_price: RecordedPrice::new(&price.token_usd, price.token_usd_publish_slot),
+        fallback_recipient: ElusivOption::None,
+        mint: RawU256::new(token.mint.to_bytes()),
+    };
+    TransferFeeEvent::new(&data).log()?;
+    verification_account.set_other_data(&data);
 
     verification_account.set_state(&VerificationState::FeeTransferred);
 
@@ -363,6 +631,7 @@ pub fn init_verification_proof(
     verification_account: &mut VerificationAccount,
 
     _verification_account_index: u8,
+    _verification_account_generation: u32,
     proof: Proof,
 ) -> ProgramResult {
     guard!(
@@ -378,10 +647,17 @@ pub fn init_verification_proof(
         ElusivError::InvalidAccount
     );
 
+    // Reject malformed points before any computation is performed
+    guard!(
+        is_g1_valid(&proof.a.0) && is_g2_valid(&proof.b.0) && is_g1_valid(&proof.c.0),
+        ElusivError::InvalidInstructionData
+    );
+
     verification_account.a.set(proof.a);
     verification_account.b.set(proof.b);
     verification_account.c.set(proof.c);
 
+    verification_account.set_proof_setup_timestamp(&current_timestamp()?);
     verification_account.set_state(&VerificationState::ProofSetup);
 
     Ok(())
@@ -390,14 +666,21 @@ pub fn init_verification_proof(
 pub const COMPUTE_VERIFICATION_IX_COUNT: u16 = 7; // two compute-unit-instructions, five compute-instructions
 
 /// Partial proof verification computation
+#[allow(clippy::too_many_arguments)]
 pub fn compute_verification(
     verification_account: &mut VerificationAccount,
     vkey_account: &VKeyAccount,
+    computation_descriptor_account: UnverifiedAccountInfo,
     instructions_account: &AccountInfo,
+    governor: &GovernorAccount,
 
     _verification_account_index: u8,
+    _verification_account_generation: u32,
     vkey_id: u32,
 ) -> ProgramResult {
+    cu_checkpoint!("compute_verification: start");
+    governor.check_category(Category::Compute)?;
+
     // Verify that an immutable vkey is setup
     guard!(vkey_account.is_setup(), ElusivError::InvalidAccount);
 
@@ -417,6 +700,32 @@ pub fn compute_verification(
         ElusivError::InvalidAccountState
     );
 
+    let is_first_instruction = matches!(
+        verification_account.get_step(),
+        VerificationStep::PublicInputPreparation
+    ) && verification_account.get_instruction() == 0;
+
+    // The vkey source has to match the digest recorded when freezing the vkey
+    if is_first_instruction {
+        vkey_account.verify_source_digest()?;
+        verify_prepare_inputs_rounds(
+            verification_account,
+            vkey_account.get_public_inputs_count() as usize,
+        )?;
+    }
+
+    // The input preparation schedule of a vkey that is not built-in has to match its registered descriptor
+    if !is_built_in_vkey(vkey_id) && is_first_instruction {
+        let public_inputs_count = vkey_account.get_public_inputs_count() as usize;
+        let descriptor = read_computation_descriptor(
+            computation_descriptor_account.get_unsafe(),
+            vkey_id,
+            public_inputs_count,
+        )?;
+        verify_prepare_inputs_instructions(verification_account, &descriptor, public_inputs_count)?;
+    }
+    cu_checkpoint!("compute_verification: checks");
+
     // instruction_index is used to allow a uniform number of ixs per tx
     let instruction_index = if cfg!(test) {
         COMPUTE_VERIFICATION_IX_COUNT - 1
@@ -430,6 +739,7 @@ pub fn compute_verification(
 
         verify_partial(verification_account, &vkey, instruction_index)
     })?;
+    cu_checkpoint!("compute_verification: verify partial");
 
     match result {
         Ok(result) => {
@@ -453,8 +763,52 @@ pub fn compute_verification(
     }
 }
 
+/// Verifies that the stored public-input preparation instructions cover exactly the rounds required by the vkey's `public_inputs_count`
+fn verify_prepare_inputs_rounds(
+    verification_account: &VerificationAccount,
+    public_inputs_count: usize,
+) -> ProgramResult {
+    guard!(
+        verification_account.get_public_inputs_count() as usize == public_inputs_count,
+        ElusivError::PrepareInputsInstructionsMismatch
+    );
+    guard!(
+        verification_account.prepare_inputs_rounds()
+            == Some(prepare_public_inputs_rounds(public_inputs_count)),
+        ElusivError::PrepareInputsInstructionsMismatch
+    );
+
+    Ok(())
+}
+
+fn verify_prepare_inputs_instructions(
+    verification_account: &VerificationAccount,
+    descriptor: &ComputationDescriptor,
+    public_inputs_count: usize,
+) -> ProgramResult {
+    let public_inputs: Vec<U256> = (0..public_inputs_count)
+        .map(|i| verification_account.load_raw_public_input(i))
+        .collect();
+    let instructions =
+        prepare_public_inputs_instructions_with_descriptor(&public_inputs, descriptor);
+
+    guard!(
+        verification_account.get_prepare_inputs_instructions_count() as usize == instructions.len(),
+        ElusivError::InvalidAccountState
+    );
+    for (i, &instruction) in instructions.iter().enumerate() {
+        guard!(
+            verification_account.get_prepare_inputs_instructions(i) as u32 == instruction,
+            ElusivError::InvalidAccountState
+        );
+    }
+
+    Ok(())
+}
+
 #[derive(BorshDeserialize, BorshSerialize, Clone, Default)]
 pub struct FinalizeSendData {
+    /// The join-split `amount` including the `fee` (see [`JoinSplitPublicInputs::total_amount`])
     pub total_amount: u64,
     pub token_id: u16,
 
@@ -466,12 +820,89 @@ pub struct FinalizeSendData {
 
     pub iv: U256,
     pub encrypted_owner: U256,
+
+    /// Optional memo encrypted to the recipient (opaque bytes bound into `hashed_inputs`)
+    pub encrypted_memo: Option<Vec<u8>>,
+    /// Optional token account receiving the amount if the recipient is not a valid token account (bound into `hashed_inputs`)
+    pub fallback_recipient: Option<U256>,
+}
+
+/// Logged by [`init_verification_transfer_fee`], contains all fees (in `token_id`-Token) and the prices used for their conversion
+///
+/// # Note
+///
+/// All finalize reimbursements are computed from these (persisted) amounts, so no price feed is read after the fee transfer.
+#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Clone, Debug)]
+pub struct TransferFeeEvent {
+    pub token_id: u16,
+    pub subvention: u64,
+    pub network_fee: u64,
+    pub commitment_hash_fee_token: u64,
+    pub proof_verification_fee: u64,
+    pub associated_token_account_rent: u64,
+    pub sol_usd_price: RecordedPrice,
+    pub token_usd_price: RecordedPrice,
+}
+
+impl TransferFeeEvent {
+    pub fn new(data: &VerificationAccountData) -> Self {
+        TransferFeeEvent {
+            token_id: data.token_id,
+            subvention: data.subvention,
+            network_fee: data.network_fee,
+            commitment_hash_fee_token: data.commitment_hash_fee_token,
+            proof_verification_fee: data.proof_verification_fee,
+            associated_token_account_rent: data.associated_token_account_rent,
+            sol_usd_price: data.sol_usd_price,
+            token_usd_price: data.token_usd_price,
+        }
+    }
+
+    pub fn log(&self) -> ProgramResult {
+        solana_program::log::sol_log_data(&[&self.try_to_vec()?]);
+        Ok(())
+    }
+}
+
+/// Logged by [`finalize_verification_send`] for valid proofs, allows indexers to associate commitments with an [`crate::state::owner_rotation::OwnerRotationAccount`]
+#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Clone, Debug)]
+pub struct FinalizeSendEvent {
+    pub identifier: U256,
+    pub commitment: U256,
+    pub encrypted_owner: U256,
+
+    /// The allow-listed memo program invoked by the memo-instruction (the default pubkey without a memo)
+    pub memo_program_id: Pubkey,
+}
+
+impl FinalizeSendEvent {
+    pub fn log(&self) -> ProgramResult {
+        solana_program::log::sol_log_data(&[&self.try_to_vec()?]);
+        Ok(())
+    }
+}
+
+/// Logged by [`finalize_verification_transfer_lamports`] and [`finalize_verification_transfer_token`] for valid sends with a non-zero amount
+#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Clone, Debug)]
+pub struct FinalizeTransferEvent {
+    pub token_id: u16,
+
+    /// The amount received by the recipient (see [`net_recipient_amount`])
+    ///
+    /// # Note
+    ///
+    /// For Solana Pay transfers, `pool` transfers this amount to the fee-payer, who forwards it to the recipient in the enforced trailing transfer.
+    pub net_recipient_amount: u64,
+
+    pub solana_pay_transfer: bool,
 }
 
-const SPL_MEMO_PROGRAM_ID: Pubkey = Pubkey::new_from_array([
-    5, 74, 83, 90, 153, 41, 33, 6, 77, 36, 232, 113, 96, 218, 56, 124, 124, 53, 181, 221, 188, 146,
-    187, 129, 228, 31, 168, 64, 65, 5, 68, 141,
-]);
+impl FinalizeTransferEvent {
+    pub fn log(&self) -> ProgramResult {
+        solana_program::log::sol_log_data(&[&self.try_to_vec()?]);
+        Ok(())
+    }
+}
 
 /// First finalize instruction
 ///
@@ -495,9 +926,11 @@ pub fn finalize_verification_send(
     verification_account: &mut VerificationAccount,
     storage_account: &StorageAccount,
     buffer: &mut CommitmentBufferAccount,
+    governor: &GovernorAccount,
     instructions_account: &AccountInfo,
 
     verification_account_index: u8,
+    verification_account_generation: u32,
     data: FinalizeSendData,
     uses_memo: bool,
 ) -> ProgramResult {
@@ -509,19 +942,41 @@ pub fn finalize_verification_send(
     let request = verification_account.get_request();
     let public_inputs = match request {
         ProofRequest::Send(public_inputs) => public_inputs,
-        _ => return Err(ElusivError::FeatureNotAvailable.into()),
+        ProofRequest::Migrate(_) => return Err(ElusivError::FeatureNotAvailable.into()),
     };
 
-    // Check spl-memo-instruction
-    let memo = if uses_memo {
-        Some(get_memo_from_instructions(
+    // Lamports sent to a program-owned account (e.g. a token account) are not recoverable by the recipient
+    if governor.get_enforce_system_owned_lamports_recipient()
+        && public_inputs.join_split.token_id == 0
+        && public_inputs.join_split.amount > 0
+        && !public_inputs.recipient_is_program_owned
+    {
+        guard!(
+            *recipient.owner == system_program::ID,
+            ElusivError::InvalidRecipient
+        );
+    }
+
+    // Check memo-instruction
+    let (memo, memo_program_id) = if uses_memo {
+        let (memo, memo_program_id) = get_memo_from_instructions(
             &DefaultInstructionsSysvar(instructions_account),
+            &governor.memo_program_ids(),
             public_inputs.solana_pay_transfer,
-        )?)
+            governor.get_enforce_single_memo(),
+        )?;
+        (Some(memo), memo_program_id)
     } else {
-        None
+        (None, Pubkey::default())
     };
 
+    if let Some(encrypted_memo) = &data.encrypted_memo {
+        guard!(
+            is_encrypted_memo_valid(encrypted_memo),
+            ElusivError::InvalidEncryptedMemo
+        );
+    }
+
     // Verify `hashed_inputs`
     let hash = generate_hashed_inputs(
         &recipient.key.to_bytes(),
@@ -534,19 +989,29 @@ pub fn finalize_verification_send(
             [0; 32]
         }),
         public_inputs.recipient_is_associated_token_account,
+        public_inputs.recipient_is_program_owned,
+        public_inputs.view_tag,
+        public_inputs.current_time,
         &public_inputs.join_split.metadata,
         &public_inputs.join_split.optional_fee,
         &memo,
-    );
+        &data.encrypted_memo,
+        &data.fallback_recipient,
+    )?;
     guard!(
         hash == public_inputs.hashed_inputs,
         ElusivError::InputsMismatch
     );
 
-    // Set `recipient_wallet`
-    verification_account.set_other_data(&mutate(&verification_account.get_other_data(), |data| {
-        data.recipient_wallet = ElusivOption::Some(RawU256::new(recipient.key.to_bytes()))
-    }));
+    // Set `recipient_wallet` and `fallback_recipient`
+    verification_account.set_other_data(&mutate(
+        &verification_account.get_other_data(),
+        |other_data| {
+            other_data.recipient_wallet =
+                ElusivOption::Some(RawU256::new(recipient.key.to_bytes()));
+            other_data.fallback_recipient = data.fallback_recipient.map(RawU256::new).into();
+        },
+    ));
 
     match verification_account.get_is_verified() {
         ElusivOption::None => return Err(ElusivError::ComputationIsNotYetFinished.into()),
@@ -565,10 +1030,19 @@ pub fn finalize_verification_send(
         _ => {}
     }
 
+    // Reject an output commitment that is already enqueued (it would result in a duplicate MT-leaf)
+    // Note: enqueued commitments matching recently inserted MT-leaves are skipped by the commitment hashing
+    guard!(
+        !CommitmentQueue::new(commitment_hash_queue)
+            .contains_commitment(&public_inputs.join_split.output_commitment.reduce()),
+        ElusivError::DuplicateOutputCommitment
+    );
+
     enforce_finalize_send_instructions(
         instructions_account,
         public_inputs.join_split.token_id == 0,
         verification_account_index,
+        verification_account_generation,
     )?;
 
     let (commitment_index, mt_index) = minimum_commitment_mt_index(
@@ -576,10 +1050,13 @@ pub fn finalize_verification_send(
         storage_account.get_next_commitment_ptr(),
         CommitmentQueue::new(commitment_hash_queue).len(),
     );
-    guard!(
-        data.total_amount == public_inputs.join_split.total_amount(),
-        ElusivError::InputsMismatch
-    );
+    if data.total_amount != public_inputs.join_split.total_amount() {
+        // A common client mistake is to pass the `amount` (excluding the fee)
+        if data.total_amount == public_inputs.join_split.amount {
+            return Err(ElusivError::AmountInsteadOfTotalAmount.into());
+        }
+        return Err(ElusivError::InputsMismatch.into());
+    }
     guard!(
         data.token_id == public_inputs.join_split.token_id,
         ElusivError::InputsMismatch
@@ -593,7 +1070,13 @@ pub fn finalize_verification_send(
     verification_account.set_state(&VerificationState::InsertNullifiers);
     verification_account.set_instruction(&0);
 
-    Ok(())
+    FinalizeSendEvent {
+        identifier: identifier_account.key.to_bytes(),
+        commitment: public_inputs.join_split.output_commitment.reduce(),
+        encrypted_owner: data.encrypted_owner,
+        memo_program_id,
+    }
+    .log()
 }
 
 pub fn finalize_verification_insert_nullifier(
@@ -601,8 +1084,14 @@ pub fn finalize_verification_insert_nullifier(
     nullifier_account: &mut NullifierAccount,
 
     _verification_account_index: u8,
+    _verification_account_generation: u32,
 ) -> ProgramResult {
-    // TODO: Handle the case in which a duplicate verification has failed (funds flow to fee-collector)
+    // A lost nullifier race finalizes the verification at the first insertion, the remaining insertions (of the same tx) are no-ops
+    if verification_account.get_state() == VerificationState::Finalized
+        && verification_account.get_is_verified() == ElusivOption::Some(false)
+    {
+        return Ok(());
+    }
 
     guard!(
         verification_account.get_state() == VerificationState::InsertNullifiers,
@@ -610,21 +1099,28 @@ pub fn finalize_verification_insert_nullifier(
     );
 
     let request = verification_account.get_request();
-    let public_inputs = match request {
-        ProofRequest::Send(public_inputs) => public_inputs,
-        _ => return Err(ElusivError::FeatureNotAvailable.into()),
-    };
+    let join_split = proof_request!(&request, public_inputs, public_inputs.join_split_inputs());
 
     let input_commitment_index = verification_account.get_instruction() as usize;
-    if input_commitment_index < public_inputs.join_split.input_commitments.len() {
+
+    // All nullifier-hashes are checked before the first insertion, so that a lost race does not insert any of them
+    // - the verification is treated as invalid, so `finalize_verification_transfer_*` routes the funds to the fee-collector
+    if input_commitment_index == 0
+        && is_nullifier_race_lost(
+            verification_account,
+            nullifier_account,
+            &join_split.nullifier_hashes(),
+        )?
+    {
+        verification_account.set_is_verified(&ElusivOption::Some(false));
+        verification_account.set_state(&VerificationState::Finalized);
+        return Ok(());
+    }
+
+    if input_commitment_index < join_split.input_commitments.len() {
         // Insert nullifier hashes
         let mut tree_index = 0;
-        for (index, input_commitment) in public_inputs
-            .join_split
-            .input_commitments
-            .iter()
-            .enumerate()
-        {
+        for (index, input_commitment) in join_split.input_commitments.iter().enumerate() {
             let tree_index = match input_commitment.root {
                 Some(_) => {
                     let t = tree_index;
@@ -652,7 +1148,7 @@ pub fn finalize_verification_insert_nullifier(
         nullifier_account.move_nullifier_hashes_to_next_account()?;
     }
 
-    if input_commitment_index >= public_inputs.join_split.input_commitments.len() - 1
+    if input_commitment_index >= join_split.input_commitments.len() - 1
         && nullifier_account.is_moved_nullifier_empty()
     {
         verification_account.set_state(&VerificationState::Finalized);
@@ -661,6 +1157,29 @@ pub fn finalize_verification_insert_nullifier(
     Ok(())
 }
 
+/// Returns `true` if a concurrent verification (using `skip_nullifier_pda`) has already inserted one of the `nullifier_hashes`
+///
+/// # Notes
+///
+/// Without `skip_nullifier_pda` the [`NullifierDuplicateAccount`] prevents concurrent verifications of the same nullifier-hashes.
+fn is_nullifier_race_lost(
+    verification_account: &VerificationAccount,
+    nullifier_account: &NullifierAccount,
+    nullifier_hashes: &[RawU256],
+) -> Result<bool, ProgramError> {
+    if !verification_account.get_other_data().skip_nullifier_pda {
+        return Ok(false);
+    }
+
+    for nullifier_hash in nullifier_hashes {
+        if !nullifier_account.can_insert_nullifier_hash(nullifier_hash.reduce())? {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn finalize_verification_transfer_lamports<'a>(
     original_fee_payer: &AccountInfo<'a>,
@@ -673,9 +1192,17 @@ pub fn finalize_verification_transfer_lamports<'a>(
     verification_account_info: &AccountInfo<'a>,
     nullifier_duplicate_account: &AccountInfo<'a>,
     instructions_account: &AccountInfo,
+    governor: &GovernorAccount,
+    rent_recipient: &AccountInfo<'a>,
+    network_fee_account: &AccountInfo,
+    verification_index_account: &AccountInfo,
 
-    _verification_account_index: u8,
+    verification_account_index: u8,
+    verification_account_generation: u32,
 ) -> ProgramResult {
+    cu_checkpoint!("finalize_verification_transfer_lamports: start");
+    governor.check_category(Category::Finalize)?;
+
     pda_account!(
         mut verification_account,
         VerificationAccount,
@@ -700,13 +1227,28 @@ pub fn finalize_verification_transfer_lamports<'a>(
             == join_split.create_nullifier_duplicate_pda(nullifier_duplicate_account)?,
         ElusivError::InvalidAccount
     );
+    guard!(
+        join_split.fee_version == data.fee_version,
+        ElusivError::InvalidFeeVersion
+    );
+    cu_checkpoint!("finalize_verification_transfer_lamports: checks");
 
     // Invalid proof
     if let ElusivOption::Some(false) = verification_account.get_is_verified() {
-        // `rent` and `commitment_hash_fee` flow to `fee_collector`
-        close_account(fee_collector, verification_account_info)?;
-        if !data.skip_nullifier_pda {
-            close_account(fee_collector, nullifier_duplicate_account)?;
+        // rent flows to `fee_collector` (or the governor's rent-recipient)
+        close_verification_pdas(
+            verification_rent_beneficiary(governor, rent_recipient, fee_collector)?,
+            verification_account_info,
+            nullifier_duplicate_account,
+            data.skip_nullifier_pda,
+        )?;
+        close_verification_index(
+            verification_index_account,
+            verification_account_index,
+            verification_account_generation,
+        )?;
+        if data.skip_nullifier_pda {
+            release_duplicate_verification(nullifier_duplicate_account)?;
         }
 
         verification_account.set_state(&VerificationState::Closed);
@@ -714,47 +1256,60 @@ pub fn finalize_verification_transfer_lamports<'a>(
         // `pool` transfers `subvention` to `fee_collector` (lamports)
         transfer_lamports_from_pda_checked(pool, fee_collector, data.subvention)?;
 
-        // `pool` transfers `commitment_hash_fee` to `fee_collector` (lamports)
-        transfer_lamports_from_pda_checked(pool, fee_collector, data.commitment_hash_fee.0)?;
-
-        return Ok(());
-    }
+        // `pool` transfers `commitment_hash_fee` and the forfeited `failure_bond` to `fee_collector` (lamports)
+        transfer_lamports_from_pda_checked(
+            pool,
+            fee_collector,
+            (data.commitment_hash_fee + data.failure_bond)?.0,
+        )?;
+
+        return Ok(());
+    }
+
+    // Amount received by `original_fee_payer` as the recipient (self-withdrawal), combined with the reimbursement
+    let mut self_withdrawal_amount = Lamports(0);
 
+    // Merges (zero amount) skip the recipient transfer and go straight to the fee settlement
     if let ProofRequest::Send(public_inputs) = &request {
         if public_inputs.join_split.amount > 0 {
             guard!(
-                recipient.key.to_bytes() == data.recipient_wallet.option().unwrap().skip_mr(),
+                recipient.key.to_bytes() == finalized_recipient_wallet(&data)?,
                 ElusivError::InvalidRecipient
             );
 
-            // Subtract the optional fee from the amount
-            let amount = public_inputs
-                .join_split
-                .amount
-                .checked_sub(public_inputs.join_split.optional_fee.amount)
-                .ok_or(ElusivError::InvalidAmount)?;
+            let amount = net_recipient_amount(&public_inputs.join_split, 0)?;
 
             if public_inputs.solana_pay_transfer {
                 // `pool` transfers `amount` to `original_fee_payer` (lamports)
-                transfer_lamports_from_pda_checked(
-                    pool,
-                    original_fee_payer,
-                    public_inputs.join_split.amount,
-                )?;
+                transfer_lamports_from_pda_checked(pool, original_fee_payer, amount)?;
 
                 // Last instruction: `original_fee_payer` transfers `amount` to `recipient`
-                let instructions_sysvar = DefaultInstructionsSysvar(instructions_account);
-                enforce_instruction(
-                    &instructions_sysvar,
-                    instructions_sysvar.find_instruction_count()? - 1,
-                    &system_instruction::transfer(original_fee_payer.key, recipient.key, amount),
-                    false,
+                enforce_solana_pay_transfer(
+                    &DefaultInstructionsSysvar(instructions_account),
+                    &solana_pay_transfer_instruction(
+                        0,
+                        &system_program::ID,
+                        original_fee_payer.key,
+                        original_fee_payer.key,
+                        recipient.key,
+                        amount,
+                    )?,
                 )?;
+            } else if recipient.key == original_fee_payer.key {
+                self_withdrawal_amount = Lamports(amount);
             } else {
                 // `pool` transfers `amount` to `recipient` (lamports)
+                // - plain lamports transfer, so `recipient` can also be a program-owned account (e.g. a PDA)
                 transfer_lamports_from_pda_checked(pool, recipient, amount)?;
             }
 
+            FinalizeTransferEvent {
+                token_id: 0,
+                net_recipient_amount: amount,
+                solana_pay_transfer: public_inputs.solana_pay_transfer,
+            }
+            .log()?;
+
             // `pool` transfers the optional fee to the corresponding collector
             if public_inputs.join_split.optional_fee.amount > 0 {
                 guard!(
@@ -771,23 +1326,36 @@ pub fn finalize_verification_transfer_lamports<'a>(
         }
     }
 
-    // `pool` transfers `commitment_hash_fee_token (incl. subvention) + proof_verification_fee` to `fee_payer` (lamports)
+    cu_checkpoint!("finalize_verification_transfer_lamports: recipient transfer");
+
+    // `pool` transfers `commitment_hash_fee_token (incl. subvention) + proof_verification_fee`, the `failure_bond` and a self-withdrawal amount to `fee_payer` (lamports)
     transfer_lamports_from_pda_checked(
         pool,
         original_fee_payer,
-        (Lamports(data.commitment_hash_fee_token) + Lamports(data.proof_verification_fee))?.0,
+        (((Lamports(data.commitment_hash_fee_token) + Lamports(data.proof_verification_fee))?
+            + data.failure_bond)?
+            + self_withdrawal_amount)?
+            .0,
     )?;
 
     // `pool` transfers `network_fee` to `fee_collector` (lamports)
     transfer_lamports_from_pda_checked(pool, fee_collector, data.network_fee)?;
+    add_collected_network_fee(network_fee_account, LAMPORTS_TOKEN_ID, data.network_fee)?;
 
     // Close `verification_account` and `nullifier_duplicate_account`
     close_verification_pdas(
-        original_fee_payer,
+        verification_rent_beneficiary(governor, rent_recipient, original_fee_payer)?,
         verification_account_info,
         nullifier_duplicate_account,
         data.skip_nullifier_pda,
     )?;
+    close_verification_index(
+        verification_index_account,
+        verification_account_index,
+        verification_account_generation,
+    )?;
+
+    cu_checkpoint!("finalize_verification_transfer_lamports: fee settlement");
 
     let mut commitment_queue = CommitmentQueue::new(commitment_hash_queue);
     let mut metadata_queue = MetadataQueue::new(metadata_queue);
@@ -799,9 +1367,12 @@ pub fn finalize_verification_transfer_lamports<'a>(
         join_split.metadata,
         join_split.fee_version,
         data.min_batching_rate,
-    )?;
+        request.view_tag(),
+    )?
+    .log()?;
 
     verification_account.set_state(&VerificationState::Closed);
+    cu_checkpoint!("finalize_verification_transfer_lamports: enqueue commitment");
 
     Ok(())
 }
@@ -811,7 +1382,7 @@ pub fn finalize_verification_transfer_token<'a>(
     original_fee_payer: &AccountInfo<'a>,
     original_fee_payer_account: &AccountInfo<'a>,
     recipient: &AccountInfo<'a>, // can be any account for merge/migrate
-    recipient_wallet: &AccountInfo<'a>,
+    recipient_wallet: &AccountInfo<'a>, // the fallback recipient for (non-associated) token account recipients
     pool: &AccountInfo<'a>,
     pool_account: &AccountInfo<'a>,
     fee_collector: &AccountInfo<'a>,
@@ -824,9 +1395,17 @@ pub fn finalize_verification_transfer_token<'a>(
     token_program: &AccountInfo<'a>,
     mint_account: &AccountInfo<'a>,
     instructions_account: &AccountInfo,
+    governor: &GovernorAccount,
+    rent_recipient: &AccountInfo<'a>,
+    network_fee_account: &AccountInfo,
+    verification_index_account: &AccountInfo,
 
-    _verification_account_index: u8,
+    verification_account_index: u8,
+    verification_account_generation: u32,
 ) -> ProgramResult {
+    cu_checkpoint!("finalize_verification_transfer_token: start");
+    governor.check_category(Category::Finalize)?;
+
     pda_account!(
         mut verification_account,
         VerificationAccount,
@@ -835,7 +1414,6 @@ pub fn finalize_verification_transfer_token<'a>(
     let data = verification_account.get_other_data();
     let request = verification_account.get_request();
     let join_split = proof_request!(&request, public_inputs, public_inputs.join_split_inputs());
-    let recipient_address = data.recipient_wallet.option().unwrap().skip_mr();
 
     let token_id = join_split.token_id;
     guard!(token_id > 0, ElusivError::InvalidAccountState);
@@ -857,19 +1435,33 @@ pub fn finalize_verification_transfer_token<'a>(
             == join_split.create_nullifier_duplicate_pda(nullifier_duplicate_account)?,
         ElusivError::InvalidAccount
     );
+    guard!(
+        join_split.fee_version == data.fee_version,
+        ElusivError::InvalidFeeVersion
+    );
 
-    verify_program_token_account(pool, pool_account, token_id)?;
-    verify_program_token_account(fee_collector, fee_collector_account, token_id)?;
+    let mint = Pubkey::new_from_array(data.mint.skip_mr());
+    verify_program_token_account(pool, pool_account, token_id, &mint)?;
+    verify_program_token_account(fee_collector, fee_collector_account, token_id, &mint)?;
+    cu_checkpoint!("finalize_verification_transfer_token: checks");
 
     // Invalid proof
     if let ElusivOption::Some(false) = verification_account.get_is_verified() {
-        // rent flows to `fee_collector`
+        // rent flows to `fee_collector` (or the governor's rent-recipient)
         close_verification_pdas(
-            fee_collector,
+            verification_rent_beneficiary(governor, rent_recipient, fee_collector)?,
             verification_account_info,
             nullifier_duplicate_account,
             data.skip_nullifier_pda,
         )?;
+        close_verification_index(
+            verification_index_account,
+            verification_account_index,
+            verification_account_generation,
+        )?;
+        if data.skip_nullifier_pda {
+            release_duplicate_verification(nullifier_duplicate_account)?;
+        }
 
         verification_account.set_state(&VerificationState::Closed);
 
@@ -884,32 +1476,58 @@ pub fn finalize_verification_transfer_token<'a>(
             None,
         )?;
 
-        // `pool` transfers `commitment_hash_fee` and `associated_token_account_rent` to `fee_collector` (lamports)
+        // `pool` transfers `commitment_hash_fee`, `associated_token_account_rent` and the forfeited `failure_bond` to `fee_collector` (lamports)
         transfer_lamports_from_pda_checked(
             pool,
             fee_collector,
-            (data.commitment_hash_fee + spl_token_account_rent()?)?.0,
+            ((data.commitment_hash_fee + spl_token_account_rent()?)? + data.failure_bond)?.0,
         )?;
 
         return Ok(());
     }
 
     let mut associated_token_account_rent_token = None;
+
+    // Amount received by `original_fee_payer_account` as the recipient (self-withdrawal), combined with the reimbursement
+    let mut self_withdrawal_token = Token::new(token_id, 0);
+
+    // Merges (zero amount) skip the recipient transfer and go straight to the fee settlement
     if let ProofRequest::Send(public_inputs) = &request {
         if public_inputs.join_split.amount > 0 {
+            let recipient_address = finalized_recipient_wallet(&data)?;
             let mut actual_recipient = recipient;
 
-            if !public_inputs.recipient_is_associated_token_account {
+            if public_inputs.recipient_is_program_owned {
+                // Token account owned by `recipient_wallet` (which can be a PDA)
+                guard!(
+                    recipient_wallet.key.to_bytes() == recipient_address,
+                    ElusivError::InvalidRecipient
+                );
+                guard!(
+                    verify_token_account_owner_with_mint(
+                        recipient,
+                        recipient_wallet.key,
+                        token_id,
+                        &mint
+                    )?,
+                    ElusivError::InvalidRecipient
+                );
+            } else if !public_inputs.recipient_is_associated_token_account {
                 // Any token account
                 guard!(
                     recipient.key.to_bytes() == recipient_address,
                     ElusivError::InvalidRecipient
                 );
 
-                // Invalid recipient token account -> funds flow to `fee_collector` instead
-                if verify_token_account(recipient, token_id) != Ok(true) {
-                    actual_recipient = fee_collector_account;
-                }
+                // Invalid recipient token account -> funds flow to the fallback recipient (supplied as `recipient_wallet`) or to `fee_collector` instead
+                actual_recipient = token_recipient(
+                    recipient,
+                    recipient_wallet,
+                    fee_collector_account,
+                    data.fallback_recipient.option(),
+                    token_id,
+                    &mint,
+                )?;
             } else {
                 // Associated-token-account
                 guard!(
@@ -917,46 +1535,41 @@ pub fn finalize_verification_transfer_token<'a>(
                     ElusivError::InvalidRecipient
                 );
                 guard!(
-                    verify_associated_token_account(recipient_wallet.key, recipient.key, token_id)?,
+                    verify_associated_token_account_with_mint(
+                        recipient_wallet.key,
+                        recipient.key,
+                        token_id,
+                        &mint
+                    )?,
                     ElusivError::InvalidRecipient
                 );
 
-                if recipient.lamports() == 0 {
-                    // Check if associated token accounts exists
-                    guard!(
-                        *mint_account.key == elusiv_token(token_id)?.mint,
-                        ElusivError::InvalidAccount
-                    );
-
-                    // We use signer (since it's an available system account) to sign the creation of the associated token account (refunded at the end)
-                    create_associated_token_account(
-                        original_fee_payer,
-                        recipient_wallet,
-                        recipient,
-                        mint_account,
-                        token_id,
-                    )?;
+                // We use signer (since it's an available system account) to sign the creation of the associated token account (refunded at the end)
+                let created = create_associated_token_account(
+                    original_fee_payer,
+                    recipient_wallet,
+                    recipient,
+                    mint_account,
+                    &mint,
+                )?;
 
-                    // `pool` transfers `associated_token_account_rent` to `fee_payer` (token)
-                    associated_token_account_rent_token = Some(data.associated_token_account_rent);
+                // `pool` transfers `associated_token_account_rent` to `fee_payer` (token) only if the account has been created
+                // TODO: can frozen account still receive funds?
+                associated_token_account_rent_token = Some(if created {
+                    data.associated_token_account_rent
                 } else {
-                    // TODO: can frozen account still receive funds?
-                    associated_token_account_rent_token = Some(0);
-                }
+                    0
+                });
             }
 
             let optional_fee = Token::new(token_id, public_inputs.join_split.optional_fee.amount);
 
-            // Subtract the optional fee and optional associated_token_account_rent from the amount
             let token = Token::new(
                 token_id,
-                public_inputs
-                    .join_split
-                    .amount
-                    .checked_sub(associated_token_account_rent_token.unwrap_or(0))
-                    .ok_or(ElusivError::InvalidAmount)?
-                    .checked_sub(public_inputs.join_split.optional_fee.amount)
-                    .ok_or(ElusivError::InvalidAmount)?,
+                net_recipient_amount(
+                    &public_inputs.join_split,
+                    associated_token_account_rent_token.unwrap_or(0),
+                )?,
             );
 
             if public_inputs.solana_pay_transfer {
@@ -972,20 +1585,19 @@ pub fn finalize_verification_transfer_token<'a>(
                 )?;
 
                 // Last instruction: `original_fee_payer_account` transfers `amount` to `recipient` (token)
-                let instructions_sysvar = DefaultInstructionsSysvar(instructions_account);
-                enforce_instruction(
-                    &instructions_sysvar,
-                    instructions_sysvar.find_instruction_count()? - 1,
-                    &spl_token::instruction::transfer(
+                enforce_solana_pay_transfer(
+                    &DefaultInstructionsSysvar(instructions_account),
+                    &solana_pay_transfer_instruction(
+                        token_id,
                         token_program.key,
+                        original_fee_payer.key,
                         original_fee_payer_account.key,
                         actual_recipient.key,
-                        original_fee_payer.key,
-                        &[original_fee_payer.key],
                         token.amount(),
                     )?,
-                    false,
                 )?;
+            } else if actual_recipient.key == original_fee_payer_account.key {
+                self_withdrawal_token = token;
             } else {
                 // `pool` transfers `amount` to `recipient` (token)
                 transfer_token_from_pda::<PoolAccount>(
@@ -999,6 +1611,13 @@ pub fn finalize_verification_transfer_token<'a>(
                 )?;
             }
 
+            FinalizeTransferEvent {
+                token_id,
+                net_recipient_amount: token.amount(),
+                solana_pay_transfer: public_inputs.solana_pay_transfer,
+            }
+            .log()?;
+
             // `pool` transfers the optional fee to the corresponding collector (token)
             if optional_fee.amount() > 0 {
                 guard!(
@@ -1019,15 +1638,18 @@ pub fn finalize_verification_transfer_token<'a>(
         }
     }
 
-    // `pool` transfers `commitment_hash_fee_token (incl. subvention) + proof_verification_fee + associated_token_account_rent_token?` to `fee_payer` (token)
+    cu_checkpoint!("finalize_verification_transfer_token: recipient transfer");
+
+    // `pool` transfers `commitment_hash_fee_token (incl. subvention) + proof_verification_fee + associated_token_account_rent_token?` and a self-withdrawal amount to `fee_payer` (token)
     transfer_token_from_pda::<PoolAccount>(
         pool,
         pool_account,
         original_fee_payer_account,
         token_program,
-        ((Token::new(token_id, data.commitment_hash_fee_token)
+        (((Token::new(token_id, data.commitment_hash_fee_token)
             + Token::new(token_id, data.proof_verification_fee))?
-            + Token::new(token_id, associated_token_account_rent_token.unwrap_or(0)))?,
+            + Token::new(token_id, associated_token_account_rent_token.unwrap_or(0)))?
+            + self_withdrawal_token)?,
         None,
         None,
     )?;
@@ -1042,19 +1664,30 @@ pub fn finalize_verification_transfer_token<'a>(
         None,
         None,
     )?;
+    add_collected_network_fee(network_fee_account, token_id, data.network_fee)?;
 
     // Close `verification_account` and `nullifier_duplicate_account`
     close_verification_pdas(
-        original_fee_payer,
+        verification_rent_beneficiary(governor, rent_recipient, original_fee_payer)?,
         verification_account_info,
         nullifier_duplicate_account,
         data.skip_nullifier_pda,
     )?;
+    close_verification_index(
+        verification_index_account,
+        verification_account_index,
+        verification_account_generation,
+    )?;
 
     if associated_token_account_rent_token.is_some() {
         transfer_lamports_from_pda_checked(pool, original_fee_payer, spl_token_account_rent()?.0)?;
     }
 
+    // `pool` refunds the `failure_bond` to `fee_payer` (lamports)
+    transfer_lamports_from_pda_checked(pool, original_fee_payer, data.failure_bond.0)?;
+
+    cu_checkpoint!("finalize_verification_transfer_token: fee settlement");
+
     let mut commitment_queue = CommitmentQueue::new(commitment_hash_queue);
     let mut metadata_queue = MetadataQueue::new(metadata_queue);
 
@@ -1065,811 +1698,4699 @@ pub fn finalize_verification_transfer_token<'a>(
         join_split.metadata,
         join_split.fee_version,
         data.min_batching_rate,
-    )?;
+        request.view_tag(),
+    )?
+    .log()?;
 
     verification_account.set_state(&VerificationState::Closed);
+    cu_checkpoint!("finalize_verification_transfer_token: enqueue commitment");
 
     Ok(())
 }
 
-fn close_verification_pdas<'a>(
-    beneficiary: &AccountInfo<'a>,
-    verification_account: &AccountInfo<'a>,
-    nullifier_duplicate_account: &AccountInfo<'a>,
-    skipped_nullifier_pda: bool,
-) -> ProgramResult {
-    close_account(beneficiary, verification_account)?;
-    if !skipped_nullifier_pda {
-        close_account(beneficiary, nullifier_duplicate_account)?;
+/// Returns the account receiving the amount of a send to a (non-associated) token account
+///
+/// # Note
+///
+/// An invalid `recipient` token account is replaced by the `fallback_recipient` (if it is a valid token account) or else by `fee_collector_account`.
+fn token_recipient<'a, 'b>(
+    recipient: &'b AccountInfo<'a>,
+    fallback_recipient_account: &'b AccountInfo<'a>,
+    fee_collector_account: &'b AccountInfo<'a>,
+    fallback_recipient: Option<RawU256>,
+    token_id: u16,
+    mint: &Pubkey,
+) -> Result<&'b AccountInfo<'a>, ProgramError> {
+    if verify_token_account_with_mint(recipient, token_id, mint) == Ok(true) {
+        return Ok(recipient);
     }
 
-    Ok(())
-}
+    if let Some(fallback_recipient) = fallback_recipient {
+        guard!(
+            fallback_recipient_account.key.to_bytes() == fallback_recipient.skip_mr(),
+            ElusivError::InvalidRecipient
+        );
 
-const TIMESTAMP_BITS_PRUNING: usize = 5;
-pub fn is_timestamp_valid(asserted_time: u64, timestamp: u64) -> bool {
-    (asserted_time >> TIMESTAMP_BITS_PRUNING) <= (timestamp >> TIMESTAMP_BITS_PRUNING)
-}
+        if verify_token_account_with_mint(fallback_recipient_account, token_id, mint) == Ok(true) {
+            return Ok(fallback_recipient_account);
+        }
+    }
 
-fn is_vec_duplicate_free<T: std::cmp::Eq + std::hash::Hash + std::clone::Clone>(
-    v: &Vec<T>,
-) -> bool {
-    (*v).clone().drain(..).collect::<HashSet<T>>().len() == v.len()
+    Ok(fee_collector_account)
 }
 
-/// Computes the minimum index of a commitment and it's corresponding MT-index
-fn minimum_commitment_mt_index(
-    mt_index: u32,
-    commitment_count: u32,
-    commitment_queue_len: u32,
-) -> (u32, u32) {
-    let count = usize_as_u32_safe(MT_COMMITMENT_COUNT);
-    let index = (commitment_count + commitment_queue_len) % count;
-    let mt_offset = (commitment_count + commitment_queue_len) / count;
-    (index, mt_index + mt_offset)
+/// Default duration (in seconds) after which an abandoned proof verification can be closed
+pub const VERIFICATION_TIMEOUT: u64 = 60 * 60 * 24;
+
+/// Closes a [`VerificationAccount`] whose proof has been setup but never been verified
+///
+/// # Notes
+///
+/// Can be called by anyone after the verification's timeout (chosen in [`init_verification`]) has passed since [`init_verification_proof`].
+///
+/// - `original_fee_payer` receives the rent and the `commitment_hash_fee` (+ `associated_token_account_rent`),
+/// - `fee_collector` receives the `subvention` and the `failure_bond`.
+#[allow(clippy::too_many_arguments)]
+pub fn close_timed_out_verification<'a>(
+    original_fee_payer: &AccountInfo<'a>,
+    pool: &AccountInfo<'a>,
+    pool_account: &AccountInfo<'a>,
+    fee_collector: &AccountInfo<'a>,
+    fee_collector_account: &AccountInfo<'a>,
+    verification_account_info: &AccountInfo<'a>,
+    verification_index_account: &AccountInfo,
+    nullifier_duplicate_account: &AccountInfo<'a>,
+    buffer: &mut CommitmentBufferAccount,
+    token_program: &AccountInfo<'a>,
+
+    verification_account_index: u8,
+    verification_account_generation: u32,
+) -> ProgramResult {
+    close_verification(
+        original_fee_payer,
+        original_fee_payer,
+        pool,
+        pool_account,
+        fee_collector,
+        fee_collector_account,
+        verification_account_info,
+        verification_index_account,
+        nullifier_duplicate_account,
+        buffer,
+        token_program,
+        verification_account_index,
+        verification_account_generation,
+        current_timestamp()?,
+    )
 }
 
-fn check_join_split_public_inputs(
-    public_inputs: &JoinSplitPublicInputs,
-    storage_account: &StorageAccount,
-    nullifier_accounts: [&NullifierAccount; MAX_MT_COUNT],
-    tree_indices: &[u32; MAX_MT_COUNT],
+/// Closes a timed-out [`VerificationAccount`] in the [`VerificationState::ProofSetup`] state and refunds its fees
+///
+/// # Note
+///
+/// `rent_beneficiary` receives the rent of the verification and nullifier-duplicate PDAs.
+#[allow(clippy::too_many_arguments)]
+fn close_verification<'a>(
+    rent_beneficiary: &AccountInfo<'a>,
+    original_fee_payer: &AccountInfo<'a>,
+    pool: &AccountInfo<'a>,
+    pool_account: &AccountInfo<'a>,
+    fee_collector: &AccountInfo<'a>,
+    fee_collector_account: &AccountInfo<'a>,
+    verification_account_info: &AccountInfo<'a>,
+    verification_index_account: &AccountInfo,
+    nullifier_duplicate_account: &AccountInfo<'a>,
+    buffer: &mut CommitmentBufferAccount,
+    token_program: &AccountInfo<'a>,
+    verification_account_index: u8,
+    verification_account_generation: u32,
+    timestamp: u64,
 ) -> ProgramResult {
-    // Check that the resulting commitment is not the zero-commitment
-    guard!(
-        public_inputs.output_commitment.skip_mr() != ZERO_COMMITMENT_RAW,
-        ElusivError::InvalidPublicInputs
+    pda_account!(
+        mut verification_account,
+        VerificationAccount,
+        verification_account_info
     );
+    let data = verification_account.get_other_data();
+    let request = verification_account.get_request();
+    let join_split = proof_request!(&request, public_inputs, public_inputs.join_split_inputs());
+    let token_id = join_split.token_id;
+
     guard!(
-        public_inputs.input_commitments[0].root.is_some(),
-        ElusivError::InvalidPublicInputs
+        verification_account.get_state() == VerificationState::ProofSetup,
+        ElusivError::InvalidAccountState
     );
     guard!(
-        public_inputs.input_commitments.len() <= JOIN_SPLIT_MAX_N_ARITY,
-        ElusivError::InvalidPublicInputs
+        verification_account.get_is_verified().option().is_none(),
+        ElusivError::ComputationIsAlreadyFinished
     );
     guard!(
-        verify_recent_commitment_index(public_inputs.recent_commitment_index, storage_account),
-        ElusivError::InvalidRecentCommitmentIndex
+        original_fee_payer.key.to_bytes() == data.fee_payer.skip_mr(),
+        ElusivError::InvalidAccount
     );
-
-    // Verify the optional fee
     guard!(
-        public_inputs.amount >= public_inputs.optional_fee.amount,
-        ElusivError::InvalidAmount
+        *nullifier_duplicate_account.key
+            == join_split.create_nullifier_duplicate_pda(nullifier_duplicate_account)?,
+        ElusivError::InvalidAccount
     );
 
-    let active_tree_index = storage_account.get_trees_count();
+    let timeout = verification_account
+        .get_proof_setup_timestamp()
+        .checked_add(verification_account.get_timeout())
+        .ok_or(ElusivError::VerificationNotTimedOut)?;
+    guard!(timestamp >= timeout, ElusivError::VerificationNotTimedOut);
 
-    let mut roots = Vec::new();
-    let mut tree_index = Vec::with_capacity(public_inputs.input_commitments.len());
-    let mut nullifier_hashes = Vec::new();
-    for InputCommitment {
-        root,
-        nullifier_hash,
-    } in &public_inputs.input_commitments
-    {
-        match root {
-            Some(root) => {
-                let index = roots.len();
-                tree_index.push(index);
-                roots.push(root);
-                nullifier_hashes.push(vec![nullifier_hash]);
-
-                // Verify that root is valid
-                // Note: roots are stored in mr-form
-                if tree_indices[index] == active_tree_index {
-                    // Active tree
-                    guard!(
-                        storage_account.is_root_valid(&root.reduce()),
-                        ElusivError::InvalidMerkleRoot
-                    );
-                } else {
-                    // Closed tree
-                    guard!(
-                        root.reduce() == nullifier_accounts[index].get_root(),
-                        ElusivError::InvalidMerkleRoot
-                    );
-                }
-            }
-            None => {
-                tree_index.push(0);
-                nullifier_hashes[0].push(nullifier_hash);
-            }
-        }
-    }
-    guard!(
-        !roots.is_empty() && roots.len() <= MAX_MT_COUNT,
-        ElusivError::InvalidPublicInputs
-    );
-    guard!(
-        tree_indices.len() >= roots.len(),
-        ElusivError::InvalidPublicInputs
-    );
+    let mint = Pubkey::new_from_array(data.mint.skip_mr());
+    verify_program_token_account(pool, pool_account, token_id, &mint)?;
+    verify_program_token_account(fee_collector, fee_collector_account, token_id, &mint)?;
 
-    // All supplied MTs (storage/nullifier-accounts) are pairwise different
-    if roots.len() > 1 {
-        guard!(
-            is_vec_duplicate_free(&tree_indices.to_vec()),
-            ElusivError::InvalidInstructionData
-        );
+    // Attempt to remove the commitment from the commitment-buffer
+    if let Some(index) = buffer.find_position(&join_split.output_commitment.reduce()) {
+        buffer.set_value(index, &[0; 32]);
     }
 
-    for (i, input_commitment) in public_inputs.input_commitments.iter().enumerate() {
-        // No duplicate nullifier-hashes for the same MT
-        for j in 0..public_inputs.input_commitments.len() {
-            if i == j {
-                continue;
-            }
-
-            if input_commitment.nullifier_hash == public_inputs.input_commitments[j].nullifier_hash
-            {
-                guard!(
-                    tree_index[i] != tree_index[j],
-                    ElusivError::InvalidPublicInputs
-                );
-            }
-        }
+    refund_verification_fees(
+        original_fee_payer,
+        pool,
+        pool_account,
+        fee_collector,
+        fee_collector_account,
+        token_program,
+        token_id,
+        &request,
+        &data,
+    )?;
 
-        // Check that `nullifier_hash` is new
-        // Note: nullifier-hashes are stored in mr-form
-        guard!(
-            nullifier_accounts[tree_index[i]]
-                .can_insert_nullifier_hash(input_commitment.nullifier_hash.reduce())?,
-            ElusivError::CouldNotInsertNullifier
-        );
+    // Close `verification_account` and `nullifier_duplicate_account`
+    close_verification_pdas(
+        rent_beneficiary,
+        verification_account_info,
+        nullifier_duplicate_account,
+        data.skip_nullifier_pda,
+    )?;
+    close_verification_index(
+        verification_index_account,
+        verification_account_index,
+        verification_account_generation,
+    )?;
+    if data.skip_nullifier_pda {
+        release_duplicate_verification(nullifier_duplicate_account)?;
     }
 
+    verification_account.set_state(&VerificationState::Closed);
+
     Ok(())
 }
 
-fn enforce_finalize_send_instructions(
-    instructions_account: &AccountInfo,
-    uses_lamports: bool,
+/// Closes a [`VerificationAccount`] whose computation has stalled (e.g. after a crashed warden) to release its nullifier-duplicate PDA
+///
+/// # Notes
+///
+/// Can be called by anyone after the verification's timeout (same as for [`close_timed_out_verification`]).
+///
+/// - `caller` receives the rent as a bounty,
+/// - `original_fee_payer` receives the `commitment_hash_fee` (+ `associated_token_account_rent`),
+/// - `fee_collector` receives the `subvention` and the `failure_bond`.
+#[allow(clippy::too_many_arguments)]
+pub fn cleanup_stalled_verification<'a>(
+    caller: &AccountInfo<'a>,
+    original_fee_payer: &AccountInfo<'a>,
+    pool: &AccountInfo<'a>,
+    pool_account: &AccountInfo<'a>,
+    fee_collector: &AccountInfo<'a>,
+    fee_collector_account: &AccountInfo<'a>,
+    verification_account_info: &AccountInfo<'a>,
+    verification_index_account: &AccountInfo,
+    nullifier_duplicate_account: &AccountInfo<'a>,
+    buffer: &mut CommitmentBufferAccount,
+    token_program: &AccountInfo<'a>,
+
     verification_account_index: u8,
+    verification_account_generation: u32,
 ) -> ProgramResult {
-    if cfg!(test) {
-        return Ok(());
-    }
-
-    enforce_finalize_send_instructions_inner(
-        &DefaultInstructionsSysvar(instructions_account),
-        uses_lamports,
+    close_verification(
+        caller,
+        original_fee_payer,
+        pool,
+        pool_account,
+        fee_collector,
+        fee_collector_account,
+        verification_account_info,
+        verification_index_account,
+        nullifier_duplicate_account,
+        buffer,
+        token_program,
         verification_account_index,
+        verification_account_generation,
+        current_timestamp()?,
     )
 }
 
-fn verify_finalize_send_instruction<I: InstructionsSysvar>(
-    ix_index: usize,
-    instruction_sysvar: &I,
-    expected_variant_index: u8,
+/// Aborts a proof verification before it's proof has been setup (e.g. after detecting invalid public inputs off-chain)
+///
+/// # Notes
+///
+/// Can only be called by the `original_fee_payer` in the [`VerificationState::None`] and [`VerificationState::FeeTransferred`] states.
+///
+/// - `original_fee_payer` receives the rent and (if already transferred) the `commitment_hash_fee` (+ `associated_token_account_rent`),
+/// - `fee_collector` receives the `subvention` and the `failure_bond`.
+#[allow(clippy::too_many_arguments)]
+pub fn abort_verification<'a>(
+    original_fee_payer: &AccountInfo<'a>,
+    pool: &AccountInfo<'a>,
+    pool_account: &AccountInfo<'a>,
+    fee_collector: &AccountInfo<'a>,
+    fee_collector_account: &AccountInfo<'a>,
+    verification_account_info: &AccountInfo<'a>,
+    verification_index_account: &AccountInfo,
+    nullifier_duplicate_account: &AccountInfo<'a>,
+    buffer: &mut CommitmentBufferAccount,
+    token_program: &AccountInfo<'a>,
+
     verification_account_index: u8,
+    verification_account_generation: u32,
 ) -> ProgramResult {
-    let ix = instruction_sysvar.instruction_at_index(ix_index)?;
+    pda_account!(
+        mut verification_account,
+        VerificationAccount,
+        verification_account_info
+    );
+    let data = verification_account.get_other_data();
+    let request = verification_account.get_request();
+    let join_split = proof_request!(&request, public_inputs, public_inputs.join_split_inputs());
+    let state = verification_account.get_state();
 
     guard!(
-        ix.program_id == crate::id(),
-        ProgramError::IncorrectProgramId
+        matches!(
+            state,
+            VerificationState::None | VerificationState::FeeTransferred
+        ),
+        ElusivError::InvalidAccountState
     );
     guard!(
-        ix.data[0] == expected_variant_index,
-        ElusivError::InvalidOtherInstruction
+        original_fee_payer.key.to_bytes() == data.fee_payer.skip_mr(),
+        ElusivError::InvalidAccount
     );
     guard!(
-        ix.data[1] == verification_account_index,
-        ElusivError::InvalidOtherInstruction
+        *nullifier_duplicate_account.key
+            == join_split.create_nullifier_duplicate_pda(nullifier_duplicate_account)?,
+        ElusivError::InvalidAccount
     );
 
+    // Attempt to remove the commitment from the commitment-buffer
+    if let Some(index) = buffer.find_position(&join_split.output_commitment.reduce()) {
+        buffer.set_value(index, &[0; 32]);
+    }
+
+    if state == VerificationState::FeeTransferred {
+        let mint = Pubkey::new_from_array(data.mint.skip_mr());
+        verify_program_token_account(pool, pool_account, join_split.token_id, &mint)?;
+        verify_program_token_account(
+            fee_collector,
+            fee_collector_account,
+            join_split.token_id,
+            &mint,
+        )?;
+
+        refund_verification_fees(
+            original_fee_payer,
+            pool,
+            pool_account,
+            fee_collector,
+            fee_collector_account,
+            token_program,
+            join_split.token_id,
+            &request,
+            &data,
+        )?;
+    }
+
+    // Close `verification_account` and `nullifier_duplicate_account`
+    close_verification_pdas(
+        original_fee_payer,
+        verification_account_info,
+        nullifier_duplicate_account,
+        data.skip_nullifier_pda,
+    )?;
+    close_verification_index(
+        verification_index_account,
+        verification_account_index,
+        verification_account_generation,
+    )?;
+    if data.skip_nullifier_pda {
+        release_duplicate_verification(nullifier_duplicate_account)?;
+    }
+
+    verification_account.set_state(&VerificationState::Closed);
+
     Ok(())
 }
 
-/// Enforces that the current transaction contains all required finalization instructions in the correct order
-fn enforce_finalize_send_instructions_inner<I: InstructionsSysvar>(
-    instruction_sysvar: &I,
-    uses_lamports: bool,
-    verification_account_index: u8,
+/// Logged by [`log_open_verifications`]
+#[derive(BorshDeserialize, BorshSerialize, PartialEq, Eq, Clone, Debug)]
+pub struct OpenVerifications {
+    pub fee_payer: Pubkey,
+
+    /// The PDA-offset and address of each open [`VerificationAccount`]
+    pub verifications: Vec<(u32, Pubkey)>,
+}
+
+impl OpenVerifications {
+    pub fn log(&self) -> ProgramResult {
+        solana_program::log::sol_log_data(&[&self.try_to_vec()?]);
+        Ok(())
+    }
+}
+
+/// Logs the [`OpenVerifications`] of `fee_payer`
+///
+/// # Note
+///
+/// Allows tooling to find orphaned verifications of a fee payer with a single simulation.
+pub fn log_open_verifications(
+    fee_payer: &AccountInfo,
+    verification_index_account: &VerificationIndexAccount,
 ) -> ProgramResult {
-    let current_ix_index = instruction_sysvar.current_index()? as usize;
+    OpenVerifications {
+        fee_payer: *fee_payer.key,
+        verifications: verification_index_account.open_verifications(fee_payer.key),
+    }
+    .log()
+}
 
-    // Leading [`ElusivInstruction::FinalizeVerificationSendInstruction`]
-    verify_finalize_send_instruction(
-        current_ix_index,
-        instruction_sysvar,
-        ElusivInstruction::FINALIZE_VERIFICATION_SEND_INDEX,
-        verification_account_index,
+/// Returns the fees of an unfinished verification from the `pool`
+#[allow(clippy::too_many_arguments)]
+fn refund_verification_fees<'a>(
+    original_fee_payer: &AccountInfo<'a>,
+    pool: &AccountInfo<'a>,
+    pool_account: &AccountInfo<'a>,
+    fee_collector: &AccountInfo<'a>,
+    fee_collector_account: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    token_id: TokenID,
+    request: &ProofRequest,
+    data: &VerificationAccountData,
+) -> ProgramResult {
+    // `pool` transfers `subvention` to `fee_collector` (token)
+    transfer_token_from_pda::<PoolAccount>(
+        pool,
+        pool_account,
+        fee_collector_account,
+        token_program,
+        Token::new(token_id, data.subvention),
+        None,
+        None,
     )?;
 
-    // Arbitrary number of [`ElusivInstruction::FinalizeVerificationInsertNullifier`]
-    let mut insertion_ix_count = 0;
-    while verify_finalize_send_instruction(
-        current_ix_index + insertion_ix_count + 1,
-        instruction_sysvar,
-        ElusivInstruction::FINALIZE_VERIFICATION_INSERT_NULLIFIER_INDEX,
-        verification_account_index,
-    )
-    .is_ok()
-    {
-        insertion_ix_count += 1;
+    // `pool` transfers `commitment_hash_fee` (+ `associated_token_account_rent`)? to `original_fee_payer` (lamports)
+    let mut associated_token_account_rent = Lamports(0);
+    if let ProofRequest::Send(public_inputs) = request {
+        if public_inputs.recipient_is_associated_token_account {
+            associated_token_account_rent = spl_token_account_rent()?;
+        }
     }
+    transfer_lamports_from_pda_checked(
+        pool,
+        original_fee_payer,
+        (data.commitment_hash_fee + associated_token_account_rent)?.0,
+    )?;
 
-    // Single transfer instruction (either [`ElusivInstruction::FinalizeVerificationTransferLamports`] or [`ElusivInstruction::FinalizeVerificationTransferToken`])
-    let transfer_ix_variant_index = if uses_lamports {
-        ElusivInstruction::FINALIZE_VERIFICATION_TRANSFER_LAMPORTS_INDEX
-    } else {
-        ElusivInstruction::FINALIZE_VERIFICATION_TRANSFER_TOKEN_INDEX
-    };
+    // `pool` transfers the forfeited `failure_bond` to `fee_collector` (lamports)
+    transfer_lamports_from_pda_checked(pool, fee_collector, data.failure_bond.0)
+}
 
-    verify_finalize_send_instruction(
-        current_ix_index + insertion_ix_count + 1,
-        instruction_sysvar,
-        transfer_ix_variant_index,
-        verification_account_index,
-    )?;
+/// Registers an additional verification of the nullifier-hashes of an existing [`NullifierDuplicateAccount`]
+///
+/// # Note
+///
+/// The account can only be reused once `grace_period` slots have passed since its last use.
+fn register_duplicate_verification(
+    nullifier_duplicate_account: &AccountInfo,
+    max_duplicate_verifications: u8,
+    grace_period: u64,
+    current_slot: u64,
+) -> ProgramResult {
+    pda_account!(
+        mut nullifier_duplicate,
+        NullifierDuplicateAccount,
+        nullifier_duplicate_account
+    );
+
+    let duplicate_count = nullifier_duplicate.get_duplicate_count();
+    guard!(
+        duplicate_count < max_duplicate_verifications,
+        ElusivError::TooManyDuplicateVerifications
+    );
+    guard!(
+        current_slot.saturating_sub(nullifier_duplicate.get_last_use_slot()) >= grace_period,
+        ElusivError::DuplicateVerificationGracePeriod
+    );
+    nullifier_duplicate.set_duplicate_count(&(duplicate_count + 1));
+    nullifier_duplicate.set_last_use_slot(&current_slot);
 
     Ok(())
 }
 
-fn enforce_instruction<I: InstructionsSysvar>(
-    instruction_sysvar: &I,
-    index: usize,
-    expected: &Instruction,
-    ignore_data: bool,
-) -> Result<Instruction, ProgramError> {
-    let instruction = instruction_sysvar.instruction_at_index(index)?;
+/// Releases an aborted additional verification (if the [`NullifierDuplicateAccount`] has not been closed yet)
+fn release_duplicate_verification(nullifier_duplicate_account: &AccountInfo) -> ProgramResult {
+    if nullifier_duplicate_account.lamports() == 0 {
+        return Ok(());
+    }
 
-    guard!(
-        instruction.program_id == expected.program_id,
-        ProgramError::IncorrectProgramId
+    pda_account!(
+        mut nullifier_duplicate,
+        NullifierDuplicateAccount,
+        nullifier_duplicate_account
     );
+    nullifier_duplicate
+        .set_duplicate_count(&nullifier_duplicate.get_duplicate_count().saturating_sub(1));
 
-    if !ignore_data {
+    Ok(())
+}
+
+/// Adds `network_fee` to the [`NetworkFeeAccount`] of `token_id` (skipped as long as it has not been opened)
+fn add_collected_network_fee(
+    network_fee_account: &AccountInfo,
+    token_id: TokenID,
+    network_fee: u64,
+) -> ProgramResult {
+    if network_fee_account.data_is_empty() {
         guard!(
-            instruction.data == expected.data,
-            ElusivError::InvalidOtherInstruction
+            *network_fee_account.key == NetworkFeeAccount::find(Some(token_id.into())).0,
+            ElusivError::InvalidAccount
         );
+        return Ok(());
     }
 
-    for (i, account) in expected.accounts.iter().enumerate() {
-        guard!(
-            instruction.accounts[i].pubkey == account.pubkey,
-            ElusivError::InvalidOtherInstruction
-        );
+    NetworkFeeAccount::verify_account(network_fee_account, Some(token_id.into()))?;
 
-        if account.is_signer {
-            guard!(
-                instruction.accounts[i].is_signer,
-                ElusivError::InvalidOtherInstruction
-            );
-        }
+    pda_account!(mut accumulator, NetworkFeeAccount, network_fee_account);
+    accumulator.add_collected_network_fee(network_fee)
+}
 
-        if account.is_writable {
-            guard!(
-                instruction.accounts[i].is_writable,
-                ElusivError::InvalidOtherInstruction
-            );
+/// Returns the account receiving the rent of the closed verification PDAs
+///
+/// # Note
+///
+/// If the [`GovernorAccount`] specifies a `rent_recipient`, `rent_recipient` has to match it, otherwise `default_beneficiary` is used.
+fn verification_rent_beneficiary<'b, 'a>(
+    governor: &GovernorAccount,
+    rent_recipient: &'b AccountInfo<'a>,
+    default_beneficiary: &'b AccountInfo<'a>,
+) -> Result<&'b AccountInfo<'a>, ProgramError> {
+    match governor.get_rent_recipient().option() {
+        Some(pubkey) => {
+            guard!(*rent_recipient.key == pubkey, ElusivError::InvalidAccount);
+            Ok(rent_recipient)
         }
+        None => Ok(default_beneficiary),
     }
-
-    Ok(instruction)
 }
 
-fn memo_instruction(memo: &[u8]) -> Instruction {
-    Instruction {
-        program_id: SPL_MEMO_PROGRAM_ID,
-        accounts: Vec::new(),
-        data: memo.to_vec(),
+fn close_verification_pdas<'a>(
+    beneficiary: &AccountInfo<'a>,
+    verification_account: &AccountInfo<'a>,
+    nullifier_duplicate_account: &AccountInfo<'a>,
+    skipped_nullifier_pda: bool,
+) -> ProgramResult {
+    close_account(beneficiary, verification_account)?;
+    if !skipped_nullifier_pda {
+        close_account(beneficiary, nullifier_duplicate_account)?;
     }
+
+    Ok(())
 }
 
-fn get_memo_from_instructions<I: InstructionsSysvar>(
-    instruction_sysvar: &I,
-    solana_pay_transfer: bool,
-) -> Result<Vec<u8>, ProgramError> {
-    let instruction_count = instruction_sysvar.find_instruction_count()?;
-    let memo_index = if solana_pay_transfer {
-        instruction_count - 2
-    } else {
-        instruction_count - 1
-    };
+/// Removes a closed verification from the fee payer's open verifications
+///
+/// # Note
+///
+/// The [`VerificationIndexAccount`] is skipped if it does not exist.
+fn close_verification_index(
+    verification_index_account: &AccountInfo,
+    verification_account_index: u8,
+    verification_account_generation: u32,
+) -> ProgramResult {
+    if verification_index_account.data_is_empty() {
+        return Ok(());
+    }
 
-    let instruction =
-        enforce_instruction(instruction_sysvar, memo_index, &memo_instruction(&[]), true)?;
+    pda_account!(
+        mut verification_index,
+        VerificationIndexAccount,
+        verification_index_account
+    );
+    verification_index.close_index(verification_account_index, verification_account_generation);
 
-    Ok(instruction.data)
+    Ok(())
 }
 
-pub(crate) fn mutate<T: Clone, F>(v: &T, f: F) -> T
-where
-    F: Fn(&mut T),
-{
-    let mut i = v.clone();
-    f(&mut i);
-    i
+/// Returns `true` if the `asserted_time` is neither in the future nor older than the previous pruned period of `timestamp`
+///
+/// # Note
+///
+/// A period spans `2^pruning_bits` seconds, with `pruning_bits` being [`GovernorAccount::timestamp_pruning_bits`].
+pub fn is_timestamp_valid(asserted_time: u64, timestamp: u64, pruning_bits: u8) -> bool {
+    let asserted_time = asserted_time >> pruning_bits;
+    let timestamp = timestamp >> pruning_bits;
+
+    asserted_time <= timestamp && timestamp - asserted_time <= 1
 }
 
-#[cfg(test)]
-macro_rules! vkey_account {
-    ($id: ident, $vkey: ident) => {
-        let mut source = <$vkey as crate::proof::vkey::VerifyingKeyInfo>::verifying_key_source();
-        source.insert(0, 0);
+fn is_vec_duplicate_free<T: std::cmp::Eq + std::hash::Hash + std::clone::Clone>(
+    v: &Vec<T>,
+) -> bool {
+    (*v).clone().drain(..).collect::<HashSet<T>>().len() == v.len()
+}
 
-        crate::macros::account_info!(
-            vkey_account,
-            solana_program::pubkey::Pubkey::new_unique(),
-            source
-        );
+/// Computes the minimum index of a commitment and it's corresponding MT-index
+fn minimum_commitment_mt_index(
+    mt_index: u32,
+    commitment_count: u32,
+    commitment_queue_len: u32,
+) -> (u32, u32) {
+    let count = usize_as_u32_safe(MT_COMMITMENT_COUNT);
+    let index = (commitment_count + commitment_queue_len) % count;
+    let mt_offset = (commitment_count + commitment_queue_len) / count;
+    (index, mt_index + mt_offset)
+}
 
-        let mut source = <$vkey as crate::proof::vkey::VerifyingKeyInfo>::verifying_key_source();
-        source.insert(0, 0);
+/// Predicts the position `(mt_index, commitment_index)` of the next enqueued commitment (as validated by [`finalize_verification_send`])
+///
+/// # Arguments
+///
+/// - `trees_count` and `next_commitment_ptr` of the [`StorageAccount`]
+/// - `commitment_queue_len`: the length of the [`CommitmentQueue`]
+#[cfg(feature = "elusiv-client")]
+pub fn predicted_commitment_position(
+    trees_count: u32,
+    next_commitment_ptr: u32,
+    commitment_queue_len: u32,
+) -> (u32, u32) {
+    let (commitment_index, mt_index) =
+        minimum_commitment_mt_index(trees_count, next_commitment_ptr, commitment_queue_len);
+    (mt_index, commitment_index)
+}
 
-        crate::macros::account_info!(
-            vkey_account1,
-            solana_program::pubkey::Pubkey::new_unique(),
-            source
-        );
+fn check_join_split_public_inputs(
+    public_inputs: &JoinSplitPublicInputs,
+    storage_account: &StorageAccount,
+    nullifier_accounts: [&NullifierAccount; MAX_MT_COUNT],
+    tree_indices: &[u32; MAX_MT_COUNT],
+    genesis_params: &GenesisParams,
+) -> ProgramResult {
+    // Check that the resulting commitment is not the zero-commitment
+    guard!(
+        public_inputs.output_commitment.skip_mr() != genesis_params.zero_commitment,
+        ElusivError::InvalidPublicInputs
+    );
+    guard!(
+        public_inputs.input_commitments[0].root.is_some(),
+        ElusivError::InvalidPublicInputs
+    );
+    guard!(
+        public_inputs.input_commitments.len() <= JOIN_SPLIT_MAX_N_ARITY,
+        ElusivError::InvalidPublicInputs
+    );
+    guard!(
+        verify_recent_commitment_index(public_inputs.recent_commitment_index, storage_account),
+        ElusivError::InvalidRecentCommitmentIndex
+    );
 
-        let mut data = vec![0; <VKeyAccount as elusiv_types::accounts::SizedAccount>::SIZE];
-        let mut $id =
-            <VKeyAccount as elusiv_types::accounts::ParentAccount>::new_with_child_accounts(
-                &mut data,
-                vec![Some(&vkey_account), Some(&vkey_account1)],
-            )
-            .unwrap();
+    // Verify the optional fee
+    guard!(
+        public_inputs.amount >= public_inputs.optional_fee.amount,
+        ElusivError::InvalidAmount
+    );
 
-        $id.set_public_inputs_count(
-            &<$vkey as crate::proof::vkey::VerifyingKeyInfo>::PUBLIC_INPUTS_COUNT,
-        );
+    let active_tree_index = storage_account.get_trees_count();
 
-        $id.set_child_pubkey(0, Some(*vkey_account.key).into());
-        $id.set_child_pubkey(0, Some(*vkey_account1.key).into());
-    };
-}
-#[cfg(test)]
-pub(crate) use vkey_account;
+    let roots = public_inputs.roots();
+    guard!(
+        !roots.is_empty() && roots.len() <= MAX_MT_COUNT,
+        ElusivError::InvalidPublicInputs
+    );
+    guard!(
+        tree_indices.len() >= roots.len(),
+        ElusivError::InvalidPublicInputs
+    );
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::fields::{u256_from_str, u256_from_str_skip_mr};
-    use crate::macros::{
-        account_info, parent_account, program_token_account_info, pyth_price_account_info,
-        test_account_info, test_pda_account_info, two_pow, zero_program_account,
-    };
-    use crate::processor::{CommitmentHashRequest, ZERO_COMMITMENT_RAW};
-    use crate::proof::verifier::{
-        proof_from_str, COMBINED_MILLER_LOOP_IXS, FINAL_EXPONENTIATION_IXS,
-    };
-    use crate::state::commitment::COMMITMENT_BUFFER_LEN;
-    use crate::state::fee::ProgramFee;
-    use crate::state::governor::PoolAccount;
-    use crate::state::metadata::CommitmentMetadata;
-    use crate::state::nullifier::NullifierChildAccount;
-    use crate::state::program_account::{PDAAccount, SizedAccount};
-    use crate::state::storage::empty_root_raw;
-    use crate::token::{spl_token_account_data, LAMPORTS_TOKEN_ID, USDC_TOKEN_ID, USDT_TOKEN_ID};
-    use crate::types::{
-        compute_fee_rec, compute_fee_rec_lamports, OptionalFee, Proof, RawU256,
-        JOIN_SPLIT_MAX_N_ARITY,
-    };
-    use elusiv_types::tokens::Price;
-    use elusiv_types::{ProgramAccount, TokenError};
-    use solana_program::native_token::LAMPORTS_PER_SOL;
-    use solana_program::pubkey::Pubkey;
-    use solana_program::system_program;
+    // Reject non-canonical root encodings (values >= the scalar modulus) before any reduction
+    for root in &roots {
+        guard!(
+            is_element_scalar_field(u256_to_big_uint(&root.skip_mr())),
+            ElusivError::NonCanonicalMerkleRoot
+        );
+    }
 
-    fn fee() -> ProgramFee {
-        ProgramFee::new(5000, 11, 100, 33, 44, 300, 555).unwrap()
+    // Verify that the roots are valid
+    // Note: roots are stored in mr-form
+    for (index, root) in roots.iter().enumerate() {
+        if tree_indices[index] == active_tree_index {
+            // Active tree
+            guard!(
+                storage_account.is_root_valid(&root.reduce()),
+                ElusivError::InvalidMerkleRoot
+            );
+        } else {
+            // Closed tree
+            guard!(
+                root.reduce() == nullifier_accounts[index].get_root(),
+                ElusivError::InvalidMerkleRoot
+            );
+        }
     }
 
-    #[test]
-    fn test_init_verification() {
-        use ProofRequest::*;
+    // Input commitments without a root belong to the first MT
+    let tree_index = public_inputs.tree_positions();
 
-        parent_account!(storage, StorageAccount);
-        parent_account!(mut nullifier, NullifierAccount);
-        zero_program_account!(mut buffer, CommitmentBufferAccount);
-        test_account_info!(fee_payer, 0);
-        test_account_info!(identifier, 0);
-        account_info!(
-            v_acc,
-            VerificationAccount::find_with_pubkey(*fee_payer.key, Some(0)).0,
-            vec![0; VerificationAccount::SIZE]
+    // Unused tree-indices need to reference the next MT, which never contains a valid root
+    // Note: we can't use `active_tree_index` here, since the same nullifier-account would be supplied twice
+    let unused_tree_index = active_tree_index.checked_add(1).ok_or(MATH_ERR)?;
+    for tree_index in &tree_indices[roots.len()..] {
+        guard!(
+            *tree_index == unused_tree_index,
+            ElusivError::InvalidInstructionData
         );
+    }
 
-        let mut inputs = SendPublicInputs {
-            join_split: JoinSplitPublicInputs {
-                input_commitments: vec![InputCommitment {
-                    root: Some(empty_root_raw()),
-                    nullifier_hash: RawU256::new(u256_from_str_skip_mr("1")),
-                }],
-                output_commitment: RawU256::new(u256_from_str_skip_mr("1")),
-                recent_commitment_index: 0,
-                fee_version: 0,
-                amount: LAMPORTS_PER_SOL,
-                fee: 0,
-                optional_fee: OptionalFee::default(),
-                token_id: 0,
-                metadata: CommitmentMetadata::default(),
-            },
-            recipient_is_associated_token_account: true,
-            hashed_inputs: u256_from_str_skip_mr("1"),
-            solana_pay_transfer: false,
-        };
-        compute_fee_rec_lamports::<SendQuadraVKey, _>(&mut inputs, &fee());
+    // All supplied MTs (storage/nullifier-accounts) are pairwise different
+    guard!(
+        is_vec_duplicate_free(&tree_indices[..roots.len()].to_vec()),
+        ElusivError::InvalidInstructionData
+    );
 
-        account_info!(
-            n_duplicate_acc,
-            inputs.join_split.nullifier_duplicate_pda().0,
-            vec![1]
+    let nullifier_hashes = public_inputs.nullifier_hashes();
+    for (i, nullifier_hash) in nullifier_hashes.iter().enumerate() {
+        // No duplicate nullifier-hashes for the same MT
+        for (j, other_nullifier_hash) in nullifier_hashes.iter().enumerate() {
+            if i != j && nullifier_hash == other_nullifier_hash {
+                guard!(
+                    tree_index[i] != tree_index[j],
+                    ElusivError::InvalidPublicInputs
+                );
+            }
+        }
+
+        // Check that `nullifier_hash` is new
+        // Note: nullifier-hashes are stored in mr-form
+        guard!(
+            nullifier_accounts[tree_index[i]].can_insert_nullifier_hash(nullifier_hash.reduce())?,
+            ElusivError::CouldNotInsertNullifier
         );
+    }
 
-        let vkey_id = SendQuadraVKey::VKEY_ID;
-        let mut data = vec![0; VKeyAccount::SIZE];
-        let mut vkey = VKeyAccount::new(&mut data).unwrap();
-        vkey.set_public_inputs_count(&SendQuadraVKey::PUBLIC_INPUTS_COUNT);
-        vkey.set_version(&1);
+    Ok(())
+}
 
-        // TODO: test skip nullifier pda
-        // TODO: wrong vkey-id
-        // TODO: vkey not checked
+fn enforce_finalize_send_instructions(
+    instructions_account: &AccountInfo,
+    uses_lamports: bool,
+    verification_account_index: u8,
+    verification_account_generation: u32,
+) -> ProgramResult {
+    if cfg!(test) {
+        return Ok(());
+    }
 
-        // vacc-id exceeds `RESERVED_VERIFICATION_ACCOUNT_IDS`
-        assert_eq!(
-            init_verification(
-                &fee_payer,
-                &v_acc,
-                &vkey,
-                &n_duplicate_acc,
-                &identifier,
-                &storage,
-                &mut buffer,
-                &nullifier,
-                &nullifier,
-                RESERVED_VERIFICATION_ACCOUNT_IDS + 1,
-                vkey_id,
-                [0, 1],
-                Send(inputs.clone()),
-                false,
-            ),
-            Err(ElusivError::InvalidAccount.into())
-        );
+    enforce_finalize_send_instructions_inner(
+        &DefaultInstructionsSysvar(instructions_account),
+        uses_lamports,
+        verification_account_index,
+        verification_account_generation,
+    )
+}
 
-        // Commitment-count too low
-        assert_eq!(
-            init_verification(
-                &fee_payer,
-                &v_acc,
-                &vkey,
-                &n_duplicate_acc,
-                &identifier,
-                &storage,
-                &mut buffer,
-                &nullifier,
-                &nullifier,
-                0,
-                vkey_id,
-                [0, 1],
-                Send(mutate(&inputs, |v| {
-                    v.join_split.input_commitments.clear();
-                })),
-                false,
-            ),
-            Err(ElusivError::InvalidPublicInputs.into())
-        );
+fn verify_finalize_send_instruction<I: InstructionsSysvar>(
+    ix_index: usize,
+    instruction_sysvar: &I,
+    expected_variant_index: u8,
+    verification_account_index: u8,
+    verification_account_generation: u32,
+) -> ProgramResult {
+    let ix = instruction_sysvar.instruction_at_index(ix_index)?;
 
-        // Invalid root
-        assert_eq!(
-            init_verification(
-                &fee_payer,
-                &v_acc,
-                &vkey,
-                &n_duplicate_acc,
-                &identifier,
-                &storage,
-                &mut buffer,
-                &nullifier,
-                &nullifier,
-                0,
-                vkey_id,
-                [0, 1],
-                Send(mutate(&inputs, |v| {
+    guard!(
+        ix.program_id == crate::id(),
+        ProgramError::IncorrectProgramId
+    );
+    guard!(
+        ix.data[0] == expected_variant_index,
+        ElusivError::InvalidOtherInstruction
+    );
+    guard!(
+        ix.data[1] == verification_account_index
+            && ix.data.get(2..6) == Some(&verification_account_generation.to_le_bytes()[..]),
+        ElusivError::InvalidOtherInstruction
+    );
+
+    Ok(())
+}
+
+/// Enforces that the current transaction contains all required finalization instructions in the correct order (and no duplicates of them)
+pub(crate) fn enforce_finalize_send_instructions_inner<I: InstructionsSysvar>(
+    instruction_sysvar: &I,
+    uses_lamports: bool,
+    verification_account_index: u8,
+    verification_account_generation: u32,
+) -> ProgramResult {
+    let current_ix_index = instruction_sysvar.current_index()? as usize;
+
+    // Leading [`ElusivInstruction::FinalizeVerificationSend`]
+    verify_finalize_send_instruction(
+        current_ix_index,
+        instruction_sysvar,
+        ElusivInstruction::FINALIZE_VERIFICATION_SEND_INDEX,
+        verification_account_index,
+        verification_account_generation,
+    )?;
+
+    // Arbitrary number of [`ElusivInstruction::FinalizeVerificationInsertNullifier`]
+    let mut insertion_ix_count = 0;
+    while verify_finalize_send_instruction(
+        current_ix_index + insertion_ix_count + 1,
+        instruction_sysvar,
+        ElusivInstruction::FINALIZE_VERIFICATION_INSERT_NULLIFIER_INDEX,
+        verification_account_index,
+        verification_account_generation,
+    )
+    .is_ok()
+    {
+        insertion_ix_count += 1;
+    }
+
+    // Single transfer instruction (either [`ElusivInstruction::FinalizeVerificationTransferLamports`] or [`ElusivInstruction::FinalizeVerificationTransferToken`])
+    let transfer_ix_variant_index = if uses_lamports {
+        ElusivInstruction::FINALIZE_VERIFICATION_TRANSFER_LAMPORTS_INDEX
+    } else {
+        ElusivInstruction::FINALIZE_VERIFICATION_TRANSFER_TOKEN_INDEX
+    };
+
+    verify_finalize_send_instruction(
+        current_ix_index + insertion_ix_count + 1,
+        instruction_sysvar,
+        transfer_ix_variant_index,
+        verification_account_index,
+        verification_account_generation,
+    )?;
+
+    // No additional finalization instructions anywhere in the transaction (partial effects like the commitment enqueueing rely on a single execution)
+    let expected_counts = [
+        (ElusivInstruction::FINALIZE_VERIFICATION_SEND_INDEX, 1),
+        (
+            ElusivInstruction::FINALIZE_VERIFICATION_INSERT_NULLIFIER_INDEX,
+            insertion_ix_count,
+        ),
+        (
+            ElusivInstruction::FINALIZE_VERIFICATION_TRANSFER_LAMPORTS_INDEX,
+            usize::from(uses_lamports),
+        ),
+        (
+            ElusivInstruction::FINALIZE_VERIFICATION_TRANSFER_TOKEN_INDEX,
+            usize::from(!uses_lamports),
+        ),
+    ];
+    let mut counts = [0; 4];
+    for ix_index in 0..instruction_sysvar.find_instruction_count()? {
+        let ix = instruction_sysvar.instruction_at_index(ix_index)?;
+        if ix.program_id != crate::id() {
+            continue;
+        }
+
+        if let Some(i) = expected_counts
+            .iter()
+            .position(|(variant_index, _)| ix.data.first() == Some(variant_index))
+        {
+            counts[i] += 1;
+            guard!(
+                counts[i] <= expected_counts[i].1,
+                ElusivError::InvalidOtherInstruction
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// The `recipient_wallet` set by [`finalize_verification_send`]
+///
+/// # Note
+///
+/// The state guards prevent a transfer without a prior [`finalize_verification_send`], so a missing recipient indicates an invalid state.
+fn finalized_recipient_wallet(data: &VerificationAccountData) -> Result<U256, ProgramError> {
+    match data.recipient_wallet.option() {
+        Some(recipient_wallet) => Ok(recipient_wallet.skip_mr()),
+        None => Err(ElusivError::InvalidAccountState.into()),
+    }
+}
+
+/// The amount received by the recipient of a send: the `amount` without the optional fee and the rent of a created associated token account
+///
+/// # Note
+///
+/// For Solana Pay transfers, this is both the amount `pool` transfers to the fee-payer and the amount of the enforced trailing transfer.
+pub(crate) fn net_recipient_amount(
+    join_split: &JoinSplitPublicInputs,
+    associated_token_account_rent_token: u64,
+) -> Result<u64, ProgramError> {
+    join_split
+        .amount
+        .checked_sub(associated_token_account_rent_token)
+        .and_then(|amount| amount.checked_sub(join_split.optional_fee.amount))
+        .ok_or_else(|| ElusivError::InvalidAmount.into())
+}
+
+/// The trailing instruction of a Solana Pay transfer, in which the fee-payer forwards the [`net_recipient_amount`] to the recipient
+pub(crate) fn solana_pay_transfer_instruction(
+    token_id: u16,
+    token_program: &Pubkey,
+    fee_payer: &Pubkey,
+    fee_payer_account: &Pubkey,
+    recipient: &Pubkey,
+    net_recipient_amount: u64,
+) -> Result<Instruction, ProgramError> {
+    if token_id == 0 {
+        Ok(system_instruction::transfer(
+            fee_payer,
+            recipient,
+            net_recipient_amount,
+        ))
+    } else {
+        spl_token::instruction::transfer(
+            token_program,
+            fee_payer_account,
+            recipient,
+            fee_payer,
+            &[fee_payer],
+            net_recipient_amount,
+        )
+    }
+}
+
+/// Enforces the [`solana_pay_transfer_instruction`] to be the last instruction of the transaction
+fn enforce_solana_pay_transfer<I: InstructionsSysvar>(
+    instruction_sysvar: &I,
+    expected: &Instruction,
+) -> ProgramResult {
+    enforce_instruction(
+        instruction_sysvar,
+        instruction_sysvar.find_instruction_count()? - 1,
+        expected,
+        false,
+    )?;
+    Ok(())
+}
+
+pub(crate) fn enforce_instruction<I: InstructionsSysvar>(
+    instruction_sysvar: &I,
+    index: usize,
+    expected: &Instruction,
+    ignore_data: bool,
+) -> Result<Instruction, ProgramError> {
+    let instruction = instruction_sysvar.instruction_at_index(index)?;
+
+    guard!(
+        instruction.program_id == expected.program_id,
+        ProgramError::IncorrectProgramId
+    );
+
+    if !ignore_data {
+        guard!(
+            instruction.data == expected.data,
+            ElusivError::InvalidOtherInstruction
+        );
+    }
+
+    for (i, account) in expected.accounts.iter().enumerate() {
+        guard!(
+            instruction.accounts[i].pubkey == account.pubkey,
+            ElusivError::InvalidOtherInstruction
+        );
+
+        if account.is_signer {
+            guard!(
+                instruction.accounts[i].is_signer,
+                ElusivError::InvalidOtherInstruction
+            );
+        }
+
+        if account.is_writable {
+            guard!(
+                instruction.accounts[i].is_writable,
+                ElusivError::InvalidOtherInstruction
+            );
+        }
+    }
+
+    Ok(instruction)
+}
+
+#[cfg(test)]
+fn memo_instruction(memo: &[u8]) -> Instruction {
+    Instruction {
+        program_id: crate::state::governor::SPL_MEMO_PROGRAM_ID,
+        accounts: Vec::new(),
+        data: memo.to_vec(),
+    }
+}
+
+/// Returns the memo of the memo-instruction (last or second-to-last instruction with `solana_pay_transfer`) and the invoked memo program
+///
+/// # Note
+///
+/// - The memo-instruction can invoke any of the allow-listed `memo_program_ids`.
+/// - With `enforce_single_memo`, the transaction is required to contain no other memo-instruction (to prevent ambiguous receipts).
+pub(crate) fn get_memo_from_instructions<I: InstructionsSysvar>(
+    instruction_sysvar: &I,
+    memo_program_ids: &[Pubkey],
+    solana_pay_transfer: bool,
+    enforce_single_memo: bool,
+) -> Result<(Vec<u8>, Pubkey), ProgramError> {
+    let instruction_count = instruction_sysvar.find_instruction_count()?;
+    if enforce_single_memo {
+        guard!(
+            memo_instruction_count(instruction_sysvar, memo_program_ids, instruction_count)? == 1,
+            ElusivError::AmbiguousMemo
+        );
+    }
+
+    let memo_index = if solana_pay_transfer {
+        instruction_count - 2
+    } else {
+        instruction_count - 1
+    };
+
+    let instruction = instruction_sysvar.instruction_at_index(memo_index)?;
+    guard!(
+        memo_program_ids.contains(&instruction.program_id),
+        ProgramError::IncorrectProgramId
+    );
+    guard!(
+        instruction.data.len() <= MAX_MEMO_SIZE,
+        ElusivError::MemoTooLarge
+    );
+
+    Ok((instruction.data, instruction.program_id))
+}
+
+/// Counts the invocations of the `memo_program_ids` among the first `instruction_count` instructions
+fn memo_instruction_count<I: InstructionsSysvar>(
+    instruction_sysvar: &I,
+    memo_program_ids: &[Pubkey],
+    instruction_count: usize,
+) -> Result<usize, ProgramError> {
+    let mut count = 0;
+    for index in 0..instruction_count {
+        if memo_program_ids.contains(&instruction_sysvar.instruction_at_index(index)?.program_id) {
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+pub(crate) fn mutate<T: Clone, F>(v: &T, f: F) -> T
+where
+    F: Fn(&mut T),
+{
+    let mut i = v.clone();
+    f(&mut i);
+    i
+}
+
+#[cfg(test)]
+macro_rules! vkey_account {
+    ($id: ident, $vkey: ident) => {
+        let mut source = <$vkey as crate::proof::vkey::VerifyingKeyInfo>::verifying_key_source();
+        source.insert(0, 0);
+
+        crate::macros::account_info!(
+            vkey_account,
+            solana_program::pubkey::Pubkey::new_unique(),
+            source
+        );
+
+        let mut source = <$vkey as crate::proof::vkey::VerifyingKeyInfo>::verifying_key_source();
+        source.insert(0, 0);
+
+        crate::macros::account_info!(
+            vkey_account1,
+            solana_program::pubkey::Pubkey::new_unique(),
+            source
+        );
+
+        let mut data = vec![0; <VKeyAccount as elusiv_types::accounts::SizedAccount>::SIZE];
+        let mut $id =
+            <VKeyAccount as elusiv_types::accounts::ParentAccount>::new_with_child_accounts(
+                &mut data,
+                vec![Some(&vkey_account), Some(&vkey_account1)],
+            )
+            .unwrap();
+
+        $id.set_public_inputs_count(
+            &<$vkey as crate::proof::vkey::VerifyingKeyInfo>::PUBLIC_INPUTS_COUNT,
+        );
+
+        $id.set_child_pubkey(0, Some(*vkey_account.key).into());
+        $id.set_child_pubkey(0, Some(*vkey_account1.key).into());
+    };
+}
+#[cfg(test)]
+pub(crate) use vkey_account;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commitment::CommitmentTestVector;
+    use crate::fields::{
+        big_uint_to_u256, u256_from_str, u256_from_str_skip_mr, G1A, G2A, SCALAR_MODULUS_RAW,
+    };
+    use crate::macros::{
+        account_info, parent_account, program_token_account_info, pyth_price_account_info,
+        test_account_info, test_pda_account_info, two_pow, zero_pda_account_info,
+        zero_program_account,
+    };
+    use crate::processor::utils::{TestClockSysvar, TestInstructionsSysvar};
+    use crate::processor::{CommitmentHashRequest, ZERO_COMMITMENT_RAW};
+    use crate::proof::test_proofs::valid_proofs;
+    use crate::proof::verifier::{
+        proof_from_str, COMBINED_MILLER_LOOP_IXS, FINAL_EXPONENTIATION_IXS,
+    };
+    use crate::proof::vkey::TestVKey;
+    use crate::state::commitment::COMMITMENT_BUFFER_LEN;
+    use crate::state::fee::ProgramFee;
+    use crate::state::genesis::GenesisParamsAccount;
+    use crate::state::governor::{
+        PoolAccount, MAX_TIMESTAMP_PRUNING_BITS, PAUSABLE_CATEGORIES, SPL_MEMO_PROGRAM_ID,
+    };
+    use crate::state::metadata::CommitmentMetadata;
+    use crate::state::nullifier::NullifierChildAccount;
+    use crate::state::program_account::{PDAAccount, SizedAccount};
+    use crate::state::proof::FeeBreakdown;
+    use crate::state::storage::empty_root_raw;
+    use crate::state::token_registry::{RegisteredToken, TokenRegistryAccount};
+    use crate::state::vkey::ComputationDescriptorAccount;
+    use crate::token::{
+        elusiv_token, pyth_price_account_data, pyth_price_account_data_with_publish_slot,
+        spl_token_account_data, spl_token_account_data_with_owner, LAMPORTS_TOKEN_ID,
+        SPL_TOKEN_COUNT, USDC_TOKEN_ID, USDT_TOKEN_ID,
+    };
+    use crate::types::{
+        compute_fee_rec, compute_fee_rec_lamports, InputCommitment, OptionalFee, Proof, RawU256,
+        JOIN_SPLIT_MAX_N_ARITY, MAX_ENCRYPTED_MEMO_SIZE,
+    };
+    use ark_bn254::{Fq, Fq2, G1Affine, G2Affine};
+    use ark_ff::BigInteger;
+    use ark_ff::{One, Zero};
+    use elusiv_types::tokens::Price;
+    use elusiv_types::{ProgramAccount, TokenError};
+    use solana_program::native_token::LAMPORTS_PER_SOL;
+    use solana_program::pubkey::Pubkey;
+    use solana_program::{program_pack::Pack, rent::Rent};
+    use spl_associated_token_account::get_associated_token_address;
+
+    fn fee() -> ProgramFee {
+        ProgramFee::new(5000, 11, 100, 33, 44, 300, 555).unwrap()
+    }
+
+    fn token_registry_data(paused_token_ids: &[TokenID]) -> Vec<u8> {
+        let mut data = vec![0; TokenRegistryAccount::SIZE];
+        let mut token_registry = TokenRegistryAccount::new(&mut data).unwrap();
+        for &token_id in paused_token_ids {
+            token_registry.set_token_paused(token_id, true).unwrap();
+        }
+        data
+    }
+
+    #[test]
+    fn test_init_verification() {
+        use ProofRequest::*;
+
+        parent_account!(storage, StorageAccount);
+        parent_account!(mut nullifier, NullifierAccount);
+        zero_program_account!(mut buffer, CommitmentBufferAccount);
+        test_account_info!(fee_payer, 0);
+        test_account_info!(identifier, 0);
+        account_info!(genesis, GenesisParamsAccount::find(None).0, vec![]);
+        zero_program_account!(mut governor, GovernorAccount);
+        governor.set_min_verification_timeout(&60);
+        governor.set_max_verification_timeout(&120);
+        test_account_info!(v_index_acc, VerificationIndexAccount::SIZE);
+        account_info!(
+            v_acc,
+            VerificationAccount::find_with_pubkey(*fee_payer.key, Some(0)).0,
+            vec![0; VerificationAccount::account_size(SendQuadraVKey::public_inputs_count())]
+        );
+
+        let mut inputs = SendPublicInputs {
+            join_split: JoinSplitPublicInputs {
+                input_commitments: vec![InputCommitment {
+                    root: Some(empty_root_raw()),
+                    nullifier_hash: RawU256::new(u256_from_str_skip_mr("1")),
+                }],
+                output_commitment: RawU256::new(u256_from_str_skip_mr("1")),
+                recent_commitment_index: 0,
+                fee_version: 0,
+                amount: LAMPORTS_PER_SOL,
+                fee: 0,
+                optional_fee: OptionalFee::default(),
+                token_id: 0,
+                metadata: CommitmentMetadata::default(),
+            },
+            recipient_is_associated_token_account: true,
+            hashed_inputs: u256_from_str_skip_mr("1"),
+            solana_pay_transfer: false,
+            recipient_is_program_owned: false,
+            view_tag: 0,
+            current_time: 0,
+        };
+        compute_fee_rec_lamports::<SendQuadraVKey, _>(&mut inputs, &fee());
+
+        account_info!(
+            n_duplicate_acc,
+            inputs.join_split.nullifier_duplicate_pda().0,
+            vec![0; NullifierDuplicateAccount::SIZE]
+        );
+
+        let vkey_id = SendQuadraVKey::VKEY_ID;
+        let mut data = vec![0; VKeyAccount::SIZE];
+        let mut vkey = VKeyAccount::new(&mut data).unwrap();
+        vkey.set_public_inputs_count(&SendQuadraVKey::PUBLIC_INPUTS_COUNT);
+        vkey.set_version(&1);
+
+        // TODO: test skip nullifier pda
+        // TODO: wrong vkey-id
+        // TODO: vkey not checked
+
+        // vacc-id exceeds `RESERVED_VERIFICATION_ACCOUNT_IDS`
+        assert_eq!(
+            init_verification(
+                &fee_payer,
+                &v_acc,
+                &v_index_acc,
+                &vkey,
+                &n_duplicate_acc,
+                &identifier,
+                &governor,
+                &storage,
+                &mut buffer,
+                &genesis,
+                &nullifier,
+                &nullifier,
+                RESERVED_VERIFICATION_ACCOUNT_IDS + 1,
+                0,
+                vkey_id,
+                [0, 1],
+                Send(inputs.clone()),
+                false,
+                ElusivOption::None,
+            ),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        // Public inputs count does not match the vkey
+        let mut mismatched_data = vec![0; VKeyAccount::SIZE];
+        let mut mismatched_vkey = VKeyAccount::new(&mut mismatched_data).unwrap();
+        mismatched_vkey.set_public_inputs_count(&MigrateUnaryVKey::PUBLIC_INPUTS_COUNT);
+        mismatched_vkey.set_version(&1);
+        assert_eq!(
+            init_verification(
+                &fee_payer,
+                &v_acc,
+                &v_index_acc,
+                &mismatched_vkey,
+                &n_duplicate_acc,
+                &identifier,
+                &governor,
+                &storage,
+                &mut buffer,
+                &genesis,
+                &nullifier,
+                &nullifier,
+                0,
+                0,
+                vkey_id,
+                [0, 1],
+                Send(inputs.clone()),
+                false,
+                ElusivOption::None,
+            ),
+            Err(ElusivError::InvalidPublicInputs.into())
+        );
+
+        // Commitment-count too low
+        assert_eq!(
+            init_verification(
+                &fee_payer,
+                &v_acc,
+                &v_index_acc,
+                &vkey,
+                &n_duplicate_acc,
+                &identifier,
+                &governor,
+                &storage,
+                &mut buffer,
+                &genesis,
+                &nullifier,
+                &nullifier,
+                0,
+                0,
+                vkey_id,
+                [0, 1],
+                Send(mutate(&inputs, |v| {
+                    v.join_split.input_commitments.clear();
+                })),
+                false,
+                ElusivOption::None,
+            ),
+            Err(ElusivError::InvalidPublicInputs.into())
+        );
+
+        // Invalid root
+        assert_eq!(
+            init_verification(
+                &fee_payer,
+                &v_acc,
+                &v_index_acc,
+                &vkey,
+                &n_duplicate_acc,
+                &identifier,
+                &governor,
+                &storage,
+                &mut buffer,
+                &genesis,
+                &nullifier,
+                &nullifier,
+                0,
+                0,
+                vkey_id,
+                [0, 1],
+                Send(mutate(&inputs, |v| {
                     v.join_split.input_commitments[0].root =
                         Some(RawU256::new(u256_from_str_skip_mr("1")));
                 })),
                 false,
+                ElusivOption::None,
+            ),
+            Err(ElusivError::InvalidMerkleRoot.into())
+        );
+
+        // First root is None
+        assert_eq!(
+            init_verification(
+                &fee_payer,
+                &v_acc,
+                &v_index_acc,
+                &vkey,
+                &n_duplicate_acc,
+                &identifier,
+                &governor,
+                &storage,
+                &mut buffer,
+                &genesis,
+                &nullifier,
+                &nullifier,
+                0,
+                0,
+                vkey_id,
+                [0, 1],
+                Send(mutate(&inputs, |v| {
+                    v.join_split.input_commitments[0].root = None;
+                })),
+                false,
+                ElusivOption::None,
+            ),
+            Err(ElusivError::InvalidPublicInputs.into())
+        );
+
+        // Invalid recent-commitment-index
+        assert_eq!(
+            init_verification(
+                &fee_payer,
+                &v_acc,
+                &v_index_acc,
+                &vkey,
+                &n_duplicate_acc,
+                &identifier,
+                &governor,
+                &storage,
+                &mut buffer,
+                &genesis,
+                &nullifier,
+                &nullifier,
+                0,
+                0,
+                vkey_id,
+                [0, 1],
+                Send(mutate(&inputs, |inputs| {
+                    inputs.join_split.recent_commitment_index = 1;
+                    compute_fee_rec_lamports::<SendQuadraVKey, _>(inputs, &fee());
+                })),
+                false,
+                ElusivOption::None,
+            ),
+            Err(ElusivError::InvalidRecentCommitmentIndex.into())
+        );
+
+        // Mismatched tree indices
+        assert_eq!(
+            init_verification(
+                &fee_payer,
+                &v_acc,
+                &v_index_acc,
+                &vkey,
+                &n_duplicate_acc,
+                &identifier,
+                &governor,
+                &storage,
+                &mut buffer,
+                &genesis,
+                &nullifier,
+                &nullifier,
+                0,
+                0,
+                vkey_id,
+                [1, 0],
+                Send(inputs.clone()),
+                false,
+                ElusivOption::None,
+            ),
+            Err(ElusivError::InvalidMerkleRoot.into())
+        );
+
+        // Zero commitment
+        assert_eq!(
+            init_verification(
+                &fee_payer,
+                &v_acc,
+                &v_index_acc,
+                &vkey,
+                &n_duplicate_acc,
+                &identifier,
+                &governor,
+                &storage,
+                &mut buffer,
+                &genesis,
+                &nullifier,
+                &nullifier,
+                0,
+                0,
+                vkey_id,
+                [0, 1],
+                Send(mutate(&inputs, |v| {
+                    v.join_split.output_commitment = RawU256::new(ZERO_COMMITMENT_RAW);
+                })),
+                false,
+                ElusivOption::None,
+            ),
+            Err(ElusivError::InvalidPublicInputs.into())
+        );
+
+        // Nullifier already exists
+        nullifier
+            .try_insert_nullifier_hash(
+                inputs.join_split.input_commitments[0]
+                    .nullifier_hash
+                    .reduce(),
+            )
+            .unwrap();
+        assert_eq!(
+            init_verification(
+                &fee_payer,
+                &v_acc,
+                &v_index_acc,
+                &vkey,
+                &n_duplicate_acc,
+                &identifier,
+                &governor,
+                &storage,
+                &mut buffer,
+                &genesis,
+                &nullifier,
+                &nullifier,
+                0,
+                0,
+                vkey_id,
+                [0, 1],
+                Send(inputs.clone()),
+                false,
+                ElusivOption::None,
+            ),
+            Err(ElusivError::CouldNotInsertNullifier.into())
+        );
+
+        // Invalid nullifier_duplicate_account
+        parent_account!(nullifier, NullifierAccount);
+        account_info!(
+            invalid_n_duplicate_acc,
+            VerificationAccount::find_with_pubkey(*fee_payer.key, Some(0)).0,
+            vec![1]
+        );
+        assert_eq!(
+            init_verification(
+                &fee_payer,
+                &v_acc,
+                &v_index_acc,
+                &vkey,
+                &invalid_n_duplicate_acc,
+                &identifier,
+                &governor,
+                &storage,
+                &mut buffer,
+                &genesis,
+                &nullifier,
+                &nullifier,
+                0,
+                0,
+                vkey_id,
+                [0, 1],
+                Send(inputs.clone()),
+                false,
+                ElusivOption::None,
+            ),
+            Err(ProgramError::InvalidSeeds)
+        );
+
+        // TODO: Invalid nullifier_duplicate_account with skip set to true
+        assert_eq!(
+            init_verification(
+                &fee_payer,
+                &v_acc,
+                &v_index_acc,
+                &vkey,
+                &invalid_n_duplicate_acc,
+                &identifier,
+                &governor,
+                &storage,
+                &mut buffer,
+                &genesis,
+                &nullifier,
+                &nullifier,
+                0,
+                0,
+                vkey_id,
+                [0, 1],
+                Send(inputs.clone()),
+                true,
+                ElusivOption::None,
+            ),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        let mut data = vec![0; VKeyAccount::SIZE];
+        let mut migrate_vkey = VKeyAccount::new(&mut data).unwrap();
+        migrate_vkey.set_public_inputs_count(&MigrateUnaryVKey::PUBLIC_INPUTS_COUNT);
+        migrate_vkey.set_version(&1);
+
+        // Migrate always fails
+        assert_eq!(
+            init_verification(
+                &fee_payer,
+                &v_acc,
+                &v_index_acc,
+                &migrate_vkey,
+                &n_duplicate_acc,
+                &identifier,
+                &governor,
+                &storage,
+                &mut buffer,
+                &genesis,
+                &nullifier,
+                &nullifier,
+                0,
+                0,
+                MigrateUnaryVKey::VKEY_ID,
+                [0, 1],
+                Migrate(MigratePublicInputs {
+                    join_split: inputs.join_split.clone(),
+                    current_nsmt_root: RawU256::new([0; 32]),
+                    next_nsmt_root: RawU256::new([0; 32]),
+                }),
+                false,
+                ElusivOption::None,
+            ),
+            Err(ElusivError::FeatureNotAvailable.into())
+        );
+
+        // Timeout outside of the governor bounds
+        for timeout in [59, 121] {
+            assert_eq!(
+                init_verification(
+                    &fee_payer,
+                    &v_acc,
+                    &v_index_acc,
+                    &vkey,
+                    &n_duplicate_acc,
+                    &identifier,
+                    &governor,
+                    &storage,
+                    &mut buffer,
+                    &genesis,
+                    &nullifier,
+                    &nullifier,
+                    0,
+                    0,
+                    vkey_id,
+                    [0, 1],
+                    Send(inputs.clone()),
+                    false,
+                    ElusivOption::Some(timeout),
+                ),
+                Err(ElusivError::InvalidVerificationTimeout.into())
+            );
+        }
+
+        // Paused verifications
+        governor.set_paused_categories(&Category::InitVerification.bit());
+        assert_eq!(
+            init_verification(
+                &fee_payer,
+                &v_acc,
+                &v_index_acc,
+                &vkey,
+                &n_duplicate_acc,
+                &identifier,
+                &governor,
+                &storage,
+                &mut buffer,
+                &genesis,
+                &nullifier,
+                &nullifier,
+                0,
+                0,
+                vkey_id,
+                [0, 1],
+                Send(inputs.clone()),
+                false,
+                ElusivOption::None,
+            ),
+            Err(ElusivError::CategoryPaused.into())
+        );
+        governor.set_paused_categories(&(PAUSABLE_CATEGORIES & !Category::InitVerification.bit()));
+
+        assert_eq!(
+            init_verification(
+                &fee_payer,
+                &v_acc,
+                &v_index_acc,
+                &vkey,
+                &n_duplicate_acc,
+                &identifier,
+                &governor,
+                &storage,
+                &mut buffer,
+                &genesis,
+                &nullifier,
+                &nullifier,
+                0,
+                0,
+                vkey_id,
+                [0, 1],
+                Send(inputs.clone()),
+                false,
+                ElusivOption::Some(120),
+            ),
+            Ok(())
+        );
+        {
+            let mut data = v_acc.data.borrow_mut();
+            let v_acc = VerificationAccount::new(&mut data).unwrap();
+            assert_eq!(v_acc.get_timeout(), 120);
+        }
+
+        let mut inputs = inputs.clone();
+        inputs.join_split.input_commitments[0].nullifier_hash =
+            RawU256::new(u256_from_str_skip_mr("2"));
+        compute_fee_rec_lamports::<SendQuadraVKey, _>(&mut inputs, &fee());
+
+        account_info!(
+            n_duplicate_acc,
+            inputs.join_split.nullifier_duplicate_pda().0,
+            vec![0; NullifierDuplicateAccount::SIZE]
+        );
+
+        // Duplicate commitment insertion will fail and only succeed after COMMITMENT_BUFFER_LEN insertions
+        for i in 0..COMMITMENT_BUFFER_LEN {
+            assert_eq!(
+                init_verification(
+                    &fee_payer,
+                    &v_acc,
+                    &v_index_acc,
+                    &vkey,
+                    &n_duplicate_acc,
+                    &identifier,
+                    &governor,
+                    &storage,
+                    &mut buffer,
+                    &genesis,
+                    &nullifier,
+                    &nullifier,
+                    0,
+                    1,
+                    vkey_id,
+                    [0, 1],
+                    Send(inputs.clone()),
+                    false,
+                    ElusivOption::None,
+                ),
+                Err(ElusivError::DuplicateValue.into())
+            );
+
+            buffer.try_insert(&[i as u8; 32]).unwrap();
+        }
+
+        // Stale generation
+        zero_program_account!(mut stale_buffer, CommitmentBufferAccount);
+        assert_eq!(
+            init_verification(
+                &fee_payer,
+                &v_acc,
+                &v_index_acc,
+                &vkey,
+                &n_duplicate_acc,
+                &identifier,
+                &governor,
+                &storage,
+                &mut stale_buffer,
+                &genesis,
+                &nullifier,
+                &nullifier,
+                0,
+                0,
+                vkey_id,
+                [0, 1],
+                Send(inputs.clone()),
+                false,
+                ElusivOption::None,
+            ),
+            Err(ElusivError::InvalidInstructionData.into())
+        );
+
+        // Reusing index 0 in the next generation results in a new `VerificationAccount`
+        let v_acc_pk = VerificationAccount::find_with_generation(*fee_payer.key, 0, 1).0;
+        assert_ne!(*v_acc.key, v_acc_pk);
+        account_info!(
+            v_acc,
+            v_acc_pk,
+            vec![0; VerificationAccount::account_size(SendQuadraVKey::public_inputs_count())]
+        );
+        assert_eq!(
+            init_verification(
+                &fee_payer,
+                &v_acc,
+                &v_index_acc,
+                &vkey,
+                &n_duplicate_acc,
+                &identifier,
+                &governor,
+                &storage,
+                &mut buffer,
+                &genesis,
+                &nullifier,
+                &nullifier,
+                0,
+                1,
+                vkey_id,
+                [0, 1],
+                Send(inputs.clone()),
+                false,
+                ElusivOption::None,
+            ),
+            Ok(())
+        );
+
+        // Default timeout
+        {
+            let mut data = v_acc.data.borrow_mut();
+            let v_acc = VerificationAccount::new(&mut data).unwrap();
+            assert_eq!(v_acc.get_timeout(), VERIFICATION_TIMEOUT);
+        }
+
+        let mut data = v_index_acc.data.borrow_mut();
+        let verification_index = VerificationIndexAccount::new(&mut data).unwrap();
+        assert_eq!(verification_index.get_generation(), 2);
+    }
+
+    #[test]
+    fn test_init_verification_with_clock() -> ProgramResult {
+        parent_account!(storage, StorageAccount);
+        parent_account!(nullifier, NullifierAccount);
+        zero_program_account!(mut buffer, CommitmentBufferAccount);
+        test_account_info!(fee_payer, 0);
+        test_account_info!(identifier, 0);
+        account_info!(genesis, GenesisParamsAccount::find(None).0, vec![]);
+        zero_program_account!(mut governor, GovernorAccount);
+        governor.set_max_duplicate_verifications(&1);
+        governor.set_duplicate_grace_period(&5);
+        test_account_info!(v_index_acc, VerificationIndexAccount::SIZE);
+
+        let mut inputs = SendPublicInputs {
+            join_split: JoinSplitPublicInputs {
+                input_commitments: vec![InputCommitment {
+                    root: Some(empty_root_raw()),
+                    nullifier_hash: RawU256::new(u256_from_str_skip_mr("1")),
+                }],
+                output_commitment: RawU256::new(u256_from_str_skip_mr("1")),
+                recent_commitment_index: 0,
+                fee_version: 0,
+                amount: LAMPORTS_PER_SOL,
+                fee: 0,
+                optional_fee: OptionalFee::default(),
+                token_id: 0,
+                metadata: CommitmentMetadata::default(),
+            },
+            recipient_is_associated_token_account: true,
+            hashed_inputs: u256_from_str_skip_mr("1"),
+            solana_pay_transfer: false,
+            recipient_is_program_owned: false,
+            view_tag: 0,
+            current_time: 0,
+        };
+        compute_fee_rec_lamports::<SendQuadraVKey, _>(&mut inputs, &fee());
+
+        let (n_duplicate_pk, n_duplicate_bump) = inputs.join_split.nullifier_duplicate_pda();
+        let mut n_duplicate_data = vec![0; NullifierDuplicateAccount::SIZE];
+        n_duplicate_data[0] = n_duplicate_bump;
+        account_info!(n_duplicate_acc, n_duplicate_pk, n_duplicate_data);
+
+        let vkey_id = SendQuadraVKey::VKEY_ID;
+        let mut data = vec![0; VKeyAccount::SIZE];
+        let mut vkey = VKeyAccount::new(&mut data).unwrap();
+        vkey.set_public_inputs_count(&SendQuadraVKey::PUBLIC_INPUTS_COUNT);
+        vkey.set_version(&1);
+
+        let clock = |slot| TestClockSysvar {
+            slot,
+            unix_timestamp: 0,
+        };
+
+        // The first verification uses the injected slot
+        account_info!(
+            v_acc,
+            VerificationAccount::find_with_generation(*fee_payer.key, 0, 0).0,
+            vec![0; VerificationAccount::account_size(SendQuadraVKey::public_inputs_count())]
+        );
+        assert_eq!(
+            init_verification_with_clock(
+                &fee_payer,
+                &v_acc,
+                &v_index_acc,
+                &vkey,
+                &n_duplicate_acc,
+                &identifier,
+                &governor,
+                &storage,
+                &mut buffer,
+                &genesis,
+                &nullifier,
+                &nullifier,
+                0,
+                0,
+                vkey_id,
+                [0, 1],
+                ProofRequest::Send(inputs.clone()),
+                false,
+                ElusivOption::None,
+                &clock(100),
+            ),
+            Ok(())
+        );
+        {
+            pda_account!(n_duplicate, NullifierDuplicateAccount, n_duplicate_acc);
+            assert_eq!(n_duplicate.get_last_use_slot(), 100);
+        }
+
+        let duplicate_inputs = mutate(&inputs, |v| {
+            v.join_split.output_commitment = RawU256::new(u256_from_str_skip_mr("2"));
+        });
+        account_info!(
+            v_acc,
+            VerificationAccount::find_with_generation(*fee_payer.key, 0, 1).0,
+            vec![0; VerificationAccount::account_size(SendQuadraVKey::public_inputs_count())]
+        );
+
+        // Duplicate verification within the grace period
+        assert_eq!(
+            init_verification_with_clock(
+                &fee_payer,
+                &v_acc,
+                &v_index_acc,
+                &vkey,
+                &n_duplicate_acc,
+                &identifier,
+                &governor,
+                &storage,
+                &mut buffer,
+                &genesis,
+                &nullifier,
+                &nullifier,
+                0,
+                1,
+                vkey_id,
+                [0, 1],
+                ProofRequest::Send(duplicate_inputs.clone()),
+                true,
+                ElusivOption::None,
+                &clock(104),
+            ),
+            Err(ElusivError::DuplicateVerificationGracePeriod.into())
+        );
+
+        // Duplicate verification after the grace period
+        assert_eq!(
+            init_verification_with_clock(
+                &fee_payer,
+                &v_acc,
+                &v_index_acc,
+                &vkey,
+                &n_duplicate_acc,
+                &identifier,
+                &governor,
+                &storage,
+                &mut buffer,
+                &genesis,
+                &nullifier,
+                &nullifier,
+                0,
+                1,
+                vkey_id,
+                [0, 1],
+                ProofRequest::Send(duplicate_inputs),
+                true,
+                ElusivOption::None,
+                &clock(105),
+            ),
+            Ok(())
+        );
+
+        pda_account!(n_duplicate, NullifierDuplicateAccount, n_duplicate_acc);
+        assert_eq!(n_duplicate.get_last_use_slot(), 105);
+        assert_eq!(n_duplicate.get_duplicate_count(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_init_verification_current_time() {
+        parent_account!(storage, StorageAccount);
+        parent_account!(nullifier, NullifierAccount);
+        zero_program_account!(mut buffer, CommitmentBufferAccount);
+        test_account_info!(fee_payer, 0);
+        test_account_info!(identifier, 0);
+        account_info!(genesis, GenesisParamsAccount::find(None).0, vec![]);
+        zero_program_account!(mut governor, GovernorAccount);
+        test_account_info!(v_index_acc, VerificationIndexAccount::SIZE);
+
+        let current_time = 1_000;
+        let mut inputs = SendPublicInputs {
+            join_split: JoinSplitPublicInputs {
+                input_commitments: vec![InputCommitment {
+                    root: Some(empty_root_raw()),
+                    nullifier_hash: RawU256::new(u256_from_str_skip_mr("1")),
+                }],
+                output_commitment: RawU256::new(u256_from_str_skip_mr("1")),
+                recent_commitment_index: 0,
+                fee_version: 0,
+                amount: LAMPORTS_PER_SOL,
+                fee: 0,
+                optional_fee: OptionalFee::default(),
+                token_id: 0,
+                metadata: CommitmentMetadata::default(),
+            },
+            recipient_is_associated_token_account: true,
+            hashed_inputs: u256_from_str_skip_mr("1"),
+            solana_pay_transfer: false,
+            recipient_is_program_owned: false,
+            view_tag: 0,
+            current_time,
+        };
+        compute_fee_rec_lamports::<SendQuadraVKey, _>(&mut inputs, &fee());
+
+        let (n_duplicate_pk, n_duplicate_bump) = inputs.join_split.nullifier_duplicate_pda();
+        let mut n_duplicate_data = vec![0; NullifierDuplicateAccount::SIZE];
+        n_duplicate_data[0] = n_duplicate_bump;
+        account_info!(n_duplicate_acc, n_duplicate_pk, n_duplicate_data);
+
+        let vkey_id = SendQuadraVKey::VKEY_ID;
+        let mut data = vec![0; VKeyAccount::SIZE];
+        let mut vkey = VKeyAccount::new(&mut data).unwrap();
+        vkey.set_public_inputs_count(&SendQuadraVKey::PUBLIC_INPUTS_COUNT);
+        vkey.set_version(&1);
+
+        account_info!(
+            v_acc,
+            VerificationAccount::find_with_generation(*fee_payer.key, 0, 0).0,
+            vec![0; VerificationAccount::account_size(SendQuadraVKey::public_inputs_count())]
+        );
+
+        // Stale and future asserted times
+        for unix_timestamp in [current_time + 64, current_time - 64] {
+            assert_eq!(
+                init_verification_with_clock(
+                    &fee_payer,
+                    &v_acc,
+                    &v_index_acc,
+                    &vkey,
+                    &n_duplicate_acc,
+                    &identifier,
+                    &governor,
+                    &storage,
+                    &mut buffer,
+                    &genesis,
+                    &nullifier,
+                    &nullifier,
+                    0,
+                    0,
+                    vkey_id,
+                    [0, 1],
+                    ProofRequest::Send(inputs.clone()),
+                    false,
+                    ElusivOption::None,
+                    &TestClockSysvar {
+                        slot: 0,
+                        unix_timestamp: unix_timestamp as i64,
+                    },
+                ),
+                Err(ElusivError::InvalidPublicInputs.into())
+            );
+        }
+
+        // A wider window (128 second periods) accepts the stale asserted time
+        governor.set_timestamp_pruning_bits(&ElusivOption::Some(7));
+        assert_eq!(
+            init_verification_with_clock(
+                &fee_payer,
+                &v_acc,
+                &v_index_acc,
+                &vkey,
+                &n_duplicate_acc,
+                &identifier,
+                &governor,
+                &storage,
+                &mut buffer,
+                &genesis,
+                &nullifier,
+                &nullifier,
+                0,
+                0,
+                vkey_id,
+                [0, 1],
+                ProofRequest::Send(inputs),
+                false,
+                ElusivOption::None,
+                &TestClockSysvar {
+                    slot: 0,
+                    unix_timestamp: current_time as i64 + 64,
+                },
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_init_verification_pre_funded_nullifier_duplicate_account() {
+        parent_account!(storage, StorageAccount);
+        parent_account!(nullifier, NullifierAccount);
+        zero_program_account!(mut buffer, CommitmentBufferAccount);
+        test_account_info!(fee_payer, 0);
+        test_account_info!(identifier, 0);
+        account_info!(genesis, GenesisParamsAccount::find(None).0, vec![]);
+        zero_program_account!(governor, GovernorAccount);
+        test_account_info!(v_index_acc, VerificationIndexAccount::SIZE);
+
+        let mut inputs = SendPublicInputs {
+            join_split: JoinSplitPublicInputs {
+                input_commitments: vec![InputCommitment {
+                    root: Some(empty_root_raw()),
+                    nullifier_hash: RawU256::new(u256_from_str_skip_mr("1")),
+                }],
+                output_commitment: RawU256::new(u256_from_str_skip_mr("1")),
+                recent_commitment_index: 0,
+                fee_version: 0,
+                amount: LAMPORTS_PER_SOL,
+                fee: 0,
+                optional_fee: OptionalFee::default(),
+                token_id: 0,
+                metadata: CommitmentMetadata::default(),
+            },
+            recipient_is_associated_token_account: true,
+            hashed_inputs: u256_from_str_skip_mr("1"),
+            solana_pay_transfer: false,
+            recipient_is_program_owned: false,
+            view_tag: 0,
+            current_time: 0,
+        };
+        compute_fee_rec_lamports::<SendQuadraVKey, _>(&mut inputs, &fee());
+        let n_duplicate_pk = inputs.join_split.nullifier_duplicate_pda().0;
+
+        let vkey_id = SendQuadraVKey::VKEY_ID;
+        let mut data = vec![0; VKeyAccount::SIZE];
+        let mut vkey = VKeyAccount::new(&mut data).unwrap();
+        vkey.set_public_inputs_count(&SendQuadraVKey::PUBLIC_INPUTS_COUNT);
+        vkey.set_version(&1);
+
+        account_info!(
+            v_acc,
+            VerificationAccount::find_with_generation(*fee_payer.key, 0, 0).0,
+            vec![0; VerificationAccount::account_size(SendQuadraVKey::public_inputs_count())]
+        );
+
+        // Shell owned by another program
+        account_info!(
+            n_duplicate_acc,
+            n_duplicate_pk,
+            vec![0; NullifierDuplicateAccount::SIZE],
+            Pubkey::new_unique(),
+            false
+        );
+        assert_eq!(
+            init_verification(
+                &fee_payer,
+                &v_acc,
+                &v_index_acc,
+                &vkey,
+                &n_duplicate_acc,
+                &identifier,
+                &governor,
+                &storage,
+                &mut buffer,
+                &genesis,
+                &nullifier,
+                &nullifier,
+                0,
+                0,
+                vkey_id,
+                [0, 1],
+                ProofRequest::Send(inputs.clone()),
+                false,
+                ElusivOption::None,
+            ),
+            Err(ElusivError::ForeignAccountOwner.into())
+        );
+
+        // Funded system-account is adopted
+        account_info!(
+            n_duplicate_acc,
+            n_duplicate_pk,
+            vec![0; NullifierDuplicateAccount::SIZE],
+            system_program::id(),
+            false
+        );
+        assert_eq!(
+            init_verification(
+                &fee_payer,
+                &v_acc,
+                &v_index_acc,
+                &vkey,
+                &n_duplicate_acc,
+                &identifier,
+                &governor,
+                &storage,
+                &mut buffer,
+                &genesis,
+                &nullifier,
+                &nullifier,
+                0,
+                0,
+                vkey_id,
+                [0, 1],
+                ProofRequest::Send(inputs),
+                false,
+                ElusivOption::None,
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_init_verification_commitment_count_too_high() {
+        test_account_info!(v_index_acc, VerificationIndexAccount::SIZE);
+        parent_account!(storage, StorageAccount);
+        parent_account!(nullifier, NullifierAccount);
+        zero_program_account!(mut buffer, CommitmentBufferAccount);
+        test_account_info!(fee_payer, 0);
+        test_account_info!(identifier, 0);
+        account_info!(genesis, GenesisParamsAccount::find(None).0, vec![]);
+        zero_program_account!(governor, GovernorAccount);
+        account_info!(
+            v_acc,
+            VerificationAccount::find_with_pubkey(*fee_payer.key, Some(0)).0,
+            vec![0; VerificationAccount::account_size(SendQuadraVKey::public_inputs_count())]
+        );
+
+        let mut inputs = SendPublicInputs {
+            join_split: JoinSplitPublicInputs {
+                input_commitments: vec![InputCommitment {
+                    root: Some(empty_root_raw()),
+                    nullifier_hash: RawU256::new(u256_from_str_skip_mr("1")),
+                }],
+                output_commitment: RawU256::new(u256_from_str_skip_mr("1")),
+                recent_commitment_index: 123,
+                fee_version: 0,
+                amount: LAMPORTS_PER_SOL,
+                fee: 0,
+                optional_fee: OptionalFee::default(),
+                token_id: 0,
+                metadata: CommitmentMetadata::default(),
+            },
+            recipient_is_associated_token_account: true,
+            hashed_inputs: u256_from_str_skip_mr("1"),
+            solana_pay_transfer: false,
+            recipient_is_program_owned: false,
+            view_tag: 0,
+            current_time: 0,
+        };
+        compute_fee_rec_lamports::<SendQuadraVKey, _>(&mut inputs, &fee());
+
+        account_info!(
+            n_duplicate_acc,
+            inputs.join_split.nullifier_duplicate_pda().0,
+            vec![0; NullifierDuplicateAccount::SIZE]
+        );
+
+        let mut data = vec![0; VKeyAccount::SIZE];
+        let mut vkey = VKeyAccount::new(&mut data).unwrap();
+        vkey.set_public_inputs_count(&SendQuadraVKey::PUBLIC_INPUTS_COUNT);
+        vkey.set_is_frozen(&true);
+
+        for i in inputs.join_split.input_commitments.len()..JOIN_SPLIT_MAX_N_ARITY + 1 {
+            inputs.join_split.input_commitments.push(InputCommitment {
+                root: None,
+                nullifier_hash: RawU256::new(u256_from_str_skip_mr(&i.to_string())),
+            });
+        }
+
+        let _ = init_verification(
+            &fee_payer,
+            &v_acc,
+            &v_index_acc,
+            &vkey,
+            &n_duplicate_acc,
+            &identifier,
+            &governor,
+            &storage,
+            &mut buffer,
+            &genesis,
+            &nullifier,
+            &nullifier,
+            0,
+            0,
+            0,
+            [0, 1],
+            ProofRequest::Send(inputs),
+            false,
+            ElusivOption::None,
+        );
+    }
+
+    #[test]
+    fn test_init_verification_transfer_fee_lamports() {
+        test_account_info!(fee_payer, 0);
+        test_account_info!(pool, 0);
+        test_account_info!(fee_collector, 0);
+        test_account_info!(any, 0);
+        account_info!(sys, system_program::id());
+        account_info!(spl, spl_token::id());
+        account_info!(token_registry, TokenRegistryAccount::find(None).0, vec![]);
+        zero_program_account!(mut governor, GovernorAccount);
+        governor.set_program_fee(&fee());
+        governor.set_failure_bond(&1_000);
+
+        let mut inputs = SendPublicInputs {
+            join_split: JoinSplitPublicInputs {
+                input_commitments: vec![InputCommitment {
+                    root: Some(empty_root_raw()),
+                    nullifier_hash: RawU256::new(u256_from_str_skip_mr("1")),
+                }],
+                output_commitment: RawU256::new(u256_from_str_skip_mr("1")),
+                recent_commitment_index: 123,
+                fee_version: 0,
+                amount: LAMPORTS_PER_SOL,
+                fee: 0,
+                optional_fee: OptionalFee::default(),
+                token_id: 0,
+                metadata: CommitmentMetadata::default(),
+            },
+            recipient_is_associated_token_account: false,
+            hashed_inputs: u256_from_str_skip_mr("1"),
+            solana_pay_transfer: false,
+            recipient_is_program_owned: false,
+            view_tag: 0,
+            current_time: 0,
+        };
+        compute_fee_rec_lamports::<SendQuadraVKey, _>(&mut inputs, &fee());
+        let instructions = prepare_public_inputs_instructions(
+            &inputs.public_signals_skip_mr(),
+            SendQuadraVKey::public_inputs_count(),
+        );
+
+        zero_program_account!(mut verification_acc, VerificationAccount);
+        verification_acc.set_request(&ProofRequest::Send(inputs.clone()));
+        verification_acc.set_prepare_inputs_instructions_count(&(instructions.len() as u32));
+        verification_acc.set_other_data(&VerificationAccountData {
+            fee_payer: RawU256::new(fee_payer.key.to_bytes()),
+            ..Default::default()
+        });
+
+        // TODO: Associated token-account with lamports is invalid
+
+        // Invalid fee_payer
+        test_account_info!(fee_payer2, 0);
+        assert_eq!(
+            init_verification_transfer_fee(
+                &fee_payer2,
+                &fee_payer,
+                &pool,
+                &pool,
+                &fee_collector,
+                &fee_collector,
+                &any,
+                &any,
+                &governor,
+                &token_registry,
+                &mut verification_acc,
+                &sys,
+                &sys,
+                0,
+                0,
+            ),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        // Invalid verification account state
+        verification_acc.set_state(&VerificationState::FeeTransferred);
+        assert_eq!(
+            init_verification_transfer_fee(
+                &fee_payer,
+                &fee_payer,
+                &pool,
+                &pool,
+                &fee_collector,
+                &fee_collector,
+                &any,
+                &any,
+                &governor,
+                &token_registry,
+                &mut verification_acc,
+                &sys,
+                &sys,
+                0,
+                0,
+            ),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+
+        // Invalid fee_version
+        verification_acc.set_state(&VerificationState::None);
+        governor.set_fee_version(&1);
+        assert_eq!(
+            init_verification_transfer_fee(
+                &fee_payer,
+                &fee_payer,
+                &pool,
+                &pool,
+                &fee_collector,
+                &fee_collector,
+                &any,
+                &any,
+                &governor,
+                &token_registry,
+                &mut verification_acc,
+                &sys,
+                &sys,
+                0,
+                0,
+            ),
+            Err(ElusivError::InvalidFeeVersion.into())
+        );
+
+        // Invalid fee (fee too low, since too high is allowed)
+        governor.set_fee_version(&0);
+        inputs.join_split.fee -= 1;
+        verification_acc.set_request(&ProofRequest::Send(inputs.clone()));
+        assert_eq!(
+            init_verification_transfer_fee(
+                &fee_payer,
+                &fee_payer,
+                &pool,
+                &pool,
+                &fee_collector,
+                &fee_collector,
+                &any,
+                &any,
+                &governor,
+                &token_registry,
+                &mut verification_acc,
+                &sys,
+                &sys,
+                0,
+                0,
+            ),
+            Err(ElusivError::InvalidFee.into())
+        );
+
+        // Invalid system_program
+        inputs.join_split.fee = 0;
+        compute_fee_rec_lamports::<SendQuadraVKey, _>(&mut inputs, &fee());
+        verification_acc.set_request(&ProofRequest::Send(inputs));
+        assert_eq!(
+            init_verification_transfer_fee(
+                &fee_payer,
+                &fee_payer,
+                &pool,
+                &pool,
+                &fee_collector,
+                &fee_collector,
+                &any,
+                &any,
+                &governor,
+                &token_registry,
+                &mut verification_acc,
+                &sys,
+                &spl,
+                0,
+                0,
+            ),
+            Err(ProgramError::IncorrectProgramId)
+        );
+
+        // Invalid pool_account
+        assert_eq!(
+            init_verification_transfer_fee(
+                &fee_payer,
+                &fee_payer,
+                &pool,
+                &any,
+                &fee_collector,
+                &fee_collector,
+                &any,
+                &any,
+                &governor,
+                &token_registry,
+                &mut verification_acc,
+                &sys,
+                &sys,
+                0,
+                0,
+            ),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        // Invalid fee_collector_account
+        assert_eq!(
+            init_verification_transfer_fee(
+                &fee_payer,
+                &fee_payer,
+                &pool,
+                &pool,
+                &fee_collector,
+                &any,
+                &any,
+                &any,
+                &governor,
+                &token_registry,
+                &mut verification_acc,
+                &sys,
+                &sys,
+                0,
+                0,
+            ),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        // Paused verifications
+        governor.set_paused_categories(&Category::InitVerification.bit());
+        assert_eq!(
+            init_verification_transfer_fee(
+                &fee_payer,
+                &fee_payer,
+                &pool,
+                &pool,
+                &fee_collector,
+                &fee_collector,
+                &any,
+                &any,
+                &governor,
+                &token_registry,
+                &mut verification_acc,
+                &sys,
+                &sys,
+                0,
+                0,
+            ),
+            Err(ElusivError::CategoryPaused.into())
+        );
+        governor.set_paused_categories(&(PAUSABLE_CATEGORIES & !Category::InitVerification.bit()));
+
+        // Paused token
+        account_info!(
+            paused_token_registry,
+            TokenRegistryAccount::find(None).0,
+            token_registry_data(&[LAMPORTS_TOKEN_ID]),
+            crate::ID,
+            false
+        );
+        assert_eq!(
+            init_verification_transfer_fee(
+                &fee_payer,
+                &fee_payer,
+                &pool,
+                &pool,
+                &fee_collector,
+                &fee_collector,
+                &any,
+                &any,
+                &governor,
+                &paused_token_registry,
+                &mut verification_acc,
+                &sys,
+                &sys,
+                0,
+                0,
+            ),
+            Err(ElusivError::TokenPaused.into())
+        );
+
+        // Pausing a different token does not affect lamports
+        account_info!(
+            token_registry,
+            TokenRegistryAccount::find(None).0,
+            token_registry_data(&[USDC_TOKEN_ID]),
+            crate::ID,
+            false
+        );
+        assert_eq!(
+            init_verification_transfer_fee(
+                &fee_payer,
+                &fee_payer,
+                &pool,
+                &pool,
+                &fee_collector,
+                &fee_collector,
+                &any,
+                &any,
+                &governor,
+                &token_registry,
+                &mut verification_acc,
+                &sys,
+                &sys,
+                0,
+                0,
+            ),
+            Ok(())
+        );
+
+        assert_eq!(
+            verification_acc.get_state(),
+            VerificationState::FeeTransferred
+        );
+
+        // The failure-bond is escrowed
+        assert_eq!(
+            verification_acc.get_other_data().failure_bond,
+            Lamports(1_000)
+        );
+
+        // The request's fee-version is captured
+        assert_eq!(verification_acc.get_other_data().fee_version, 0);
+    }
+
+    #[test]
+    fn test_init_verification_transfer_fee_token() {
+        test_account_info!(fee_payer, 0);
+        account_info!(sys, system_program::id());
+        account_info!(spl, spl_token::id());
+        account_info!(token_registry, TokenRegistryAccount::find(None).0, vec![]);
+        zero_program_account!(mut governor, GovernorAccount);
+        governor.set_program_fee(&fee());
+
+        let usdc_mint = elusiv_token(USDC_TOKEN_ID).unwrap().mint;
+        account_info!(
+            token_acc,
+            get_associated_token_address(fee_payer.key, &usdc_mint),
+            spl_token_account_data_with_owner(USDC_TOKEN_ID, fee_payer.key),
+            spl_token::id(),
+            false
+        );
+        account_info!(
+            legacy_token_acc,
+            Pubkey::new_unique(),
+            spl_token_account_data_with_owner(USDC_TOKEN_ID, fee_payer.key),
+            spl_token::id(),
+            false
+        );
+        let foreign_wallet = Pubkey::new_unique();
+        account_info!(
+            foreign_token_acc,
+            get_associated_token_address(&foreign_wallet, &usdc_mint),
+            spl_token_account_data_with_owner(USDC_TOKEN_ID, &foreign_wallet),
+            spl_token::id(),
+            false
+        );
+        account_info!(
+            foreign_legacy_token_acc,
+            Pubkey::new_unique(),
+            spl_token_account_data_with_owner(USDC_TOKEN_ID, &foreign_wallet),
+            spl_token::id(),
+            false
+        );
+        account_info!(
+            wrong_token_acc,
+            Pubkey::new_unique(),
+            spl_token_account_data(USDT_TOKEN_ID),
+            spl_token::id(),
+            false
+        );
+
+        test_pda_account_info!(pool, PoolAccount, None);
+        test_pda_account_info!(fee_collector, FeeCollectorAccount, None);
+        program_token_account_info!(pool_token, PoolAccount, USDC_TOKEN_ID);
+        program_token_account_info!(fee_collector_token, FeeCollectorAccount, USDC_TOKEN_ID);
+
+        let sol_usd = Price {
+            price: 39,
+            conf: 1,
+            expo: 0,
+        };
+        let usdc_usd = Price {
+            price: 1,
+            conf: 1,
+            expo: 0,
+        };
+        let price = TokenPrice::new_from_sol_price(sol_usd, usdc_usd, USDC_TOKEN_ID).unwrap();
+        pyth_price_account_info!(sol, LAMPORTS_TOKEN_ID, sol_usd);
+        pyth_price_account_info!(usdc, USDC_TOKEN_ID, usdc_usd);
+
+        let mut inputs = SendPublicInputs {
+            join_split: JoinSplitPublicInputs {
+                input_commitments: vec![InputCommitment {
+                    root: Some(empty_root_raw()),
+                    nullifier_hash: RawU256::new(u256_from_str_skip_mr("1")),
+                }],
+                output_commitment: RawU256::new(u256_from_str_skip_mr("1")),
+                recent_commitment_index: 123,
+                fee_version: 0,
+                amount: 1_000_000,
+                fee: 0,
+                optional_fee: OptionalFee::default(),
+                token_id: USDC_TOKEN_ID,
+                metadata: CommitmentMetadata::default(),
+            },
+            recipient_is_associated_token_account: false,
+            hashed_inputs: u256_from_str_skip_mr("1"),
+            solana_pay_transfer: false,
+            recipient_is_program_owned: false,
+            view_tag: 0,
+            current_time: 0,
+        };
+        compute_fee_rec::<SendQuadraVKey, _>(&mut inputs, &fee(), &price);
+        let instructions = prepare_public_inputs_instructions(
+            &inputs.public_signals_skip_mr(),
+            SendQuadraVKey::public_inputs_count(),
+        );
+
+        zero_program_account!(mut verification_acc, VerificationAccount);
+        verification_acc.set_request(&ProofRequest::Send(inputs.clone()));
+        verification_acc.set_prepare_inputs_instructions_count(&(instructions.len() as u32));
+        verification_acc.set_other_data(&VerificationAccountData {
+            fee_payer: RawU256::new(fee_payer.key.to_bytes()),
+            ..Default::default()
+        });
+
+        // Invalid fee (fee too low, since too high is allowed)
+        inputs.join_split.fee -= 1;
+        verification_acc.set_request(&ProofRequest::Send(inputs.clone()));
+        assert_eq!(
+            init_verification_transfer_fee(
+                &fee_payer,
+                &token_acc,
+                &pool,
+                &pool_token,
+                &fee_collector,
+                &fee_collector_token,
+                &sol,
+                &usdc,
+                &governor,
+                &token_registry,
+                &mut verification_acc,
+                &spl,
+                &sys,
+                0,
+                0
+            ),
+            Err(ElusivError::InvalidFee.into())
+        );
+
+        inputs.join_split.fee = 0;
+        compute_fee_rec::<SendQuadraVKey, _>(&mut inputs, &fee(), &price);
+        verification_acc.set_request(&ProofRequest::Send(inputs.clone()));
+
+        // Invalid system_program
+        assert_eq!(
+            init_verification_transfer_fee(
+                &fee_payer,
+                &token_acc,
+                &pool,
+                &pool_token,
+                &fee_collector,
+                &fee_collector_token,
+                &sol,
+                &usdc,
+                &governor,
+                &token_registry,
+                &mut verification_acc,
+                &spl,
+                &spl,
+                0,
+                0
+            ),
+            Err(ProgramError::IncorrectProgramId)
+        );
+
+        // Invalid token_program
+        assert_eq!(
+            init_verification_transfer_fee(
+                &fee_payer,
+                &token_acc,
+                &pool,
+                &pool_token,
+                &fee_collector,
+                &fee_collector_token,
+                &sol,
+                &usdc,
+                &governor,
+                &token_registry,
+                &mut verification_acc,
+                &sys,
+                &sys,
+                0,
+                0
+            ),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        // Invalid fee_payer_account
+        assert_eq!(
+            init_verification_transfer_fee(
+                &fee_payer,
+                &wrong_token_acc,
+                &pool,
+                &pool_token,
+                &fee_collector,
+                &fee_collector_token,
+                &sol,
+                &usdc,
+                &governor,
+                &token_registry,
+                &mut verification_acc,
+                &spl,
+                &sys,
+                0,
+                0
+            ),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        // Invalid pool_account
+        assert_eq!(
+            init_verification_transfer_fee(
+                &fee_payer,
+                &token_acc,
+                &pool,
+                &fee_collector_token,
+                &fee_collector,
+                &fee_collector_token,
+                &sol,
+                &usdc,
+                &governor,
+                &token_registry,
+                &mut verification_acc,
+                &spl,
+                &sys,
+                0,
+                0
+            ),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        // Invalid fee_collector_account
+        assert_eq!(
+            init_verification_transfer_fee(
+                &fee_payer,
+                &token_acc,
+                &pool,
+                &pool_token,
+                &fee_collector,
+                &pool_token,
+                &sol,
+                &usdc,
+                &governor,
+                &token_registry,
+                &mut verification_acc,
+                &spl,
+                &sys,
+                0,
+                0
+            ),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        // Invalid sol_usd_price_account
+        assert_eq!(
+            init_verification_transfer_fee(
+                &fee_payer,
+                &token_acc,
+                &pool,
+                &pool_token,
+                &fee_collector,
+                &fee_collector_token,
+                &usdc,
+                &usdc,
+                &governor,
+                &token_registry,
+                &mut verification_acc,
+                &spl,
+                &sys,
+                0,
+                0
+            ),
+            Err(TokenError::InvalidPriceAccount.into())
+        );
+
+        // Invalid token_usd_price_account
+        assert_eq!(
+            init_verification_transfer_fee(
+                &fee_payer,
+                &token_acc,
+                &pool,
+                &pool_token,
+                &fee_collector,
+                &fee_collector_token,
+                &sol,
+                &sol,
+                &governor,
+                &token_registry,
+                &mut verification_acc,
+                &spl,
+                &sys,
+                0,
+                0
+            ),
+            Err(TokenError::InvalidPriceAccount.into())
+        );
+
+        // Non-associated fee_payer_account (legacy accounts are not allowed)
+        assert_eq!(
+            init_verification_transfer_fee(
+                &fee_payer,
+                &legacy_token_acc,
+                &pool,
+                &pool_token,
+                &fee_collector,
+                &fee_collector_token,
+                &sol,
+                &usdc,
+                &governor,
+                &token_registry,
+                &mut verification_acc,
+                &spl,
+                &sys,
+                0,
+                0
+            ),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        // Associated token-account of a different wallet
+        assert_eq!(
+            init_verification_transfer_fee(
+                &fee_payer,
+                &foreign_token_acc,
+                &pool,
+                &pool_token,
+                &fee_collector,
+                &fee_collector_token,
+                &sol,
+                &usdc,
+                &governor,
+                &token_registry,
+                &mut verification_acc,
+                &spl,
+                &sys,
+                0,
+                0
+            ),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        // Paused token
+        account_info!(
+            paused_token_registry,
+            TokenRegistryAccount::find(None).0,
+            token_registry_data(&[USDC_TOKEN_ID]),
+            crate::ID,
+            false
+        );
+        assert_eq!(
+            init_verification_transfer_fee(
+                &fee_payer,
+                &token_acc,
+                &pool,
+                &pool_token,
+                &fee_collector,
+                &fee_collector_token,
+                &sol,
+                &usdc,
+                &governor,
+                &paused_token_registry,
+                &mut verification_acc,
+                &spl,
+                &sys,
+                0,
+                0
+            ),
+            Err(ElusivError::TokenPaused.into())
+        );
+
+        assert_eq!(
+            init_verification_transfer_fee(
+                &fee_payer,
+                &token_acc,
+                &pool,
+                &pool_token,
+                &fee_collector,
+                &fee_collector_token,
+                &sol,
+                &usdc,
+                &governor,
+                &token_registry,
+                &mut verification_acc,
+                &spl,
+                &sys,
+                0,
+                0
+            ),
+            Ok(())
+        );
+
+        assert_eq!(
+            verification_acc.get_state(),
+            VerificationState::FeeTransferred
+        );
+
+        // Non-associated fee_payer_account (legacy accounts are allowed)
+        verification_acc.set_state(&VerificationState::None);
+        governor.set_allow_legacy_fee_payer_token_account(&true);
+
+        // Non-associated token-account owned by a different wallet
+        assert_eq!(
+            init_verification_transfer_fee(
+                &fee_payer,
+                &foreign_legacy_token_acc,
+                &pool,
+                &pool_token,
+                &fee_collector,
+                &fee_collector_token,
+                &sol,
+                &usdc,
+                &governor,
+                &token_registry,
+                &mut verification_acc,
+                &spl,
+                &sys,
+                0,
+                0
+            ),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        assert_eq!(
+            init_verification_transfer_fee(
+                &fee_payer,
+                &legacy_token_acc,
+                &pool,
+                &pool_token,
+                &fee_collector,
+                &fee_collector_token,
+                &sol,
+                &usdc,
+                &governor,
+                &token_registry,
+                &mut verification_acc,
+                &spl,
+                &sys,
+                0,
+                0
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            verification_acc
+                .get_other_data()
+                .fee_payer_account
+                .skip_mr(),
+            legacy_token_acc.key.to_bytes()
+        );
+
+        // The prices used for the conversion are persisted
+        let data = verification_acc.get_other_data();
+        assert_eq!(
+            data.sol_usd_price,
+            RecordedPrice::new(&price.lamports_usd, 0)
+        );
+        assert_eq!(
+            data.token_usd_price,
+            RecordedPrice::new(&price.token_usd, 0)
+        );
+
+        let proof_verification_fee = fee().proof_verification_computation_fee(instructions.len());
+        assert_eq!(
+            proof_verification_fee
+                .into_token(&data.token_price(), USDC_TOKEN_ID, rounding::FEE)
+                .unwrap()
+                .amount(),
+            data.proof_verification_fee
+        );
+        assert_eq!(
+            fee()
+                .proof_subvention
+                .into_token(&data.token_price(), USDC_TOKEN_ID, rounding::PAYOUT)
+                .unwrap()
+                .amount(),
+            data.subvention
+        );
+        assert_eq!(verification_acc.get_other_data(), data);
+
+        assert_eq!(
+            verification_acc.fee_breakdown(),
+            FeeBreakdown {
+                subvention: Token::new(USDC_TOKEN_ID, data.subvention),
+                network_fee: Token::new(USDC_TOKEN_ID, data.network_fee),
+                commitment_hash_fee: fee()
+                    .commitment_hash_computation_fee(data.min_batching_rate)
+                    .into_token(&price, USDC_TOKEN_ID, rounding::FEE)
+                    .unwrap(),
+                proof_verification_fee: proof_verification_fee
+                    .into_token(&price, USDC_TOKEN_ID, rounding::FEE)
+                    .unwrap(),
+                associated_token_account_rent: Token::new(USDC_TOKEN_ID, 0),
+            }
+        );
+    }
+
+    #[test]
+    fn test_finalize_verification_transfer_token_uses_recorded_prices() -> ProgramResult {
+        test_account_info!(fee_payer, 0);
+        test_account_info!(any, 0);
+        test_account_info!(rent_recipient, 0);
+        account_info!(sys, system_program::id());
+        account_info!(spl, spl_token::id());
+        account_info!(token_registry, TokenRegistryAccount::find(None).0, vec![]);
+        zero_program_account!(mut governor, GovernorAccount);
+        governor.set_program_fee(&fee());
+        zero_pda_account_info!(network_fee, NetworkFeeAccount, Some(USDC_TOKEN_ID.into()));
+        test_account_info!(v_index_acc, 0);
+        zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
+        zero_program_account!(mut metadata_queue, MetadataQueueAccount);
+
+        let usdc_mint = elusiv_token(USDC_TOKEN_ID).unwrap().mint;
+        account_info!(
+            token_acc,
+            get_associated_token_address(fee_payer.key, &usdc_mint),
+            spl_token_account_data_with_owner(USDC_TOKEN_ID, fee_payer.key),
+            spl_token::id(),
+            false
+        );
+        test_pda_account_info!(pool, PoolAccount, None);
+        test_pda_account_info!(fee_collector, FeeCollectorAccount, None);
+        program_token_account_info!(pool_token, PoolAccount, USDC_TOKEN_ID);
+        program_token_account_info!(fee_collector_token, FeeCollectorAccount, USDC_TOKEN_ID);
+
+        let sol_usd = Price {
+            price: 39,
+            conf: 1,
+            expo: 0,
+        };
+        let usdc_usd = Price {
+            price: 1,
+            conf: 1,
+            expo: 0,
+        };
+        account_info!(
+            sol,
+            elusiv_token(LAMPORTS_TOKEN_ID).unwrap().pyth_usd_price_key,
+            pyth_price_account_data_with_publish_slot(&sol_usd, 100).unwrap()
+        );
+        account_info!(
+            usdc,
+            elusiv_token(USDC_TOKEN_ID).unwrap().pyth_usd_price_key,
+            pyth_price_account_data_with_publish_slot(&usdc_usd, 101).unwrap()
+        );
+        let price = TokenPrice::new(&sol, &usdc, USDC_TOKEN_ID).unwrap();
+
+        let recipient = Pubkey::new_unique();
+        let mut inputs = SendPublicInputs {
+            join_split: JoinSplitPublicInputs {
+                input_commitments: vec![InputCommitment {
+                    root: Some(empty_root_raw()),
+                    nullifier_hash: RawU256::new(u256_from_str_skip_mr("1")),
+                }],
+                output_commitment: RawU256::new(u256_from_str_skip_mr("1")),
+                recent_commitment_index: 123,
+                fee_version: 0,
+                amount: 1_000_000,
+                fee: 0,
+                optional_fee: OptionalFee::default(),
+                token_id: USDC_TOKEN_ID,
+                metadata: CommitmentMetadata::default(),
+            },
+            recipient_is_associated_token_account: false,
+            hashed_inputs: u256_from_str_skip_mr("1"),
+            solana_pay_transfer: false,
+            recipient_is_program_owned: false,
+            view_tag: 0,
+            current_time: 0,
+        };
+        compute_fee_rec::<SendQuadraVKey, _>(&mut inputs, &fee(), &price);
+        let instructions = prepare_public_inputs_instructions(
+            &inputs.public_signals_skip_mr(),
+            SendQuadraVKey::public_inputs_count(),
+        );
+
+        let mut verification_acc_data = vec![0; VerificationAccount::SIZE];
+        let data = {
+            let mut verification_acc =
+                VerificationAccount::new(&mut verification_acc_data).unwrap();
+            verification_acc.set_request(&ProofRequest::Send(inputs.clone()));
+            verification_acc.set_prepare_inputs_instructions_count(&(instructions.len() as u32));
+            verification_acc.set_other_data(&VerificationAccountData {
+                fee_payer: RawU256::new(fee_payer.key.to_bytes()),
+                ..Default::default()
+            });
+
+            init_verification_transfer_fee(
+                &fee_payer,
+                &token_acc,
+                &pool,
+                &pool_token,
+                &fee_collector,
+                &fee_collector_token,
+                &sol,
+                &usdc,
+                &governor,
+                &token_registry,
+                &mut verification_acc,
+                &spl,
+                &sys,
+                0,
+                0,
+            )
+            .unwrap();
+
+            // The published prices (and the slots of their updates) are persisted
+            let mut data = verification_acc.get_other_data();
+            assert_eq!(
+                data.sol_usd_price,
+                RecordedPrice::new(&price.lamports_usd, 100)
+            );
+            assert_eq!(
+                data.token_usd_price,
+                RecordedPrice::new(&price.token_usd, 101)
+            );
+
+            // Proof verification succeeded
+            data.recipient_wallet = ElusivOption::Some(RawU256::new(recipient.to_bytes()));
+            verification_acc.set_other_data(&data);
+            verification_acc.set_state(&VerificationState::Finalized);
+            verification_acc.set_is_verified(&ElusivOption::Some(true));
+            data
+        };
+        let fee_breakdown = VerificationAccount::new(&mut verification_acc_data)
+            .unwrap()
+            .fee_breakdown();
+
+        // The price accounts are updated between the fee transfer and the finalization
+        let changed_sol_usd = Price {
+            price: 78,
+            conf: 1,
+            expo: 0,
+        };
+        sol.data.borrow_mut().copy_from_slice(
+            &pyth_price_account_data_with_publish_slot(&changed_sol_usd, 200).unwrap(),
+        );
+        usdc.data
+            .borrow_mut()
+            .copy_from_slice(&pyth_price_account_data_with_publish_slot(&usdc_usd, 201).unwrap());
+        let changed_price = TokenPrice::new(&sol, &usdc, USDC_TOKEN_ID).unwrap();
+        assert_eq!(changed_price.lamports_usd_publish_slot, 200);
+        assert_ne!(
+            fee()
+                .proof_verification_computation_fee(instructions.len())
+                .into_token(&changed_price, USDC_TOKEN_ID, rounding::FEE)
+                .unwrap(),
+            fee_breakdown.proof_verification_fee
+        );
+
+        account_info!(r, recipient);
+        account_info!(v_acc, Pubkey::new_unique(), verification_acc_data);
+        test_pda_account_info!(
+            n_pda,
+            NullifierDuplicateAccount,
+            inputs
+                .join_split
+                .associated_nullifier_duplicate_pda_pubkey(),
+            None
+        );
+        assert_eq!(
+            finalize_verification_transfer_token(
+                &fee_payer,
+                &token_acc,
+                &r,
+                &r,
+                &pool,
+                &pool_token,
+                &fee_collector,
+                &fee_collector_token,
+                &any,
+                &mut commitment_queue,
+                &mut metadata_queue,
+                &v_acc,
+                &n_pda,
+                &spl,
+                &any,
+                &any,
+                &governor,
+                &rent_recipient,
+                &network_fee,
+                &v_index_acc,
+                0,
+                0
+            ),
+            Ok(())
+        );
+
+        // The finalization settles the fees converted with the recorded (and not the current) prices
+        pda_account!(v_acc, VerificationAccount, v_acc);
+        assert_eq!(v_acc.get_state(), VerificationState::Closed);
+        assert_eq!(v_acc.get_other_data(), data);
+        assert_eq!(v_acc.fee_breakdown(), fee_breakdown);
+        assert_eq!(
+            fee_breakdown.proof_verification_fee,
+            fee()
+                .proof_verification_computation_fee(instructions.len())
+                .into_token(&price, USDC_TOKEN_ID, rounding::FEE)
+                .unwrap()
+        );
+
+        pda_account!(network_fee, NetworkFeeAccount, network_fee);
+        assert_eq!(network_fee.get_collected_network_fee(), data.network_fee);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_init_verification_transfer_fee_registered_token() {
+        test_account_info!(fee_payer, 0);
+        account_info!(sys, system_program::id());
+        account_info!(spl, spl_token::id());
+        zero_program_account!(mut governor, GovernorAccount);
+        governor.set_program_fee(&fee());
+
+        // Token registered after deployment (identical to USDC apart from the keys)
+        let token_id = SPL_TOKEN_COUNT as TokenID + 1;
+        let usdc = elusiv_token(USDC_TOKEN_ID).unwrap();
+        let token = RegisteredToken {
+            mint: Pubkey::new_unique(),
+            decimals: usdc.decimals,
+            price_base_exp: usdc.price_base_exp,
+            pyth_usd_price_key: Pubkey::new_unique(),
+            min: usdc.min,
+            max: usdc.max,
+        };
+        let mut registry_data = token_registry_data(&[]);
+        TokenRegistryAccount::new(&mut registry_data)
+            .unwrap()
+            .register_token(token_id, &token)
+            .unwrap();
+        account_info!(
+            token_registry,
+            TokenRegistryAccount::find(None).0,
+            registry_data,
+            crate::ID,
+            false
+        );
+        account_info!(
+            static_token_registry,
+            TokenRegistryAccount::find(None).0,
+            vec![]
+        );
+
+        let token_account_data = |owner: &Pubkey| {
+            let account = spl_token::state::Account {
+                mint: token.mint,
+                owner: *owner,
+                state: spl_token::state::AccountState::Initialized,
+                ..Default::default()
+            };
+            let mut data = vec![0; spl_token::state::Account::LEN];
+            spl_token::state::Account::pack(account, &mut data[..]).unwrap();
+            data
+        };
+        account_info!(
+            token_acc,
+            get_associated_token_address(fee_payer.key, &token.mint),
+            token_account_data(fee_payer.key),
+            spl_token::id(),
+            false
+        );
+
+        test_pda_account_info!(pool, PoolAccount, None);
+        test_pda_account_info!(fee_collector, FeeCollectorAccount, None);
+        account_info!(
+            pool_token,
+            get_associated_token_address(pool.key, &token.mint),
+            token_account_data(pool.key),
+            spl_token::id(),
+            false
+        );
+        account_info!(
+            fee_collector_token,
+            get_associated_token_address(fee_collector.key, &token.mint),
+            token_account_data(fee_collector.key),
+            spl_token::id(),
+            false
+        );
+
+        let token_usd = Price {
+            price: 1,
+            conf: 1,
+            expo: 0,
+        };
+        pyth_price_account_info!(
+            sol,
+            LAMPORTS_TOKEN_ID,
+            Price {
+                price: 39,
+                conf: 1,
+                expo: 0,
+            }
+        );
+        account_info!(
+            token_usd_account,
+            token.pyth_usd_price_key,
+            pyth_price_account_data(&token_usd).unwrap()
+        );
+        let price =
+            TokenPrice::new_with_token(&sol, &token_usd_account, token_id, &token.elusiv_token())
+                .unwrap();
+
+        let mut inputs = SendPublicInputs {
+            join_split: JoinSplitPublicInputs {
+                input_commitments: vec![InputCommitment {
+                    root: Some(empty_root_raw()),
+                    nullifier_hash: RawU256::new(u256_from_str_skip_mr("1")),
+                }],
+                output_commitment: RawU256::new(u256_from_str_skip_mr("1")),
+                recent_commitment_index: 123,
+                fee_version: 0,
+                amount: 1_000_000,
+                fee: 0,
+                optional_fee: OptionalFee::default(),
+                token_id,
+                metadata: CommitmentMetadata::default(),
+            },
+            recipient_is_associated_token_account: false,
+            hashed_inputs: u256_from_str_skip_mr("1"),
+            solana_pay_transfer: false,
+            recipient_is_program_owned: false,
+            view_tag: 0,
+            current_time: 0,
+        };
+        compute_fee_rec::<SendQuadraVKey, _>(&mut inputs, &fee(), &price);
+        let instructions = prepare_public_inputs_instructions(
+            &inputs.public_signals_skip_mr(),
+            SendQuadraVKey::public_inputs_count(),
+        );
+
+        zero_program_account!(mut verification_acc, VerificationAccount);
+        verification_acc.set_request(&ProofRequest::Send(inputs));
+        verification_acc.set_prepare_inputs_instructions_count(&(instructions.len() as u32));
+        verification_acc.set_other_data(&VerificationAccountData {
+            fee_payer: RawU256::new(fee_payer.key.to_bytes()),
+            ..Default::default()
+        });
+
+        // Unknown to the static token table
+        assert_eq!(
+            init_verification_transfer_fee(
+                &fee_payer,
+                &token_acc,
+                &pool,
+                &pool_token,
+                &fee_collector,
+                &fee_collector_token,
+                &sol,
+                &token_usd_account,
+                &governor,
+                &static_token_registry,
+                &mut verification_acc,
+                &spl,
+                &sys,
+                0,
+                0
+            ),
+            Err(TokenError::InvalidTokenID.into())
+        );
+
+        assert_eq!(
+            init_verification_transfer_fee(
+                &fee_payer,
+                &token_acc,
+                &pool,
+                &pool_token,
+                &fee_collector,
+                &fee_collector_token,
+                &sol,
+                &token_usd_account,
+                &governor,
+                &token_registry,
+                &mut verification_acc,
+                &spl,
+                &sys,
+                0,
+                0
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            verification_acc.get_state(),
+            VerificationState::FeeTransferred
+        );
+
+        // The registered mint is persisted for the finalization
+        let data = verification_acc.get_other_data();
+        assert_eq!(data.token_id, token_id);
+        assert_eq!(data.mint, RawU256::new(token.mint.to_bytes()));
+        assert_eq!(
+            data.token_usd_price,
+            RecordedPrice::new(&price.token_usd, 0)
+        );
+    }
+
+    #[test]
+    fn test_init_verification_proof() {
+        let proof = valid_proofs()[0].proof;
+        let valid_pk = Pubkey::new(&[0; 32]);
+        account_info!(fee_payer, valid_pk, vec![0; 0]);
+        zero_program_account!(mut verification_account, VerificationAccount);
+
+        // Account setup
+        verification_account.set_state(&VerificationState::ProofSetup);
+        assert_eq!(
+            init_verification_proof(&fee_payer, &mut verification_account, 0, 0, proof),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+        verification_account.set_state(&VerificationState::FeeTransferred);
+
+        // Computation already finished
+        verification_account.set_is_verified(&ElusivOption::Some(true));
+        assert_eq!(
+            init_verification_proof(&fee_payer, &mut verification_account, 0, 0, proof),
+            Err(ElusivError::ComputationIsAlreadyFinished.into())
+        );
+        verification_account.set_is_verified(&ElusivOption::Some(false));
+        assert_eq!(
+            init_verification_proof(&fee_payer, &mut verification_account, 0, 0, proof),
+            Err(ElusivError::ComputationIsAlreadyFinished.into())
+        );
+        verification_account.set_is_verified(&ElusivOption::None);
+
+        // Invalid fee_payer
+        let invalid_pk = Pubkey::new_unique();
+        account_info!(invalid_fee_payer, invalid_pk, vec![0; 0]);
+        assert_eq!(
+            init_verification_proof(&invalid_fee_payer, &mut verification_account, 0, 0, proof),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        // Off-curve points
+        for invalid_proof in [
+            Proof {
+                a: G1A(G1Affine::new(proof.a.0.x, proof.a.0.y + Fq::one(), false)),
+                ..proof
+            },
+            Proof {
+                b: G2A(G2Affine::new(proof.b.0.x, proof.b.0.y + Fq2::one(), false)),
+                ..proof
+            },
+            Proof {
+                c: G1A(G1Affine::new(proof.c.0.y, proof.c.0.x, false)),
+                ..proof
+            },
+            test_proof(),
+        ] {
+            assert_eq!(
+                init_verification_proof(&fee_payer, &mut verification_account, 0, 0, invalid_proof),
+                Err(ElusivError::InvalidInstructionData.into())
+            );
+        }
+
+        // Points at infinity
+        for invalid_proof in [
+            Proof {
+                a: G1A(G1Affine::zero()),
+                ..proof
+            },
+            Proof {
+                b: G2A(G2Affine::zero()),
+                ..proof
+            },
+            Proof {
+                c: G1A(G1Affine::zero()),
+                ..proof
+            },
+        ] {
+            assert_eq!(
+                init_verification_proof(&fee_payer, &mut verification_account, 0, 0, invalid_proof),
+                Err(ElusivError::InvalidInstructionData.into())
+            );
+        }
+
+        // Success
+        assert_eq!(
+            init_verification_proof(&fee_payer, &mut verification_account, 0, 0, proof),
+            Ok(())
+        );
+        assert_eq!(
+            verification_account.get_state(),
+            VerificationState::ProofSetup
+        );
+        assert_eq!(verification_account.a.get(), proof.a);
+        assert_eq!(verification_account.b.get(), proof.b);
+        assert_eq!(verification_account.c.get(), proof.c);
+
+        // Already setup proof
+        assert_eq!(
+            init_verification_proof(&fee_payer, &mut verification_account, 0, 0, proof),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+    }
+
+    #[test]
+    fn test_compute_verification() {
+        zero_program_account!(mut governor, GovernorAccount);
+        zero_program_account!(
+            mut verification_account,
+            VerificationAccount,
+            VerificationAccount::account_size(SendQuadraVKey::public_inputs_count())
+        );
+        vkey_account!(vkey, SendQuadraVKey);
+        vkey.set_version(&1);
+        test_account_info!(any, 0);
+
+        // Setup
+        let public_inputs = test_public_inputs();
+        verification_account
+            .set_public_inputs(
+                &public_inputs
+                    .iter()
+                    .map(|&public_input| RawU256::new(public_input))
+                    .collect::<Vec<RawU256>>(),
+            )
+            .unwrap();
+        let instructions = prepare_public_inputs_instructions(
+            &public_inputs,
+            SendQuadraVKey::public_inputs_count(),
+        );
+        verification_account.set_prepare_inputs_instructions_count(&(instructions.len() as u32));
+        for (i, &ix) in instructions.iter().enumerate() {
+            verification_account.set_prepare_inputs_instructions(i, &(ix as u16));
+        }
+
+        // Paused
+        governor.set_paused_categories(&Category::Compute.bit());
+        assert_eq!(
+            compute_verification(
+                &mut verification_account,
+                &vkey,
+                UnverifiedAccountInfo::new(&any),
+                &any,
+                &governor,
+                0,
+                0,
+                SendQuadraVKey::VKEY_ID
+            ),
+            Err(ElusivError::CategoryPaused.into())
+        );
+        governor.set_paused_categories(&(PAUSABLE_CATEGORIES & !Category::Compute.bit()));
+
+        // Computation is already finished (is_verified is Some)
+        verification_account.set_is_verified(&ElusivOption::Some(true));
+        assert_eq!(
+            compute_verification(
+                &mut verification_account,
+                &vkey,
+                UnverifiedAccountInfo::new(&any),
+                &any,
+                &governor,
+                0,
+                0,
+                SendQuadraVKey::VKEY_ID
+            ),
+            Err(ElusivError::ComputationIsAlreadyFinished.into())
+        );
+        verification_account.set_is_verified(&ElusivOption::None);
+
+        // Vkey source does not match the recorded digest
+        vkey.set_expected_source_digest(&ElusivOption::Some([0; 32]));
+        assert_eq!(
+            compute_verification(
+                &mut verification_account,
+                &vkey,
+                UnverifiedAccountInfo::new(&any),
+                &any,
+                &governor,
+                0,
+                0,
+                SendQuadraVKey::VKEY_ID
+            ),
+            Err(ElusivError::InvalidVKeySourceDigest.into())
+        );
+        vkey.set_expected_source_digest(&ElusivOption::Some(vkey.source_digest().unwrap()));
+
+        // Success for public input preparation
+        for _ in 0..instructions.len() {
+            assert_eq!(
+                compute_verification(
+                    &mut verification_account,
+                    &vkey,
+                    UnverifiedAccountInfo::new(&any),
+                    &any,
+                    &governor,
+                    0,
+                    0,
+                    SendQuadraVKey::VKEY_ID
+                ),
+                Ok(())
+            );
+        }
+
+        // Failure for miller loop (proof not setup)
+        assert_eq!(
+            compute_verification(
+                &mut verification_account,
+                &vkey,
+                UnverifiedAccountInfo::new(&any),
+                &any,
+                &governor,
+                0,
+                0,
+                SendQuadraVKey::VKEY_ID
+            ),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+
+        let proof = test_proof();
+        verification_account.a.set(proof.a);
+        verification_account.b.set(proof.b);
+        verification_account.c.set(proof.c);
+        verification_account.set_state(&VerificationState::ProofSetup);
+
+        // Success
+        for _ in 0..COMBINED_MILLER_LOOP_IXS + FINAL_EXPONENTIATION_IXS {
+            assert_eq!(
+                compute_verification(
+                    &mut verification_account,
+                    &vkey,
+                    UnverifiedAccountInfo::new(&any),
+                    &any,
+                    &governor,
+                    0,
+                    0,
+                    SendQuadraVKey::VKEY_ID
+                ),
+                Ok(())
+            );
+        }
+
+        // Computation is finished
+        assert_eq!(
+            compute_verification(
+                &mut verification_account,
+                &vkey,
+                UnverifiedAccountInfo::new(&any),
+                &any,
+                &governor,
+                0,
+                0,
+                SendQuadraVKey::VKEY_ID
+            ),
+            Err(ElusivError::ComputationIsAlreadyFinished.into())
+        );
+        assert_eq!(verification_account.get_is_verified().option(), Some(false));
+    }
+
+    #[test]
+    fn test_compute_verification_with_descriptor() {
+        zero_program_account!(governor, GovernorAccount);
+        zero_program_account!(
+            mut verification_account,
+            VerificationAccount,
+            VerificationAccount::account_size(TestVKey::public_inputs_count())
+        );
+        vkey_account!(vkey, TestVKey);
+        vkey.set_version(&1);
+        test_account_info!(any, 0);
+
+        // Synthetic descriptor registered for the (non built-in) test vkey
+        let public_inputs_count = TestVKey::public_inputs_count();
+        let descriptor = ComputationDescriptor {
+            add_mixed_cost: 90,
+            add_cost: 120,
+            ..ComputationDescriptor::built_in(public_inputs_count)
+        };
+        let (pk, bump) = ComputationDescriptorAccount::find(Some(TestVKey::VKEY_ID));
+        let mut data = vec![0; ComputationDescriptorAccount::SIZE];
+        data[0] = bump;
+        let mut computation_descriptor = ComputationDescriptorAccount::new(&mut data).unwrap();
+        computation_descriptor.set_descriptor(&descriptor);
+        computation_descriptor.set_is_setup(&true);
+        account_info!(computation_descriptor_account, pk, data, crate::ID, false);
+
+        let test_proof = &valid_proofs()[0];
+        verification_account
+            .set_public_inputs(
+                &test_proof
+                    .public_inputs
+                    .iter()
+                    .map(|&public_input| RawU256::new(public_input))
+                    .collect::<Vec<RawU256>>(),
+            )
+            .unwrap();
+        verification_account.a.set(test_proof.proof.a);
+        verification_account.b.set(test_proof.proof.b);
+        verification_account.c.set(test_proof.proof.c);
+        verification_account.set_state(&VerificationState::ProofSetup);
+        verification_account.set_vkey_id(&TestVKey::VKEY_ID);
+
+        macro_rules! compute {
+            ($computation_descriptor_account: ident) => {
+                compute_verification(
+                    &mut verification_account,
+                    &vkey,
+                    UnverifiedAccountInfo::new(&$computation_descriptor_account),
+                    &any,
+                    &governor,
+                    0,
+                    0,
+                    TestVKey::VKEY_ID,
+                )
+            };
+        }
+
+        // Schedule generated with the built-in constants
+        verification_account
+            .setup_public_inputs_instructions(&prepare_public_inputs_instructions(
+                &test_proof.public_inputs,
+                public_inputs_count,
+            ))
+            .unwrap();
+        assert_eq!(
+            compute!(computation_descriptor_account),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+
+        let instructions = prepare_public_inputs_instructions_with_descriptor(
+            &test_proof.public_inputs,
+            &descriptor,
+        );
+        verification_account
+            .setup_public_inputs_instructions(&instructions)
+            .unwrap();
+
+        // Missing descriptor
+        assert_eq!(compute!(any), Err(ElusivError::InvalidAccount.into()));
+
+        for _ in 0..instructions.len() + COMBINED_MILLER_LOOP_IXS + FINAL_EXPONENTIATION_IXS {
+            assert_eq!(compute!(computation_descriptor_account), Ok(()));
+        }
+        assert_eq!(verification_account.get_is_verified().option(), Some(true));
+    }
+
+    #[test]
+    fn test_compute_verification_prepare_inputs_mismatch() {
+        zero_program_account!(governor, GovernorAccount);
+        zero_program_account!(
+            mut verification_account,
+            VerificationAccount,
+            VerificationAccount::account_size(SendQuadraVKey::public_inputs_count())
+        );
+        vkey_account!(vkey, SendQuadraVKey);
+        vkey.set_version(&1);
+        test_account_info!(any, 0);
+
+        let public_inputs = test_public_inputs();
+        verification_account
+            .set_public_inputs(
+                &public_inputs
+                    .iter()
+                    .map(|&public_input| RawU256::new(public_input))
+                    .collect::<Vec<RawU256>>(),
+            )
+            .unwrap();
+        let instructions = prepare_public_inputs_instructions(
+            &public_inputs,
+            SendQuadraVKey::public_inputs_count(),
+        );
+        verification_account
+            .setup_public_inputs_instructions(&instructions)
+            .unwrap();
+
+        macro_rules! compute {
+            () => {
+                compute_verification(
+                    &mut verification_account,
+                    &vkey,
+                    UnverifiedAccountInfo::new(&any),
+                    &any,
+                    &governor,
+                    0,
+                    0,
+                    SendQuadraVKey::VKEY_ID,
+                )
+            };
+        }
+
+        // Stored instructions count too small
+        verification_account
+            .set_prepare_inputs_instructions_count(&(instructions.len() as u32 - 1));
+        assert_eq!(
+            compute!(),
+            Err(ElusivError::PrepareInputsInstructionsMismatch.into())
+        );
+
+        // Stored instructions count too large
+        verification_account
+            .set_prepare_inputs_instructions_count(&(instructions.len() as u32 + 1));
+        verification_account.set_prepare_inputs_instructions(instructions.len(), &1);
+        assert_eq!(
+            compute!(),
+            Err(ElusivError::PrepareInputsInstructionsMismatch.into())
+        );
+
+        // Invalid stored instructions count
+        verification_account.set_prepare_inputs_instructions_count(&u32::MAX);
+        assert_eq!(verification_account.prepare_inputs_rounds(), None);
+        assert_eq!(
+            compute!(),
+            Err(ElusivError::PrepareInputsInstructionsMismatch.into())
+        );
+
+        verification_account.set_prepare_inputs_instructions_count(&(instructions.len() as u32));
+        verification_account.set_prepare_inputs_instructions(instructions.len(), &0);
+
+        // Public inputs count does not match the vkey
+        verification_account
+            .set_public_inputs_count(&(SendQuadraVKey::public_inputs_count() as u32 - 1));
+        assert_eq!(
+            compute!(),
+            Err(ElusivError::PrepareInputsInstructionsMismatch.into())
+        );
+        verification_account
+            .set_public_inputs_count(&(SendQuadraVKey::public_inputs_count() as u32));
+
+        assert_eq!(compute!(), Ok(()));
+        assert_eq!(verification_account.get_instruction(), 1);
+
+        // The check is only performed before the first instruction
+        verification_account
+            .set_prepare_inputs_instructions_count(&(instructions.len() as u32 - 1));
+        assert_eq!(compute!(), Ok(()));
+    }
+
+    macro_rules! finalize_send_test {
+        (
+            $token_id: expr,
+            $optional_fee: expr,
+            $public_inputs: ident,
+            $v_data: ident,
+            $recipient: ident,
+            $identifier: ident,
+            $reference: ident,
+            $finalize_data: ident
+        ) => {
+            finalize_send_test!(
+                $token_id,
+                0,
+                $optional_fee,
+                $public_inputs,
+                $v_data,
+                $recipient,
+                $identifier,
+                $reference,
+                $finalize_data,
+                _optional_fee_collector
+            )
+        };
+        (
+            $token_id: expr,
+            $amount: expr,
+            $optional_fee: expr,
+            $public_inputs: ident,
+            $v_data: ident,
+            $recipient: ident,
+            $identifier: ident,
+            $reference: ident,
+            $finalize_data: ident,
+            $optional_fee_collector: ident
+        ) => {
+            finalize_send_test!(
+                $token_id,
+                $amount,
+                $optional_fee,
+                $public_inputs,
+                $v_data,
+                $recipient,
+                $identifier,
+                $reference,
+                $finalize_data,
+                $optional_fee_collector,
+                false
+            )
+        };
+        (
+            $token_id: expr,
+            $amount: expr,
+            $optional_fee: expr,
+            $public_inputs: ident,
+            $v_data: ident,
+            $recipient: ident,
+            $identifier: ident,
+            $reference: ident,
+            $finalize_data: ident,
+            $optional_fee_collector: ident,
+            $recipient_is_program_owned: expr
+        ) => {
+            let $recipient = if $recipient_is_program_owned {
+                // Multi-sig vault PDA
+                Pubkey::find_program_address(&[b"vault"], &Pubkey::new_unique())
+                    .0
+                    .to_bytes()
+            } else {
+                Pubkey::new_unique().to_bytes()
+            };
+            let $identifier = Pubkey::new_unique().to_bytes();
+            let $reference = Pubkey::new_unique().to_bytes();
+            let iv = Pubkey::new_unique().to_bytes();
+            let encrypted_owner = Pubkey::new_unique().to_bytes();
+
+            let metadata = CommitmentMetadata::default();
+            let $optional_fee_collector = Pubkey::new_unique();
+            let optional_fee = OptionalFee {
+                collector: $optional_fee_collector,
+                amount: $optional_fee,
+            };
+            let $public_inputs = SendPublicInputs {
+                join_split: JoinSplitPublicInputs {
+                    input_commitments: vec![InputCommitment {
+                        root: Some(empty_root_raw()),
+                        nullifier_hash: RawU256::new(u256_from_str_skip_mr("1")),
+                    }],
+                    output_commitment: RawU256::new(u256_from_str_skip_mr("987654321")),
+                    recent_commitment_index: 123,
+                    fee_version: 0,
+                    amount: $amount,
+                    fee: 10000,
+                    optional_fee: optional_fee.clone(),
+                    token_id: $token_id,
+                    metadata,
+                },
+                recipient_is_associated_token_account: false,
+                hashed_inputs: generate_hashed_inputs(
+                    &$recipient,
+                    &$identifier,
+                    &iv,
+                    &encrypted_owner,
+                    &$reference,
+                    false,
+                    $recipient_is_program_owned,
+                    0,
+                    0,
+                    &metadata,
+                    &optional_fee,
+                    &None,
+                    &None,
+                    &None,
+                )
+                .unwrap(),
+                solana_pay_transfer: false,
+                recipient_is_program_owned: $recipient_is_program_owned,
+                view_tag: 0,
+                current_time: 0,
+            };
+
+            let mut $v_data = vec![0; VerificationAccount::SIZE];
+            let mut v_account = VerificationAccount::new(&mut $v_data).unwrap();
+            let fee_payer = RawU256::new(Pubkey::new_unique().to_bytes());
+            v_account
+                .setup(
+                    fee_payer,
+                    false,
+                    &[],
+                    &vec![0],
+                    0,
+                    ProofRequest::Send($public_inputs.clone()),
+                    [0, 1],
+                )
+                .unwrap();
+            v_account.set_state(&VerificationState::ProofSetup);
+            v_account.set_is_verified(&ElusivOption::Some(true));
+            v_account.set_other_data(&VerificationAccountData {
+                fee_payer,
+                fee_payer_account: fee_payer,
+                recipient_wallet: ElusivOption::Some(RawU256::new($recipient)),
+                mint: RawU256::new(elusiv_token($token_id).unwrap().mint.to_bytes()),
+                ..Default::default()
+            });
+
+            let $finalize_data = FinalizeSendData {
+                total_amount: $public_inputs.join_split.total_amount(),
+                token_id: $token_id,
+                mt_index: 0,
+                commitment_index: 0,
+                encrypted_owner,
+                iv,
+                encrypted_memo: None,
+                fallback_recipient: None,
+            };
+        };
+    }
+
+    macro_rules! simple_storage_account {
+        ($id: ident) => {
+            let mut data = vec![0; StorageAccount::SIZE];
+            let $id =
+                <StorageAccount as elusiv_types::accounts::ProgramAccount>::new(&mut data).unwrap();
+        };
+    }
+
+    #[test]
+    fn test_finalize_verification_send_valid() {
+        finalize_send_test!(
+            USDC_TOKEN_ID,
+            LAMPORTS_PER_SOL,
+            public_inputs,
+            verification_acc_data,
+            recipient_bytes,
+            identifier_bytes,
+            reference_bytes,
+            finalize_data
+        );
+
+        let mut verification_acc = VerificationAccount::new(&mut verification_acc_data).unwrap();
+        let mut data = vec![0; CommitmentQueueAccount::SIZE];
+        let mut queue = CommitmentQueueAccount::new(&mut data).unwrap();
+        simple_storage_account!(storage);
+        zero_program_account!(governor, GovernorAccount);
+        zero_program_account!(mut buffer, CommitmentBufferAccount);
+
+        account_info!(recipient, Pubkey::new_from_array(recipient_bytes));
+        account_info!(identifier, Pubkey::new_from_array(identifier_bytes));
+        account_info!(reference, Pubkey::new_from_array(reference_bytes));
+        test_account_info!(any, 0);
+
+        // Verification is not finished
+        verification_acc.set_is_verified(&ElusivOption::None);
+        assert_eq!(
+            finalize_verification_send(
+                &recipient,
+                &identifier,
+                &reference,
+                &mut queue,
+                &mut verification_acc,
+                &storage,
+                &mut buffer,
+                &governor,
+                &any,
+                0,
+                0,
+                finalize_data.clone(),
+                false,
             ),
-            Err(ElusivError::InvalidMerkleRoot.into())
+            Err(ElusivError::ComputationIsNotYetFinished.into())
         );
 
-        // First root is None
+        verification_acc.set_is_verified(&ElusivOption::Some(true));
+
+        // Invalid recipient
+        {
+            account_info!(recipient, Pubkey::new_from_array(identifier_bytes));
+            assert_eq!(
+                finalize_verification_send(
+                    &recipient,
+                    &identifier,
+                    &reference,
+                    &mut queue,
+                    &mut verification_acc,
+                    &storage,
+                    &mut buffer,
+                    &governor,
+                    &any,
+                    0,
+                    0,
+                    finalize_data.clone(),
+                    false,
+                ),
+                Err(ElusivError::InputsMismatch.into())
+            );
+        }
+
+        // Invalid identifier
+        {
+            account_info!(identifier, Pubkey::new_from_array(recipient_bytes));
+            assert_eq!(
+                finalize_verification_send(
+                    &recipient,
+                    &identifier,
+                    &reference,
+                    &mut queue,
+                    &mut verification_acc,
+                    &storage,
+                    &mut buffer,
+                    &governor,
+                    &any,
+                    0,
+                    0,
+                    finalize_data.clone(),
+                    false,
+                ),
+                Err(ElusivError::InputsMismatch.into())
+            );
+        }
+
+        // Invalid reference
+        {
+            account_info!(reference, Pubkey::new_from_array(recipient_bytes));
+            assert_eq!(
+                finalize_verification_send(
+                    &recipient,
+                    &identifier,
+                    &reference,
+                    &mut queue,
+                    &mut verification_acc,
+                    &storage,
+                    &mut buffer,
+                    &governor,
+                    &any,
+                    0,
+                    0,
+                    finalize_data.clone(),
+                    false,
+                ),
+                Err(ElusivError::InputsMismatch.into())
+            );
+        }
+
+        // Invalid finalize_data
+        for invalid_data in [
+            mutate(&finalize_data, |d| d.total_amount += 1),
+            mutate(&finalize_data, |d| d.token_id = 0),
+            mutate(&finalize_data, |d| d.commitment_index = 1),
+            mutate(&finalize_data, |d| d.mt_index = 1),
+            mutate(&finalize_data, |d| d.encrypted_owner = d.iv),
+            mutate(&finalize_data, |d| d.iv = d.encrypted_owner),
+        ] {
+            assert_eq!(
+                finalize_verification_send(
+                    &recipient,
+                    &identifier,
+                    &reference,
+                    &mut queue,
+                    &mut verification_acc,
+                    &storage,
+                    &mut buffer,
+                    &governor,
+                    &any,
+                    0,
+                    0,
+                    invalid_data,
+                    false,
+                ),
+                Err(ElusivError::InputsMismatch.into())
+            );
+        }
+
+        // Amount (excluding the fee) passed as the total amount
         assert_eq!(
-            init_verification(
-                &fee_payer,
-                &v_acc,
-                &vkey,
-                &n_duplicate_acc,
+            finalize_verification_send(
+                &recipient,
                 &identifier,
+                &reference,
+                &mut queue,
+                &mut verification_acc,
                 &storage,
                 &mut buffer,
-                &nullifier,
-                &nullifier,
+                &governor,
+                &any,
                 0,
-                vkey_id,
-                [0, 1],
-                Send(mutate(&inputs, |v| {
-                    v.join_split.input_commitments[0].root = None;
-                })),
+                0,
+                mutate(&finalize_data, |d| {
+                    d.total_amount = public_inputs.join_split.amount
+                }),
                 false,
             ),
-            Err(ElusivError::InvalidPublicInputs.into())
+            Err(ElusivError::AmountInsteadOfTotalAmount.into())
         );
 
-        // Invalid recent-commitment-index
+        // Success
         assert_eq!(
-            init_verification(
-                &fee_payer,
-                &v_acc,
-                &vkey,
-                &n_duplicate_acc,
+            finalize_verification_send(
+                &recipient,
                 &identifier,
+                &reference,
+                &mut queue,
+                &mut verification_acc,
                 &storage,
                 &mut buffer,
-                &nullifier,
-                &nullifier,
+                &governor,
+                &any,
                 0,
-                vkey_id,
-                [0, 1],
-                Send(mutate(&inputs, |inputs| {
-                    inputs.join_split.recent_commitment_index = 1;
-                    compute_fee_rec_lamports::<SendQuadraVKey, _>(inputs, &fee());
-                })),
+                0,
+                finalize_data.clone(),
                 false,
             ),
-            Err(ElusivError::InvalidRecentCommitmentIndex.into())
+            Ok(())
         );
 
-        // Mismatched tree indices
         assert_eq!(
-            init_verification(
-                &fee_payer,
-                &v_acc,
-                &vkey,
-                &n_duplicate_acc,
-                &identifier,
-                &storage,
-                &mut buffer,
-                &nullifier,
-                &nullifier,
-                0,
-                vkey_id,
-                [1, 0],
-                Send(inputs.clone()),
-                false,
-            ),
-            Err(ElusivError::InvalidMerkleRoot.into())
+            verification_acc.get_state(),
+            VerificationState::InsertNullifiers
         );
 
-        // Zero commitment
+        // Called twice
         assert_eq!(
-            init_verification(
-                &fee_payer,
-                &v_acc,
-                &vkey,
-                &n_duplicate_acc,
+            finalize_verification_send(
+                &recipient,
                 &identifier,
+                &reference,
+                &mut queue,
+                &mut verification_acc,
                 &storage,
                 &mut buffer,
-                &nullifier,
-                &nullifier,
+                &governor,
+                &any,
                 0,
-                vkey_id,
-                [0, 1],
-                Send(mutate(&inputs, |v| {
-                    v.join_split.output_commitment = RawU256::new(ZERO_COMMITMENT_RAW);
-                })),
+                0,
+                finalize_data,
                 false,
             ),
-            Err(ElusivError::InvalidPublicInputs.into())
+            Err(ElusivError::InvalidAccountState.into())
+        );
+    }
+
+    #[test]
+    fn test_finalize_verification_send_duplicate_output_commitment() {
+        finalize_send_test!(
+            USDC_TOKEN_ID,
+            LAMPORTS_PER_SOL,
+            public_inputs,
+            verification_acc_data,
+            recipient_bytes,
+            identifier_bytes,
+            reference_bytes,
+            finalize_data
         );
 
-        // Nullifier already exists
-        nullifier
-            .try_insert_nullifier_hash(
-                inputs.join_split.input_commitments[0]
-                    .nullifier_hash
-                    .reduce(),
-            )
-            .unwrap();
+        let mut verification_acc = VerificationAccount::new(&mut verification_acc_data).unwrap();
+        verification_acc.set_is_verified(&ElusivOption::Some(true));
+        let mut data = vec![0; CommitmentQueueAccount::SIZE];
+        let mut queue = CommitmentQueueAccount::new(&mut data).unwrap();
+        simple_storage_account!(storage);
+        zero_program_account!(governor, GovernorAccount);
+        zero_program_account!(mut buffer, CommitmentBufferAccount);
+
+        account_info!(recipient, Pubkey::new_from_array(recipient_bytes));
+        account_info!(identifier, Pubkey::new_from_array(identifier_bytes));
+        account_info!(reference, Pubkey::new_from_array(reference_bytes));
+        test_account_info!(any, 0);
+
+        // The output commitment is already enqueued (behind a different commitment)
+        {
+            let mut queue = CommitmentQueue::new(&mut queue);
+            for commitment in [[1; 32], public_inputs.join_split.output_commitment.reduce()] {
+                queue
+                    .enqueue(CommitmentHashRequest {
+                        commitment,
+                        fee_version: 0,
+                        min_batching_rate: 0,
+                        view_tag: 0,
+                    })
+                    .unwrap();
+            }
+        }
+
         assert_eq!(
-            init_verification(
-                &fee_payer,
-                &v_acc,
-                &vkey,
-                &n_duplicate_acc,
+            finalize_verification_send(
+                &recipient,
                 &identifier,
+                &reference,
+                &mut queue,
+                &mut verification_acc,
                 &storage,
                 &mut buffer,
-                &nullifier,
-                &nullifier,
+                &governor,
+                &any,
                 0,
-                vkey_id,
-                [0, 1],
-                Send(inputs.clone()),
+                0,
+                finalize_data.clone(),
                 false,
             ),
-            Err(ElusivError::CouldNotInsertNullifier.into())
+            Err(ElusivError::DuplicateOutputCommitment.into())
         );
 
-        // Invalid nullifier_duplicate_account
-        parent_account!(nullifier, NullifierAccount);
-        account_info!(
-            invalid_n_duplicate_acc,
-            VerificationAccount::find_with_pubkey(*fee_payer.key, Some(0)).0,
-            vec![1]
-        );
+        // The output commitment has been dequeued
+        CommitmentQueue::new(&mut queue).remove(2).unwrap();
+
         assert_eq!(
-            init_verification(
-                &fee_payer,
-                &v_acc,
-                &vkey,
-                &invalid_n_duplicate_acc,
+            finalize_verification_send(
+                &recipient,
                 &identifier,
+                &reference,
+                &mut queue,
+                &mut verification_acc,
                 &storage,
                 &mut buffer,
-                &nullifier,
-                &nullifier,
+                &governor,
+                &any,
                 0,
-                vkey_id,
-                [0, 1],
-                Send(inputs.clone()),
+                0,
+                finalize_data,
                 false,
             ),
-            Err(ProgramError::InvalidSeeds)
+            Ok(())
         );
+    }
 
-        // TODO: Invalid nullifier_duplicate_account with skip set to true
+    #[test]
+    fn test_finalize_verification_send_encrypted_memo() {
+        finalize_send_test!(
+            USDC_TOKEN_ID,
+            LAMPORTS_PER_SOL,
+            public_inputs,
+            verification_acc_data,
+            recipient_bytes,
+            identifier_bytes,
+            reference_bytes,
+            finalize_data
+        );
+
+        // Opaque (client-side encrypted) memo
+        let encrypted_memo: Vec<u8> = (0..MAX_ENCRYPTED_MEMO_SIZE).map(|i| i as u8).collect();
+        let mut public_inputs = public_inputs;
+        public_inputs.hashed_inputs = generate_hashed_inputs(
+            &recipient_bytes,
+            &identifier_bytes,
+            &finalize_data.iv,
+            &finalize_data.encrypted_owner,
+            &reference_bytes,
+            false,
+            false,
+            0,
+            0,
+            &public_inputs.join_split.metadata,
+            &public_inputs.join_split.optional_fee,
+            &None,
+            &Some(encrypted_memo.clone()),
+            &None,
+        )
+        .unwrap();
+
+        let mut verification_acc = VerificationAccount::new(&mut verification_acc_data).unwrap();
+        verification_acc.set_request(&ProofRequest::Send(public_inputs));
+        let mut data = vec![0; CommitmentQueueAccount::SIZE];
+        let mut queue = CommitmentQueueAccount::new(&mut data).unwrap();
+        simple_storage_account!(storage);
+        zero_program_account!(governor, GovernorAccount);
+        zero_program_account!(mut buffer, CommitmentBufferAccount);
+
+        account_info!(recipient, Pubkey::new_from_array(recipient_bytes));
+        account_info!(identifier, Pubkey::new_from_array(identifier_bytes));
+        account_info!(reference, Pubkey::new_from_array(reference_bytes));
+        test_account_info!(any, 0);
+
+        let mut modified_memo = encrypted_memo.clone();
+        modified_memo[0] ^= 1;
+
+        for (invalid_memo, error) in [
+            // Missing memo
+            (None, ElusivError::InputsMismatch),
+            // Modified memo
+            (Some(modified_memo), ElusivError::InputsMismatch),
+            // Truncated memo
+            (
+                Some(encrypted_memo[..MAX_ENCRYPTED_MEMO_SIZE - 1].to_vec()),
+                ElusivError::InputsMismatch,
+            ),
+            // Empty memo
+            (Some(Vec::new()), ElusivError::InvalidEncryptedMemo),
+            // Memo too large
+            (
+                Some(vec![0; MAX_ENCRYPTED_MEMO_SIZE + 1]),
+                ElusivError::InvalidEncryptedMemo,
+            ),
+        ] {
+            assert_eq!(
+                finalize_verification_send(
+                    &recipient,
+                    &identifier,
+                    &reference,
+                    &mut queue,
+                    &mut verification_acc,
+                    &storage,
+                    &mut buffer,
+                    &governor,
+                    &any,
+                    0,
+                    0,
+                    mutate(&finalize_data, |d| d.encrypted_memo = invalid_memo.clone()),
+                    false,
+                ),
+                Err(error.into())
+            );
+        }
+
+        // Success
         assert_eq!(
-            init_verification(
-                &fee_payer,
-                &v_acc,
-                &vkey,
-                &invalid_n_duplicate_acc,
+            finalize_verification_send(
+                &recipient,
                 &identifier,
+                &reference,
+                &mut queue,
+                &mut verification_acc,
                 &storage,
                 &mut buffer,
-                &nullifier,
-                &nullifier,
+                &governor,
+                &any,
                 0,
-                vkey_id,
-                [0, 1],
-                Send(inputs.clone()),
-                true,
+                0,
+                mutate(&finalize_data, |d| d.encrypted_memo =
+                    Some(encrypted_memo.clone())),
+                false,
             ),
-            Err(ElusivError::InvalidAccount.into())
+            Ok(())
         );
+        assert_eq!(
+            verification_acc.get_state(),
+            VerificationState::InsertNullifiers
+        );
+    }
 
-        let mut data = vec![0; VKeyAccount::SIZE];
-        let mut migrate_vkey = VKeyAccount::new(&mut data).unwrap();
-        migrate_vkey.set_public_inputs_count(&MigrateUnaryVKey::PUBLIC_INPUTS_COUNT);
-        migrate_vkey.set_version(&1);
+    #[test]
+    fn test_finalize_verification_send_wrapped_queue() {
+        finalize_send_test!(
+            USDC_TOKEN_ID,
+            LAMPORTS_PER_SOL,
+            _public_inputs,
+            verification_acc_data,
+            recipient_bytes,
+            identifier_bytes,
+            reference_bytes,
+            finalize_data
+        );
 
-        // Migrate always fails
+        simple_storage_account!(storage);
+        zero_program_account!(governor, GovernorAccount);
+        zero_program_account!(mut buffer, CommitmentBufferAccount);
+        account_info!(recipient, Pubkey::new_from_array(recipient_bytes));
+        account_info!(identifier, Pubkey::new_from_array(identifier_bytes));
+        account_info!(reference, Pubkey::new_from_array(reference_bytes));
+        test_account_info!(any, 0);
+
+        // Drive the queue past the wrap point (three commitments remain enqueued)
+        zero_program_account!(mut queue, CommitmentQueueAccount);
+        {
+            let mut commitment_queue = CommitmentQueue::new(&mut queue);
+            let size = CommitmentQueue::SIZE;
+            commitment_queue.set_head(&(size - 2));
+            commitment_queue.set_tail(&(size - 2));
+
+            for i in 0..3 {
+                commitment_queue
+                    .enqueue(CommitmentHashRequest {
+                        commitment: [i + 1; 32],
+                        fee_version: 0,
+                        min_batching_rate: 0,
+                        view_tag: 0,
+                    })
+                    .unwrap();
+            }
+            assert_eq!(commitment_queue.get_tail(), 1);
+            assert_eq!(commitment_queue.len(), 3);
+        }
+
+        let mut verification_acc = VerificationAccount::new(&mut verification_acc_data).unwrap();
+
+        // Commitment index estimated beyond the enqueued commitments
         assert_eq!(
-            init_verification(
-                &fee_payer,
-                &v_acc,
-                &migrate_vkey,
-                &n_duplicate_acc,
+            finalize_verification_send(
+                &recipient,
                 &identifier,
+                &reference,
+                &mut queue,
+                &mut verification_acc,
                 &storage,
                 &mut buffer,
-                &nullifier,
-                &nullifier,
+                &governor,
+                &any,
                 0,
-                MigrateUnaryVKey::VKEY_ID,
-                [0, 1],
-                Migrate(MigratePublicInputs {
-                    join_split: inputs.join_split.clone(),
-                    current_nsmt_root: RawU256::new([0; 32]),
-                    next_nsmt_root: RawU256::new([0; 32]),
-                }),
+                0,
+                mutate(&finalize_data, |d| d.commitment_index = 4),
                 false,
             ),
-            Err(ElusivError::FeatureNotAvailable.into())
+            Err(ElusivError::InputsMismatch.into())
         );
 
         assert_eq!(
-            init_verification(
-                &fee_payer,
-                &v_acc,
-                &vkey,
-                &n_duplicate_acc,
+            finalize_verification_send(
+                &recipient,
                 &identifier,
+                &reference,
+                &mut queue,
+                &mut verification_acc,
                 &storage,
                 &mut buffer,
-                &nullifier,
-                &nullifier,
+                &governor,
+                &any,
                 0,
-                vkey_id,
-                [0, 1],
-                Send(inputs.clone()),
+                0,
+                mutate(&finalize_data, |d| d.commitment_index = 3),
                 false,
             ),
             Ok(())
         );
+        assert_eq!(
+            verification_acc.get_state(),
+            VerificationState::InsertNullifiers
+        );
+    }
 
-        let mut inputs = inputs.clone();
-        inputs.join_split.input_commitments[0].nullifier_hash =
-            RawU256::new(u256_from_str_skip_mr("2"));
-        compute_fee_rec_lamports::<SendQuadraVKey, _>(&mut inputs, &fee());
+    #[test]
+    fn test_finalize_verification_send_program_owned_lamports_recipient() {
+        for (recipient_is_program_owned, enforce, system_owned, result) in [
+            // Token account recipient
+            (false, false, false, Ok(())),
+            (
+                false,
+                true,
+                false,
+                Err(ElusivError::InvalidRecipient.into()),
+            ),
+            (true, true, false, Ok(())),
+            // System-owned recipient
+            (false, true, true, Ok(())),
+        ] {
+            finalize_send_test!(
+                LAMPORTS_TOKEN_ID,
+                LAMPORTS_PER_SOL,
+                0,
+                _public_inputs,
+                verification_acc_data,
+                recipient_bytes,
+                identifier_bytes,
+                reference_bytes,
+                finalize_data,
+                _optional_fee_collector,
+                recipient_is_program_owned
+            );
 
-        account_info!(
-            n_duplicate_acc,
-            inputs.join_split.nullifier_duplicate_pda().0,
-            vec![1]
-        );
+            let mut verification_acc =
+                VerificationAccount::new(&mut verification_acc_data).unwrap();
+            zero_program_account!(mut queue, CommitmentQueueAccount);
+            simple_storage_account!(storage);
+            zero_program_account!(mut governor, GovernorAccount);
+            governor.set_enforce_system_owned_lamports_recipient(&enforce);
+            zero_program_account!(mut buffer, CommitmentBufferAccount);
+
+            let owner = if system_owned {
+                system_program::ID
+            } else {
+                spl_token::id()
+            };
+            account_info!(
+                recipient,
+                Pubkey::new_from_array(recipient_bytes),
+                spl_token_account_data(USDC_TOKEN_ID),
+                owner,
+                false
+            );
+            account_info!(identifier, Pubkey::new_from_array(identifier_bytes));
+            account_info!(reference, Pubkey::new_from_array(reference_bytes));
+            test_account_info!(any, 0);
 
-        // Duplicate commitment insertion will fail and only succeed after COMMITMENT_BUFFER_LEN insertions
-        for i in 0..COMMITMENT_BUFFER_LEN {
             assert_eq!(
-                init_verification(
-                    &fee_payer,
-                    &v_acc,
-                    &vkey,
-                    &n_duplicate_acc,
+                finalize_verification_send(
+                    &recipient,
                     &identifier,
+                    &reference,
+                    &mut queue,
+                    &mut verification_acc,
                     &storage,
                     &mut buffer,
-                    &nullifier,
-                    &nullifier,
+                    &governor,
+                    &any,
                     0,
-                    vkey_id,
-                    [0, 1],
-                    Send(inputs.clone()),
+                    0,
+                    finalize_data,
                     false,
                 ),
-                Err(ElusivError::DuplicateValue.into())
+                result
             );
+        }
+    }
 
-            buffer.try_insert(&[i as u8; 32]).unwrap();
+    #[test]
+    fn test_finalize_verification_send_fallback_recipient() {
+        finalize_send_test!(
+            USDC_TOKEN_ID,
+            LAMPORTS_PER_SOL,
+            public_inputs,
+            verification_acc_data,
+            recipient_bytes,
+            identifier_bytes,
+            reference_bytes,
+            finalize_data
+        );
+
+        let fallback_recipient = Pubkey::new_unique().to_bytes();
+        let mut public_inputs = public_inputs;
+        public_inputs.hashed_inputs = generate_hashed_inputs(
+            &recipient_bytes,
+            &identifier_bytes,
+            &finalize_data.iv,
+            &finalize_data.encrypted_owner,
+            &reference_bytes,
+            false,
+            false,
+            0,
+            0,
+            &public_inputs.join_split.metadata,
+            &public_inputs.join_split.optional_fee,
+            &None,
+            &None,
+            &Some(fallback_recipient),
+        )
+        .unwrap();
+
+        let mut verification_acc = VerificationAccount::new(&mut verification_acc_data).unwrap();
+        verification_acc.set_request(&ProofRequest::Send(public_inputs));
+        zero_program_account!(mut queue, CommitmentQueueAccount);
+        simple_storage_account!(storage);
+        zero_program_account!(governor, GovernorAccount);
+        zero_program_account!(mut buffer, CommitmentBufferAccount);
+
+        account_info!(recipient, Pubkey::new_from_array(recipient_bytes));
+        account_info!(identifier, Pubkey::new_from_array(identifier_bytes));
+        account_info!(reference, Pubkey::new_from_array(reference_bytes));
+        test_account_info!(any, 0);
+
+        // Missing or modified fallback recipient
+        for invalid_fallback_recipient in [None, Some(Pubkey::new_unique().to_bytes())] {
+            assert_eq!(
+                finalize_verification_send(
+                    &recipient,
+                    &identifier,
+                    &reference,
+                    &mut queue,
+                    &mut verification_acc,
+                    &storage,
+                    &mut buffer,
+                    &governor,
+                    &any,
+                    0,
+                    0,
+                    mutate(&finalize_data, |d| d.fallback_recipient =
+                        invalid_fallback_recipient),
+                    false,
+                ),
+                Err(ElusivError::InputsMismatch.into())
+            );
         }
 
+        finalize_verification_send(
+            &recipient,
+            &identifier,
+            &reference,
+            &mut queue,
+            &mut verification_acc,
+            &storage,
+            &mut buffer,
+            &governor,
+            &any,
+            0,
+            0,
+            mutate(&finalize_data, |d| {
+                d.fallback_recipient = Some(fallback_recipient)
+            }),
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            verification_acc.get_other_data().fallback_recipient,
+            ElusivOption::Some(RawU256::new(fallback_recipient))
+        );
+    }
+
+    #[test]
+    fn test_finalize_verification_send_invalid() {
+        finalize_send_test!(
+            USDC_TOKEN_ID,
+            LAMPORTS_PER_SOL,
+            public_inputs,
+            verification_acc_data,
+            recipient_bytes,
+            identifier_bytes,
+            reference_bytes,
+            finalize_data
+        );
+
+        let mut verification_acc = VerificationAccount::new(&mut verification_acc_data).unwrap();
+        let mut data = vec![0; CommitmentQueueAccount::SIZE];
+        let mut queue = CommitmentQueueAccount::new(&mut data).unwrap();
+        simple_storage_account!(storage);
+        zero_program_account!(governor, GovernorAccount);
+        zero_program_account!(mut buffer, CommitmentBufferAccount);
+        test_account_info!(any, 0);
+
+        account_info!(recipient, Pubkey::new_from_array(recipient_bytes));
+        account_info!(identifier, Pubkey::new_from_array(identifier_bytes));
+        account_info!(reference, Pubkey::new_from_array(reference_bytes));
+
+        verification_acc.set_is_verified(&ElusivOption::Some(false));
+
         assert_eq!(
-            init_verification(
-                &fee_payer,
-                &v_acc,
-                &vkey,
-                &n_duplicate_acc,
+            finalize_verification_send(
+                &recipient,
                 &identifier,
+                &reference,
+                &mut queue,
+                &mut verification_acc,
                 &storage,
                 &mut buffer,
-                &nullifier,
-                &nullifier,
+                &governor,
+                &any,
                 0,
-                vkey_id,
-                [0, 1],
-                Send(inputs.clone()),
+                0,
+                finalize_data,
                 false,
             ),
             Ok(())
         );
+        assert_eq!(verification_acc.get_state(), VerificationState::Finalized);
     }
 
     #[test]
-    #[should_panic]
-    fn test_init_verification_commitment_count_too_high() {
-        parent_account!(storage, StorageAccount);
-        parent_account!(nullifier, NullifierAccount);
-        zero_program_account!(mut buffer, CommitmentBufferAccount);
-        test_account_info!(fee_payer, 0);
-        test_account_info!(identifier, 0);
-        account_info!(
-            v_acc,
-            VerificationAccount::find_with_pubkey(*fee_payer.key, Some(0)).0,
-            vec![0; VerificationAccount::SIZE]
-        );
-
-        let mut inputs = SendPublicInputs {
+    fn test_finalize_verification_migrate() {
+        let migrate_public_inputs = MigratePublicInputs {
             join_split: JoinSplitPublicInputs {
                 input_commitments: vec![InputCommitment {
                     root: Some(empty_root_raw()),
@@ -1879,1163 +6400,2056 @@ mod tests {
                 recent_commitment_index: 123,
                 fee_version: 0,
                 amount: LAMPORTS_PER_SOL,
-                fee: 0,
+                fee: 10000,
                 optional_fee: OptionalFee::default(),
                 token_id: 0,
                 metadata: CommitmentMetadata::default(),
             },
-            recipient_is_associated_token_account: true,
-            hashed_inputs: u256_from_str_skip_mr("1"),
-            solana_pay_transfer: false,
+            current_nsmt_root: RawU256::new([0; 32]),
+            next_nsmt_root: RawU256::new([0; 32]),
         };
-        compute_fee_rec_lamports::<SendQuadraVKey, _>(&mut inputs, &fee());
 
-        account_info!(
-            n_duplicate_acc,
-            inputs.join_split.nullifier_duplicate_pda().0,
-            vec![1]
+        let mut data = vec![0; VerificationAccount::SIZE];
+        let mut v_account = VerificationAccount::new(&mut data).unwrap();
+        v_account.set_request(&ProofRequest::Migrate(migrate_public_inputs));
+        v_account.set_state(&VerificationState::ProofSetup);
+        v_account.set_is_verified(&ElusivOption::Some(true));
+
+        zero_program_account!(mut queue, CommitmentQueueAccount);
+        simple_storage_account!(storage);
+        zero_program_account!(governor, GovernorAccount);
+        zero_program_account!(mut buffer, CommitmentBufferAccount);
+        test_account_info!(any, 0);
+
+        // Migrate requests are not available
+        assert_eq!(
+            finalize_verification_send(
+                &any,
+                &any,
+                &any,
+                &mut queue,
+                &mut v_account,
+                &storage,
+                &mut buffer,
+                &governor,
+                &any,
+                0,
+                0,
+                FinalizeSendData::default(),
+                false,
+            ),
+            Err(ElusivError::FeatureNotAvailable.into())
         );
+        assert_eq!(v_account.get_state(), VerificationState::ProofSetup);
+    }
 
-        let mut data = vec![0; VKeyAccount::SIZE];
-        let mut vkey = VKeyAccount::new(&mut data).unwrap();
-        vkey.set_public_inputs_count(&SendQuadraVKey::PUBLIC_INPUTS_COUNT);
-        vkey.set_is_frozen(&true);
+    #[test]
+    fn test_finalize_verification_insert_nullifier() {
+        finalize_send_test!(
+            USDC_TOKEN_ID,
+            LAMPORTS_PER_SOL,
+            public_inputs,
+            verification_acc_data,
+            _recipient_bytes,
+            _identifier_bytes,
+            _reference_bytes,
+            _finalize_data
+        );
 
-        for i in inputs.join_split.input_commitments.len()..JOIN_SPLIT_MAX_N_ARITY + 1 {
-            inputs.join_split.input_commitments.push(InputCommitment {
-                root: None,
-                nullifier_hash: RawU256::new(u256_from_str_skip_mr(&i.to_string())),
-            });
-        }
+        let mut verification_acc = VerificationAccount::new(&mut verification_acc_data).unwrap();
+        parent_account!(mut n_acc_0, NullifierAccount);
 
-        let _ = init_verification(
-            &fee_payer,
-            &v_acc,
-            &vkey,
-            &n_duplicate_acc,
-            &identifier,
-            &storage,
-            &mut buffer,
-            &nullifier,
-            &nullifier,
-            0,
-            0,
-            [0, 1],
-            ProofRequest::Send(inputs),
-            false,
+        // finalize_verification_send not called
+        verification_acc.set_state(&VerificationState::InsertNullifiers);
+
+        // Nullifier duplicate
+        n_acc_0
+            .try_insert_nullifier_hash(
+                public_inputs.join_split.input_commitments[0]
+                    .nullifier_hash
+                    .reduce(),
+            )
+            .unwrap();
+        assert_eq!(
+            finalize_verification_insert_nullifier(&mut verification_acc, &mut n_acc_0, 0, 0),
+            Err(ElusivError::CouldNotInsertNullifier.into())
+        );
+
+        parent_account!(mut n_acc_0, NullifierAccount);
+
+        // Success
+        assert_eq!(
+            finalize_verification_insert_nullifier(&mut verification_acc, &mut n_acc_0, 0, 0),
+            Ok(())
+        );
+
+        assert!(!n_acc_0
+            .can_insert_nullifier_hash(
+                public_inputs.join_split.input_commitments[0]
+                    .nullifier_hash
+                    .reduce()
+            )
+            .unwrap());
+        assert_eq!(verification_acc.get_state(), VerificationState::Finalized);
+
+        // Called twice
+        assert_eq!(
+            finalize_verification_insert_nullifier(&mut verification_acc, &mut n_acc_0, 0, 0),
+            Err(ElusivError::InvalidAccountState.into())
         );
     }
 
     #[test]
-    fn test_init_verification_transfer_fee_lamports() {
-        test_account_info!(fee_payer, 0);
+    fn test_finalize_verification_insert_nullifier_race() -> ProgramResult {
+        zero_program_account!(governor, GovernorAccount);
+        test_account_info!(rent_recipient, 0);
+        zero_pda_account_info!(
+            network_fee,
+            NetworkFeeAccount,
+            Some(LAMPORTS_TOKEN_ID.into())
+        );
+        test_account_info!(v_index_acc, 0);
+        finalize_send_test!(
+            LAMPORTS_TOKEN_ID,
+            LAMPORTS_PER_SOL,
+            0,
+            public_inputs,
+            verification_acc_data,
+            _recipient_bytes,
+            _i,
+            _r,
+            _f,
+            _optional_fee_collector
+        );
+        let nullifier_hash = public_inputs.join_split.input_commitments[0]
+            .nullifier_hash
+            .reduce();
+
+        // Two concurrent verifications of the same nullifier-hash using `skip_nullifier_pda`
+        {
+            let mut verification_acc = VerificationAccount::new(&mut verification_acc_data)?;
+            verification_acc.set_state(&VerificationState::InsertNullifiers);
+
+            let mut data = verification_acc.get_other_data();
+            data.skip_nullifier_pda = true;
+            data.subvention = 100;
+            data.commitment_hash_fee = Lamports(200);
+            verification_acc.set_other_data(&data);
+        }
+        let mut verification_acc_data_b = verification_acc_data.clone();
+        parent_account!(mut n_acc_0, NullifierAccount);
+
+        let mut verification_acc_a = VerificationAccount::new(&mut verification_acc_data)?;
+        finalize_verification_insert_nullifier(&mut verification_acc_a, &mut n_acc_0, 0, 0)?;
+        assert_eq!(verification_acc_a.get_state(), VerificationState::Finalized);
+        assert_eq!(
+            verification_acc_a.get_is_verified(),
+            ElusivOption::Some(true)
+        );
+        assert!(!n_acc_0.can_insert_nullifier_hash(nullifier_hash)?);
+        assert_eq!(n_acc_0.get_nullifier_hash_count(), 1);
+
+        // The second verification loses the race and is treated as invalid
+        {
+            let mut verification_acc_b = VerificationAccount::new(&mut verification_acc_data_b)?;
+            finalize_verification_insert_nullifier(&mut verification_acc_b, &mut n_acc_0, 0, 0)?;
+            assert_eq!(verification_acc_b.get_state(), VerificationState::Finalized);
+            assert_eq!(
+                verification_acc_b.get_is_verified(),
+                ElusivOption::Some(false)
+            );
+            assert_eq!(n_acc_0.get_nullifier_hash_count(), 1);
+        }
+
+        // The funds of the second verification flow to the fee-collector
+        let fee_payer_pk = Pubkey::new_from_array(
+            VerificationAccount::new(&mut verification_acc_data_b)?
+                .get_other_data()
+                .fee_payer
+                .skip_mr(),
+        );
+        account_info!(f, fee_payer_pk);
+        account_info!(recipient, Pubkey::new_unique());
         test_account_info!(pool, 0);
         test_account_info!(fee_collector, 0);
         test_account_info!(any, 0);
-        account_info!(sys, system_program::id());
-        account_info!(spl, spl_token::id());
-        zero_program_account!(mut governor, GovernorAccount);
-        governor.set_program_fee(&fee());
 
-        let mut inputs = SendPublicInputs {
-            join_split: JoinSplitPublicInputs {
-                input_commitments: vec![InputCommitment {
-                    root: Some(empty_root_raw()),
-                    nullifier_hash: RawU256::new(u256_from_str_skip_mr("1")),
-                }],
-                output_commitment: RawU256::new(u256_from_str_skip_mr("1")),
-                recent_commitment_index: 123,
-                fee_version: 0,
-                amount: LAMPORTS_PER_SOL,
-                fee: 0,
-                optional_fee: OptionalFee::default(),
-                token_id: 0,
-                metadata: CommitmentMetadata::default(),
-            },
-            recipient_is_associated_token_account: false,
-            hashed_inputs: u256_from_str_skip_mr("1"),
-            solana_pay_transfer: false,
-        };
-        compute_fee_rec_lamports::<SendQuadraVKey, _>(&mut inputs, &fee());
-        let instructions = prepare_public_inputs_instructions(
-            &inputs.public_signals_skip_mr(),
-            SendQuadraVKey::public_inputs_count(),
+        let (n_pda_pk, n_pda_bump) = NullifierDuplicateAccount::find_with_pubkey(
+            public_inputs
+                .join_split
+                .associated_nullifier_duplicate_pda_pubkey(),
+            None,
         );
+        let mut n_pda_data = vec![0; NullifierDuplicateAccount::SIZE];
+        n_pda_data[0] = n_pda_bump;
+        NullifierDuplicateAccount::new(&mut n_pda_data)?.set_duplicate_count(&1);
+        account_info!(n_pda, n_pda_pk, n_pda_data);
 
-        zero_program_account!(mut verification_acc, VerificationAccount);
-        verification_acc.set_request(&ProofRequest::Send(inputs.clone()));
-        verification_acc.set_prepare_inputs_instructions_count(&(instructions.len() as u32));
-        verification_acc.set_other_data(&VerificationAccountData {
-            fee_payer: RawU256::new(fee_payer.key.to_bytes()),
-            ..Default::default()
-        });
+        account_info!(v_acc, Pubkey::new_unique(), verification_acc_data_b);
+        zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
+        zero_program_account!(mut metadata_queue, MetadataQueueAccount);
 
-        // TODO: Associated token-account with lamports is invalid
+        let fee_collector_lamports = fee_collector.lamports();
+        let v_acc_lamports = v_acc.lamports();
+
+        finalize_verification_transfer_lamports(
+            &f,
+            &recipient,
+            &pool,
+            &fee_collector,
+            &any,
+            &mut commitment_queue,
+            &mut metadata_queue,
+            &v_acc,
+            &n_pda,
+            &any,
+            &governor,
+            &rent_recipient,
+            &network_fee,
+            &v_index_acc,
+            0,
+            0,
+        )?;
 
-        // Invalid fee_payer
-        test_account_info!(fee_payer2, 0);
         assert_eq!(
-            init_verification_transfer_fee(
-                &fee_payer2,
-                &fee_payer,
-                &pool,
-                &pool,
-                &fee_collector,
-                &fee_collector,
-                &any,
-                &any,
-                &governor,
-                &mut verification_acc,
-                &sys,
-                &sys,
-                0,
-            ),
-            Err(ElusivError::InvalidAccount.into())
+            fee_collector.lamports(),
+            fee_collector_lamports + v_acc_lamports + 100 + 200
+        );
+        assert!(CommitmentQueue::new(&mut commitment_queue).is_empty());
+
+        pda_account!(n_pda, NullifierDuplicateAccount, n_pda);
+        assert_eq!(n_pda.get_duplicate_count(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_finalize_verification_insert_nullifier_race_two_nullifiers() -> ProgramResult {
+        finalize_send_test!(
+            USDC_TOKEN_ID,
+            LAMPORTS_PER_SOL,
+            public_inputs,
+            verification_acc_data,
+            _recipient_bytes,
+            _identifier_bytes,
+            _reference_bytes,
+            _finalize_data
+        );
+
+        let input_commitment = public_inputs.join_split.input_commitments[0].clone();
+        let mut public_inputs = public_inputs;
+        public_inputs.join_split.input_commitments = vec![
+            InputCommitment {
+                root: input_commitment.root,
+                nullifier_hash: RawU256::new(u256_from_str_skip_mr("123")),
+            },
+            InputCommitment {
+                root: None,
+                nullifier_hash: input_commitment.nullifier_hash,
+            },
+        ];
+        let nullifier_hashes = public_inputs.join_split.nullifier_hashes();
+
+        // Either nullifier-hash has already been inserted by a concurrent verification
+        for lost_nullifier_hash in nullifier_hashes {
+            let mut verification_acc_data = verification_acc_data.clone();
+            let mut verification_acc = VerificationAccount::new(&mut verification_acc_data)?;
+            verification_acc.set_request(&ProofRequest::Send(public_inputs.clone()));
+            verification_acc.set_state(&VerificationState::InsertNullifiers);
+
+            let mut data = verification_acc.get_other_data();
+            data.skip_nullifier_pda = true;
+            verification_acc.set_other_data(&data);
+
+            parent_account!(mut n_acc_0, NullifierAccount);
+            n_acc_0.try_insert_nullifier_hash(lost_nullifier_hash.reduce())?;
+
+            // Both insertions of the finalize tx succeed
+            finalize_verification_insert_nullifier(&mut verification_acc, &mut n_acc_0, 0, 0)?;
+            assert_eq!(verification_acc.get_state(), VerificationState::Finalized);
+            finalize_verification_insert_nullifier(&mut verification_acc, &mut n_acc_0, 0, 0)?;
+
+            assert_eq!(verification_acc.get_state(), VerificationState::Finalized);
+            assert_eq!(
+                verification_acc.get_is_verified(),
+                ElusivOption::Some(false)
+            );
+
+            // No nullifier-hash of the losing verification has been inserted
+            assert_eq!(n_acc_0.get_nullifier_hash_count(), 1);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_finalize_verification_transfer_lamports() -> ProgramResult {
+        zero_program_account!(mut governor, GovernorAccount);
+        test_account_info!(rent_recipient, 0);
+        zero_pda_account_info!(
+            network_fee,
+            NetworkFeeAccount,
+            Some(LAMPORTS_TOKEN_ID.into())
+        );
+        test_account_info!(v_index_acc, 0);
+        finalize_send_test!(
+            LAMPORTS_TOKEN_ID,
+            LAMPORTS_PER_SOL,
+            10,
+            public_inputs,
+            verification_acc_data,
+            recipient_bytes,
+            _i,
+            _r,
+            _f,
+            optional_fee_collector
         );
 
-        // Invalid verification account state
-        verification_acc.set_state(&VerificationState::FeeTransferred);
-        assert_eq!(
-            init_verification_transfer_fee(
-                &fee_payer,
-                &fee_payer,
-                &pool,
-                &pool,
-                &fee_collector,
-                &fee_collector,
-                &any,
-                &any,
-                &governor,
-                &mut verification_acc,
-                &sys,
-                &sys,
-                0,
-            ),
-            Err(ElusivError::InvalidAccountState.into())
+        account_info!(recipient, Pubkey::new_from_array(recipient_bytes));
+        let fee_payer_pk = Pubkey::new(
+            &VerificationAccount::new(&mut verification_acc_data)
+                .unwrap()
+                .get_other_data()
+                .fee_payer
+                .skip_mr(),
         );
-
-        // Invalid fee_version
-        verification_acc.set_state(&VerificationState::None);
-        governor.set_fee_version(&1);
-        assert_eq!(
-            init_verification_transfer_fee(
-                &fee_payer,
-                &fee_payer,
-                &pool,
-                &pool,
-                &fee_collector,
-                &fee_collector,
-                &any,
-                &any,
-                &governor,
-                &mut verification_acc,
-                &sys,
-                &sys,
-                0,
-            ),
-            Err(ElusivError::InvalidFeeVersion.into())
+        account_info!(f, fee_payer_pk); // fee_payer
+        test_account_info!(pool, 0);
+        test_account_info!(fee_collector, 0);
+        account_info!(optional_fee_collector, optional_fee_collector);
+        test_account_info!(any, 0);
+        test_pda_account_info!(
+            n_pda,
+            NullifierDuplicateAccount,
+            public_inputs
+                .join_split
+                .associated_nullifier_duplicate_pda_pubkey(),
+            None
         );
+        account_info!(v_acc, Pubkey::new_unique(), verification_acc_data);
+        zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
+        zero_program_account!(mut metadata_queue, MetadataQueueAccount);
 
-        // Invalid fee (fee too low, since too high is allowed)
-        governor.set_fee_version(&0);
-        inputs.join_split.fee -= 1;
-        verification_acc.set_request(&ProofRequest::Send(inputs.clone()));
-        assert_eq!(
-            init_verification_transfer_fee(
-                &fee_payer,
-                &fee_payer,
-                &pool,
-                &pool,
-                &fee_collector,
-                &fee_collector,
-                &any,
-                &any,
-                &governor,
-                &mut verification_acc,
-                &sys,
-                &sys,
-                0,
-            ),
-            Err(ElusivError::InvalidFee.into())
-        );
+        {
+            pda_account!(mut v_acc, VerificationAccount, v_acc);
+            v_acc.set_state(&VerificationState::None);
+            v_acc.set_is_verified(&ElusivOption::Some(true));
+        }
 
-        // Invalid system_program
-        inputs.join_split.fee = 0;
-        compute_fee_rec_lamports::<SendQuadraVKey, _>(&mut inputs, &fee());
-        verification_acc.set_request(&ProofRequest::Send(inputs));
+        // Invalid state
         assert_eq!(
-            init_verification_transfer_fee(
-                &fee_payer,
-                &fee_payer,
-                &pool,
+            finalize_verification_transfer_lamports(
+                &f,
+                &recipient,
                 &pool,
                 &fee_collector,
-                &fee_collector,
-                &any,
+                &optional_fee_collector,
+                &mut commitment_queue,
+                &mut metadata_queue,
+                &v_acc,
+                &n_pda,
                 &any,
                 &governor,
-                &mut verification_acc,
-                &sys,
-                &spl,
+                &rent_recipient,
+                &network_fee,
+                &v_index_acc,
                 0,
+                0
             ),
-            Err(ProgramError::IncorrectProgramId)
+            Err(ElusivError::InvalidAccountState.into())
         );
 
-        // Invalid pool_account
-        assert_eq!(
-            init_verification_transfer_fee(
-                &fee_payer,
-                &fee_payer,
-                &pool,
-                &any,
-                &fee_collector,
-                &fee_collector,
-                &any,
-                &any,
-                &governor,
-                &mut verification_acc,
-                &sys,
-                &sys,
-                0,
-            ),
-            Err(ElusivError::InvalidAccount.into())
-        );
+        {
+            pda_account!(mut v_acc, VerificationAccount, v_acc);
+            v_acc.set_state(&VerificationState::Finalized);
+        }
 
-        // Invalid fee_collector_account
+        // Invalid nullifier_duplicate_account
+        account_info!(
+            invalid_n_pda,
+            VerificationAccount::find_with_pubkey(*f.key, Some(0)).0,
+            vec![1]
+        );
         assert_eq!(
-            init_verification_transfer_fee(
-                &fee_payer,
-                &fee_payer,
-                &pool,
+            finalize_verification_transfer_lamports(
+                &f,
+                &recipient,
                 &pool,
                 &fee_collector,
-                &any,
-                &any,
+                &optional_fee_collector,
+                &mut commitment_queue,
+                &mut metadata_queue,
+                &v_acc,
+                &invalid_n_pda,
                 &any,
                 &governor,
-                &mut verification_acc,
-                &sys,
-                &sys,
+                &rent_recipient,
+                &network_fee,
+                &v_index_acc,
                 0,
+                0
             ),
             Err(ElusivError::InvalidAccount.into())
         );
 
+        // Paused finalizations
+        governor.set_paused_categories(&Category::Finalize.bit());
         assert_eq!(
-            init_verification_transfer_fee(
-                &fee_payer,
-                &fee_payer,
-                &pool,
+            finalize_verification_transfer_lamports(
+                &f,
+                &recipient,
                 &pool,
                 &fee_collector,
-                &fee_collector,
-                &any,
+                &optional_fee_collector,
+                &mut commitment_queue,
+                &mut metadata_queue,
+                &v_acc,
+                &n_pda,
                 &any,
                 &governor,
-                &mut verification_acc,
-                &sys,
-                &sys,
+                &rent_recipient,
+                &network_fee,
+                &v_index_acc,
                 0,
-            ),
-            Ok(())
-        );
-
-        assert_eq!(
-            verification_acc.get_state(),
-            VerificationState::FeeTransferred
-        );
-    }
-
-    #[test]
-    fn test_init_verification_transfer_fee_token() {
-        test_account_info!(fee_payer, 0);
-        account_info!(sys, system_program::id());
-        account_info!(spl, spl_token::id());
-        zero_program_account!(mut governor, GovernorAccount);
-        governor.set_program_fee(&fee());
-
-        account_info!(
-            token_acc,
-            Pubkey::new_unique(),
-            spl_token_account_data(USDC_TOKEN_ID),
-            spl_token::id(),
-            false
-        );
-        account_info!(
-            wrong_token_acc,
-            Pubkey::new_unique(),
-            spl_token_account_data(USDT_TOKEN_ID),
-            spl_token::id(),
-            false
-        );
-
-        test_pda_account_info!(pool, PoolAccount, None);
-        test_pda_account_info!(fee_collector, FeeCollectorAccount, None);
-        program_token_account_info!(pool_token, PoolAccount, USDC_TOKEN_ID);
-        program_token_account_info!(fee_collector_token, FeeCollectorAccount, USDC_TOKEN_ID);
-
-        let sol_usd = Price {
-            price: 39,
-            conf: 1,
-            expo: 0,
-        };
-        let usdc_usd = Price {
-            price: 1,
-            conf: 1,
-            expo: 0,
-        };
-        let price = TokenPrice::new_from_sol_price(sol_usd, usdc_usd, USDC_TOKEN_ID).unwrap();
-        pyth_price_account_info!(sol, LAMPORTS_TOKEN_ID, sol_usd);
-        pyth_price_account_info!(usdc, USDC_TOKEN_ID, usdc_usd);
-
-        let mut inputs = SendPublicInputs {
-            join_split: JoinSplitPublicInputs {
-                input_commitments: vec![InputCommitment {
-                    root: Some(empty_root_raw()),
-                    nullifier_hash: RawU256::new(u256_from_str_skip_mr("1")),
-                }],
-                output_commitment: RawU256::new(u256_from_str_skip_mr("1")),
-                recent_commitment_index: 123,
-                fee_version: 0,
-                amount: 1_000_000,
-                fee: 0,
-                optional_fee: OptionalFee::default(),
-                token_id: USDC_TOKEN_ID,
-                metadata: CommitmentMetadata::default(),
-            },
-            recipient_is_associated_token_account: false,
-            hashed_inputs: u256_from_str_skip_mr("1"),
-            solana_pay_transfer: false,
-        };
-        compute_fee_rec::<SendQuadraVKey, _>(&mut inputs, &fee(), &price);
-        let instructions = prepare_public_inputs_instructions(
-            &inputs.public_signals_skip_mr(),
-            SendQuadraVKey::public_inputs_count(),
+                0
+            ),
+            Err(ElusivError::CategoryPaused.into())
         );
+        governor.set_paused_categories(&(PAUSABLE_CATEGORIES & !Category::Finalize.bit()));
 
-        zero_program_account!(mut verification_acc, VerificationAccount);
-        verification_acc.set_request(&ProofRequest::Send(inputs.clone()));
-        verification_acc.set_prepare_inputs_instructions_count(&(instructions.len() as u32));
-        verification_acc.set_other_data(&VerificationAccountData {
-            fee_payer: RawU256::new(fee_payer.key.to_bytes()),
-            ..Default::default()
-        });
-
-        // Invalid fee (fee too low, since too high is allowed)
-        inputs.join_split.fee -= 1;
-        verification_acc.set_request(&ProofRequest::Send(inputs.clone()));
+        // Invalid original_fee_payer
         assert_eq!(
-            init_verification_transfer_fee(
-                &fee_payer,
-                &token_acc,
+            finalize_verification_transfer_lamports(
+                &any,
+                &recipient,
                 &pool,
-                &pool_token,
                 &fee_collector,
-                &fee_collector_token,
-                &sol,
-                &usdc,
+                &optional_fee_collector,
+                &mut commitment_queue,
+                &mut metadata_queue,
+                &v_acc,
+                &n_pda,
+                &any,
                 &governor,
-                &mut verification_acc,
-                &spl,
-                &sys,
+                &rent_recipient,
+                &network_fee,
+                &v_index_acc,
+                0,
                 0
             ),
-            Err(ElusivError::InvalidFee.into())
+            Err(ElusivError::InvalidAccount.into())
         );
 
-        inputs.join_split.fee = 0;
-        compute_fee_rec::<SendQuadraVKey, _>(&mut inputs, &fee(), &price);
-        verification_acc.set_request(&ProofRequest::Send(inputs.clone()));
-
-        // Invalid system_program
+        // Invalid recipient
         assert_eq!(
-            init_verification_transfer_fee(
-                &fee_payer,
-                &token_acc,
+            finalize_verification_transfer_lamports(
+                &f,
+                &any,
                 &pool,
-                &pool_token,
                 &fee_collector,
-                &fee_collector_token,
-                &sol,
-                &usdc,
+                &optional_fee_collector,
+                &mut commitment_queue,
+                &mut metadata_queue,
+                &v_acc,
+                &n_pda,
+                &any,
                 &governor,
-                &mut verification_acc,
-                &spl,
-                &spl,
+                &rent_recipient,
+                &network_fee,
+                &v_index_acc,
+                0,
                 0
             ),
-            Err(ProgramError::IncorrectProgramId)
+            Err(ElusivError::InvalidRecipient.into())
         );
 
-        // Invalid token_program
+        // Missing recipient_wallet (`finalize_verification_send` skipped)
+        let recipient_wallet = {
+            pda_account!(mut v_acc, VerificationAccount, v_acc);
+            let mut data = v_acc.get_other_data();
+            let recipient_wallet = data.recipient_wallet;
+            data.recipient_wallet = ElusivOption::None;
+            v_acc.set_other_data(&data);
+            recipient_wallet
+        };
         assert_eq!(
-            init_verification_transfer_fee(
-                &fee_payer,
-                &token_acc,
+            finalize_verification_transfer_lamports(
+                &f,
+                &recipient,
                 &pool,
-                &pool_token,
                 &fee_collector,
-                &fee_collector_token,
-                &sol,
-                &usdc,
+                &optional_fee_collector,
+                &mut commitment_queue,
+                &mut metadata_queue,
+                &v_acc,
+                &n_pda,
+                &any,
                 &governor,
-                &mut verification_acc,
-                &sys,
-                &sys,
+                &rent_recipient,
+                &network_fee,
+                &v_index_acc,
+                0,
                 0
             ),
-            Err(ElusivError::InvalidAccount.into())
+            Err(ElusivError::InvalidAccountState.into())
         );
+        {
+            pda_account!(mut v_acc, VerificationAccount, v_acc);
+            let mut data = v_acc.get_other_data();
+            data.recipient_wallet = recipient_wallet;
+            v_acc.set_other_data(&data);
+        }
 
-        // Invalid fee_payer_account
+        // Invalid optional-fee-collector
+        test_account_info!(invalid_optional_fee_collector, 0);
         assert_eq!(
-            init_verification_transfer_fee(
-                &fee_payer,
-                &wrong_token_acc,
+            finalize_verification_transfer_lamports(
+                &f,
+                &recipient,
                 &pool,
-                &pool_token,
                 &fee_collector,
-                &fee_collector_token,
-                &sol,
-                &usdc,
+                &invalid_optional_fee_collector,
+                &mut commitment_queue,
+                &mut metadata_queue,
+                &v_acc,
+                &n_pda,
+                &any,
                 &governor,
-                &mut verification_acc,
-                &spl,
-                &sys,
+                &rent_recipient,
+                &network_fee,
+                &v_index_acc,
+                0,
                 0
             ),
             Err(ElusivError::InvalidAccount.into())
         );
 
-        // Invalid pool_account
+        // Stored request fee_version differs from the one captured at the fee transfer
+        {
+            pda_account!(mut v_acc, VerificationAccount, v_acc);
+            let mut public_inputs = public_inputs.clone();
+            public_inputs.join_split.fee_version = 1;
+            v_acc.set_request(&ProofRequest::Send(public_inputs));
+        }
         assert_eq!(
-            init_verification_transfer_fee(
-                &fee_payer,
-                &token_acc,
+            finalize_verification_transfer_lamports(
+                &f,
+                &recipient,
                 &pool,
-                &fee_collector_token,
                 &fee_collector,
-                &fee_collector_token,
-                &sol,
-                &usdc,
+                &optional_fee_collector,
+                &mut commitment_queue,
+                &mut metadata_queue,
+                &v_acc,
+                &n_pda,
+                &any,
                 &governor,
-                &mut verification_acc,
-                &spl,
-                &sys,
+                &rent_recipient,
+                &network_fee,
+                &v_index_acc,
+                0,
                 0
             ),
-            Err(ElusivError::InvalidAccount.into())
+            Err(ElusivError::InvalidFeeVersion.into())
         );
+        {
+            pda_account!(mut v_acc, VerificationAccount, v_acc);
+            v_acc.set_request(&ProofRequest::Send(public_inputs.clone()));
+        }
 
-        // Invalid fee_collector_account
+        // Commitment queue is full
+        {
+            let mut queue = CommitmentQueue::new(&mut commitment_queue);
+            for _ in 0..CommitmentQueue::CAPACITY {
+                queue
+                    .enqueue(CommitmentHashRequest {
+                        commitment: [0; 32],
+                        fee_version: 0,
+                        min_batching_rate: 0,
+                        view_tag: 0,
+                    })
+                    .unwrap();
+            }
+        }
         assert_eq!(
-            init_verification_transfer_fee(
-                &fee_payer,
-                &token_acc,
+            finalize_verification_transfer_lamports(
+                &f,
+                &recipient,
                 &pool,
-                &pool_token,
                 &fee_collector,
-                &pool_token,
-                &sol,
-                &usdc,
+                &optional_fee_collector,
+                &mut commitment_queue,
+                &mut metadata_queue,
+                &v_acc,
+                &n_pda,
+                &any,
                 &governor,
-                &mut verification_acc,
-                &spl,
-                &sys,
+                &rent_recipient,
+                &network_fee,
+                &v_index_acc,
+                0,
                 0
             ),
-            Err(ElusivError::InvalidAccount.into())
+            Err(ElusivError::QueueIsFull.into())
         );
 
-        // Invalid sol_usd_price_account
+        zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
+
+        {
+            pda_account!(mut v_acc, VerificationAccount, v_acc);
+            let mut public_inputs = public_inputs.clone();
+            public_inputs.view_tag = 42;
+            v_acc.set_request(&ProofRequest::Send(public_inputs));
+        }
+
         assert_eq!(
-            init_verification_transfer_fee(
-                &fee_payer,
-                &token_acc,
+            finalize_verification_transfer_lamports(
+                &f,
+                &recipient,
                 &pool,
-                &pool_token,
                 &fee_collector,
-                &fee_collector_token,
-                &usdc,
-                &usdc,
+                &optional_fee_collector,
+                &mut commitment_queue,
+                &mut metadata_queue,
+                &v_acc,
+                &n_pda,
+                &any,
                 &governor,
-                &mut verification_acc,
-                &spl,
-                &sys,
+                &rent_recipient,
+                &network_fee,
+                &v_index_acc,
+                0,
                 0
             ),
-            Err(TokenError::InvalidPriceAccount.into())
+            Ok(())
+        );
+
+        assert_eq!(n_pda.lamports(), 0);
+        assert_eq!(v_acc.lamports(), 0);
+        pda_account!(v_acc, VerificationAccount, v_acc);
+        assert_eq!(v_acc.get_state(), VerificationState::Closed);
+
+        // The view tag is stored alongside the commitment
+        let queue = CommitmentQueue::new(&mut commitment_queue);
+        let request = queue.view_first()?;
+        assert_eq!(
+            request.commitment,
+            public_inputs.join_split.output_commitment.reduce()
+        );
+        assert_eq!(request.view_tag, 42);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_finalize_verification_transfer_lamports_rent_recipient() -> ProgramResult {
+        finalize_send_test!(
+            LAMPORTS_TOKEN_ID,
+            LAMPORTS_PER_SOL,
+            10,
+            public_inputs,
+            verification_acc_data,
+            recipient_bytes,
+            _i,
+            _r,
+            _f,
+            optional_fee_collector
+        );
+
+        account_info!(recipient, Pubkey::new_from_array(recipient_bytes));
+        let fee_payer_pk = Pubkey::new_from_array(
+            VerificationAccount::new(&mut verification_acc_data)
+                .unwrap()
+                .get_other_data()
+                .fee_payer
+                .skip_mr(),
+        );
+        account_info!(f, fee_payer_pk); // fee_payer
+        test_account_info!(pool, 0);
+        test_account_info!(fee_collector, 0);
+        account_info!(optional_fee_collector, optional_fee_collector);
+        test_account_info!(any, 0);
+        test_pda_account_info!(
+            n_pda,
+            NullifierDuplicateAccount,
+            public_inputs
+                .join_split
+                .associated_nullifier_duplicate_pda_pubkey(),
+            None
+        );
+        account_info!(v_acc, Pubkey::new_unique(), verification_acc_data);
+        zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
+        zero_program_account!(mut metadata_queue, MetadataQueueAccount);
+
+        {
+            pda_account!(mut v_acc, VerificationAccount, v_acc);
+            v_acc.set_state(&VerificationState::Finalized);
+            v_acc.set_is_verified(&ElusivOption::Some(true));
+        }
+
+        let treasury_pk = Pubkey::new_unique();
+        zero_program_account!(mut governor, GovernorAccount);
+        governor.set_rent_recipient(&ElusivOption::Some(treasury_pk));
+        zero_pda_account_info!(
+            network_fee,
+            NetworkFeeAccount,
+            Some(LAMPORTS_TOKEN_ID.into())
         );
+        test_account_info!(v_index_acc, 0);
+        account_info!(treasury, treasury_pk);
 
-        // Invalid token_usd_price_account
+        // Invalid rent-recipient
         assert_eq!(
-            init_verification_transfer_fee(
-                &fee_payer,
-                &token_acc,
+            finalize_verification_transfer_lamports(
+                &f,
+                &recipient,
                 &pool,
-                &pool_token,
                 &fee_collector,
-                &fee_collector_token,
-                &sol,
-                &sol,
+                &optional_fee_collector,
+                &mut commitment_queue,
+                &mut metadata_queue,
+                &v_acc,
+                &n_pda,
+                &any,
                 &governor,
-                &mut verification_acc,
-                &spl,
-                &sys,
+                &f,
+                &network_fee,
+                &v_index_acc,
+                0,
                 0
             ),
-            Err(TokenError::InvalidPriceAccount.into())
+            Err(ElusivError::InvalidAccount.into())
         );
 
+        let rent = v_acc.lamports() + n_pda.lamports();
+        let treasury_lamports = treasury.lamports();
+        let fee_payer_lamports = f.lamports();
+        let fee_payer_fees = {
+            pda_account!(v_acc, VerificationAccount, v_acc);
+            let data = v_acc.get_other_data();
+            data.commitment_hash_fee_token + data.proof_verification_fee
+        };
+
         assert_eq!(
-            init_verification_transfer_fee(
-                &fee_payer,
-                &token_acc,
+            finalize_verification_transfer_lamports(
+                &f,
+                &recipient,
                 &pool,
-                &pool_token,
                 &fee_collector,
-                &fee_collector_token,
-                &sol,
-                &usdc,
+                &optional_fee_collector,
+                &mut commitment_queue,
+                &mut metadata_queue,
+                &v_acc,
+                &n_pda,
+                &any,
                 &governor,
-                &mut verification_acc,
-                &spl,
-                &sys,
+                &treasury,
+                &network_fee,
+                &v_index_acc,
+                0,
                 0
             ),
             Ok(())
         );
 
-        assert_eq!(
-            verification_acc.get_state(),
-            VerificationState::FeeTransferred
-        );
+        // The rent flows to the treasury, the fee-payer only receives the fees
+        assert_eq!(v_acc.lamports(), 0);
+        assert_eq!(n_pda.lamports(), 0);
+        assert_eq!(treasury.lamports(), treasury_lamports + rent);
+        assert_eq!(f.lamports(), fee_payer_lamports + fee_payer_fees);
+
+        Ok(())
     }
 
     #[test]
-    fn test_init_verification_proof() {
-        let proof = test_proof();
-        let valid_pk = Pubkey::new(&[0; 32]);
-        account_info!(fee_payer, valid_pk, vec![0; 0]);
-        zero_program_account!(mut verification_account, VerificationAccount);
+    fn test_finalize_verification_transfer_lamports_failure_bond() -> ProgramResult {
+        let failure_bond = Lamports(1_000);
 
-        // Account setup
-        verification_account.set_state(&VerificationState::ProofSetup);
-        assert_eq!(
-            init_verification_proof(&fee_payer, &mut verification_account, 0, proof),
-            Err(ElusivError::InvalidAccountState.into())
+        for is_verified in [true, false] {
+            finalize_send_test!(
+                LAMPORTS_TOKEN_ID,
+                LAMPORTS_PER_SOL,
+                10,
+                public_inputs,
+                verification_acc_data,
+                recipient_bytes,
+                _i,
+                _r,
+                _f,
+                optional_fee_collector
+            );
+
+            account_info!(recipient, Pubkey::new_from_array(recipient_bytes));
+            let fee_payer_pk = Pubkey::new_from_array(
+                VerificationAccount::new(&mut verification_acc_data)
+                    .unwrap()
+                    .get_other_data()
+                    .fee_payer
+                    .skip_mr(),
+            );
+            account_info!(f, fee_payer_pk); // fee_payer
+            test_account_info!(pool, 0);
+            test_account_info!(fee_collector, 0);
+            account_info!(optional_fee_collector, optional_fee_collector);
+            test_account_info!(any, 0);
+            test_pda_account_info!(
+                n_pda,
+                NullifierDuplicateAccount,
+                public_inputs
+                    .join_split
+                    .associated_nullifier_duplicate_pda_pubkey(),
+                None
+            );
+            account_info!(v_acc, Pubkey::new_unique(), verification_acc_data);
+            zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
+            zero_program_account!(mut metadata_queue, MetadataQueueAccount);
+
+            // The rent flows to the treasury, so only fees and the bond are moved between fee-payer and fee-collector
+            let treasury_pk = Pubkey::new_unique();
+            zero_program_account!(mut governor, GovernorAccount);
+            governor.set_rent_recipient(&ElusivOption::Some(treasury_pk));
+            zero_pda_account_info!(
+                network_fee,
+                NetworkFeeAccount,
+                Some(LAMPORTS_TOKEN_ID.into())
+            );
+            test_account_info!(v_index_acc, 0);
+            account_info!(treasury, treasury_pk);
+
+            let data = {
+                pda_account!(mut v_acc, VerificationAccount, v_acc);
+                v_acc.set_state(&VerificationState::Finalized);
+                v_acc.set_is_verified(&ElusivOption::Some(is_verified));
+
+                let mut data = v_acc.get_other_data();
+                data.failure_bond = failure_bond;
+                v_acc.set_other_data(&data);
+                data
+            };
+
+            let fee_payer_lamports = f.lamports();
+            let fee_collector_lamports = fee_collector.lamports();
+
+            assert_eq!(
+                finalize_verification_transfer_lamports(
+                    &f,
+                    &recipient,
+                    &pool,
+                    &fee_collector,
+                    &optional_fee_collector,
+                    &mut commitment_queue,
+                    &mut metadata_queue,
+                    &v_acc,
+                    &n_pda,
+                    &any,
+                    &governor,
+                    &treasury,
+                    &network_fee,
+                    &v_index_acc,
+                    0,
+                    0
+                ),
+                Ok(())
+            );
+
+            if is_verified {
+                // The bond is refunded to the fee-payer
+                assert_eq!(
+                    f.lamports(),
+                    fee_payer_lamports
+                        + data.commitment_hash_fee_token
+                        + data.proof_verification_fee
+                        + failure_bond.0
+                );
+                assert_eq!(fee_collector.lamports(), fee_collector_lamports);
+            } else {
+                // The bond is forfeited to the fee-collector
+                assert_eq!(f.lamports(), fee_payer_lamports);
+                assert_eq!(
+                    fee_collector.lamports(),
+                    fee_collector_lamports
+                        + data.subvention
+                        + data.commitment_hash_fee.0
+                        + failure_bond.0
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_finalize_verification_transfer_lamports_collected_network_fees() -> ProgramResult {
+        zero_program_account!(governor, GovernorAccount);
+        zero_pda_account_info!(
+            network_fee_account,
+            NetworkFeeAccount,
+            Some(LAMPORTS_TOKEN_ID.into())
         );
-        verification_account.set_state(&VerificationState::FeeTransferred);
+        test_account_info!(v_index_acc, 0);
+        let mut expected = 0;
 
-        // Computation already finished
-        verification_account.set_is_verified(&ElusivOption::Some(true));
+        for (i, is_verified) in [true, false, true, true].into_iter().enumerate() {
+            finalize_send_test!(
+                LAMPORTS_TOKEN_ID,
+                LAMPORTS_PER_SOL,
+                10,
+                public_inputs,
+                verification_acc_data,
+                recipient_bytes,
+                _i,
+                _r,
+                _f,
+                optional_fee_collector
+            );
+
+            account_info!(recipient, Pubkey::new_from_array(recipient_bytes));
+            let fee_payer_pk = Pubkey::new_from_array(
+                VerificationAccount::new(&mut verification_acc_data)
+                    .unwrap()
+                    .get_other_data()
+                    .fee_payer
+                    .skip_mr(),
+            );
+            account_info!(f, fee_payer_pk); // fee_payer
+            test_account_info!(pool, 0);
+            test_account_info!(fee_collector, 0);
+            account_info!(optional_fee_collector, optional_fee_collector);
+            test_account_info!(any, 0);
+            test_pda_account_info!(
+                n_pda,
+                NullifierDuplicateAccount,
+                public_inputs
+                    .join_split
+                    .associated_nullifier_duplicate_pda_pubkey(),
+                None
+            );
+            account_info!(v_acc, Pubkey::new_unique(), verification_acc_data);
+            test_account_info!(rent_recipient, 0);
+            zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
+            zero_program_account!(mut metadata_queue, MetadataQueueAccount);
+
+            let network_fee = 100 * (i as u64 + 1);
+            {
+                pda_account!(mut v_acc, VerificationAccount, v_acc);
+                v_acc.set_state(&VerificationState::Finalized);
+                v_acc.set_is_verified(&ElusivOption::Some(is_verified));
+
+                let mut data = v_acc.get_other_data();
+                data.network_fee = network_fee;
+                v_acc.set_other_data(&data);
+            }
+
+            finalize_verification_transfer_lamports(
+                &f,
+                &recipient,
+                &pool,
+                &fee_collector,
+                &optional_fee_collector,
+                &mut commitment_queue,
+                &mut metadata_queue,
+                &v_acc,
+                &n_pda,
+                &any,
+                &governor,
+                &rent_recipient,
+                &network_fee_account,
+                &v_index_acc,
+                0,
+                0,
+            )?;
+
+            // Only successful finalizations collect the network fee
+            if is_verified {
+                expected += network_fee;
+            }
+            pda_account!(accumulator, NetworkFeeAccount, network_fee_account);
+            assert_eq!(accumulator.get_collected_network_fee(), expected);
+        }
+
+        // Other tokens are accumulated in separate PDAs
         assert_eq!(
-            init_verification_proof(&fee_payer, &mut verification_account, 0, proof),
-            Err(ElusivError::ComputationIsAlreadyFinished.into())
+            add_collected_network_fee(&network_fee_account, USDC_TOKEN_ID, 1),
+            Err(ProgramError::InvalidSeeds)
         );
-        verification_account.set_is_verified(&ElusivOption::Some(false));
-        assert_eq!(
-            init_verification_proof(&fee_payer, &mut verification_account, 0, proof),
-            Err(ElusivError::ComputationIsAlreadyFinished.into())
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_finalize_verification_transfer_lamports_unopened_network_fee_account() -> ProgramResult
+    {
+        zero_program_account!(governor, GovernorAccount);
+        account_info!(
+            network_fee_account,
+            NetworkFeeAccount::find(Some(LAMPORTS_TOKEN_ID.into())).0,
+            vec![]
+        );
+        test_account_info!(v_index_acc, 0);
+        finalize_send_test!(
+            LAMPORTS_TOKEN_ID,
+            LAMPORTS_PER_SOL,
+            10,
+            public_inputs,
+            verification_acc_data,
+            recipient_bytes,
+            _i,
+            _r,
+            _f,
+            optional_fee_collector
         );
-        verification_account.set_is_verified(&ElusivOption::None);
 
-        // Invalid fee_payer
-        let invalid_pk = Pubkey::new_unique();
-        account_info!(invalid_fee_payer, invalid_pk, vec![0; 0]);
+        account_info!(recipient, Pubkey::new_from_array(recipient_bytes));
+        let fee_payer_pk = Pubkey::new_from_array(
+            VerificationAccount::new(&mut verification_acc_data)?
+                .get_other_data()
+                .fee_payer
+                .skip_mr(),
+        );
+        account_info!(f, fee_payer_pk);
+        test_account_info!(pool, 0);
+        test_account_info!(fee_collector, 0);
+        account_info!(optional_fee_collector, optional_fee_collector);
+        test_account_info!(any, 0);
+        test_pda_account_info!(
+            n_pda,
+            NullifierDuplicateAccount,
+            public_inputs
+                .join_split
+                .associated_nullifier_duplicate_pda_pubkey(),
+            None
+        );
+        account_info!(v_acc, Pubkey::new_unique(), verification_acc_data);
+        test_account_info!(rent_recipient, 0);
+        zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
+        zero_program_account!(mut metadata_queue, MetadataQueueAccount);
+
+        {
+            pda_account!(mut v_acc, VerificationAccount, v_acc);
+            v_acc.set_state(&VerificationState::Finalized);
+            v_acc.set_is_verified(&ElusivOption::Some(true));
+        }
+
+        // The network fee is not accumulated before the accumulator is opened
+        let recipient_lamports = recipient.lamports();
+        finalize_verification_transfer_lamports(
+            &f,
+            &recipient,
+            &pool,
+            &fee_collector,
+            &optional_fee_collector,
+            &mut commitment_queue,
+            &mut metadata_queue,
+            &v_acc,
+            &n_pda,
+            &any,
+            &governor,
+            &rent_recipient,
+            &network_fee_account,
+            &v_index_acc,
+            0,
+            0,
+        )?;
+        assert!(recipient.lamports() > recipient_lamports);
+        assert!(network_fee_account.data_is_empty());
+
+        // An arbitrary empty account can't be used to skip the accumulation
+        test_account_info!(invalid_network_fee_account, 0);
         assert_eq!(
-            init_verification_proof(&invalid_fee_payer, &mut verification_account, 0, proof),
+            add_collected_network_fee(&invalid_network_fee_account, LAMPORTS_TOKEN_ID, 1),
             Err(ElusivError::InvalidAccount.into())
         );
 
-        // Success
+        Ok(())
+    }
+
+    #[test]
+    fn test_finalize_verification_transfer_lamports_closes_verification_index() -> ProgramResult {
+        zero_program_account!(governor, GovernorAccount);
+        zero_pda_account_info!(
+            network_fee_account,
+            NetworkFeeAccount,
+            Some(LAMPORTS_TOKEN_ID.into())
+        );
+
+        for is_verified in [true, false] {
+            finalize_send_test!(
+                LAMPORTS_TOKEN_ID,
+                LAMPORTS_PER_SOL,
+                10,
+                public_inputs,
+                verification_acc_data,
+                recipient_bytes,
+                _i,
+                _r,
+                _f,
+                optional_fee_collector
+            );
+
+            account_info!(recipient, Pubkey::new_from_array(recipient_bytes));
+            let fee_payer_pk = Pubkey::new_from_array(
+                VerificationAccount::new(&mut verification_acc_data)
+                    .unwrap()
+                    .get_other_data()
+                    .fee_payer
+                    .skip_mr(),
+            );
+            account_info!(f, fee_payer_pk); // fee_payer
+            test_account_info!(pool, 0);
+            test_account_info!(fee_collector, 0);
+            account_info!(optional_fee_collector, optional_fee_collector);
+            test_account_info!(any, 0);
+            test_pda_account_info!(
+                n_pda,
+                NullifierDuplicateAccount,
+                public_inputs
+                    .join_split
+                    .associated_nullifier_duplicate_pda_pubkey(),
+                None
+            );
+            account_info!(v_acc, Pubkey::new_unique(), verification_acc_data);
+            test_account_info!(rent_recipient, 0);
+            zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
+            zero_program_account!(mut metadata_queue, MetadataQueueAccount);
+
+            let mut v_index_data = vec![0; VerificationIndexAccount::SIZE];
+            {
+                let mut v_index = VerificationIndexAccount::new(&mut v_index_data).unwrap();
+                v_index.open_index(0, 0).unwrap();
+                v_index.open_index(1, 1).unwrap();
+            }
+            account_info!(v_index_acc, Pubkey::new_unique(), v_index_data);
+
+            {
+                pda_account!(mut v_acc, VerificationAccount, v_acc);
+                v_acc.set_state(&VerificationState::Finalized);
+                v_acc.set_is_verified(&ElusivOption::Some(is_verified));
+            }
+
+            finalize_verification_transfer_lamports(
+                &f,
+                &recipient,
+                &pool,
+                &fee_collector,
+                &optional_fee_collector,
+                &mut commitment_queue,
+                &mut metadata_queue,
+                &v_acc,
+                &n_pda,
+                &any,
+                &governor,
+                &rent_recipient,
+                &network_fee_account,
+                &v_index_acc,
+                1,
+                1,
+            )?;
+
+            // Only the finalized verification is no longer open
+            pda_account!(mut v_index, VerificationIndexAccount, v_index_acc);
+            assert!(v_index.is_index_open(0));
+            assert!(!v_index.is_index_open(1));
+            assert_eq!(v_index.open_verifications(&fee_payer_pk).len(), 1);
+
+            // The index can be reused
+            v_index.open_index(1, 2)?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_verification_rent_beneficiary() {
+        test_account_info!(fee_payer, 0);
+        test_account_info!(fee_collector, 0);
+        test_account_info!(treasury, 0);
+        zero_program_account!(mut governor, GovernorAccount);
+
+        // Defaults to the fee-payer or fee-collector (any rent-recipient is ignored)
         assert_eq!(
-            init_verification_proof(&fee_payer, &mut verification_account, 0, proof),
-            Ok(())
+            verification_rent_beneficiary(&governor, &treasury, &fee_payer)
+                .unwrap()
+                .key,
+            fee_payer.key
         );
         assert_eq!(
-            verification_account.get_state(),
-            VerificationState::ProofSetup
+            verification_rent_beneficiary(&governor, &treasury, &fee_collector)
+                .unwrap()
+                .key,
+            fee_collector.key
         );
-        assert_eq!(verification_account.a.get(), proof.a);
-        assert_eq!(verification_account.b.get(), proof.b);
-        assert_eq!(verification_account.c.get(), proof.c);
 
-        // Already setup proof
+        // Configured rent-recipient
+        governor.set_rent_recipient(&ElusivOption::Some(*treasury.key));
+        assert_eq!(
+            verification_rent_beneficiary(&governor, &treasury, &fee_payer)
+                .unwrap()
+                .key,
+            treasury.key
+        );
+        assert_eq!(
+            verification_rent_beneficiary(&governor, &treasury, &fee_collector)
+                .unwrap()
+                .key,
+            treasury.key
+        );
         assert_eq!(
-            init_verification_proof(&fee_payer, &mut verification_account, 0, proof),
-            Err(ElusivError::InvalidAccountState.into())
+            verification_rent_beneficiary(&governor, &fee_payer, &fee_payer).map(|_| ()),
+            Err(ElusivError::InvalidAccount.into())
         );
     }
 
     #[test]
-    fn test_compute_verification() {
-        zero_program_account!(mut verification_account, VerificationAccount);
-        vkey_account!(vkey, SendQuadraVKey);
-        vkey.set_version(&1);
-        test_account_info!(any, 0);
-
-        // Setup
-        let public_inputs = test_public_inputs();
-        for (i, &public_input) in public_inputs.iter().enumerate() {
-            verification_account.set_public_input(i, &RawU256::new(public_input));
-        }
-        let instructions = prepare_public_inputs_instructions(
-            &public_inputs,
-            SendQuadraVKey::public_inputs_count(),
+    fn test_user_facing_fee() -> ProgramResult {
+        test_account_info!(rent_recipient, 0);
+        zero_pda_account_info!(
+            network_fee,
+            NetworkFeeAccount,
+            Some(LAMPORTS_TOKEN_ID.into())
+        );
+        test_account_info!(v_index_acc, 0);
+        finalize_send_test!(
+            LAMPORTS_TOKEN_ID,
+            LAMPORTS_PER_SOL,
+            10,
+            public_inputs,
+            verification_acc_data,
+            recipient_bytes,
+            _i,
+            _r,
+            _f,
+            optional_fee_collector
         );
-        verification_account.set_prepare_inputs_instructions_count(&(instructions.len() as u32));
-        for (i, &ix) in instructions.iter().enumerate() {
-            verification_account.set_prepare_inputs_instructions(i, &(ix as u16));
-        }
+        let mut public_inputs = public_inputs;
+        compute_fee_rec_lamports::<SendQuadraVKey, _>(&mut public_inputs, &fee());
+        let request = ProofRequest::Send(public_inputs.clone());
+        let price = TokenPrice::new_lamports();
 
-        // Computation is already finished (is_verified is Some)
-        verification_account.set_is_verified(&ElusivOption::Some(true));
-        assert_eq!(
-            compute_verification(
-                &mut verification_account,
-                &vkey,
-                &any,
-                0,
-                SendQuadraVKey::VKEY_ID
-            ),
-            Err(ElusivError::ComputationIsAlreadyFinished.into())
-        );
-        verification_account.set_is_verified(&ElusivOption::None);
+        zero_program_account!(mut governor, GovernorAccount);
+        governor.set_program_fee(&fee());
 
-        // Success for public input preparation
-        for _ in 0..instructions.len() {
-            assert_eq!(
-                compute_verification(
-                    &mut verification_account,
-                    &vkey,
-                    &any,
-                    0,
-                    SendQuadraVKey::VKEY_ID
-                ),
-                Ok(())
-            );
-        }
+        let user_fee = user_facing_fee(&request, &governor, &price, false)?.amount();
+        assert_eq!(user_fee, public_inputs.join_split.fee + 10);
 
-        // Failure for miller loop (proof not setup)
+        // Associated-token-account rent
         assert_eq!(
-            compute_verification(
-                &mut verification_account,
-                &vkey,
-                &any,
-                0,
-                SendQuadraVKey::VKEY_ID
-            ),
-            Err(ElusivError::InvalidAccountState.into())
+            user_facing_fee(&request, &governor, &price, true)?.amount(),
+            user_fee + Rent::default().minimum_balance(spl_token::state::Account::LEN)
         );
 
-        let proof = test_proof();
-        verification_account.a.set(proof.a);
-        verification_account.b.set(proof.b);
-        verification_account.c.set(proof.c);
-        verification_account.set_state(&VerificationState::ProofSetup);
+        account_info!(recipient, Pubkey::new_from_array(recipient_bytes));
+        let fee_payer_pk = Pubkey::new_from_array(
+            VerificationAccount::new(&mut verification_acc_data)
+                .unwrap()
+                .get_other_data()
+                .fee_payer
+                .skip_mr(),
+        );
+        account_info!(f, fee_payer_pk);
+        test_account_info!(pool, 0);
+        test_account_info!(fee_collector, 0);
+        account_info!(optional_fee_collector, optional_fee_collector);
+        test_account_info!(any, 0);
+        account_info!(token_registry, TokenRegistryAccount::find(None).0, vec![]);
+        account_info!(sys, system_program::id());
+        test_pda_account_info!(
+            n_pda,
+            NullifierDuplicateAccount,
+            public_inputs
+                .join_split
+                .associated_nullifier_duplicate_pda_pubkey(),
+            None
+        );
+        account_info!(v_acc, Pubkey::new_unique(), verification_acc_data);
+        zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
+        zero_program_account!(mut metadata_queue, MetadataQueueAccount);
 
-        // Success
-        for _ in 0..COMBINED_MILLER_LOOP_IXS + FINAL_EXPONENTIATION_IXS {
-            assert_eq!(
-                compute_verification(
-                    &mut verification_account,
-                    &vkey,
-                    &any,
-                    0,
-                    SendQuadraVKey::VKEY_ID
-                ),
-                Ok(())
+        // Pool inflow
+        let pool_lamports = pool.lamports();
+        {
+            pda_account!(mut v_acc, VerificationAccount, v_acc);
+            v_acc.set_request(&request);
+            v_acc.set_prepare_inputs_instructions_count(
+                &(prepare_public_inputs_instructions(
+                    &public_inputs.public_signals_skip_mr(),
+                    SendQuadraVKey::public_inputs_count(),
+                )
+                .len() as u32),
             );
-        }
+            v_acc.set_state(&VerificationState::None);
 
-        // Computation is finished
-        assert_eq!(
-            compute_verification(
-                &mut verification_account,
-                &vkey,
+            init_verification_transfer_fee(
+                &f,
+                &f,
+                &pool,
+                &pool,
+                &fee_collector,
+                &fee_collector,
                 &any,
+                &any,
+                &governor,
+                &token_registry,
+                &mut v_acc,
+                &sys,
+                &sys,
                 0,
-                SendQuadraVKey::VKEY_ID
-            ),
-            Err(ElusivError::ComputationIsAlreadyFinished.into())
-        );
-        assert_eq!(verification_account.get_is_verified().option(), Some(false));
-    }
-
-    macro_rules! finalize_send_test {
-        (
-            $token_id: expr,
-            $optional_fee: expr,
-            $public_inputs: ident,
-            $v_data: ident,
-            $recipient: ident,
-            $identifier: ident,
-            $reference: ident,
-            $finalize_data: ident
-        ) => {
-            finalize_send_test!(
-                $token_id,
                 0,
-                $optional_fee,
-                $public_inputs,
-                $v_data,
-                $recipient,
-                $identifier,
-                $reference,
-                $finalize_data,
-                _optional_fee_collector
-            )
-        };
-        (
-            $token_id: expr,
-            $amount: expr,
-            $optional_fee: expr,
-            $public_inputs: ident,
-            $v_data: ident,
-            $recipient: ident,
-            $identifier: ident,
-            $reference: ident,
-            $finalize_data: ident,
-            $optional_fee_collector: ident
-        ) => {
-            let $recipient = Pubkey::new_unique().to_bytes();
-            let $identifier = Pubkey::new_unique().to_bytes();
-            let $reference = Pubkey::new_unique().to_bytes();
-            let iv = Pubkey::new_unique().to_bytes();
-            let encrypted_owner = Pubkey::new_unique().to_bytes();
+            )?;
 
-            let metadata = CommitmentMetadata::default();
-            let $optional_fee_collector = Pubkey::new_unique();
-            let optional_fee = OptionalFee {
-                collector: $optional_fee_collector,
-                amount: $optional_fee,
-            };
-            let $public_inputs = SendPublicInputs {
-                join_split: JoinSplitPublicInputs {
-                    input_commitments: vec![InputCommitment {
-                        root: Some(empty_root_raw()),
-                        nullifier_hash: RawU256::new(u256_from_str_skip_mr("1")),
-                    }],
-                    output_commitment: RawU256::new(u256_from_str_skip_mr("987654321")),
-                    recent_commitment_index: 123,
-                    fee_version: 0,
-                    amount: $amount,
-                    fee: 10000,
-                    optional_fee: optional_fee.clone(),
-                    token_id: $token_id,
-                    metadata,
-                },
-                recipient_is_associated_token_account: false,
-                hashed_inputs: generate_hashed_inputs(
-                    &$recipient,
-                    &$identifier,
-                    &iv,
-                    &encrypted_owner,
-                    &$reference,
-                    false,
-                    &metadata,
-                    &optional_fee,
-                    &None,
-                ),
-                solana_pay_transfer: false,
-            };
+            let mut data = v_acc.get_other_data();
+            data.recipient_wallet = ElusivOption::Some(RawU256::new(recipient_bytes));
+            v_acc.set_other_data(&data);
+            v_acc.set_state(&VerificationState::Finalized);
+            v_acc.set_is_verified(&ElusivOption::Some(true));
+        }
 
-            let mut $v_data = vec![0; VerificationAccount::SIZE];
-            let mut v_account = VerificationAccount::new(&mut $v_data).unwrap();
-            let fee_payer = RawU256::new(Pubkey::new_unique().to_bytes());
-            v_account
-                .setup(
-                    fee_payer,
-                    false,
-                    &[],
-                    &vec![0],
-                    0,
-                    ProofRequest::Send($public_inputs.clone()),
-                    [0, 1],
-                )
-                .unwrap();
-            v_account.set_state(&VerificationState::ProofSetup);
-            v_account.set_is_verified(&ElusivOption::Some(true));
-            v_account.set_other_data(&VerificationAccountData {
-                fee_payer,
-                fee_payer_account: fee_payer,
-                recipient_wallet: ElusivOption::Some(RawU256::new($recipient)),
-                ..Default::default()
-            });
+        // Note: the `commitment_hash_fee` transfer (CPI) is not executed in unit tests
+        let pool_inflow = pool.lamports() - pool_lamports;
+
+        // Pool outflow
+        let pool_lamports = pool.lamports();
+        let recipient_lamports = recipient.lamports();
+        finalize_verification_transfer_lamports(
+            &f,
+            &recipient,
+            &pool,
+            &fee_collector,
+            &optional_fee_collector,
+            &mut commitment_queue,
+            &mut metadata_queue,
+            &v_acc,
+            &n_pda,
+            &any,
+            &governor,
+            &rent_recipient,
+            &network_fee,
+            &v_index_acc,
+            0,
+            0,
+        )?;
+        let pool_outflow = pool_lamports - pool.lamports();
+        let recipient_received = recipient.lamports() - recipient_lamports;
 
-            let $finalize_data = FinalizeSendData {
-                total_amount: $public_inputs.join_split.total_amount(),
-                token_id: $token_id,
-                mt_index: 0,
-                commitment_index: 0,
-                encrypted_owner,
-                iv,
-            };
-        };
+        // The user pays `amount + fee`, which covers the pool outflow not covered by the subvention
+        let user_debit = public_inputs.join_split.amount + public_inputs.join_split.fee;
+        assert_eq!(pool_outflow - pool_inflow, user_debit);
+
+        // The recipient receives everything but the user-facing fee
+        assert_eq!(user_debit - recipient_received, user_fee);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_duplicate_verifications() -> ProgramResult {
+        let data = vec![0; NullifierDuplicateAccount::SIZE];
+        account_info!(n_pda, Pubkey::new_unique(), data);
+        let max_duplicate_verifications = 2;
+
+        for _ in 0..max_duplicate_verifications {
+            register_duplicate_verification(&n_pda, max_duplicate_verifications, 0, 0).unwrap();
+        }
+        assert_eq!(
+            register_duplicate_verification(&n_pda, max_duplicate_verifications, 0, 0),
+            Err(ElusivError::TooManyDuplicateVerifications.into())
+        );
+
+        // An aborted duplicate allows for a new one
+        release_duplicate_verification(&n_pda).unwrap();
+        {
+            pda_account!(n_pda, NullifierDuplicateAccount, n_pda);
+            assert_eq!(n_pda.get_duplicate_count(), 1);
+        }
+        register_duplicate_verification(&n_pda, max_duplicate_verifications, 0, 0).unwrap();
+
+        // Skipping is disabled
+        let data = vec![0; NullifierDuplicateAccount::SIZE];
+        account_info!(n_pda, Pubkey::new_unique(), data);
+        assert_eq!(
+            register_duplicate_verification(&n_pda, 0, 0, 0),
+            Err(ElusivError::TooManyDuplicateVerifications.into())
+        );
+
+        // Closed account
+        let data = vec![0; NullifierDuplicateAccount::SIZE];
+        account_info!(n_pda, Pubkey::new_unique(), data);
+        **n_pda.try_borrow_mut_lamports().unwrap() = 0;
+        assert_eq!(release_duplicate_verification(&n_pda), Ok(()));
+
+        Ok(())
     }
 
-    macro_rules! simple_storage_account {
-        ($id: ident) => {
-            let mut data = vec![0; StorageAccount::SIZE];
-            let $id =
-                <StorageAccount as elusiv_types::accounts::ProgramAccount>::new(&mut data).unwrap();
-        };
+    #[test]
+    fn test_duplicate_verification_grace_period() -> ProgramResult {
+        let max_duplicate_verifications = 3;
+        let grace_period = 5;
+
+        let mut data = vec![0; NullifierDuplicateAccount::SIZE];
+        NullifierDuplicateAccount::new(&mut data)?.set_last_use_slot(&100);
+        account_info!(n_pda, Pubkey::new_unique(), data);
+
+        // Reuse within the grace period
+        for slot in [0, 100, 104] {
+            assert_eq!(
+                register_duplicate_verification(
+                    &n_pda,
+                    max_duplicate_verifications,
+                    grace_period,
+                    slot
+                ),
+                Err(ElusivError::DuplicateVerificationGracePeriod.into())
+            );
+        }
+
+        // Reuse after the grace period
+        register_duplicate_verification(&n_pda, max_duplicate_verifications, grace_period, 105)?;
+        {
+            pda_account!(n_pda, NullifierDuplicateAccount, n_pda);
+            assert_eq!(n_pda.get_duplicate_count(), 1);
+            assert_eq!(n_pda.get_last_use_slot(), 105);
+        }
+
+        // The grace period restarts with each use
+        assert_eq!(
+            register_duplicate_verification(&n_pda, max_duplicate_verifications, grace_period, 109),
+            Err(ElusivError::DuplicateVerificationGracePeriod.into())
+        );
+        register_duplicate_verification(&n_pda, max_duplicate_verifications, grace_period, 110)?;
+
+        // Without a grace period, reuse within the same slot is possible
+        register_duplicate_verification(&n_pda, max_duplicate_verifications, 0, 110)?;
+
+        Ok(())
     }
 
     #[test]
-    fn test_finalize_verification_send_valid() {
+    fn test_close_timed_out_duplicate_verification() -> ProgramResult {
         finalize_send_test!(
-            USDC_TOKEN_ID,
+            LAMPORTS_TOKEN_ID,
             LAMPORTS_PER_SOL,
+            10,
             public_inputs,
             verification_acc_data,
-            recipient_bytes,
-            identifier_bytes,
-            reference_bytes,
-            finalize_data
+            _recipient_bytes,
+            _i,
+            _r,
+            _f,
+            _optional_fee_collector
         );
 
-        let mut verification_acc = VerificationAccount::new(&mut verification_acc_data).unwrap();
-        let mut data = vec![0; CommitmentQueueAccount::SIZE];
-        let mut queue = CommitmentQueueAccount::new(&mut data).unwrap();
-        simple_storage_account!(storage);
+        let fee_payer_pk = Pubkey::new_from_array(
+            VerificationAccount::new(&mut verification_acc_data)
+                .unwrap()
+                .get_other_data()
+                .fee_payer
+                .skip_mr(),
+        );
+        account_info!(f, fee_payer_pk); // fee_payer
+        test_account_info!(pool, 0);
+        test_account_info!(fee_collector, 0);
+        account_info!(sys, system_program::id());
+
+        let (n_pda_pk, n_pda_bump) = NullifierDuplicateAccount::find_with_pubkey(
+            public_inputs
+                .join_split
+                .associated_nullifier_duplicate_pda_pubkey(),
+            None,
+        );
+        let mut n_pda_data = vec![0; NullifierDuplicateAccount::SIZE];
+        n_pda_data[0] = n_pda_bump;
+        NullifierDuplicateAccount::new(&mut n_pda_data)?.set_duplicate_count(&2);
+        account_info!(n_pda, n_pda_pk, n_pda_data);
+        let n_pda_lamports = n_pda.lamports();
+
+        account_info!(v_acc, Pubkey::new_unique(), verification_acc_data);
+        test_account_info!(v_index_acc, 0);
         zero_program_account!(mut buffer, CommitmentBufferAccount);
 
-        account_info!(recipient, Pubkey::new_from_array(recipient_bytes));
-        account_info!(identifier, Pubkey::new_from_array(identifier_bytes));
-        account_info!(reference, Pubkey::new_from_array(reference_bytes));
+        let timestamp = VERIFICATION_TIMEOUT;
+        {
+            pda_account!(mut v_acc, VerificationAccount, v_acc);
+            v_acc.set_is_verified(&ElusivOption::None);
+            v_acc.set_proof_setup_timestamp(&0);
+            v_acc.set_timeout(&VERIFICATION_TIMEOUT);
+
+            let mut data = v_acc.get_other_data();
+            data.skip_nullifier_pda = true;
+            v_acc.set_other_data(&data);
+        }
+
+        close_verification(
+            &f,
+            &f,
+            &pool,
+            &pool,
+            &fee_collector,
+            &fee_collector,
+            &v_acc,
+            &v_index_acc,
+            &n_pda,
+            &mut buffer,
+            &sys,
+            0,
+            0,
+            timestamp,
+        )?;
+
+        // The duplicate-account remains open, but the aborted verification is released
+        assert_eq!(n_pda.lamports(), n_pda_lamports);
+        pda_account!(n_pda, NullifierDuplicateAccount, n_pda);
+        assert_eq!(n_pda.get_duplicate_count(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_close_timed_out_verification() -> ProgramResult {
+        finalize_send_test!(
+            LAMPORTS_TOKEN_ID,
+            LAMPORTS_PER_SOL,
+            10,
+            public_inputs,
+            verification_acc_data,
+            _recipient_bytes,
+            _i,
+            _r,
+            _f,
+            _optional_fee_collector
+        );
+
+        let fee_payer_pk = Pubkey::new(
+            &VerificationAccount::new(&mut verification_acc_data)
+                .unwrap()
+                .get_other_data()
+                .fee_payer
+                .skip_mr(),
+        );
+        account_info!(f, fee_payer_pk); // fee_payer
+        test_account_info!(pool, 0);
+        test_account_info!(fee_collector, 0);
         test_account_info!(any, 0);
+        account_info!(sys, system_program::id());
+        test_pda_account_info!(
+            n_pda,
+            NullifierDuplicateAccount,
+            public_inputs
+                .join_split
+                .associated_nullifier_duplicate_pda_pubkey(),
+            None
+        );
+        account_info!(v_acc, Pubkey::new_unique(), verification_acc_data);
+        test_account_info!(v_index_acc, 0);
+        zero_program_account!(mut buffer, CommitmentBufferAccount);
+        let commitment = public_inputs.join_split.output_commitment.reduce();
+        buffer.try_insert(&commitment).unwrap();
 
-        // Verification is not finished
-        verification_acc.set_is_verified(&ElusivOption::None);
+        let proof_setup_timestamp = 1_000;
+        let timeout = proof_setup_timestamp + VERIFICATION_TIMEOUT;
+        {
+            pda_account!(mut v_acc, VerificationAccount, v_acc);
+            v_acc.set_state(&VerificationState::FeeTransferred);
+            v_acc.set_is_verified(&ElusivOption::None);
+            v_acc.set_proof_setup_timestamp(&proof_setup_timestamp);
+            v_acc.set_timeout(&VERIFICATION_TIMEOUT);
+        }
+
+        // Invalid state
         assert_eq!(
-            finalize_verification_send(
-                &recipient,
-                &identifier,
-                &reference,
-                &mut queue,
-                &mut verification_acc,
-                &storage,
+            close_verification(
+                &f,
+                &f,
+                &pool,
+                &pool,
+                &fee_collector,
+                &fee_collector,
+                &v_acc,
+                &v_index_acc,
+                &n_pda,
                 &mut buffer,
-                &any,
+                &sys,
                 0,
-                finalize_data.clone(),
-                false,
+                0,
+                timeout
             ),
-            Err(ElusivError::ComputationIsNotYetFinished.into())
+            Err(ElusivError::InvalidAccountState.into())
         );
 
-        verification_acc.set_is_verified(&ElusivOption::Some(true));
-
-        // Invalid recipient
         {
-            account_info!(recipient, Pubkey::new_from_array(identifier_bytes));
-            assert_eq!(
-                finalize_verification_send(
-                    &recipient,
-                    &identifier,
-                    &reference,
-                    &mut queue,
-                    &mut verification_acc,
-                    &storage,
-                    &mut buffer,
-                    &any,
-                    0,
-                    finalize_data.clone(),
-                    false,
-                ),
-                Err(ElusivError::InputsMismatch.into())
-            );
+            pda_account!(mut v_acc, VerificationAccount, v_acc);
+            v_acc.set_state(&VerificationState::ProofSetup);
         }
 
-        // Invalid identifier
-        {
-            account_info!(identifier, Pubkey::new_from_array(recipient_bytes));
-            assert_eq!(
-                finalize_verification_send(
-                    &recipient,
-                    &identifier,
-                    &reference,
-                    &mut queue,
-                    &mut verification_acc,
-                    &storage,
-                    &mut buffer,
-                    &any,
-                    0,
-                    finalize_data.clone(),
-                    false,
-                ),
-                Err(ElusivError::InputsMismatch.into())
-            );
-        }
+        // Timeout not reached yet
+        assert_eq!(
+            close_verification(
+                &f,
+                &f,
+                &pool,
+                &pool,
+                &fee_collector,
+                &fee_collector,
+                &v_acc,
+                &v_index_acc,
+                &n_pda,
+                &mut buffer,
+                &sys,
+                0,
+                0,
+                timeout - 1
+            ),
+            Err(ElusivError::VerificationNotTimedOut.into())
+        );
+
+        // Cluster timestamp regressed below the proof setup timestamp (e.g. a clamped negative timestamp)
+        assert_eq!(
+            close_verification(
+                &f,
+                &f,
+                &pool,
+                &pool,
+                &fee_collector,
+                &fee_collector,
+                &v_acc,
+                &v_index_acc,
+                &n_pda,
+                &mut buffer,
+                &sys,
+                0,
+                0,
+                TestClockSysvar {
+                    slot: 0,
+                    unix_timestamp: -1,
+                }
+                .timestamp()?
+            ),
+            Err(ElusivError::VerificationNotTimedOut.into())
+        );
+
+        // Invalid original_fee_payer
+        assert_eq!(
+            close_verification(
+                &any,
+                &any,
+                &pool,
+                &pool,
+                &fee_collector,
+                &fee_collector,
+                &v_acc,
+                &v_index_acc,
+                &n_pda,
+                &mut buffer,
+                &sys,
+                0,
+                0,
+                timeout
+            ),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        // Invalid nullifier_duplicate_account
+        account_info!(
+            invalid_n_pda,
+            VerificationAccount::find_with_pubkey(*f.key, Some(0)).0,
+            vec![1]
+        );
+        assert_eq!(
+            close_verification(
+                &f,
+                &f,
+                &pool,
+                &pool,
+                &fee_collector,
+                &fee_collector,
+                &v_acc,
+                &v_index_acc,
+                &invalid_n_pda,
+                &mut buffer,
+                &sys,
+                0,
+                0,
+                timeout
+            ),
+            Err(ElusivError::InvalidAccount.into())
+        );
 
-        // Invalid reference
-        {
-            account_info!(reference, Pubkey::new_from_array(recipient_bytes));
-            assert_eq!(
-                finalize_verification_send(
-                    &recipient,
-                    &identifier,
-                    &reference,
-                    &mut queue,
-                    &mut verification_acc,
-                    &storage,
-                    &mut buffer,
-                    &any,
-                    0,
-                    finalize_data.clone(),
-                    false,
-                ),
-                Err(ElusivError::InputsMismatch.into())
-            );
-        }
+        // Proof has already been verified
+        for is_verified in [true, false] {
+            {
+                pda_account!(mut v_acc, VerificationAccount, v_acc);
+                v_acc.set_is_verified(&ElusivOption::Some(is_verified));
+            }
 
-        // Invalid finalize_data
-        for invalid_data in [
-            mutate(&finalize_data, |d| {
-                d.total_amount = public_inputs.join_split.amount
-            }),
-            mutate(&finalize_data, |d| d.token_id = 0),
-            mutate(&finalize_data, |d| d.commitment_index = 1),
-            mutate(&finalize_data, |d| d.mt_index = 1),
-            mutate(&finalize_data, |d| d.encrypted_owner = d.iv),
-            mutate(&finalize_data, |d| d.iv = d.encrypted_owner),
-        ] {
             assert_eq!(
-                finalize_verification_send(
-                    &recipient,
-                    &identifier,
-                    &reference,
-                    &mut queue,
-                    &mut verification_acc,
-                    &storage,
+                close_verification(
+                    &f,
+                    &f,
+                    &pool,
+                    &pool,
+                    &fee_collector,
+                    &fee_collector,
+                    &v_acc,
+                    &v_index_acc,
+                    &n_pda,
                     &mut buffer,
-                    &any,
+                    &sys,
                     0,
-                    invalid_data,
-                    false,
+                    0,
+                    timeout
                 ),
-                Err(ElusivError::InputsMismatch.into())
+                Err(ElusivError::ComputationIsAlreadyFinished.into())
             );
         }
 
-        // Success
+        let failure_bond = Lamports(1_000);
+        let subvention = {
+            pda_account!(mut v_acc, VerificationAccount, v_acc);
+            v_acc.set_is_verified(&ElusivOption::None);
+
+            let mut data = v_acc.get_other_data();
+            data.failure_bond = failure_bond;
+            v_acc.set_other_data(&data);
+            data.subvention
+        };
+        let fee_collector_lamports = fee_collector.lamports();
+
         assert_eq!(
-            finalize_verification_send(
-                &recipient,
-                &identifier,
-                &reference,
-                &mut queue,
-                &mut verification_acc,
-                &storage,
+            close_verification(
+                &f,
+                &f,
+                &pool,
+                &pool,
+                &fee_collector,
+                &fee_collector,
+                &v_acc,
+                &v_index_acc,
+                &n_pda,
                 &mut buffer,
-                &any,
+                &sys,
                 0,
-                finalize_data.clone(),
-                false,
+                0,
+                timeout
             ),
             Ok(())
         );
 
-        assert_eq!(
-            verification_acc.get_state(),
-            VerificationState::InsertNullifiers
-        );
+        assert_eq!(n_pda.lamports(), 0);
+        assert_eq!(v_acc.lamports(), 0);
+        assert_eq!(buffer.find_position(&commitment), None);
 
-        // Called twice
+        // The failure-bond is forfeited to the fee-collector
         assert_eq!(
-            finalize_verification_send(
-                &recipient,
-                &identifier,
-                &reference,
-                &mut queue,
-                &mut verification_acc,
-                &storage,
-                &mut buffer,
-                &any,
-                0,
-                finalize_data,
-                false,
-            ),
-            Err(ElusivError::InvalidAccountState.into())
+            fee_collector.lamports(),
+            fee_collector_lamports + subvention + failure_bond.0
         );
+        pda_account!(v_acc, VerificationAccount, v_acc);
+        assert_eq!(v_acc.get_state(), VerificationState::Closed);
+
+        Ok(())
     }
 
     #[test]
-    fn test_finalize_verification_send_invalid() {
+    fn test_cleanup_stalled_verification() -> ProgramResult {
         finalize_send_test!(
-            USDC_TOKEN_ID,
+            LAMPORTS_TOKEN_ID,
             LAMPORTS_PER_SOL,
+            10,
             public_inputs,
             verification_acc_data,
-            recipient_bytes,
-            identifier_bytes,
-            reference_bytes,
-            finalize_data
+            _recipient_bytes,
+            _i,
+            _r,
+            _f,
+            _optional_fee_collector
         );
 
-        let mut verification_acc = VerificationAccount::new(&mut verification_acc_data).unwrap();
-        let mut data = vec![0; CommitmentQueueAccount::SIZE];
-        let mut queue = CommitmentQueueAccount::new(&mut data).unwrap();
-        simple_storage_account!(storage);
+        let fee_payer_pk = Pubkey::new_from_array(
+            VerificationAccount::new(&mut verification_acc_data)
+                .unwrap()
+                .get_other_data()
+                .fee_payer
+                .skip_mr(),
+        );
+        account_info!(f, fee_payer_pk); // fee_payer
+        test_account_info!(caller, 0);
+        test_account_info!(pool, 0);
+        test_account_info!(fee_collector, 0);
+        account_info!(sys, system_program::id());
+        test_pda_account_info!(
+            n_pda,
+            NullifierDuplicateAccount,
+            public_inputs
+                .join_split
+                .associated_nullifier_duplicate_pda_pubkey(),
+            None
+        );
+        account_info!(v_acc, Pubkey::new_unique(), verification_acc_data);
+        test_account_info!(v_index_acc, 0);
         zero_program_account!(mut buffer, CommitmentBufferAccount);
-        test_account_info!(any, 0);
+        let commitment = public_inputs.join_split.output_commitment.reduce();
+        buffer.try_insert(&commitment).unwrap();
 
-        account_info!(recipient, Pubkey::new_from_array(recipient_bytes));
-        account_info!(identifier, Pubkey::new_from_array(identifier_bytes));
-        account_info!(reference, Pubkey::new_from_array(reference_bytes));
+        // Timed out (the test clock is at zero)
+        let commitment_hash_fee = {
+            pda_account!(mut v_acc, VerificationAccount, v_acc);
+            v_acc.set_state(&VerificationState::ProofSetup);
+            v_acc.set_is_verified(&ElusivOption::None);
+            v_acc.set_proof_setup_timestamp(&0);
+            v_acc.set_timeout(&0);
+            v_acc.get_other_data().commitment_hash_fee
+        };
+        let rent = v_acc.lamports() + n_pda.lamports();
+        let caller_lamports = caller.lamports();
+        let fee_payer_lamports = f.lamports();
+
+        cleanup_stalled_verification(
+            &caller,
+            &f,
+            &pool,
+            &pool,
+            &fee_collector,
+            &fee_collector,
+            &v_acc,
+            &v_index_acc,
+            &n_pda,
+            &mut buffer,
+            &sys,
+            0,
+            0,
+        )?;
 
-        verification_acc.set_is_verified(&ElusivOption::Some(false));
+        // The rent is the caller's bounty, the fees are refunded as for a timed out verification
+        assert_eq!(caller.lamports(), caller_lamports + rent);
+        assert_eq!(f.lamports(), fee_payer_lamports + commitment_hash_fee.0);
+        pda_account!(v_acc, VerificationAccount, v_acc);
+        assert_eq!(v_acc.get_state(), VerificationState::Closed);
+        assert_eq!(buffer.find_position(&commitment), None);
 
-        assert_eq!(
-            finalize_verification_send(
-                &recipient,
-                &identifier,
-                &reference,
-                &mut queue,
-                &mut verification_acc,
-                &storage,
-                &mut buffer,
-                &any,
-                0,
-                finalize_data,
-                false,
-            ),
-            Ok(())
-        );
-        assert_eq!(verification_acc.get_state(), VerificationState::Finalized);
+        Ok(())
     }
 
     #[test]
-    fn test_finalize_verification_migrate() {
-        let migrate_public_inputs = MigratePublicInputs {
-            join_split: JoinSplitPublicInputs {
-                input_commitments: vec![InputCommitment {
-                    root: Some(empty_root_raw()),
-                    nullifier_hash: RawU256::new(u256_from_str_skip_mr("1")),
-                }],
-                output_commitment: RawU256::new(u256_from_str_skip_mr("1")),
-                recent_commitment_index: 123,
-                fee_version: 0,
-                amount: LAMPORTS_PER_SOL,
-                fee: 10000,
-                optional_fee: OptionalFee::default(),
-                token_id: 0,
-                metadata: CommitmentMetadata::default(),
-            },
-            current_nsmt_root: RawU256::new([0; 32]),
-            next_nsmt_root: RawU256::new([0; 32]),
-        };
+    fn test_abort_verification() -> ProgramResult {
+        for state in [VerificationState::None, VerificationState::FeeTransferred] {
+            finalize_send_test!(
+                LAMPORTS_TOKEN_ID,
+                LAMPORTS_PER_SOL,
+                10,
+                public_inputs,
+                verification_acc_data,
+                _recipient_bytes,
+                _i,
+                _r,
+                _f,
+                _optional_fee_collector
+            );
 
-        let pk = Pubkey::new_unique();
-        account_info!(acc, pk);
+            let fee_payer_pk = Pubkey::new_from_array(
+                VerificationAccount::new(&mut verification_acc_data)
+                    .unwrap()
+                    .get_other_data()
+                    .fee_payer
+                    .skip_mr(),
+            );
+            account_info!(f, fee_payer_pk); // fee_payer
+            test_account_info!(pool, 0);
+            test_account_info!(fee_collector, 0);
+            test_account_info!(any, 0);
+            account_info!(sys, system_program::id());
+            test_pda_account_info!(
+                n_pda,
+                NullifierDuplicateAccount,
+                public_inputs
+                    .join_split
+                    .associated_nullifier_duplicate_pda_pubkey(),
+                None
+            );
+            account_info!(v_acc, Pubkey::new_unique(), verification_acc_data);
 
-        let mut data = vec![0; VerificationAccount::SIZE];
-        let mut v_account = VerificationAccount::new(&mut data).unwrap();
-        v_account.set_request(&ProofRequest::Migrate(migrate_public_inputs));
-        v_account.set_state(&VerificationState::ProofSetup);
-        v_account.set_is_verified(&ElusivOption::Some(true));
+            let mut v_index_data = vec![0; VerificationIndexAccount::SIZE];
+            {
+                let mut v_index = VerificationIndexAccount::new(&mut v_index_data).unwrap();
+                v_index.open_index(0, 0).unwrap();
+                v_index.open_index(1, 1).unwrap();
+            }
+            account_info!(v_index_acc, Pubkey::new_unique(), v_index_data);
+            zero_program_account!(mut buffer, CommitmentBufferAccount);
+            let commitment = public_inputs.join_split.output_commitment.reduce();
+            buffer.try_insert(&commitment).unwrap();
+
+            let failure_bond = Lamports(1_000);
+            let data = {
+                pda_account!(mut v_acc, VerificationAccount, v_acc);
+                v_acc.set_state(&VerificationState::ProofSetup);
+                v_acc.set_is_verified(&ElusivOption::None);
+
+                let mut data = v_acc.get_other_data();
+                data.commitment_hash_fee = Lamports(2_000);
+                data.subvention = 3_000;
+                data.failure_bond = failure_bond;
+                v_acc.set_other_data(&data);
+                data
+            };
 
-        let mut data = vec![0; CommitmentQueueAccount::SIZE];
-        let mut queue = CommitmentQueueAccount::new(&mut data).unwrap();
+            // Invalid states (proof has already been setup)
+            for invalid_state in [
+                VerificationState::ProofSetup,
+                VerificationState::InsertNullifiers,
+                VerificationState::Finalized,
+                VerificationState::Closed,
+            ] {
+                {
+                    pda_account!(mut v_acc, VerificationAccount, v_acc);
+                    v_acc.set_state(&invalid_state);
+                }
 
-        let finalize_data = FinalizeSendData::default();
-        simple_storage_account!(storage);
-        zero_program_account!(mut buffer, CommitmentBufferAccount);
-        test_account_info!(any, 0);
+                assert_eq!(
+                    abort_verification(
+                        &f,
+                        &pool,
+                        &pool,
+                        &fee_collector,
+                        &fee_collector,
+                        &v_acc,
+                        &v_index_acc,
+                        &n_pda,
+                        &mut buffer,
+                        &sys,
+                        0,
+                        0
+                    ),
+                    Err(ElusivError::InvalidAccountState.into())
+                );
+            }
+            assert!(buffer.find_position(&commitment).is_some());
 
-        assert_eq!(
-            finalize_verification_send(
-                &acc,
-                &acc,
-                &acc,
-                &mut queue,
-                &mut v_account,
-                &storage,
-                &mut buffer,
-                &any,
-                0,
-                finalize_data,
-                false,
-            ),
-            Err(ElusivError::FeatureNotAvailable.into())
-        );
-    }
+            {
+                pda_account!(mut v_acc, VerificationAccount, v_acc);
+                v_acc.set_state(&state);
+            }
 
-    #[test]
-    fn test_finalize_verification_insert_nullifier() {
-        finalize_send_test!(
-            USDC_TOKEN_ID,
-            LAMPORTS_PER_SOL,
-            public_inputs,
-            verification_acc_data,
-            _recipient_bytes,
-            _identifier_bytes,
-            _reference_bytes,
-            _finalize_data
-        );
+            // Invalid original_fee_payer
+            assert_eq!(
+                abort_verification(
+                    &any,
+                    &pool,
+                    &pool,
+                    &fee_collector,
+                    &fee_collector,
+                    &v_acc,
+                    &v_index_acc,
+                    &n_pda,
+                    &mut buffer,
+                    &sys,
+                    0,
+                    0
+                ),
+                Err(ElusivError::InvalidAccount.into())
+            );
+
+            let rent = v_acc.lamports() + n_pda.lamports();
+            let fee_payer_lamports = f.lamports();
+            let fee_collector_lamports = fee_collector.lamports();
+
+            assert_eq!(
+                abort_verification(
+                    &f,
+                    &pool,
+                    &pool,
+                    &fee_collector,
+                    &fee_collector,
+                    &v_acc,
+                    &v_index_acc,
+                    &n_pda,
+                    &mut buffer,
+                    &sys,
+                    0,
+                    0
+                ),
+                Ok(())
+            );
+
+            assert_eq!(n_pda.lamports(), 0);
+            assert_eq!(v_acc.lamports(), 0);
+            assert_eq!(buffer.find_position(&commitment), None);
+
+            if state == VerificationState::FeeTransferred {
+                // Fees are refunded, subvention and failure-bond flow to the fee-collector
+                assert_eq!(
+                    f.lamports(),
+                    fee_payer_lamports + rent + data.commitment_hash_fee.0
+                );
+                assert_eq!(
+                    fee_collector.lamports(),
+                    fee_collector_lamports + data.subvention + failure_bond.0
+                );
+            } else {
+                // No fees have been transferred yet
+                assert_eq!(f.lamports(), fee_payer_lamports + rent);
+                assert_eq!(fee_collector.lamports(), fee_collector_lamports);
+            }
 
-        let mut verification_acc = VerificationAccount::new(&mut verification_acc_data).unwrap();
-        parent_account!(mut n_acc_0, NullifierAccount);
+            pda_account!(v_acc, VerificationAccount, v_acc);
+            assert_eq!(v_acc.get_state(), VerificationState::Closed);
 
-        // finalize_verification_send not called
-        verification_acc.set_state(&VerificationState::InsertNullifiers);
+            // Only the aborted verification is no longer open
+            pda_account!(v_index, VerificationIndexAccount, v_index_acc);
+            assert!(!v_index.is_index_open(0));
+            assert!(v_index.is_index_open(1));
+        }
 
-        // Nullifier duplicate
-        n_acc_0
-            .try_insert_nullifier_hash(
-                public_inputs.join_split.input_commitments[0]
-                    .nullifier_hash
-                    .reduce(),
-            )
-            .unwrap();
-        assert_eq!(
-            finalize_verification_insert_nullifier(&mut verification_acc, &mut n_acc_0, 0),
-            Err(ElusivError::CouldNotInsertNullifier.into())
-        );
+        Ok(())
+    }
 
-        parent_account!(mut n_acc_0, NullifierAccount);
+    #[test]
+    fn test_log_open_verifications() {
+        let fee_payer_pk = Pubkey::new_unique();
+        account_info!(fee_payer, fee_payer_pk);
+
+        let mut data = vec![0; VerificationIndexAccount::SIZE];
+        let mut verification_index = VerificationIndexAccount::new(&mut data).unwrap();
+        for index in 0..3 {
+            verification_index.open_index(index, index as u32).unwrap();
+        }
+        verification_index.close_index(1, 1);
 
-        // Success
         assert_eq!(
-            finalize_verification_insert_nullifier(&mut verification_acc, &mut n_acc_0, 0),
-            Ok(())
+            verification_index
+                .open_verifications(&fee_payer_pk)
+                .iter()
+                .map(|(pda_offset, _)| *pda_offset)
+                .collect::<Vec<_>>(),
+            vec![
+                VerificationAccount::pda_offset(0, 0),
+                VerificationAccount::pda_offset(2, 2)
+            ]
         );
-
-        assert!(!n_acc_0
-            .can_insert_nullifier_hash(
-                public_inputs.join_split.input_commitments[0]
-                    .nullifier_hash
-                    .reduce()
-            )
-            .unwrap());
-        assert_eq!(verification_acc.get_state(), VerificationState::Finalized);
-
-        // Called twice
         assert_eq!(
-            finalize_verification_insert_nullifier(&mut verification_acc, &mut n_acc_0, 0),
-            Err(ElusivError::InvalidAccountState.into())
+            log_open_verifications(&fee_payer, &verification_index),
+            Ok(())
         );
     }
 
     #[test]
-    fn test_finalize_verification_transfer_lamports() -> ProgramResult {
+    fn test_finalize_verification_transfer_lamports_merge() -> ProgramResult {
+        zero_program_account!(governor, GovernorAccount);
+        test_account_info!(rent_recipient, 0);
+        zero_pda_account_info!(
+            network_fee,
+            NetworkFeeAccount,
+            Some(LAMPORTS_TOKEN_ID.into())
+        );
+        test_account_info!(v_index_acc, 0);
         finalize_send_test!(
             LAMPORTS_TOKEN_ID,
-            LAMPORTS_PER_SOL,
-            10,
+            0,
             public_inputs,
             verification_acc_data,
             recipient_bytes,
             _i,
             _r,
-            _f,
-            optional_fee_collector
+            _f
         );
 
-        account_info!(recipient, Pubkey::new_from_array(recipient_bytes));
         let fee_payer_pk = Pubkey::new(
             &VerificationAccount::new(&mut verification_acc_data)
                 .unwrap()
@@ -3046,7 +8460,7 @@ mod tests {
         account_info!(f, fee_payer_pk); // fee_payer
         test_account_info!(pool, 0);
         test_account_info!(fee_collector, 0);
-        account_info!(optional_fee_collector, optional_fee_collector);
+        test_account_info!(optional_fee_collector, 0);
         test_account_info!(any, 0);
         test_pda_account_info!(
             n_pda,
@@ -3056,17 +8470,20 @@ mod tests {
                 .associated_nullifier_duplicate_pda_pubkey(),
             None
         );
+
         account_info!(v_acc, Pubkey::new_unique(), verification_acc_data);
         zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
         zero_program_account!(mut metadata_queue, MetadataQueueAccount);
 
         {
             pda_account!(mut v_acc, VerificationAccount, v_acc);
-            v_acc.set_state(&VerificationState::None);
+            v_acc.set_state(&VerificationState::Finalized);
             v_acc.set_is_verified(&ElusivOption::Some(true));
         }
 
-        // Invalid state
+        // For merges (zero-amount) the recipient key is ignored
+        account_info!(recipient, Pubkey::new_unique());
+        let recipient_lamports = recipient.lamports();
         assert_eq!(
             finalize_verification_transfer_lamports(
                 &f,
@@ -3079,56 +8496,82 @@ mod tests {
                 &v_acc,
                 &n_pda,
                 &any,
+                &governor,
+                &rent_recipient,
+                &network_fee,
+                &v_index_acc,
+                0,
                 0
             ),
-            Err(ElusivError::InvalidAccountState.into())
+            Ok(())
         );
 
-        {
-            pda_account!(mut v_acc, VerificationAccount, v_acc);
-            v_acc.set_state(&VerificationState::Finalized);
-        }
+        // No recipient transfer
+        assert_eq!(recipient.lamports(), recipient_lamports);
 
-        // Invalid nullifier_duplicate_account
+        Ok(())
+    }
+
+    #[test]
+    fn test_finalize_verification_transfer_lamports_program_owned_recipient() -> ProgramResult {
+        zero_program_account!(governor, GovernorAccount);
+        test_account_info!(rent_recipient, 0);
+        zero_pda_account_info!(
+            network_fee,
+            NetworkFeeAccount,
+            Some(LAMPORTS_TOKEN_ID.into())
+        );
+        test_account_info!(v_index_acc, 0);
+        finalize_send_test!(
+            LAMPORTS_TOKEN_ID,
+            LAMPORTS_PER_SOL,
+            10,
+            public_inputs,
+            verification_acc_data,
+            recipient_bytes,
+            _i,
+            _r,
+            _f,
+            optional_fee_collector,
+            true
+        );
+
+        // The vault PDA is owned by some other program
         account_info!(
-            invalid_n_pda,
-            VerificationAccount::find_with_pubkey(*f.key, Some(0)).0,
-            vec![1]
+            recipient,
+            Pubkey::new_from_array(recipient_bytes),
+            vec![0; 8],
+            Pubkey::new_unique(),
+            false
         );
-        assert_eq!(
-            finalize_verification_transfer_lamports(
-                &f,
-                &recipient,
-                &pool,
-                &fee_collector,
-                &optional_fee_collector,
-                &mut commitment_queue,
-                &mut metadata_queue,
-                &v_acc,
-                &invalid_n_pda,
-                &any,
-                0
-            ),
-            Err(ElusivError::InvalidAccount.into())
+        let fee_payer_pk = Pubkey::new(
+            &VerificationAccount::new(&mut verification_acc_data)
+                .unwrap()
+                .get_other_data()
+                .fee_payer
+                .skip_mr(),
         );
-
-        // Invalid original_fee_payer
-        assert_eq!(
-            finalize_verification_transfer_lamports(
-                &any,
-                &recipient,
-                &pool,
-                &fee_collector,
-                &optional_fee_collector,
-                &mut commitment_queue,
-                &mut metadata_queue,
-                &v_acc,
-                &n_pda,
-                &any,
-                0
-            ),
-            Err(ElusivError::InvalidAccount.into())
+        account_info!(f, fee_payer_pk);
+        test_account_info!(pool, 0);
+        test_account_info!(fee_collector, 0);
+        account_info!(optional_fee_collector, optional_fee_collector);
+        test_account_info!(any, 0);
+        test_pda_account_info!(
+            n_pda,
+            NullifierDuplicateAccount,
+            public_inputs
+                .join_split
+                .associated_nullifier_duplicate_pda_pubkey(),
+            None
         );
+        account_info!(v_acc, Pubkey::new_unique(), verification_acc_data);
+        zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
+        zero_program_account!(mut metadata_queue, MetadataQueueAccount);
+
+        {
+            pda_account!(mut v_acc, VerificationAccount, v_acc);
+            v_acc.set_state(&VerificationState::Finalized);
+        }
 
         // Invalid recipient
         assert_eq!(
@@ -3143,43 +8586,17 @@ mod tests {
                 &v_acc,
                 &n_pda,
                 &any,
+                &governor,
+                &rent_recipient,
+                &network_fee,
+                &v_index_acc,
+                0,
                 0
             ),
             Err(ElusivError::InvalidRecipient.into())
         );
 
-        // Invalid optional-fee-collector
-        test_account_info!(invalid_optional_fee_collector, 0);
-        assert_eq!(
-            finalize_verification_transfer_lamports(
-                &f,
-                &recipient,
-                &pool,
-                &fee_collector,
-                &invalid_optional_fee_collector,
-                &mut commitment_queue,
-                &mut metadata_queue,
-                &v_acc,
-                &n_pda,
-                &any,
-                0
-            ),
-            Err(ElusivError::InvalidAccount.into())
-        );
-
-        // Commitment queue is full
-        {
-            let mut queue = CommitmentQueue::new(&mut commitment_queue);
-            for _ in 0..CommitmentQueue::CAPACITY {
-                queue
-                    .enqueue(CommitmentHashRequest {
-                        commitment: [0; 32],
-                        fee_version: 0,
-                        min_batching_rate: 0,
-                    })
-                    .unwrap();
-            }
-        }
+        let recipient_lamports = recipient.lamports();
         assert_eq!(
             finalize_verification_transfer_lamports(
                 &f,
@@ -3192,32 +8609,20 @@ mod tests {
                 &v_acc,
                 &n_pda,
                 &any,
+                &governor,
+                &rent_recipient,
+                &network_fee,
+                &v_index_acc,
+                0,
                 0
             ),
-            Err(ElusivError::QueueIsFull.into())
+            Ok(())
         );
 
-        zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
-
         assert_eq!(
-            finalize_verification_transfer_lamports(
-                &f,
-                &recipient,
-                &pool,
-                &fee_collector,
-                &optional_fee_collector,
-                &mut commitment_queue,
-                &mut metadata_queue,
-                &v_acc,
-                &n_pda,
-                &any,
-                0
-            ),
-            Ok(())
+            recipient.lamports(),
+            recipient_lamports + LAMPORTS_PER_SOL - 10
         );
-
-        assert_eq!(n_pda.lamports(), 0);
-        assert_eq!(v_acc.lamports(), 0);
         pda_account!(v_acc, VerificationAccount, v_acc);
         assert_eq!(v_acc.get_state(), VerificationState::Closed);
 
@@ -3225,55 +8630,69 @@ mod tests {
     }
 
     #[test]
-    fn test_finalize_verification_transfer_lamports_merge() -> ProgramResult {
-        finalize_send_test!(
-            LAMPORTS_TOKEN_ID,
-            0,
-            public_inputs,
-            verification_acc_data,
-            recipient_bytes,
-            _i,
-            _r,
-            _f
-        );
+    fn test_finalize_verification_transfer_lamports_self_withdrawal() -> ProgramResult {
+        // Returns the lamports received by the fee-payer and the recipient
+        fn finalize(self_withdrawal: bool) -> Result<(u64, u64), ProgramError> {
+            zero_program_account!(governor, GovernorAccount);
+            test_account_info!(rent_recipient, 0);
+            zero_pda_account_info!(
+                network_fee,
+                NetworkFeeAccount,
+                Some(LAMPORTS_TOKEN_ID.into())
+            );
+            test_account_info!(v_index_acc, 0);
+            finalize_send_test!(
+                LAMPORTS_TOKEN_ID,
+                LAMPORTS_PER_SOL,
+                10,
+                public_inputs,
+                verification_acc_data,
+                recipient_bytes,
+                _i,
+                _r,
+                _f,
+                optional_fee_collector
+            );
 
-        let fee_payer_pk = Pubkey::new(
-            &VerificationAccount::new(&mut verification_acc_data)
-                .unwrap()
-                .get_other_data()
-                .fee_payer
-                .skip_mr(),
-        );
-        account_info!(f, fee_payer_pk); // fee_payer
-        test_account_info!(pool, 0);
-        test_account_info!(fee_collector, 0);
-        test_account_info!(optional_fee_collector, 0);
-        test_account_info!(any, 0);
-        test_pda_account_info!(
-            n_pda,
-            NullifierDuplicateAccount,
-            public_inputs
-                .join_split
-                .associated_nullifier_duplicate_pda_pubkey(),
-            None
-        );
+            let fee_payer_pk = {
+                let mut v_acc = VerificationAccount::new(&mut verification_acc_data).unwrap();
+                let mut data = v_acc.get_other_data();
+                if self_withdrawal {
+                    data.recipient_wallet = ElusivOption::Some(data.fee_payer);
+                }
+                data.commitment_hash_fee_token = 100;
+                data.proof_verification_fee = 200;
+                data.network_fee = 50;
+                data.failure_bond = Lamports(300);
+                v_acc.set_other_data(&data);
+                v_acc.set_state(&VerificationState::Finalized);
+                Pubkey::new_from_array(data.fee_payer.skip_mr())
+            };
 
-        account_info!(v_acc, Pubkey::new_unique(), verification_acc_data);
-        zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
-        zero_program_account!(mut metadata_queue, MetadataQueueAccount);
+            account_info!(f, fee_payer_pk);
+            account_info!(recipient, Pubkey::new_from_array(recipient_bytes));
+            test_account_info!(pool, 0);
+            test_account_info!(fee_collector, 0);
+            account_info!(optional_fee_collector, optional_fee_collector);
+            test_account_info!(any, 0);
+            test_pda_account_info!(
+                n_pda,
+                NullifierDuplicateAccount,
+                public_inputs
+                    .join_split
+                    .associated_nullifier_duplicate_pda_pubkey(),
+                None
+            );
+            account_info!(v_acc, Pubkey::new_unique(), verification_acc_data);
+            zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
+            zero_program_account!(mut metadata_queue, MetadataQueueAccount);
 
-        {
-            pda_account!(mut v_acc, VerificationAccount, v_acc);
-            v_acc.set_state(&VerificationState::Finalized);
-            v_acc.set_is_verified(&ElusivOption::Some(true));
-        }
+            let fee_payer_lamports = f.lamports();
+            let recipient_lamports = recipient.lamports();
 
-        // For merges (zero-amount) the recipient key is ignored
-        account_info!(recipient, Pubkey::new_unique());
-        assert_eq!(
             finalize_verification_transfer_lamports(
                 &f,
-                &recipient,
+                if self_withdrawal { &f } else { &recipient },
                 &pool,
                 &fee_collector,
                 &optional_fee_collector,
@@ -3282,16 +8701,35 @@ mod tests {
                 &v_acc,
                 &n_pda,
                 &any,
-                0
-            ),
-            Ok(())
-        );
+                &governor,
+                &rent_recipient,
+                &network_fee,
+                &v_index_acc,
+                0,
+                0,
+            )?;
+
+            Ok((
+                f.lamports() - fee_payer_lamports,
+                recipient.lamports() - recipient_lamports,
+            ))
+        }
+
+        let (fee_payer, recipient) = finalize(false)?;
+        assert_eq!(recipient, LAMPORTS_PER_SOL - 10);
+
+        // The fee-payer receives the same total as fee-payer and recipient in the non-self case
+        assert_eq!(finalize(true)?, (fee_payer + recipient, 0));
 
         Ok(())
     }
 
     #[test]
     fn test_finalize_verification_transfer_token() -> ProgramResult {
+        zero_program_account!(mut governor, GovernorAccount);
+        test_account_info!(rent_recipient, 0);
+        zero_pda_account_info!(network_fee, NetworkFeeAccount, Some(USDC_TOKEN_ID.into()));
+        test_account_info!(v_index_acc, 0);
         finalize_send_test!(
             USDC_TOKEN_ID,
             LAMPORTS_PER_SOL,
@@ -3355,6 +8793,37 @@ mod tests {
             v_acc.set_is_verified(&ElusivOption::Some(true));
         }
 
+        // Paused finalizations
+        governor.set_paused_categories(&Category::Finalize.bit());
+        assert_eq!(
+            finalize_verification_transfer_token(
+                &fee_payer,
+                &fee_payer_token,
+                &r,
+                &r,
+                &pool,
+                &pool_token,
+                &fee_collector,
+                &fee_collector_token,
+                &optional_fee_collector,
+                &mut commitment_queue,
+                &mut metadata_queue,
+                &v_acc,
+                &n_pda,
+                &spl,
+                &any,
+                &any,
+                &governor,
+                &rent_recipient,
+                &network_fee,
+                &v_index_acc,
+                0,
+                0
+            ),
+            Err(ElusivError::CategoryPaused.into())
+        );
+        governor.set_paused_categories(&(PAUSABLE_CATEGORIES & !Category::Finalize.bit()));
+
         // Invalid pool_account
         assert_eq!(
             finalize_verification_transfer_token(
@@ -3374,6 +8843,11 @@ mod tests {
                 &spl,
                 &any,
                 &any,
+                &governor,
+                &rent_recipient,
+                &network_fee,
+                &v_index_acc,
+                0,
                 0
             ),
             Err(ElusivError::InvalidAccount.into())
@@ -3398,6 +8872,11 @@ mod tests {
                 &spl,
                 &any,
                 &any,
+                &governor,
+                &rent_recipient,
+                &network_fee,
+                &v_index_acc,
+                0,
                 0
             ),
             Err(ElusivError::InvalidAccount.into())
@@ -3423,6 +8902,11 @@ mod tests {
                 &spl,
                 &any,
                 &any,
+                &governor,
+                &rent_recipient,
+                &network_fee,
+                &v_index_acc,
+                0,
                 0
             ),
             Err(ElusivError::InvalidAccount.into())
@@ -3447,6 +8931,11 @@ mod tests {
                 &any,
                 &any,
                 &any,
+                &governor,
+                &rent_recipient,
+                &network_fee,
+                &v_index_acc,
+                0,
                 0
             ),
             Err(ElusivError::InvalidAccount.into())
@@ -3471,6 +8960,11 @@ mod tests {
                 &spl,
                 &any,
                 &any,
+                &governor,
+                &rent_recipient,
+                &network_fee,
+                &v_index_acc,
+                0,
                 0
             ),
             Err(ElusivError::InvalidAccount.into())
@@ -3495,11 +8989,55 @@ mod tests {
                 &spl,
                 &any,
                 &any,
+                &governor,
+                &rent_recipient,
+                &network_fee,
+                &v_index_acc,
+                0,
                 0
             ),
             Err(ElusivError::InvalidRecipient.into())
         );
 
+        // Stored request fee_version differs from the one captured at the fee transfer
+        {
+            pda_account!(mut v_acc, VerificationAccount, v_acc);
+            let mut public_inputs = public_inputs.clone();
+            public_inputs.join_split.fee_version = 1;
+            v_acc.set_request(&ProofRequest::Send(public_inputs));
+        }
+        assert_eq!(
+            finalize_verification_transfer_token(
+                &fee_payer,
+                &fee_payer_token,
+                &r,
+                &r,
+                &pool,
+                &pool_token,
+                &fee_collector,
+                &fee_collector_token,
+                &optional_fee_collector,
+                &mut commitment_queue,
+                &mut metadata_queue,
+                &v_acc,
+                &n_pda,
+                &spl,
+                &any,
+                &any,
+                &governor,
+                &rent_recipient,
+                &network_fee,
+                &v_index_acc,
+                0,
+                0
+            ),
+            Err(ElusivError::InvalidFeeVersion.into())
+        );
+        {
+            pda_account!(mut v_acc, VerificationAccount, v_acc);
+            v_acc.set_request(&ProofRequest::Send(public_inputs.clone()));
+        }
+
         assert_eq!(
             finalize_verification_transfer_token(
                 &fee_payer,
@@ -3518,6 +9056,11 @@ mod tests {
                 &spl,
                 &any,
                 &any,
+                &governor,
+                &rent_recipient,
+                &network_fee,
+                &v_index_acc,
+                0,
                 0
             ),
             Ok(())
@@ -3528,11 +9071,289 @@ mod tests {
         pda_account!(v_acc, VerificationAccount, v_acc);
         assert_eq!(v_acc.get_state(), VerificationState::Closed);
 
+        // The network fee is accumulated for the token
+        pda_account!(accumulator, NetworkFeeAccount, network_fee);
+        assert_eq!(
+            accumulator.get_collected_network_fee(),
+            v_acc.get_other_data().network_fee
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_finalize_verification_transfer_token_program_owned_recipient() -> ProgramResult {
+        zero_program_account!(governor, GovernorAccount);
+        test_account_info!(rent_recipient, 0);
+        zero_pda_account_info!(network_fee, NetworkFeeAccount, Some(USDC_TOKEN_ID.into()));
+        test_account_info!(v_index_acc, 0);
+        finalize_send_test!(
+            USDC_TOKEN_ID,
+            LAMPORTS_PER_SOL,
+            10,
+            public_inputs,
+            verification_acc_data,
+            recipient_bytes,
+            _i,
+            _r,
+            _f,
+            optional_fee_collector,
+            true
+        );
+
+        // The vault PDA and a token account owned by it (not the associated-token-account)
+        let vault = Pubkey::new_from_array(recipient_bytes);
+        account_info!(r_wallet, vault, vec![], Pubkey::new_unique(), false);
+        account_info!(
+            r,
+            Pubkey::new_unique(),
+            spl_token_account_data_with_owner(USDC_TOKEN_ID, &vault),
+            spl_token::id(),
+            false
+        );
+        account_info!(
+            r_invalid_owner,
+            Pubkey::new_unique(),
+            spl_token_account_data_with_owner(USDC_TOKEN_ID, &Pubkey::new_unique()),
+            spl_token::id(),
+            false
+        );
+        account_info!(
+            r_invalid_mint,
+            Pubkey::new_unique(),
+            spl_token_account_data_with_owner(USDT_TOKEN_ID, &vault),
+            spl_token::id(),
+            false
+        );
+
+        let fee_payer_pk = Pubkey::new(
+            &VerificationAccount::new(&mut verification_acc_data)
+                .unwrap()
+                .get_other_data()
+                .fee_payer
+                .skip_mr(),
+        );
+        account_info!(fee_payer, fee_payer_pk, vec![]);
+        account_info!(
+            fee_payer_token,
+            fee_payer_pk,
+            vec![],
+            spl_token::id(),
+            false
+        );
+
+        test_pda_account_info!(pool, PoolAccount, None);
+        test_pda_account_info!(fee_collector, FeeCollectorAccount, None);
+        program_token_account_info!(pool_token, PoolAccount, USDC_TOKEN_ID);
+        program_token_account_info!(fee_collector_token, FeeCollectorAccount, USDC_TOKEN_ID);
+
+        account_info!(
+            optional_fee_collector,
+            optional_fee_collector,
+            vec![],
+            spl_token::id(),
+            false
+        );
+
+        test_account_info!(any, 0);
+        account_info!(spl, spl_token::id(), vec![]);
+        test_pda_account_info!(
+            n_pda,
+            NullifierDuplicateAccount,
+            public_inputs
+                .join_split
+                .associated_nullifier_duplicate_pda_pubkey(),
+            None
+        );
+        account_info!(v_acc, Pubkey::new_unique(), verification_acc_data);
+        zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
+        zero_program_account!(mut metadata_queue, MetadataQueueAccount);
+
+        {
+            pda_account!(mut v_acc, VerificationAccount, v_acc);
+            v_acc.set_state(&VerificationState::Finalized);
+        }
+
+        for (recipient, recipient_wallet) in [
+            (&r, &any),                    // Invalid recipient_wallet
+            (&r_invalid_owner, &r_wallet), // Token account not owned by the vault
+            (&r_invalid_mint, &r_wallet),  // Token account for a different mint
+            (&r_wallet, &r_wallet),        // Vault itself is no token account
+        ] {
+            assert_eq!(
+                finalize_verification_transfer_token(
+                    &fee_payer,
+                    &fee_payer_token,
+                    recipient,
+                    recipient_wallet,
+                    &pool,
+                    &pool_token,
+                    &fee_collector,
+                    &fee_collector_token,
+                    &optional_fee_collector,
+                    &mut commitment_queue,
+                    &mut metadata_queue,
+                    &v_acc,
+                    &n_pda,
+                    &spl,
+                    &any,
+                    &any,
+                    &governor,
+                    &rent_recipient,
+                    &network_fee,
+                    &v_index_acc,
+                    0,
+                    0
+                ),
+                Err(ElusivError::InvalidRecipient.into())
+            );
+        }
+
+        assert_eq!(
+            finalize_verification_transfer_token(
+                &fee_payer,
+                &fee_payer_token,
+                &r,
+                &r_wallet,
+                &pool,
+                &pool_token,
+                &fee_collector,
+                &fee_collector_token,
+                &optional_fee_collector,
+                &mut commitment_queue,
+                &mut metadata_queue,
+                &v_acc,
+                &n_pda,
+                &spl,
+                &any,
+                &any,
+                &governor,
+                &rent_recipient,
+                &network_fee,
+                &v_index_acc,
+                0,
+                0
+            ),
+            Ok(())
+        );
+
+        pda_account!(v_acc, VerificationAccount, v_acc);
+        assert_eq!(v_acc.get_state(), VerificationState::Closed);
+
         Ok(())
     }
 
+    #[test]
+    fn test_token_recipient() {
+        account_info!(
+            valid,
+            Pubkey::new_unique(),
+            spl_token_account_data(USDC_TOKEN_ID),
+            spl_token::id(),
+            false
+        );
+        account_info!(
+            valid_fallback,
+            Pubkey::new_unique(),
+            spl_token_account_data(USDC_TOKEN_ID),
+            spl_token::id(),
+            false
+        );
+        account_info!(
+            invalid,
+            Pubkey::new_unique(),
+            spl_token_account_data(USDT_TOKEN_ID),
+            spl_token::id(),
+            false
+        );
+        account_info!(invalid_fallback, Pubkey::new_unique(), vec![]);
+        test_account_info!(fee_collector, 0);
+        let usdc_mint = elusiv_token(USDC_TOKEN_ID).unwrap().mint;
+        let fallback = |account: &AccountInfo| Some(RawU256::new(account.key.to_bytes()));
+
+        // Valid primary
+        for fallback_recipient in [None, fallback(&valid_fallback)] {
+            assert_eq!(
+                token_recipient(
+                    &valid,
+                    &valid_fallback,
+                    &fee_collector,
+                    fallback_recipient,
+                    USDC_TOKEN_ID,
+                    &usdc_mint
+                )
+                .unwrap()
+                .key,
+                valid.key
+            );
+        }
+
+        // Invalid primary and valid fallback
+        assert_eq!(
+            token_recipient(
+                &invalid,
+                &valid_fallback,
+                &fee_collector,
+                fallback(&valid_fallback),
+                USDC_TOKEN_ID,
+                &usdc_mint
+            )
+            .unwrap()
+            .key,
+            valid_fallback.key
+        );
+
+        // Fallback account does not match the bound fallback recipient
+        assert_eq!(
+            token_recipient(
+                &invalid,
+                &valid,
+                &fee_collector,
+                fallback(&valid_fallback),
+                USDC_TOKEN_ID,
+                &usdc_mint
+            )
+            .map(|account| account.key),
+            Err(ElusivError::InvalidRecipient.into())
+        );
+
+        // Both invalid
+        assert_eq!(
+            token_recipient(
+                &invalid,
+                &invalid_fallback,
+                &fee_collector,
+                fallback(&invalid_fallback),
+                USDC_TOKEN_ID,
+                &usdc_mint
+            )
+            .unwrap()
+            .key,
+            fee_collector.key
+        );
+
+        // Invalid primary without a fallback
+        assert_eq!(
+            token_recipient(
+                &invalid,
+                &valid_fallback,
+                &fee_collector,
+                None,
+                USDC_TOKEN_ID,
+                &usdc_mint
+            )
+            .unwrap()
+            .key,
+            fee_collector.key
+        );
+    }
+
     #[test]
     fn test_finalize_verification_transfer_token_merge() -> ProgramResult {
+        zero_program_account!(governor, GovernorAccount);
+        test_account_info!(rent_recipient, 0);
+        zero_pda_account_info!(network_fee, NetworkFeeAccount, Some(USDC_TOKEN_ID.into()));
+        test_account_info!(v_index_acc, 0);
         finalize_send_test!(
             USDC_TOKEN_ID,
             0,
@@ -3583,10 +9404,16 @@ mod tests {
             pda_account!(mut v_acc, VerificationAccount, v_acc);
             v_acc.set_state(&VerificationState::Finalized);
             v_acc.set_is_verified(&ElusivOption::Some(true));
+
+            // Merges don't require a recipient
+            let mut data = v_acc.get_other_data();
+            data.recipient_wallet = ElusivOption::None;
+            v_acc.set_other_data(&data);
         }
 
         // For merges (zero-amount) the recipient key is ignored
         account_info!(r, Pubkey::new_unique());
+        let recipient_lamports = r.lamports();
         assert_eq!(
             finalize_verification_transfer_token(
                 &fee_payer,
@@ -3605,20 +9432,61 @@ mod tests {
                 &spl,
                 &any,
                 &any,
+                &governor,
+                &rent_recipient,
+                &network_fee,
+                &v_index_acc,
+                0,
                 0
             ),
             Ok(())
         );
 
+        // No recipient transfer (or associated-token-account creation)
+        assert_eq!(r.lamports(), recipient_lamports);
+        assert_eq!(*r.owner, crate::ID);
+
         Ok(())
     }
 
     #[test]
     fn test_is_timestamp_valid() {
-        assert!(is_timestamp_valid(0, 1));
-        assert!(is_timestamp_valid(two_pow!(5) as u64 - 1, 0));
+        assert!(is_timestamp_valid(0, 1, 5));
+        assert!(is_timestamp_valid(two_pow!(5) as u64 - 1, 0, 5));
+
+        assert!(!is_timestamp_valid(two_pow!(5) as u64, 0, 5));
+
+        // Stale
+        assert!(is_timestamp_valid(0, two_pow!(6) as u64 - 1, 5));
+        assert!(!is_timestamp_valid(0, two_pow!(6) as u64, 5));
 
-        assert!(!is_timestamp_valid(two_pow!(5) as u64, 0));
+        // Changing the governor value changes the accepted window
+        zero_program_account!(mut governor, GovernorAccount);
+        let bits = |governor: &GovernorAccount| governor.timestamp_pruning_bits();
+        assert!(!is_timestamp_valid(two_pow!(5) as u64, 0, bits(&governor)));
+
+        governor.set_timestamp_pruning_bits(&ElusivOption::Some(6));
+        assert!(is_timestamp_valid(two_pow!(5) as u64, 0, bits(&governor)));
+        assert!(!is_timestamp_valid(two_pow!(6) as u64, 0, bits(&governor)));
+        assert!(is_timestamp_valid(
+            0,
+            two_pow!(7) as u64 - 1,
+            bits(&governor)
+        ));
+        assert!(!is_timestamp_valid(0, two_pow!(7) as u64, bits(&governor)));
+
+        governor.set_timestamp_pruning_bits(&ElusivOption::Some(0));
+        assert!(!is_timestamp_valid(1, 0, bits(&governor)));
+        assert!(is_timestamp_valid(0, 1, bits(&governor)));
+        assert!(!is_timestamp_valid(0, 2, bits(&governor)));
+
+        // The maximum value does not overflow
+        assert!(is_timestamp_valid(
+            u32::MAX as u64,
+            0,
+            MAX_TIMESTAMP_PRUNING_BITS
+        ));
+        assert!(!is_timestamp_valid(u64::MAX, 0, MAX_TIMESTAMP_PRUNING_BITS));
     }
 
     #[test]
@@ -3641,6 +9509,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_predicted_commitment_position() {
+        let count = MT_COMMITMENT_COUNT as u32;
+        for (trees_count, next_commitment_ptr, commitment_queue_len) in [
+            (0, 0, 0),
+            (0, 1, 1),
+            (3, count - 1, 0),
+            (3, count - 1, 1),
+            (1, count, count + 1),
+        ] {
+            let (commitment_index, mt_index) =
+                minimum_commitment_mt_index(trees_count, next_commitment_ptr, commitment_queue_len);
+            assert_eq!(
+                predicted_commitment_position(
+                    trees_count,
+                    next_commitment_ptr,
+                    commitment_queue_len
+                ),
+                (mt_index, commitment_index)
+            );
+        }
+
+        assert_eq!(predicted_commitment_position(3, count - 1, 1), (4, 0));
+    }
+
     #[test]
     fn test_is_vec_duplicate_free() {
         assert!(is_vec_duplicate_free(&<Vec<u8>>::new()));
@@ -3659,7 +9552,7 @@ mod tests {
 
         let commitments_count = 1000;
         storage.set_next_commitment_ptr(&commitments_count);
-        storage.set_node(&empty_root_raw().reduce(), 0, 0).unwrap();
+        storage.set_node(&empty_root_raw().reduce(), 0, 0);
 
         let valid_inputs = JoinSplitPublicInputs {
             input_commitments: vec![InputCommitment {
@@ -3694,6 +9587,15 @@ mod tests {
                 }),
                 ElusivError::InvalidMerkleRoot,
             ),
+            // Non-canonical encoding of the valid root (root + modulus)
+            (
+                mutate(&valid_inputs, |inputs| {
+                    let mut root = u256_to_big_uint(&empty_root_raw().skip_mr());
+                    assert!(!root.add_nocarry(&SCALAR_MODULUS_RAW));
+                    inputs.input_commitments[0].root = Some(RawU256::new(big_uint_to_u256(&root)));
+                }),
+                ElusivError::NonCanonicalMerkleRoot,
+            ),
             // First root is None
             (
                 mutate(&valid_inputs, |inputs| {
@@ -3762,7 +9664,8 @@ mod tests {
                     &public_inputs,
                     &storage,
                     [&n_account, &n_account],
-                    &[0, 1]
+                    &[0, 1],
+                    &GenesisParams::default()
                 ),
                 Err(err.into())
             );
@@ -3785,18 +9688,49 @@ mod tests {
                 }),
                 &storage,
                 [&n_account, &n_account],
-                &[0, 0]
+                &[0, 0],
+                &GenesisParams::default()
             ),
             Err(ElusivError::InvalidInstructionData.into())
         );
 
+        // Zero-commitment sourced from the genesis params
+        assert_eq!(
+            check_join_split_public_inputs(
+                &valid_inputs,
+                &storage,
+                [&n_account, &n_account],
+                &[0, 1],
+                &GenesisParams {
+                    zero_commitment: valid_inputs.output_commitment.skip_mr(),
+                    ..GenesisParams::default()
+                }
+            ),
+            Err(ElusivError::InvalidPublicInputs.into())
+        );
+
+        // Unused tree-index is not the next MT's index
+        for tree_indices in [[0, 0], [0, 2], [0, u32::MAX]] {
+            assert_eq!(
+                check_join_split_public_inputs(
+                    &valid_inputs,
+                    &storage,
+                    [&n_account, &n_account],
+                    &tree_indices,
+                    &GenesisParams::default()
+                ),
+                Err(ElusivError::InvalidInstructionData.into())
+            );
+        }
+
         // Success
         assert_eq!(
             check_join_split_public_inputs(
                 &valid_inputs,
                 &storage,
                 [&n_account, &n_account],
-                &[0, 1]
+                &[0, 1],
+                &GenesisParams::default()
             ),
             Ok(())
         );
@@ -3830,7 +9764,8 @@ mod tests {
                     &public_inputs,
                     &storage,
                     [&n_account, &n_account],
-                    &[0, 1]
+                    &[0, 1],
+                    &GenesisParams::default()
                 ),
                 Ok(())
             );
@@ -3860,12 +9795,69 @@ mod tests {
                 }),
                 &storage,
                 [&n_account, &n_account],
-                &[0, 1]
+                &[0, 1],
+                &GenesisParams::default()
             ),
             Err(ElusivError::CouldNotInsertNullifier.into())
         );
     }
 
+    #[test]
+    fn test_check_join_split_public_inputs_test_vectors() {
+        parent_account!(mut storage, StorageAccount);
+        parent_account!(n_account, NullifierAccount);
+        parent_account!(mut closed_n_account, NullifierAccount);
+
+        let active = CommitmentTestVector::new(1);
+        let closed = CommitmentTestVector::new(2);
+        active.insert_into(&mut storage);
+        closed_n_account.set_root(&closed.root.reduce());
+
+        let public_inputs = JoinSplitPublicInputs {
+            input_commitments: vec![
+                InputCommitment {
+                    root: Some(active.root),
+                    nullifier_hash: active.nullifier_hash,
+                },
+                InputCommitment {
+                    root: Some(closed.root),
+                    nullifier_hash: closed.nullifier_hash,
+                },
+            ],
+            output_commitment: CommitmentTestVector::new(3).commitment,
+            recent_commitment_index: storage.get_next_commitment_ptr(),
+            fee_version: 0,
+            amount: 1000,
+            fee: 0,
+            optional_fee: OptionalFee::default(),
+            token_id: 0,
+            metadata: CommitmentMetadata::default(),
+        };
+
+        assert_eq!(
+            check_join_split_public_inputs(
+                &public_inputs,
+                &storage,
+                [&n_account, &closed_n_account],
+                &[0, 1],
+                &GenesisParams::default()
+            ),
+            Ok(())
+        );
+
+        // Roots of the two vectors are not interchangeable
+        assert_eq!(
+            check_join_split_public_inputs(
+                &public_inputs,
+                &storage,
+                [&n_account, &n_account],
+                &[0, 1],
+                &GenesisParams::default()
+            ),
+            Err(ElusivError::InvalidMerkleRoot.into())
+        );
+    }
+
     struct StubInstruction(u8, Option<Vec<u8>>, Pubkey);
 
     impl From<StubInstruction> for Instruction {
@@ -3883,39 +9875,19 @@ mod tests {
         }
     }
 
-    struct TestInstructionsSysvar {
-        current_index: Option<u16>,
-        instructions: Vec<Instruction>,
-    }
-
-    impl InstructionsSysvar for TestInstructionsSysvar {
-        fn current_index(&self) -> Result<u16, ProgramError> {
-            match self.current_index {
-                Some(current_index) => Ok(current_index),
-                None => Err(ProgramError::InvalidArgument),
-            }
-        }
-
-        fn instruction_at_index(&self, index: usize) -> Result<Instruction, ProgramError> {
-            match self.instructions.get(index) {
-                Some(ix) => Ok(ix.clone()),
-                None => Err(ProgramError::InvalidArgument),
-            }
-        }
-    }
-
     #[test]
     fn test_verify_finalize_send_instruction() {
         let i = 123;
         let v = 255;
+        let g = 7;
 
         let instruction_sysvar = TestInstructionsSysvar {
             current_index: Some(0),
-            instructions: vec![StubInstruction(i, Some(vec![v]), crate::id()).into()],
+            instructions: vec![StubInstruction(i, Some(vec![v, g, 0, 0, 0]), crate::id()).into()],
         };
 
         assert_eq!(
-            verify_finalize_send_instruction(0, &instruction_sysvar, i, v),
+            verify_finalize_send_instruction(0, &instruction_sysvar, i, v, g.into()),
             Ok(())
         );
 
@@ -3925,31 +9897,41 @@ mod tests {
                 0,
                 &TestInstructionsSysvar {
                     current_index: Some(0),
-                    instructions: vec![
-                        StubInstruction(i, Some(vec![v]), Pubkey::new_unique()).into()
-                    ],
+                    instructions: vec![StubInstruction(
+                        i,
+                        Some(vec![v, g, 0, 0, 0]),
+                        Pubkey::new_unique()
+                    )
+                    .into()],
                 },
                 i,
-                v
+                v,
+                g.into()
             ),
             Err(ProgramError::IncorrectProgramId)
         );
 
         // Invalid ix_index (-> invalid instruction access)
         assert_eq!(
-            verify_finalize_send_instruction(1, &instruction_sysvar, i, v),
+            verify_finalize_send_instruction(1, &instruction_sysvar, i, v, g.into()),
             Err(ProgramError::InvalidArgument)
         );
 
         // Invalid variant-index
         assert_eq!(
-            verify_finalize_send_instruction(0, &instruction_sysvar, i - 1, v),
+            verify_finalize_send_instruction(0, &instruction_sysvar, i - 1, v, g.into()),
             Err(ElusivError::InvalidOtherInstruction.into())
         );
 
         // Invalid verification-account-index
         assert_eq!(
-            verify_finalize_send_instruction(0, &instruction_sysvar, i, v - 1),
+            verify_finalize_send_instruction(0, &instruction_sysvar, i, v - 1, g.into()),
+            Err(ElusivError::InvalidOtherInstruction.into())
+        );
+
+        // Invalid verification-account-generation
+        assert_eq!(
+            verify_finalize_send_instruction(0, &instruction_sysvar, i, v, 0),
             Err(ElusivError::InvalidOtherInstruction.into())
         );
     }
@@ -3961,7 +9943,7 @@ mod tests {
         for i in 0..10 {
             let mut instructions = vec![StubInstruction(
                 ElusivInstruction::FINALIZE_VERIFICATION_SEND_INDEX,
-                Some(vec![verification_account_index]),
+                Some(vec![verification_account_index, 0, 0, 0, 0]),
                 crate::id(),
             )
             .into()];
@@ -3970,7 +9952,7 @@ mod tests {
                 instructions.push(
                     StubInstruction(
                         ElusivInstruction::FINALIZE_VERIFICATION_INSERT_NULLIFIER_INDEX,
-                        Some(vec![verification_account_index]),
+                        Some(vec![verification_account_index, 0, 0, 0, 0]),
                         crate::id(),
                     )
                     .into(),
@@ -3980,7 +9962,7 @@ mod tests {
             instructions.push(
                 StubInstruction(
                     ElusivInstruction::FINALIZE_VERIFICATION_TRANSFER_LAMPORTS_INDEX,
-                    Some(vec![verification_account_index]),
+                    Some(vec![verification_account_index, 0, 0, 0, 0]),
                     crate::id(),
                 )
                 .into(),
@@ -3994,6 +9976,7 @@ mod tests {
                     },
                     true,
                     verification_account_index,
+                    0,
                 ),
                 Ok(())
             );
@@ -4008,13 +9991,13 @@ mod tests {
                     instructions: vec![
                         StubInstruction(
                             ElusivInstruction::FINALIZE_VERIFICATION_SEND_INDEX,
-                            Some(vec![verification_account_index]),
+                            Some(vec![verification_account_index, 0, 0, 0, 0]),
                             crate::id()
                         )
                         .into(),
                         StubInstruction(
                             ElusivInstruction::FINALIZE_VERIFICATION_TRANSFER_LAMPORTS_INDEX,
-                            Some(vec![verification_account_index]),
+                            Some(vec![verification_account_index, 0, 0, 0, 0]),
                             crate::id()
                         )
                         .into(),
@@ -4022,6 +10005,7 @@ mod tests {
                 },
                 true,
                 verification_account_index,
+                0,
             ),
             Err(ElusivError::InvalidOtherInstruction.into())
         );
@@ -4034,13 +10018,13 @@ mod tests {
                     instructions: vec![
                         StubInstruction(
                             ElusivInstruction::FINALIZE_VERIFICATION_SEND_INDEX,
-                            Some(vec![verification_account_index]),
+                            Some(vec![verification_account_index, 0, 0, 0, 0]),
                             crate::id()
                         )
                         .into(),
                         StubInstruction(
                             ElusivInstruction::FINALIZE_VERIFICATION_TRANSFER_LAMPORTS_INDEX,
-                            Some(vec![verification_account_index]),
+                            Some(vec![verification_account_index, 0, 0, 0, 0]),
                             crate::id()
                         )
                         .into(),
@@ -4048,6 +10032,7 @@ mod tests {
                 },
                 true,
                 verification_account_index,
+                0,
             ),
             Ok(())
         );
@@ -4059,13 +10044,14 @@ mod tests {
                     current_index: Some(0),
                     instructions: vec![StubInstruction(
                         ElusivInstruction::FINALIZE_VERIFICATION_SEND_INDEX,
-                        Some(vec![verification_account_index]),
+                        Some(vec![verification_account_index, 0, 0, 0, 0]),
                         crate::id()
                     )
                     .into(),],
                 },
                 true,
-                verification_account_index
+                verification_account_index,
+                0
             ),
             Err(ProgramError::InvalidArgument)
         );
@@ -4078,20 +10064,21 @@ mod tests {
                     instructions: vec![
                         StubInstruction(
                             ElusivInstruction::FINALIZE_VERIFICATION_SEND_INDEX,
-                            Some(vec![verification_account_index]),
+                            Some(vec![verification_account_index, 0, 0, 0, 0]),
                             crate::id()
                         )
                         .into(),
                         StubInstruction(
                             ElusivInstruction::FINALIZE_VERIFICATION_TRANSFER_TOKEN_INDEX,
-                            Some(vec![verification_account_index]),
+                            Some(vec![verification_account_index, 0, 0, 0, 0]),
                             crate::id()
                         )
                         .into(),
                     ],
                 },
                 true,
-                verification_account_index
+                verification_account_index,
+                0
             ),
             Err(ElusivError::InvalidOtherInstruction.into())
         );
@@ -4100,13 +10087,13 @@ mod tests {
         let instructions: Vec<Instruction> = vec![
             StubInstruction(
                 ElusivInstruction::FINALIZE_VERIFICATION_SEND_INDEX,
-                Some(vec![verification_account_index]),
+                Some(vec![verification_account_index, 0, 0, 0, 0]),
                 crate::id(),
             )
             .into(),
             StubInstruction(
                 ElusivInstruction::FINALIZE_VERIFICATION_TRANSFER_LAMPORTS_INDEX,
-                Some(vec![verification_account_index]),
+                Some(vec![verification_account_index, 0, 0, 0, 0]),
                 crate::id(),
             )
             .into(),
@@ -4123,13 +10110,91 @@ mod tests {
                         instructions,
                     },
                     true,
-                    verification_account_index
+                    verification_account_index,
+                    0
                 ),
                 Err(ElusivError::InvalidOtherInstruction.into())
             );
         }
     }
 
+    #[test]
+    fn test_enforce_finalize_send_instructions_duplicates() {
+        let verification_account_index = 123;
+        let ix = |variant_index: u8| -> Instruction {
+            StubInstruction(
+                variant_index,
+                Some(vec![verification_account_index, 0, 0, 0, 0]),
+                crate::id(),
+            )
+            .into()
+        };
+        let send = ix(ElusivInstruction::FINALIZE_VERIFICATION_SEND_INDEX);
+        let insert = ix(ElusivInstruction::FINALIZE_VERIFICATION_INSERT_NULLIFIER_INDEX);
+        let transfer = ix(ElusivInstruction::FINALIZE_VERIFICATION_TRANSFER_LAMPORTS_INDEX);
+        let transfer_token = ix(ElusivInstruction::FINALIZE_VERIFICATION_TRANSFER_TOKEN_INDEX);
+        let other = system_instruction::transfer(&Pubkey::new_unique(), &Pubkey::new_unique(), 1);
+
+        let enforce = |current_index: u16, instructions: Vec<Instruction>| {
+            enforce_finalize_send_instructions_inner(
+                &TestInstructionsSysvar {
+                    current_index: Some(current_index),
+                    instructions,
+                },
+                true,
+                verification_account_index,
+                0,
+            )
+        };
+
+        // Unrelated instructions are allowed
+        assert_eq!(
+            enforce(
+                1,
+                vec![
+                    other.clone(),
+                    send.clone(),
+                    insert.clone(),
+                    transfer.clone(),
+                    other.clone()
+                ]
+            ),
+            Ok(())
+        );
+
+        for (current_index, instructions) in [
+            // Duplicated transfer instruction
+            (
+                0,
+                vec![
+                    send.clone(),
+                    insert.clone(),
+                    transfer.clone(),
+                    transfer.clone(),
+                ],
+            ),
+            (
+                0,
+                vec![send.clone(), transfer.clone(), other, transfer.clone()],
+            ),
+            // Duplicated send instruction
+            (1, vec![send.clone(), send.clone(), transfer.clone()]),
+            (0, vec![send.clone(), transfer.clone(), send.clone()]),
+            // Trailing insertion instruction
+            (
+                0,
+                vec![send.clone(), insert.clone(), transfer.clone(), insert],
+            ),
+            // Additional token transfer
+            (0, vec![send.clone(), transfer.clone(), transfer_token]),
+        ] {
+            assert_eq!(
+                enforce(current_index, instructions),
+                Err(ElusivError::InvalidOtherInstruction.into())
+            );
+        }
+    }
+
     #[test]
     fn test_enforce_instruction() {
         let instruction =
@@ -4286,9 +10351,132 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_net_recipient_amount() {
+        let mut join_split = JoinSplitPublicInputs {
+            input_commitments: Vec::new(),
+            output_commitment: RawU256::new([0; 32]),
+            recent_commitment_index: 0,
+            fee_version: 0,
+            amount: 1_000,
+            fee: 0,
+            optional_fee: OptionalFee {
+                collector: Pubkey::new_unique(),
+                amount: 10,
+            },
+            token_id: 0,
+            metadata: CommitmentMetadata::default(),
+        };
+
+        assert_eq!(net_recipient_amount(&join_split, 0), Ok(990));
+        assert_eq!(net_recipient_amount(&join_split, 100), Ok(890));
+        assert_eq!(net_recipient_amount(&join_split, 990), Ok(0));
+        assert_eq!(
+            net_recipient_amount(&join_split, 991),
+            Err(ElusivError::InvalidAmount.into())
+        );
+
+        join_split.optional_fee.amount = 1_001;
+        assert_eq!(
+            net_recipient_amount(&join_split, 0),
+            Err(ElusivError::InvalidAmount.into())
+        );
+    }
+
+    #[test]
+    fn test_solana_pay_transfer_amount() {
+        let fee_payer = Pubkey::new_unique();
+        let fee_payer_account = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+
+        for (token_id, associated_token_account_rent) in
+            [(LAMPORTS_TOKEN_ID, 0), (USDC_TOKEN_ID, 100)]
+        {
+            for solana_pay_transfer in [false, true] {
+                let join_split = JoinSplitPublicInputs {
+                    input_commitments: Vec::new(),
+                    output_commitment: RawU256::new([0; 32]),
+                    recent_commitment_index: 0,
+                    fee_version: 0,
+                    amount: 1_000_000,
+                    fee: 0,
+                    optional_fee: OptionalFee {
+                        collector: Pubkey::new_unique(),
+                        amount: 1_000,
+                    },
+                    token_id,
+                    metadata: CommitmentMetadata::default(),
+                };
+
+                // Amount transferred by `pool` (to the recipient or to the fee-payer for Solana Pay transfers)
+                let actual_amount =
+                    net_recipient_amount(&join_split, associated_token_account_rent).unwrap();
+                assert_eq!(
+                    actual_amount,
+                    1_000_000 - 1_000 - associated_token_account_rent
+                );
+
+                if !solana_pay_transfer {
+                    continue;
+                }
+
+                // Trailing transfer built by a wallet
+                let transfer = |amount| {
+                    if token_id == LAMPORTS_TOKEN_ID {
+                        system_instruction::transfer(&fee_payer, &recipient, amount)
+                    } else {
+                        spl_token::instruction::transfer(
+                            &spl_token::ID,
+                            &fee_payer_account,
+                            &recipient,
+                            &fee_payer,
+                            &[&fee_payer],
+                            amount,
+                        )
+                        .unwrap()
+                    }
+                };
+                let expected = solana_pay_transfer_instruction(
+                    token_id,
+                    &spl_token::ID,
+                    &fee_payer,
+                    &fee_payer_account,
+                    &recipient,
+                    actual_amount,
+                )
+                .unwrap();
+                let instructions_sysvar = |amount| TestInstructionsSysvar {
+                    current_index: Some(0),
+                    instructions: vec![
+                        StubInstruction(
+                            ElusivInstruction::FINALIZE_VERIFICATION_TRANSFER_LAMPORTS_INDEX,
+                            None,
+                            crate::id(),
+                        )
+                        .into(),
+                        transfer(amount),
+                    ],
+                };
+
+                // The enforced amount equals the actual amount
+                assert_eq!(
+                    enforce_solana_pay_transfer(&instructions_sysvar(actual_amount), &expected),
+                    Ok(())
+                );
+
+                // Gross amount
+                assert_eq!(
+                    enforce_solana_pay_transfer(&instructions_sysvar(join_split.amount), &expected),
+                    Err(ElusivError::InvalidOtherInstruction.into())
+                );
+            }
+        }
+    }
+
     #[test]
     fn test_memo_program_id() {
-        assert_eq!(SPL_MEMO_PROGRAM_ID, spl_memo::ID);
+        // Prevents a `spl-memo` version bump from silently desyncing the hard-coded program id
+        assert_eq!(SPL_MEMO_PROGRAM_ID.to_bytes(), spl_memo::ID.to_bytes());
     }
 
     #[test]
@@ -4315,6 +10503,8 @@ mod tests {
                     current_index: Some(0),
                     instructions: vec![memo_instruction.clone(), instruction.clone(),],
                 },
+                &[SPL_MEMO_PROGRAM_ID],
+                false,
                 false,
             ),
             Err(ProgramError::IncorrectProgramId)
@@ -4331,9 +10521,12 @@ mod tests {
                         instruction.clone(),
                     ],
                 },
+                &[SPL_MEMO_PROGRAM_ID],
                 true,
+                false,
             )
-            .unwrap(),
+            .unwrap()
+            .0,
             memo
         );
 
@@ -4342,13 +10535,160 @@ mod tests {
             get_memo_from_instructions(
                 &TestInstructionsSysvar {
                     current_index: Some(0),
-                    instructions: vec![instruction, memo_instruction,],
+                    instructions: vec![instruction.clone(), memo_instruction,],
                 },
+                &[SPL_MEMO_PROGRAM_ID],
+                false,
                 false,
             )
-            .unwrap(),
+            .unwrap()
+            .0,
+            memo
+        );
+
+        // Maximum memo size
+        let memo = vec![b'a'; MAX_MEMO_SIZE];
+        assert_eq!(
+            get_memo_from_instructions(
+                &TestInstructionsSysvar {
+                    current_index: Some(0),
+                    instructions: vec![instruction.clone(), super::memo_instruction(&memo)],
+                },
+                &[SPL_MEMO_PROGRAM_ID],
+                false,
+                false,
+            )
+            .unwrap()
+            .0,
             memo
         );
+
+        let memo = vec![b'a'; MAX_MEMO_SIZE + 1];
+        assert_eq!(
+            get_memo_from_instructions(
+                &TestInstructionsSysvar {
+                    current_index: Some(0),
+                    instructions: vec![instruction, super::memo_instruction(&memo)],
+                },
+                &[SPL_MEMO_PROGRAM_ID],
+                false,
+                false,
+            ),
+            Err(ElusivError::MemoTooLarge.into())
+        );
+    }
+
+    #[test]
+    fn test_get_memo_from_instructions_single_memo() {
+        let instruction =
+            system_instruction::transfer(&Pubkey::new_unique(), &Pubkey::new_unique(), 123);
+        let memo = b"Hello".to_vec();
+        let sysvar = |memo_count: usize| TestInstructionsSysvar {
+            current_index: Some(0),
+            instructions: [
+                vec![instruction.clone()],
+                vec![memo_instruction(&memo); memo_count],
+            ]
+            .concat(),
+        };
+
+        // No memo
+        assert_eq!(
+            get_memo_from_instructions(&sysvar(0), &[SPL_MEMO_PROGRAM_ID], false, true),
+            Err(ElusivError::AmbiguousMemo.into())
+        );
+
+        assert_eq!(
+            get_memo_from_instructions(&sysvar(1), &[SPL_MEMO_PROGRAM_ID], false, true),
+            Ok((memo.clone(), SPL_MEMO_PROGRAM_ID))
+        );
+
+        // Multiple memos
+        assert_eq!(
+            get_memo_from_instructions(&sysvar(2), &[SPL_MEMO_PROGRAM_ID], false, true),
+            Err(ElusivError::AmbiguousMemo.into())
+        );
+        assert_eq!(
+            get_memo_from_instructions(&sysvar(2), &[SPL_MEMO_PROGRAM_ID], false, false),
+            Ok((memo, SPL_MEMO_PROGRAM_ID))
+        );
+    }
+
+    #[test]
+    fn test_get_memo_from_instructions_allow_list() {
+        let instruction =
+            system_instruction::transfer(&Pubkey::new_unique(), &Pubkey::new_unique(), 123);
+        let memo = b"Hello".to_vec();
+        let v1_memo_instruction = Instruction::new_with_bytes(spl_memo::v1::ID, &memo, Vec::new());
+        let v2_memo_instruction = memo_instruction(&memo);
+        let unlisted_memo_instruction =
+            Instruction::new_with_bytes(Pubkey::new_unique(), &memo, Vec::new());
+        let sysvar = |memo_instructions: Vec<Instruction>| TestInstructionsSysvar {
+            current_index: Some(0),
+            instructions: [vec![instruction.clone()], memo_instructions].concat(),
+        };
+
+        // Memo v1
+        assert_eq!(
+            get_memo_from_instructions(
+                &sysvar(vec![v1_memo_instruction.clone()]),
+                &[SPL_MEMO_PROGRAM_ID],
+                false,
+                false
+            ),
+            Err(ProgramError::IncorrectProgramId)
+        );
+        assert_eq!(
+            get_memo_from_instructions(
+                &sysvar(vec![v1_memo_instruction.clone()]),
+                &[SPL_MEMO_PROGRAM_ID, spl_memo::v1::ID],
+                false,
+                true
+            ),
+            Ok((memo.clone(), spl_memo::v1::ID))
+        );
+
+        // Memo v2
+        assert_eq!(
+            get_memo_from_instructions(
+                &sysvar(vec![v2_memo_instruction.clone()]),
+                &[SPL_MEMO_PROGRAM_ID, spl_memo::v1::ID],
+                false,
+                true
+            ),
+            Ok((memo.clone(), SPL_MEMO_PROGRAM_ID))
+        );
+
+        // Unlisted program
+        assert_eq!(
+            get_memo_from_instructions(
+                &sysvar(vec![unlisted_memo_instruction.clone()]),
+                &[SPL_MEMO_PROGRAM_ID, spl_memo::v1::ID],
+                false,
+                false
+            ),
+            Err(ProgramError::IncorrectProgramId)
+        );
+
+        // Memos of all allow-listed programs are counted
+        assert_eq!(
+            get_memo_from_instructions(
+                &sysvar(vec![v1_memo_instruction, v2_memo_instruction.clone()]),
+                &[SPL_MEMO_PROGRAM_ID, spl_memo::v1::ID],
+                false,
+                true
+            ),
+            Err(ElusivError::AmbiguousMemo.into())
+        );
+        assert_eq!(
+            get_memo_from_instructions(
+                &sysvar(vec![unlisted_memo_instruction, v2_memo_instruction]),
+                &[SPL_MEMO_PROGRAM_ID, spl_memo::v1::ID],
+                false,
+                true
+            ),
+            Ok((memo, SPL_MEMO_PROGRAM_ID))
+        );
     }
 
     fn test_proof() -> Proof {
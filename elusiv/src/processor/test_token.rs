@@ -0,0 +1,259 @@
+use super::utils::*;
+use crate::error::ElusivError;
+use crate::macros::*;
+use crate::state::test_token::{TestMintAccount, TestPriceAccount};
+use crate::token::{elusiv_token, pyth_price_account_data, Price, TokenError, TokenID};
+use elusiv_types::PDAAccount;
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, program::invoke_signed,
+    program_pack::Pack, pubkey::Pubkey, rent::Rent, system_instruction, sysvar::Sysvar,
+};
+
+/// Creates the devnet test-mint of an SPL-token
+///
+/// # Note
+///
+/// - Only available in `devnet-tools` builds (which are not allowed on mainnet).
+/// - The mint is a [`TestMintAccount`] which is it's own mint-authority.
+pub fn create_test_mint<'a>(
+    payer: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+
+    token_id: TokenID,
+) -> ProgramResult {
+    guard!(token_id != 0, TokenError::InvalidTokenID);
+    let token = elusiv_token(token_id)?;
+    guard!(*mint.key == token.mint, ElusivError::InvalidAccount);
+    guard!(
+        *token_program.key == spl_token::ID,
+        ElusivError::InvalidAccount
+    );
+
+    let (_, bump) = TestMintAccount::find(Some(token_id.into()));
+    let seeds = TestMintAccount::signers_seeds(None, Some(token_id.into()), bump);
+    let signers_seeds = signers_seeds!(seeds);
+
+    create_test_token_account(
+        payer,
+        mint,
+        &spl_token::ID,
+        spl_token::state::Mint::LEN,
+        &signers_seeds,
+    )?;
+
+    invoke_signed(
+        &spl_token::instruction::initialize_mint2(
+            &spl_token::ID,
+            mint.key,
+            mint.key,
+            None,
+            token.decimals,
+        )?,
+        &[mint.clone(), token_program.clone()],
+        &[&signers_seeds],
+    )
+}
+
+/// Mints devnet test-tokens into an arbitrary token-account
+///
+/// # Note
+///
+/// `amount` is limited by the maximum amount of the token.
+pub fn mint_test_tokens<'a>(
+    mint: &AccountInfo<'a>,
+    recipient: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+
+    token_id: TokenID,
+    amount: u64,
+) -> ProgramResult {
+    guard!(token_id != 0, TokenError::InvalidTokenID);
+    let token = elusiv_token(token_id)?;
+    guard!(*mint.key == token.mint, ElusivError::InvalidAccount);
+    guard!(amount <= token.max, ElusivError::InvalidAmount);
+    guard!(
+        *token_program.key == spl_token::ID,
+        ElusivError::InvalidAccount
+    );
+
+    let (_, bump) = TestMintAccount::find(Some(token_id.into()));
+    let seeds = TestMintAccount::signers_seeds(None, Some(token_id.into()), bump);
+    let signers_seeds = signers_seeds!(seeds);
+
+    invoke_signed(
+        &spl_token::instruction::mint_to(
+            &spl_token::ID,
+            mint.key,
+            recipient.key,
+            mint.key,
+            &[],
+            amount,
+        )?,
+        &[mint.clone(), recipient.clone(), token_program.clone()],
+        &[&signers_seeds],
+    )
+}
+
+/// Sets the USD-price of a token in it's [`TestPriceAccount`] (creating the account if required)
+pub fn set_test_price<'a>(
+    payer: &AccountInfo<'a>,
+    price_account: &AccountInfo<'a>,
+
+    token_id: TokenID,
+    price: i64,
+    conf: u64,
+    expo: i32,
+) -> ProgramResult {
+    let token = elusiv_token(token_id)?;
+    guard!(
+        *price_account.key == token.pyth_usd_price_key,
+        ElusivError::InvalidAccount
+    );
+
+    let data = pyth_price_account_data(&Price { price, conf, expo })?;
+
+    if price_account.data_is_empty() {
+        let (_, bump) = TestPriceAccount::find(Some(token_id.into()));
+        let seeds = TestPriceAccount::signers_seeds(None, Some(token_id.into()), bump);
+        let signers_seeds = signers_seeds!(seeds);
+
+        create_test_token_account(payer, price_account, &crate::ID, data.len(), &signers_seeds)?;
+    }
+
+    guard!(
+        *price_account.owner == crate::ID,
+        ElusivError::InvalidAccount
+    );
+    guard!(
+        price_account.data_len() == data.len(),
+        ElusivError::InvalidAccount
+    );
+
+    price_account.data.borrow_mut()[..].copy_from_slice(&data);
+
+    Ok(())
+}
+
+/// Creates a rent-exempt PDA without writing any [`crate::state::program_account::PDAAccountData`]
+fn create_test_token_account<'a>(
+    payer: &AccountInfo<'a>,
+    account: &AccountInfo<'a>,
+    owner: &Pubkey,
+    account_size: usize,
+    signers_seeds: &[&[u8]],
+) -> ProgramResult {
+    invoke_signed(
+        &system_instruction::create_account(
+            payer.key,
+            account.key,
+            Rent::get()?.minimum_balance(account_size),
+            account_size as u64,
+            owner,
+        ),
+        &[payer.clone(), account.clone()],
+        &[signers_seeds],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::{account_info, test_account_info};
+    use crate::token::{TokenPrice, USDC_TOKEN_ID};
+
+    #[test]
+    fn test_create_test_mint() {
+        account_info!(mint, TestMintAccount::find(Some(USDC_TOKEN_ID.into())).0);
+        account_info!(token_program, spl_token::ID);
+        test_account_info!(payer, 0);
+        test_account_info!(any, 0);
+
+        // Lamports have no mint
+        assert_eq!(
+            create_test_mint(&payer, &mint, &token_program, 0),
+            Err(TokenError::InvalidTokenID.into())
+        );
+
+        // Invalid mint
+        assert_eq!(
+            create_test_mint(&payer, &any, &token_program, USDC_TOKEN_ID),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        // Invalid token-program
+        assert_eq!(
+            create_test_mint(&payer, &mint, &any, USDC_TOKEN_ID),
+            Err(ElusivError::InvalidAccount.into())
+        );
+    }
+
+    #[test]
+    fn test_mint_test_tokens() {
+        account_info!(mint, TestMintAccount::find(Some(USDC_TOKEN_ID.into())).0);
+        account_info!(token_program, spl_token::ID);
+        test_account_info!(recipient, 0);
+        test_account_info!(any, 0);
+        let max = elusiv_token(USDC_TOKEN_ID).unwrap().max;
+
+        // Invalid mint
+        assert_eq!(
+            mint_test_tokens(&any, &recipient, &token_program, USDC_TOKEN_ID, max),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        // Amount too high
+        assert_eq!(
+            mint_test_tokens(&mint, &recipient, &token_program, USDC_TOKEN_ID, max + 1),
+            Err(ElusivError::InvalidAmount.into())
+        );
+
+        // Invalid token-program
+        assert_eq!(
+            mint_test_tokens(&mint, &recipient, &any, USDC_TOKEN_ID, max),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        assert_eq!(
+            mint_test_tokens(&mint, &recipient, &token_program, USDC_TOKEN_ID, max),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_set_test_price() {
+        let price = Price {
+            price: 41,
+            conf: 0,
+            expo: 0,
+        };
+        let size = pyth_price_account_data(&price).unwrap().len();
+        account_info!(
+            price_account,
+            TestPriceAccount::find(Some(USDC_TOKEN_ID.into())).0,
+            vec![0; size]
+        );
+        test_account_info!(payer, 0);
+        test_account_info!(any, 0, crate::ID);
+
+        // Invalid price-account
+        assert_eq!(
+            set_test_price(&payer, &any, USDC_TOKEN_ID, 41, 0, 0),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        set_test_price(&payer, &price_account, USDC_TOKEN_ID, 41, 0, 0).unwrap();
+        assert_eq!(
+            TokenPrice::load_token_usd_price(&price_account, USDC_TOKEN_ID)
+                .unwrap()
+                .price,
+            price
+                .cmul(
+                    1,
+                    -(elusiv_token(USDC_TOKEN_ID).unwrap().price_base_exp as i32)
+                )
+                .unwrap()
+                .price
+        );
+    }
+}
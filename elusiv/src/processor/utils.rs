@@ -9,10 +9,12 @@ use solana_program::pubkey::Pubkey;
 use solana_program::sysvar::instructions;
 use solana_program::{
     account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError, rent::Rent,
-    sysvar::Sysvar,
 };
 use spl_associated_token_account::get_associated_token_address;
 
+#[cfg(not(test))]
+use solana_program::sysvar::Sysvar;
+
 pub use elusiv_utils::*;
 
 /// No-operation instruction
@@ -33,6 +35,29 @@ pub trait InstructionsSysvar {
     }
 }
 
+#[cfg(test)]
+pub struct TestInstructionsSysvar {
+    pub current_index: Option<u16>,
+    pub instructions: Vec<Instruction>,
+}
+
+#[cfg(test)]
+impl InstructionsSysvar for TestInstructionsSysvar {
+    fn current_index(&self) -> Result<u16, ProgramError> {
+        match self.current_index {
+            Some(current_index) => Ok(current_index),
+            None => Err(ProgramError::InvalidArgument),
+        }
+    }
+
+    fn instruction_at_index(&self, index: usize) -> Result<Instruction, ProgramError> {
+        match self.instructions.get(index) {
+            Some(ix) => Ok(ix.clone()),
+            None => Err(ProgramError::InvalidArgument),
+        }
+    }
+}
+
 pub struct DefaultInstructionsSysvar<'a, 'b>(pub &'a AccountInfo<'b>);
 
 impl<'a, 'b> InstructionsSysvar for DefaultInstructionsSysvar<'a, 'b> {
@@ -154,19 +179,36 @@ fn transfer_with_token_program<'a>(
     }
 }
 
+/// Creates the associated-token-account of `wallet_account` if it does not exist yet
+///
+/// # Note
+///
+/// Returns `true` if the account has been created (and `payer` paid it's rent).
 pub fn create_associated_token_account<'a>(
     payer: &AccountInfo<'a>,
     wallet_account: &AccountInfo<'a>,
     associated_token_account: &AccountInfo<'a>,
     mint_account: &AccountInfo<'a>,
 
-    token_id: u16,
-) -> Result<(), ProgramError> {
+    mint: &Pubkey,
+) -> Result<bool, ProgramError> {
+    if *associated_token_account.owner == spl_token::ID {
+        return Ok(false);
+    }
+
+    guard!(*mint_account.key == *mint, ElusivError::InvalidAccount);
+
+    // Lamports already held by the (uninitialized) account count towards the rent
+    let rent = spl_token_account_rent()?
+        .0
+        .saturating_sub(associated_token_account.lamports());
+    guard!(payer.lamports() >= rent, ElusivError::InsufficientRentFunds);
+
     invoke(
-        &spl_associated_token_account::instruction::create_associated_token_account(
+        &spl_associated_token_account::instruction::create_associated_token_account_idempotent(
             payer.key,
             wallet_account.key,
-            &elusiv_token(token_id)?.mint,
+            mint,
             &spl_token::ID,
         ),
         &[
@@ -175,7 +217,9 @@ pub fn create_associated_token_account<'a>(
             wallet_account.clone(),
             mint_account.clone(),
         ],
-    )
+    )?;
+
+    Ok(true)
 }
 
 pub fn program_token_account_address<A: PDAAccount>(
@@ -188,10 +232,16 @@ pub fn program_token_account_address<A: PDAAccount>(
     ))
 }
 
+/// Verifies that `token_account` is the (uncompromised) `mint` token-account of `owner_pda`
+///
+/// # Note
+///
+/// A delegate or close-authority would allow draining the token-account outside of the program.
 pub fn verify_program_token_account(
     owner_pda: &AccountInfo,
     token_account: &AccountInfo,
     token_id: u16,
+    mint: &Pubkey,
 ) -> ProgramResult {
     if token_id == 0 {
         guard!(
@@ -199,8 +249,16 @@ pub fn verify_program_token_account(
             ElusivError::InvalidAccount
         );
     } else {
-        let pubkey = get_associated_token_address(owner_pda.key, &elusiv_token(token_id)?.mint);
+        let pubkey = get_associated_token_address(owner_pda.key, mint);
         guard!(pubkey == *token_account.key, ElusivError::InvalidAccount);
+
+        let account = spl_token::state::Account::unpack_unchecked(&token_account.data.borrow())?;
+        guard!(
+            account.delegate.is_none()
+                && account.close_authority.is_none()
+                && account.state == spl_token::state::AccountState::Initialized,
+            ElusivError::CompromisedTokenAccount
+        );
     }
 
     Ok(())
@@ -218,22 +276,192 @@ pub fn system_program_account_rent() -> Result<Lamports, ProgramError> {
     }
 }
 
+pub trait ClockSysvar {
+    fn slot(&self) -> Result<u64, ProgramError>;
+    fn unix_timestamp(&self) -> Result<i64, ProgramError>;
+
+    /// The current unix timestamp (negative timestamps are clamped to zero)
+    fn timestamp(&self) -> Result<u64, ProgramError> {
+        Ok(clamp_timestamp(self.unix_timestamp()?))
+    }
+}
+
+#[cfg(test)]
+pub struct TestClockSysvar {
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[cfg(test)]
+impl ClockSysvar for TestClockSysvar {
+    fn slot(&self) -> Result<u64, ProgramError> {
+        Ok(self.slot)
+    }
+
+    fn unix_timestamp(&self) -> Result<i64, ProgramError> {
+        Ok(self.unix_timestamp)
+    }
+}
+
+pub struct DefaultClockSysvar;
+
+impl ClockSysvar for DefaultClockSysvar {
+    fn slot(&self) -> Result<u64, ProgramError> {
+        current_slot()
+    }
+
+    fn unix_timestamp(&self) -> Result<i64, ProgramError> {
+        #[cfg(test)]
+        {
+            Ok(0)
+        }
+
+        #[cfg(not(test))]
+        {
+            Ok(clock()?.unix_timestamp)
+        }
+    }
+}
+
+/// Clamps a negative `unix_timestamp` (e.g. of a misconfigured local validator) to zero
+pub fn clamp_timestamp(unix_timestamp: i64) -> u64 {
+    u64::try_from(unix_timestamp).unwrap_or_else(|_| {
+        solana_program::msg!("Warning: negative unix timestamp clamped to zero");
+        0
+    })
+}
+
+pub fn current_timestamp() -> Result<u64, ProgramError> {
+    DefaultClockSysvar.timestamp()
+}
+
+pub fn current_slot() -> Result<u64, ProgramError> {
+    #[cfg(test)]
+    {
+        Ok(0)
+    }
+
+    #[cfg(not(test))]
+    {
+        Ok(clock()?.slot)
+    }
+}
+
+#[cfg(not(test))]
+fn clock() -> Result<solana_program::clock::Clock, ProgramError> {
+    solana_program::clock::Clock::get().or(Err(ElusivError::ClockUnavailable.into()))
+}
+
 pub fn spl_token_account_rent() -> Result<Lamports, ProgramError> {
+    #[cfg(test)]
+    let rent = Rent::default();
+
+    #[cfg(not(test))]
+    let rent = Rent::get()?;
+
     Ok(Lamports(
-        Rent::get()?.minimum_balance(spl_token::state::Account::LEN),
+        rent.minimum_balance(spl_token::state::Account::LEN),
     ))
 }
 
+/// Guards that a not yet opened PDA can be opened, even if a third party already funded its address
+///
+/// # Note
+///
+/// A funded system-account (without data) is adopted by [`create_pda_account`], accounts of other programs are rejected.
+pub fn verify_adoptable_pda_account(pda_account: &AccountInfo) -> ProgramResult {
+    if pda_account.lamports() == 0 || *pda_account.owner == crate::ID {
+        return Ok(());
+    }
+
+    guard!(
+        *pda_account.owner == solana_program::system_program::ID,
+        ElusivError::ForeignAccountOwner
+    );
+    guard!(
+        pda_account.data.borrow().iter().all(|&b| b == 0),
+        ElusivError::InvalidAccount
+    );
+
+    Ok(())
+}
+
+/// Grows a program-owned `account` to `new_size` (zero-initialized), `payer` funds the additional rent
+pub fn realloc_account<'a>(
+    payer: &AccountInfo<'a>,
+    account: &AccountInfo<'a>,
+    new_size: usize,
+) -> ProgramResult {
+    guard!(
+        new_size > account.data_len(),
+        ElusivError::InvalidAccountState
+    );
+
+    #[cfg(test)]
+    let rent = Rent::default();
+
+    #[cfg(not(test))]
+    let rent = Rent::get()?;
+
+    let lamports = rent
+        .minimum_balance(new_size)
+        .saturating_sub(account.lamports());
+    if lamports > 0 {
+        invoke(
+            &solana_program::system_instruction::transfer(payer.key, account.key, lamports),
+            &[payer.clone(), account.clone()],
+        )?;
+    }
+
+    #[cfg(not(test))]
+    account.realloc(new_size, true)?;
+
+    // Unit-tests have no runtime-serialized account data that could grow in place
+    #[cfg(test)]
+    {
+        let mut data = account.data.borrow().to_vec();
+        data.resize(new_size, 0);
+        *account.data.borrow_mut() = Box::leak(data.into_boxed_slice());
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
         macros::{account_info, test_account_info},
         state::{governor::PoolAccount, proof::VerificationAccount},
-        token::TOKENS,
+        token::{spl_token_account_data_with_owner, SPL_TOKEN_COUNT, TOKENS},
     };
     use solana_program::{pubkey::Pubkey, system_program};
 
+    #[test]
+    fn test_clamp_timestamp() {
+        assert_eq!(clamp_timestamp(0), 0);
+        assert_eq!(clamp_timestamp(1_700_000_000), 1_700_000_000);
+        assert_eq!(clamp_timestamp(-1), 0);
+        assert_eq!(clamp_timestamp(i64::MIN), 0);
+        assert_eq!(clamp_timestamp(i64::MAX), i64::MAX as u64);
+    }
+
+    #[test]
+    fn test_clock_sysvar_timestamp() {
+        let clock = |unix_timestamp| TestClockSysvar {
+            slot: 0,
+            unix_timestamp,
+        };
+
+        assert_eq!(clock(-100).timestamp(), Ok(0));
+
+        // A regressing cluster timestamp is passed on unchanged
+        assert_eq!(clock(1_000).timestamp(), Ok(1_000));
+        assert_eq!(clock(999).timestamp(), Ok(999));
+
+        assert_eq!(DefaultClockSysvar.timestamp(), current_timestamp());
+    }
+
     #[test]
     fn test_transfer_token_from_pda() {
         test_account_info!(non_pda, 0, Pubkey::new_unique());
@@ -528,32 +756,170 @@ mod tests {
         assert_eq!(payer.lamports(), start_balance * 2);
     }
 
+    #[test]
+    fn test_verify_adoptable_pda_account() {
+        let pk = Pubkey::new_unique();
+
+        // Funded system-account
+        account_info!(account, pk, vec![], system_program::id(), false);
+        assert_eq!(verify_adoptable_pda_account(&account), Ok(()));
+
+        // Funded system-account with data
+        account_info!(account, pk, vec![0, 1], system_program::id(), false);
+        assert_eq!(
+            verify_adoptable_pda_account(&account),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        // Account of another program
+        account_info!(account, pk, vec![], Pubkey::new_unique(), false);
+        assert_eq!(
+            verify_adoptable_pda_account(&account),
+            Err(ElusivError::ForeignAccountOwner.into())
+        );
+
+        // Unfunded account
+        account_info!(payer, Pubkey::new_unique(), vec![]);
+        close_account(&payer, &account).unwrap();
+        assert_eq!(verify_adoptable_pda_account(&account), Ok(()));
+    }
+
     #[test]
     fn test_verify_program_token_account() {
         let pk_pool_0 = get_associated_token_address(&PoolAccount::find(None).0, &TOKENS[1].mint);
         let pk_pool_1 = get_associated_token_address(&PoolAccount::find(None).0, &TOKENS[2].mint);
 
         account_info!(pool, PoolAccount::find(None).0, vec![]);
-        account_info!(token_account0, pk_pool_0, vec![]);
-        account_info!(token_account1, pk_pool_1, vec![]);
+        account_info!(
+            token_account0,
+            pk_pool_0,
+            spl_token_account_data_with_owner(1, pool.key)
+        );
+        account_info!(
+            token_account1,
+            pk_pool_1,
+            spl_token_account_data_with_owner(2, pool.key)
+        );
 
-        assert_eq!(verify_program_token_account(&pool, &pool, 0), Ok(()));
         assert_eq!(
-            verify_program_token_account(&pool, &token_account0, 1),
+            verify_program_token_account(&pool, &pool, 0, &TOKENS[0].mint),
             Ok(())
         );
         assert_eq!(
-            verify_program_token_account(&pool, &token_account1, 1),
+            verify_program_token_account(&pool, &token_account0, 1, &TOKENS[1].mint),
+            Ok(())
+        );
+        assert_eq!(
+            verify_program_token_account(&pool, &token_account1, 1, &TOKENS[1].mint),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        assert_eq!(
+            verify_program_token_account(&pool, &token_account1, 2, &TOKENS[2].mint),
+            Ok(())
+        );
+        assert_eq!(
+            verify_program_token_account(&pool, &token_account0, 2, &TOKENS[2].mint),
             Err(ElusivError::InvalidAccount.into())
         );
 
+        // Registered (non-static) tokens are verified against their mint as well
+        let token_id = SPL_TOKEN_COUNT as u16 + 1;
         assert_eq!(
-            verify_program_token_account(&pool, &token_account1, 2),
+            verify_program_token_account(&pool, &token_account1, token_id, &TOKENS[2].mint),
             Ok(())
         );
         assert_eq!(
-            verify_program_token_account(&pool, &token_account0, 2),
+            verify_program_token_account(&pool, &token_account0, token_id, &TOKENS[2].mint),
             Err(ElusivError::InvalidAccount.into())
         );
+
+        let compromised_data = |f: &dyn Fn(&mut spl_token::state::Account)| {
+            let mut data = spl_token_account_data_with_owner(1, pool.key);
+            let mut account = spl_token::state::Account::unpack(&data).unwrap();
+            f(&mut account);
+            spl_token::state::Account::pack(account, &mut data[..]).unwrap();
+            data
+        };
+
+        // Delegate
+        account_info!(
+            delegated,
+            pk_pool_0,
+            compromised_data(&|account| account.delegate = Some(Pubkey::new_unique()).into())
+        );
+        assert_eq!(
+            verify_program_token_account(&pool, &delegated, 1, &TOKENS[1].mint),
+            Err(ElusivError::CompromisedTokenAccount.into())
+        );
+
+        // Close-authority
+        account_info!(
+            closable,
+            pk_pool_0,
+            compromised_data(&|account| {
+                account.close_authority = Some(Pubkey::new_unique()).into()
+            })
+        );
+        assert_eq!(
+            verify_program_token_account(&pool, &closable, 1, &TOKENS[1].mint),
+            Err(ElusivError::CompromisedTokenAccount.into())
+        );
+
+        // Frozen
+        account_info!(
+            frozen,
+            pk_pool_0,
+            compromised_data(&|account| account.state = spl_token::state::AccountState::Frozen)
+        );
+        assert_eq!(
+            verify_program_token_account(&pool, &frozen, 1, &TOKENS[1].mint),
+            Err(ElusivError::CompromisedTokenAccount.into())
+        );
+    }
+
+    #[test]
+    fn test_create_associated_token_account() {
+        let mint = TOKENS[1].mint;
+        let wallet_pk = Pubkey::new_unique();
+        let ata = get_associated_token_address(&wallet_pk, &mint);
+        account_info!(wallet, wallet_pk, vec![]);
+        account_info!(mint_account, mint, vec![]);
+        account_info!(payer, Pubkey::new_unique(), vec![]);
+        account_info!(broke_payer, Pubkey::new_unique(), vec![]);
+        account_info!(existing, ata, vec![], spl_token::ID, false);
+        account_info!(recipient, ata, vec![], system_program::ID, false);
+        **broke_payer.lamports.borrow_mut() = spl_token_account_rent().unwrap().0 - 1;
+        **recipient.lamports.borrow_mut() = 0;
+
+        // Pre-existing account
+        assert_eq!(
+            create_associated_token_account(&payer, &wallet, &existing, &mint_account, &mint),
+            Ok(false)
+        );
+
+        // Invalid mint
+        assert_eq!(
+            create_associated_token_account(&payer, &wallet, &recipient, &wallet, &mint),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        // Payer cannot cover the rent
+        assert_eq!(
+            create_associated_token_account(
+                &broke_payer,
+                &wallet,
+                &recipient,
+                &mint_account,
+                &mint
+            ),
+            Err(ElusivError::InsufficientRentFunds.into())
+        );
+
+        // Fresh creation
+        assert_eq!(
+            create_associated_token_account(&payer, &wallet, &recipient, &mint_account, &mint),
+            Ok(true)
+        );
     }
 }
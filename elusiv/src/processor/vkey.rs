@@ -1,9 +1,20 @@
 use crate::{
-    error::ElusivError, processor::setup_child_account, proof::vkey::VerifyingKey,
-    state::vkey::VKeyAccount,
+    error::ElusivError,
+    processor::setup_child_account,
+    proof::{
+        verifier::ComputationDescriptor,
+        vkey::{is_built_in_vkey, VerifyingKey},
+    },
+    state::{
+        governor::{Category, GovernorAccount},
+        vkey::{ComputationDescriptorAccount, VKeyAccount},
+    },
+    types::U256,
 };
 use borsh::{BorshDeserialize, BorshSerialize};
-use elusiv_types::{BorshSerDeSized, ChildAccountConfig, ElusivOption, ParentAccount};
+use elusiv_types::{
+    BorshSerDeSized, ChildAccountConfig, ElusivOption, ParentAccount, UnverifiedAccountInfo,
+};
 use elusiv_utils::{
     guard, open_pda_account_with_offset, pda_account, transfer_with_system_program,
 };
@@ -27,11 +38,13 @@ impl elusiv_types::BorshSerDeSized for VKeyAccountDataPacket {
 pub fn create_vkey_account<'a>(
     signer: &AccountInfo<'a>,
     vkey_account: &AccountInfo<'a>,
+    governor: &GovernorAccount,
 
     vkey_id: u32,
     public_inputs_count: u32,
     authority: ElusivOption<Pubkey>,
 ) -> ProgramResult {
+    governor.check_category(Category::VKeyAdmin)?;
     guard!(
         vkey_id < MAX_NUMBER_OF_VKEYS,
         ElusivError::InvalidAccountState
@@ -50,10 +63,11 @@ pub fn create_new_vkey_version(
     signer: &AccountInfo,
     vkey_account: &mut VKeyAccount,
     vkey_binary_data_account: &AccountInfo,
+    governor: &GovernorAccount,
 
     _vkey_id: u32,
 ) -> ProgramResult {
-    verify_vkey_modification(signer, vkey_account)?;
+    verify_vkey_modification(signer, vkey_account, governor)?;
 
     guard!(
         vkey_account.get_child_pubkey(1).is_none(),
@@ -78,12 +92,13 @@ pub fn create_new_vkey_version(
 pub fn set_vkey_data(
     signer: &AccountInfo,
     vkey_account: &mut VKeyAccount,
+    governor: &GovernorAccount,
 
     _vkey_id: u32,
     data_position: u32,
     packet: VKeyAccountDataPacket,
 ) -> ProgramResult {
-    verify_vkey_modification(signer, vkey_account)?;
+    verify_vkey_modification(signer, vkey_account, governor)?;
 
     let public_inputs_count = vkey_account.get_public_inputs_count();
     let len = VerifyingKey::source_size(public_inputs_count as usize);
@@ -107,10 +122,11 @@ pub fn update_vkey_version<'a>(
     vkey_account: &mut VKeyAccount,
     old_vkey_binary_data_account: &AccountInfo<'a>,
     system_program: &AccountInfo<'a>,
+    governor: &GovernorAccount,
 
     _vkey_id: u32,
 ) -> ProgramResult {
-    verify_vkey_modification(signer, vkey_account)?;
+    verify_vkey_modification(signer, vkey_account, governor)?;
 
     guard!(
         vkey_account.get_child_pubkey(1).is_some(),
@@ -147,15 +163,27 @@ pub fn update_vkey_version<'a>(
     Ok(())
 }
 
-/// Freezes a [`VKeyAccount`]
+/// Freezes a [`VKeyAccount`] and records the `expected_source_digest` of its active vkey
+///
+/// # Note
+///
+/// Fails if the active vkey's [`VKeyAccount::source_digest`] does not match the `expected_source_digest`.
 pub fn freeze_vkey(
     signer: &AccountInfo,
     vkey_account: &mut VKeyAccount,
+    governor: &GovernorAccount,
 
     _vkey_id: u32,
+    expected_source_digest: U256,
 ) -> ProgramResult {
-    verify_vkey_modification(signer, vkey_account)?;
+    verify_vkey_modification(signer, vkey_account, governor)?;
+    guard!(
+        vkey_account.source_digest()? == expected_source_digest,
+        ElusivError::InvalidVKeySourceDigest
+    );
+
     vkey_account.set_is_frozen(&true);
+    vkey_account.set_expected_source_digest(&ElusivOption::Some(expected_source_digest));
 
     Ok(())
 }
@@ -164,17 +192,65 @@ pub fn freeze_vkey(
 pub fn change_vkey_authority(
     signer: &AccountInfo,
     vkey_account: &mut VKeyAccount,
+    governor: &GovernorAccount,
 
     _vkey_id: u32,
     authority: Pubkey,
 ) -> ProgramResult {
-    verify_vkey_modification(signer, vkey_account)?;
+    verify_vkey_modification(signer, vkey_account, governor)?;
     vkey_account.set_authority(&Some(authority).into());
 
     Ok(())
 }
 
-fn verify_vkey_modification(signer: &AccountInfo, vkey_account: &VKeyAccount) -> ProgramResult {
+/// Registers the [`ComputationDescriptor`] of a vkey that is not built-in
+pub fn register_computation_descriptor<'a>(
+    authority: &AccountInfo,
+    payer: &AccountInfo<'a>,
+    vkey_account: &VKeyAccount,
+    computation_descriptor_account: UnverifiedAccountInfo<'_, 'a>,
+    governor: &GovernorAccount,
+
+    vkey_id: u32,
+    descriptor: ComputationDescriptor,
+) -> ProgramResult {
+    governor.check_category(Category::VKeyAdmin)?;
+    guard!(*authority.key == crate::ID, ElusivError::InvalidAccount);
+    guard!(
+        !is_built_in_vkey(vkey_id),
+        ElusivError::InvalidInstructionData
+    );
+    guard!(
+        descriptor.is_valid(vkey_account.get_public_inputs_count() as usize),
+        ElusivError::InvalidInstructionData
+    );
+
+    open_pda_account_with_offset::<ComputationDescriptorAccount>(
+        &crate::id(),
+        payer,
+        computation_descriptor_account.get_unsafe(),
+        vkey_id,
+        None,
+    )?;
+
+    pda_account!(
+        mut computation_descriptor,
+        ComputationDescriptorAccount,
+        computation_descriptor_account.get_unsafe()
+    );
+    computation_descriptor.set_descriptor(&descriptor);
+    computation_descriptor.set_is_setup(&true);
+
+    Ok(())
+}
+
+fn verify_vkey_modification(
+    signer: &AccountInfo,
+    vkey_account: &VKeyAccount,
+    governor: &GovernorAccount,
+) -> ProgramResult {
+    governor.check_category(Category::VKeyAdmin)?;
+
     guard!(
         !vkey_account.get_is_frozen(),
         ElusivError::InvalidAccountState
@@ -198,13 +274,18 @@ mod test {
     use super::*;
     use crate::{
         bytes::div_ceiling_usize,
-        macros::{signing_test_account_info, test_account_info},
+        macros::{
+            account_info, signing_test_account_info, test_account_info, zero_program_account,
+        },
         processor::vkey_account,
-        proof::vkey::{TestVKey, VerifyingKeyInfo},
+        proof::vkey::{SendQuadraVKey, TestVKey, VerifyingKeyInfo},
+        state::governor::PAUSABLE_CATEGORIES,
     };
+    use elusiv_types::{PDAAccount, SizedAccount};
 
     #[test]
     fn test_create_new_vkey_version() {
+        zero_program_account!(governor, GovernorAccount);
         vkey_account!(vkey_account, TestVKey);
         signing_test_account_info!(signer);
 
@@ -226,6 +307,7 @@ mod test {
                 &signer,
                 &mut vkey_account,
                 &valid_vkey_binary_data_account,
+                &governor,
                 0
             ),
             Err(ElusivError::InvalidAccountState.into())
@@ -239,6 +321,7 @@ mod test {
                 &signer,
                 &mut vkey_account,
                 &invalid_vkey_binary_data_account,
+                &governor,
                 0
             ),
             Err(ProgramError::InvalidAccountData)
@@ -252,6 +335,7 @@ mod test {
                 &signer,
                 &mut vkey_account,
                 &invalid_vkey_binary_data_account,
+                &governor,
                 0
             ),
             Err(ProgramError::AccountAlreadyInitialized)
@@ -264,6 +348,7 @@ mod test {
                 &signer,
                 &mut vkey_account,
                 &valid_vkey_binary_data_account,
+                &governor,
                 0
             ),
             Ok(())
@@ -272,6 +357,7 @@ mod test {
 
     #[test]
     fn test_set_vkey_data() {
+        zero_program_account!(governor, GovernorAccount);
         let data = TestVKey::verifying_key_source();
         vkey_account!(vkey_account, TestVKey);
         signing_test_account_info!(signer);
@@ -294,6 +380,7 @@ mod test {
             set_vkey_data(
                 &signer,
                 &mut vkey_account,
+                &governor,
                 0,
                 i as u32,
                 VKeyAccountDataPacket(slice.to_vec()),
@@ -310,6 +397,7 @@ mod test {
 
     #[test]
     fn test_update_vkey_account() {
+        zero_program_account!(governor, GovernorAccount);
         vkey_account!(vkey_account, TestVKey);
         signing_test_account_info!(signer);
         test_account_info!(acc);
@@ -319,7 +407,7 @@ mod test {
         vkey_account.set_authority(&Some(*signer.key).into());
 
         assert_eq!(
-            update_vkey_version(&signer, &mut vkey_account, &acc, &acc, 0),
+            update_vkey_version(&signer, &mut vkey_account, &acc, &acc, &governor, 0),
             Err(ElusivError::InvalidAccountState.into())
         );
 
@@ -327,7 +415,7 @@ mod test {
         vkey_account.set_child_pubkey(1, Some(*vkey_binary_data_account.key).into());
 
         assert_eq!(
-            update_vkey_version(&signer, &mut vkey_account, &acc, &acc, 0),
+            update_vkey_version(&signer, &mut vkey_account, &acc, &acc, &governor, 0),
             Ok(())
         );
 
@@ -341,6 +429,7 @@ mod test {
 
     #[test]
     fn test_freeze_vkey() {
+        zero_program_account!(governor, GovernorAccount);
         vkey_account!(vkey_account, TestVKey);
         signing_test_account_info!(signer);
 
@@ -351,62 +440,175 @@ mod test {
             })
             .unwrap();
 
-        freeze_vkey(&signer, &mut vkey_account, 0).unwrap();
+        let digest = vkey_account.source_digest().unwrap();
+
+        // Mismatching digest
+        assert_eq!(
+            freeze_vkey(&signer, &mut vkey_account, &governor, 0, [0; 32]),
+            Err(ElusivError::InvalidVKeySourceDigest.into())
+        );
+        assert!(!vkey_account.get_is_frozen());
+
+        freeze_vkey(&signer, &mut vkey_account, &governor, 0, digest).unwrap();
 
         assert!(vkey_account.get_is_frozen());
         assert_eq!(
-            freeze_vkey(&signer, &mut vkey_account, 0),
+            vkey_account.get_expected_source_digest().option(),
+            Some(digest)
+        );
+        assert_eq!(
+            freeze_vkey(&signer, &mut vkey_account, &governor, 0, digest),
             Err(ElusivError::InvalidAccountState.into())
         );
     }
 
     #[test]
     fn test_change_vkey_authority() {
+        zero_program_account!(governor, GovernorAccount);
         vkey_account!(vkey_account, TestVKey);
         signing_test_account_info!(signer);
         signing_test_account_info!(signer2);
 
         assert_eq!(
-            change_vkey_authority(&signer, &mut vkey_account, 0, *signer.key),
+            change_vkey_authority(&signer, &mut vkey_account, &governor, 0, *signer.key),
             Ok(())
         );
 
         assert_eq!(
-            change_vkey_authority(&signer2, &mut vkey_account, 0, *signer.key),
+            change_vkey_authority(&signer2, &mut vkey_account, &governor, 0, *signer.key),
             Err(ElusivError::InvalidAccount.into())
         );
 
         assert_eq!(
-            change_vkey_authority(&signer, &mut vkey_account, 0, *signer2.key),
+            change_vkey_authority(&signer, &mut vkey_account, &governor, 0, *signer2.key),
             Ok(())
         );
 
         assert_eq!(
-            change_vkey_authority(&signer, &mut vkey_account, 0, *signer.key),
+            change_vkey_authority(&signer, &mut vkey_account, &governor, 0, *signer.key),
+            Err(ElusivError::InvalidAccount.into())
+        );
+    }
+
+    #[test]
+    fn test_register_computation_descriptor() -> ProgramResult {
+        zero_program_account!(mut governor, GovernorAccount);
+        vkey_account!(vkey_account, TestVKey);
+        account_info!(authority, crate::ID, vec![], crate::ID, true);
+        signing_test_account_info!(invalid_authority);
+        test_account_info!(payer, 0);
+        account_info!(
+            computation_descriptor_account,
+            ComputationDescriptorAccount::find(Some(TestVKey::VKEY_ID)).0,
+            vec![0; ComputationDescriptorAccount::SIZE]
+        );
+
+        let public_inputs_count = TestVKey::public_inputs_count();
+        let descriptor = ComputationDescriptor {
+            add_mixed_cost: 90,
+            add_cost: 120,
+            ..ComputationDescriptor::built_in(public_inputs_count)
+        };
+
+        macro_rules! register {
+            ($authority: ident, $vkey_id: expr, $descriptor: expr) => {
+                register_computation_descriptor(
+                    &$authority,
+                    &payer,
+                    &vkey_account,
+                    UnverifiedAccountInfo::new(&computation_descriptor_account),
+                    &governor,
+                    $vkey_id,
+                    $descriptor,
+                )
+            };
+        }
+
+        // Invalid authority
+        assert_eq!(
+            register!(invalid_authority, TestVKey::VKEY_ID, descriptor),
             Err(ElusivError::InvalidAccount.into())
         );
+
+        // Built-in vkey
+        assert_eq!(
+            register!(authority, SendQuadraVKey::VKEY_ID, descriptor),
+            Err(ElusivError::InvalidInstructionData.into())
+        );
+
+        // Descriptor does not match the vkey
+        assert_eq!(
+            register!(
+                authority,
+                TestVKey::VKEY_ID,
+                ComputationDescriptor::built_in(public_inputs_count - 1)
+            ),
+            Err(ElusivError::InvalidInstructionData.into())
+        );
+
+        // Invalid PDA
+        assert_eq!(
+            register!(authority, TestVKey::VKEY_ID + 1, descriptor),
+            Err(ProgramError::InvalidSeeds)
+        );
+
+        // Paused
+        governor.set_paused_categories(&Category::VKeyAdmin.bit());
+        assert_eq!(
+            register!(authority, TestVKey::VKEY_ID, descriptor),
+            Err(ElusivError::CategoryPaused.into())
+        );
+        governor.set_paused_categories(&(PAUSABLE_CATEGORIES & !Category::VKeyAdmin.bit()));
+
+        assert_eq!(register!(authority, TestVKey::VKEY_ID, descriptor), Ok(()));
+
+        pda_account!(
+            computation_descriptor,
+            ComputationDescriptorAccount,
+            computation_descriptor_account
+        );
+        assert!(computation_descriptor.get_is_setup());
+        assert_eq!(computation_descriptor.get_descriptor(), descriptor);
+
+        Ok(())
     }
 
     #[test]
     fn test_verify_vkey_modification() {
+        zero_program_account!(mut governor, GovernorAccount);
         vkey_account!(vkey_account, TestVKey);
         signing_test_account_info!(signer);
         signing_test_account_info!(invalid_signer);
 
         // Any signer allowed
         assert_eq!(
-            verify_vkey_modification(&invalid_signer, &vkey_account),
+            verify_vkey_modification(&invalid_signer, &vkey_account, &governor),
             Ok(())
         );
 
         vkey_account.set_authority(&Some(*signer.key).into());
 
         // Valid signer
-        assert_eq!(verify_vkey_modification(&signer, &vkey_account), Ok(()));
+        assert_eq!(
+            verify_vkey_modification(&signer, &vkey_account, &governor),
+            Ok(())
+        );
+
+        // Paused
+        governor.set_paused_categories(&Category::VKeyAdmin.bit());
+        assert_eq!(
+            verify_vkey_modification(&signer, &vkey_account, &governor),
+            Err(ElusivError::CategoryPaused.into())
+        );
+        governor.set_paused_categories(&(PAUSABLE_CATEGORIES & !Category::VKeyAdmin.bit()));
+        assert_eq!(
+            verify_vkey_modification(&signer, &vkey_account, &governor),
+            Ok(())
+        );
 
         // Invalid authority
         assert_eq!(
-            verify_vkey_modification(&invalid_signer, &vkey_account),
+            verify_vkey_modification(&invalid_signer, &vkey_account, &governor),
             Err(ElusivError::InvalidAccount.into())
         );
 
@@ -414,7 +616,7 @@ mod test {
 
         // Frozen account
         assert_eq!(
-            verify_vkey_modification(&signer, &vkey_account),
+            verify_vkey_modification(&signer, &vkey_account, &governor),
             Err(ElusivError::InvalidAccountState.into())
         );
 
@@ -423,7 +625,7 @@ mod test {
         test_account_info!(signer);
         vkey_account.set_authority(&Some(*signer.key).into());
         assert_eq!(
-            verify_vkey_modification(&signer, &vkey_account),
+            verify_vkey_modification(&signer, &vkey_account, &governor),
             Err(ProgramError::MissingRequiredSignature)
         );
     }
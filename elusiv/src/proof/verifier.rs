@@ -16,7 +16,7 @@ use crate::error::ElusivError::{
 use crate::error::ElusivResult;
 use crate::fields::{G2HomProjective, Wrap, G1A, G2A};
 use crate::processor::COMPUTE_VERIFICATION_IX_COUNT;
-use crate::state::proof::{RAMFq, VerificationAccount, VerificationState};
+use crate::state::proof::{RAMFq, VerificationAccount, VerificationState, MAX_PUBLIC_INPUTS_COUNT};
 use crate::types::U256;
 use ark_bn254::{
     Fq, Fq12, Fq12Parameters, Fq2, Fq6, Fq6Parameters, G1Affine, G1Projective, G2Affine, Parameters,
@@ -213,7 +213,7 @@ macro_rules! read_g1_p {
 }
 
 const PREPARE_PUBLIC_INPUTS_ROUNDS: usize = 33;
-const fn prepare_public_inputs_rounds(public_inputs_count: usize) -> usize {
+pub(crate) const fn prepare_public_inputs_rounds(public_inputs_count: usize) -> usize {
     PREPARE_PUBLIC_INPUTS_ROUNDS * public_inputs_count
 }
 
@@ -305,12 +305,71 @@ const ADD_MIXED_COST: u16 = 22;
 const ADD_COST: u16 = 30;
 const MAX_CUS: u16 = 1_330; // 1_400_000 / 1000 minus padding
 
+/// Batching metadata of the public input preparation of a [`VerifyingKey`]
+///
+/// # Notes
+///
+/// - all costs are in thousands of compute units
+/// - the built-in vkeys use [`ComputationDescriptor::built_in`], other vkeys require a registered [`crate::state::vkey::ComputationDescriptorAccount`]
+#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ComputationDescriptor {
+    /// Cost of a round adding a non-zero window
+    pub add_mixed_cost: u16,
+
+    /// Cost of the last round of a non-zero public input
+    pub add_cost: u16,
+
+    /// Maximum cost of a single instruction
+    pub max_compute_units: u16,
+
+    /// Total number of input preparation rounds
+    pub total_rounds: u32,
+}
+
+impl ComputationDescriptor {
+    pub const fn built_in(public_inputs_count: usize) -> Self {
+        ComputationDescriptor {
+            add_mixed_cost: ADD_MIXED_COST,
+            add_cost: ADD_COST,
+            max_compute_units: MAX_CUS,
+            total_rounds: usize_as_u32_safe(prepare_public_inputs_rounds(public_inputs_count)),
+        }
+    }
+
+    /// Returns true if the descriptor matches a vkey with `public_inputs_count` public inputs
+    ///
+    /// # Notes
+    ///
+    /// The weights are bounded, so that a schedule requires at most `10 * public_inputs_count` instructions.
+    pub fn is_valid(&self, public_inputs_count: usize) -> bool {
+        let max_cost = std::cmp::max(self.add_mixed_cost, self.add_cost) as u32;
+
+        public_inputs_count > 0
+            && public_inputs_count <= MAX_PUBLIC_INPUTS_COUNT
+            && self.total_rounds as usize == prepare_public_inputs_rounds(public_inputs_count)
+            && max_cost <= self.max_compute_units as u32
+            && PREPARE_PUBLIC_INPUTS_ROUNDS as u32 * max_cost
+                <= 9 * (self.max_compute_units as u32 - max_cost)
+    }
+}
+
 /// Returns the instructions (and their rounds) required for a specific public-input-bound input preparation
 pub fn prepare_public_inputs_instructions(
     public_inputs: &[U256],
     public_inputs_count: usize,
 ) -> Vec<u32> {
-    assert!(public_inputs.len() == public_inputs_count);
+    prepare_public_inputs_instructions_with_descriptor(
+        public_inputs,
+        &ComputationDescriptor::built_in(public_inputs_count),
+    )
+}
+
+/// Returns the instructions (and their rounds) required for an input preparation batched according to `descriptor`
+pub fn prepare_public_inputs_instructions_with_descriptor(
+    public_inputs: &[U256],
+    descriptor: &ComputationDescriptor,
+) -> Vec<u32> {
+    assert!(prepare_public_inputs_rounds(public_inputs.len()) == descriptor.total_rounds as usize);
 
     let mut instructions = Vec::new();
 
@@ -324,15 +383,15 @@ pub fn prepare_public_inputs_instructions(
                 if *public_input == [0; 32] {
                     0
                 } else {
-                    ADD_COST
+                    descriptor.add_cost as u32
                 }
             } else if public_input[b] == 0 {
                 0
             } else {
-                ADD_MIXED_COST
+                descriptor.add_mixed_cost as u32
             };
 
-            if compute_units + cus > MAX_CUS {
+            if compute_units + cus > descriptor.max_compute_units as u32 {
                 instructions.push(rounds);
 
                 rounds = 1;
@@ -351,10 +410,7 @@ pub fn prepare_public_inputs_instructions(
     }
 
     // Redundant check
-    assert_eq!(
-        total_rounds,
-        prepare_public_inputs_rounds(public_inputs_count)
-    );
+    assert_eq!(total_rounds, descriptor.total_rounds);
 
     instructions
 }
@@ -961,6 +1017,8 @@ mod tests {
     use crate::proof::test_proofs::{invalid_proofs, valid_proofs};
     use crate::proof::vkey::{TestVKey, VerifyingKeyInfo};
     use crate::state::metadata::CommitmentMetadata;
+    use crate::state::program_account::ProgramAccount;
+    use crate::state::proof::remaining_compute_rounds;
     use crate::state::storage::empty_root_raw;
     use crate::types::{
         InputCommitment, JoinSplitPublicInputs, OptionalFee, PublicInputs, RawU256,
@@ -972,24 +1030,48 @@ mod tests {
     use ark_ec::PairingEngine;
     use ark_groth16::prepare_inputs;
     use solana_program::native_token::LAMPORTS_PER_SOL;
+    use solana_program::program_error::ProgramError;
     use std::str::FromStr;
 
+    /// Size of a [`VerificationAccount`] storing the public inputs of the [`TestVKey`]
+    const STORAGE_SIZE: usize =
+        VerificationAccount::account_size(TestVKey::PUBLIC_INPUTS_COUNT as usize);
+
     fn setup_storage_account<VKey: VerifyingKeyInfo>(
         storage: &mut VerificationAccount,
         proof: Proof,
         public_inputs: &[U256],
+    ) {
+        setup_storage_account_with_descriptor(
+            storage,
+            proof,
+            public_inputs,
+            &ComputationDescriptor::built_in(VKey::public_inputs_count()),
+        )
+    }
+
+    fn setup_storage_account_with_descriptor(
+        storage: &mut VerificationAccount,
+        proof: Proof,
+        public_inputs: &[U256],
+        descriptor: &ComputationDescriptor,
     ) {
         storage.a.set(proof.a);
         storage.b.set(proof.b);
         storage.c.set(proof.c);
         storage.set_state(&VerificationState::ProofSetup);
 
-        for (i, &public_input) in public_inputs.iter().enumerate() {
-            storage.set_public_input(i, &RawU256::new(public_input));
-        }
+        storage
+            .set_public_inputs(
+                &public_inputs
+                    .iter()
+                    .map(|&public_input| RawU256::new(public_input))
+                    .collect::<Vec<RawU256>>(),
+            )
+            .unwrap();
 
         let instructions =
-            prepare_public_inputs_instructions(public_inputs, VKey::public_inputs_count());
+            prepare_public_inputs_instructions_with_descriptor(public_inputs, descriptor);
         storage
             .setup_public_inputs_instructions(&instructions)
             .unwrap();
@@ -1064,10 +1146,15 @@ mod tests {
         ];
 
         // First version
-        zero_program_account!(mut storage, VerificationAccount);
-        for (i, public_input) in public_inputs.iter().enumerate() {
-            storage.set_public_input(i, &RawU256::new(u256_from_str_skip_mr(public_input)));
-        }
+        zero_program_account!(mut storage, VerificationAccount, STORAGE_SIZE);
+        storage
+            .set_public_inputs(
+                &public_inputs
+                    .iter()
+                    .map(|&public_input| RawU256::new(u256_from_str_skip_mr(public_input)))
+                    .collect::<Vec<RawU256>>(),
+            )
+            .unwrap();
 
         // precomputed_input_preparation version
         let p_result = precomputed_input_preparation(
@@ -1095,7 +1182,7 @@ mod tests {
         assert_eq!(result, p_result);
 
         // Second version
-        zero_program_account!(mut storage, VerificationAccount);
+        zero_program_account!(mut storage, VerificationAccount, STORAGE_SIZE);
         let public_inputs = valid_proofs()[0].public_inputs.clone();
         setup_storage_account::<TestVKey>(&mut storage, valid_proofs()[0].proof, &public_inputs);
 
@@ -1476,6 +1563,9 @@ mod tests {
             hashed_inputs: u256_from_str_skip_mr("230508240750559904196809564625"),
             recipient_is_associated_token_account: true,
             solana_pay_transfer: false,
+            recipient_is_program_owned: false,
+            view_tag: 0,
+            current_time: 0,
         };
         let p = public_inputs.public_signals_skip_mr();
         let v = prepare_public_inputs_instructions(&p, TestVKey::public_inputs_count());
@@ -1495,13 +1585,90 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_prepare_public_inputs_instructions_with_descriptor() {
+        let public_inputs_count = TestVKey::public_inputs_count();
+        let public_inputs = vec![[1; 32]; public_inputs_count];
+        let built_in = ComputationDescriptor::built_in(public_inputs_count);
+
+        assert_eq!(
+            prepare_public_inputs_instructions_with_descriptor(&public_inputs, &built_in),
+            prepare_public_inputs_instructions(&public_inputs, public_inputs_count)
+        );
+
+        // Each instruction contains exactly one public input
+        let descriptor = ComputationDescriptor {
+            add_mixed_cost: 10,
+            add_cost: 10,
+            max_compute_units: 330,
+            ..built_in
+        };
+        assert!(descriptor.is_valid(public_inputs_count));
+        assert_eq!(
+            prepare_public_inputs_instructions_with_descriptor(&public_inputs, &descriptor),
+            vec![PREPARE_PUBLIC_INPUTS_ROUNDS as u32; public_inputs_count]
+        );
+    }
+
+    #[test]
+    fn test_computation_descriptor_is_valid() {
+        let public_inputs_count = TestVKey::public_inputs_count();
+        let built_in = ComputationDescriptor::built_in(public_inputs_count);
+        assert!(built_in.is_valid(public_inputs_count));
+
+        // Mismatched total rounds
+        assert!(!built_in.is_valid(public_inputs_count - 1));
+        assert!(!ComputationDescriptor::built_in(0).is_valid(0));
+        assert!(
+            !ComputationDescriptor::built_in(MAX_PUBLIC_INPUTS_COUNT + 1)
+                .is_valid(MAX_PUBLIC_INPUTS_COUNT + 1)
+        );
+
+        // A single round exceeds the instruction limit
+        assert!(!ComputationDescriptor {
+            add_cost: MAX_CUS + 1,
+            ..built_in
+        }
+        .is_valid(public_inputs_count));
+
+        // Too many instructions
+        assert!(!ComputationDescriptor {
+            add_mixed_cost: 100,
+            add_cost: 100,
+            max_compute_units: 466,
+            ..built_in
+        }
+        .is_valid(public_inputs_count));
+        assert!(ComputationDescriptor {
+            add_mixed_cost: 100,
+            add_cost: 100,
+            max_compute_units: 467,
+            ..built_in
+        }
+        .is_valid(public_inputs_count));
+    }
+
     fn full_verification<VKey: VerifyingKeyInfo>(
         proof: Proof,
         public_inputs: &[U256],
         vkey: &VerifyingKey,
     ) -> bool {
-        zero_program_account!(mut storage, VerificationAccount);
-        setup_storage_account::<VKey>(&mut storage, proof, public_inputs);
+        full_verification_with_descriptor(
+            proof,
+            public_inputs,
+            vkey,
+            &ComputationDescriptor::built_in(VKey::public_inputs_count()),
+        )
+    }
+
+    fn full_verification_with_descriptor(
+        proof: Proof,
+        public_inputs: &[U256],
+        vkey: &VerifyingKey,
+        descriptor: &ComputationDescriptor,
+    ) -> bool {
+        zero_program_account!(mut storage, VerificationAccount, STORAGE_SIZE);
+        setup_storage_account_with_descriptor(&mut storage, proof, public_inputs, descriptor);
         let instruction_count = storage.get_prepare_inputs_instructions_count() as usize
             + COMBINED_MILLER_LOOP_IXS
             + FINAL_EXPONENTIATION_IXS;
@@ -1535,11 +1702,52 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_verify_proofs_with_descriptor() {
+        vkey!(vkey, TestVKey);
+
+        // A synthetic descriptor with higher weights results in more (smaller) instructions
+        let descriptor = ComputationDescriptor {
+            add_mixed_cost: 90,
+            add_cost: 120,
+            ..ComputationDescriptor::built_in(TestVKey::public_inputs_count())
+        };
+        assert!(descriptor.is_valid(TestVKey::public_inputs_count()));
+
+        for p in valid_proofs() {
+            assert!(
+                prepare_public_inputs_instructions_with_descriptor(&p.public_inputs, &descriptor)
+                    .len()
+                    > prepare_public_inputs_instructions(
+                        &p.public_inputs,
+                        TestVKey::public_inputs_count()
+                    )
+                    .len()
+            );
+
+            assert!(full_verification_with_descriptor(
+                p.proof,
+                &p.public_inputs,
+                &vkey,
+                &descriptor
+            ));
+        }
+
+        for p in invalid_proofs() {
+            assert!(!full_verification_with_descriptor(
+                p.proof,
+                &p.public_inputs,
+                &vkey,
+                &descriptor
+            ));
+        }
+    }
+
     #[test]
     fn test_verify_partial_too_many_calls() {
         let proof = valid_proofs()[0].proof;
         let public_inputs = valid_proofs()[0].public_inputs.clone();
-        zero_program_account!(mut storage, VerificationAccount);
+        zero_program_account!(mut storage, VerificationAccount, STORAGE_SIZE);
         setup_storage_account::<TestVKey>(&mut storage, proof, &public_inputs);
         let instruction_count = storage.get_prepare_inputs_instructions_count() as usize
             + COMBINED_MILLER_LOOP_IXS
@@ -1558,6 +1766,87 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_remaining_instructions() {
+        let proof = valid_proofs()[0].proof;
+        let public_inputs = valid_proofs()[0].public_inputs.clone();
+        zero_program_account!(mut storage, VerificationAccount, STORAGE_SIZE);
+        setup_storage_account::<TestVKey>(&mut storage, proof, &public_inputs);
+        let prepare_instructions = storage.get_prepare_inputs_instructions_count();
+        let instruction_count =
+            prepare_instructions as usize + COMBINED_MILLER_LOOP_IXS + FINAL_EXPONENTIATION_IXS;
+
+        vkey!(vkey, TestVKey);
+
+        assert_eq!(
+            storage.remaining_prepare_instructions(),
+            prepare_instructions
+        );
+        assert_eq!(storage.remaining_instructions() as usize, instruction_count);
+
+        for i in 0..instruction_count {
+            let remaining_prepare = storage.remaining_prepare_instructions();
+            if i < prepare_instructions as usize {
+                assert_eq!(storage.get_step(), VerificationStep::PublicInputPreparation);
+                assert_eq!(remaining_prepare, prepare_instructions - i as u32);
+            } else {
+                assert_eq!(remaining_prepare, 0);
+            }
+            assert_eq!(
+                storage.remaining_instructions() as usize,
+                instruction_count - i
+            );
+
+            verify_partial(&mut storage, &vkey, COMPUTE_VERIFICATION_IX_COUNT - 1).unwrap();
+
+            // The prepare count reaches zero exactly when the combined miller loop starts
+            if i + 1 == prepare_instructions as usize {
+                assert_eq!(storage.remaining_prepare_instructions(), 0);
+                assert_eq!(storage.get_step(), VerificationStep::CombinedMillerLoop);
+            }
+        }
+
+        assert_eq!(storage.remaining_prepare_instructions(), 0);
+        assert_eq!(storage.remaining_instructions(), 0);
+    }
+
+    #[test]
+    fn test_remaining_compute_rounds() {
+        let proof = valid_proofs()[0].proof;
+        let public_inputs = valid_proofs()[0].public_inputs.clone();
+        let mut data = vec![0; STORAGE_SIZE];
+        let mut storage = VerificationAccount::new(&mut data).unwrap();
+        setup_storage_account::<TestVKey>(&mut storage, proof, &public_inputs);
+        let prepare_instructions = storage.get_prepare_inputs_instructions_count() as usize;
+        let instruction_count =
+            prepare_instructions + COMBINED_MILLER_LOOP_IXS + FINAL_EXPONENTIATION_IXS;
+        vkey!(vkey, TestVKey);
+
+        // Mid-prepare
+        assert!(prepare_instructions > 1);
+        verify_partial(&mut storage, &vkey, COMPUTE_VERIFICATION_IX_COUNT - 1).unwrap();
+        assert_eq!(storage.get_step(), VerificationStep::PublicInputPreparation);
+        drop(storage);
+        assert_eq!(remaining_compute_rounds(&data), Ok(instruction_count - 1));
+
+        // Mid-miller-loop
+        let mut storage = VerificationAccount::new(&mut data).unwrap();
+        for _ in 1..prepare_instructions + 3 {
+            verify_partial(&mut storage, &vkey, COMPUTE_VERIFICATION_IX_COUNT - 1).unwrap();
+        }
+        assert_eq!(storage.get_step(), VerificationStep::CombinedMillerLoop);
+        drop(storage);
+        assert_eq!(
+            remaining_compute_rounds(&data),
+            Ok(COMBINED_MILLER_LOOP_IXS - 3 + FINAL_EXPONENTIATION_IXS)
+        );
+
+        assert_eq!(
+            remaining_compute_rounds(&data[..1]),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+
     // https://github.com/arkworks-rs/algebra/blob/6ea310ef09f8b7510ce947490919ea6229bbecd6/ec/src/models/bn/mod.rs#L59
     fn reference_ell(f: Fq12, coeffs: (Fq2, Fq2, Fq2), p: G1Affine) -> Fq12 {
         let mut c0: Fq2 = coeffs.0;
@@ -72,6 +72,11 @@ verification_key_info!(MigrateUnaryVKey, 1, 7, "migrate_unary");
 #[cfg(test)]
 verification_key_info!(TestVKey, 2, 14, "test");
 
+/// Returns true for the vkeys whose computation is described by [`super::verifier::ComputationDescriptor::built_in`]
+pub fn is_built_in_vkey(vkey_id: u32) -> bool {
+    vkey_id == SendQuadraVKey::VKEY_ID || vkey_id == MigrateUnaryVKey::VKEY_ID
+}
+
 /// A Groth16 verifying key with precomputed values
 pub struct VerifyingKey<'a> {
     source: &'a [u8],
@@ -1,7 +1,7 @@
 use super::metadata::CommitmentMetadata;
 use super::queue::{queue_account, RingQueue};
 use crate::buffer::buffer_account;
-use crate::bytes::usize_as_u32_safe;
+use crate::bytes::{usize_as_u32_safe, ElusivOption};
 use crate::commitment::poseidon_hash::BinarySpongeHashingState;
 use crate::commitment::{commitments_per_batch, MAX_HT_SIZE, MT_HEIGHT};
 use crate::error::ElusivError;
@@ -13,7 +13,9 @@ use crate::state::storage::{StorageAccount, HISTORY_ARRAY_SIZE};
 use crate::types::U256;
 use ark_bn254::Fr;
 use ark_ff::{BigInteger256, PrimeField};
+use solana_program::entrypoint::ProgramResult;
 use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
 
 /// Account used for computing `commitment = h(base_commitment, amount)`
 #[elusiv_account(partial_computation: true, eager_type: true)]
@@ -95,6 +97,9 @@ pub struct CommitmentHashingAccount {
 
     // commitments and hashes in the HT
     pub hash_tree: [U256; MAX_HT_SIZE],
+
+    /// The fee-payer performing all compute steps of the current batch (recorded by the first compute step)
+    pub fee_payer: ElusivOption<Pubkey>,
 }
 
 impl<'a> CommitmentHashingAccount<'a> {
@@ -128,6 +133,7 @@ impl<'a> CommitmentHashingAccount<'a> {
         self.set_is_active(&true);
         self.set_fee_version(&fee_version);
         self.set_batching_rate(&batching_rate);
+        self.set_fee_payer(&ElusivOption::None);
 
         assert!(commitments.len() <= MAX_HT_SIZE);
         for (i, commitment) in commitments.iter().enumerate() {
@@ -139,6 +145,18 @@ impl<'a> CommitmentHashingAccount<'a> {
         Ok(())
     }
 
+    /// Records `fee_payer` as the fee-payer of the current batch (first compute step) or verifies it (all later compute steps)
+    pub fn attribute_fee_payer(&mut self, fee_payer: &Pubkey) -> ProgramResult {
+        match self.get_fee_payer().option() {
+            Some(batch_fee_payer) => {
+                guard!(batch_fee_payer == *fee_payer, ElusivError::InvalidAccount);
+            }
+            None => self.set_fee_payer(&ElusivOption::Some(*fee_payer)),
+        }
+
+        Ok(())
+    }
+
     /// Returns the initial state for the next hash
     /// - hashing order:
     ///     1. commitment sibling hashes on MT-layer `n`: h(c0, c1), h(c2, c3), ..
@@ -213,7 +231,11 @@ impl<'a> CommitmentHashingAccount<'a> {
     }
 
     /// Updates the active MT with all finished hashes and commitments
-    pub fn update_mt(&self, storage_account: &mut StorageAccount, finalization_ix: u32) {
+    pub fn update_mt(
+        &self,
+        storage_account: &mut StorageAccount,
+        finalization_ix: u32,
+    ) -> ProgramResult {
         let batching_rate = self.get_batching_rate();
         let ordering = self.get_ordering();
 
@@ -233,13 +255,11 @@ impl<'a> CommitmentHashingAccount<'a> {
             let ordering = ordering >> (MT_HEIGHT - mt_level);
 
             for i in 0..ht_level_size {
-                storage_account
-                    .set_node(
-                        &self.get_hash_tree(nodes_below + i),
-                        ordering as usize + i,
-                        mt_level,
-                    )
-                    .unwrap();
+                storage_account.try_set_node(
+                    &self.get_hash_tree(nodes_below + i),
+                    ordering as usize + i,
+                    mt_level,
+                )?;
             }
         }
 
@@ -249,9 +269,7 @@ impl<'a> CommitmentHashingAccount<'a> {
                 let mt_layer = MT_HEIGHT - batching_rate as usize - i - 1;
                 let ordering = ordering as usize >> (batching_rate as usize + i + 1);
 
-                storage_account
-                    .set_node(&self.get_above_hashes(i), ordering, mt_layer)
-                    .unwrap();
+                storage_account.try_set_node(&self.get_above_hashes(i), ordering, mt_layer)?;
             }
 
             storage_account.set_next_commitment_ptr(
@@ -259,12 +277,14 @@ impl<'a> CommitmentHashingAccount<'a> {
             );
 
             // This inserts the new root into the `active_mt_root_history`
-            storage_account.set_active_mt_root_history(
+            storage_account.insert_history_root(
                 ordering as usize % HISTORY_ARRAY_SIZE,
-                &storage_account.get_root().unwrap(),
-            );
+                &storage_account.get_root()?,
+            )?;
             storage_account.set_mt_roots_count(&(storage_account.get_mt_roots_count() + 1));
         }
+
+        Ok(())
     }
 }
 
@@ -293,24 +313,30 @@ queue_account!(
 );
 
 impl<'a, 'b> CommitmentQueue<'a, 'b> {
-    /// Returns the next batch of commitments to be hashed together
+    /// Returns the next batch of commitments to be hashed together and its effective batching rate
+    ///
+    /// # Note
+    ///
+    /// Only the consistent prefix of the queue is returned: the batch ends at the first request with a differing `fee_version` or at the end of the queue.
+    /// So the batch can contain less than `commitments_per_batch(batching_rate)` requests (use [`Self::dequeue_batch`] to only take complete batches).
     pub fn next_batch(&self) -> Result<(Vec<CommitmentHashRequest>, u32), ProgramError> {
-        let mut requests = Vec::new();
+        let len = self.len() as usize;
+        let mut requests: Vec<CommitmentHashRequest> = Vec::new();
         let mut highest_batching_rate = 0;
         let mut commitment_count: usize = u32::MAX as usize;
-        let mut fee_version = None;
 
-        while requests.len() < commitment_count {
+        while requests.len() < std::cmp::min(commitment_count, len) {
             let request = self.view(requests.len())?;
 
-            highest_batching_rate = std::cmp::max(highest_batching_rate, request.min_batching_rate);
-            commitment_count = commitments_per_batch(highest_batching_rate);
-
             // Just a (hopefully always) redundant fee-check (depends on the fee upgrade logic)
-            if let Some(f) = fee_version {
-                guard!(f == request.fee_version, ElusivError::InvalidFeeVersion);
+            if let Some(first) = requests.first() {
+                if first.fee_version != request.fee_version {
+                    break;
+                }
             }
-            fee_version = Some(request.fee_version);
+
+            highest_batching_rate = std::cmp::max(highest_batching_rate, request.min_batching_rate);
+            commitment_count = commitments_per_batch(highest_batching_rate);
 
             requests.push(request);
         }
@@ -321,6 +347,38 @@ impl<'a, 'b> CommitmentQueue<'a, 'b> {
 
         Ok((requests, highest_batching_rate))
     }
+
+    /// Dequeues the next complete batch of commitments to be hashed together and returns it with its batching rate
+    pub fn dequeue_batch(&mut self) -> Result<(Vec<CommitmentHashRequest>, u32), ProgramError> {
+        let (batch, batching_rate) = self.next_batch()?;
+
+        if batch.len() < commitments_per_batch(batching_rate) {
+            // Either the batch is cut short by a fee-version boundary or there are not enough requests (yet)
+            if batch.len() < self.len() as usize {
+                return Err(ElusivError::InvalidFeeVersion.into());
+            }
+            return Err(ElusivError::InvalidQueueAccess.into());
+        }
+
+        self.remove(usize_as_u32_safe(batch.len()))?;
+
+        Ok((batch, batching_rate))
+    }
+
+    /// Whether a request for `commitment` is enqueued (scans the whole queue)
+    pub fn contains_commitment(&self, commitment: &U256) -> bool {
+        (0..self.len() as usize).any(
+            |offset| matches!(self.view(offset), Ok(request) if request.commitment == *commitment),
+        )
+    }
+}
+
+/// The effective anonymity set: all inserted commitments and the commitments awaiting their insertion
+pub fn effective_anonymity_set(
+    storage_account: &StorageAccount,
+    commitment_queue: &CommitmentQueue,
+) -> u64 {
+    storage_account.commitment_count() + commitment_queue.len() as u64
 }
 
 #[cfg(test)]
@@ -531,16 +589,14 @@ mod tests {
 
             // Update
             for i in 0..=batching_rate {
-                account.update_mt(&mut storage_account, i);
+                account.update_mt(&mut storage_account, i).unwrap();
             }
 
             // Check commitments
             for index in 0..commitments_count {
                 assert_eq!(
                     u64_to_u256_skip_mr(index as u64),
-                    storage_account
-                        .get_node(previous_commitments_count + index, MT_HEIGHT)
-                        .unwrap()
+                    storage_account.get_node(previous_commitments_count + index, MT_HEIGHT)
                 );
             }
 
@@ -554,9 +610,7 @@ mod tests {
                 for i in 0..layer_size {
                     assert_eq!(
                         u64_to_u256_skip_mr((i + commitments_count + offset) as u64),
-                        storage_account
-                            .get_node(previous_offset + i, mt_level)
-                            .unwrap()
+                        storage_account.get_node(previous_offset + i, mt_level)
                     );
                 }
 
@@ -569,6 +623,7 @@ mod tests {
                 ordering + commitments_count as u32
             );
             assert_eq!(storage_account.get_mt_roots_count(), i as u32 + 1);
+            storage_account.verify_root_index().unwrap();
 
             previous_commitments_count += commitments_count;
         }
@@ -659,6 +714,67 @@ mod tests {
             .unwrap();
     }
 
+    #[test]
+    fn test_effective_anonymity_set() {
+        parent_account!(mut storage, StorageAccount);
+        storage.set_trees_count(&1);
+        storage.set_next_commitment_ptr(&5);
+
+        let mut data = vec![0; <CommitmentQueueAccount as elusiv_types::SizedAccount>::SIZE];
+        let mut q = CommitmentQueueAccount::new(&mut data).unwrap();
+        let mut q = CommitmentQueue::new(&mut q);
+        assert_eq!(
+            effective_anonymity_set(&storage, &q),
+            storage.commitment_count()
+        );
+
+        for i in 0..3 {
+            q.enqueue(CommitmentHashRequest {
+                commitment: [i; 32],
+                fee_version: 0,
+                min_batching_rate: 0,
+                view_tag: 0,
+            })
+            .unwrap();
+        }
+        assert_eq!(
+            effective_anonymity_set(&storage, &q),
+            storage.commitment_count() + 3
+        );
+
+        q.dequeue_first().unwrap();
+        assert_eq!(
+            effective_anonymity_set(&storage, &q),
+            storage.commitment_count() + 2
+        );
+    }
+
+    #[test]
+    fn test_commitment_queue_contains_commitment() {
+        let mut data = vec![0; <CommitmentQueueAccount as elusiv_types::SizedAccount>::SIZE];
+        let mut q = CommitmentQueueAccount::new(&mut data).unwrap();
+        let mut q = CommitmentQueue::new(&mut q);
+        assert!(!q.contains_commitment(&[0; 32]));
+
+        for i in 1..=3 {
+            q.enqueue(CommitmentHashRequest {
+                commitment: [i; 32],
+                fee_version: 0,
+                min_batching_rate: 0,
+                view_tag: 0,
+            })
+            .unwrap();
+        }
+        assert!(q.contains_commitment(&[1; 32]));
+        assert!(q.contains_commitment(&[3; 32]));
+        assert!(!q.contains_commitment(&[4; 32]));
+
+        // Dequeued commitments are not contained
+        q.dequeue_first().unwrap();
+        assert!(!q.contains_commitment(&[1; 32]));
+        assert!(q.contains_commitment(&[2; 32]));
+    }
+
     #[test]
     fn test_commitment_queue_next_batch() {
         let mut data = vec![0; <CommitmentQueueAccount as elusiv_types::SizedAccount>::SIZE];
@@ -671,10 +787,13 @@ mod tests {
                 commitment: [0; 32],
                 fee_version: 0,
                 min_batching_rate: 2,
+                view_tag: 0,
             })
             .unwrap();
         }
-        assert_eq!(q.next_batch(), Err(ElusivError::InvalidQueueAccess.into()));
+        let (batch, batching_rate) = q.next_batch().unwrap();
+        assert_eq!(batch.len(), 3);
+        assert_eq!(batching_rate, 2);
 
         // Complete batches (with variing batching rates)
         q.clear();
@@ -685,6 +804,7 @@ mod tests {
                     commitment: fr_to_u256_le(&u64_to_scalar(i as u64)),
                     fee_version: 0,
                     min_batching_rate: if i == 0 { b as u32 } else { 0 },
+                    view_tag: 0,
                 })
                 .unwrap();
             }
@@ -708,14 +828,84 @@ mod tests {
             commitment: [0; 32],
             fee_version: 0,
             min_batching_rate: 1,
+            view_tag: 0,
         })
         .unwrap();
         q.enqueue(CommitmentHashRequest {
             commitment: [0; 32],
             fee_version: 1,
             min_batching_rate: 1,
+            view_tag: 0,
         })
         .unwrap();
-        assert_eq!(q.next_batch(), Err(ElusivError::InvalidFeeVersion.into()));
+        let (batch, batching_rate) = q.next_batch().unwrap();
+        assert_eq!(batch.len(), 1);
+        assert_eq!(batch[0].fee_version, 0);
+        assert_eq!(batching_rate, 1);
+
+        q.clear();
+        assert_eq!(q.next_batch(), Err(ElusivError::QueueIsEmpty.into()));
+    }
+
+    #[test]
+    fn test_commitment_queue_dequeue_batch() {
+        let mut data = vec![0; <CommitmentQueueAccount as elusiv_types::SizedAccount>::SIZE];
+        let mut q = CommitmentQueueAccount::new(&mut data).unwrap();
+        let mut q = CommitmentQueue::new(&mut q);
+
+        let request =
+            |commitment: u8, fee_version: u32, min_batching_rate: u32| CommitmentHashRequest {
+                commitment: [commitment; 32],
+                fee_version,
+                min_batching_rate,
+                view_tag: 0,
+            };
+
+        assert_eq!(q.dequeue_batch(), Err(ElusivError::QueueIsEmpty.into()));
+
+        // Batch of two (rate 1) with fee-version 0, followed by a batch of one (rate 0) with fee-version 1
+        q.enqueue(request(0, 0, 1)).unwrap();
+        q.enqueue(request(1, 0, 0)).unwrap();
+        q.enqueue(request(2, 1, 0)).unwrap();
+
+        // Incomplete batch of four (rate 2), cut short by the fee-version boundary
+        q.enqueue(request(3, 1, 2)).unwrap();
+        q.enqueue(request(4, 1, 0)).unwrap();
+        q.enqueue(request(5, 2, 0)).unwrap();
+
+        let (batch, batching_rate) = q.dequeue_batch().unwrap();
+        assert_eq!(batching_rate, 1);
+        assert_eq!(batch, vec![request(0, 0, 1), request(1, 0, 0)]);
+        assert_eq!(q.len(), 4);
+
+        let (batch, batching_rate) = q.dequeue_batch().unwrap();
+        assert_eq!(batching_rate, 0);
+        assert_eq!(batch, vec![request(2, 1, 0)]);
+
+        let (batch, batching_rate) = q.next_batch().unwrap();
+        assert_eq!(batching_rate, 2);
+        assert_eq!(batch, vec![request(3, 1, 2), request(4, 1, 0)]);
+        assert_eq!(
+            q.dequeue_batch(),
+            Err(ElusivError::InvalidFeeVersion.into())
+        );
+        assert_eq!(q.len(), 3);
+
+        // Incomplete batch at the end of the queue
+        q.clear();
+        q.enqueue(request(3, 1, 2)).unwrap();
+        q.enqueue(request(4, 1, 0)).unwrap();
+        q.enqueue(request(5, 1, 0)).unwrap();
+        assert_eq!(
+            q.dequeue_batch(),
+            Err(ElusivError::InvalidQueueAccess.into())
+        );
+        assert_eq!(q.len(), 3);
+
+        q.enqueue(request(6, 1, 0)).unwrap();
+        let (batch, batching_rate) = q.dequeue_batch().unwrap();
+        assert_eq!(batching_rate, 2);
+        assert_eq!(batch.len(), 4);
+        assert_eq!(q.len(), 0);
     }
 }
@@ -0,0 +1,29 @@
+use super::program_account::PDAAccountData;
+use crate::bytes::ElusivOption;
+use crate::macros::elusiv_account;
+use solana_program::pubkey::Pubkey;
+
+/// Flags forcing program-side failures (used by Wardens for testing their retry logic)
+///
+/// # Note
+///
+/// - Only available in `devnet-tools` builds (which are not allowed on mainnet).
+/// - The flags are only consulted by instructions that receive this account as an additional (trailing) account.
+#[elusiv_account(eager_type: true)]
+pub struct FailureInjectionAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    /// The only signer allowed to change the flags
+    pub authority: Pubkey,
+
+    /// Commitment-enqueuing instructions fail with [`crate::error::ElusivError::QueueIsFull`]
+    pub force_queue_full: bool,
+
+    /// Price-loading instructions fail with [`crate::token::TokenError::PriceError`]
+    pub force_price_stale: bool,
+
+    /// Proof-verification computations fail with [`crate::error::ElusivError::PartialComputationError`] at the supplied round
+    pub force_compute_error_at_round: ElusivOption<u32>,
+}
@@ -6,7 +6,10 @@ use crate::commitment::{
 };
 use crate::macros::elusiv_account;
 use crate::proof::verifier::{CombinedMillerLoop, FinalExponentiation};
-use crate::token::{Lamports, Token, TokenError, TokenPrice};
+use crate::token::{
+    rounding::{self, div_rounded, Rounding},
+    Lamports, Token, TokenError, TokenPrice,
+};
 use borsh::{BorshDeserialize, BorshSerialize};
 use elusiv_computation::PartialComputation;
 use elusiv_derive::BorshSerDeSized;
@@ -16,8 +19,10 @@ use elusiv_derive::BorshSerDeSized;
 pub struct BasisPointFee(pub u64);
 
 impl BasisPointFee {
-    pub fn calc(&self, amount: u64) -> u64 {
-        self.0 * amount / 10_000
+    pub fn calc(&self, amount: u64, rounding: Rounding) -> u64 {
+        div_rounded(self.0 as u128 * amount as u128, 10_000, rounding)
+            .and_then(|fee| fee.try_into().ok())
+            .unwrap_or(u64::MAX)
     }
 }
 
@@ -134,6 +139,17 @@ impl ProgramFee {
         ))
     }
 
+    /// Estimated number of commitment hash transactions that have to be processed before the commitment at `queue_position` is hashed
+    ///
+    /// # Notes
+    ///
+    /// Assumes all commitments ahead are hashed in batches of `min_batching_rate`.
+    pub fn commitment_hash_tx_count_ahead(queue_position: u32, min_batching_rate: u32) -> u64 {
+        let batches_ahead =
+            u64::from(queue_position) / commitments_per_batch(min_batching_rate) as u64;
+        batches_ahead * commitment_hash_computation_instructions(min_batching_rate).len() as u64
+    }
+
     pub fn proof_verification_computation_fee(
         &self,
         input_preparation_tx_count: usize,
@@ -155,12 +171,14 @@ impl ProgramFee {
     ) -> Result<Token, TokenError> {
         let proof_verification_fee = self
             .proof_verification_computation_fee(input_preparation_tx_count)
-            .into_token(price, token_id)?;
+            .into_token(price, token_id, rounding::FEE)?;
         let commitment_hash_fee = self
             .commitment_hash_computation_fee(min_batching_rate)
-            .into_token(price, token_id)?;
-        let network_fee = Token::new(token_id, self.proof_network_fee.calc(amount));
-        let subvention = self.proof_subvention.into_token(price, token_id)?;
+            .into_token(price, token_id, rounding::FEE)?;
+        let network_fee = Token::new(token_id, self.proof_network_fee.calc(amount, rounding::FEE));
+        let subvention = self
+            .proof_subvention
+            .into_token(price, token_id, rounding::PAYOUT)?;
 
         ((proof_verification_fee + commitment_hash_fee)? + network_fee)? - subvention
     }
@@ -0,0 +1,121 @@
+use crate::error::ElusivError;
+use crate::macros::{elusiv_account, guard, pda_account};
+use crate::processor::{ZERO_BASE_COMMITMENT_RAW, ZERO_COMMITMENT_RAW};
+use crate::types::U256;
+use elusiv_types::PDAAccountData;
+use solana_program::{account_info::AccountInfo, program_error::ProgramError};
+
+/// Parameters depending on the Poseidon parameters, written once at program initialization
+///
+/// # Note
+///
+/// As long as the account does not exist, the compiled defaults ([`GenesisParams::default`]) are used.
+#[elusiv_account(eager_type: true)]
+pub struct GenesisParamsAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    /// poseidon(poseidon(0, 0), 0)
+    pub zero_commitment: U256,
+
+    /// poseidon(0, 0), the base-commitment of the zero-nullifier
+    pub zero_base_commitment: U256,
+
+    pub is_setup: bool,
+}
+
+/// The genesis parameters (not in mr-form)
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct GenesisParams {
+    pub zero_commitment: U256,
+    pub zero_base_commitment: U256,
+}
+
+impl Default for GenesisParams {
+    fn default() -> Self {
+        GenesisParams {
+            zero_commitment: ZERO_COMMITMENT_RAW,
+            zero_base_commitment: ZERO_BASE_COMMITMENT_RAW,
+        }
+    }
+}
+
+impl GenesisParams {
+    /// Reads the [`GenesisParams`] from a (verified) [`GenesisParamsAccount`] or falls back to the defaults if it does not exist
+    pub fn from_account(genesis_params_account: &AccountInfo) -> Result<Self, ProgramError> {
+        if genesis_params_account.data_is_empty() {
+            return Ok(Self::default());
+        }
+
+        guard!(
+            *genesis_params_account.owner == crate::ID,
+            ElusivError::InvalidAccount
+        );
+
+        pda_account!(genesis_params, GenesisParamsAccount, genesis_params_account);
+        guard!(
+            genesis_params.get_is_setup(),
+            ElusivError::InvalidAccountState
+        );
+
+        Ok(GenesisParams {
+            zero_commitment: genesis_params.get_zero_commitment(),
+            zero_base_commitment: genesis_params.get_zero_base_commitment(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::account_info;
+    use crate::state::program_account::{PDAAccount, ProgramAccount, SizedAccount};
+    use solana_program::pubkey::Pubkey;
+
+    #[test]
+    fn test_genesis_params_default() {
+        let pk = GenesisParamsAccount::find(None).0;
+
+        // Absent account
+        account_info!(account, pk, vec![]);
+        assert_eq!(
+            GenesisParams::from_account(&account),
+            Ok(GenesisParams::default())
+        );
+    }
+
+    #[test]
+    fn test_genesis_params_from_account() {
+        let pk = GenesisParamsAccount::find(None).0;
+        let params = GenesisParams {
+            zero_commitment: [1; 32],
+            zero_base_commitment: [2; 32],
+        };
+
+        let mut data = vec![0; GenesisParamsAccount::SIZE];
+        let mut genesis_params = GenesisParamsAccount::new(&mut data).unwrap();
+        genesis_params.set_zero_commitment(&params.zero_commitment);
+        genesis_params.set_zero_base_commitment(&params.zero_base_commitment);
+
+        // Not setup
+        account_info!(account, pk, data.clone(), crate::ID, false);
+        assert_eq!(
+            GenesisParams::from_account(&account),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+
+        let mut genesis_params = GenesisParamsAccount::new(&mut data).unwrap();
+        genesis_params.set_is_setup(&true);
+
+        // Invalid owner
+        account_info!(account, pk, data.clone(), Pubkey::new_unique(), false);
+        assert_eq!(
+            GenesisParams::from_account(&account),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        account_info!(account, pk, data, crate::ID, false);
+        assert_eq!(GenesisParams::from_account(&account), Ok(params));
+    }
+}
@@ -1,5 +1,25 @@
 use super::{fee::ProgramFee, program_account::PDAAccountData};
-use crate::macros::elusiv_account;
+use crate::bytes::ElusivOption;
+use crate::error::ElusivError;
+use crate::macros::{elusiv_account, guard, BorshSerDeSized};
+use borsh::{BorshDeserialize, BorshSerialize};
+use elusiv_utils::MATH_ERR;
+use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+
+/// The maximum number of memo program ids accepted by finalize transactions
+pub const MAX_MEMO_PROGRAM_IDS: usize = 4;
+
+/// The default number of low bits ignored when comparing an asserted timestamp with the cluster timestamp
+pub const DEFAULT_TIMESTAMP_PRUNING_BITS: u8 = 5;
+
+/// The maximum number of low bits ignored when comparing an asserted timestamp with the cluster timestamp
+pub const MAX_TIMESTAMP_PRUNING_BITS: u8 = 32;
+
+/// The SPL memo program (v2)
+pub const SPL_MEMO_PROGRAM_ID: Pubkey = Pubkey::new_from_array([
+    5, 74, 83, 90, 153, 41, 33, 6, 77, 36, 232, 113, 96, 218, 56, 124, 124, 53, 181, 221, 188, 146,
+    187, 129, 228, 31, 168, 64, 65, 5, 68, 141,
+]);
 
 #[elusiv_account(eager_type: true)]
 pub struct GovernorAccount {
@@ -17,6 +37,140 @@ pub struct GovernorAccount {
     pub commitment_batching_rate: u32,
 
     program_version: u32,
+
+    /// Allows fee-payer token-accounts that are not associated-token-accounts (only for the transition of legacy accounts)
+    pub allow_legacy_fee_payer_token_account: bool,
+
+    /// Address-lookup-table containing the common static accounts used by verification and finalize transactions
+    pub common_lut: ElusivOption<Pubkey>,
+
+    /// The maximum number of additional verifications (`skip_nullifier_pda`) of the same nullifier-hashes
+    pub max_duplicate_verifications: u8,
+
+    /// Receives the rent of closed verification PDAs (instead of the fee-payer or fee-collector)
+    pub rent_recipient: ElusivOption<Pubkey>,
+
+    /// The number of slots after the last use of a [`crate::state::proof::NullifierDuplicateAccount`] before it can be reused (using `skip_nullifier_pda`)
+    pub duplicate_grace_period: u64,
+
+    /// Lamports escrowed by the fee-payer for each verification (refunded on success, forfeited to the fee-collector on failure)
+    pub failure_bond: u64,
+
+    /// Inclusive minimum of a verification-timeout (in seconds) chosen by a fee-payer
+    pub min_verification_timeout: u64,
+
+    /// Inclusive maximum of a verification-timeout (in seconds) chosen by a fee-payer
+    pub max_verification_timeout: u64,
+
+    /// Bitmask of the paused [`Category`]s
+    pub paused_categories: u32,
+
+    /// Requires the SPL memo program to be invoked exactly once in a finalize transaction that uses a memo
+    pub enforce_single_memo: bool,
+
+    /// The memo program ids accepted in finalize transactions (see [`GovernorAccount::memo_program_ids`])
+    pub memo_program_ids: [ElusivOption<Pubkey>; MAX_MEMO_PROGRAM_IDS],
+    /// Requires lamports recipients to be system-owned (unless the sender flags the recipient with `recipient_is_program_owned`)
+    pub enforce_system_owned_lamports_recipient: bool,
+
+    /// The number of low bits ignored by [`crate::processor::is_timestamp_valid`] (see [`GovernorAccount::timestamp_pruning_bits`])
+    pub timestamp_pruning_bits: ElusivOption<u8>,
+}
+
+/// Instruction handler groups that can be paused separately by the governor
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Category {
+    Deposit,
+    InitVerification,
+    Compute,
+    Finalize,
+    VKeyAdmin,
+
+    /// Can never be paused (so that pausing is always reversible)
+    Governance,
+}
+
+impl Category {
+    pub const ALL: [Category; 6] = [
+        Category::Deposit,
+        Category::InitVerification,
+        Category::Compute,
+        Category::Finalize,
+        Category::VKeyAdmin,
+        Category::Governance,
+    ];
+
+    /// The bit of the category in [`GovernorAccount::paused_categories`]
+    pub const fn bit(self) -> u32 {
+        1 << self as u32
+    }
+
+    pub const fn is_pausable(self) -> bool {
+        !matches!(self, Category::Governance)
+    }
+}
+
+/// Bitmask of all pausable [`Category`]s
+pub const PAUSABLE_CATEGORIES: u32 = Category::Deposit.bit()
+    | Category::InitVerification.bit()
+    | Category::Compute.bit()
+    | Category::Finalize.bit()
+    | Category::VKeyAdmin.bit();
+
+impl<'a> GovernorAccount<'a> {
+    pub fn is_paused(&self, category: Category) -> bool {
+        category.is_pausable() && self.get_paused_categories() & category.bit() != 0
+    }
+
+    /// Returns the allow-listed memo program ids
+    ///
+    /// # Note
+    ///
+    /// Without any allow-listed id (e.g. for a governor opened before the allow-list existed), only [`SPL_MEMO_PROGRAM_ID`] is accepted.
+    pub fn memo_program_ids(&self) -> Vec<Pubkey> {
+        let memo_program_ids: Vec<Pubkey> = (0..MAX_MEMO_PROGRAM_IDS)
+            .filter_map(|i| self.get_memo_program_ids(i).option())
+            .collect();
+
+        if memo_program_ids.is_empty() {
+            vec![SPL_MEMO_PROGRAM_ID]
+        } else {
+            memo_program_ids
+        }
+    }
+
+    /// Returns the number of low bits ignored when validating asserted timestamps
+    ///
+    /// # Note
+    ///
+    /// Without a configured value (e.g. for a governor opened before it existed), [`DEFAULT_TIMESTAMP_PRUNING_BITS`] is used.
+    pub fn timestamp_pruning_bits(&self) -> u8 {
+        self.get_timestamp_pruning_bits()
+            .option()
+            .unwrap_or(DEFAULT_TIMESTAMP_PRUNING_BITS)
+    }
+
+    /// Fails with [`ElusivError::CategoryPaused`] if the handlers of `category` are paused
+    pub fn check_category(&self, category: Category) -> ProgramResult {
+        guard!(!self.is_paused(category), ElusivError::CategoryPaused);
+        Ok(())
+    }
+}
+
+/// The [`GovernorAccount`] values required by clients to build a new request
+#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Clone, Debug)]
+pub struct GovernorHead {
+    pub fee_version: u32,
+    pub commitment_batching_rate: u32,
+}
+
+impl GovernorHead {
+    pub fn new(governor: &GovernorAccount) -> Self {
+        GovernorHead {
+            fee_version: governor.get_fee_version(),
+            commitment_batching_rate: governor.get_commitment_batching_rate(),
+        }
+    }
 }
 
 #[elusiv_account(eager_type: true)]
@@ -32,3 +186,67 @@ pub struct FeeCollectorAccount {
     #[no_setter]
     pda_data: PDAAccountData,
 }
+
+/// The cumulative network fees (of a single token) transferred to the fee-collector by successful finalizations
+///
+/// # Note
+///
+/// PDA with the token-id as offset (kept out of the [`GovernorAccount`], so that finalizations don't write-lock the governor).
+#[elusiv_account(eager_type: true)]
+pub struct NetworkFeeAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    pub collected_network_fee: u64,
+}
+
+impl<'a> NetworkFeeAccount<'a> {
+    pub fn add_collected_network_fee(&mut self, network_fee: u64) -> ProgramResult {
+        let collected = self
+            .get_collected_network_fee()
+            .checked_add(network_fee)
+            .ok_or(MATH_ERR)?;
+        self.set_collected_network_fee(&collected);
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "debug-display"))]
+mod tests {
+    use super::*;
+    use crate::state::fee::BasisPointFee;
+    use crate::token::Lamports;
+    use elusiv_types::{ProgramAccount, SizedAccount};
+
+    #[test]
+    fn test_dump_governor_account() {
+        let mut data = vec![0; GovernorAccount::SIZE];
+        let mut governor = GovernorAccount::new(&mut data).unwrap();
+        governor.set_fee_version(&3);
+        governor.set_program_fee(&ProgramFee {
+            lamports_per_tx: Lamports(1_500_000_001),
+            base_commitment_network_fee: BasisPointFee(11),
+            proof_network_fee: BasisPointFee(100),
+            base_commitment_subvention: Lamports(0),
+            proof_subvention: Lamports(0),
+            warden_hash_tx_reward: Lamports(0),
+            warden_proof_reward: Lamports(0),
+            proof_base_tx_count: 0,
+        });
+        governor.set_failure_bond(&987654321);
+
+        let dump = dump_governor_account(&data);
+        assert!(dump.starts_with("GovernorAccount {"));
+        assert!(dump.contains("fee_version: 3,"));
+        assert!(dump.contains("lamports_per_tx: 1.500000001 SOL,"));
+        assert!(dump.contains("proof_subvention: 0.000000000 SOL,"));
+        assert!(dump.contains("987654321"));
+        assert!(dump.contains("rent_recipient:"));
+
+        assert_eq!(
+            dump_governor_account(&data[1..]),
+            format!("Invalid GovernorAccount data ({} bytes)", data.len() - 1)
+        );
+    }
+}
@@ -1,11 +1,16 @@
-use super::{commitment::COMMITMENT_QUEUE_LEN, queue::queue_account};
+use super::{
+    commitment::COMMITMENT_QUEUE_LEN, program_account::uniform_child_account_and_local_index,
+    queue::queue_account,
+};
 use crate::commitment::MT_HEIGHT;
+use crate::error::ElusivError;
+use crate::macros::guard;
 use elusiv_proc_macros::elusiv_account;
 use elusiv_types::{
     accounts::PDAAccountData, BorshSerDeSized, ChildAccount, ElusivOption, ParentAccount,
 };
 use elusiv_utils::two_pow;
-use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+use solana_program::{entrypoint::ProgramResult, program_error::ProgramError, pubkey::Pubkey};
 
 pub type CommitmentMetadata = [u8; 17];
 
@@ -41,7 +46,7 @@ pub struct MetadataAccount {
 impl<'a, 'b, 't> MetadataAccount<'a, 'b, 't> {
     pub fn add_commitment_metadata(&mut self, metadata: &CommitmentMetadata) -> ProgramResult {
         let metadata_index = self.get_next_metadata_ptr() as usize;
-        let (child_index, index) = Self::child_account_and_local_index(metadata_index);
+        let (child_index, index) = Self::child_account_and_local_index(metadata_index)?;
 
         self.execute_on_child_account_mut(child_index, |data| {
             let offset = index * CommitmentMetadata::SIZE;
@@ -58,13 +63,11 @@ impl<'a, 'b, 't> MetadataAccount<'a, 'b, 't> {
     pub fn get_commitment_metadata(
         &self,
         index: usize,
-    ) -> Result<CommitmentMetadata, solana_program::program_error::ProgramError> {
-        use crate::error::ElusivError;
-
+    ) -> Result<CommitmentMetadata, ProgramError> {
         let metadata_index = self.get_next_metadata_ptr() as usize;
-        crate::macros::guard!(index < metadata_index, ElusivError::MissingValue);
+        guard!(index < metadata_index, ElusivError::MissingValue);
 
-        let (child_index, index) = Self::child_account_and_local_index(index);
+        let (child_index, index) = Self::child_account_and_local_index(index)?;
 
         self.execute_on_child_account(child_index, |data| {
             let offset = index * CommitmentMetadata::SIZE;
@@ -74,11 +77,18 @@ impl<'a, 'b, 't> MetadataAccount<'a, 'b, 't> {
         })
     }
 
-    fn child_account_and_local_index(metadata_index: usize) -> (usize, usize) {
-        let child_index = metadata_index / VALUES_PER_METADATA_CHILD_ACCOUNT;
-        let index = metadata_index % VALUES_PER_METADATA_CHILD_ACCOUNT;
-
-        (child_index, index)
+    fn child_account_and_local_index(
+        metadata_index: usize,
+    ) -> Result<(usize, usize), ProgramError> {
+        guard!(
+            metadata_index < ACCOUNTS_COUNT * VALUES_PER_METADATA_CHILD_ACCOUNT,
+            ElusivError::IndexOutOfBounds
+        );
+
+        Ok(uniform_child_account_and_local_index(
+            metadata_index,
+            VALUES_PER_METADATA_CHILD_ACCOUNT,
+        ))
     }
 }
 
@@ -112,4 +122,18 @@ mod test {
             );
         }
     }
+
+    #[test]
+    fn test_child_account_and_local_index() {
+        let values_count = ACCOUNTS_COUNT * VALUES_PER_METADATA_CHILD_ACCOUNT;
+
+        assert_eq!(
+            MetadataAccount::child_account_and_local_index(values_count - 1),
+            Ok((ACCOUNTS_COUNT - 1, VALUES_PER_METADATA_CHILD_ACCOUNT - 1))
+        );
+        assert_eq!(
+            MetadataAccount::child_account_and_local_index(values_count),
+            Err(ElusivError::IndexOutOfBounds.into())
+        );
+    }
 }
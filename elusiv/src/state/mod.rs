@@ -1,10 +1,17 @@
 pub mod commitment;
+#[cfg(feature = "devnet-tools")]
+pub mod failure_injection;
 pub mod fee;
+pub mod genesis;
 pub mod governor;
 pub mod metadata;
 pub mod nullifier;
+pub mod owner_rotation;
 pub mod program_account;
 pub mod proof;
 pub mod queue;
 pub mod storage;
+#[cfg(feature = "devnet-tools")]
+pub mod test_token;
+pub mod token_registry;
 pub mod vkey;
@@ -3,7 +3,7 @@ use super::storage::MT_HEIGHT;
 use crate::bytes::*;
 use crate::error::ElusivError;
 use crate::macros::{elusiv_account, guard, two_pow};
-use crate::map::ElusivSet;
+use crate::map::{ElusivMapPtr, ElusivSet};
 use crate::types::{OrdU256, JOIN_SPLIT_MAX_N_ARITY, U256};
 use elusiv_types::{ChildAccount, ParentAccount};
 use solana_program::entrypoint::ProgramResult;
@@ -48,6 +48,9 @@ pub struct NullifierAccount {
     moved_values_count: u8,
     moved_values: [U256; JOIN_SPLIT_MAX_N_ARITY],
     moved_values_target: [u8; JOIN_SPLIT_MAX_N_ARITY],
+
+    /// Bitmap of the child-accounts verified by [`crate::processor::warm_tree_accounts`]
+    verified_children: u64,
 }
 
 /// Tree account after archiving (only a single collapsed N-SMT root)
@@ -61,12 +64,32 @@ pub struct ArchivedNullifierAccount {
     nullifier_root: U256,
 }
 
+/// Tracks the progress of merging the nullifier-hashes of a closed MT into another closed MT
+///
+/// # Note
+///
+/// The account is bound to the source MT (`pda_offset = source_mt_index`), so each MT can only be merged once.
+#[elusiv_account]
+pub struct NullifierMergeAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    pub destination_mt_index: u32,
+    pub is_finished: bool,
+
+    /// Position of the next nullifier-hash in the source MT (child-account index and slot in its [`NullifierMap`])
+    pub child_account_index: u32,
+    pub map_index: u32,
+    pub merged_count: u32,
+}
+
 impl<'a, 'b, 'c> NullifierAccount<'a, 'b, 'c> {
     pub fn can_insert_nullifier_hash(&self, nullifier_hash: U256) -> Result<bool, ProgramError> {
         let count = self.get_nullifier_hash_count();
         guard!(
             (count as usize) < NULLIFIERS_COUNT,
-            ElusivError::CouldNotInsertNullifier
+            ElusivError::NullifierTreeFull
         );
 
         let account_index = self.find_child_account_index(&nullifier_hash);
@@ -88,11 +111,62 @@ impl<'a, 'b, 'c> NullifierAccount<'a, 'b, 'c> {
         Ok(!contains)
     }
 
+    /// Batched [`Self::can_insert_nullifier_hash`] that accesses each child-account at most once
+    ///
+    /// # Note
+    ///
+    /// A nullifier-hash that is equal to a previous entry of `nullifier_hashes` cannot be inserted.
+    pub fn can_insert_nullifier_hashes(
+        &self,
+        nullifier_hashes: &[U256],
+    ) -> Result<Vec<bool>, ProgramError> {
+        let count = self.get_nullifier_hash_count();
+        guard!(
+            (count as usize) < NULLIFIERS_COUNT,
+            ElusivError::NullifierTreeFull
+        );
+
+        let moved_values = self.get_all_moved_values();
+        let mut result: Vec<bool> = nullifier_hashes
+            .iter()
+            .enumerate()
+            .map(|(i, nullifier_hash)| {
+                !nullifier_hashes[..i].contains(nullifier_hash)
+                    && !moved_values
+                        .iter()
+                        .any(|(value, _)| *value == OrdU256(*nullifier_hash))
+            })
+            .collect();
+
+        // Group the remaining nullifier-hashes by their child-account
+        let mut candidates: Vec<(usize, usize)> = nullifier_hashes
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| result[*i])
+            .map(|(i, nullifier_hash)| (self.find_child_account_index(nullifier_hash), i))
+            .collect();
+        candidates.sort_unstable();
+
+        let mut account_indices: Vec<usize> = candidates.iter().map(|(a, _)| *a).collect();
+        account_indices.dedup();
+
+        for account_index in account_indices {
+            self.execute_on_child_account_mut(account_index, |data| {
+                let mut map = NullifierMap::new(data);
+                for (_, i) in candidates.iter().filter(|(a, _)| *a == account_index) {
+                    result[*i] = map.contains(&OrdU256(nullifier_hashes[*i])).is_none();
+                }
+            })?;
+        }
+
+        Ok(result)
+    }
+
     pub fn try_insert_nullifier_hash(&mut self, nullifier_hash: U256) -> ProgramResult {
         let count = self.get_nullifier_hash_count();
         guard!(
             (count as usize) < NULLIFIERS_COUNT,
-            ElusivError::CouldNotInsertNullifier
+            ElusivError::NullifierTreeFull
         );
 
         let account_index = self.find_child_account_index(&nullifier_hash);
@@ -214,6 +288,27 @@ impl<'a, 'b, 'c> NullifierAccount<'a, 'b, 'c> {
         }
     }
 
+    /// Returns the number of nullifier-hashes stored in the child-account at `child_index`
+    pub fn child_nullifier_hash_count(&self, child_index: usize) -> Result<u32, ProgramError> {
+        self.execute_on_child_account_mut(child_index, |data| NullifierMap::new(data).len())
+    }
+
+    /// Returns the nullifier-hash stored at `map_index` (insertion slot, not the sorted position) in the child-account at `child_index`
+    pub fn nullifier_hash_at(
+        &self,
+        child_index: usize,
+        map_index: u32,
+    ) -> Result<U256, ProgramError> {
+        let map_index: u16 = map_index
+            .try_into()
+            .map_err(|_| ElusivError::IndexOutOfBounds)?;
+        guard!(child_index < Self::COUNT, ElusivError::IndexOutOfBounds);
+
+        self.execute_on_child_account_mut(child_index, |data| {
+            NullifierMap::new(data).key(&ElusivMapPtr(map_index)).0
+        })
+    }
+
     pub fn is_moved_nullifier_empty(&self) -> bool {
         self.get_moved_values_count() == 0
     }
@@ -283,6 +378,63 @@ mod tests {
         assert!(nullifier_account.can_insert_nullifier_hash(c).unwrap());
     }
 
+    #[test]
+    fn test_can_insert_nullifier_hashes() {
+        parent_account!(mut nullifier_account, NullifierAccount);
+
+        assert_eq!(
+            nullifier_account.can_insert_nullifier_hashes(&[]),
+            Ok(Vec::new())
+        );
+
+        let a = [0; 32];
+        let b = [1; 32];
+        let c = [2; 32];
+        let d = [3; 32];
+        nullifier_account.try_insert_nullifier_hash(a).unwrap();
+        nullifier_account.set_all_moved_values(&[(OrdU256(b), 0)]);
+
+        // `c` collides with itself inside of the input slice
+        assert_eq!(
+            nullifier_account.can_insert_nullifier_hashes(&[a, b, c, d, c]),
+            Ok(vec![false, false, true, true, false])
+        );
+
+        for hash in [a, b, c, d] {
+            assert_eq!(
+                nullifier_account.can_insert_nullifier_hashes(&[hash]),
+                Ok(vec![nullifier_account
+                    .can_insert_nullifier_hash(hash)
+                    .unwrap()])
+            );
+        }
+    }
+
+    #[test]
+    fn test_can_insert_nullifier_hashes_multiple_child_accounts() {
+        parent_account!(mut nullifier_account, NullifierAccount);
+
+        for i in 0..NULLIFIERS_PER_ACCOUNT as u64 + 1 {
+            nullifier_account
+                .try_insert_nullifier_hash(u64_to_u256_skip_mr(i * 2))
+                .unwrap();
+        }
+
+        // The hashes are spread over the first two child-accounts
+        let hashes: Vec<U256> = (0..6)
+            .map(|i| u64_to_u256_skip_mr(NULLIFIERS_PER_ACCOUNT as u64 * 2 - 3 + i))
+            .collect();
+        let expected: Vec<bool> = hashes
+            .iter()
+            .map(|hash| nullifier_account.can_insert_nullifier_hash(*hash).unwrap())
+            .collect();
+        assert_eq!(expected, vec![true, false, true, false, true, true]);
+        assert_eq!(
+            nullifier_account.can_insert_nullifier_hashes(&hashes),
+            Ok(expected)
+        );
+    }
+
     #[test]
     fn test_can_insert_nullifier_hash_moved_values() {
         parent_account!(mut nullifier_account, NullifierAccount);
@@ -324,6 +476,47 @@ mod tests {
 
         assert_eq!(
             nullifier_account.try_insert_nullifier_hash(u256_from_str("1")),
+            Err(ElusivError::NullifierTreeFull.into())
+        );
+    }
+
+    #[test]
+    fn test_nullifier_tree_full() {
+        parent_account!(mut nullifier_account, NullifierAccount);
+
+        nullifier_account
+            .try_insert_nullifier_hash(u64_to_u256_skip_mr(100))
+            .unwrap();
+
+        // Only room for two more nullifier-hashes
+        nullifier_account.set_nullifier_hash_count(&(NULLIFIERS_COUNT as u32 - 2));
+        for i in 0..2 {
+            nullifier_account
+                .try_insert_nullifier_hash(u64_to_u256_skip_mr(i))
+                .unwrap();
+        }
+        assert_eq!(
+            nullifier_account.get_nullifier_hash_count() as usize,
+            NULLIFIERS_COUNT
+        );
+
+        // New and duplicate values both fail with the full-specific error
+        for v in [u64_to_u256_skip_mr(2), u64_to_u256_skip_mr(0)] {
+            assert_eq!(
+                nullifier_account.can_insert_nullifier_hash(v),
+                Err(ElusivError::NullifierTreeFull.into())
+            );
+            assert_eq!(
+                nullifier_account.try_insert_nullifier_hash(v),
+                Err(ElusivError::NullifierTreeFull.into())
+            );
+        }
+
+        // A duplicate in a tree with remaining capacity is not reported as full
+        nullifier_account.set_nullifier_hash_count(&(NULLIFIERS_COUNT as u32 - 1));
+        nullifier_account.set_all_moved_values(&[(OrdU256(u64_to_u256_skip_mr(3)), 0)]);
+        assert_eq!(
+            nullifier_account.try_insert_nullifier_hash(u64_to_u256_skip_mr(3)),
             Err(ElusivError::CouldNotInsertNullifier.into())
         );
     }
@@ -414,7 +607,7 @@ mod tests {
 
         assert_eq!(
             nullifier_account.try_insert_nullifier_hash(u64_to_u256_skip_mr(count)),
-            Err(ElusivError::CouldNotInsertNullifier.into())
+            Err(ElusivError::NullifierTreeFull.into())
         );
     }
 
@@ -0,0 +1,22 @@
+use super::program_account::PDAAccountData;
+use crate::macros::elusiv_account;
+use crate::types::U256;
+
+/// Published by the owner of an identifier to rotate the key used for encrypting future `encrypted_owner`s
+///
+/// # Note
+///
+/// PDA with the identifier as associated pubkey, past `encrypted_owner`s remain unaffected.
+#[elusiv_account(eager_type: true)]
+pub struct OwnerRotationAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    /// The encryption pubkey for all commitments after `rotation_slot`
+    pub encryption_pubkey: U256,
+    pub rotation_slot: u64,
+
+    /// Number of rotations (zero for a freshly opened account)
+    pub rotation_count: u32,
+}
@@ -1,4 +1,44 @@
+use crate::error::ElusivError;
 pub use elusiv_types::accounts::*;
+use solana_program::program_error::ProgramError;
+
+/// Maps a flat value-`index` to its child-account index and the local index inside of that child-account
+///
+/// # Note
+///
+/// Every child-account stores exactly `values_per_account` values.
+pub const fn uniform_child_account_and_local_index(
+    index: usize,
+    values_per_account: usize,
+) -> (usize, usize) {
+    (index / values_per_account, index % values_per_account)
+}
+
+/// Maps a flat value-`index` to its child-account index and the local index inside of that child-account
+///
+/// # Note
+///
+/// `values_per_account(i)` returns the amount of values stored in the `i`-th child-account,
+/// which allows child-accounts to have differing capacities (e.g. a first account that also stores header fields).
+pub fn child_account_and_local_index<F>(
+    index: usize,
+    child_account_count: usize,
+    values_per_account: F,
+) -> Result<(usize, usize), ProgramError>
+where
+    F: Fn(usize) -> usize,
+{
+    let mut local_index = index;
+    for account_index in 0..child_account_count {
+        let size = values_per_account(account_index);
+        if local_index < size {
+            return Ok((account_index, local_index));
+        }
+        local_index -= size;
+    }
+
+    Err(ElusivError::IndexOutOfBounds.into())
+}
 
 #[cfg(test)]
 mod tests {
@@ -55,12 +95,67 @@ mod tests {
         assert_eq!(inner_data.len(), TestChildAccount::INNER_SIZE);
     }
 
+    #[test]
+    fn test_uniform_child_account_and_local_index() {
+        assert_eq!(uniform_child_account_and_local_index(0, 10), (0, 0));
+        assert_eq!(uniform_child_account_and_local_index(9, 10), (0, 9));
+        assert_eq!(uniform_child_account_and_local_index(10, 10), (1, 0));
+        assert_eq!(uniform_child_account_and_local_index(25, 10), (2, 5));
+
+        for index in 0..30 {
+            assert_eq!(
+                child_account_and_local_index(index, 3, |_| 10),
+                Ok(uniform_child_account_and_local_index(index, 10))
+            );
+        }
+        assert_eq!(
+            child_account_and_local_index(30, 3, |_| 10),
+            Err(ElusivError::IndexOutOfBounds.into())
+        );
+    }
+
+    #[test]
+    fn test_child_account_and_local_index_smaller_first_account() {
+        // The first account stores 4 values, all others 10
+        let values_per_account = |i: usize| if i == 0 { 4 } else { 10 };
+
+        assert_eq!(
+            child_account_and_local_index(0, 3, values_per_account),
+            Ok((0, 0))
+        );
+        assert_eq!(
+            child_account_and_local_index(3, 3, values_per_account),
+            Ok((0, 3))
+        );
+        assert_eq!(
+            child_account_and_local_index(4, 3, values_per_account),
+            Ok((1, 0))
+        );
+        assert_eq!(
+            child_account_and_local_index(13, 3, values_per_account),
+            Ok((1, 9))
+        );
+        assert_eq!(
+            child_account_and_local_index(14, 3, values_per_account),
+            Ok((2, 0))
+        );
+        assert_eq!(
+            child_account_and_local_index(23, 3, values_per_account),
+            Ok((2, 9))
+        );
+        assert_eq!(
+            child_account_and_local_index(24, 3, values_per_account),
+            Err(ElusivError::IndexOutOfBounds.into())
+        );
+    }
+
     const CHILD_ACCOUNT_COUNT: usize = 3;
 
     struct TestParentAccount<'a, 'b, 't> {
         _data: &'a [u8],
         pub pubkeys: [ElusivOption<Pubkey>; CHILD_ACCOUNT_COUNT],
         pub accounts: Vec<Option<&'b AccountInfo<'t>>>,
+        pub verified_children: u64,
     }
 
     impl<'a, 'b, 't> PDAAccount for TestParentAccount<'a, 'b, 't> {
@@ -88,6 +183,7 @@ mod tests {
                 _data,
                 pubkeys: [ElusivOption::None; CHILD_ACCOUNT_COUNT],
                 accounts: vec![None; CHILD_ACCOUNT_COUNT],
+                verified_children: 0,
             })
         }
     }
@@ -115,8 +211,17 @@ mod tests {
         }
 
         fn set_child_pubkey(&mut self, index: usize, pubkey: ElusivOption<Pubkey>) {
+            self.verified_children &= !(1 << index);
             self.pubkeys[index] = pubkey
         }
+
+        fn verified_children_bitmap(&self) -> u64 {
+            self.verified_children
+        }
+
+        fn set_verified_children_bitmap(&mut self, bitmap: u64) {
+            self.verified_children = bitmap
+        }
     }
 
     #[test]
@@ -244,6 +349,49 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_find_verified_child_accounts() {
+        parent_account!(mut parent, TestParentAccount);
+        account_info!(
+            foreign_account,
+            Pubkey::new_unique(),
+            vec![0; TestChildAccount::SIZE],
+            Pubkey::new_unique(),
+            false
+        );
+        parent.set_child_pubkey(0, ElusivOption::Some(*foreign_account.key));
+
+        // Owner is checked for unverified child-accounts
+        assert!(!parent.is_child_verified(0));
+        assert_eq!(
+            TestParentAccount::find_child_accounts(
+                &parent,
+                &crate::ID,
+                false,
+                &mut [&foreign_account].into_iter()
+            )
+            .unwrap_err(),
+            ProgramError::IllegalOwner
+        );
+
+        // Owner check is skipped for verified child-accounts
+        parent.set_verified_children_bitmap(0b1);
+        assert!(parent.is_child_verified(0));
+        let matched_accounts = TestParentAccount::find_child_accounts(
+            &parent,
+            &crate::ID,
+            false,
+            &mut [&foreign_account].into_iter(),
+        )
+        .unwrap();
+        assert_eq!(matched_accounts[0].unwrap().key, foreign_account.key);
+
+        // Re-setting the pubkey invalidates the verification
+        parent.set_child_pubkey(0, ElusivOption::Some(*foreign_account.key));
+        assert!(!parent.is_child_verified(0));
+        assert!(!parent.is_child_verified(u64::BITS as usize));
+    }
+
     #[test]
     fn test_unverified_account_info() {
         account_info!(account, Pubkey::new_unique());
@@ -1,11 +1,15 @@
 use crate::bytes::{
     usize_as_u32_safe, BorshSerDeSized, BorshSerDeSizedEnum, ElusivOption, SizedType,
 };
+use crate::error::ElusivError;
 use crate::fields::{G2HomProjective, Wrap, G1A, G2A};
-use crate::processor::{ProofRequest, MAX_MT_COUNT};
-use crate::proof::verifier::VerificationStep;
-use crate::state::program_account::PDAAccountData;
-use crate::token::Lamports;
+use crate::macros::guard;
+use crate::processor::{ProofRequest, MAX_MT_COUNT, RESERVED_VERIFICATION_ACCOUNT_IDS};
+use crate::proof::verifier::{
+    VerificationStep, COMBINED_MILLER_LOOP_IXS, FINAL_EXPONENTIATION_IXS,
+};
+use crate::state::program_account::{PDAAccount, PDAAccountData, SizedAccount};
+use crate::token::{Lamports, Price, Token, TokenPrice};
 use crate::types::{Lazy, LazyField, RawU256, U256};
 use ark_bn254::{Fq, Fq12, Fq2, Fq6};
 use borsh::{BorshDeserialize, BorshSerialize};
@@ -13,6 +17,7 @@ use elusiv_computation::RAM;
 use elusiv_derive::{BorshSerDeSized, EnumVariantIndex};
 use elusiv_proc_macros::elusiv_account;
 use solana_program::entrypoint::ProgramResult;
+use solana_program::program_error::ProgramError;
 use solana_program::pubkey::Pubkey;
 
 pub type RAMFq<'a> = LazyRAM<'a, Fq, 6>;
@@ -21,7 +26,7 @@ pub type RAMFq6<'a> = LazyRAM<'a, Fq6, 3>;
 pub type RAMFq12<'a> = LazyRAM<'a, Fq12, 7>;
 pub type RAMG2A<'a> = LazyRAM<'a, G2A, 1>;
 
-const MAX_PUBLIC_INPUTS_COUNT: usize = 14;
+pub const MAX_PUBLIC_INPUTS_COUNT: usize = 14;
 const MAX_PREPARE_INPUTS_INSTRUCTIONS: usize = MAX_PUBLIC_INPUTS_COUNT * 10;
 
 /// Describes the state of the proof-verification initialization and finalization
@@ -40,12 +45,76 @@ pub enum VerificationState {
     Closed,
 }
 
+/// Maximum number of [`VerificationState`]s recorded in a [`VerificationStateHistory`]
+#[cfg(any(test, feature = "state-history"))]
+pub const VERIFICATION_STATE_HISTORY_CAPACITY: usize = 16;
+#[cfg(not(any(test, feature = "state-history")))]
+pub const VERIFICATION_STATE_HISTORY_CAPACITY: usize = 0;
+
+// Entries are stored with 4 bits
+#[cfg(test)]
+const_assert!(VerificationState::VARIANT_COUNT < 16);
+
+/// Compact log of the [`VerificationState`]s visited by a [`VerificationAccount`] (4 bits per state)
+///
+/// # Notes
+///
+/// - states are only recorded with the `state-history` feature (and in tests), otherwise the log has a size of zero
+/// - consecutive duplicates are recorded once, states exceeding [`VERIFICATION_STATE_HISTORY_CAPACITY`] are dropped
+#[derive(
+    BorshDeserialize, BorshSerialize, BorshSerDeSized, Clone, Copy, Default, Debug, PartialEq, Eq,
+)]
+pub struct VerificationStateHistory {
+    entries: [u8; VERIFICATION_STATE_HISTORY_CAPACITY / 2],
+}
+
+impl VerificationStateHistory {
+    /// An entry is the variant index of the state + 1 (zero marks the end of the log)
+    fn entry(&self, index: usize) -> u8 {
+        (self.entries[index / 2] >> (4 * (index % 2))) & 0x0f
+    }
+
+    fn capacity(&self) -> usize {
+        self.entries.len() * 2
+    }
+
+    pub fn len(&self) -> usize {
+        (0..self.capacity())
+            .take_while(|&i| self.entry(i) != 0)
+            .count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn record(&mut self, state: &VerificationState) {
+        let len = self.len();
+        let entry = state.variant_index() + 1;
+
+        if len >= self.capacity() || (len > 0 && self.entry(len - 1) == entry) {
+            return;
+        }
+
+        self.entries[len / 2] |= entry << (4 * (len % 2));
+    }
+
+    /// Reconstructs the recorded path of states
+    pub fn states(&self) -> Vec<VerificationState> {
+        (0..self.len())
+            .map(|i| VerificationState::try_from_slice(&[self.entry(i) - 1]).unwrap())
+            .collect()
+    }
+}
+
 /// Account used for verifying proofs over the span of multiple transactions
 ///
 /// # Note
 ///
 /// Exists only temporarily for verifying a single proof and is closed afterwards.
-#[elusiv_account(partial_computation: true, eager_type: true)]
+///
+/// The public inputs are stored in the trailing data, so the account is sized by the vkey (see [`VerificationAccount::account_size`]).
+#[elusiv_account(partial_computation: true, eager_type: true, trailing_data: public_inputs)]
 pub struct VerificationAccount {
     #[no_getter]
     #[no_setter]
@@ -59,10 +128,11 @@ pub struct VerificationAccount {
 
     pub vkey_id: u32,
     pub step: VerificationStep,
+    #[no_setter]
     pub state: VerificationState,
 
-    // Public inputs
-    pub public_input: [RawU256; MAX_PUBLIC_INPUTS_COUNT],
+    /// The number of public inputs stored in the trailing data
+    pub public_inputs_count: u32,
 
     // Proof
     #[lazy]
@@ -100,6 +170,16 @@ pub struct VerificationAccount {
     #[no_getter]
     pub request: ProofRequest,
     pub tree_indices: [u32; MAX_MT_COUNT],
+
+    /// Unix timestamp at which the proof has been setup (used for timing out abandoned verifications)
+    pub proof_setup_timestamp: u64,
+
+    /// Duration (in seconds) after the proof setup after which the verification can be closed
+    pub timeout: u64,
+
+    /// States visited since the setup (empty without the `state-history` feature)
+    #[no_setter]
+    pub state_history: VerificationStateHistory,
 }
 
 #[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Clone, Default)]
@@ -133,9 +213,95 @@ pub struct VerificationAccountData {
 
     /// The expected associated-token-account-rent in `token_id`-Token
     pub associated_token_account_rent: u64,
+
+    /// The escrowed failure-bond in `Lamports`
+    pub failure_bond: Lamports,
+
+    /// The fee-version of the request at the time of the fee transfer
+    pub fee_version: u32,
+
+    /// The SOL-USD price used for converting the fees into `token_id`-Token
+    pub sol_usd_price: RecordedPrice,
+
+    /// The `token_id`-USD price used for converting the fees into `token_id`-Token
+    pub token_usd_price: RecordedPrice,
+
+    /// Token account receiving the amount if `recipient_wallet` is not a valid token account (instead of the fee-collector)
+    pub fallback_recipient: ElusivOption<RawU256>,
+
+    /// The mint of `token_id` (resolved through the token registry at the fee transfer)
+    pub mint: RawU256,
+}
+
+impl VerificationAccountData {
+    /// The [`TokenPrice`] used by [`crate::processor::init_verification_transfer_fee`] for all fee conversions
+    pub fn token_price(&self) -> TokenPrice {
+        TokenPrice::new_from_price(
+            self.sol_usd_price.price(),
+            self.token_usd_price.price(),
+            self.token_id,
+        )
+    }
+}
+
+/// The fees of a verification (in the request's token), see [`VerificationAccount::fee_breakdown`]
+#[derive(PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(any(test, feature = "elusiv-client"), derive(Debug))]
+pub struct FeeBreakdown {
+    pub subvention: Token,
+    pub network_fee: Token,
+    pub commitment_hash_fee: Token,
+    pub proof_verification_fee: Token,
+    pub associated_token_account_rent: Token,
+}
+
+/// A Pyth price (`price * 10^expo`) persisted in a [`VerificationAccountData`]
+#[derive(
+    BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Eq, Clone, Copy, Default, Debug,
+)]
+pub struct RecordedPrice {
+    pub price: i64,
+    pub conf: u64,
+    pub expo: i32,
+
+    /// The slot at which the price has been published to the Pyth price account (identifies the used price update)
+    pub publish_slot: u64,
+}
+
+impl RecordedPrice {
+    pub fn new(price: &Price, publish_slot: u64) -> Self {
+        RecordedPrice {
+            price: price.price,
+            conf: price.conf,
+            expo: price.expo,
+            publish_slot,
+        }
+    }
+
+    pub fn price(&self) -> Price {
+        Price {
+            price: self.price,
+            conf: self.conf,
+            expo: self.expo,
+        }
+    }
 }
 
 impl<'a> VerificationAccount<'a> {
+    /// The [`PDAOffset`] of the verification at `index` (of a single fee payer) in the specified `generation`
+    ///
+    /// # Note
+    ///
+    /// Distinct for all `index < RESERVED_VERIFICATION_ACCOUNT_IDS` and `generation <= MAX_VERIFICATION_ACCOUNT_GENERATION`.
+    pub fn pda_offset(index: u8, generation: u32) -> u32 {
+        u32::from(index)
+            .wrapping_add(generation.wrapping_mul(RESERVED_VERIFICATION_ACCOUNT_IDS as u32))
+    }
+
+    pub fn find_with_generation(fee_payer: Pubkey, index: u8, generation: u32) -> (Pubkey, u8) {
+        Self::find_with_pubkey(fee_payer, Some(Self::pda_offset(index, generation)))
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn setup(
         &mut self,
@@ -149,16 +315,13 @@ impl<'a> VerificationAccount<'a> {
     ) -> ProgramResult {
         self.set_vkey_id(&vkey_id);
         self.set_request(&request);
+        self.reset_state_history();
+        self.set_state(&VerificationState::None);
         for (i, tree_index) in tree_indices.iter().enumerate() {
             self.set_tree_indices(i, tree_index);
         }
 
-        for (i, &public_input) in public_inputs.iter().enumerate() {
-            let offset = i * 32;
-            self.public_input[offset..(32 + offset)]
-                .copy_from_slice(&public_input.skip_mr_ref()[..32]);
-        }
-
+        self.set_public_inputs(public_inputs)?;
         self.setup_public_inputs_instructions(instructions)?;
 
         // Remembers the authorized signer
@@ -187,10 +350,110 @@ impl<'a> VerificationAccount<'a> {
         Ok(())
     }
 
+    /// The number of public-input preparation instructions left (zero once the combined miller loop has started)
+    pub fn remaining_prepare_instructions(&self) -> u32 {
+        match self.get_step() {
+            VerificationStep::PublicInputPreparation => self
+                .get_prepare_inputs_instructions_count()
+                .saturating_sub(self.get_instruction()),
+            _ => 0,
+        }
+    }
+
+    /// The sum of the rounds of all stored public-input preparation instructions (`None` for an invalid instructions count)
+    pub fn prepare_inputs_rounds(&self) -> Option<usize> {
+        let count = self.get_prepare_inputs_instructions_count() as usize;
+        if count > MAX_PREPARE_INPUTS_INSTRUCTIONS {
+            return None;
+        }
+
+        Some(
+            (0..count)
+                .map(|i| self.get_prepare_inputs_instructions(i) as usize)
+                .sum(),
+        )
+    }
+
+    /// The total number of computation instructions left until the proof is verified
+    pub fn remaining_instructions(&self) -> u32 {
+        let combined_miller_loop_ixs = COMBINED_MILLER_LOOP_IXS as u32;
+        let final_exponentiation_ixs = FINAL_EXPONENTIATION_IXS as u32;
+
+        match self.get_step() {
+            VerificationStep::PublicInputPreparation => {
+                self.remaining_prepare_instructions()
+                    + combined_miller_loop_ixs
+                    + final_exponentiation_ixs
+            }
+            VerificationStep::CombinedMillerLoop => {
+                combined_miller_loop_ixs.saturating_sub(self.get_instruction())
+                    + final_exponentiation_ixs
+            }
+            VerificationStep::FinalExponentiation => {
+                final_exponentiation_ixs.saturating_sub(self.get_instruction())
+            }
+        }
+    }
+
+    /// Sets the [`VerificationState`] and records it in the [`VerificationStateHistory`]
+    pub fn set_state(&mut self, state: &VerificationState) {
+        let mut slice = &mut self.state[..];
+        state.serialize(&mut slice).unwrap();
+
+        let mut history = self.get_state_history();
+        history.record(state);
+        self.write_state_history(&history);
+    }
+
+    fn reset_state_history(&mut self) {
+        self.write_state_history(&VerificationStateHistory::default());
+    }
+
+    fn write_state_history(&mut self, history: &VerificationStateHistory) {
+        let mut slice = &mut self.state_history[..];
+        history.serialize(&mut slice).unwrap();
+    }
+
+    /// The size of a [`VerificationAccount`] storing `public_inputs_count` public inputs
+    pub const fn account_size(public_inputs_count: usize) -> usize {
+        <Self as SizedAccount>::SIZE + public_inputs_count * RawU256::SIZE
+    }
+
+    /// Stores the `public_inputs` (requires the account to be sized with [`VerificationAccount::account_size`])
+    pub fn set_public_inputs(&mut self, public_inputs: &[RawU256]) -> ProgramResult {
+        guard!(
+            public_inputs.len() <= MAX_PUBLIC_INPUTS_COUNT
+                && public_inputs.len() * RawU256::SIZE <= self.public_inputs.len(),
+            ProgramError::AccountDataTooSmall
+        );
+
+        self.set_public_inputs_count(&usize_as_u32_safe(public_inputs.len()));
+        for (i, public_input) in public_inputs.iter().enumerate() {
+            self.set_public_input(i, public_input);
+        }
+
+        Ok(())
+    }
+
+    pub fn get_public_input(&self, index: usize) -> RawU256 {
+        let offset = self.public_input_offset(index);
+        RawU256::try_from_slice(&self.public_inputs[offset..offset + RawU256::SIZE]).unwrap()
+    }
+
+    pub fn set_public_input(&mut self, index: usize, value: &RawU256) {
+        let offset = self.public_input_offset(index);
+        let mut slice = &mut self.public_inputs[offset..offset + RawU256::SIZE];
+        value.serialize(&mut slice).unwrap();
+    }
+
     /// Only valid before public inputs have been setup
     pub fn load_raw_public_input(&self, index: usize) -> U256 {
-        let offset = index * 32;
-        self.public_input[offset..offset + 32].try_into().unwrap()
+        self.get_public_input(index).skip_mr()
+    }
+
+    fn public_input_offset(&self, index: usize) -> usize {
+        assert!(index < self.get_public_inputs_count() as usize);
+        index * RawU256::SIZE
     }
 
     pub fn serialize_rams(&mut self) -> Result<(), std::io::Error> {
@@ -210,11 +473,39 @@ impl<'a> VerificationAccount<'a> {
         m
     }
 
+    /// The fees stored by [`crate::processor::init_verification_transfer_fee`] (all zero before the fee transfer)
+    pub fn fee_breakdown(&self) -> FeeBreakdown {
+        let data = self.get_other_data();
+        let token = |amount| Token::new(data.token_id, amount);
+
+        FeeBreakdown {
+            subvention: token(data.subvention),
+            network_fee: token(data.network_fee),
+            commitment_hash_fee: token(data.commitment_hash_fee_token),
+            proof_verification_fee: token(data.proof_verification_fee),
+            associated_token_account_rent: token(data.associated_token_account_rent),
+        }
+    }
+
     pub fn get_request(&self) -> ProofRequest {
         ProofRequest::deserialize_enum_full(&mut &self.request[..]).unwrap()
     }
 }
 
+/// The number of computation instructions left until the proof stored in the raw [`VerificationAccount`] data is verified
+///
+/// # Note
+///
+/// Allows relayers to resume a verification (e.g. after a crash) based on the fetched `account_bytes` alone.
+#[cfg(feature = "elusiv-client")]
+pub fn remaining_compute_rounds(account_bytes: &[u8]) -> Result<usize, ProgramError> {
+    use crate::state::program_account::ProgramAccount;
+
+    let mut data = account_bytes.to_vec();
+    let verification_account = VerificationAccount::new(&mut data)?;
+    Ok(verification_account.remaining_instructions() as usize)
+}
+
 /// Stores data lazily on the heap, read requests will trigger deserialization
 ///
 /// # Note
@@ -315,6 +606,12 @@ pub struct NullifierDuplicateAccount {
     #[no_getter]
     #[no_setter]
     pda_data: PDAAccountData,
+
+    /// The number of pending verifications using `skip_nullifier_pda`
+    pub duplicate_count: u8,
+
+    /// Slot of the last (initial or additional) verification of the nullifier-hashes
+    pub last_use_slot: u64,
 }
 
 impl<'a> NullifierDuplicateAccount<'a> {
@@ -325,11 +622,130 @@ impl<'a> NullifierDuplicateAccount<'a> {
     }
 }
 
+/// The maximum generation of a [`VerificationIndexAccount`] (with distinct [`VerificationAccount`] offsets)
+pub const MAX_VERIFICATION_ACCOUNT_GENERATION: u32 =
+    u32::MAX / RESERVED_VERIFICATION_ACCOUNT_IDS as u32;
+
+const OPEN_INDICES_BITMAP_LEN: usize = RESERVED_VERIFICATION_ACCOUNT_IDS as usize / 64;
+
+/// Tracks the [`VerificationAccount`] generation and the open verifications of a single fee payer
+///
+/// # Note
+///
+/// The `generation` is advanced with each initialized verification, so a reused index results in a new [`VerificationAccount`] address.
+#[elusiv_account]
+pub struct VerificationIndexAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    pub generation: u32,
+
+    /// Bitmap of the indices with an open [`VerificationAccount`]
+    pub open_indices: [u64; OPEN_INDICES_BITMAP_LEN],
+
+    /// The generation each index has been opened with most recently
+    pub index_generations: [u32; RESERVED_VERIFICATION_ACCOUNT_IDS as usize],
+}
+
+impl<'a> VerificationIndexAccount<'a> {
+    /// Consumes the current `generation` for a new verification at `index`
+    pub fn open_index(&mut self, index: u8, generation: u32) -> ProgramResult {
+        guard!(
+            index < RESERVED_VERIFICATION_ACCOUNT_IDS,
+            ElusivError::InvalidInstructionData
+        );
+        guard!(
+            generation == self.get_generation(),
+            ElusivError::InvalidInstructionData
+        );
+        guard!(
+            generation < MAX_VERIFICATION_ACCOUNT_GENERATION,
+            ElusivError::InvalidAccountState
+        );
+
+        self.set_generation(&(generation + 1));
+        self.set_index_open(index, true);
+        self.set_index_generations(index as usize, &generation);
+
+        Ok(())
+    }
+
+    /// Removes the verification at `index` from the open verifications (if it has been opened with `generation`)
+    pub fn close_index(&mut self, index: u8, generation: u32) {
+        if self.is_index_open(index) && self.get_index_generations(index as usize) == generation {
+            self.set_index_open(index, false);
+        }
+    }
+
+    pub fn is_index_open(&self, index: u8) -> bool {
+        if index >= RESERVED_VERIFICATION_ACCOUNT_IDS {
+            return false;
+        }
+
+        let word = self.get_open_indices(index as usize / 64);
+        word & (1 << (index % 64)) != 0
+    }
+
+    fn set_index_open(&mut self, index: u8, open: bool) {
+        let word_index = index as usize / 64;
+        let word = self.get_open_indices(word_index);
+        let bit = 1 << (index % 64);
+        let word = if open { word | bit } else { word & !bit };
+        self.set_open_indices(word_index, &word);
+    }
+
+    /// Returns the PDA-offset and address of each open [`VerificationAccount`] of `fee_payer`
+    ///
+    /// # Note
+    ///
+    /// An index is released whenever its [`VerificationAccount`] is closed (finalized, aborted or timed-out).
+    pub fn open_verifications(&self, fee_payer: &Pubkey) -> Vec<(u32, Pubkey)> {
+        (0..RESERVED_VERIFICATION_ACCOUNT_IDS)
+            .filter(|&index| self.is_index_open(index))
+            .map(|index| {
+                let pda_offset = VerificationAccount::pda_offset(
+                    index,
+                    self.get_index_generations(index as usize),
+                );
+                let (pubkey, _) =
+                    VerificationAccount::find_with_pubkey(*fee_payer, Some(pda_offset));
+                (pda_offset, pubkey)
+            })
+            .collect()
+    }
+}
+
+/// Returns the open verifications (see [`VerificationIndexAccount::open_verifications`]) from the raw [`VerificationIndexAccount`] data
+#[cfg(feature = "elusiv-client")]
+pub fn open_verifications(
+    verification_index_data: &[u8],
+    fee_payer: &Pubkey,
+) -> Result<Vec<(u32, Pubkey)>, ProgramError> {
+    let mut data = verification_index_data.to_vec();
+    let verification_index =
+        <VerificationIndexAccount as crate::state::program_account::ProgramAccount>::new(
+            &mut data,
+        )?;
+    Ok(verification_index.open_verifications(fee_payer))
+}
+
+/// Parses the [`VerificationAccountData`] from the raw [`VerificationAccount`] data
+#[cfg(feature = "elusiv-client")]
+pub fn parse_other_data(account_bytes: &[u8]) -> Result<VerificationAccountData, ProgramError> {
+    let mut data = account_bytes.to_vec();
+    let verification_account =
+        <VerificationAccount as crate::state::program_account::ProgramAccount>::new(&mut data)?;
+    Ok(verification_account.get_other_data())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::token::{rounding, USDC_TOKEN_ID};
     use crate::{
         fields::{u256_from_str, u256_from_str_skip_mr},
+        proof::vkey::{MigrateUnaryVKey, SendQuadraVKey, VerifyingKeyInfo},
         state::{metadata::CommitmentMetadata, program_account::ProgramAccount},
         types::{
             InputCommitment, JoinSplitPublicInputs, OptionalFee, PublicInputs, SendPublicInputs,
@@ -337,12 +753,8 @@ mod tests {
     };
     use elusiv_types::SizedAccount;
 
-    #[test]
-    fn test_setup_verification_account() {
-        let mut data = vec![0; VerificationAccount::SIZE];
-        let mut verification_account = VerificationAccount::new(&mut data).unwrap();
-
-        let public_inputs = SendPublicInputs {
+    fn test_public_inputs() -> SendPublicInputs {
+        SendPublicInputs {
             join_split: JoinSplitPublicInputs {
                 input_commitments: vec![InputCommitment {
                     root: Some(RawU256::new(u256_from_str("22"))),
@@ -360,7 +772,19 @@ mod tests {
             hashed_inputs: u256_from_str_skip_mr("7777777"),
             recipient_is_associated_token_account: true,
             solana_pay_transfer: false,
-        };
+            recipient_is_program_owned: false,
+            view_tag: 0,
+            current_time: 0,
+        }
+    }
+
+    #[test]
+    fn test_setup_verification_account() {
+        let mut data =
+            vec![0; VerificationAccount::account_size(SendPublicInputs::PUBLIC_INPUTS_COUNT)];
+        let mut verification_account = VerificationAccount::new(&mut data).unwrap();
+
+        let public_inputs = test_public_inputs();
         let request = ProofRequest::Send(public_inputs.clone());
         let data = VerificationAccountData {
             fee_payer: RawU256::new([1; 32]),
@@ -409,6 +833,198 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "elusiv-client")]
+    #[test]
+    fn test_parse_other_data() {
+        let other_data = VerificationAccountData {
+            fee_payer: RawU256::new([1; 32]),
+            recipient_wallet: ElusivOption::Some(RawU256::new([2; 32])),
+            skip_nullifier_pda: true,
+            subvention: 3,
+            network_fee: 4,
+            commitment_hash_fee: Lamports(5),
+            failure_bond: Lamports(6),
+            fee_version: 7,
+            sol_usd_price: RecordedPrice {
+                price: -8,
+                conf: 9,
+                expo: -10,
+                publish_slot: 11,
+            },
+            ..Default::default()
+        };
+
+        let mut data =
+            vec![0; VerificationAccount::account_size(SendPublicInputs::PUBLIC_INPUTS_COUNT)];
+        let mut verification_account = VerificationAccount::new(&mut data).unwrap();
+        verification_account.set_other_data(&other_data);
+        verification_account.set_round(&12);
+
+        assert_eq!(parse_other_data(&data), Ok(other_data));
+
+        // Too small
+        assert!(parse_other_data(&data[..VerificationAccount::SIZE - 1]).is_err());
+    }
+
+    #[test]
+    fn test_verification_account_public_inputs() {
+        assert!(VerificationAccount::account_size(MAX_PUBLIC_INPUTS_COUNT) <= 10240);
+
+        for public_inputs_count in [
+            SendQuadraVKey::PUBLIC_INPUTS_COUNT,
+            MigrateUnaryVKey::PUBLIC_INPUTS_COUNT,
+        ] {
+            let public_inputs_count = public_inputs_count as usize;
+            let size = VerificationAccount::account_size(public_inputs_count);
+            assert_eq!(size, VerificationAccount::SIZE + public_inputs_count * 32);
+
+            let public_inputs: Vec<RawU256> = (0..public_inputs_count)
+                .map(|i| RawU256::new(u256_from_str_skip_mr(&(i * 1_000_000 + 7).to_string())))
+                .collect();
+
+            // Too small
+            let mut data = vec![0; size - 1];
+            let mut verification_account = VerificationAccount::new(&mut data).unwrap();
+            assert_eq!(
+                verification_account.set_public_inputs(&public_inputs),
+                Err(ProgramError::AccountDataTooSmall)
+            );
+
+            let mut data = vec![0; size];
+            let mut verification_account = VerificationAccount::new(&mut data).unwrap();
+            verification_account
+                .set_public_inputs(&public_inputs)
+                .unwrap();
+            verification_account.set_vkey_id(&u32::MAX);
+
+            let verification_account = VerificationAccount::new(&mut data).unwrap();
+            assert_eq!(
+                verification_account.get_public_inputs_count() as usize,
+                public_inputs_count
+            );
+            for (i, public_input) in public_inputs.iter().enumerate() {
+                assert_eq!(verification_account.get_public_input(i), *public_input);
+                assert_eq!(
+                    verification_account.load_raw_public_input(i),
+                    public_input.skip_mr()
+                );
+            }
+            assert_eq!(verification_account.get_vkey_id(), u32::MAX);
+        }
+
+        // The unary vkey requires less rent than the quadra vkey
+        assert!(
+            VerificationAccount::account_size(MigrateUnaryVKey::PUBLIC_INPUTS_COUNT as usize)
+                < VerificationAccount::account_size(SendQuadraVKey::PUBLIC_INPUTS_COUNT as usize)
+        );
+    }
+
+    #[test]
+    fn test_verification_account_data_token_price() {
+        let sol_usd = Price {
+            price: 41,
+            conf: 2,
+            expo: -1,
+        };
+        let usdc_usd = Price {
+            price: 99,
+            conf: 1,
+            expo: -2,
+        };
+        let data = VerificationAccountData {
+            token_id: USDC_TOKEN_ID,
+            sol_usd_price: RecordedPrice::new(&sol_usd, 1),
+            token_usd_price: RecordedPrice::new(&usdc_usd, 2),
+            ..Default::default()
+        };
+
+        let price = data.token_price();
+        assert_eq!(price.lamports_usd, sol_usd);
+        assert_eq!(price.token_usd, usdc_usd);
+        assert_eq!(
+            Lamports(123_456_789).into_token(&price, USDC_TOKEN_ID, rounding::FEE),
+            Lamports(123_456_789).into_token(
+                &TokenPrice::new_from_price(sol_usd, usdc_usd, USDC_TOKEN_ID),
+                USDC_TOKEN_ID,
+                rounding::FEE
+            )
+        );
+
+        // Lamports
+        let data = VerificationAccountData::default();
+        assert_eq!(
+            Lamports(123).into_token(&data.token_price(), 0, rounding::FEE),
+            Ok(Token::new(0, 123))
+        );
+    }
+
+    #[test]
+    fn test_verification_state_history() {
+        let mut data = vec![0; VerificationAccount::SIZE];
+        let mut verification_account = VerificationAccount::new(&mut data).unwrap();
+        verification_account.set_state(&VerificationState::Closed);
+
+        // Setup resets the history
+        verification_account
+            .setup(
+                RawU256::new([1; 32]),
+                false,
+                &[],
+                &vec![],
+                0,
+                ProofRequest::Send(test_public_inputs()),
+                [0, 0],
+            )
+            .unwrap();
+        assert_eq!(
+            verification_account.get_state_history().states(),
+            vec![VerificationState::None]
+        );
+
+        for state in [
+            VerificationState::FeeTransferred,
+            VerificationState::ProofSetup,
+            VerificationState::ProofSetup,
+            VerificationState::InsertNullifiers,
+            VerificationState::Finalized,
+            VerificationState::Closed,
+        ] {
+            verification_account.set_state(&state);
+        }
+
+        assert_eq!(verification_account.get_state(), VerificationState::Closed);
+        assert_eq!(
+            verification_account.get_state_history().states(),
+            vec![
+                VerificationState::None,
+                VerificationState::FeeTransferred,
+                VerificationState::ProofSetup,
+                VerificationState::InsertNullifiers,
+                VerificationState::Finalized,
+                VerificationState::Closed,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_verification_state_history_capacity() {
+        let mut history = VerificationStateHistory::default();
+        assert!(history.is_empty());
+
+        for i in 0..VERIFICATION_STATE_HISTORY_CAPACITY + 1 {
+            history.record(if i % 2 == 0 {
+                &VerificationState::FeeTransferred
+            } else {
+                &VerificationState::ProofSetup
+            });
+        }
+
+        let states = history.states();
+        assert_eq!(history.len(), VERIFICATION_STATE_HISTORY_CAPACITY);
+        assert_eq!(states.len(), VERIFICATION_STATE_HISTORY_CAPACITY);
+        assert_eq!(states.last(), Some(&VerificationState::ProofSetup));
+    }
+
     impl BorshDeserialize for Wrap<u64> {
         fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
             Ok(Wrap(u64::deserialize(buf)?))
@@ -444,6 +1060,29 @@ mod tests {
         assert_eq!(&data[8..], &u64::to_le_bytes(u64::MAX)[..]);
     }
 
+    #[test]
+    #[cfg(feature = "debug-display")]
+    fn test_dump_verification_account() {
+        let mut data = vec![0; VerificationAccount::account_size(2)];
+        let mut verification_account = VerificationAccount::new(&mut data).unwrap();
+        verification_account.set_vkey_id(&7);
+        verification_account
+            .set_public_inputs(&[RawU256::ZERO, RawU256::ZERO])
+            .unwrap();
+        verification_account.set_state(&VerificationState::ProofSetup);
+
+        let dump = dump_verification_account(&data);
+        assert!(dump.starts_with("VerificationAccount {"));
+        assert!(dump.contains("vkey_id: 7,"));
+        assert!(dump.contains("state: ProofSetup,"));
+        assert!(dump.contains("public_inputs_count: 2,"));
+        assert!(dump.contains("public_inputs: <64 bytes>,"));
+        assert!(dump.contains(&format!(
+            "ram_fq: <{} bytes>,",
+            <RAMFq as elusiv_types::SizedType>::SIZE
+        )));
+    }
+
     #[test]
     fn test_check_vector_size() {
         let mut data = vec![0; VerificationAccount::SIZE];
@@ -465,4 +1104,87 @@ mod tests {
         assert_eq!(ram.data.len(), 3);
         assert_eq!(ram.changes.len(), 3);
     }
+
+    #[test]
+    fn test_verification_index_account_open_index() {
+        let mut data = vec![0; VerificationIndexAccount::SIZE];
+        let mut account = VerificationIndexAccount::new(&mut data).unwrap();
+
+        // Invalid index
+        assert_eq!(
+            account.open_index(RESERVED_VERIFICATION_ACCOUNT_IDS, 0),
+            Err(ElusivError::InvalidInstructionData.into())
+        );
+
+        account.open_index(0, 0).unwrap();
+        assert_eq!(account.get_generation(), 1);
+
+        // Stale generation
+        assert_eq!(
+            account.open_index(0, 0),
+            Err(ElusivError::InvalidInstructionData.into())
+        );
+
+        account.open_index(0, 1).unwrap();
+        assert_eq!(account.get_generation(), 2);
+
+        // Generation exhausted
+        account.set_generation(&MAX_VERIFICATION_ACCOUNT_GENERATION);
+        assert_eq!(
+            account.open_index(0, MAX_VERIFICATION_ACCOUNT_GENERATION),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+    }
+
+    #[test]
+    fn test_verification_index_account_open_verifications() {
+        let mut data = vec![0; VerificationIndexAccount::SIZE];
+        let mut account = VerificationIndexAccount::new(&mut data).unwrap();
+        let fee_payer = Pubkey::new_unique();
+
+        account.open_index(0, 0).unwrap();
+        account.open_index(65, 1).unwrap();
+        account
+            .open_index(RESERVED_VERIFICATION_ACCOUNT_IDS - 1, 2)
+            .unwrap();
+        assert!(account.is_index_open(65));
+        assert!(!account.is_index_open(1));
+
+        // Closing a stale generation has no effect
+        account.close_index(65, 0);
+        assert!(account.is_index_open(65));
+
+        account.close_index(65, 1);
+        assert!(!account.is_index_open(65));
+
+        let expected: Vec<(u32, Pubkey)> = [(0, 0), (RESERVED_VERIFICATION_ACCOUNT_IDS - 1, 2)]
+            .iter()
+            .map(|&(index, generation)| {
+                (
+                    VerificationAccount::pda_offset(index, generation),
+                    VerificationAccount::find_with_generation(fee_payer, index, generation).0,
+                )
+            })
+            .collect();
+        assert_eq!(account.open_verifications(&fee_payer), expected);
+
+        #[cfg(feature = "elusiv-client")]
+        assert_eq!(open_verifications(&data, &fee_payer), Ok(expected));
+    }
+
+    #[test]
+    fn test_verification_account_pda_offset() {
+        assert_eq!(VerificationAccount::pda_offset(0, 0), 0);
+        assert_eq!(VerificationAccount::pda_offset(1, 0), 1);
+        assert_eq!(
+            VerificationAccount::pda_offset(0, 1),
+            RESERVED_VERIFICATION_ACCOUNT_IDS as u32
+        );
+
+        let fee_payer = Pubkey::new_unique();
+        assert_ne!(
+            VerificationAccount::find_with_generation(fee_payer, 0, 0).0,
+            VerificationAccount::find_with_generation(fee_payer, 0, 1).0
+        );
+    }
 }
@@ -140,6 +140,39 @@ pub trait RingQueue {
         Ok(self.get_data((head as usize + offset) % Self::SIZE as usize))
     }
 
+    /// Returns up to `n` elements in FIFO order without removing them
+    fn peek_n(&self, n: usize) -> Vec<Self::N> {
+        let head = self.get_head() as usize;
+        let count = n.min(self.len() as usize);
+
+        (0..count)
+            .map(|offset| self.get_data((head + offset) % Self::SIZE as usize))
+            .collect()
+    }
+
+    /// Returns the `count` elements starting at `offset` in FIFO order without removing them
+    fn view_range(&self, offset: usize, count: usize) -> Result<Vec<Self::N>, ProgramError> {
+        let head = self.get_head() as usize;
+        let end = offset.checked_add(count).ok_or(QueueIsEmpty)?;
+        guard!(end <= self.len() as usize, QueueIsEmpty);
+
+        Ok((offset..end)
+            .map(|offset| self.get_data((head + offset) % Self::SIZE as usize))
+            .collect())
+    }
+
+    /// Returns an iterator over all elements in FIFO order (reading `head` and `tail` only once)
+    fn iter(&self) -> RingQueueIter<'_, Self>
+    where
+        Self: Sized,
+    {
+        RingQueueIter {
+            queue: self,
+            ptr: self.get_head(),
+            tail: self.get_tail(),
+        }
+    }
+
     /// Try to remove the first element from the queue
     fn dequeue_first(&mut self) -> Result<Self::N, ProgramError> {
         let head = self.get_head();
@@ -152,6 +185,15 @@ pub trait RingQueue {
         Ok(value)
     }
 
+    /// Try to remove the first `n` elements from the queue (either all or none of them are removed)
+    fn dequeue_n(&mut self, n: usize) -> Result<Vec<Self::N>, ProgramError> {
+        let values = self.view_range(0, n)?;
+        let head = self.get_head();
+        self.set_head(&((head + usize_as_u32_safe(n)) % Self::SIZE));
+
+        Ok(values)
+    }
+
     fn remove(&mut self, count: u32) -> Result<(), ProgramError> {
         let head = self.get_head();
         guard!(self.len() >= count, InvalidQueueAccess);
@@ -177,11 +219,7 @@ pub trait RingQueue {
         let head = self.get_head();
         let tail = self.get_tail();
 
-        if tail >= head {
-            tail - head
-        } else {
-            Self::SIZE - head + tail
-        }
+        (tail + Self::SIZE - head) % Self::SIZE
     }
 
     fn is_empty(&self) -> bool {
@@ -199,6 +237,27 @@ pub trait RingQueue {
     }
 }
 
+/// Iterator over the elements of a [`RingQueue`] (see [`RingQueue::iter`])
+pub struct RingQueueIter<'a, Q: RingQueue> {
+    queue: &'a Q,
+    ptr: u32,
+    tail: u32,
+}
+
+impl<'a, Q: RingQueue> Iterator for RingQueueIter<'a, Q> {
+    type Item = Q::N;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.ptr == self.tail {
+            return None;
+        }
+
+        let value = self.queue.get_data(self.ptr as usize);
+        self.ptr = (self.ptr + 1) % Q::SIZE;
+        Some(value)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -338,6 +397,56 @@ mod tests {
         assert_eq!(queue.len(), 0);
     }
 
+    #[test]
+    fn test_len_wrapped() {
+        // Wrapped state: the elements are at the indices 7, 8, 9, 0, 1
+        test_queue!(queue, 10, 7, 2);
+        assert_eq!(queue.len(), 5);
+        assert_eq!(queue.empty_slots(), 4);
+
+        for i in 0..5 {
+            queue.view(i).unwrap();
+        }
+        assert_eq!(queue.view(5), Err(ElusivError::InvalidQueueAccess.into()));
+
+        queue.remove(3).unwrap();
+        assert_eq!(queue.len(), 2);
+
+        // Wrapped full queue
+        test_queue!(queue, 10, 7, 6);
+        assert_eq!(queue.len(), queue.capacity());
+        assert_eq!(queue.enqueue(1), Err(ElusivError::QueueIsFull.into()));
+
+        test_queue!(queue, 10, 9, 0);
+        assert_eq!(queue.len(), 1);
+        queue.enqueue(1).unwrap();
+        assert_eq!(queue.len(), 2);
+        queue.dequeue_first().unwrap();
+        assert_eq!(queue.len(), 1);
+    }
+
+    #[test]
+    fn test_len_wrapped_positions() {
+        // SIZE 7 with head=5 and tail=2: the elements are at the indices 5, 6, 0, 1
+        test_queue!(queue, 7, 5, 2);
+        assert_eq!(queue.len(), 4);
+        queue.dequeue_first().unwrap();
+        assert_eq!(queue.len(), 3);
+
+        for head in 0..7 {
+            for len in 0..7 {
+                let tail = (head + len) % 7;
+                test_queue!(queue, 7, 0, 0);
+                queue.set_head(&head);
+                queue.set_tail(&tail);
+
+                assert_eq!(queue.len(), len);
+                assert_eq!(queue.empty_slots(), queue.capacity() - len);
+                assert_eq!(queue.iter().count(), len as usize);
+            }
+        }
+    }
+
     #[test]
     fn test_ordering() {
         test_queue!(queue, 13, 0, 0);
@@ -375,6 +484,127 @@ mod tests {
         assert_eq!(queue.view(2), Err(ElusivError::InvalidQueueAccess.into()));
     }
 
+    #[test]
+    fn test_peek_n() {
+        test_queue!(queue, 10, 0, 0);
+        assert!(queue.peek_n(3).is_empty());
+
+        for i in 1..4 {
+            queue.enqueue(i).unwrap();
+        }
+        assert!(queue.peek_n(0).is_empty());
+        assert_eq!(queue.peek_n(2), vec![1, 2]);
+        assert_eq!(queue.peek_n(5), vec![1, 2, 3]);
+        assert_eq!(queue.len(), 3);
+
+        // Wrapped state: the elements are at the indices 7, 8, 9, 0, 1
+        test_queue!(queue, 10, 7, 2);
+        queue.data = [4, 5, 0, 0, 0, 0, 0, 1, 2, 3];
+        assert_eq!(queue.peek_n(usize::MAX), vec![1, 2, 3, 4, 5]);
+        assert_eq!(queue.peek_n(4), vec![1, 2, 3, 4]);
+        assert_eq!(queue.get_head(), 7);
+        assert_eq!(queue.get_tail(), 2);
+    }
+
+    #[test]
+    fn test_view_range() {
+        test_queue!(queue, 10, 0, 0);
+        assert_eq!(queue.view_range(0, 0), Ok(vec![]));
+        assert_eq!(
+            queue.view_range(0, 1),
+            Err(ElusivError::QueueIsEmpty.into())
+        );
+
+        for i in 1..4 {
+            queue.enqueue(i).unwrap();
+        }
+        assert_eq!(queue.view_range(0, 3), Ok(vec![1, 2, 3]));
+        assert_eq!(queue.view_range(1, 2), Ok(vec![2, 3]));
+        assert_eq!(
+            queue.view_range(0, 4),
+            Err(ElusivError::QueueIsEmpty.into())
+        );
+        assert_eq!(
+            queue.view_range(2, 2),
+            Err(ElusivError::QueueIsEmpty.into())
+        );
+        assert_eq!(
+            queue.view_range(usize::MAX, 2),
+            Err(ElusivError::QueueIsEmpty.into())
+        );
+
+        // Wrapped state: the elements are at the indices 7, 8, 9, 0, 1
+        test_queue!(queue, 10, 7, 2);
+        queue.data = [4, 5, 0, 0, 0, 0, 0, 1, 2, 3];
+        assert_eq!(queue.view_range(0, 5), Ok(vec![1, 2, 3, 4, 5]));
+        assert_eq!(queue.view_range(2, 2), Ok(vec![3, 4]));
+        assert_eq!(
+            queue.view_range(1, 5),
+            Err(ElusivError::QueueIsEmpty.into())
+        );
+
+        // Full queue
+        test_queue!(queue, 10, 3, 2);
+        queue.data = [7, 8, 9, 0, 1, 2, 3, 4, 5, 6];
+        assert_eq!(
+            queue.view_range(0, queue.capacity() as usize),
+            Ok((0..9).collect())
+        );
+        assert_eq!(queue.view_range(6, 3), Ok(vec![6, 7, 8]));
+    }
+
+    #[test]
+    fn test_iter() {
+        test_queue!(queue, 10, 0, 0);
+        assert_eq!(queue.iter().next(), None);
+
+        for i in 1..4 {
+            queue.enqueue(i).unwrap();
+        }
+        assert_eq!(queue.iter().collect::<Vec<_>>(), vec![1, 2, 3]);
+
+        // Wrapped state
+        test_queue!(queue, 10, 7, 2);
+        queue.data = [4, 5, 0, 0, 0, 0, 0, 1, 2, 3];
+        assert_eq!(queue.iter().collect::<Vec<_>>(), vec![1, 2, 3, 4, 5]);
+
+        // Full queue
+        test_queue!(queue, 10, 3, 2);
+        queue.data = [7, 8, 9, 0, 1, 2, 3, 4, 5, 6];
+        assert_eq!(queue.iter().collect::<Vec<_>>(), (0..9).collect::<Vec<_>>());
+        assert_eq!(queue.iter().count(), queue.len() as usize);
+    }
+
+    #[test]
+    fn test_dequeue_n() {
+        test_queue!(queue, 10, 0, 0);
+        assert_eq!(queue.dequeue_n(0), Ok(vec![]));
+        assert_eq!(queue.dequeue_n(1), Err(ElusivError::QueueIsEmpty.into()));
+
+        for i in 1..4 {
+            queue.enqueue(i).unwrap();
+        }
+
+        // All or nothing
+        assert_eq!(queue.dequeue_n(4), Err(ElusivError::QueueIsEmpty.into()));
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.view_first(), Ok(1));
+
+        assert_eq!(queue.dequeue_n(2), Ok(vec![1, 2]));
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.dequeue_n(1), Ok(vec![3]));
+        assert!(queue.is_empty());
+
+        // Wrapped state: the elements are at the indices 7, 8, 9, 0, 1
+        test_queue!(queue, 10, 7, 2);
+        queue.data = [4, 5, 0, 0, 0, 0, 0, 1, 2, 3];
+        assert_eq!(queue.dequeue_n(6), Err(ElusivError::QueueIsEmpty.into()));
+        assert_eq!(queue.get_head(), 7);
+        assert_eq!(queue.dequeue_n(4), Ok(vec![1, 2, 3, 4]));
+        assert_eq!(queue.get_head(), 1);
+        assert_eq!(queue.view_first(), Ok(5));
+    }
+
     #[test]
     fn test_remove() {
         test_queue!(queue, 13, 0, 0);
@@ -1,6 +1,7 @@
 use super::program_account::*;
 use crate::bytes::*;
-use crate::macros::{elusiv_account, two_pow};
+use crate::error::ElusivError;
+use crate::macros::{elusiv_account, guard, two_pow, BorshSerDeSized};
 use crate::types::U256;
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::entrypoint::ProgramResult;
@@ -63,6 +64,36 @@ pub struct StorageAccount {
     /// Stores the last [`HISTORY_ARRAY_SIZE`] roots of the active tree (including the current root)
     pub active_mt_root_history: [U256; HISTORY_ARRAY_SIZE],
     pub mt_roots_count: u32, // required since we batch insert commitments
+
+    /// Bitmap of the child-accounts verified by [`crate::processor::warm_tree_accounts`]
+    verified_children: u64,
+
+    /// The roots of the `active_mt_root_history`, sorted by [`RootIndexEntry`] (the first `root_index_len` entries are used)
+    pub root_index: [RootIndexEntry; HISTORY_ARRAY_SIZE],
+    pub root_index_len: u32,
+}
+
+/// Maps a root to its position in the [`StorageAccount`]'s `active_mt_root_history`
+///
+/// # Note
+///
+/// Entries are ordered by `root` and then `position`.
+#[derive(
+    BorshDeserialize,
+    BorshSerialize,
+    BorshSerDeSized,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Copy,
+    Clone,
+    Debug,
+    Default,
+)]
+pub struct RootIndexEntry {
+    pub root: U256,
+    pub position: u32,
 }
 
 impl<'a, 'b, 't> StorageAccount<'a, 'b, 't> {
@@ -73,6 +104,11 @@ impl<'a, 'b, 't> StorageAccount<'a, 'b, 't> {
         for i in 0..self.active_mt_root_history.len() {
             self.active_mt_root_history[i] = 0;
         }
+
+        for i in 0..self.root_index.len() {
+            self.root_index[i] = 0;
+        }
+        self.set_root_index_len(&0);
     }
 
     pub fn is_full(&self) -> bool {
@@ -80,14 +116,18 @@ impl<'a, 'b, 't> StorageAccount<'a, 'b, 't> {
         ptr >= MT_COMMITMENT_COUNT
     }
 
-    fn account_and_local_index(&self, index: usize) -> (usize, usize) {
-        let account_index = index / VALUES_PER_STORAGE_SUB_ACCOUNT;
-        (account_index, index % VALUES_PER_STORAGE_SUB_ACCOUNT)
+    fn account_and_local_index(index: usize) -> Result<(usize, usize), ProgramError> {
+        guard!(index < MT_SIZE, ElusivError::IndexOutOfBounds);
+
+        Ok(uniform_child_account_and_local_index(
+            index,
+            VALUES_PER_STORAGE_SUB_ACCOUNT,
+        ))
     }
 
     /// `level`: `0` is the root level, `MT_HEIGHT` the commitment level
-    pub fn get_node(&self, index: usize, level: usize) -> Result<U256, ProgramError> {
-        assert!(level <= MT_HEIGHT as usize);
+    pub fn try_get_node(&self, index: usize, level: usize) -> Result<U256, ProgramError> {
+        guard!(level <= MT_HEIGHT as usize, ElusivError::IndexOutOfBounds);
 
         let ptr = self.get_next_commitment_ptr() as usize;
 
@@ -96,46 +136,163 @@ impl<'a, 'b, 't> StorageAccount<'a, 'b, 't> {
             Ok(EMPTY_TREE[MT_HEIGHT as usize - level])
         } else {
             let (account_index, local_index) =
-                self.account_and_local_index(mt_array_index(index, level));
+                Self::account_and_local_index(try_mt_array_index(index, level)?)?;
             let result = self.execute_on_child_account(account_index, |data| {
-                U256::try_from_slice(
-                    &data[local_index * U256::SIZE..(local_index + 1) * U256::SIZE],
-                )
+                let slice = data
+                    .get(local_index * U256::SIZE..(local_index + 1) * U256::SIZE)
+                    .ok_or(ElusivError::IndexOutOfBounds)?;
+                U256::try_from_slice(slice).map_err(ProgramError::from)
             })??;
 
             Ok(result)
         }
     }
 
-    pub fn set_node(&mut self, value: &U256, index: usize, level: usize) -> ProgramResult {
-        assert!(level <= MT_HEIGHT as usize);
-
-        let (account_index, local_index) =
-            self.account_and_local_index(mt_array_index(index, level));
+    pub fn try_set_node(&mut self, value: &U256, index: usize, level: usize) -> ProgramResult {
+        let array_index = try_mt_array_index(index, level)?;
+        let (account_index, local_index) = Self::account_and_local_index(array_index)?;
         self.execute_on_child_account_mut(account_index, |data| {
-            let mut slice = &mut data[local_index * U256::SIZE..(local_index + 1) * U256::SIZE];
-            BorshSerialize::serialize(value, &mut slice)
+            let mut slice = data
+                .get_mut(local_index * U256::SIZE..(local_index + 1) * U256::SIZE)
+                .ok_or(ElusivError::IndexOutOfBounds)?;
+            BorshSerialize::serialize(value, &mut slice).map_err(ProgramError::from)
         })??;
 
         Ok(())
     }
 
+    /// Infallible [`Self::try_get_node`] for an already validated `index` and `level`
+    pub fn get_node(&self, index: usize, level: usize) -> U256 {
+        self.try_get_node(index, level).unwrap()
+    }
+
+    /// Infallible [`Self::try_set_node`] for an already validated `index` and `level`
+    pub fn set_node(&mut self, value: &U256, index: usize, level: usize) {
+        self.try_set_node(value, index, level).unwrap()
+    }
+
     pub fn get_root(&self) -> Result<U256, ProgramError> {
-        self.get_node(0, 0)
+        self.try_get_node(0, 0)
     }
 
     /// A root is valid if it's the current root or inside of the active_mt_root_history array
     pub fn is_root_valid(&self, root: &U256) -> bool {
-        let max_history_roots =
-            std::cmp::min(self.get_mt_roots_count() as usize, HISTORY_ARRAY_SIZE);
-
         // TODO: remove this, has become redundant
         if let Ok(current_root) = self.get_root() {
             return *root == current_root;
         }
 
-        max_history_roots > 0
-            && contains(root, &self.active_mt_root_history[..max_history_roots * 32])
+        self.search_root_index(|entry| entry.root.cmp(root)).is_ok()
+    }
+
+    /// Inserts `root` at `position` into the `active_mt_root_history` (overwriting the previous root) and updates the `root_index`
+    pub fn insert_history_root(&mut self, position: usize, root: &U256) -> ProgramResult {
+        guard!(position < HISTORY_ARRAY_SIZE, ElusivError::IndexOutOfBounds);
+
+        let mut len = self.get_root_index_len() as usize;
+
+        // Remove the overwritten root
+        let overwritten = RootIndexEntry {
+            root: self.get_active_mt_root_history(position),
+            position: usize_as_u32_safe(position),
+        };
+        if let Ok(index) = self.search_root_index(|entry| entry.cmp(&overwritten)) {
+            for i in index..len - 1 {
+                self.set_root_index(i, &self.get_root_index(i + 1));
+            }
+            len -= 1;
+        }
+
+        // Binary insert of the new root
+        let inserted = RootIndexEntry {
+            root: *root,
+            position: usize_as_u32_safe(position),
+        };
+        let index = match self.search_root_index(|entry| entry.cmp(&inserted)) {
+            Ok(index) | Err(index) => index,
+        };
+        for i in (index..len).rev() {
+            self.set_root_index(i + 1, &self.get_root_index(i));
+        }
+        self.set_root_index(index, &inserted);
+        self.set_root_index_len(&usize_as_u32_safe(len + 1));
+
+        self.set_active_mt_root_history(position, root);
+
+        Ok(())
+    }
+
+    /// Verifies that the `root_index` is sorted and matches the `active_mt_root_history`
+    pub fn verify_root_index(&self) -> ProgramResult {
+        let len = self.get_root_index_len() as usize;
+        guard!(len <= HISTORY_ARRAY_SIZE, ElusivError::InvalidAccountState);
+
+        let mut positions = [false; HISTORY_ARRAY_SIZE];
+        for i in 0..len {
+            let entry = self.get_root_index(i);
+            let position = entry.position as usize;
+
+            guard!(
+                position < HISTORY_ARRAY_SIZE && !positions[position],
+                ElusivError::InvalidAccountState
+            );
+            guard!(
+                self.get_active_mt_root_history(position) == entry.root,
+                ElusivError::InvalidAccountState
+            );
+            if i > 0 {
+                guard!(
+                    self.get_root_index(i - 1) < entry,
+                    ElusivError::InvalidAccountState
+                );
+            }
+
+            positions[position] = true;
+        }
+
+        Ok(())
+    }
+
+    /// Binary search over the used entries of the `root_index`
+    fn search_root_index<F>(&self, cmp: F) -> Result<usize, usize>
+    where
+        F: Fn(&RootIndexEntry) -> std::cmp::Ordering,
+    {
+        let mut low = 0;
+        let mut high = self.get_root_index_len() as usize;
+
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match cmp(&self.get_root_index(mid)) {
+                std::cmp::Ordering::Less => low = mid + 1,
+                std::cmp::Ordering::Greater => high = mid,
+                std::cmp::Ordering::Equal => return Ok(mid),
+            }
+        }
+
+        Err(low)
+    }
+
+    /// Whether `commitment` is one of the last `window` commitments inserted into the active MT
+    pub fn is_recently_inserted(
+        &self,
+        commitment: &U256,
+        window: usize,
+    ) -> Result<bool, ProgramError> {
+        let ptr = self.get_next_commitment_ptr() as usize;
+        for index in ptr.saturating_sub(window)..ptr {
+            if self.try_get_node(index, MT_HEIGHT as usize)? == *commitment {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// The count of commitments inserted into all MTs (closed MTs are full)
+    pub fn commitment_count(&self) -> u64 {
+        self.get_trees_count() as u64 * MT_COMMITMENT_COUNT as u64
+            + self.get_next_commitment_ptr() as u64
     }
 
     #[allow(clippy::needless_range_loop)]
@@ -146,7 +303,7 @@ impl<'a, 'b, 't> StorageAccount<'a, 'b, 't> {
         for i in 0..MT_HEIGHT as usize {
             let level = MT_HEIGHT as usize - i;
             let n_index = if index % 2 == 0 { index + 1 } else { index - 1 };
-            opening[i] = self.get_node(n_index, level)?;
+            opening[i] = self.try_get_node(n_index, level)?;
             index >>= 1;
         }
 
@@ -154,9 +311,18 @@ impl<'a, 'b, 't> StorageAccount<'a, 'b, 't> {
     }
 }
 
+pub fn try_mt_array_index(index: usize, level: usize) -> Result<usize, ProgramError> {
+    guard!(level <= MT_HEIGHT as usize, ElusivError::IndexOutOfBounds);
+
+    let level_size = two_pow!(usize_as_u32_safe(level));
+    guard!(index < level_size, ElusivError::IndexOutOfBounds);
+
+    Ok(level_size - 1 + index)
+}
+
+/// Infallible [`try_mt_array_index`] for an already validated `index` and `level`
 pub fn mt_array_index(index: usize, level: usize) -> usize {
-    assert!(index < two_pow!(usize_as_u32_safe(level)));
-    two_pow!(usize_as_u32_safe(level)) - 1 + index
+    try_mt_array_index(index, level).unwrap()
 }
 
 fn use_default_value(index: usize, level: usize, next_leaf_ptr: usize) -> bool {
@@ -293,6 +459,21 @@ mod tests {
         assert_eq!(empty_root_raw().reduce(), EMPTY_TREE[MT_HEIGHT as usize]);
     }
 
+    #[test]
+    fn test_commitment_count() {
+        parent_account!(mut storage_account, StorageAccount);
+        assert_eq!(storage_account.commitment_count(), 0);
+
+        storage_account.set_next_commitment_ptr(&3);
+        assert_eq!(storage_account.commitment_count(), 3);
+
+        storage_account.set_trees_count(&2);
+        assert_eq!(
+            storage_account.commitment_count(),
+            2 * MT_COMMITMENT_COUNT as u64 + 3
+        );
+    }
+
     #[test]
     fn test_set_node() {
         parent_account!(mut storage_account, StorageAccount);
@@ -302,22 +483,12 @@ mod tests {
             let last = two_pow!(level) - 1;
 
             // First node
-            storage_account
-                .set_node(&[1; 32], 0, level as usize)
-                .unwrap();
-            assert_eq!(
-                storage_account.get_node(0, level as usize).unwrap(),
-                [1; 32]
-            );
+            storage_account.set_node(&[1; 32], 0, level as usize);
+            assert_eq!(storage_account.get_node(0, level as usize), [1; 32]);
 
             // Last node
-            storage_account
-                .set_node(&[2; 32], last, level as usize)
-                .unwrap();
-            assert_eq!(
-                storage_account.get_node(last, level as usize).unwrap(),
-                [2; 32]
-            );
+            storage_account.set_node(&[2; 32], last, level as usize);
+            assert_eq!(storage_account.get_node(last, level as usize), [2; 32]);
         }
     }
 
@@ -325,16 +496,62 @@ mod tests {
     #[should_panic]
     fn test_set_node_invalid_level() {
         parent_account!(mut storage_account, StorageAccount);
-        storage_account
-            .set_node(&[1; 32], 0, MT_HEIGHT as usize + 1)
-            .unwrap();
+        storage_account.set_node(&[1; 32], 0, MT_HEIGHT as usize + 1);
     }
 
     #[test]
     #[should_panic]
     fn test_set_node_invalid_level_index() {
         parent_account!(mut storage_account, StorageAccount);
-        storage_account.set_node(&[1; 32], 4, 2).unwrap();
+        storage_account.set_node(&[1; 32], 4, 2);
+    }
+
+    #[test]
+    fn test_try_node_index_out_of_bounds() {
+        parent_account!(mut storage_account, StorageAccount);
+        storage_account.set_next_commitment_ptr(&(MT_COMMITMENT_COUNT as u32));
+
+        assert_eq!(
+            StorageAccount::account_and_local_index(MT_SIZE),
+            Err(ElusivError::IndexOutOfBounds.into())
+        );
+        assert_eq!(
+            StorageAccount::account_and_local_index(MT_SIZE - 1),
+            Ok((
+                ACCOUNTS_COUNT - 1,
+                (MT_SIZE - 1) % VALUES_PER_STORAGE_SUB_ACCOUNT
+            ))
+        );
+
+        // Invalid level
+        assert_eq!(
+            storage_account.try_get_node(0, MT_HEIGHT as usize + 1),
+            Err(ElusivError::IndexOutOfBounds.into())
+        );
+        assert_eq!(
+            storage_account.try_set_node(&[1; 32], 0, MT_HEIGHT as usize + 1),
+            Err(ElusivError::IndexOutOfBounds.into())
+        );
+
+        // Invalid index in level
+        assert_eq!(
+            storage_account.try_set_node(&[1; 32], MT_COMMITMENT_COUNT, MT_HEIGHT as usize),
+            Err(ElusivError::IndexOutOfBounds.into())
+        );
+        assert_eq!(
+            storage_account.try_set_node(&[1; 32], 4, 2),
+            Err(ElusivError::IndexOutOfBounds.into())
+        );
+
+        storage_account
+            .try_set_node(&[1; 32], MT_COMMITMENT_COUNT - 1, MT_HEIGHT as usize)
+            .unwrap();
+        assert_eq!(
+            storage_account
+                .try_get_node(MT_COMMITMENT_COUNT - 1, MT_HEIGHT as usize)
+                .unwrap(),
+            [1; 32]
+        );
     }
 
     #[test]
@@ -358,20 +575,20 @@ mod tests {
 
         // No commitments -> default values
         assert_eq!(
-            storage_account.get_node(0, 0).unwrap(),
+            storage_account.get_node(0, 0),
             u256_from_str(
                 "11702828337982203149177882813338547876343922920234831094975924378932809409969"
             )
         );
         assert_eq!(
-            storage_account.get_node(0, MT_HEIGHT as usize).unwrap(),
+            storage_account.get_node(0, MT_HEIGHT as usize),
             u256_from_str(
                 "14744269619966411208579211824598458697587494354926760081771325075741142829156"
             )
         );
         for level in 0..=MT_HEIGHT {
             assert_eq!(
-                storage_account.get_node(0, level as usize).unwrap(),
+                storage_account.get_node(0, level as usize),
                 EMPTY_TREE[(MT_HEIGHT - level) as usize],
             );
         }
@@ -381,9 +598,7 @@ mod tests {
 
             for level in 0..=MT_HEIGHT as usize {
                 assert_eq!(
-                    storage_account
-                        .get_node(i >> (MT_HEIGHT as usize - level), level)
-                        .unwrap(),
+                    storage_account.get_node(i >> (MT_HEIGHT as usize - level), level),
                     u256_from_str("0")
                 );
 
@@ -391,7 +606,7 @@ mod tests {
                 let offset = (i + 1) >> (MT_HEIGHT as usize - level);
                 if offset > (i + 1) {
                     assert_eq!(
-                        storage_account.get_node(offset, level).unwrap(),
+                        storage_account.get_node(offset, level),
                         EMPTY_TREE[MT_HEIGHT as usize - level],
                     );
                 }
@@ -402,7 +617,7 @@ mod tests {
     #[test]
     fn test_get_root() {
         parent_account!(mut storage_account, StorageAccount);
-        storage_account.set_node(&[1; 32], 0, 0).unwrap();
+        storage_account.set_node(&[1; 32], 0, 0);
         storage_account.set_next_commitment_ptr(&1);
 
         assert_eq!(storage_account.get_root().unwrap(), [1; 32]);
@@ -432,10 +647,130 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_is_recently_inserted() {
+        parent_account!(mut storage_account, StorageAccount);
+
+        assert!(!storage_account
+            .is_recently_inserted(&EMPTY_TREE[0], 4)
+            .unwrap());
+
+        for i in 0..8 {
+            storage_account.set_node(&[i as u8 + 1; 32], i, MT_HEIGHT as usize);
+        }
+        storage_account.set_next_commitment_ptr(&8);
+
+        assert!(storage_account.is_recently_inserted(&[8; 32], 4).unwrap());
+        assert!(storage_account.is_recently_inserted(&[5; 32], 4).unwrap());
+        assert!(storage_account.is_recently_inserted(&[1; 32], 8).unwrap());
+
+        // Outside of the window
+        assert!(!storage_account.is_recently_inserted(&[4; 32], 4).unwrap());
+        assert!(!storage_account.is_recently_inserted(&[8; 32], 0).unwrap());
+
+        // Not inserted
+        assert!(!storage_account.is_recently_inserted(&[9; 32], 8).unwrap());
+    }
+
     #[test]
     fn test_is_root_valid() {
         parent_account!(storage_account, StorageAccount);
         assert!(storage_account.is_root_valid(&EMPTY_TREE[MT_HEIGHT as usize]));
         assert!(!storage_account.is_root_valid(&[0; 32]));
     }
+
+    fn history_root(k: usize) -> U256 {
+        let mut root = [0; 32];
+        root[..8].copy_from_slice(&((k * 7919 % 1009) as u64).to_le_bytes());
+        root
+    }
+
+    fn is_indexed(storage_account: &StorageAccount, root: &U256) -> bool {
+        storage_account
+            .search_root_index(|entry| entry.root.cmp(root))
+            .is_ok()
+    }
+
+    #[test]
+    fn test_insert_history_root() {
+        parent_account!(mut storage_account, StorageAccount);
+        assert!(!is_indexed(&storage_account, &[0; 32]));
+
+        // Wraps around the history multiple times
+        let count = HISTORY_ARRAY_SIZE * 2 + 7;
+        for k in 0..count {
+            storage_account
+                .insert_history_root(k % HISTORY_ARRAY_SIZE, &history_root(k))
+                .unwrap();
+
+            storage_account.verify_root_index().unwrap();
+            assert_eq!(
+                storage_account.get_root_index_len() as usize,
+                std::cmp::min(k + 1, HISTORY_ARRAY_SIZE)
+            );
+        }
+
+        // Overwritten roots are removed from the index
+        for k in 0..count {
+            assert_eq!(
+                is_indexed(&storage_account, &history_root(k)),
+                k + HISTORY_ARRAY_SIZE >= count
+            );
+        }
+
+        assert_eq!(
+            storage_account.insert_history_root(HISTORY_ARRAY_SIZE, &[1; 32]),
+            Err(ElusivError::IndexOutOfBounds.into())
+        );
+
+        storage_account.reset();
+        assert_eq!(storage_account.get_root_index_len(), 0);
+        assert!(!is_indexed(&storage_account, &history_root(count - 1)));
+        storage_account.verify_root_index().unwrap();
+    }
+
+    #[test]
+    fn test_verify_root_index() {
+        parent_account!(mut storage_account, StorageAccount);
+        for k in 0..3 {
+            storage_account
+                .insert_history_root(k, &history_root(k))
+                .unwrap();
+        }
+        storage_account.verify_root_index().unwrap();
+
+        // Unsorted
+        let first = storage_account.get_root_index(0);
+        let second = storage_account.get_root_index(1);
+        storage_account.set_root_index(0, &second);
+        storage_account.set_root_index(1, &first);
+        assert_eq!(
+            storage_account.verify_root_index(),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+        storage_account.set_root_index(0, &first);
+        storage_account.set_root_index(1, &second);
+
+        // Root does not match the history
+        storage_account.set_active_mt_root_history(first.position as usize, &[1; 32]);
+        assert_eq!(
+            storage_account.verify_root_index(),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+        storage_account.set_active_mt_root_history(first.position as usize, &first.root);
+
+        // Duplicate position
+        storage_account.set_root_index_len(&4);
+        storage_account.set_root_index(
+            3,
+            &RootIndexEntry {
+                root: [255; 32],
+                position: first.position,
+            },
+        );
+        assert_eq!(
+            storage_account.verify_root_index(),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+    }
 }
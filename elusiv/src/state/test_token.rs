@@ -0,0 +1,42 @@
+use crate::macros::PDAAccount;
+
+/// The mint of a devnet test-token (owned by the token-program)
+///
+/// # Note
+///
+/// - The PDA is its own mint-authority, so only the program is able to mint test-tokens.
+/// - In `devnet-tools` builds the registry-entry of each SPL-token points at this PDA (with the offset `token_id`).
+#[derive(PDAAccount)]
+pub struct TestMintAccount;
+
+/// A Pyth-compatible price-account of a devnet test-token (owned by the program)
+///
+/// # Note
+///
+/// The data is a raw Pyth price-account (without a leading [`super::program_account::PDAAccountData`]).
+#[derive(PDAAccount)]
+pub struct TestPriceAccount;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token::{elusiv_token, SPL_TOKEN_COUNT};
+    use elusiv_types::PDAAccount;
+
+    #[test]
+    fn test_test_token_registry() {
+        assert_eq!(
+            elusiv_token(0).unwrap().pyth_usd_price_key,
+            TestPriceAccount::find(Some(0)).0
+        );
+
+        for token_id in 1..=SPL_TOKEN_COUNT as u16 {
+            let token = elusiv_token(token_id).unwrap();
+            assert_eq!(token.mint, TestMintAccount::find(Some(token_id as u32)).0);
+            assert_eq!(
+                token.pyth_usd_price_key,
+                TestPriceAccount::find(Some(token_id as u32)).0
+            );
+        }
+    }
+}
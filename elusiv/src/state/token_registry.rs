@@ -0,0 +1,289 @@
+use crate::bytes::ElusivOption;
+use crate::error::ElusivError;
+use crate::macros::{elusiv_account, guard, pda_account, BorshSerDeSized};
+use crate::token::{elusiv_token, ElusivToken, TokenError, TokenID};
+use borsh::{BorshDeserialize, BorshSerialize};
+use elusiv_types::PDAAccountData;
+use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+
+/// The number of token-ids (starting at `1`) that can be registered
+pub const TOKEN_REGISTRY_CAPACITY: usize = 16;
+
+/// The number of token-ids (starting at `0`) that can be paused
+const PAUSABLE_TOKEN_COUNT: usize = TOKEN_REGISTRY_CAPACITY + 1;
+
+/// Token metadata registered by the governance (see [`ElusivToken`])
+#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(any(test, feature = "elusiv-client"), derive(Debug))]
+pub struct RegisteredToken {
+    pub mint: Pubkey,
+    pub decimals: u8,
+    pub price_base_exp: u8,
+    pub pyth_usd_price_key: Pubkey,
+    pub min: u64,
+    pub max: u64,
+}
+
+impl RegisteredToken {
+    pub fn elusiv_token(&self) -> ElusivToken {
+        ElusivToken {
+            #[cfg(feature = "elusiv-client")]
+            ident: "",
+            mint: self.mint,
+            decimals: self.decimals,
+            price_base_exp: self.price_base_exp,
+            pyth_usd_price_key: self.pyth_usd_price_key,
+            min: self.min,
+            max: self.max,
+        }
+    }
+}
+
+/// Token metadata that is updatable without a redeployment
+///
+/// # Note
+///
+/// Tokens that are not registered (or as long as the account does not exist) use the static [`crate::token::TOKENS`].
+#[elusiv_account(eager_type: true)]
+pub struct TokenRegistryAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    /// The registered tokens with the token-ids `1..=TOKEN_REGISTRY_CAPACITY`
+    pub tokens: [ElusivOption<RegisteredToken>; TOKEN_REGISTRY_CAPACITY],
+
+    /// Pause flags for the token-ids `0..=TOKEN_REGISTRY_CAPACITY` (all tokens are unpaused by default)
+    pub paused: [bool; PAUSABLE_TOKEN_COUNT],
+}
+
+impl<'a> TokenRegistryAccount<'a> {
+    pub fn registered_token(&self, token_id: TokenID) -> Option<RegisteredToken> {
+        let index = registry_index(token_id).ok()?;
+        self.get_tokens(index).option()
+    }
+
+    pub fn register_token(
+        &mut self,
+        token_id: TokenID,
+        token: &RegisteredToken,
+    ) -> Result<(), ProgramError> {
+        let index = registry_index(token_id)?;
+        self.set_tokens(index, &ElusivOption::Some(*token));
+        Ok(())
+    }
+
+    pub fn is_paused(&self, token_id: TokenID) -> bool {
+        let index = token_id as usize;
+        index < PAUSABLE_TOKEN_COUNT && self.get_paused(index)
+    }
+
+    pub fn set_token_paused(
+        &mut self,
+        token_id: TokenID,
+        paused: bool,
+    ) -> Result<(), ProgramError> {
+        let index = token_id as usize;
+        guard!(index < PAUSABLE_TOKEN_COUNT, TokenError::InvalidTokenID);
+        self.set_paused(index, &paused);
+        Ok(())
+    }
+
+    /// Returns the registered metadata of `token_id` or falls back to [`elusiv_token`]
+    pub fn elusiv_token(&self, token_id: TokenID) -> Result<ElusivToken, TokenError> {
+        match self.registered_token(token_id) {
+            Some(token) => Ok(token.elusiv_token()),
+            None => elusiv_token(token_id),
+        }
+    }
+}
+
+fn registry_index(token_id: TokenID) -> Result<usize, TokenError> {
+    let token_id = token_id as usize;
+    if token_id == 0 || token_id > TOKEN_REGISTRY_CAPACITY {
+        Err(TokenError::InvalidTokenID)
+    } else {
+        Ok(token_id - 1)
+    }
+}
+
+/// Reads the metadata of `token_id` from a (verified) [`TokenRegistryAccount`] or falls back to [`elusiv_token`] if it does not exist
+pub fn registered_elusiv_token(
+    token_registry_account: &AccountInfo,
+    token_id: TokenID,
+) -> Result<ElusivToken, ProgramError> {
+    if token_registry_account.data_is_empty() {
+        return Ok(elusiv_token(token_id)?);
+    }
+
+    guard!(
+        *token_registry_account.owner == crate::ID,
+        ElusivError::InvalidAccount
+    );
+
+    pda_account!(token_registry, TokenRegistryAccount, token_registry_account);
+    Ok(token_registry.elusiv_token(token_id)?)
+}
+
+/// Checks whether `token_id` is paused in a (verified) [`TokenRegistryAccount`] (no token is paused if it does not exist)
+pub fn is_token_paused(
+    token_registry_account: &AccountInfo,
+    token_id: TokenID,
+) -> Result<bool, ProgramError> {
+    if token_registry_account.data_is_empty() {
+        return Ok(false);
+    }
+
+    guard!(
+        *token_registry_account.owner == crate::ID,
+        ElusivError::InvalidAccount
+    );
+
+    pda_account!(token_registry, TokenRegistryAccount, token_registry_account);
+    Ok(token_registry.is_paused(token_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::account_info;
+    use crate::state::program_account::{PDAAccount, ProgramAccount, SizedAccount};
+    use crate::token::{SPL_TOKEN_COUNT, TOKENS, USDC_TOKEN_ID};
+
+    fn registered_token() -> RegisteredToken {
+        RegisteredToken {
+            mint: Pubkey::new_unique(),
+            decimals: 8,
+            price_base_exp: 0,
+            pyth_usd_price_key: Pubkey::new_unique(),
+            min: 1,
+            max: 1_000_000,
+        }
+    }
+
+    #[test]
+    fn test_registered_elusiv_token() {
+        let pk = TokenRegistryAccount::find(None).0;
+        let token_id = SPL_TOKEN_COUNT as TokenID + 1;
+        let token = registered_token();
+
+        // Absent account
+        account_info!(account, pk, vec![]);
+        assert_eq!(
+            registered_elusiv_token(&account, USDC_TOKEN_ID),
+            Ok(TOKENS[USDC_TOKEN_ID as usize])
+        );
+        assert_eq!(
+            registered_elusiv_token(&account, token_id),
+            Err(TokenError::InvalidTokenID.into())
+        );
+
+        let mut data = vec![0; TokenRegistryAccount::SIZE];
+        let mut token_registry = TokenRegistryAccount::new(&mut data).unwrap();
+        token_registry.register_token(token_id, &token).unwrap();
+
+        // Invalid owner
+        account_info!(account, pk, data.clone(), Pubkey::new_unique(), false);
+        assert_eq!(
+            registered_elusiv_token(&account, token_id),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        account_info!(account, pk, data, crate::ID, false);
+        assert_eq!(
+            registered_elusiv_token(&account, token_id),
+            Ok(token.elusiv_token())
+        );
+
+        // Fallback for unregistered tokens
+        assert_eq!(
+            registered_elusiv_token(&account, USDC_TOKEN_ID),
+            Ok(TOKENS[USDC_TOKEN_ID as usize])
+        );
+    }
+
+    #[test]
+    fn test_register_token() {
+        let mut data = vec![0; TokenRegistryAccount::SIZE];
+        let mut token_registry = TokenRegistryAccount::new(&mut data).unwrap();
+        let token = registered_token();
+
+        for token_id in [0, TOKEN_REGISTRY_CAPACITY as TokenID + 1] {
+            assert_eq!(
+                token_registry.register_token(token_id, &token),
+                Err(TokenError::InvalidTokenID.into())
+            );
+            assert_eq!(token_registry.registered_token(token_id), None);
+        }
+
+        // Overrides the static metadata
+        token_registry
+            .register_token(USDC_TOKEN_ID, &token)
+            .unwrap();
+        assert_eq!(
+            token_registry.elusiv_token(USDC_TOKEN_ID),
+            Ok(token.elusiv_token())
+        );
+
+        token_registry
+            .register_token(TOKEN_REGISTRY_CAPACITY as TokenID, &token)
+            .unwrap();
+        assert_eq!(
+            token_registry.registered_token(TOKEN_REGISTRY_CAPACITY as TokenID),
+            Some(token)
+        );
+    }
+
+    #[test]
+    fn test_set_token_paused() {
+        let mut data = vec![0; TokenRegistryAccount::SIZE];
+        let mut token_registry = TokenRegistryAccount::new(&mut data).unwrap();
+
+        for token_id in 0..=TOKEN_REGISTRY_CAPACITY as TokenID {
+            assert!(!token_registry.is_paused(token_id));
+        }
+
+        assert_eq!(
+            token_registry.set_token_paused(TOKEN_REGISTRY_CAPACITY as TokenID + 1, true),
+            Err(TokenError::InvalidTokenID.into())
+        );
+        assert!(!token_registry.is_paused(TOKEN_REGISTRY_CAPACITY as TokenID + 1));
+
+        token_registry
+            .set_token_paused(USDC_TOKEN_ID, true)
+            .unwrap();
+        assert!(token_registry.is_paused(USDC_TOKEN_ID));
+        assert!(!token_registry.is_paused(0));
+
+        token_registry
+            .set_token_paused(USDC_TOKEN_ID, false)
+            .unwrap();
+        assert!(!token_registry.is_paused(USDC_TOKEN_ID));
+    }
+
+    #[test]
+    fn test_is_token_paused() {
+        let pk = TokenRegistryAccount::find(None).0;
+
+        // Absent account
+        account_info!(account, pk, vec![]);
+        assert_eq!(is_token_paused(&account, USDC_TOKEN_ID), Ok(false));
+
+        let mut data = vec![0; TokenRegistryAccount::SIZE];
+        let mut token_registry = TokenRegistryAccount::new(&mut data).unwrap();
+        token_registry
+            .set_token_paused(USDC_TOKEN_ID, true)
+            .unwrap();
+
+        // Invalid owner
+        account_info!(account, pk, data.clone(), Pubkey::new_unique(), false);
+        assert_eq!(
+            is_token_paused(&account, USDC_TOKEN_ID),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        account_info!(account, pk, data, crate::ID, false);
+        assert_eq!(is_token_paused(&account, USDC_TOKEN_ID), Ok(true));
+        assert_eq!(is_token_paused(&account, 0), Ok(false));
+    }
+}
@@ -1,6 +1,11 @@
+use crate::error::ElusivError;
+use crate::macros::{guard, pda_account};
+use crate::proof::verifier::ComputationDescriptor;
+use crate::proof::vkey::{is_built_in_vkey, VerifyingKey};
+use crate::types::U256;
 use elusiv_proc_macros::elusiv_account;
-use elusiv_types::{ChildAccount, ElusivOption, PDAAccountData};
-use solana_program::pubkey::Pubkey;
+use elusiv_types::{ChildAccount, ElusivOption, PDAAccount, PDAAccountData, ParentAccount};
+use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
 
 pub struct VKeyChildAccount;
 
@@ -20,10 +25,194 @@ pub struct VKeyAccount {
     pub authority: ElusivOption<Pubkey>,
     pub is_frozen: bool,
     pub version: u32,
+
+    /// The [`VKeyAccount::source_digest`] recorded when freezing the vkey
+    pub expected_source_digest: ElusivOption<U256>,
 }
 
 impl<'a, 'b, 't> VKeyAccount<'a, 'b, 't> {
     pub fn is_setup(&self) -> bool {
         self.get_version() != 0
     }
+
+    /// Computes the sha256-hash of the active [`VerifyingKey`] source (the first sub-account)
+    pub fn source_digest(&self) -> Result<U256, ProgramError> {
+        let len = VerifyingKey::source_size(self.get_public_inputs_count() as usize);
+
+        self.execute_on_child_account(0, |data| {
+            data.get(..len)
+                .map(|source| solana_program::hash::hash(source).to_bytes())
+                .ok_or(ElusivError::InvalidAccountState)
+        })?
+        .map_err(|e| e.into())
+    }
+
+    /// Verifies that the [`VKeyAccount::source_digest`] matches the `expected_source_digest` (if one has been recorded)
+    pub fn verify_source_digest(&self) -> Result<(), ProgramError> {
+        if let Some(expected) = self.get_expected_source_digest().option() {
+            guard!(
+                self.source_digest()? == expected,
+                ElusivError::InvalidVKeySourceDigest
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Account storing the [`ComputationDescriptor`] of a vkey that is not built-in (PDA with the vkey-id as offset)
+#[elusiv_account(eager_type: true)]
+pub struct ComputationDescriptorAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    pub descriptor: ComputationDescriptor,
+    pub is_setup: bool,
+}
+
+/// Reads the [`ComputationDescriptor`] of the vkey with `vkey_id`
+///
+/// # Notes
+///
+/// For built-in vkeys `computation_descriptor_account` is ignored.
+pub fn read_computation_descriptor(
+    computation_descriptor_account: &AccountInfo,
+    vkey_id: u32,
+    public_inputs_count: usize,
+) -> Result<ComputationDescriptor, ProgramError> {
+    if is_built_in_vkey(vkey_id) {
+        return Ok(ComputationDescriptor::built_in(public_inputs_count));
+    }
+
+    guard!(
+        !computation_descriptor_account.data_is_empty(),
+        ElusivError::InvalidAccount
+    );
+    guard!(
+        *computation_descriptor_account.owner == crate::ID,
+        ElusivError::InvalidAccount
+    );
+    ComputationDescriptorAccount::verify_account(computation_descriptor_account, Some(vkey_id))?;
+
+    pda_account!(
+        computation_descriptor,
+        ComputationDescriptorAccount,
+        computation_descriptor_account
+    );
+    guard!(
+        computation_descriptor.get_is_setup(),
+        ElusivError::InvalidAccountState
+    );
+
+    let descriptor = computation_descriptor.get_descriptor();
+    guard!(
+        descriptor.is_valid(public_inputs_count),
+        ElusivError::InvalidAccountState
+    );
+
+    Ok(descriptor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::account_info;
+    use crate::processor::vkey_account;
+    use crate::proof::vkey::{SendQuadraVKey, TestVKey, VerifyingKeyInfo};
+    use elusiv_types::{ProgramAccount, SizedAccount};
+
+    #[test]
+    fn test_source_digest() {
+        vkey_account!(vkey_account, SendQuadraVKey);
+        let digest = vkey_account.source_digest().unwrap();
+
+        // Stable
+        assert_eq!(vkey_account.source_digest(), Ok(digest));
+        assert_eq!(
+            digest,
+            solana_program::hash::hash(&SendQuadraVKey::verifying_key_source()).to_bytes()
+        );
+
+        // No recorded digest
+        assert_eq!(vkey_account.verify_source_digest(), Ok(()));
+
+        vkey_account.set_expected_source_digest(&ElusivOption::Some(digest));
+        assert_eq!(vkey_account.verify_source_digest(), Ok(()));
+
+        // Mutated source
+        vkey_account
+            .execute_on_child_account_mut(0, |data| data[0] ^= 1)
+            .unwrap();
+        assert_ne!(vkey_account.source_digest(), Ok(digest));
+        assert_eq!(
+            vkey_account.verify_source_digest(),
+            Err(ElusivError::InvalidVKeySourceDigest.into())
+        );
+    }
+
+    #[test]
+    fn test_read_computation_descriptor() {
+        let public_inputs_count = TestVKey::public_inputs_count();
+        let (pk, bump) = ComputationDescriptorAccount::find(Some(TestVKey::VKEY_ID));
+        let descriptor = ComputationDescriptor {
+            add_mixed_cost: 90,
+            add_cost: 120,
+            ..ComputationDescriptor::built_in(public_inputs_count)
+        };
+
+        // Built-in vkeys ignore the account
+        account_info!(account, pk, vec![]);
+        assert_eq!(
+            read_computation_descriptor(&account, SendQuadraVKey::VKEY_ID, public_inputs_count),
+            Ok(ComputationDescriptor::built_in(public_inputs_count))
+        );
+
+        // Absent account
+        account_info!(account, pk, vec![], crate::ID, false);
+        assert_eq!(
+            read_computation_descriptor(&account, TestVKey::VKEY_ID, public_inputs_count),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        let mut data = vec![0; ComputationDescriptorAccount::SIZE];
+        data[0] = bump;
+        let mut computation_descriptor = ComputationDescriptorAccount::new(&mut data).unwrap();
+        computation_descriptor.set_descriptor(&descriptor);
+
+        // Not setup
+        account_info!(account, pk, data.clone(), crate::ID, false);
+        assert_eq!(
+            read_computation_descriptor(&account, TestVKey::VKEY_ID, public_inputs_count),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+
+        let mut computation_descriptor = ComputationDescriptorAccount::new(&mut data).unwrap();
+        computation_descriptor.set_is_setup(&true);
+
+        // Invalid owner
+        account_info!(account, pk, data.clone(), Pubkey::new_unique(), false);
+        assert_eq!(
+            read_computation_descriptor(&account, TestVKey::VKEY_ID, public_inputs_count),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        // Invalid vkey-id
+        account_info!(account, pk, data.clone(), crate::ID, false);
+        assert_eq!(
+            read_computation_descriptor(&account, TestVKey::VKEY_ID + 1, public_inputs_count),
+            Err(ProgramError::InvalidSeeds)
+        );
+
+        // Invalid public inputs count
+        assert_eq!(
+            read_computation_descriptor(&account, TestVKey::VKEY_ID, public_inputs_count - 1),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+
+        assert_eq!(
+            read_computation_descriptor(&account, TestVKey::VKEY_ID, public_inputs_count),
+            Ok(descriptor)
+        );
+    }
 }
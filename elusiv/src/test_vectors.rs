@@ -0,0 +1,302 @@
+//! Canonical test vectors for cross-implementation compatibility (e.g. with the TypeScript SDK)
+//!
+//! The vectors are generated by the Rust implementations and committed as JSON in `test-vectors/vectors.json`.
+//! All 32-byte values are hex-encoded, field elements in little-endian byte order (as stored on-chain).
+//!
+//! # Regenerating the vectors
+//!
+//! A changed implementation is detected by the tests of this module.
+//! If the change is intended, the committed vectors can be regenerated with:
+//! `ELUSIV_REGENERATE_TEST_VECTORS=1 cargo test --features test-unit,test-vectors test_vectors`
+
+use crate::commitment::poseidon_hash::full_poseidon2_hash;
+use crate::fields::fr_to_u256_le;
+use crate::state::fee::ProgramFee;
+use crate::state::metadata::CommitmentMetadata;
+use crate::token::{rounding, Price, TokenPrice};
+use crate::types::{generate_hashed_inputs, OptionalFee, U256};
+use ark_bn254::Fr;
+use serde::{Deserialize, Serialize};
+use solana_program::pubkey::Pubkey;
+use std::str::FromStr;
+
+/// The committed vectors as JSON
+pub const COMMITTED_TEST_VECTORS_JSON: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/test-vectors/vectors.json"
+));
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+pub struct TestVectors {
+    pub hashed_inputs: Vec<HashedInputsVector>,
+    pub fees: Vec<FeeVector>,
+    pub poseidon: Vec<PoseidonVector>,
+}
+
+/// Inputs and result of [`generate_hashed_inputs`]
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+pub struct HashedInputsVector {
+    pub recipient: String,
+    pub identifier: String,
+    pub iv: String,
+    pub encrypted_owner: String,
+    pub transaction_reference: String,
+    pub is_associated_token_account: bool,
+    pub is_program_owned: bool,
+    pub view_tag: u8,
+    pub current_time: u64,
+    pub metadata: String,
+    pub optional_fee_collector: String,
+    pub optional_fee_amount: u64,
+    pub memo: Option<String>,
+    pub encrypted_memo: Option<String>,
+
+    pub hashed_inputs: String,
+}
+
+/// Inputs and results of [`ProgramFee::proof_verification_fee`] (using [`test_vectors_program_fee`])
+///
+/// # Note
+///
+/// Token amounts are `None` if the conversion fails.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+pub struct FeeVector {
+    pub token_id: u16,
+    pub amount: u64,
+    pub sol_usd_price: i64,
+    pub sol_usd_expo: i32,
+    pub token_usd_price: i64,
+    pub token_usd_expo: i32,
+    pub min_batching_rate: u32,
+    pub input_preparation_tx_count: usize,
+
+    pub commitment_hash_fee_lamports: u64,
+    pub proof_verification_computation_fee_lamports: u64,
+    pub network_fee: u64,
+    pub proof_verification_fee: Option<u64>,
+}
+
+/// Inputs (decimal) and result of [`full_poseidon2_hash`]
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+pub struct PoseidonVector {
+    pub a: String,
+    pub b: String,
+    pub hash: String,
+}
+
+/// Parses the committed vectors
+pub fn committed_test_vectors() -> serde_json::Result<TestVectors> {
+    serde_json::from_str(COMMITTED_TEST_VECTORS_JSON)
+}
+
+/// Generates all vectors with the current implementations
+pub fn generate_test_vectors() -> TestVectors {
+    TestVectors {
+        hashed_inputs: hashed_inputs_vectors(),
+        fees: fee_vectors(),
+        poseidon: poseidon_vectors(),
+    }
+}
+
+pub fn test_vectors_to_json(test_vectors: &TestVectors) -> serde_json::Result<String> {
+    serde_json::to_string_pretty(test_vectors)
+}
+
+/// The [`ProgramFee`] used for the [`FeeVector`]s
+pub fn test_vectors_program_fee() -> ProgramFee {
+    ProgramFee::new(5000, 11, 100, 33, 44, 300, 555).unwrap()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn bytes(seed: u8) -> U256 {
+    let mut bytes = [0; 32];
+    for (i, b) in bytes.iter_mut().enumerate() {
+        *b = seed.wrapping_mul(31).wrapping_add(i as u8);
+    }
+    bytes
+}
+
+fn hashed_inputs_vectors() -> Vec<HashedInputsVector> {
+    let mut vectors = Vec::new();
+
+    // All combinations of the flags, memo and encrypted memo
+    for i in 0..16u8 {
+        let is_associated_token_account = i & 1 != 0;
+        let is_program_owned = i & 0b10 != 0;
+        let memo = (i & 0b100 != 0).then(|| b"elusiv test vector".to_vec());
+        let encrypted_memo = (i & 0b1000 != 0).then(|| vec![i; 48]);
+
+        let recipient = bytes(1);
+        let identifier = bytes(2);
+        let iv = bytes(3);
+        let encrypted_owner = bytes(4);
+        let transaction_reference = if i % 3 == 0 { [0; 32] } else { bytes(5) };
+        let view_tag = i.wrapping_mul(17);
+        let current_time = 1_700_000_000 + u64::from(i) * 97;
+        let metadata: CommitmentMetadata = [i; 17];
+        let optional_fee = OptionalFee {
+            collector: Pubkey::new_from_array(bytes(6)),
+            amount: u64::from(i) * 1000,
+        };
+
+        let hashed_inputs = generate_hashed_inputs(
+            &recipient,
+            &identifier,
+            &iv,
+            &encrypted_owner,
+            &transaction_reference,
+            is_associated_token_account,
+            is_program_owned,
+            view_tag,
+            current_time,
+            &metadata,
+            &optional_fee,
+            &memo,
+            &encrypted_memo,
+            &None,
+        )
+        .unwrap();
+
+        vectors.push(HashedInputsVector {
+            recipient: hex(&recipient),
+            identifier: hex(&identifier),
+            iv: hex(&iv),
+            encrypted_owner: hex(&encrypted_owner),
+            transaction_reference: hex(&transaction_reference),
+            is_associated_token_account,
+            is_program_owned,
+            view_tag,
+            current_time,
+            metadata: hex(&metadata),
+            optional_fee_collector: hex(&optional_fee.collector.to_bytes()),
+            optional_fee_amount: optional_fee.amount,
+            memo: memo.as_deref().map(hex),
+            encrypted_memo: encrypted_memo.as_deref().map(hex),
+            hashed_inputs: hex(&hashed_inputs),
+        });
+    }
+
+    vectors
+}
+
+fn fee_vectors() -> Vec<FeeVector> {
+    let program_fee = test_vectors_program_fee();
+    let prices = [((39, 0), (1, 0)), ((2345, -2), (99, -2))];
+    let mut vectors = Vec::new();
+
+    for token_id in 0..=2 {
+        for amount in [0, 1_000, 1_000_000_000] {
+            for ((sol_usd_price, sol_usd_expo), (token_usd_price, token_usd_expo)) in prices {
+                for min_batching_rate in [0, 4] {
+                    for input_preparation_tx_count in [1, 10] {
+                        let sol_usd = Price {
+                            price: sol_usd_price,
+                            conf: 0,
+                            expo: sol_usd_expo,
+                        };
+                        let token_usd = Price {
+                            price: token_usd_price,
+                            conf: 0,
+                            expo: token_usd_expo,
+                        };
+                        let price =
+                            TokenPrice::new_from_sol_price(sol_usd, token_usd, token_id).unwrap();
+
+                        vectors.push(FeeVector {
+                            token_id,
+                            amount,
+                            sol_usd_price,
+                            sol_usd_expo,
+                            token_usd_price,
+                            token_usd_expo,
+                            min_batching_rate,
+                            input_preparation_tx_count,
+                            commitment_hash_fee_lamports: program_fee
+                                .commitment_hash_computation_fee(min_batching_rate)
+                                .0,
+                            proof_verification_computation_fee_lamports: program_fee
+                                .proof_verification_computation_fee(input_preparation_tx_count)
+                                .0,
+                            network_fee: program_fee.proof_network_fee.calc(amount, rounding::FEE),
+                            proof_verification_fee: program_fee
+                                .proof_verification_fee(
+                                    input_preparation_tx_count,
+                                    min_batching_rate,
+                                    amount,
+                                    token_id,
+                                    &price,
+                                )
+                                .ok()
+                                .map(|fee| fee.amount()),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    vectors
+}
+
+fn poseidon_vectors() -> Vec<PoseidonVector> {
+    [
+        ("0", "0"),
+        ("1", "2"),
+        ("4631032765893457899344", "3453623782378239237823937"),
+        (
+            "21888242871839275222246405745257275088548364400416034343698204186575808495616",
+            "1",
+        ),
+    ]
+    .iter()
+    .map(|(a, b)| {
+        let hash = full_poseidon2_hash(Fr::from_str(a).unwrap(), Fr::from_str(b).unwrap());
+        PoseidonVector {
+            a: a.to_string(),
+            b: b.to_string(),
+            hash: hex(&fr_to_u256_le(&hash)),
+        }
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vectors_match_committed() {
+        let generated = generate_test_vectors();
+
+        if std::env::var("ELUSIV_REGENERATE_TEST_VECTORS").is_ok() {
+            let path = concat!(env!("CARGO_MANIFEST_DIR"), "/test-vectors/vectors.json");
+            std::fs::write(path, test_vectors_to_json(&generated).unwrap() + "\n").unwrap();
+            return;
+        }
+
+        assert_eq!(committed_test_vectors().unwrap(), generated);
+    }
+
+    #[test]
+    fn test_vectors_coverage() {
+        let vectors = generate_test_vectors();
+
+        assert_eq!(vectors.hashed_inputs.len(), 16);
+        assert!(vectors.fees.iter().any(|v| v.token_id == 0));
+        assert!(vectors.fees.iter().any(|v| v.token_id > 0));
+
+        // poseidon(0, 0)
+        assert_eq!(
+            vectors.poseidon[0].hash,
+            hex(&fr_to_u256_le(
+                &Fr::from_str(
+                    "14744269619966411208579211824598458697587494354926760081771325075741142829156"
+                )
+                .unwrap()
+            ))
+        );
+    }
+}
@@ -1,9 +1,11 @@
+pub use elusiv_types::rounding;
 pub use elusiv_types::tokens::*;
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::macros::{account_info, pyth_price_account_info};
+    use rounding::{AmountKind, Rounding, RoundingDirection};
     use solana_program::{native_token::LAMPORTS_PER_SOL, pubkey::Pubkey};
     use std::{num::NonZeroU16, ops::Add, ops::Sub};
 
@@ -70,7 +72,7 @@ mod tests {
             conf: 100,
         };
         assert_eq!(
-            Token::new_from_price(0, price, true),
+            Token::new_from_price(0, price, true, rounding::PAYOUT),
             Err(TokenError::InvalidAmount)
         );
 
@@ -80,9 +82,13 @@ mod tests {
             conf: 100,
         };
         assert_eq!(
-            Token::new_from_price(0, price, true),
+            Token::new_from_price(0, price, true, rounding::PAYOUT),
             Ok(Token::Lamports(Lamports(1234567)))
         );
+        assert_eq!(
+            Token::new_from_price(0, price, true, rounding::FEE),
+            Ok(Token::Lamports(Lamports(1234568)))
+        );
     }
 
     #[test]
@@ -200,6 +206,37 @@ mod tests {
         assert!(!verify_token_account(&usdc_account, 2).unwrap());
     }
 
+    #[test]
+    fn test_verify_token_account_owner() {
+        let owner = Pubkey::new_unique();
+        account_info!(sol_account, owner, vec![]);
+
+        assert!(verify_token_account_owner(&sol_account, &owner, 0).unwrap());
+        assert!(!verify_token_account_owner(&sol_account, &Pubkey::new_unique(), 0).unwrap());
+        assert!(!verify_token_account_owner(&sol_account, &owner, 1).unwrap());
+
+        // Owner is a PDA (off-curve)
+        let (pda, _) = Pubkey::find_program_address(&[b"vault"], &Pubkey::new_unique());
+        account_info!(
+            usdc_account,
+            Pubkey::new_unique(),
+            spl_token_account_data_with_owner(USDC_TOKEN_ID, &pda),
+            spl_token::id(),
+            false
+        );
+        assert!(verify_token_account_owner(&usdc_account, &pda, USDC_TOKEN_ID).unwrap());
+        assert!(!verify_token_account_owner(&usdc_account, &owner, USDC_TOKEN_ID).unwrap());
+        assert!(!verify_token_account_owner(&usdc_account, &pda, USDT_TOKEN_ID).unwrap());
+
+        // Not owned by the token program
+        account_info!(
+            usdc_account,
+            Pubkey::new_unique(),
+            spl_token_account_data_with_owner(USDC_TOKEN_ID, &pda)
+        );
+        assert!(!verify_token_account_owner(&usdc_account, &pda, USDC_TOKEN_ID).unwrap());
+    }
+
     #[test]
     fn test_token_price_new() {
         let sol_usd = Price {
@@ -297,7 +334,7 @@ mod tests {
     fn test_token_price_new_lamports() {
         let price = TokenPrice::new_lamports();
         assert_eq!(
-            price.token_into_lamports(Token::Lamports(Lamports(123))),
+            price.token_into_lamports(Token::Lamports(Lamports(123)), rounding::FEE),
             Ok(Lamports(123))
         );
     }
@@ -323,7 +360,14 @@ mod tests {
         assert_eq!(
             12820512,
             price
-                .token_into_lamports(Token::new(USDC_TOKEN_ID, 1_000_000))
+                .token_into_lamports(Token::new(USDC_TOKEN_ID, 1_000_000), rounding::PAYOUT)
+                .unwrap()
+                .0
+        );
+        assert_eq!(
+            12820513,
+            price
+                .token_into_lamports(Token::new(USDC_TOKEN_ID, 1_000_000), rounding::FEE)
                 .unwrap()
                 .0
         );
@@ -332,7 +376,7 @@ mod tests {
         assert_eq!(
             1269230769,
             price
-                .token_into_lamports(Token::new(USDC_TOKEN_ID, 99_000_000))
+                .token_into_lamports(Token::new(USDC_TOKEN_ID, 99_000_000), rounding::PAYOUT)
                 .unwrap()
                 .0
         );
@@ -358,7 +402,14 @@ mod tests {
         assert_eq!(
             0,
             price
-                .lamports_into_token(&Lamports(1), USDC_TOKEN_ID)
+                .lamports_into_token(&Lamports(1), USDC_TOKEN_ID, rounding::PAYOUT)
+                .unwrap()
+                .amount()
+        );
+        assert_eq!(
+            1,
+            price
+                .lamports_into_token(&Lamports(1), USDC_TOKEN_ID, rounding::FEE)
                 .unwrap()
                 .amount()
         );
@@ -367,7 +418,7 @@ mod tests {
         assert_eq!(
             78,
             price
-                .lamports_into_token(&Lamports(1_000), USDC_TOKEN_ID)
+                .lamports_into_token(&Lamports(1_000), USDC_TOKEN_ID, rounding::FEE)
                 .unwrap()
                 .amount()
         );
@@ -376,12 +427,206 @@ mod tests {
         assert_eq!(
             99 * 39 * 2 * 1_000_000,
             price
-                .lamports_into_token(&Lamports(99 * LAMPORTS_PER_SOL), USDC_TOKEN_ID)
+                .lamports_into_token(
+                    &Lamports(99 * LAMPORTS_PER_SOL),
+                    USDC_TOKEN_ID,
+                    rounding::FEE
+                )
                 .unwrap()
                 .amount()
         );
     }
 
+    /// `numerator / denominator` of an exact conversion (`lamports_into_token` or `token_into_lamports`)
+    fn exact_conversion(
+        amount: u64,
+        from_usd: Price,
+        to_usd: Price,
+        exponent: i32,
+    ) -> (u128, u128) {
+        let mut numerator = amount as u128 * from_usd.price as u128;
+        let mut denominator = to_usd.price as u128;
+        if exponent >= 0 {
+            numerator *= 10u128.pow(exponent as u32);
+        } else {
+            denominator *= 10u128.pow(-exponent as u32);
+        }
+        (numerator, denominator)
+    }
+
+    fn assert_rounded(
+        result: Result<u64, TokenError>,
+        (numerator, denominator): (u128, u128),
+        rounding: Rounding,
+    ) {
+        let result = match result {
+            Ok(result) => result as u128,
+            Err(err) => {
+                // Only amounts exceeding the `u64` range cannot be represented
+                assert_eq!(err, TokenError::PriceError);
+                assert!(numerator / denominator >= u64::MAX as u128);
+                return;
+            }
+        };
+
+        if rounding.rounds_up() {
+            assert!(result * denominator >= numerator);
+            assert!(result == 0 || (result - 1) * denominator < numerator);
+        } else {
+            assert!(result * denominator <= numerator);
+            assert!((result + 1) * denominator > numerator);
+        }
+    }
+
+    #[test]
+    fn test_conversions_favor_pool() {
+        let decimals = usdc_token().decimals as i32;
+        let roundings = [
+            rounding::FEE,
+            rounding::PAYOUT,
+            rounding::REFUND,
+            Rounding {
+                kind: AmountKind::Fee,
+                direction: RoundingDirection::FavorUser,
+            },
+        ];
+
+        for lamports_usd_price in [1, 39, 2_123_456_789] {
+            for lamports_usd_expo in [-17, -12, -9] {
+                for token_usd_price in [1, 999_999, 100_000_000] {
+                    for token_usd_expo in [-8, -6, 0] {
+                        let lamports_usd = Price {
+                            price: lamports_usd_price,
+                            conf: 0,
+                            expo: lamports_usd_expo,
+                        };
+                        let token_usd = Price {
+                            price: token_usd_price,
+                            conf: 0,
+                            expo: token_usd_expo,
+                        };
+                        let price =
+                            TokenPrice::new_from_price(lamports_usd, token_usd, USDC_TOKEN_ID);
+
+                        for amount in [0, 1, 7, 1_000, 123_456_789, 1_000_000_000_000] {
+                            for rounding in roundings {
+                                let token = price.lamports_into_token(
+                                    &Lamports(amount),
+                                    USDC_TOKEN_ID,
+                                    rounding,
+                                );
+                                assert_rounded(
+                                    token.map(|token| token.amount()),
+                                    exact_conversion(
+                                        amount,
+                                        lamports_usd,
+                                        token_usd,
+                                        lamports_usd_expo - token_usd_expo + decimals,
+                                    ),
+                                    rounding,
+                                );
+
+                                let lamports = price.token_into_lamports(
+                                    Token::new(USDC_TOKEN_ID, amount),
+                                    rounding,
+                                );
+                                assert_rounded(
+                                    lamports.map(|lamports| lamports.0),
+                                    exact_conversion(
+                                        amount,
+                                        token_usd,
+                                        lamports_usd,
+                                        token_usd_expo - decimals - lamports_usd_expo,
+                                    ),
+                                    rounding,
+                                );
+                            }
+
+                            // Converting a fee back and forth never results in less than the original amount
+                            if let Ok(fee) =
+                                Lamports(amount).into_token(&price, USDC_TOKEN_ID, rounding::FEE)
+                            {
+                                if let Ok(lamports) = price.token_into_lamports(fee, rounding::FEE)
+                                {
+                                    assert!(lamports.0 >= amount);
+                                }
+                            }
+
+                            // Converting a payout back and forth never results in more than the original amount
+                            if let Ok(payout) =
+                                Lamports(amount).into_token(&price, USDC_TOKEN_ID, rounding::PAYOUT)
+                            {
+                                let lamports =
+                                    price.token_into_lamports(payout, rounding::PAYOUT).unwrap();
+                                assert!(lamports.0 <= amount);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_proof_verification_fee_components() {
+        use crate::state::fee::ProgramFee;
+
+        // The fee computed by the client (and checked by `init_verification_transfer_fee`) equals the components transferred at finalization
+        let program_fee = ProgramFee::new(5000, 11, 100, 33, 44, 300, 555).unwrap();
+        for (sol_usd, token_usd) in [(39, 1), (2_123, 999_999), (17, 100_000_000)] {
+            let price = TokenPrice::new_from_sol_price(
+                Price {
+                    price: sol_usd,
+                    conf: 0,
+                    expo: 0,
+                },
+                Price {
+                    price: token_usd,
+                    conf: 0,
+                    expo: -8,
+                },
+                USDC_TOKEN_ID,
+            )
+            .unwrap();
+
+            for amount in [0, 1, 9_999, 123_456_789] {
+                for input_preparation_tx_count in [0, 1, 7] {
+                    let fee = program_fee
+                        .proof_verification_fee(
+                            input_preparation_tx_count,
+                            0,
+                            amount,
+                            USDC_TOKEN_ID,
+                            &price,
+                        )
+                        .unwrap();
+
+                    let proof_verification_fee = program_fee
+                        .proof_verification_computation_fee(input_preparation_tx_count)
+                        .into_token(&price, USDC_TOKEN_ID, rounding::FEE)
+                        .unwrap();
+                    let commitment_hash_fee = program_fee
+                        .commitment_hash_computation_fee(0)
+                        .into_token(&price, USDC_TOKEN_ID, rounding::FEE)
+                        .unwrap();
+                    let network_fee = program_fee.proof_network_fee.calc(amount, rounding::FEE);
+                    let subvention = program_fee
+                        .proof_subvention
+                        .into_token(&price, USDC_TOKEN_ID, rounding::PAYOUT)
+                        .unwrap();
+
+                    assert_eq!(
+                        fee.amount(),
+                        proof_verification_fee.amount()
+                            + commitment_hash_fee.amount()
+                            + network_fee
+                            - subvention.amount()
+                    );
+                }
+            }
+        }
+    }
+
     #[test]
     fn test_pyth_price_account_data() {
         let price = Price {
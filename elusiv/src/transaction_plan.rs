@@ -0,0 +1,779 @@
+//! Client-side planning of finalize transaction sequences
+//!
+//! The finalize instructions need to be executed as siblings in a single transaction (see [`crate::processor::enforce_finalize_send_instructions_inner`]):
+//! - [`ElusivInstruction::FinalizeVerificationSend`] is directly followed by an arbitrary number of [`ElusivInstruction::FinalizeVerificationInsertNullifier`] and a single transfer instruction,
+//! - the optional memo instruction is the last instruction of that transaction (or the second-to-last if a Solana Pay transfer is used),
+//! - the optional Solana Pay transfer is the last instruction of that transaction.
+//!
+//! All other instructions (e.g. the creation of the recipient's associated-token-account) can be moved into preceding transactions.
+
+use crate::instruction::ElusivInstruction;
+use borsh::BorshDeserialize;
+use solana_program::instruction::Instruction;
+use solana_program::pubkey::Pubkey;
+use std::collections::HashMap;
+
+/// The maximum size of a serialized transaction (IPv6 MTU - headers)
+pub const MAX_TRANSACTION_SIZE: usize = 1232;
+
+const SIGNATURE_SIZE: usize = 64;
+const PUBKEY_SIZE: usize = 32;
+const BLOCKHASH_SIZE: usize = 32;
+const MESSAGE_HEADER_SIZE: usize = 3;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum FinalizePlanError {
+    /// The provided instructions do not form a valid finalize sequence
+    InvalidShape,
+
+    /// The instructions that need to be siblings do not fit into a single transaction
+    SiblingsExceedTransactionSize,
+
+    /// A single preceding instruction does not fit into a transaction
+    InstructionExceedsTransactionSize,
+}
+
+/// All instructions required for finalizing a single send-request
+pub struct FinalizeShape {
+    pub fee_payer: Pubkey,
+
+    /// Instructions that are placed at the start of every transaction (e.g. compute-budget instructions)
+    pub compute_budget_instructions: Vec<Instruction>,
+
+    /// Instructions that can be executed before (or in the same transaction as) the finalization (e.g. associated-token-account creation)
+    pub preceding_instructions: Vec<Instruction>,
+
+    /// [`ElusivInstruction::FinalizeVerificationSend`]
+    pub send: Instruction,
+
+    /// [`ElusivInstruction::FinalizeVerificationInsertNullifier`]s
+    pub insert_nullifiers: Vec<Instruction>,
+
+    /// [`ElusivInstruction::FinalizeVerificationTransferLamports`] or [`ElusivInstruction::FinalizeVerificationTransferToken`]
+    pub transfer: Instruction,
+
+    /// Required iff `uses_memo` is set for the [`ElusivInstruction::FinalizeVerificationSend`]
+    pub memo: Option<Instruction>,
+
+    pub solana_pay_transfer: Option<Instruction>,
+}
+
+impl FinalizeShape {
+    fn verify(&self) -> Result<(), FinalizePlanError> {
+        let uses_memo = match ElusivInstruction::try_from_slice(&self.send.data) {
+            Ok(ElusivInstruction::FinalizeVerificationSend { uses_memo, .. }) => uses_memo,
+            _ => return Err(FinalizePlanError::InvalidShape),
+        };
+        if uses_memo != self.memo.is_some() {
+            return Err(FinalizePlanError::InvalidShape);
+        }
+
+        // Verification-account-index and -generation
+        let verification_account = &self.send.data[1..6];
+        let is_finalize_instruction = |ix: &Instruction, variant_indices: &[u8]| {
+            ix.program_id == crate::id()
+                && ix.data.len() > 5
+                && variant_indices.contains(&ix.data[0])
+                && ix.data[1..6] == *verification_account
+        };
+
+        if self.send.program_id != crate::id()
+            || !self.insert_nullifiers.iter().all(|ix| {
+                is_finalize_instruction(
+                    ix,
+                    &[ElusivInstruction::FINALIZE_VERIFICATION_INSERT_NULLIFIER_INDEX],
+                )
+            })
+            || !is_finalize_instruction(
+                &self.transfer,
+                &[
+                    ElusivInstruction::FINALIZE_VERIFICATION_TRANSFER_LAMPORTS_INDEX,
+                    ElusivInstruction::FINALIZE_VERIFICATION_TRANSFER_TOKEN_INDEX,
+                ],
+            )
+        {
+            return Err(FinalizePlanError::InvalidShape);
+        }
+
+        Ok(())
+    }
+
+    /// The instructions that need to be siblings in the final transaction (in order)
+    fn siblings(&self) -> Vec<Instruction> {
+        let mut instructions = vec![self.send.clone()];
+        instructions.extend(self.insert_nullifiers.iter().cloned());
+        instructions.push(self.transfer.clone());
+        instructions.extend(self.memo.iter().cloned());
+        instructions.extend(self.solana_pay_transfer.iter().cloned());
+        instructions
+    }
+}
+
+/// Groups the instructions of a [`FinalizeShape`] into transactions that each fit into [`MAX_TRANSACTION_SIZE`]
+///
+/// # Notes
+///
+/// - If all instructions fit into a single transaction, only one transaction is returned.
+/// - Otherwise the preceding instructions are moved (in order) into as few preceding transactions as possible.
+/// - With `use_lut`, all non-signer accounts that are not invoked programs are assumed to be contained in the lookup-table.
+pub fn plan_finalize_transactions(
+    shape: FinalizeShape,
+    use_lut: Option<Pubkey>,
+) -> Result<Vec<Vec<Instruction>>, FinalizePlanError> {
+    shape.verify()?;
+
+    let with_compute_budget = |instructions: &[Instruction]| {
+        let mut v = shape.compute_budget_instructions.clone();
+        v.extend(instructions.iter().cloned());
+        v
+    };
+    let fits = |instructions: &[Instruction]| {
+        transaction_size(
+            &with_compute_budget(instructions),
+            &shape.fee_payer,
+            use_lut,
+        ) <= MAX_TRANSACTION_SIZE
+    };
+
+    let siblings = shape.siblings();
+    if !fits(&siblings) {
+        return Err(FinalizePlanError::SiblingsExceedTransactionSize);
+    }
+
+    // Single transaction
+    let mut all = shape.preceding_instructions.clone();
+    all.extend(siblings.iter().cloned());
+    if fits(&all) {
+        return Ok(vec![with_compute_budget(&all)]);
+    }
+
+    // Preceding transactions
+    let mut transactions = Vec::new();
+    let mut current: Vec<Instruction> = Vec::new();
+    for instruction in shape.preceding_instructions.iter() {
+        current.push(instruction.clone());
+        if fits(&current) {
+            continue;
+        }
+
+        current.pop();
+        if current.is_empty() {
+            return Err(FinalizePlanError::InstructionExceedsTransactionSize);
+        }
+        transactions.push(with_compute_budget(&current));
+        current = vec![instruction.clone()];
+
+        if !fits(&current) {
+            return Err(FinalizePlanError::InstructionExceedsTransactionSize);
+        }
+    }
+
+    // The remaining preceding instructions are merged into the final transaction if possible
+    let mut last = current.clone();
+    last.extend(siblings.iter().cloned());
+    if fits(&last) {
+        transactions.push(with_compute_budget(&last));
+    } else {
+        if !current.is_empty() {
+            transactions.push(with_compute_budget(&current));
+        }
+        transactions.push(with_compute_budget(&siblings));
+    }
+
+    Ok(transactions)
+}
+
+/// Returns the maximum number of the shape's [`ElusivInstruction::FinalizeVerificationInsertNullifier`]s (in order) that fit into the final transaction
+///
+/// # Notes
+///
+/// - The remaining siblings (send, transfer, memo, Solana Pay transfer) and the compute-budget instructions are always included.
+/// - With `use_lut`, all non-signer accounts that are not invoked programs are assumed to be contained in the lookup-table.
+pub fn max_insert_nullifier_instructions(
+    shape: &FinalizeShape,
+    use_lut: Option<Pubkey>,
+) -> Result<usize, FinalizePlanError> {
+    shape.verify()?;
+
+    let mut instructions = shape.compute_budget_instructions.clone();
+    instructions.push(shape.send.clone());
+    let insert_position = instructions.len();
+    instructions.push(shape.transfer.clone());
+    instructions.extend(shape.memo.iter().cloned());
+    instructions.extend(shape.solana_pay_transfer.iter().cloned());
+
+    if transaction_size(&instructions, &shape.fee_payer, use_lut) > MAX_TRANSACTION_SIZE {
+        return Err(FinalizePlanError::SiblingsExceedTransactionSize);
+    }
+
+    for (i, insert_nullifier) in shape.insert_nullifiers.iter().enumerate() {
+        instructions.insert(insert_position + i, insert_nullifier.clone());
+        if transaction_size(&instructions, &shape.fee_payer, use_lut) > MAX_TRANSACTION_SIZE {
+            return Ok(i);
+        }
+    }
+
+    Ok(shape.insert_nullifiers.len())
+}
+
+/// Computes the size of a serialized transaction containing `instructions`
+///
+/// # Notes
+///
+/// - Without `lut` a legacy transaction is assumed, with `lut` a versioned (v0) transaction.
+/// - With `lut`, all non-signer accounts that are not invoked programs are assumed to be contained in the lookup-table.
+pub fn transaction_size(
+    instructions: &[Instruction],
+    fee_payer: &Pubkey,
+    lut: Option<Pubkey>,
+) -> usize {
+    // (is_signer, is_writable, is_program)
+    let mut keys: HashMap<Pubkey, (bool, bool, bool)> = HashMap::new();
+    keys.insert(*fee_payer, (true, true, false));
+
+    for instruction in instructions {
+        keys.entry(instruction.program_id).or_default().2 = true;
+
+        for account in &instruction.accounts {
+            let key = keys.entry(account.pubkey).or_default();
+            key.0 |= account.is_signer;
+            key.1 |= account.is_writable;
+        }
+    }
+
+    let signers_count = keys.values().filter(|(s, _, _)| *s).count();
+    let (static_keys_count, lut_writable_count, lut_readonly_count) = match lut {
+        None => (keys.len(), 0, 0),
+        Some(_) => keys.values().fold(
+            (0, 0, 0),
+            |(s, w, r), (is_signer, is_writable, is_program)| {
+                if *is_signer || *is_program {
+                    (s + 1, w, r)
+                } else if *is_writable {
+                    (s, w + 1, r)
+                } else {
+                    (s, w, r + 1)
+                }
+            },
+        ),
+    };
+
+    let mut size = compact_u16_size(signers_count) + signers_count * SIGNATURE_SIZE;
+
+    // Message
+    if lut.is_some() {
+        size += 1; // version prefix
+    }
+    size += MESSAGE_HEADER_SIZE;
+    size += compact_u16_size(static_keys_count) + static_keys_count * PUBKEY_SIZE;
+    size += BLOCKHASH_SIZE;
+    size += compact_u16_size(instructions.len());
+    for instruction in instructions {
+        size += 1 // program-id index
+            + compact_u16_size(instruction.accounts.len())
+            + instruction.accounts.len()
+            + compact_u16_size(instruction.data.len())
+            + instruction.data.len();
+    }
+
+    // Address-table-lookups
+    if lut.is_some() {
+        if lut_writable_count + lut_readonly_count > 0 {
+            size += compact_u16_size(1)
+                + PUBKEY_SIZE
+                + compact_u16_size(lut_writable_count)
+                + lut_writable_count
+                + compact_u16_size(lut_readonly_count)
+                + lut_readonly_count;
+        } else {
+            size += compact_u16_size(0);
+        }
+    }
+
+    size
+}
+
+fn compact_u16_size(value: usize) -> usize {
+    match value {
+        0..=0x7f => 1,
+        0x80..=0x3fff => 2,
+        _ => 3,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::{UserAccount, WritableSignerAccount, WritableUserAccount};
+    use crate::processor::{
+        enforce_finalize_send_instructions_inner, enforce_instruction, get_memo_from_instructions,
+        FinalizeSendData, TestInstructionsSysvar,
+    };
+    use crate::state::governor::{NetworkFeeAccount, SPL_MEMO_PROGRAM_ID};
+    use crate::token::{elusiv_token, LAMPORTS_TOKEN_ID, USDC_TOKEN_ID};
+    use elusiv_types::PDAAccount;
+    use solana_program::instruction::AccountMeta;
+    use solana_program::system_instruction;
+
+    const VERIFICATION_ACCOUNT_INDEX: u8 = 3;
+
+    struct ShapeConfig {
+        uses_lamports: bool,
+        nullifier_count: usize,
+        memo: Option<Vec<u8>>,
+        solana_pay_transfer: bool,
+        encrypted_memo: Option<Vec<u8>>,
+        preceding_instructions_count: usize,
+    }
+
+    impl Default for ShapeConfig {
+        fn default() -> Self {
+            ShapeConfig {
+                uses_lamports: true,
+                nullifier_count: 1,
+                memo: None,
+                solana_pay_transfer: false,
+                encrypted_memo: None,
+                preceding_instructions_count: 0,
+            }
+        }
+    }
+
+    fn shape(config: &ShapeConfig) -> FinalizeShape {
+        let fee_payer = Pubkey::new_unique();
+        let fee_payer_account = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let recipient_wallet = Pubkey::new_unique();
+
+        let send = ElusivInstruction::finalize_verification_send_instruction(
+            VERIFICATION_ACCOUNT_INDEX,
+            0,
+            FinalizeSendData {
+                encrypted_memo: config.encrypted_memo.clone(),
+                ..Default::default()
+            },
+            config.memo.is_some(),
+            UserAccount(recipient),
+            UserAccount(Pubkey::new_unique()),
+            UserAccount(Pubkey::new_unique()),
+            UserAccount(fee_payer),
+        );
+
+        let insert_nullifiers = (0..config.nullifier_count)
+            .map(|i| {
+                ElusivInstruction::finalize_verification_insert_nullifier_instruction(
+                    VERIFICATION_ACCOUNT_INDEX,
+                    0,
+                    UserAccount(fee_payer),
+                    Some(i as u32),
+                    &[WritableUserAccount(Pubkey::new_unique())],
+                )
+            })
+            .collect();
+
+        let transfer = if config.uses_lamports {
+            ElusivInstruction::finalize_verification_transfer_lamports_instruction(
+                VERIFICATION_ACCOUNT_INDEX,
+                0,
+                WritableSignerAccount(fee_payer),
+                WritableUserAccount(recipient),
+                WritableUserAccount(Pubkey::new_unique()),
+                WritableUserAccount(Pubkey::new_unique()),
+                WritableUserAccount(fee_payer),
+                WritableUserAccount(NetworkFeeAccount::find(Some(LAMPORTS_TOKEN_ID.into())).0),
+            )
+        } else {
+            ElusivInstruction::finalize_verification_transfer_token_instruction(
+                VERIFICATION_ACCOUNT_INDEX,
+                0,
+                WritableSignerAccount(fee_payer),
+                WritableUserAccount(fee_payer_account),
+                WritableUserAccount(recipient),
+                WritableUserAccount(recipient_wallet),
+                WritableUserAccount(Pubkey::new_unique()),
+                WritableUserAccount(Pubkey::new_unique()),
+                WritableUserAccount(Pubkey::new_unique()),
+                WritableUserAccount(Pubkey::new_unique()),
+                UserAccount(elusiv_token(USDC_TOKEN_ID).unwrap().mint),
+                WritableUserAccount(fee_payer),
+                WritableUserAccount(NetworkFeeAccount::find(Some(USDC_TOKEN_ID.into())).0),
+            )
+        };
+
+        let solana_pay_transfer = if !config.solana_pay_transfer {
+            None
+        } else if config.uses_lamports {
+            Some(system_instruction::transfer(&fee_payer, &recipient, 123))
+        } else {
+            Some(
+                spl_token::instruction::transfer(
+                    &spl_token::id(),
+                    &fee_payer_account,
+                    &recipient,
+                    &fee_payer,
+                    &[&fee_payer],
+                    123,
+                )
+                .unwrap(),
+            )
+        };
+
+        let preceding_instructions = (0..config.preceding_instructions_count)
+            .map(|_| {
+                spl_associated_token_account::instruction::create_associated_token_account(
+                    &fee_payer,
+                    &Pubkey::new_unique(),
+                    &elusiv_token(USDC_TOKEN_ID).unwrap().mint,
+                    &spl_token::id(),
+                )
+            })
+            .collect();
+
+        FinalizeShape {
+            fee_payer,
+            compute_budget_instructions: vec![Instruction::new_with_bytes(
+                Pubkey::new_unique(),
+                &[2, 0, 0, 0, 0],
+                vec![],
+            )],
+            preceding_instructions,
+            send,
+            insert_nullifiers,
+            transfer,
+            memo: config
+                .memo
+                .as_ref()
+                .map(|memo| spl_memo::build_memo(memo, &[])),
+            solana_pay_transfer,
+        }
+    }
+
+    /// Replays all transactions through the on-chain sibling enforcement
+    fn replay(
+        config: &ShapeConfig,
+        transactions: &[Vec<Instruction>],
+        fee_payer: &Pubkey,
+        lut: Option<Pubkey>,
+    ) {
+        let mut send_count = 0;
+
+        for transaction in transactions {
+            assert!(transaction_size(transaction, fee_payer, lut) <= MAX_TRANSACTION_SIZE);
+
+            for (i, instruction) in transaction.iter().enumerate() {
+                let sysvar = TestInstructionsSysvar {
+                    current_index: Some(i as u16),
+                    instructions: transaction.clone(),
+                };
+
+                if instruction.program_id != crate::id() {
+                    continue;
+                }
+
+                match instruction.data[0] {
+                    ElusivInstruction::FINALIZE_VERIFICATION_SEND_INDEX => {
+                        send_count += 1;
+
+                        assert_eq!(
+                            enforce_finalize_send_instructions_inner(
+                                &sysvar,
+                                config.uses_lamports,
+                                VERIFICATION_ACCOUNT_INDEX,
+                                0
+                            ),
+                            Ok(())
+                        );
+
+                        if let Some(memo) = &config.memo {
+                            assert_eq!(
+                                get_memo_from_instructions(
+                                    &sysvar,
+                                    &[SPL_MEMO_PROGRAM_ID],
+                                    config.solana_pay_transfer,
+                                    true
+                                )
+                                .unwrap()
+                                .0,
+                                *memo
+                            );
+                        }
+                    }
+                    ElusivInstruction::FINALIZE_VERIFICATION_TRANSFER_LAMPORTS_INDEX
+                    | ElusivInstruction::FINALIZE_VERIFICATION_TRANSFER_TOKEN_INDEX
+                        if config.solana_pay_transfer =>
+                    {
+                        let last = transaction.last().unwrap();
+                        assert!(
+                            enforce_instruction(&sysvar, transaction.len() - 1, last, false)
+                                .is_ok()
+                        );
+                        assert!(last.program_id != crate::id());
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        assert_eq!(send_count, 1);
+    }
+
+    #[test]
+    fn test_plan_finalize_transactions() {
+        let configs = [
+            ShapeConfig::default(),
+            ShapeConfig {
+                nullifier_count: 2,
+                memo: Some(b"Hello".to_vec()),
+                solana_pay_transfer: true,
+                ..Default::default()
+            },
+            ShapeConfig {
+                uses_lamports: false,
+                memo: Some(vec![1; 8]),
+                preceding_instructions_count: 1,
+                ..Default::default()
+            },
+            split_config(),
+            // Multiple preceding transactions
+            ShapeConfig {
+                uses_lamports: false,
+                preceding_instructions_count: 12,
+                ..Default::default()
+            },
+        ];
+
+        for config in configs {
+            let shape = shape(&config);
+            let fee_payer = shape.fee_payer;
+            let transactions = plan_finalize_transactions(shape, None).unwrap();
+            replay(&config, &transactions, &fee_payer, None);
+
+            // All instructions are contained exactly once (ignoring compute-budget instructions)
+            let instruction_count: usize = transactions.iter().map(|t| t.len() - 1).sum();
+            assert_eq!(
+                instruction_count,
+                config.preceding_instructions_count
+                    + 2
+                    + config.nullifier_count
+                    + config.memo.is_some() as usize
+                    + config.solana_pay_transfer as usize
+            );
+        }
+    }
+
+    /// Token finalize with associated-token-account creation and Solana Pay transfer
+    /// - requires the associated-token-account creation to be moved into a preceding transaction
+    fn split_config() -> ShapeConfig {
+        ShapeConfig {
+            uses_lamports: false,
+            solana_pay_transfer: true,
+            preceding_instructions_count: 1,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_plan_finalize_transactions_split() {
+        let config = split_config();
+
+        let shape = shape(&config);
+        let fee_payer = shape.fee_payer;
+        let transactions = plan_finalize_transactions(shape, None).unwrap();
+        assert_eq!(transactions.len(), 2);
+        replay(&config, &transactions, &fee_payer, None);
+
+        // With a lookup-table everything fits into a single transaction
+        let shape = self::shape(&config);
+        let fee_payer = shape.fee_payer;
+        let lut = Some(Pubkey::new_unique());
+        let transactions = plan_finalize_transactions(shape, lut).unwrap();
+        assert_eq!(transactions.len(), 1);
+        replay(&config, &transactions, &fee_payer, lut);
+    }
+
+    #[test]
+    fn test_plan_finalize_transactions_lut() {
+        // Token finalize with memo and Solana Pay transfer
+        let config = ShapeConfig {
+            uses_lamports: false,
+            memo: Some(vec![1; 16]),
+            solana_pay_transfer: true,
+            ..Default::default()
+        };
+
+        // The siblings only fit into a single transaction with a lookup-table
+        assert_eq!(
+            plan_finalize_transactions(shape(&config), None).err(),
+            Some(FinalizePlanError::SiblingsExceedTransactionSize)
+        );
+
+        let shape = shape(&config);
+        let fee_payer = shape.fee_payer;
+        let lut = Some(Pubkey::new_unique());
+        let transactions = plan_finalize_transactions(shape, lut).unwrap();
+        assert_eq!(transactions.len(), 1);
+        replay(&config, &transactions, &fee_payer, lut);
+    }
+
+    #[test]
+    fn test_plan_finalize_transactions_unsatisfiable() {
+        // Memo too large for the sibling transaction
+        let config = ShapeConfig {
+            memo: Some(vec![1; MAX_TRANSACTION_SIZE]),
+            ..Default::default()
+        };
+        assert_eq!(
+            plan_finalize_transactions(shape(&config), None).err(),
+            Some(FinalizePlanError::SiblingsExceedTransactionSize)
+        );
+
+        // Preceding instruction too large
+        let mut s = shape(&ShapeConfig::default());
+        s.preceding_instructions = vec![spl_memo::build_memo(&[1; MAX_TRANSACTION_SIZE], &[])];
+        assert_eq!(
+            plan_finalize_transactions(s, None).err(),
+            Some(FinalizePlanError::InstructionExceedsTransactionSize)
+        );
+
+        // Memo instruction missing
+        let config = ShapeConfig {
+            memo: Some(b"Hello".to_vec()),
+            ..Default::default()
+        };
+        let mut s = shape(&config);
+        s.memo = None;
+        assert_eq!(
+            plan_finalize_transactions(s, None).err(),
+            Some(FinalizePlanError::InvalidShape)
+        );
+
+        // Memo instruction without `uses_memo`
+        let mut s = shape(&ShapeConfig::default());
+        s.memo = Some(spl_memo::build_memo(b"Hello", &[]));
+        assert_eq!(
+            plan_finalize_transactions(s, None).err(),
+            Some(FinalizePlanError::InvalidShape)
+        );
+
+        // Mismatched verification-account-index
+        let mut s = shape(&ShapeConfig::default());
+        s.transfer.data[1] += 1;
+        assert_eq!(
+            plan_finalize_transactions(s, None).err(),
+            Some(FinalizePlanError::InvalidShape)
+        );
+
+        // Mismatched verification-account-generation
+        let mut s = shape(&ShapeConfig::default());
+        s.insert_nullifiers[0].data[2] += 1;
+        assert_eq!(
+            plan_finalize_transactions(s, None).err(),
+            Some(FinalizePlanError::InvalidShape)
+        );
+
+        // Invalid leading instruction
+        let mut s = shape(&ShapeConfig::default());
+        s.send = s.transfer.clone();
+        assert_eq!(
+            plan_finalize_transactions(s, None).err(),
+            Some(FinalizePlanError::InvalidShape)
+        );
+    }
+
+    #[test]
+    fn test_max_insert_nullifier_instructions() {
+        // Low arity: all nullifiers fit
+        for nullifier_count in [1, 2] {
+            let config = ShapeConfig {
+                nullifier_count,
+                ..Default::default()
+            };
+            assert_eq!(
+                max_insert_nullifier_instructions(&shape(&config), None),
+                Ok(nullifier_count)
+            );
+        }
+
+        // High arity: only a prefix fits
+        for lut in [None, Some(Pubkey::new_unique())] {
+            let config = ShapeConfig {
+                nullifier_count: 128,
+                ..Default::default()
+            };
+            let max = max_insert_nullifier_instructions(&shape(&config), lut).unwrap();
+            assert!(max > 0);
+            assert!(max < config.nullifier_count);
+
+            let mut s = shape(&config);
+            s.insert_nullifiers.truncate(max);
+            assert!(plan_finalize_transactions(s, lut).is_ok());
+
+            let mut s = shape(&config);
+            s.insert_nullifiers.truncate(max + 1);
+            assert_eq!(
+                plan_finalize_transactions(s, lut).err(),
+                Some(FinalizePlanError::SiblingsExceedTransactionSize)
+            );
+        }
+
+        // A lookup-table allows more nullifiers
+        let config = ShapeConfig {
+            nullifier_count: 128,
+            ..Default::default()
+        };
+        assert!(
+            max_insert_nullifier_instructions(&shape(&config), Some(Pubkey::new_unique())).unwrap()
+                > max_insert_nullifier_instructions(&shape(&config), None).unwrap()
+        );
+
+        // Siblings without any nullifier do not fit
+        let config = ShapeConfig {
+            memo: Some(vec![1; MAX_TRANSACTION_SIZE]),
+            ..Default::default()
+        };
+        assert_eq!(
+            max_insert_nullifier_instructions(&shape(&config), None),
+            Err(FinalizePlanError::SiblingsExceedTransactionSize)
+        );
+    }
+
+    #[test]
+    fn test_transaction_size() {
+        let fee_payer = Pubkey::new_unique();
+
+        // Empty legacy transaction: signatures + header + keys + blockhash + instructions
+        assert_eq!(
+            transaction_size(&[], &fee_payer, None),
+            1 + 64 + 3 + 1 + 32 + 32 + 1
+        );
+
+        let a = Pubkey::new_unique();
+        let b = Pubkey::new_unique();
+        let instruction = Instruction::new_with_bytes(
+            crate::id(),
+            &[1, 2, 3],
+            vec![
+                AccountMeta::new(fee_payer, true),
+                AccountMeta::new(a, false),
+                AccountMeta::new_readonly(b, false),
+            ],
+        );
+
+        // Keys: fee_payer, program, a, b
+        assert_eq!(
+            transaction_size(std::slice::from_ref(&instruction), &fee_payer, None),
+            1 + 64 + 3 + 1 + 4 * 32 + 32 + 1 + (1 + 1 + 3 + 1 + 3)
+        );
+
+        // Static keys: fee_payer, program; lookup-table: a (writable), b (readonly)
+        assert_eq!(
+            transaction_size(&[instruction], &fee_payer, Some(Pubkey::new_unique())),
+            1 + 64 + 1 + 3 + 1 + 2 * 32 + 32 + 1 + (1 + 1 + 3 + 1 + 3) + (1 + 32 + 1 + 1 + 1 + 1)
+        );
+
+        assert_eq!(compact_u16_size(0x7f), 1);
+        assert_eq!(compact_u16_size(0x80), 2);
+        assert_eq!(compact_u16_size(0x3fff), 2);
+        assert_eq!(compact_u16_size(0x4000), 3);
+    }
+}
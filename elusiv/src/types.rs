@@ -1,10 +1,12 @@
 use crate::bytes::BorshSerDeSized;
+use crate::error::ElusivError;
 use crate::fields::{fr_to_u256_le, u256_to_big_uint, u64_to_u256_skip_mr, G1A, G2A};
-use crate::macros::BorshSerDeSized;
+use crate::macros::{guard, BorshSerDeSized};
 use crate::processor::MAX_MT_COUNT;
 use crate::proof::vkey::{MigrateUnaryVKey, SendQuadraVKey, VerifyingKeyInfo};
 use crate::state::metadata::CommitmentMetadata;
 use crate::state::proof::NullifierDuplicateAccount;
+use crate::state::vkey::VKeyAccount;
 use crate::u64_array;
 use ark_bn254::Fr;
 use ark_ff::PrimeField;
@@ -310,10 +312,19 @@ pub struct JoinSplitPublicInputs {
 }
 
 impl JoinSplitPublicInputs {
-    pub fn roots(&self) -> Vec<Option<RawU256>> {
-        self.input_commitments.iter().map(|c| c.root).collect()
+    /// The supplied MT-roots (one per distinct MT), in the order of the input commitments
+    ///
+    /// # Notes
+    ///
+    /// Input commitments without a root belong to the MT of the first root.
+    pub fn roots(&self) -> Vec<RawU256> {
+        self.input_commitments
+            .iter()
+            .filter_map(|c| c.root)
+            .collect()
     }
 
+    /// The nullifier-hashes of all input commitments
     pub fn nullifier_hashes(&self) -> Vec<RawU256> {
         self.input_commitments
             .iter()
@@ -321,6 +332,30 @@ impl JoinSplitPublicInputs {
             .collect()
     }
 
+    /// The position of the MT (in [`Self::roots`]) of each input commitment (commitments without a root belong to the first MT)
+    pub fn tree_positions(&self) -> Vec<usize> {
+        let mut next_tree_index = 0;
+        self.input_commitments
+            .iter()
+            .map(|c| match c.root {
+                Some(_) => {
+                    next_tree_index += 1;
+                    next_tree_index - 1
+                }
+                None => 0,
+            })
+            .collect()
+    }
+
+    /// The nullifier-hashes grouped by their MT (in the order of [`Self::roots`])
+    pub fn nullifier_hashes_per_tree(&self) -> Vec<Vec<&RawU256>> {
+        let mut trees: Vec<Vec<&RawU256>> = vec![Vec::new(); self.roots().len().max(1)];
+        for (c, tree_position) in self.input_commitments.iter().zip(self.tree_positions()) {
+            trees[tree_position].push(&c.nullifier_hash);
+        }
+        trees
+    }
+
     pub fn associated_nullifier_duplicate_pda_pubkey(&self) -> Pubkey {
         let nullifier_hashes: Vec<&RawU256> = self
             .input_commitments
@@ -331,6 +366,23 @@ impl JoinSplitPublicInputs {
         NullifierDuplicateAccount::associated_pubkey(&nullifier_hashes)
     }
 
+    /// The nullifier-duplicate PDAs of all MTs referenced by the join-split (one per MT)
+    ///
+    /// # Notes
+    ///
+    /// For a single-MT join-split this is equal to [`Self::nullifier_duplicate_pda`].
+    pub fn nullifier_duplicate_pdas(&self) -> Vec<(Pubkey, u8)> {
+        self.nullifier_hashes_per_tree()
+            .iter()
+            .map(|nullifier_hashes| {
+                NullifierDuplicateAccount::find_with_pubkey(
+                    NullifierDuplicateAccount::associated_pubkey(nullifier_hashes),
+                    None,
+                )
+            })
+            .collect()
+    }
+
     pub fn nullifier_duplicate_pda(&self) -> (Pubkey, u8) {
         NullifierDuplicateAccount::find_with_pubkey(
             self.associated_nullifier_duplicate_pda_pubkey(),
@@ -349,6 +401,7 @@ impl JoinSplitPublicInputs {
         )
     }
 
+    /// The `amount` including the `fee` (the value expected as [`crate::processor::FinalizeSendData::total_amount`])
     pub fn total_amount(&self) -> u64 {
         self.amount + self.fee
     }
@@ -388,6 +441,11 @@ pub trait PublicInputs {
     fn public_signals_skip_mr(&self) -> Vec<U256> {
         self.public_signals().iter().map(|&p| p.skip_mr()).collect()
     }
+
+    /// Returns true if the number of public signals matches the `public_inputs_count` of the vkey
+    fn matches_vkey(&self, vkey: &VKeyAccount) -> bool {
+        self.public_signals().len() == vkey.get_public_inputs_count() as usize
+    }
 }
 
 /// https://github.com/elusiv-privacy/circuits/blob/master/circuits/main/send_quadra.circom
@@ -399,6 +457,33 @@ pub struct SendPublicInputs {
     pub recipient_is_associated_token_account: bool,
     pub solana_pay_transfer: bool,
     pub hashed_inputs: U256,
+
+    /// The recipient is a program-owned wallet (e.g. a multisig vault PDA)
+    /// - for tokens, the associated-token-account path is skipped and the recipient token-account's owner has to be the `recipient_wallet`
+    /// - for lamports, `amount` is transferred directly to the `recipient_wallet` (unflagged recipients have to be system-owned with the governor's `enforce_system_owned_lamports_recipient`)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub recipient_is_program_owned: bool,
+
+    /// Ownership hint derived client-side from the shared secret (bound into `hashed_inputs`)
+    /// - emitted next to the output commitment, allowing wallets to skip the trial-decryption of most commitments
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub view_tag: u8,
+
+    /// Unix timestamp asserted by the sender (bound into `hashed_inputs`)
+    /// - `init_verification` rejects stale and future timestamps (see [`crate::processor::is_timestamp_valid`])
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub current_time: u64,
+}
+
+/// Maximum size of a (plaintext) memo supplied with an spl-memo instruction
+pub const MAX_MEMO_SIZE: usize = 256;
+
+/// Maximum size of an encrypted memo (encrypted client-side to the recipient)
+pub const MAX_ENCRYPTED_MEMO_SIZE: usize = 256;
+
+/// An encrypted memo is an opaque non-empty byte blob of at most [`MAX_ENCRYPTED_MEMO_SIZE`] bytes
+pub fn is_encrypted_memo_valid(encrypted_memo: &[u8]) -> bool {
+    !encrypted_memo.is_empty() && encrypted_memo.len() <= MAX_ENCRYPTED_MEMO_SIZE
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -409,29 +494,54 @@ pub fn generate_hashed_inputs(
     encrypted_owner: &U256,
     transaction_reference: &U256,
     is_associated_token_account: bool,
+    is_program_owned: bool,
+    view_tag: u8,
+    current_time: u64,
     metadata: &CommitmentMetadata,
     optional_fee: &OptionalFee,
     memo: &Option<Vec<u8>>,
-) -> U256 {
+    encrypted_memo: &Option<Vec<u8>>,
+    fallback_recipient: &Option<U256>,
+) -> Result<U256, ProgramError> {
+    if let Some(memo) = memo {
+        guard!(memo.len() <= MAX_MEMO_SIZE, ElusivError::MemoTooLarge);
+    }
+
     let mut data = recipient.to_vec();
     data.extend(identifier);
     data.extend(iv);
     data.extend(encrypted_owner);
     data.extend(transaction_reference);
-    data.extend([u8::from(is_associated_token_account)]);
+    data.extend([u8::from(is_associated_token_account)
+        | (u8::from(is_program_owned) << 1)
+        | (u8::from(fallback_recipient.is_some()) << 2)
+        | (u8::from(encrypted_memo.is_some()) << 3)]);
+    data.extend([view_tag]);
+    data.extend(current_time.to_le_bytes());
     data.extend(metadata);
     data.extend(optional_fee.collector.to_bytes());
     data.extend(optional_fee.amount.to_le_bytes());
 
+    // The fallback recipient is flagged above (so a hash without it remains unchanged)
+    if let Some(fallback_recipient) = fallback_recipient {
+        data.extend(fallback_recipient);
+    }
+
     if let Some(memo) = memo {
         data.extend(memo);
     }
 
+    // The encrypted memo is flagged above and treated as opaque bytes (length-prefixed)
+    if let Some(encrypted_memo) = encrypted_memo {
+        data.extend((encrypted_memo.len() as u32).to_le_bytes());
+        data.extend(encrypted_memo);
+    }
+
     let mut hash = solana_program::hash::hash(&data).to_bytes();
 
     // mask the lower 253 bits
     hash[31] &= 0b11111;
-    hash
+    Ok(hash)
 }
 
 /// https://github.com/elusiv-privacy/circuits/blob/master/circuits/main/migrate_unary.circom
@@ -627,6 +737,7 @@ mod test {
         proof::verifier::proof_from_str,
     };
     use ark_bn254::{Fq, Fq2, G1Affine, G2Affine};
+    use elusiv_types::{ProgramAccount, SizedAccount};
     use std::str::FromStr;
 
     #[test]
@@ -731,6 +842,130 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_join_split_public_inputs_roots_and_nullifier_hashes() {
+        let inputs = JoinSplitPublicInputs {
+            input_commitments: vec![
+                InputCommitment {
+                    root: Some(RawU256::new(u256_from_str_skip_mr("1"))),
+                    nullifier_hash: RawU256::new(u256_from_str_skip_mr("10")),
+                },
+                InputCommitment {
+                    root: None,
+                    nullifier_hash: RawU256::new(u256_from_str_skip_mr("11")),
+                },
+                InputCommitment {
+                    root: Some(RawU256::new(u256_from_str_skip_mr("2"))),
+                    nullifier_hash: RawU256::new(u256_from_str_skip_mr("12")),
+                },
+                InputCommitment {
+                    root: None,
+                    nullifier_hash: RawU256::new(u256_from_str_skip_mr("13")),
+                },
+            ],
+            output_commitment: RawU256::new(u256_from_str_skip_mr("44444")),
+            recent_commitment_index: 123,
+            fee_version: 0,
+            amount: 0,
+            fee: 0,
+            optional_fee: OptionalFee::default(),
+            token_id: 0,
+            metadata: CommitmentMetadata::default(),
+        };
+
+        let mut roots = Vec::new();
+        let mut nullifier_hashes = Vec::new();
+        for input_commitment in &inputs.input_commitments {
+            if let Some(root) = input_commitment.root {
+                roots.push(root);
+            }
+            nullifier_hashes.push(input_commitment.nullifier_hash);
+        }
+
+        assert_eq!(inputs.roots(), roots);
+        assert_eq!(
+            inputs.roots(),
+            vec![
+                RawU256::new(u256_from_str_skip_mr("1")),
+                RawU256::new(u256_from_str_skip_mr("2")),
+            ]
+        );
+
+        assert_eq!(inputs.nullifier_hashes(), nullifier_hashes);
+        assert_eq!(inputs.nullifier_hashes().len(), 4);
+        assert_eq!(
+            inputs.nullifier_hashes()[2],
+            RawU256::new(u256_from_str_skip_mr("12"))
+        );
+    }
+
+    #[test]
+    fn test_nullifier_duplicate_pdas() {
+        let input_commitment = |root: Option<&str>, nullifier_hash: &str| InputCommitment {
+            root: root.map(|r| RawU256::new(u256_from_str_skip_mr(r))),
+            nullifier_hash: RawU256::new(u256_from_str_skip_mr(nullifier_hash)),
+        };
+        let mut inputs = JoinSplitPublicInputs {
+            input_commitments: vec![
+                input_commitment(Some("1"), "10"),
+                input_commitment(None, "11"),
+            ],
+            output_commitment: RawU256::new(u256_from_str_skip_mr("44444")),
+            recent_commitment_index: 123,
+            fee_version: 0,
+            amount: 0,
+            fee: 0,
+            optional_fee: OptionalFee::default(),
+            token_id: 0,
+            metadata: CommitmentMetadata::default(),
+        };
+
+        // Single MT
+        assert_eq!(
+            inputs.nullifier_hashes_per_tree(),
+            vec![vec![
+                &inputs.input_commitments[0].nullifier_hash,
+                &inputs.input_commitments[1].nullifier_hash,
+            ]]
+        );
+        assert_eq!(
+            inputs.nullifier_duplicate_pdas(),
+            vec![inputs.nullifier_duplicate_pda()]
+        );
+
+        // Two MTs
+        inputs.input_commitments = vec![
+            input_commitment(Some("1"), "10"),
+            input_commitment(None, "11"),
+            input_commitment(Some("2"), "12"),
+            input_commitment(None, "13"),
+        ];
+        let n = |i: usize| &inputs.input_commitments[i].nullifier_hash;
+        assert_eq!(
+            inputs.nullifier_hashes_per_tree(),
+            vec![vec![n(0), n(1), n(3)], vec![n(2)]]
+        );
+
+        let pdas = inputs.nullifier_duplicate_pdas();
+        assert_eq!(pdas.len(), 2);
+        assert_eq!(
+            pdas[0],
+            NullifierDuplicateAccount::find_with_pubkey(
+                NullifierDuplicateAccount::associated_pubkey(&[n(0), n(1), n(3)]),
+                None
+            )
+        );
+        assert_eq!(
+            pdas[1],
+            NullifierDuplicateAccount::find_with_pubkey(
+                NullifierDuplicateAccount::associated_pubkey(&[n(2)]),
+                None
+            )
+        );
+        assert_ne!(pdas[0], pdas[1]);
+        assert!(!pdas.contains(&inputs.nullifier_duplicate_pda()));
+    }
+
     #[test]
     fn test_send_public_inputs_verify() {
         let valid_inputs = SendPublicInputs {
@@ -757,6 +992,9 @@ mod test {
             hashed_inputs: [0; 32],
             recipient_is_associated_token_account: true,
             solana_pay_transfer: false,
+            recipient_is_program_owned: false,
+            view_tag: 0,
+            current_time: 0,
         };
         assert!(valid_inputs.verify_additional_constraints());
 
@@ -802,6 +1040,9 @@ mod test {
             hashed_inputs: u256_from_str_skip_mr("306186522190603117929438292402982536627"),
             recipient_is_associated_token_account: true,
             solana_pay_transfer: false,
+            recipient_is_program_owned: false,
+            view_tag: 0,
+            current_time: 0,
         };
 
         let expected = [
@@ -828,6 +1069,48 @@ mod test {
         assert_eq!(expected.len(), SendPublicInputs::PUBLIC_INPUTS_COUNT);
     }
 
+    #[test]
+    fn test_public_inputs_matches_vkey() {
+        let inputs = SendPublicInputs {
+            join_split: JoinSplitPublicInputs {
+                input_commitments: vec![InputCommitment {
+                    root: Some(RawU256::new(u256_from_str_skip_mr("1"))),
+                    nullifier_hash: RawU256::new(u256_from_str_skip_mr("2")),
+                }],
+                output_commitment: RawU256::new(u256_from_str_skip_mr("3")),
+                recent_commitment_index: 0,
+                fee_version: 0,
+                amount: 0,
+                fee: 0,
+                optional_fee: OptionalFee::default(),
+                token_id: 0,
+                metadata: CommitmentMetadata::default(),
+            },
+            hashed_inputs: u256_from_str_skip_mr("4"),
+            recipient_is_associated_token_account: false,
+            solana_pay_transfer: false,
+            recipient_is_program_owned: false,
+            view_tag: 0,
+            current_time: 0,
+        };
+
+        let mut data = vec![0; VKeyAccount::SIZE];
+        let mut vkey = VKeyAccount::new(&mut data).unwrap();
+
+        vkey.set_public_inputs_count(&SendQuadraVKey::PUBLIC_INPUTS_COUNT);
+        assert!(inputs.matches_vkey(&vkey));
+
+        // Mismatched public inputs count
+        for count in [
+            0,
+            MigrateUnaryVKey::PUBLIC_INPUTS_COUNT,
+            SendQuadraVKey::PUBLIC_INPUTS_COUNT + 1,
+        ] {
+            vkey.set_public_inputs_count(&count);
+            assert!(!inputs.matches_vkey(&vkey));
+        }
+    }
+
     #[test]
     fn test_join_split_public_inputs_size() {
         let mut input_commitments = vec![
@@ -1048,7 +1331,7 @@ mod test {
         };
 
         let expected = u256_from_str_skip_mr(
-            "5593953132782974239527342909647286690390142208813910555015910557707363192433",
+            "8890461405275944235570622483887357752771615036910967759365333246932490849387",
         );
 
         assert_eq!(
@@ -1059,16 +1342,22 @@ mod test {
                 &encrypted_owner,
                 &solana_pay_id,
                 is_associated_token_account,
+                false,
+                0,
+                0,
                 &metadata,
                 &optional_fee,
+                &None,
+                &None,
                 &None
-            ),
+            )
+            .unwrap(),
             expected
         );
 
         let memo = Some(vec![1, 6, 7, 88, 88, 8, 8, 8, 8, 84, 3]);
         let expected = u256_from_str_skip_mr(
-            "7190753645577115026314391505244643580055580854837751025314898582887072501874",
+            "1602918099810385224960782384976493082836091170273756834437917698283624658162",
         );
 
         assert_eq!(
@@ -1079,11 +1368,189 @@ mod test {
                 &encrypted_owner,
                 &solana_pay_id,
                 is_associated_token_account,
+                false,
+                0,
+                0,
                 &metadata,
                 &optional_fee,
-                &memo
-            ),
+                &memo,
+                &None,
+                &None
+            )
+            .unwrap(),
             expected
         );
     }
+
+    #[test]
+    fn test_compute_hashed_inputs_encrypted_memo() {
+        let hash = |memo: Option<Vec<u8>>, encrypted_memo: Option<Vec<u8>>| {
+            generate_hashed_inputs(
+                &[1; 32],
+                &[2; 32],
+                &[3; 32],
+                &[4; 32],
+                &[5; 32],
+                false,
+                false,
+                0,
+                0,
+                &CommitmentMetadata::default(),
+                &OptionalFee::default(),
+                &memo,
+                &encrypted_memo,
+                &None,
+            )
+            .unwrap()
+        };
+
+        let encrypted_memo = vec![9; MAX_ENCRYPTED_MEMO_SIZE];
+        let h = hash(None, Some(encrypted_memo.clone()));
+
+        assert_eq!(h, hash(None, Some(encrypted_memo.clone())));
+        assert_ne!(h, hash(None, None));
+        assert_ne!(h, hash(Some(encrypted_memo.clone()), None));
+        assert_ne!(h, hash(None, Some(encrypted_memo[1..].to_vec())));
+
+        // Bytes can't be moved between the memo and the encrypted memo
+        assert_ne!(
+            hash(Some(vec![1, 2]), Some(vec![3])),
+            hash(Some(vec![1]), Some(vec![2, 3]))
+        );
+
+        // An encrypted memo can't be smuggled into the plaintext memo (`memo || len || encrypted_memo`)
+        let memo = vec![1, 2];
+        let smuggled_encrypted_memo = vec![9; 16];
+        let mut combined_memo = memo.clone();
+        combined_memo.extend((smuggled_encrypted_memo.len() as u32).to_le_bytes());
+        combined_memo.extend(&smuggled_encrypted_memo);
+        assert_ne!(
+            hash(Some(combined_memo), None),
+            hash(Some(memo), Some(smuggled_encrypted_memo))
+        );
+    }
+
+    #[test]
+    fn test_compute_hashed_inputs_fallback_recipient() {
+        let hash = |is_associated_token_account: bool, fallback_recipient: Option<U256>| {
+            generate_hashed_inputs(
+                &[1; 32],
+                &[2; 32],
+                &[3; 32],
+                &[4; 32],
+                &[5; 32],
+                is_associated_token_account,
+                false,
+                0,
+                0,
+                &CommitmentMetadata::default(),
+                &OptionalFee::default(),
+                &None,
+                &None,
+                &fallback_recipient,
+            )
+            .unwrap()
+        };
+
+        let h = hash(false, Some([6; 32]));
+        assert_eq!(h, hash(false, Some([6; 32])));
+        assert_ne!(h, hash(false, None));
+        assert_ne!(h, hash(false, Some([7; 32])));
+
+        // The fallback recipient can't be confused with the recipient flags
+        assert_ne!(hash(false, Some([0; 32])), hash(true, None));
+    }
+
+    #[test]
+    fn test_compute_hashed_inputs_memo_size() {
+        let hash = |memo: Vec<u8>| {
+            generate_hashed_inputs(
+                &[1; 32],
+                &[2; 32],
+                &[3; 32],
+                &[4; 32],
+                &[5; 32],
+                false,
+                false,
+                0,
+                0,
+                &CommitmentMetadata::default(),
+                &OptionalFee::default(),
+                &Some(memo),
+                &None,
+                &None,
+            )
+        };
+
+        assert!(hash(vec![1; MAX_MEMO_SIZE]).is_ok());
+        assert_eq!(
+            hash(vec![1; MAX_MEMO_SIZE + 1]),
+            Err(ElusivError::MemoTooLarge.into())
+        );
+    }
+
+    #[test]
+    fn test_compute_hashed_inputs_view_tag() {
+        let hash = |is_program_owned: bool, view_tag: u8| {
+            generate_hashed_inputs(
+                &[1; 32],
+                &[2; 32],
+                &[3; 32],
+                &[4; 32],
+                &[5; 32],
+                false,
+                is_program_owned,
+                view_tag,
+                0,
+                &CommitmentMetadata::default(),
+                &OptionalFee::default(),
+                &None,
+                &None,
+                &None,
+            )
+            .unwrap()
+        };
+
+        assert_eq!(hash(false, 1), hash(false, 1));
+        assert_ne!(hash(false, 0), hash(false, 1));
+        assert_ne!(hash(false, 1), hash(false, 255));
+
+        // The view tag can't be confused with the recipient flags
+        assert_ne!(hash(true, 0), hash(false, 2));
+    }
+
+    #[test]
+    fn test_compute_hashed_inputs_current_time() {
+        let hash = |view_tag: u8, current_time: u64| {
+            generate_hashed_inputs(
+                &[1; 32],
+                &[2; 32],
+                &[3; 32],
+                &[4; 32],
+                &[5; 32],
+                false,
+                false,
+                view_tag,
+                current_time,
+                &CommitmentMetadata::default(),
+                &OptionalFee::default(),
+                &None,
+                &None,
+                &None,
+            )
+            .unwrap()
+        };
+
+        assert_eq!(hash(0, 1_700_000_000), hash(0, 1_700_000_000));
+        assert_ne!(hash(0, 1_700_000_000), hash(0, 1_700_000_001));
+        assert_ne!(hash(1, 1_700_000_000), hash(0, 1_700_000_000));
+    }
+
+    #[test]
+    fn test_is_encrypted_memo_valid() {
+        assert!(!is_encrypted_memo_valid(&[]));
+        assert!(is_encrypted_memo_valid(&[0]));
+        assert!(is_encrypted_memo_valid(&[0; MAX_ENCRYPTED_MEMO_SIZE]));
+        assert!(!is_encrypted_memo_valid(&[0; MAX_ENCRYPTED_MEMO_SIZE + 1]));
+    }
 }
@@ -278,6 +278,7 @@ async fn test_reset_active_mt() {
                 commitment: [0; 32],
                 min_batching_rate: 1,
                 fee_version: 0,
+                view_tag: 0,
             })
             .unwrap();
         queue
@@ -285,6 +286,7 @@ async fn test_reset_active_mt() {
                 commitment: [0; 32],
                 min_batching_rate: 1,
                 fee_version: 0,
+                view_tag: 0,
             })
             .unwrap();
     })
@@ -26,7 +26,7 @@ use elusiv::{
         queue::{Queue, RingQueue},
         storage::{StorageAccount, EMPTY_TREE, MT_HEIGHT},
     },
-    token::{Lamports, Token, TokenPrice, LAMPORTS_TOKEN_ID, USDC_TOKEN_ID},
+    token::{rounding, Lamports, Token, TokenPrice, LAMPORTS_TOKEN_ID, USDC_TOKEN_ID},
     types::{RawU256, U256},
 };
 use elusiv_computation::PartialComputation;
@@ -94,7 +94,9 @@ async fn test_store_base_commitment_lamports_transfer() {
         + fee.commitment_hash_computation_fee(request.min_batching_rate))
     .unwrap()
     .0;
-    let network_fee = fee.base_commitment_network_fee.calc(request.amount);
+    let network_fee = fee
+        .base_commitment_network_fee
+        .calc(request.amount, rounding::FEE);
     let hashing_account_rent = test.rent(BaseCommitmentHashingAccount::SIZE).await;
 
     client
@@ -205,15 +207,18 @@ async fn test_store_base_commitment_token_transfer() {
     let fee = genesis_fee(&mut test).await;
     let subvention = fee
         .base_commitment_subvention
-        .into_token(&price, USDC_TOKEN_ID)
+        .into_token(&price, USDC_TOKEN_ID, rounding::PAYOUT)
         .unwrap();
     let computation_fee = (fee.base_commitment_hash_computation_fee()
         + fee.commitment_hash_computation_fee(request.min_batching_rate))
     .unwrap();
-    let computation_fee_token = computation_fee.into_token(&price, USDC_TOKEN_ID).unwrap();
+    let computation_fee_token = computation_fee
+        .into_token(&price, USDC_TOKEN_ID, rounding::FEE)
+        .unwrap();
     let network_fee = Token::new(
         USDC_TOKEN_ID,
-        fee.base_commitment_network_fee.calc(request.amount),
+        fee.base_commitment_network_fee
+            .calc(request.amount, rounding::FEE),
     );
     let hashing_account_rent = test.rent(BaseCommitmentHashingAccount::SIZE).await;
 
@@ -339,7 +344,9 @@ async fn test_base_commitment_lamports() {
         + fee.commitment_hash_computation_fee(request0.min_batching_rate))
     .unwrap()
     .0;
-    let network_fee = fee.base_commitment_network_fee.calc(request0.amount);
+    let network_fee = fee
+        .base_commitment_network_fee
+        .calc(request0.amount, rounding::FEE);
 
     client
         .airdrop(
@@ -430,7 +437,9 @@ async fn test_base_commitment_lamports() {
     assert_eq!(0, warden_a.lamports(&mut test).await);
 
     // Client stores the second request
-    let network_fee1 = fee.base_commitment_network_fee.calc(request1.amount);
+    let network_fee1 = fee
+        .base_commitment_network_fee
+        .calc(request1.amount, rounding::FEE);
     client
         .airdrop(
             LAMPORTS_TOKEN_ID,
@@ -650,15 +659,18 @@ async fn test_base_commitment_token() {
     let fee = genesis_fee(&mut test).await;
     let subvention = fee
         .base_commitment_subvention
-        .into_token(&price, USDC_TOKEN_ID)
+        .into_token(&price, USDC_TOKEN_ID, rounding::PAYOUT)
         .unwrap();
     let computation_fee = (fee.base_commitment_hash_computation_fee()
         + fee.commitment_hash_computation_fee(request.min_batching_rate))
     .unwrap();
-    let computation_fee_token = computation_fee.into_token(&price, USDC_TOKEN_ID).unwrap();
+    let computation_fee_token = computation_fee
+        .into_token(&price, USDC_TOKEN_ID, rounding::FEE)
+        .unwrap();
     let network_fee = Token::new(
         USDC_TOKEN_ID,
-        fee.base_commitment_network_fee.calc(request.amount),
+        fee.base_commitment_network_fee
+            .calc(request.amount, rounding::FEE),
     );
     let hashing_account_rent = test.rent(BaseCommitmentHashingAccount::SIZE).await;
 
@@ -798,6 +810,7 @@ async fn test_single_commitment() {
             commitment: request.commitment.reduce(),
             fee_version: 0,
             min_batching_rate: 0,
+            view_tag: 0,
         }],
         Some(&[metadata]),
     )
@@ -816,7 +829,7 @@ async fn test_single_commitment() {
 
     // Init succeeds
     test.tx_should_succeed_simple(&[
-        ElusivInstruction::init_commitment_hash_setup_instruction(false, &[]),
+        ElusivInstruction::init_commitment_hash_setup_instruction(false, 0, &[]),
         ElusivInstruction::init_commitment_hash_instruction(
             false,
             &writable_user_accounts(&metadata_accounts),
@@ -845,7 +858,7 @@ async fn test_single_commitment() {
 
     // Second init fails, since a hashing is already active
     test.tx_should_fail_simple(&[
-        ElusivInstruction::init_commitment_hash_setup_instruction(false, &[]),
+        ElusivInstruction::init_commitment_hash_setup_instruction(false, 0, &[]),
         ElusivInstruction::init_commitment_hash_instruction(
             false,
             &writable_user_accounts(&metadata_accounts),
@@ -921,16 +934,13 @@ async fn test_single_commitment() {
             )
         );
         assert_eq!(
-            s.get_node(0, MT_HEIGHT as usize).unwrap(),
+            s.get_node(0, MT_HEIGHT as usize),
             request.commitment.reduce()
         );
         assert_eq!(s.get_next_commitment_ptr(), 1);
         let mut hash = u256_to_fr_skip_mr(&request.commitment.reduce());
         for i in 0..MT_HEIGHT as usize {
-            assert_eq!(
-                fr_to_u256_le(&hash),
-                s.get_node(0, MT_HEIGHT as usize - i).unwrap()
-            );
+            assert_eq!(fr_to_u256_le(&hash), s.get_node(0, MT_HEIGHT as usize - i));
             hash = full_poseidon2_hash(hash, u256_to_fr_skip_mr(&EMPTY_TREE[i]));
         }
         assert_eq!(fr_to_u256_le(&hash), s.get_root().unwrap());
@@ -981,6 +991,7 @@ async fn test_commitment_full_queue() {
         commitment: u256_from_str("0"),
         fee_version: 0,
         min_batching_rate: 0,
+        view_tag: 0,
     };
 
     // Enqueue all
@@ -1043,7 +1054,7 @@ async fn test_commitment_correct_storage_account_insertion() {
     storage_account(None, &mut test, |s: &StorageAccount| {
         for i in 0..commitment_count {
             assert_eq!(
-                s.get_node(i as usize, MT_HEIGHT as usize).unwrap(),
+                s.get_node(i as usize, MT_HEIGHT as usize),
                 fr_to_u256_le(&u64_to_scalar_skip_mr(i as u64))
             );
         }
@@ -1095,6 +1106,7 @@ async fn test_commitment_hash_multiple_commitments_zero_batch() {
             commitment: *c,
             fee_version: 0,
             min_batching_rate: 0,
+            view_tag: 0,
         })
         .collect();
 
@@ -1140,6 +1152,7 @@ async fn test_commitment_hash_multiple_commitments_zero_batch() {
         test.tx_should_succeed_simple(&[
             ElusivInstruction::init_commitment_hash_setup_instruction(
                 false,
+                0,
                 &user_accounts(&storage_accounts),
             ),
             ElusivInstruction::init_commitment_hash_instruction(
@@ -1171,10 +1184,7 @@ async fn test_commitment_hash_multiple_commitments_zero_batch() {
 
         // Verify commitment and root
         storage_account(None, &mut test, |s: &StorageAccount| {
-            assert_eq!(
-                s.get_node(i, MT_HEIGHT as usize).unwrap(),
-                requests[i].commitment
-            );
+            assert_eq!(s.get_node(i, MT_HEIGHT as usize), requests[i].commitment);
             assert_eq!(s.get_root().unwrap(), correct_roots_afterwards[i]);
         })
         .await;
@@ -1183,10 +1193,7 @@ async fn test_commitment_hash_multiple_commitments_zero_batch() {
     // Verify all commitments
     storage_account(None, &mut test, |s: &StorageAccount| {
         for (i, request) in requests.iter().enumerate() {
-            assert_eq!(
-                s.get_node(i, MT_HEIGHT as usize).unwrap(),
-                request.commitment
-            );
+            assert_eq!(s.get_node(i, MT_HEIGHT as usize), request.commitment);
         }
     })
     .await;
@@ -1228,6 +1235,7 @@ async fn test_commitment_hash_with_batching_rate(
             commitment: *c,
             fee_version: 0,
             min_batching_rate: batching_rate,
+            view_tag: 0,
         })
         .collect();
 
@@ -1248,6 +1256,7 @@ async fn test_commitment_hash_with_batching_rate(
     test.tx_should_succeed_simple(&[
         ElusivInstruction::init_commitment_hash_setup_instruction(
             false,
+            0,
             &user_accounts(&storage_accounts),
         ),
         ElusivInstruction::init_commitment_hash_instruction(
@@ -1282,10 +1291,7 @@ async fn test_commitment_hash_with_batching_rate(
     // Verify all commitments and root
     storage_account(None, &mut test, |s: &StorageAccount| {
         for (i, request) in requests.iter().enumerate() {
-            assert_eq!(
-                s.get_node(i, MT_HEIGHT as usize).unwrap(),
-                request.commitment
-            );
+            assert_eq!(s.get_node(i, MT_HEIGHT as usize), request.commitment);
         }
         if let Some(root) = root {
             assert_eq!(s.get_root().unwrap(), root);
@@ -11,6 +11,7 @@ use elusiv::{
         nullifier::NullifierAccount,
         storage::StorageAccount,
     },
+    token::SPL_TOKEN_COUNT,
     types::U256,
 };
 use elusiv_computation::PartialComputation;
@@ -21,12 +22,24 @@ use elusiv_types::{
 use std::str::FromStr;
 
 pub async fn start_test() -> ElusivProgramTest {
-    ElusivProgramTest::start(&[(
+    let mut test = ElusivProgramTest::start(&[(
         String::from("elusiv"),
         elusiv::id(),
         processor!(elusiv::process_instruction),
     )])
-    .await
+    .await;
+
+    // The `current_time` asserted by the send-fixtures is zero
+    let mut clock = test
+        .context()
+        .banks_client
+        .get_sysvar::<Clock>()
+        .await
+        .unwrap();
+    clock.unix_timestamp = 0;
+    test.context().set_sysvar(&clock);
+
+    test
 }
 
 pub async fn start_test_with_setup() -> ElusivProgramTest {
@@ -59,11 +72,20 @@ pub async fn setup_initial_pdas(test: &mut ElusivProgramTest) {
 }
 
 pub fn initial_single_instance_pdas(payer: Pubkey) -> Vec<Instruction> {
-    vec![
+    let mut ixs = vec![
         ElusivInstruction::setup_governor_account_instruction(WritableSignerAccount(payer)),
         ElusivInstruction::open_single_instance_accounts_instruction(WritableSignerAccount(payer)),
         ElusivInstruction::create_new_accounts_v1_instruction(WritableSignerAccount(payer)),
-    ]
+    ];
+
+    for token_id in 0..=SPL_TOKEN_COUNT as u16 {
+        ixs.push(ElusivInstruction::open_network_fee_account_instruction(
+            token_id,
+            WritableSignerAccount(payer),
+        ));
+    }
+
+    ixs
 }
 
 pub async fn setup_fee(test: &mut ElusivProgramTest, fee_version: u32, program_fee: ProgramFee) {
@@ -187,7 +209,7 @@ pub(crate) use pda_account;
 #[allow(unused_imports)]
 pub(crate) use queue;
 
-use solana_program::{instruction::Instruction, pubkey::Pubkey};
+use solana_program::{clock::Clock, instruction::Instruction, pubkey::Pubkey};
 use solana_program_test::processor;
 use spl_associated_token_account::instruction::create_associated_token_account;
 
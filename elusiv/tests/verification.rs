@@ -2,7 +2,7 @@
 
 mod common;
 
-use borsh::{BorshDeserialize, BorshSerialize};
+use borsh::BorshSerialize;
 use common::*;
 use elusiv::bytes::{BorshSerDeSized, ElusivOption};
 use elusiv::fields::{u64_to_u256, u64_to_u256_skip_mr};
@@ -17,22 +17,24 @@ use elusiv::proof::verifier::{
 use elusiv::proof::vkey::{SendQuadraVKey, VerifyingKeyInfo};
 use elusiv::state::commitment::CommitmentQueue;
 use elusiv::state::fee::ProgramFee;
-use elusiv::state::governor::{FeeCollectorAccount, PoolAccount};
+use elusiv::state::governor::{FeeCollectorAccount, NetworkFeeAccount, PoolAccount};
 use elusiv::state::metadata::{CommitmentMetadata, MetadataQueue};
 use elusiv::state::nullifier::{NullifierAccount, NullifierMap, NULLIFIERS_PER_ACCOUNT};
 use elusiv::state::program_account::{PDAAccount, PDAAccountData, ProgramAccount, SizedAccount};
-use elusiv::state::proof::{VerificationAccount, VerificationState};
+use elusiv::state::proof::{
+    NullifierDuplicateAccount, VerificationAccount, VerificationIndexAccount, VerificationState,
+};
 use elusiv::state::queue::RingQueue;
 use elusiv::state::storage::{empty_root_raw, StorageAccount, MT_HEIGHT};
 use elusiv::state::vkey::{VKeyAccount, VKeyAccountEager};
 use elusiv::token::{
-    spl_token_account_data, Lamports, Token, TokenPrice, LAMPORTS_TOKEN_ID, TOKENS, USDC_TOKEN_ID,
-    USDT_TOKEN_ID,
+    rounding, spl_token_account_data, Lamports, Token, TokenPrice, LAMPORTS_TOKEN_ID, TOKENS,
+    USDC_TOKEN_ID, USDT_TOKEN_ID,
 };
 use elusiv::types::{
     compute_fee_rec, compute_fee_rec_lamports, generate_hashed_inputs, InputCommitment,
-    JoinSplitPublicInputs, OptionalFee, OrdU256, Proof, PublicInputs, RawProof, RawU256,
-    SendPublicInputs, JOIN_SPLIT_MAX_N_ARITY, U256,
+    JoinSplitPublicInputs, OptionalFee, OrdU256, Proof, PublicInputs, RawU256, SendPublicInputs,
+    JOIN_SPLIT_MAX_N_ARITY, U256,
 };
 use elusiv_computation::PartialComputation;
 use elusiv_types::tokens::Price;
@@ -74,26 +76,27 @@ impl FullSendRequest {
 }
 
 fn send_request(index: usize) -> FullSendRequest {
+    // Valid curve points (required by `init_verification_proof`)
     let proof = proof_from_str(
         (
-            "10026859857882131638516328056627849627085232677511724829502598764489185541935",
-            "19685960310506634721912121951341598678325833230508240750559904196809564625591",
+            "14690239631763315837453664042432597412358242015145136618358222387278279116195",
+            "3643780132787394650252740182203975834437718299044985767317449850565317488166",
             false,
         ),
         (
             (
-                "857882131638516328056627849627085232677511724829502598764489185541935",
-                "685960310506634721912121951341598678325833230508240750559904196809564625591",
+                "12318858301116136039901780880140636659938620239898996708075490787377990627021",
+                "2655335215981242007154487245887430969280221036621749020134517693786655613279",
             ),
             (
-                "837064132573119120838379738103457054645361649757131991036638108422638197362",
-                "86803555845400161937398579081414146527572885637089779856221229551142844794",
+                "13665401110313137408934496500722861939604143361381592485089904000626841203657",
+                "16886134483886522029016161222749430345330639128944557054644673266184517343819",
             ),
             false,
         ),
         (
-            "21186803555845400161937398579081414146527572885637089779856221229551142844794",
-            "85960310506634721912121951341598678325833230508240750559904196809564625591",
+            "20648835712776577082472214104799321681109444262412204126993043827327940209500",
+            "18221482463531702349023663967222567126976044483242847353303931705097934869008",
             false,
         ),
     );
@@ -123,6 +126,9 @@ fn send_request(index: usize) -> FullSendRequest {
                 recipient_is_associated_token_account: false,
                 hashed_inputs: default_hashed_inputs,
                 solana_pay_transfer: false,
+                recipient_is_program_owned: false,
+                view_tag: 0,
+                current_time: 0,
             }
         },
         FullSendRequest {
@@ -151,6 +157,9 @@ fn send_request(index: usize) -> FullSendRequest {
                 recipient_is_associated_token_account: false,
                 hashed_inputs: default_hashed_inputs,
                 solana_pay_transfer: false,
+                recipient_is_program_owned: false,
+                view_tag: 0,
+                current_time: 0,
             }
         },
         FullSendRequest {
@@ -179,6 +188,9 @@ fn send_request(index: usize) -> FullSendRequest {
                 recipient_is_associated_token_account: false,
                 hashed_inputs: default_hashed_inputs,
                 solana_pay_transfer: false,
+                recipient_is_program_owned: false,
+                view_tag: 0,
+                current_time: 0,
             }
         },
         FullSendRequest {
@@ -211,6 +223,9 @@ fn send_request(index: usize) -> FullSendRequest {
                 recipient_is_associated_token_account: false,
                 hashed_inputs: default_hashed_inputs,
                 solana_pay_transfer: false,
+                recipient_is_program_owned: false,
+                view_tag: 0,
+                current_time: 0,
             }
         },
     ];
@@ -224,6 +239,7 @@ struct ExtraData {
     encrypted_owner: U256,
     reference: U256,
     is_associated_token_account: bool,
+    is_program_owned: bool,
     metadata: CommitmentMetadata,
     optional_fee: OptionalFee,
     memo: Option<Vec<u8>>,
@@ -240,6 +256,7 @@ impl Default for ExtraData {
             encrypted_owner: u256_from_str_skip_mr("5789489458548458945478235642378"),
             reference: [0; 32],
             is_associated_token_account: false,
+            is_program_owned: false,
             metadata: CommitmentMetadata::default(),
             optional_fee: OptionalFee::default(),
             memo: None,
@@ -256,10 +273,16 @@ impl ExtraData {
             &self.encrypted_owner,
             &self.reference,
             self.is_associated_token_account,
+            self.is_program_owned,
+            0,
+            0,
             &self.metadata,
             &self.optional_fee,
             &self.memo,
+            &None,
+            &None,
         )
+        .unwrap()
     }
 
     fn recipient(&self) -> Pubkey {
@@ -285,19 +308,22 @@ async fn init_verification_simple(
 
     test.tx_should_succeed_simple(&[
         ElusivInstruction::init_verification_instruction(
+            0,
             0,
             SendQuadraVKey::VKEY_ID,
             [0, 1],
             ProofRequest::Send(public_inputs.clone()),
             false,
+            ElusivOption::None,
             WritableSignerAccount(test.payer()),
             WritableUserAccount(public_inputs.join_split.nullifier_duplicate_pda().0),
             UserAccount(Pubkey::new_from_array(identifier)),
             &user_accounts(&[nullifier_accounts[0]]),
             &[],
         ),
-        ElusivInstruction::init_verification_transfer_fee_sol_instruction(0, test.payer()),
+        ElusivInstruction::init_verification_transfer_fee_sol_instruction(0, 0, test.payer()),
         ElusivInstruction::init_verification_proof_instruction(
+            0,
             0,
             *proof,
             SignerAccount(test.payer()),
@@ -308,14 +334,14 @@ async fn init_verification_simple(
 
 async fn skip_computation(
     warden_pubkey: Pubkey,
-    verification_account_index: u32,
+    verification_account_offset: u32,
     success: bool,
     test: &mut ElusivProgramTest,
 ) {
     test.set_pda_account::<VerificationAccount, _>(
         &elusiv::id(),
         Some(warden_pubkey),
-        Some(verification_account_index),
+        Some(verification_account_offset),
         |data| {
             let mut verification_account = VerificationAccount::new(data).unwrap();
             verification_account.set_is_verified(&ElusivOption::Some(success));
@@ -326,14 +352,14 @@ async fn skip_computation(
 
 async fn set_verification_state(
     warden_pubkey: Pubkey,
-    verification_account_index: u32,
+    verification_account_offset: u32,
     state: VerificationState,
     test: &mut ElusivProgramTest,
 ) {
     test.set_pda_account::<VerificationAccount, _>(
         &elusiv::id(),
         Some(warden_pubkey),
-        Some(verification_account_index),
+        Some(verification_account_offset),
         |data| {
             let mut verification_account = VerificationAccount::new(data).unwrap();
             verification_account.set_state(&state);
@@ -455,12 +481,19 @@ async fn test_init_proof_signers() {
     let subvention = fee.proof_subvention;
     let commitment_hash_fee = fee.commitment_hash_computation_fee(0);
 
-    let verification_account_rent = test.rent(VerificationAccount::SIZE).await;
-    let nullifier_duplicate_account_rent = test.rent(PDAAccountData::SIZE).await;
+    let verification_account_rent = test
+        .rent(VerificationAccount::account_size(
+            SendQuadraVKey::public_inputs_count(),
+        ))
+        .await;
+    let nullifier_duplicate_account_rent = test.rent(NullifierDuplicateAccount::SIZE).await;
+    let verification_index_account_rent = test.rent(VerificationIndexAccount::SIZE).await;
     warden
         .airdrop(
             LAMPORTS_TOKEN_ID,
-            verification_account_rent.0 + nullifier_duplicate_account_rent.0,
+            verification_account_rent.0
+                + nullifier_duplicate_account_rent.0
+                + verification_index_account_rent.0,
             &mut test,
         )
         .await;
@@ -474,11 +507,13 @@ async fn test_init_proof_signers() {
 
     test.ix_should_succeed(
         ElusivInstruction::init_verification_instruction(
+            0,
             0,
             SendQuadraVKey::VKEY_ID,
             [0, 1],
             ProofRequest::Send(request.public_inputs.clone()),
             false,
+            ElusivOption::None,
             WritableSignerAccount(warden.pubkey),
             WritableUserAccount(nullifier_duplicate_account),
             UserAccount(Pubkey::new_unique()),
@@ -492,6 +527,7 @@ async fn test_init_proof_signers() {
     // Invalid signer calls `init_verification_transfer_fee`
     test.ix_should_fail(
         ElusivInstruction::init_verification_transfer_fee_instruction(
+            0,
             0,
             WritableSignerAccount(warden2.pubkey),
             WritableUserAccount(warden2.pubkey),
@@ -507,6 +543,7 @@ async fn test_init_proof_signers() {
 
     test.ix_should_succeed(
         ElusivInstruction::init_verification_transfer_fee_instruction(
+            0,
             0,
             WritableSignerAccount(warden.pubkey),
             WritableUserAccount(warden.pubkey),
@@ -524,10 +561,8 @@ async fn test_init_proof_signers() {
     test.ix_should_fail(
         ElusivInstruction::init_verification_proof_instruction(
             0,
-            RawProof::try_from_slice(&vec![0; RawProof::SIZE])
-                .unwrap()
-                .try_into()
-                .unwrap(),
+            0,
+            request.proof,
             SignerAccount(warden2.pubkey),
         ),
         &[&warden2.keypair],
@@ -537,10 +572,8 @@ async fn test_init_proof_signers() {
     test.ix_should_succeed(
         ElusivInstruction::init_verification_proof_instruction(
             0,
-            RawProof::try_from_slice(&vec![0; RawProof::SIZE])
-                .unwrap()
-                .try_into()
-                .unwrap(),
+            0,
+            request.proof,
             SignerAccount(warden.pubkey),
         ),
         &[&warden.keypair],
@@ -563,16 +596,24 @@ async fn test_init_proof_lamports() {
     let fee_collector = FeeCollectorAccount::find(None).0;
     let nullifier_duplicate_account = request.public_inputs.join_split.nullifier_duplicate_pda().0;
 
-    let verification_account_rent = test.rent(VerificationAccount::SIZE).await;
-    let nullifier_duplicate_account_rent = test.rent(PDAAccountData::SIZE).await;
+    let verification_account_rent = test
+        .rent(VerificationAccount::account_size(
+            SendQuadraVKey::public_inputs_count(),
+        ))
+        .await;
+    let nullifier_duplicate_account_rent = test.rent(NullifierDuplicateAccount::SIZE).await;
+    let verification_index_account_rent = test.rent(VerificationIndexAccount::SIZE).await;
     warden
         .airdrop(
             LAMPORTS_TOKEN_ID,
-            verification_account_rent.0 + nullifier_duplicate_account_rent.0,
+            verification_account_rent.0
+                + nullifier_duplicate_account_rent.0
+                + verification_index_account_rent.0,
             &mut test,
         )
         .await;
 
+    // Each index is used by a single verification, so the n-th verification (at index n) uses generation n
     let init_verification_instruction =
         |v_index: u8, commitment: Option<U256>, skip_nullifier_pda: bool| {
             let mut request = request.clone();
@@ -583,10 +624,12 @@ async fn test_init_proof_lamports() {
 
             ElusivInstruction::init_verification_instruction(
                 v_index,
+                u32::from(v_index),
                 SendQuadraVKey::VKEY_ID,
                 [0, 1],
                 ProofRequest::Send(request.public_inputs),
                 skip_nullifier_pda,
+                ElusivOption::None,
                 WritableSignerAccount(warden.pubkey),
                 WritableUserAccount(nullifier_duplicate_account),
                 UserAccount(Pubkey::new_unique()),
@@ -664,6 +707,7 @@ async fn test_init_proof_lamports() {
     .await;
 
     let transfer_fee_instruction = ElusivInstruction::init_verification_transfer_fee_instruction(
+        0,
         0,
         WritableSignerAccount(warden.pubkey),
         WritableUserAccount(warden.pubkey),
@@ -743,12 +787,19 @@ async fn test_init_proof_token() {
     let nullifier_duplicate_account = request.public_inputs.join_split.nullifier_duplicate_pda().0;
     let nullifier_accounts = nullifier_accounts(&mut test, 0).await;
 
-    let verification_account_rent = test.rent(VerificationAccount::SIZE).await;
-    let nullifier_duplicate_account_rent = test.rent(PDAAccountData::SIZE).await;
+    let verification_account_rent = test
+        .rent(VerificationAccount::account_size(
+            SendQuadraVKey::public_inputs_count(),
+        ))
+        .await;
+    let nullifier_duplicate_account_rent = test.rent(NullifierDuplicateAccount::SIZE).await;
+    let verification_index_account_rent = test.rent(VerificationIndexAccount::SIZE).await;
     warden
         .airdrop(
             LAMPORTS_TOKEN_ID,
-            verification_account_rent.0 + nullifier_duplicate_account_rent.0,
+            verification_account_rent.0
+                + nullifier_duplicate_account_rent.0
+                + verification_index_account_rent.0,
             &mut test,
         )
         .await;
@@ -765,11 +816,13 @@ async fn test_init_proof_token() {
 
     test.ix_should_succeed(
         ElusivInstruction::init_verification_instruction(
+            0,
             0,
             SendQuadraVKey::VKEY_ID,
             [0, 1],
             ProofRequest::Send(request.public_inputs.clone()),
             false,
+            ElusivOption::None,
             WritableSignerAccount(warden.pubkey),
             WritableUserAccount(nullifier_duplicate_account),
             UserAccount(Pubkey::new_unique()),
@@ -784,7 +837,7 @@ async fn test_init_proof_token() {
 
     let subvention = fee
         .proof_subvention
-        .into_token(&price, USDC_TOKEN_ID)
+        .into_token(&price, USDC_TOKEN_ID, rounding::PAYOUT)
         .unwrap();
     let commitment_hash_fee = fee.commitment_hash_computation_fee(0);
 
@@ -799,6 +852,7 @@ async fn test_init_proof_token() {
 
     test.ix_should_succeed(
         ElusivInstruction::init_verification_transfer_fee_instruction(
+            0,
             0,
             WritableSignerAccount(warden.pubkey),
             WritableUserAccount(warden.get_token_account(USDC_TOKEN_ID)),
@@ -866,16 +920,22 @@ async fn test_finalize_proof_lamports() {
     let commitment_hash_fee = fee.commitment_hash_computation_fee(0);
     let network_fee = Lamports(
         fee.proof_network_fee
-            .calc(request.public_inputs.join_split.amount),
+            .calc(request.public_inputs.join_split.amount, rounding::FEE),
     );
-    let verification_account_rent = test.rent(VerificationAccount::SIZE).await;
-    let nullifier_duplicate_account_rent = test.rent(PDAAccountData::SIZE).await;
+    let verification_account_rent = test
+        .rent(VerificationAccount::account_size(
+            SendQuadraVKey::public_inputs_count(),
+        ))
+        .await;
+    let nullifier_duplicate_account_rent = test.rent(NullifierDuplicateAccount::SIZE).await;
+    let verification_index_account_rent = test.rent(VerificationIndexAccount::SIZE).await;
 
     warden
         .airdrop(
             LAMPORTS_TOKEN_ID,
             verification_account_rent.0
                 + nullifier_duplicate_account_rent.0
+                + verification_index_account_rent.0
                 + commitment_hash_fee.0,
             &mut test,
         )
@@ -886,19 +946,22 @@ async fn test_finalize_proof_lamports() {
     test.tx_should_succeed(
         &[
             ElusivInstruction::init_verification_instruction(
+                0,
                 0,
                 SendQuadraVKey::VKEY_ID,
                 [0, 1],
                 ProofRequest::Send(request.public_inputs.clone()),
                 false,
+                ElusivOption::None,
                 WritableSignerAccount(warden.pubkey),
                 WritableUserAccount(nullifier_duplicate_account),
                 UserAccount(Pubkey::new_from_array(extra_data.identifier)),
                 &user_accounts(&[nullifier_accounts[0]]),
                 &[],
             ),
-            ElusivInstruction::init_verification_transfer_fee_sol_instruction(0, warden.pubkey),
+            ElusivInstruction::init_verification_transfer_fee_sol_instruction(0, 0, warden.pubkey),
             ElusivInstruction::init_verification_proof_instruction(
+                0,
                 0,
                 request.proof,
                 SignerAccount(warden.pubkey),
@@ -936,6 +999,7 @@ async fn test_finalize_proof_lamports() {
     // Finalize
     let finalize_verification_send_instruction =
         ElusivInstruction::finalize_verification_send_instruction(
+            0,
             0,
             FinalizeSendData {
                 total_amount: request.public_inputs.join_split.total_amount(),
@@ -951,6 +1015,7 @@ async fn test_finalize_proof_lamports() {
         );
     let finalize_verification_send_nullifier_instruction =
         ElusivInstruction::finalize_verification_insert_nullifier_instruction(
+            0,
             0,
             UserAccount(warden.pubkey),
             Some(0),
@@ -958,11 +1023,14 @@ async fn test_finalize_proof_lamports() {
         );
     let finalize_verification_transfer_lamports_instruction =
         ElusivInstruction::finalize_verification_transfer_lamports_instruction(
+            0,
             0,
             WritableSignerAccount(warden.pubkey),
             WritableUserAccount(recipient),
             WritableUserAccount(optional_fee_collector.pubkey),
             WritableUserAccount(nullifier_duplicate_account),
+            WritableUserAccount(warden.pubkey),
+            WritableUserAccount(NetworkFeeAccount::find(Some(LAMPORTS_TOKEN_ID.into())).0),
         );
 
     // IMPORTANT: Pool already contains subvention (so we airdrop commitment_hash_fee - subvention)
@@ -997,7 +1065,7 @@ async fn test_finalize_proof_lamports() {
 
     assert!(
         test.account_does_not_exist(
-            &VerificationAccount::find_with_pubkey(warden.pubkey, Some(0)).0
+            &VerificationAccount::find_with_generation(warden.pubkey, 0, 0).0
         )
         .await
     );
@@ -1122,23 +1190,28 @@ async fn test_finalize_proof_token() {
             .len();
     let subvention = fee
         .proof_subvention
-        .into_token(&price, USDC_TOKEN_ID)
+        .into_token(&price, USDC_TOKEN_ID, rounding::PAYOUT)
         .unwrap();
     let proof_verification_fee = fee
         .proof_verification_computation_fee(input_preparation_tx_count)
-        .into_token(&price, USDC_TOKEN_ID)
+        .into_token(&price, USDC_TOKEN_ID, rounding::FEE)
         .unwrap();
     let commitment_hash_fee = fee.commitment_hash_computation_fee(0);
     let commitment_hash_fee_token = commitment_hash_fee
-        .into_token(&price, USDC_TOKEN_ID)
+        .into_token(&price, USDC_TOKEN_ID, rounding::FEE)
         .unwrap();
     let network_fee = Token::new(
         USDC_TOKEN_ID,
         fee.proof_network_fee
-            .calc(request.public_inputs.join_split.amount),
+            .calc(request.public_inputs.join_split.amount, rounding::FEE),
     );
-    let verification_account_rent = test.rent(VerificationAccount::SIZE).await;
-    let nullifier_duplicate_account_rent = test.rent(PDAAccountData::SIZE).await;
+    let verification_account_rent = test
+        .rent(VerificationAccount::account_size(
+            SendQuadraVKey::public_inputs_count(),
+        ))
+        .await;
+    let nullifier_duplicate_account_rent = test.rent(NullifierDuplicateAccount::SIZE).await;
+    let verification_index_account_rent = test.rent(VerificationIndexAccount::SIZE).await;
 
     let pool_account = program_token_account_address::<PoolAccount>(USDC_TOKEN_ID, None).unwrap();
     let fee_collector_account =
@@ -1149,6 +1222,7 @@ async fn test_finalize_proof_token() {
             LAMPORTS_TOKEN_ID,
             verification_account_rent.0
                 + nullifier_duplicate_account_rent.0
+                + verification_index_account_rent.0
                 + commitment_hash_fee.0,
             &mut test,
         )
@@ -1159,11 +1233,13 @@ async fn test_finalize_proof_token() {
     test.tx_should_succeed(
         &[
             ElusivInstruction::init_verification_instruction(
+                0,
                 0,
                 SendQuadraVKey::VKEY_ID,
                 [0, 1],
                 ProofRequest::Send(request.public_inputs.clone()),
                 false,
+                ElusivOption::None,
                 WritableSignerAccount(warden.pubkey),
                 WritableUserAccount(nullifier_duplicate_account),
                 UserAccount(Pubkey::new_from_array(extra_data.identifier)),
@@ -1171,6 +1247,7 @@ async fn test_finalize_proof_token() {
                 &[],
             ),
             ElusivInstruction::init_verification_transfer_fee_instruction(
+                0,
                 0,
                 WritableSignerAccount(warden.pubkey),
                 WritableUserAccount(warden.get_token_account(USDC_TOKEN_ID)),
@@ -1181,6 +1258,7 @@ async fn test_finalize_proof_token() {
                 UserAccount(spl_token::id()),
             ),
             ElusivInstruction::init_verification_proof_instruction(
+                0,
                 0,
                 request.proof,
                 SignerAccount(warden.pubkey),
@@ -1208,6 +1286,7 @@ async fn test_finalize_proof_token() {
     // Finalize
     let finalize_verification_send_instruction =
         ElusivInstruction::finalize_verification_send_instruction(
+            0,
             0,
             FinalizeSendData {
                 total_amount: request.public_inputs.join_split.total_amount(),
@@ -1224,6 +1303,7 @@ async fn test_finalize_proof_token() {
         );
     let finalize_verification_send_nullifier_instruction =
         ElusivInstruction::finalize_verification_insert_nullifier_instruction(
+            0,
             0,
             UserAccount(warden.pubkey),
             Some(0),
@@ -1231,16 +1311,19 @@ async fn test_finalize_proof_token() {
         );
     let finalize_verification_transfer_token_instruction =
         ElusivInstruction::finalize_verification_transfer_token_instruction(
+            0,
             0,
             WritableSignerAccount(warden.pubkey),
             WritableUserAccount(warden.get_token_account(USDC_TOKEN_ID)),
             WritableUserAccount(recipient_token_account),
-            UserAccount(recipient_token_account),
+            WritableUserAccount(recipient_token_account),
             WritableUserAccount(pool_account),
             WritableUserAccount(fee_collector_account),
             WritableUserAccount(optional_fee_collector.get_token_account(USDC_TOKEN_ID)),
             WritableUserAccount(nullifier_duplicate_account),
             UserAccount(spl_token::id()),
+            WritableUserAccount(warden.pubkey),
+            WritableUserAccount(NetworkFeeAccount::find(Some(USDC_TOKEN_ID.into())).0),
         );
 
     // IMPORTANT: Pool already contains subvention (so we airdrop commitment_hash_fee - subvention)
@@ -1263,76 +1346,584 @@ async fn test_finalize_proof_token() {
     )
     .await;
 
-    // Invalid signer
-    test.tx_should_fail_simple(&[
-        finalize_verification_send_instruction.clone(),
-        finalize_verification_send_nullifier_instruction.clone(),
-        finalize_verification_transfer_token_instruction.clone(),
-    ])
-    .await;
-
-    // TODO: Invalid optional-fee-collector
+    // Invalid signer
+    test.tx_should_fail_simple(&[
+        finalize_verification_send_instruction.clone(),
+        finalize_verification_send_nullifier_instruction.clone(),
+        finalize_verification_transfer_token_instruction.clone(),
+    ])
+    .await;
+
+    // TODO: Invalid optional-fee-collector
+
+    test.tx_should_succeed(
+        &[
+            finalize_verification_send_instruction,
+            finalize_verification_send_nullifier_instruction,
+            finalize_verification_transfer_token_instruction,
+        ],
+        &[&warden.keypair],
+    )
+    .await;
+
+    assert!(
+        test.account_does_not_exist(
+            &VerificationAccount::find_with_generation(warden.pubkey, 0, 0).0
+        )
+        .await
+    );
+    assert!(
+        test.account_does_not_exist(&nullifier_duplicate_account)
+            .await
+    );
+
+    assert_eq!(
+        verification_account_rent.0 + nullifier_duplicate_account_rent.0,
+        warden.lamports(&mut test).await
+    );
+
+    // warden has proof_verification_fee and commitment_hash_fee (token)
+    assert_eq!(
+        proof_verification_fee.amount() + commitment_hash_fee_token.amount(),
+        warden.balance(USDC_TOKEN_ID, &mut test).await
+    );
+
+    // recipient has amount - optional_fee.amount (token)
+    assert_eq!(
+        request.public_inputs.join_split.amount
+            - request.public_inputs.join_split.optional_fee.amount,
+        recipient.balance(USDC_TOKEN_ID, &mut test).await
+    );
+
+    // optional_fee_collector has optional_fee.amount (token)
+    assert_eq!(
+        request.public_inputs.join_split.optional_fee.amount,
+        optional_fee_collector
+            .balance(USDC_TOKEN_ID, &mut test)
+            .await
+    );
+
+    // fee_collector has network_fee (token)
+    assert_eq!(
+        network_fee.amount(),
+        test.spl_balance(&fee_collector_account).await
+    );
+
+    // Pool contains computation_fee (lamports)
+    assert_eq!(
+        commitment_hash_fee.0,
+        test.pda_lamports(&PoolAccount::find(None).0, PoolAccount::SIZE)
+            .await
+            .0
+    );
+}
+
+#[tokio::test]
+async fn test_finalize_proof_token_self_withdrawal() {
+    let mut test = start_verification_test().await;
+    test.create_spl_token(USDC_TOKEN_ID).await;
+    enable_program_token_account::<PoolAccount>(&mut test, USDC_TOKEN_ID, None).await;
+    enable_program_token_account::<FeeCollectorAccount>(&mut test, USDC_TOKEN_ID, None).await;
+    setup_vkey_account::<SendQuadraVKey>(&mut test).await;
+    let nullifier_accounts = nullifier_accounts(&mut test, 0).await;
+    let fee = genesis_fee(&mut test).await;
+
+    let mut warden = test.new_actor().await;
+    warden.open_token_account(USDC_TOKEN_ID, 0, &mut test).await;
+
+    let mut optional_fee_collector = test.new_actor().await;
+    optional_fee_collector
+        .open_token_account(USDC_TOKEN_ID, 0, &mut test)
+        .await;
+
+    let sol_usd_price = Price {
+        price: 41,
+        conf: 0,
+        expo: 0,
+    };
+    let usdc_usd_price = Price {
+        price: 1,
+        conf: 0,
+        expo: 0,
+    };
+    let price =
+        TokenPrice::new_from_sol_price(sol_usd_price, usdc_usd_price, USDC_TOKEN_ID).unwrap();
+    let sol_price_account = test.token_to_usd_price_pyth_account(0);
+    let token_price_account = test.token_to_usd_price_pyth_account(USDC_TOKEN_ID);
+    test.set_token_to_usd_price_pyth(0, sol_usd_price).await;
+    test.set_token_to_usd_price_pyth(USDC_TOKEN_ID, usdc_usd_price)
+        .await;
+
+    let mut request = send_request(0);
+    request.public_inputs.join_split.optional_fee = OptionalFee {
+        collector: optional_fee_collector.get_token_account(USDC_TOKEN_ID),
+        amount: 1234,
+    };
+    request.public_inputs.join_split.token_id = USDC_TOKEN_ID;
+    request.public_inputs.join_split.amount = 1_000_000;
+
+    // The warden withdraws to its own token account
+    let recipient_token_account = warden.get_token_account(USDC_TOKEN_ID);
+    let extra_data = ExtraData {
+        optional_fee: request.public_inputs.join_split.optional_fee.clone(),
+        recipient: recipient_token_account.to_bytes(),
+        ..Default::default()
+    };
+    request.public_inputs.hashed_inputs = extra_data.hash();
+    request.update_fee_token(&fee, &price);
+
+    let nullifier_duplicate_account = request.public_inputs.join_split.nullifier_duplicate_pda().0;
+
+    let public_inputs = request.public_inputs.public_signals_skip_mr();
+    let input_preparation_tx_count =
+        prepare_public_inputs_instructions(&public_inputs, SendQuadraVKey::public_inputs_count())
+            .len();
+    let subvention = fee
+        .proof_subvention
+        .into_token(&price, USDC_TOKEN_ID, rounding::PAYOUT)
+        .unwrap();
+    let proof_verification_fee = fee
+        .proof_verification_computation_fee(input_preparation_tx_count)
+        .into_token(&price, USDC_TOKEN_ID, rounding::FEE)
+        .unwrap();
+    let commitment_hash_fee = fee.commitment_hash_computation_fee(0);
+    let commitment_hash_fee_token = commitment_hash_fee
+        .into_token(&price, USDC_TOKEN_ID, rounding::FEE)
+        .unwrap();
+    let network_fee = Token::new(
+        USDC_TOKEN_ID,
+        fee.proof_network_fee
+            .calc(request.public_inputs.join_split.amount, rounding::FEE),
+    );
+    let verification_account_rent = test
+        .rent(VerificationAccount::account_size(
+            SendQuadraVKey::public_inputs_count(),
+        ))
+        .await;
+    let nullifier_duplicate_account_rent = test.rent(NullifierDuplicateAccount::SIZE).await;
+    let verification_index_account_rent = test.rent(VerificationIndexAccount::SIZE).await;
+
+    let pool_account = program_token_account_address::<PoolAccount>(USDC_TOKEN_ID, None).unwrap();
+    let fee_collector_account =
+        program_token_account_address::<FeeCollectorAccount>(USDC_TOKEN_ID, None).unwrap();
+
+    warden
+        .airdrop(
+            LAMPORTS_TOKEN_ID,
+            verification_account_rent.0
+                + nullifier_duplicate_account_rent.0
+                + verification_index_account_rent.0
+                + commitment_hash_fee.0,
+            &mut test,
+        )
+        .await;
+    test.airdrop(&fee_collector_account, subvention).await;
+
+    // Init
+    test.tx_should_succeed(
+        &[
+            ElusivInstruction::init_verification_instruction(
+                0,
+                0,
+                SendQuadraVKey::VKEY_ID,
+                [0, 1],
+                ProofRequest::Send(request.public_inputs.clone()),
+                false,
+                ElusivOption::None,
+                WritableSignerAccount(warden.pubkey),
+                WritableUserAccount(nullifier_duplicate_account),
+                UserAccount(Pubkey::new_from_array(extra_data.identifier)),
+                &user_accounts(&[nullifier_accounts[0]]),
+                &[],
+            ),
+            ElusivInstruction::init_verification_transfer_fee_instruction(
+                0,
+                0,
+                WritableSignerAccount(warden.pubkey),
+                WritableUserAccount(warden.get_token_account(USDC_TOKEN_ID)),
+                WritableUserAccount(pool_account),
+                WritableUserAccount(fee_collector_account),
+                UserAccount(sol_price_account),
+                UserAccount(token_price_account),
+                UserAccount(spl_token::id()),
+            ),
+            ElusivInstruction::init_verification_proof_instruction(
+                0,
+                0,
+                request.proof,
+                SignerAccount(warden.pubkey),
+            ),
+        ],
+        &[&warden.keypair],
+    )
+    .await;
+
+    assert_eq!(0, warden.lamports(&mut test).await);
+    assert_eq!(0, warden.balance(USDC_TOKEN_ID, &mut test).await);
+    assert_eq!(
+        commitment_hash_fee.0,
+        test.pda_lamports(&PoolAccount::find(None).0, PoolAccount::SIZE)
+            .await
+            .0
+    );
+    assert_eq!(subvention.amount(), test.spl_balance(&pool_account).await);
+
+    skip_computation(warden.pubkey, 0, true, &mut test).await;
+
+    let identifier = Pubkey::new_from_array(extra_data.identifier);
+    let reference = Pubkey::new_from_array(extra_data.reference);
+
+    // Finalize
+    let finalize_verification_send_instruction =
+        ElusivInstruction::finalize_verification_send_instruction(
+            0,
+            0,
+            FinalizeSendData {
+                total_amount: request.public_inputs.join_split.total_amount(),
+                token_id: USDC_TOKEN_ID,
+                encrypted_owner: extra_data.encrypted_owner,
+                iv: extra_data.iv,
+                ..Default::default()
+            },
+            false,
+            UserAccount(recipient_token_account),
+            UserAccount(identifier),
+            UserAccount(reference),
+            UserAccount(warden.pubkey),
+        );
+    let finalize_verification_send_nullifier_instruction =
+        ElusivInstruction::finalize_verification_insert_nullifier_instruction(
+            0,
+            0,
+            UserAccount(warden.pubkey),
+            Some(0),
+            &writable_user_accounts(&[nullifier_accounts[0]]),
+        );
+    let finalize_verification_transfer_token_instruction =
+        ElusivInstruction::finalize_verification_transfer_token_instruction(
+            0,
+            0,
+            WritableSignerAccount(warden.pubkey),
+            WritableUserAccount(warden.get_token_account(USDC_TOKEN_ID)),
+            WritableUserAccount(recipient_token_account),
+            WritableUserAccount(recipient_token_account),
+            WritableUserAccount(pool_account),
+            WritableUserAccount(fee_collector_account),
+            WritableUserAccount(optional_fee_collector.get_token_account(USDC_TOKEN_ID)),
+            WritableUserAccount(nullifier_duplicate_account),
+            UserAccount(spl_token::id()),
+            WritableUserAccount(warden.pubkey),
+            WritableUserAccount(NetworkFeeAccount::find(Some(USDC_TOKEN_ID.into())).0),
+        );
+
+    // IMPORTANT: Pool already contains subvention (so we airdrop commitment_hash_fee - subvention)
+    test.airdrop(
+        &pool_account,
+        Token::new(
+            USDC_TOKEN_ID,
+            request.public_inputs.join_split.amount + commitment_hash_fee_token.amount()
+                - subvention.amount()
+                + proof_verification_fee.amount()
+                + network_fee.amount(),
+        ),
+    )
+    .await;
+
+    test.tx_should_succeed(
+        &[
+            finalize_verification_send_instruction,
+            finalize_verification_send_nullifier_instruction,
+            finalize_verification_transfer_token_instruction,
+        ],
+        &[&warden.keypair],
+    )
+    .await;
+
+    assert!(
+        test.account_does_not_exist(
+            &VerificationAccount::find_with_generation(warden.pubkey, 0, 0).0
+        )
+        .await
+    );
+    assert!(
+        test.account_does_not_exist(&nullifier_duplicate_account)
+            .await
+    );
+
+    assert_eq!(
+        verification_account_rent.0 + nullifier_duplicate_account_rent.0,
+        warden.lamports(&mut test).await
+    );
+
+    // warden has the same total as fee-payer and recipient in the non-self case (token)
+    assert_eq!(
+        proof_verification_fee.amount()
+            + commitment_hash_fee_token.amount()
+            + request.public_inputs.join_split.amount
+            - request.public_inputs.join_split.optional_fee.amount,
+        warden.balance(USDC_TOKEN_ID, &mut test).await
+    );
+
+    // optional_fee_collector has optional_fee.amount (token)
+    assert_eq!(
+        request.public_inputs.join_split.optional_fee.amount,
+        optional_fee_collector
+            .balance(USDC_TOKEN_ID, &mut test)
+            .await
+    );
+
+    // fee_collector has network_fee (token)
+    assert_eq!(
+        network_fee.amount(),
+        test.spl_balance(&fee_collector_account).await
+    );
+
+    // Pool contains computation_fee (lamports)
+    assert_eq!(
+        commitment_hash_fee.0,
+        test.pda_lamports(&PoolAccount::find(None).0, PoolAccount::SIZE)
+            .await
+            .0
+    );
+}
+
+#[cfg(feature = "devnet-tools")]
+#[tokio::test]
+async fn test_finalize_proof_test_token() {
+    let mut test = start_verification_test().await;
+
+    // Test-mint and test-prices are created through the program
+    let sol_usd_price = Price {
+        price: 41,
+        conf: 0,
+        expo: 0,
+    };
+    let usdc_usd_price = Price {
+        price: 1,
+        conf: 0,
+        expo: 0,
+    };
+    test.tx_should_succeed_simple(&[
+        ElusivInstruction::create_test_mint_instruction(
+            USDC_TOKEN_ID,
+            WritableSignerAccount(test.payer()),
+        ),
+        ElusivInstruction::set_test_price_instruction(
+            0,
+            sol_usd_price.price,
+            sol_usd_price.conf,
+            sol_usd_price.expo,
+            WritableSignerAccount(test.payer()),
+        ),
+        ElusivInstruction::set_test_price_instruction(
+            USDC_TOKEN_ID,
+            usdc_usd_price.price,
+            usdc_usd_price.conf,
+            usdc_usd_price.expo,
+            WritableSignerAccount(test.payer()),
+        ),
+    ])
+    .await;
+
+    enable_program_token_account::<PoolAccount>(&mut test, USDC_TOKEN_ID, None).await;
+    enable_program_token_account::<FeeCollectorAccount>(&mut test, USDC_TOKEN_ID, None).await;
+    setup_vkey_account::<SendQuadraVKey>(&mut test).await;
+    let nullifier_accounts = nullifier_accounts(&mut test, 0).await;
+    let fee = genesis_fee(&mut test).await;
+
+    let mut recipient = test.new_actor().await;
+    recipient
+        .open_token_account(USDC_TOKEN_ID, 0, &mut test)
+        .await;
+
+    let mut warden = test.new_actor().await;
+    warden.open_token_account(USDC_TOKEN_ID, 0, &mut test).await;
+
+    let price =
+        TokenPrice::new_from_sol_price(sol_usd_price, usdc_usd_price, USDC_TOKEN_ID).unwrap();
+    let sol_price_account = test.token_to_usd_price_pyth_account(0);
+    let token_price_account = test.token_to_usd_price_pyth_account(USDC_TOKEN_ID);
+
+    let mut request = send_request(0);
+    request.public_inputs.join_split.token_id = USDC_TOKEN_ID;
+    request.public_inputs.join_split.amount = 1_000_000;
+
+    let recipient_token_account = recipient.get_token_account(USDC_TOKEN_ID);
+    let extra_data = ExtraData {
+        recipient: recipient_token_account.to_bytes(),
+        ..Default::default()
+    };
+    request.public_inputs.hashed_inputs = extra_data.hash();
+    request.update_fee_token(&fee, &price);
+
+    let nullifier_duplicate_account = request.public_inputs.join_split.nullifier_duplicate_pda().0;
+
+    let public_inputs = request.public_inputs.public_signals_skip_mr();
+    let input_preparation_tx_count =
+        prepare_public_inputs_instructions(&public_inputs, SendQuadraVKey::public_inputs_count())
+            .len();
+    let subvention = fee
+        .proof_subvention
+        .into_token(&price, USDC_TOKEN_ID, rounding::PAYOUT)
+        .unwrap();
+    let proof_verification_fee = fee
+        .proof_verification_computation_fee(input_preparation_tx_count)
+        .into_token(&price, USDC_TOKEN_ID, rounding::FEE)
+        .unwrap();
+    let commitment_hash_fee = fee.commitment_hash_computation_fee(0);
+    let commitment_hash_fee_token = commitment_hash_fee
+        .into_token(&price, USDC_TOKEN_ID, rounding::FEE)
+        .unwrap();
+    let network_fee = Token::new(
+        USDC_TOKEN_ID,
+        fee.proof_network_fee
+            .calc(request.public_inputs.join_split.amount, rounding::FEE),
+    );
+    let verification_account_rent = test
+        .rent(VerificationAccount::account_size(
+            SendQuadraVKey::public_inputs_count(),
+        ))
+        .await;
+    let nullifier_duplicate_account_rent = test.rent(NullifierDuplicateAccount::SIZE).await;
+    let verification_index_account_rent = test.rent(VerificationIndexAccount::SIZE).await;
+
+    let pool_account = program_token_account_address::<PoolAccount>(USDC_TOKEN_ID, None).unwrap();
+    let fee_collector_account =
+        program_token_account_address::<FeeCollectorAccount>(USDC_TOKEN_ID, None).unwrap();
+
+    warden
+        .airdrop(
+            LAMPORTS_TOKEN_ID,
+            verification_account_rent.0
+                + nullifier_duplicate_account_rent.0
+                + verification_index_account_rent.0
+                + commitment_hash_fee.0,
+            &mut test,
+        )
+        .await;
+
+    // Test-tokens are minted by the program (the pool already contains the subvention after the init)
+    test.tx_should_succeed_simple(&[
+        ElusivInstruction::mint_test_tokens_instruction(
+            USDC_TOKEN_ID,
+            subvention.amount(),
+            WritableUserAccount(fee_collector_account),
+        ),
+        ElusivInstruction::mint_test_tokens_instruction(
+            USDC_TOKEN_ID,
+            request.public_inputs.join_split.amount + commitment_hash_fee_token.amount()
+                - subvention.amount()
+                + proof_verification_fee.amount()
+                + network_fee.amount(),
+            WritableUserAccount(pool_account),
+        ),
+    ])
+    .await;
+
+    // Init
+    test.tx_should_succeed(
+        &[
+            ElusivInstruction::init_verification_instruction(
+                0,
+                0,
+                SendQuadraVKey::VKEY_ID,
+                [0, 1],
+                ProofRequest::Send(request.public_inputs.clone()),
+                false,
+                ElusivOption::None,
+                WritableSignerAccount(warden.pubkey),
+                WritableUserAccount(nullifier_duplicate_account),
+                UserAccount(Pubkey::new_from_array(extra_data.identifier)),
+                &user_accounts(&[nullifier_accounts[0]]),
+                &[],
+            ),
+            ElusivInstruction::init_verification_transfer_fee_instruction(
+                0,
+                0,
+                WritableSignerAccount(warden.pubkey),
+                WritableUserAccount(warden.get_token_account(USDC_TOKEN_ID)),
+                WritableUserAccount(pool_account),
+                WritableUserAccount(fee_collector_account),
+                UserAccount(sol_price_account),
+                UserAccount(token_price_account),
+                UserAccount(spl_token::id()),
+            ),
+            ElusivInstruction::init_verification_proof_instruction(
+                0,
+                0,
+                request.proof,
+                SignerAccount(warden.pubkey),
+            ),
+        ],
+        &[&warden.keypair],
+    )
+    .await;
+
+    skip_computation(warden.pubkey, 0, true, &mut test).await;
 
+    // Finalize
     test.tx_should_succeed(
         &[
-            finalize_verification_send_instruction,
-            finalize_verification_send_nullifier_instruction,
-            finalize_verification_transfer_token_instruction,
+            ElusivInstruction::finalize_verification_send_instruction(
+                0,
+                0,
+                FinalizeSendData {
+                    total_amount: request.public_inputs.join_split.total_amount(),
+                    token_id: USDC_TOKEN_ID,
+                    encrypted_owner: extra_data.encrypted_owner,
+                    iv: extra_data.iv,
+                    ..Default::default()
+                },
+                false,
+                UserAccount(recipient_token_account),
+                UserAccount(Pubkey::new_from_array(extra_data.identifier)),
+                UserAccount(Pubkey::new_from_array(extra_data.reference)),
+                UserAccount(warden.pubkey),
+            ),
+            ElusivInstruction::finalize_verification_insert_nullifier_instruction(
+                0,
+                0,
+                UserAccount(warden.pubkey),
+                Some(0),
+                &writable_user_accounts(&[nullifier_accounts[0]]),
+            ),
+            ElusivInstruction::finalize_verification_transfer_token_instruction(
+                0,
+                0,
+                WritableSignerAccount(warden.pubkey),
+                WritableUserAccount(warden.get_token_account(USDC_TOKEN_ID)),
+                WritableUserAccount(recipient_token_account),
+                WritableUserAccount(recipient_token_account),
+                WritableUserAccount(pool_account),
+                WritableUserAccount(fee_collector_account),
+                WritableUserAccount(recipient_token_account),
+                WritableUserAccount(nullifier_duplicate_account),
+                UserAccount(spl_token::id()),
+                WritableUserAccount(warden.pubkey),
+                WritableUserAccount(NetworkFeeAccount::find(Some(USDC_TOKEN_ID.into())).0),
+            ),
         ],
         &[&warden.keypair],
     )
     .await;
 
-    assert!(
-        test.account_does_not_exist(
-            &VerificationAccount::find_with_pubkey(warden.pubkey, Some(0)).0
-        )
-        .await
-    );
-    assert!(
-        test.account_does_not_exist(&nullifier_duplicate_account)
-            .await
-    );
-
-    assert_eq!(
-        verification_account_rent.0 + nullifier_duplicate_account_rent.0,
-        warden.lamports(&mut test).await
-    );
-
     // warden has proof_verification_fee and commitment_hash_fee (token)
     assert_eq!(
         proof_verification_fee.amount() + commitment_hash_fee_token.amount(),
         warden.balance(USDC_TOKEN_ID, &mut test).await
     );
 
-    // recipient has amount - optional_fee.amount (token)
+    // recipient has amount (token)
     assert_eq!(
-        request.public_inputs.join_split.amount
-            - request.public_inputs.join_split.optional_fee.amount,
+        request.public_inputs.join_split.amount,
         recipient.balance(USDC_TOKEN_ID, &mut test).await
     );
 
-    // optional_fee_collector has optional_fee.amount (token)
-    assert_eq!(
-        request.public_inputs.join_split.optional_fee.amount,
-        optional_fee_collector
-            .balance(USDC_TOKEN_ID, &mut test)
-            .await
-    );
-
     // fee_collector has network_fee (token)
     assert_eq!(
         network_fee.amount(),
         test.spl_balance(&fee_collector_account).await
     );
-
-    // Pool contains computation_fee (lamports)
-    assert_eq!(
-        commitment_hash_fee.0,
-        test.pda_lamports(&PoolAccount::find(None).0, PoolAccount::SIZE)
-            .await
-            .0
-    );
 }
 
 #[tokio::test]
@@ -1364,6 +1955,7 @@ async fn test_finalize_proof_skip_nullifier_pda() {
     test.airdrop_lamports(&PoolAccount::find(None).0, LAMPORTS_PER_SOL * 1000)
         .await;
 
+    // Each index is used by a single verification, so the n-th verification (at index n) uses generation n
     let init_instructions = |v_index: u8, commitment: Option<U256>, skip_nullifier_pda: bool| {
         let mut request = request.clone();
         if let Some(commitment) = commitment {
@@ -1374,10 +1966,12 @@ async fn test_finalize_proof_skip_nullifier_pda() {
         [
             ElusivInstruction::init_verification_instruction(
                 v_index,
+                u32::from(v_index),
                 SendQuadraVKey::VKEY_ID,
                 [0, 1],
                 ProofRequest::Send(request.public_inputs.clone()),
                 skip_nullifier_pda,
+                ElusivOption::None,
                 WritableSignerAccount(warden.pubkey),
                 WritableUserAccount(nullifier_duplicate_account),
                 UserAccount(Pubkey::new_from_array(extra_data.identifier)),
@@ -1386,10 +1980,12 @@ async fn test_finalize_proof_skip_nullifier_pda() {
             ),
             ElusivInstruction::init_verification_transfer_fee_sol_instruction(
                 v_index,
+                u32::from(v_index),
                 warden.pubkey,
             ),
             ElusivInstruction::init_verification_proof_instruction(
                 v_index,
+                u32::from(v_index),
                 request.proof,
                 SignerAccount(warden.pubkey),
             ),
@@ -1415,13 +2011,20 @@ async fn test_finalize_proof_skip_nullifier_pda() {
 
     // Skip computations
     for (i, is_valid) in (0..3).zip([true, true, false]) {
-        skip_computation(warden.pubkey, i, is_valid, &mut test).await;
+        skip_computation(
+            warden.pubkey,
+            VerificationAccount::pda_offset(i, u32::from(i)),
+            is_valid,
+            &mut test,
+        )
+        .await;
     }
 
     let finalize = |v_index: u8, is_valid: bool| {
         let ixs = [
             ElusivInstruction::finalize_verification_send_instruction(
                 v_index,
+                u32::from(v_index),
                 FinalizeSendData {
                     total_amount: request.public_inputs.join_split.total_amount(),
                     encrypted_owner: extra_data.encrypted_owner,
@@ -1436,16 +2039,20 @@ async fn test_finalize_proof_skip_nullifier_pda() {
             ),
             ElusivInstruction::finalize_verification_insert_nullifier_instruction(
                 v_index,
+                u32::from(v_index),
                 UserAccount(warden.pubkey),
                 Some(0),
                 &writable_user_accounts(&[nullifier_accounts[0]]),
             ),
             ElusivInstruction::finalize_verification_transfer_lamports_instruction(
                 v_index,
+                u32::from(v_index),
                 WritableSignerAccount(warden.pubkey),
                 WritableUserAccount(recipient.pubkey),
                 WritableUserAccount(Pubkey::new_unique()),
                 WritableUserAccount(nullifier_duplicate_account),
+                WritableUserAccount(warden.pubkey),
+                WritableUserAccount(NetworkFeeAccount::find(Some(LAMPORTS_TOKEN_ID.into())).0),
             ),
         ];
 
@@ -1470,18 +2077,18 @@ async fn test_finalize_proof_skip_nullifier_pda() {
 
     assert!(
         test.account_does_not_exist(
-            &VerificationAccount::find_with_pubkey(warden.pubkey, Some(1)).0
+            &VerificationAccount::find_with_generation(warden.pubkey, 1, 1).0
         )
         .await
     );
     assert!(
         test.account_does_not_exist(
-            &VerificationAccount::find_with_pubkey(warden.pubkey, Some(2)).0
+            &VerificationAccount::find_with_generation(warden.pubkey, 2, 2).0
         )
         .await
     );
     assert!(
-        test.account_does_exist(&VerificationAccount::find_with_pubkey(warden.pubkey, Some(0)).0)
+        test.account_does_exist(&VerificationAccount::find_with_generation(warden.pubkey, 0, 0).0)
             .await
     );
     assert!(test.account_does_exist(&nullifier_duplicate_account).await);
@@ -1518,19 +2125,22 @@ async fn test_finalize_proof_commitment_index() {
 
     let init_instructions = [
         ElusivInstruction::init_verification_instruction(
+            0,
             0,
             SendQuadraVKey::VKEY_ID,
             [0, 1],
             ProofRequest::Send(request.public_inputs.clone()),
             false,
+            ElusivOption::None,
             WritableSignerAccount(warden.pubkey),
             WritableUserAccount(nullifier_duplicate_account),
             UserAccount(Pubkey::new_from_array(extra_data.identifier)),
             &user_accounts(&[nullifier_accounts[0]]),
             &[],
         ),
-        ElusivInstruction::init_verification_transfer_fee_sol_instruction(0, warden.pubkey),
+        ElusivInstruction::init_verification_transfer_fee_sol_instruction(0, 0, warden.pubkey),
         ElusivInstruction::init_verification_proof_instruction(
+            0,
             0,
             request.proof,
             SignerAccount(warden.pubkey),
@@ -1544,6 +2154,7 @@ async fn test_finalize_proof_commitment_index() {
     let finalize = |commitment_index: u32| {
         [
             ElusivInstruction::finalize_verification_send_instruction(
+                0,
                 0,
                 FinalizeSendData {
                     total_amount: request.public_inputs.join_split.total_amount(),
@@ -1552,6 +2163,7 @@ async fn test_finalize_proof_commitment_index() {
                     commitment_index,
                     encrypted_owner: extra_data.encrypted_owner,
                     iv: extra_data.iv,
+                    encrypted_memo: None,
                 },
                 false,
                 UserAccount(recipient.pubkey),
@@ -1560,17 +2172,21 @@ async fn test_finalize_proof_commitment_index() {
                 UserAccount(warden.pubkey),
             ),
             ElusivInstruction::finalize_verification_insert_nullifier_instruction(
+                0,
                 0,
                 UserAccount(warden.pubkey),
                 Some(0),
                 &writable_user_accounts(&[nullifier_accounts[0]]),
             ),
             ElusivInstruction::finalize_verification_transfer_lamports_instruction(
+                0,
                 0,
                 WritableSignerAccount(warden.pubkey),
                 WritableUserAccount(recipient.pubkey),
                 WritableUserAccount(Pubkey::new_unique()),
                 WritableUserAccount(nullifier_duplicate_account),
+                WritableUserAccount(warden.pubkey),
+                WritableUserAccount(NetworkFeeAccount::find(Some(LAMPORTS_TOKEN_ID.into())).0),
             ),
         ]
     };
@@ -1626,7 +2242,7 @@ async fn test_associated_token_account() {
         TokenPrice::new_from_sol_price(sol_usd_price, usdc_usd_price, USDC_TOKEN_ID).unwrap();
     let subvention = fee
         .proof_subvention
-        .into_token(&price, USDC_TOKEN_ID)
+        .into_token(&price, USDC_TOKEN_ID, rounding::PAYOUT)
         .unwrap();
     let commitment_hash_fee = fee.commitment_hash_computation_fee(0);
     test.set_token_to_usd_price_pyth(0, sol_usd_price).await;
@@ -1649,11 +2265,16 @@ async fn test_associated_token_account() {
     let nullifier_duplicate_account = request.public_inputs.join_split.nullifier_duplicate_pda().0;
     let nullifier_accounts = nullifier_accounts(&mut test, 0).await;
 
-    let verification_account_rent = test.rent(VerificationAccount::SIZE).await;
-    let nullifier_duplicate_account_rent = test.rent(PDAAccountData::SIZE).await;
+    let verification_account_rent = test
+        .rent(VerificationAccount::account_size(
+            SendQuadraVKey::public_inputs_count(),
+        ))
+        .await;
+    let nullifier_duplicate_account_rent = test.rent(NullifierDuplicateAccount::SIZE).await;
+    let verification_index_account_rent = test.rent(VerificationIndexAccount::SIZE).await;
     let token_account_rent = test.rent(spl_token::state::Account::LEN).await;
     let token_account_rent_token = token_account_rent
-        .into_token(&price, USDC_TOKEN_ID)
+        .into_token(&price, USDC_TOKEN_ID, rounding::FEE)
         .unwrap();
     warden
         .airdrop(
@@ -1661,6 +2282,7 @@ async fn test_associated_token_account() {
             commitment_hash_fee.0
                 + verification_account_rent.0
                 + nullifier_duplicate_account_rent.0
+                + verification_index_account_rent.0
                 + token_account_rent.0,
             &mut test,
         )
@@ -1673,11 +2295,13 @@ async fn test_associated_token_account() {
 
     test.ix_should_succeed(
         ElusivInstruction::init_verification_instruction(
+            0,
             0,
             SendQuadraVKey::VKEY_ID,
             [0, 1],
             ProofRequest::Send(request.clone().public_inputs),
             false,
+            ElusivOption::None,
             WritableSignerAccount(warden.pubkey),
             WritableUserAccount(nullifier_duplicate_account),
             UserAccount(Pubkey::new_from_array(extra_data.identifier)),
@@ -1689,6 +2313,7 @@ async fn test_associated_token_account() {
     .await;
 
     let transfer_ix = ElusivInstruction::init_verification_transfer_fee_token_instruction(
+        0,
         0,
         USDC_TOKEN_ID,
         warden.pubkey,
@@ -1723,6 +2348,7 @@ async fn test_associated_token_account() {
     let instructions = |recipient: Pubkey, recipient_wallet: Pubkey| {
         vec![
             ElusivInstruction::finalize_verification_send_instruction(
+                0,
                 0,
                 FinalizeSendData {
                     total_amount: request.public_inputs.join_split.total_amount(),
@@ -1738,22 +2364,26 @@ async fn test_associated_token_account() {
                 UserAccount(warden.pubkey),
             ),
             ElusivInstruction::finalize_verification_insert_nullifier_instruction(
+                0,
                 0,
                 UserAccount(warden.pubkey),
                 Some(0),
                 &writable_user_accounts(&[nullifier_accounts[0]]),
             ),
             ElusivInstruction::finalize_verification_transfer_token_instruction(
+                0,
                 0,
                 WritableSignerAccount(warden.pubkey),
                 WritableUserAccount(warden.get_token_account(USDC_TOKEN_ID)),
                 WritableUserAccount(recipient),
-                UserAccount(recipient_wallet),
+                WritableUserAccount(recipient_wallet),
                 WritableUserAccount(pool_account),
                 WritableUserAccount(fee_collector_account),
                 WritableUserAccount(Pubkey::new_unique()),
                 WritableUserAccount(nullifier_duplicate_account),
                 UserAccount(mint),
+                WritableUserAccount(warden.pubkey),
+                WritableUserAccount(NetworkFeeAccount::find(Some(USDC_TOKEN_ID.into())).0),
             ),
         ]
     };
@@ -1883,14 +2513,20 @@ async fn test_compute_proof_verifcation_invalid_proof() {
             .len();
     let subvention = fee.proof_subvention;
     let commitment_hash_fee = fee.commitment_hash_computation_fee(0);
-    let verification_account_rent = test.rent(VerificationAccount::SIZE).await;
-    let nullifier_duplicate_account_rent = test.rent(PDAAccountData::SIZE).await;
+    let verification_account_rent = test
+        .rent(VerificationAccount::account_size(
+            SendQuadraVKey::public_inputs_count(),
+        ))
+        .await;
+    let nullifier_duplicate_account_rent = test.rent(NullifierDuplicateAccount::SIZE).await;
+    let verification_index_account_rent = test.rent(VerificationIndexAccount::SIZE).await;
 
     warden
         .airdrop(
             LAMPORTS_TOKEN_ID,
             verification_account_rent.0
                 + nullifier_duplicate_account_rent.0
+                + verification_index_account_rent.0
                 + commitment_hash_fee.0,
             &mut test,
         )
@@ -1900,19 +2536,22 @@ async fn test_compute_proof_verifcation_invalid_proof() {
     test.tx_should_succeed(
         &[
             ElusivInstruction::init_verification_instruction(
+                0,
                 0,
                 SendQuadraVKey::VKEY_ID,
                 [0, 1],
                 ProofRequest::Send(request.public_inputs.clone()),
                 false,
+                ElusivOption::None,
                 WritableSignerAccount(warden.pubkey),
                 WritableUserAccount(nullifier_duplicate_account),
                 UserAccount(Pubkey::new_unique()),
                 &user_accounts(&[nullifier_accounts[0]]),
                 &[],
             ),
-            ElusivInstruction::init_verification_transfer_fee_sol_instruction(0, warden.pubkey),
+            ElusivInstruction::init_verification_transfer_fee_sol_instruction(0, 0, warden.pubkey),
             ElusivInstruction::init_verification_proof_instruction(
+                0,
                 0,
                 request.proof,
                 SignerAccount(warden.pubkey),
@@ -1926,30 +2565,35 @@ async fn test_compute_proof_verifcation_invalid_proof() {
         request_compute_units(1_400_000),
         ComputeBudgetInstruction::set_compute_unit_price(0),
         ElusivInstruction::compute_verification_instruction(
+            0,
             0,
             SendQuadraVKey::VKEY_ID,
             UserAccount(warden.pubkey),
             &[UserAccount(vkey_sub_account)],
         ),
         ElusivInstruction::compute_verification_instruction(
+            0,
             0,
             SendQuadraVKey::VKEY_ID,
             UserAccount(warden.pubkey),
             &[UserAccount(vkey_sub_account)],
         ),
         ElusivInstruction::compute_verification_instruction(
+            0,
             0,
             SendQuadraVKey::VKEY_ID,
             UserAccount(warden.pubkey),
             &[UserAccount(vkey_sub_account)],
         ),
         ElusivInstruction::compute_verification_instruction(
+            0,
             0,
             SendQuadraVKey::VKEY_ID,
             UserAccount(warden.pubkey),
             &[UserAccount(vkey_sub_account)],
         ),
         ElusivInstruction::compute_verification_instruction(
+            0,
             0,
             SendQuadraVKey::VKEY_ID,
             UserAccount(warden.pubkey),
@@ -2003,6 +2647,175 @@ async fn test_compute_proof_verifcation_invalid_proof() {
     assert_eq!(v_acc.get_step(), VerificationStep::FinalExponentiation);
 }
 
+/// Compute units of the standard 1-input lamports send flow compared against the baselines in `tests/cu_baselines.txt`
+///
+/// # Note
+///
+/// The measured compute units are only representative when running against the BPF program (`cargo test-bpf --features cu-trace`).
+#[cfg(feature = "cu-trace")]
+#[tokio::test]
+async fn test_compute_units_send_lamports() {
+    let mut test = start_verification_test().await;
+    let (_, vkey_sub_account) = setup_vkey_account::<SendQuadraVKey>(&mut test).await;
+    let warden = test.new_actor().await;
+    let nullifier_accounts = nullifier_accounts(&mut test, 0).await;
+    let fee = genesis_fee(&mut test).await;
+
+    let mut request = send_request(0);
+    let extra_data = ExtraData::default();
+    request.public_inputs.hashed_inputs = extra_data.hash();
+    request.update_fee_lamports(&fee);
+
+    let pool = PoolAccount::find(None).0;
+    let fee_collector = FeeCollectorAccount::find(None).0;
+    let nullifier_duplicate_account = request.public_inputs.join_split.nullifier_duplicate_pda().0;
+
+    let public_inputs = request.public_inputs.public_signals_skip_mr();
+    let input_preparation_tx_count =
+        prepare_public_inputs_instructions(&public_inputs, SendQuadraVKey::public_inputs_count())
+            .len();
+    let subvention = fee.proof_subvention;
+    let proof_verification_fee = fee.proof_verification_computation_fee(input_preparation_tx_count);
+    let commitment_hash_fee = fee.commitment_hash_computation_fee(0);
+    let network_fee = Lamports(
+        fee.proof_network_fee
+            .calc(request.public_inputs.join_split.amount, rounding::FEE),
+    );
+    let verification_account_rent = test
+        .rent(VerificationAccount::account_size(
+            SendQuadraVKey::public_inputs_count(),
+        ))
+        .await;
+    let nullifier_duplicate_account_rent = test.rent(NullifierDuplicateAccount::SIZE).await;
+    let verification_index_account_rent = test.rent(VerificationIndexAccount::SIZE).await;
+
+    warden
+        .airdrop(
+            LAMPORTS_TOKEN_ID,
+            verification_account_rent.0
+                + nullifier_duplicate_account_rent.0
+                + verification_index_account_rent.0
+                + commitment_hash_fee.0,
+            &mut test,
+        )
+        .await;
+    test.airdrop_lamports(&fee_collector, subvention.0).await;
+    test.airdrop_lamports(
+        &pool,
+        request.public_inputs.join_split.amount + commitment_hash_fee.0 - subvention.0
+            + proof_verification_fee.0
+            + network_fee.0,
+    )
+    .await;
+
+    test.start_cu_trace();
+
+    // Init
+    test.tx_should_succeed(
+        &[
+            request_compute_units(1_400_000),
+            ElusivInstruction::init_verification_instruction(
+                0,
+                0,
+                SendQuadraVKey::VKEY_ID,
+                [0, 1],
+                ProofRequest::Send(request.public_inputs.clone()),
+                false,
+                ElusivOption::None,
+                WritableSignerAccount(warden.pubkey),
+                WritableUserAccount(nullifier_duplicate_account),
+                UserAccount(Pubkey::new_from_array(extra_data.identifier)),
+                &user_accounts(&[nullifier_accounts[0]]),
+                &[],
+            ),
+            ElusivInstruction::init_verification_transfer_fee_sol_instruction(0, 0, warden.pubkey),
+            ElusivInstruction::init_verification_proof_instruction(
+                0,
+                0,
+                request.proof,
+                SignerAccount(warden.pubkey),
+            ),
+        ],
+        &[&warden.keypair],
+    )
+    .await;
+
+    // Computation
+    let compute_verification_instruction = ElusivInstruction::compute_verification_instruction(
+        0,
+        0,
+        SendQuadraVKey::VKEY_ID,
+        UserAccount(warden.pubkey),
+        &[UserAccount(vkey_sub_account)],
+    );
+    let instructions = [
+        request_compute_units(1_400_000),
+        ComputeBudgetInstruction::set_compute_unit_price(0),
+        compute_verification_instruction.clone(),
+        compute_verification_instruction.clone(),
+        compute_verification_instruction.clone(),
+        compute_verification_instruction.clone(),
+        compute_verification_instruction,
+    ];
+    for _ in
+        0..input_preparation_tx_count + CombinedMillerLoop::TX_COUNT + FinalExponentiation::TX_COUNT
+    {
+        test.tx_should_succeed_simple(&instructions).await;
+    }
+
+    // The dummy proof is invalid, so we only use the computation for measuring
+    skip_computation(warden.pubkey, 0, true, &mut test).await;
+
+    // Finalize
+    let recipient = Pubkey::new_from_array(extra_data.recipient);
+    test.tx_should_succeed(
+        &[
+            request_compute_units(1_400_000),
+            ElusivInstruction::finalize_verification_send_instruction(
+                0,
+                0,
+                FinalizeSendData {
+                    total_amount: request.public_inputs.join_split.total_amount(),
+                    encrypted_owner: extra_data.encrypted_owner,
+                    iv: extra_data.iv,
+                    ..Default::default()
+                },
+                false,
+                UserAccount(recipient),
+                UserAccount(Pubkey::new_from_array(extra_data.identifier)),
+                UserAccount(Pubkey::new_from_array(extra_data.reference)),
+                UserAccount(warden.pubkey),
+            ),
+            ElusivInstruction::finalize_verification_insert_nullifier_instruction(
+                0,
+                0,
+                UserAccount(warden.pubkey),
+                Some(0),
+                &writable_user_accounts(&[nullifier_accounts[0]]),
+            ),
+            ElusivInstruction::finalize_verification_transfer_lamports_instruction(
+                0,
+                0,
+                WritableSignerAccount(warden.pubkey),
+                WritableUserAccount(recipient),
+                WritableUserAccount(warden.pubkey),
+                WritableUserAccount(nullifier_duplicate_account),
+                WritableUserAccount(warden.pubkey),
+                WritableUserAccount(NetworkFeeAccount::find(Some(LAMPORTS_TOKEN_ID.into())).0),
+            ),
+        ],
+        &[&warden.keypair],
+    )
+    .await;
+
+    let table = compute_units::parse_cu_checkpoints(&test.take_cu_trace());
+    assert!(table.contains_key("init_verification: verification account"));
+    assert!(table.contains_key("compute_verification: verify partial"));
+    assert!(table.contains_key("finalize_verification_transfer_lamports: enqueue commitment"));
+
+    compute_units::check_cu_baselines(std::path::Path::new("tests/cu_baselines.txt"), &table, 10);
+}
+
 #[tokio::test]
 async fn test_enforced_finalization_order() {
     let mut test = start_verification_test().await;
@@ -2032,6 +2845,7 @@ async fn test_enforced_finalization_order() {
 
     let finalize_verification_send_instruction =
         ElusivInstruction::finalize_verification_send_instruction(
+            0,
             0,
             FinalizeSendData {
                 total_amount: request.public_inputs.join_split.total_amount(),
@@ -2047,6 +2861,7 @@ async fn test_enforced_finalization_order() {
         );
     let finalize_verification_send_nullifier_instruction =
         ElusivInstruction::finalize_verification_insert_nullifier_instruction(
+            0,
             0,
             UserAccount(test.payer()),
             Some(0),
@@ -2054,11 +2869,14 @@ async fn test_enforced_finalization_order() {
         );
     let finalize_verification_transfer_lamports_instruction =
         ElusivInstruction::finalize_verification_transfer_lamports_instruction(
+            0,
             0,
             WritableSignerAccount(test.payer()),
             WritableUserAccount(extra_data.recipient()),
             WritableUserAccount(Pubkey::new_unique()),
             WritableUserAccount(nullifier_duplicate_account),
+            WritableUserAccount(test.payer()),
+            WritableUserAccount(NetworkFeeAccount::find(Some(LAMPORTS_TOKEN_ID.into())).0),
         );
 
     set_verification_state(test.payer(), 0, VerificationState::ProofSetup, &mut test).await;
@@ -2123,6 +2941,9 @@ async fn nullifier_finalization_test(number_of_start_nullifiers: u64, input_comm
         recipient_is_associated_token_account: false,
         hashed_inputs: extra_data.hash(),
         solana_pay_transfer: false,
+        recipient_is_program_owned: false,
+        view_tag: 0,
+        current_time: 0,
     };
     compute_fee_rec_lamports::<SendQuadraVKey, _>(
         &mut public_inputs,
@@ -2144,6 +2965,7 @@ async fn nullifier_finalization_test(number_of_start_nullifiers: u64, input_comm
     let mut instructions = vec![
         request_compute_units(1_400_000),
         ElusivInstruction::finalize_verification_send_instruction(
+            0,
             0,
             FinalizeSendData {
                 total_amount: public_inputs.join_split.total_amount(),
@@ -2174,6 +2996,7 @@ async fn nullifier_finalization_test(number_of_start_nullifiers: u64, input_comm
 
         instructions.push(
             ElusivInstruction::finalize_verification_insert_nullifier_instruction(
+                0,
                 0,
                 UserAccount(test.payer()),
                 Some(0),
@@ -2190,6 +3013,7 @@ async fn nullifier_finalization_test(number_of_start_nullifiers: u64, input_comm
     for i in 0..number_of_movement_instructions {
         instructions.push(
             ElusivInstruction::finalize_verification_insert_nullifier_instruction(
+                0,
                 0,
                 UserAccount(test.payer()),
                 Some(0),
@@ -2200,11 +3024,14 @@ async fn nullifier_finalization_test(number_of_start_nullifiers: u64, input_comm
 
     instructions.push(
         ElusivInstruction::finalize_verification_transfer_lamports_instruction(
+            0,
             0,
             WritableSignerAccount(test.payer()),
             WritableUserAccount(recipient),
             WritableUserAccount(Pubkey::new_unique()),
             WritableUserAccount(nullifier_duplicate_account),
+            WritableUserAccount(test.payer()),
+            WritableUserAccount(NetworkFeeAccount::find(Some(LAMPORTS_TOKEN_ID.into())).0),
         ),
     );
 
@@ -2234,6 +3061,7 @@ async fn finalize_instructions(
 
     vec![
         ElusivInstruction::finalize_verification_send_instruction(
+            0,
             0,
             FinalizeSendData {
                 total_amount: request.public_inputs.join_split.total_amount(),
@@ -2248,17 +3076,21 @@ async fn finalize_instructions(
             UserAccount(*signer),
         ),
         ElusivInstruction::finalize_verification_insert_nullifier_instruction(
+            0,
             0,
             UserAccount(*signer),
             Some(0),
             &writable_user_accounts(&[nullifier_accounts[0]]),
         ),
         ElusivInstruction::finalize_verification_transfer_lamports_instruction(
+            0,
             0,
             WritableSignerAccount(*signer),
             WritableUserAccount(extra_data.recipient()),
             WritableUserAccount(Pubkey::new_unique()),
             WritableUserAccount(request.public_inputs.join_split.nullifier_duplicate_pda().0),
+            WritableUserAccount(*signer),
+            WritableUserAccount(NetworkFeeAccount::find(Some(LAMPORTS_TOKEN_ID.into())).0),
         ),
     ]
 }
@@ -2655,11 +3487,13 @@ async fn test_solana_pay_tokens() {
     test.tx_should_succeed(
         &[
             ElusivInstruction::init_verification_instruction(
+                0,
                 0,
                 SendQuadraVKey::VKEY_ID,
                 [0, 1],
                 ProofRequest::Send(request.public_inputs.clone()),
                 false,
+                ElusivOption::None,
                 WritableSignerAccount(warden.pubkey),
                 WritableUserAccount(nullifier_duplicate_account),
                 UserAccount(Pubkey::new_from_array(extra_data.identifier)),
@@ -2667,6 +3501,7 @@ async fn test_solana_pay_tokens() {
                 &[],
             ),
             ElusivInstruction::init_verification_transfer_fee_instruction(
+                0,
                 0,
                 WritableSignerAccount(warden.pubkey),
                 WritableUserAccount(warden.get_token_account(USDC_TOKEN_ID)),
@@ -2677,6 +3512,7 @@ async fn test_solana_pay_tokens() {
                 UserAccount(spl_token::id()),
             ),
             ElusivInstruction::init_verification_proof_instruction(
+                0,
                 0,
                 request.proof,
                 SignerAccount(warden.pubkey),
@@ -2690,6 +3526,7 @@ async fn test_solana_pay_tokens() {
 
     let valid_finalize_ixs = vec![
         ElusivInstruction::finalize_verification_send_instruction(
+            0,
             0,
             FinalizeSendData {
                 total_amount: request.public_inputs.join_split.total_amount(),
@@ -2705,22 +3542,26 @@ async fn test_solana_pay_tokens() {
             UserAccount(warden.pubkey),
         ),
         ElusivInstruction::finalize_verification_insert_nullifier_instruction(
+            0,
             0,
             UserAccount(warden.pubkey),
             Some(0),
             &writable_user_accounts(&[nullifier_accounts[0]]),
         ),
         ElusivInstruction::finalize_verification_transfer_token_instruction(
+            0,
             0,
             WritableSignerAccount(warden.pubkey),
             WritableUserAccount(warden.get_token_account(USDC_TOKEN_ID)),
             WritableUserAccount(recipient_token_account),
-            UserAccount(recipient_token_account),
+            WritableUserAccount(recipient_token_account),
             WritableUserAccount(pool_account),
             WritableUserAccount(fee_collector_account),
             WritableUserAccount(Pubkey::new_unique()),
             WritableUserAccount(nullifier_duplicate_account),
             UserAccount(spl_token::id()),
+            WritableUserAccount(warden.pubkey),
+            WritableUserAccount(NetworkFeeAccount::find(Some(USDC_TOKEN_ID.into())).0),
         ),
     ];
 
@@ -23,22 +23,17 @@ pub fn impl_elusiv_instruction(ast: &syn::DeriveInput) -> proc_macro2::TokenStre
     let mut matches = quote!();
     let mut functions = quote!();
     let mut abi_functions = quote!();
-    let mut variant_indices = quote!();
 
     if let syn::Data::Enum(e) = &ast.data {
-        for (var_index, var) in e.variants.clone().iter().enumerate() {
+        for var in e.variants.clone().iter() {
             let ident = &var.ident;
             let name = upper_camel_to_upper_snake(&ident.to_string()).to_lowercase();
             let fn_name_abi: TokenStream = format!("{name}_instruction").parse().unwrap();
+            let fn_name_slots: TokenStream = format!("{name}_account_slots").parse().unwrap();
+            let fn_name_validate: TokenStream =
+                format!("{name}_validate_accounts").parse().unwrap();
             let fn_name: TokenStream = name.parse().unwrap();
 
-            let var_index_name: TokenStream =
-                format!("{}_INDEX", name.to_uppercase()).parse().unwrap();
-            let var_index = var_index as u8;
-            variant_indices.extend(quote! {
-                pub const #var_index_name: u8 = #var_index;
-            });
-
             // Processor calls
             let mut accounts = quote!();
             let mut fields = quote!();
@@ -48,6 +43,8 @@ pub fn impl_elusiv_instruction(ast: &syn::DeriveInput) -> proc_macro2::TokenStre
             let mut fields_with_type = quote!();
             let mut user_accounts = quote!();
             let mut instruction_accounts = quote!();
+            let mut instruction_slots = quote!();
+            let mut user_account_names = quote!();
 
             let mut docs = quote!();
             let mut other_attrs = quote!();
@@ -124,10 +121,24 @@ pub fn impl_elusiv_instruction(ast: &syn::DeriveInput) -> proc_macro2::TokenStre
 
                 let mut account: TokenStream = sub_attrs[0].0.parse().unwrap();
                 let mut account_init = Vec::new(); // used for creating the instruction objects with the abi-feature
-
-                accounts.extend(quote! {
-                    let #account = &solana_program::account_info::next_account_info(account_info_iter)?;
-                });
+                let mut slot_init = Vec::new(); // used for validating fetched accounts with the abi-feature
+
+                // All remaining accounts (as a slice)
+                let is_remaining = contains_key(&sub_attrs, "remaining");
+                if is_remaining {
+                    assert_eq!(
+                        attr_name, ACC_ATTR,
+                        "'remaining' can only be used with 'acc'"
+                    );
+
+                    accounts.extend(quote! {
+                        let #account = account_info_iter.as_slice();
+                    });
+                } else {
+                    accounts.extend(quote! {
+                        let #account = &solana_program::account_info::next_account_info(account_info_iter)?;
+                    });
+                }
 
                 // Signer check
                 let is_signer = contains_key(&sub_attrs, "signer");
@@ -185,10 +196,35 @@ pub fn impl_elusiv_instruction(ast: &syn::DeriveInput) -> proc_macro2::TokenStre
                 match attr_name.as_str() {
                     // `AccountInfo` (usage: <name>)
                     ACC_ATTR => {
-                        user_accounts.extend(quote! { #account: #user_account_type, });
-                        account_init.push(quote!{
-                            accounts.push(solana_program::instruction::AccountMeta::#account_init_fn(#account.0, #is_signer));
-                        });
+                        let expected_owner = if is_owned {
+                            quote! { Some(crate::ID) }
+                        } else {
+                            quote! { None }
+                        };
+
+                        if is_remaining {
+                            user_accounts.extend(quote! { #account: &[#user_account_type], });
+                            user_account_names.extend(quote! { #account, });
+                            account_init.push(quote!{
+                                for account in #account {
+                                    accounts.push(solana_program::instruction::AccountMeta::#account_init_fn(account.0, #is_signer));
+                                }
+                            });
+                            slot_init.push(quote!{
+                                for account in #account {
+                                    slots.push(elusiv_types::accounts::AccountSlot::new(account.0, #expected_owner, 0, #is_writable));
+                                }
+                            });
+                        } else {
+                            user_accounts.extend(quote! { #account: #user_account_type, });
+                            user_account_names.extend(quote! { #account, });
+                            account_init.push(quote!{
+                                accounts.push(solana_program::instruction::AccountMeta::#account_init_fn(#account.0, #is_signer));
+                            });
+                            slot_init.push(quote!{
+                                slots.push(elusiv_types::accounts::AccountSlot::new(#account.0, #expected_owner, 0, #is_writable));
+                            });
+                        }
                     }
 
                     // System program `AccountInfo` (usage: <name> <key = ..>)
@@ -204,6 +240,9 @@ pub fn impl_elusiv_instruction(ast: &syn::DeriveInput) -> proc_macro2::TokenStre
                         account_init.push(quote!{
                             accounts.push(solana_program::instruction::AccountMeta::#account_init_fn(#key, #is_signer));
                         });
+                        slot_init.push(quote!{
+                            slots.push(elusiv_types::accounts::AccountSlot::new(#key, None, 0, #is_writable));
+                        });
                     }
 
                     // PDA accounts (usage: <name> <AccountType> <pda_offset: u32 = ..>? <account_info>? <include_child_accounts>? <ownership>)
@@ -228,7 +267,7 @@ pub fn impl_elusiv_instruction(ast: &syn::DeriveInput) -> proc_macro2::TokenStre
                             contains_key(&sub_attrs, "include_child_accounts");
 
                         let skip_abi = contains_key(&sub_attrs, "skip_abi");
-                        if skip_abi {
+                        let pda_key = if skip_abi {
                             let offset_ident: TokenStream =
                                 format!("{}_pda_offset", sub_attrs[0].0).parse().unwrap();
 
@@ -237,44 +276,32 @@ pub fn impl_elusiv_instruction(ast: &syn::DeriveInput) -> proc_macro2::TokenStre
                                     format!("{}_pda_pubkey", sub_attrs[0].0).parse().unwrap();
 
                                 user_accounts.extend(quote!{ #pubkey_ident: solana_program::pubkey::Pubkey, #offset_ident: Option<u32>, });
-                                account_init.push(quote!{
-                                    accounts.push(
-                                        solana_program::instruction::AccountMeta::#account_init_fn(
-                                            <#ty as elusiv_types::accounts::PDAAccount>::find_with_pubkey(#pubkey_ident, #offset_ident).0,
-                                            #is_signer
-                                        )
-                                    );
-                                });
+                                user_account_names.extend(quote! { #pubkey_ident, #offset_ident, });
+                                quote! {
+                                    <#ty as elusiv_types::accounts::PDAAccount>::find_with_pubkey(#pubkey_ident, #offset_ident).0
+                                }
                             } else {
                                 user_accounts.extend(quote! { #offset_ident: Option<u32>, });
-                                account_init.push(quote!{
-                                    accounts.push(
-                                        solana_program::instruction::AccountMeta::#account_init_fn(
-                                            <#ty as elusiv_types::accounts::PDAAccount>::find(#offset_ident).0,
-                                            #is_signer
-                                        )
-                                    );
-                                });
+                                user_account_names.extend(quote! { #offset_ident, });
+                                quote! {
+                                    <#ty as elusiv_types::accounts::PDAAccount>::find(#offset_ident).0
+                                }
                             }
                         } else if let Some(pda_pubkey) = pda_pubkey.clone() {
-                            account_init.push(quote!{
-                                accounts.push(
-                                    solana_program::instruction::AccountMeta::#account_init_fn(
-                                        <#ty as elusiv_types::accounts::PDAAccount>::find_with_pubkey(#pda_pubkey, #pda_offset).0,
-                                        #is_signer
-                                    )
-                                );
-                            });
+                            quote! {
+                                <#ty as elusiv_types::accounts::PDAAccount>::find_with_pubkey(#pda_pubkey, #pda_offset).0
+                            }
                         } else {
-                            account_init.push(quote!{
-                                accounts.push(
-                                    solana_program::instruction::AccountMeta::#account_init_fn(
-                                        <#ty as elusiv_types::accounts::PDAAccount>::find(#pda_offset).0,
-                                        #is_signer
-                                    )
-                                );
-                            });
-                        }
+                            quote! {
+                                <#ty as elusiv_types::accounts::PDAAccount>::find(#pda_offset).0
+                            }
+                        };
+
+                        account_init.push(quote!{
+                            accounts.push(
+                                solana_program::instruction::AccountMeta::#account_init_fn(#pda_key, #is_signer)
+                            );
+                        });
 
                         // PDA verification
                         let find_pda = contains_key(&sub_attrs, "find_pda"); // does not read the bump byte from the account data
@@ -283,6 +310,24 @@ pub fn impl_elusiv_instruction(ast: &syn::DeriveInput) -> proc_macro2::TokenStre
                         let skip_pda_verification =
                             contains_key(&sub_attrs, "skip_pda_verification");
 
+                        // Accounts that are used as `AccountInfo` or whose PDA is not verified (yet) might not be initialized
+                        if as_account_info || find_pda || skip_pda_verification {
+                            slot_init.push(quote!{
+                                slots.push(elusiv_types::accounts::AccountSlot::new(#pda_key, None, 0, #is_writable));
+                            });
+                        } else {
+                            slot_init.push(quote! {
+                                slots.push(
+                                    elusiv_types::accounts::AccountSlot::new(
+                                        #pda_key,
+                                        Some(crate::ID),
+                                        <#ty as elusiv_types::accounts::SizedAccount>::SIZE,
+                                        #is_writable,
+                                    )
+                                );
+                            });
+                        }
+
                         #[allow(clippy::collapsible_else_if)]
                         if !skip_pda_verification {
                             let check_pda = if find_pda {
@@ -328,11 +373,24 @@ pub fn impl_elusiv_instruction(ast: &syn::DeriveInput) -> proc_macro2::TokenStre
                             });
 
                             user_accounts.extend(quote! { #account: &[#user_account_type], });
+                            user_account_names.extend(quote! { #account, });
                             account_init.push(quote!{
                                 for account in #account {
                                     accounts.push(solana_program::instruction::AccountMeta::#account_init_fn(account.0, #is_signer));
                                 }
                             });
+                            slot_init.push(quote!{
+                                for account in #account {
+                                    slots.push(
+                                        elusiv_types::accounts::AccountSlot::new(
+                                            account.0,
+                                            Some(crate::ID),
+                                            <<#ty as elusiv_types::accounts::ParentAccount>::Child as elusiv_types::accounts::SizedAccount>::SIZE,
+                                            #is_writable,
+                                        )
+                                    );
+                                }
+                            });
 
                             if as_account_info {
                                 accounts.extend(quote! {
@@ -394,6 +452,11 @@ pub fn impl_elusiv_instruction(ast: &syn::DeriveInput) -> proc_macro2::TokenStre
                         .iter()
                         .fold(quote! {}, |acc, x| quote! { #acc #x }),
                 );
+                instruction_slots.extend(
+                    slot_init
+                        .iter()
+                        .fold(quote! {}, |acc, x| quote! { #acc #x }),
+                );
             }
 
             matches.extend(quote! {
@@ -429,6 +492,29 @@ pub fn impl_elusiv_instruction(ast: &syn::DeriveInput) -> proc_macro2::TokenStre
                         accounts,
                     )
                 }
+
+                /// The expected owner, minimum size and writability of each account slot (in instruction order)
+                #other_attrs
+                #[allow(unused_variables)]
+                pub fn #fn_name_slots(#fields_with_type #user_accounts) -> Vec<elusiv_types::accounts::AccountSlot> {
+                    let mut slots = Vec::new();
+
+                    #instruction_slots
+                    slots
+                }
+
+                /// Validates fetched accounts against the account slots of the instruction
+                #other_attrs
+                pub fn #fn_name_validate(
+                    #fields_with_type
+                    #user_accounts
+                    rpc_snapshot: &std::collections::HashMap<solana_program::pubkey::Pubkey, elusiv_types::accounts::AccountSnapshot>,
+                ) -> Result<(), elusiv_types::accounts::ValidationError> {
+                    elusiv_types::accounts::validate_accounts(
+                        &Self::#fn_name_slots(#fields #user_account_names),
+                        rpc_snapshot,
+                    )
+                }
             });
         }
 
@@ -442,8 +528,6 @@ pub fn impl_elusiv_instruction(ast: &syn::DeriveInput) -> proc_macro2::TokenStre
                 }
 
                 #functions
-
-                #variant_indices
             }
 
             #[cfg(feature = "elusiv-client")]
@@ -1,37 +1,91 @@
+use super::utils::upper_camel_to_upper_snake;
 use proc_macro2::TokenStream;
-use quote::quote;
+use quote::{format_ident, quote};
 
 pub fn impl_enum_variant_index(ast: &syn::DeriveInput) -> TokenStream {
     let ident = &ast.ident.clone();
+    let vis = &ast.vis;
+    let kind_ident = format_ident!("{}Kind", ident);
+    let kind_doc = format!(
+        "The variants of [`{ident}`] without their fields (convertible from a variant index)"
+    );
     let (impl_generics, ty_generics, where_clause) = &ast.generics.split_for_impl();
     let mut output = quote! {};
+    let mut kind_variants = quote! {};
+    let mut kind_matches = quote! {};
+    let mut kind_indices = quote! {};
+    let mut index_consts = quote! {};
 
-    match &ast.data {
+    let variant_count = match &ast.data {
         syn::Data::Enum(e) => {
             assert!(e.variants.len() <= u8::MAX as usize);
 
             for (i, var) in e.variants.iter().enumerate() {
                 let id = var.ident.clone();
                 let i = i as u8;
+                let index_name =
+                    format_ident!("{}_INDEX", upper_camel_to_upper_snake(&id.to_string()));
 
                 output.extend(quote! {
                     #ident::#id { .. } => #i,
-                })
+                });
+                kind_variants.extend(quote! {
+                    #id,
+                });
+                kind_matches.extend(quote! {
+                    #ident::#id { .. } => #kind_ident::#id,
+                });
+                kind_indices.extend(quote! {
+                    #i => Ok(#kind_ident::#id),
+                });
+                index_consts.extend(quote! {
+                    pub const #index_name: u8 = #i;
+                });
             }
+
+            e.variants.len()
         }
         _ => {
             panic!()
         }
-    }
+    };
 
     quote! {
         impl #impl_generics #ident #ty_generics #where_clause {
+            pub const VARIANT_COUNT: usize = #variant_count;
+
+            #index_consts
+
             pub fn variant_index(&self) -> u8 {
                 match self {
                     #output
                     _ => panic!()
                 }
             }
+
+            pub fn kind(&self) -> #kind_ident {
+                match self {
+                    #kind_matches
+                    _ => panic!()
+                }
+            }
+        }
+
+        #[doc = #kind_doc]
+        #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+        #vis enum #kind_ident {
+            #kind_variants
+        }
+
+        impl TryFrom<u8> for #kind_ident {
+            type Error = u8;
+
+            fn try_from(index: u8) -> Result<Self, Self::Error> {
+                match index {
+                    #kind_indices
+                    _ => Err(index),
+                }
+            }
         }
     }
 }
@@ -40,6 +40,7 @@ use pda_account::*;
 ///         - `account_info`: returns an `AccountInfo` object (only relevant for PDAs)
 ///         - `include_child_accounts`: the `Type` has to implement the `crate::state::program_account::ParentAccount` trait and up to `Type::COUNT + 1` accounts can be matched (but at least 1)
 ///         - `skip_abi`: can be used to add manual pda_offsets in the abi
+///         - `remaining`: matches all remaining accounts as a `&[AccountInfo]` (only for `acc`, has to be the last account attribute)
 ///
 /// # Other attributes
 /// - Each variant can also be equipped with any other kind of attributes (cfg or do documentation).
@@ -0,0 +1,54 @@
+use elusiv_derive::EnumVariantIndex;
+
+#[derive(EnumVariantIndex, Debug, PartialEq)]
+pub enum TestEnum {
+    Unit,
+    Tuple(u8, u16),
+    NamedFields { a: u32 },
+    TwoWordName,
+}
+
+#[test]
+fn test_variant_index() {
+    assert_eq!(TestEnum::Unit.variant_index(), 0);
+    assert_eq!(TestEnum::Tuple(1, 2).variant_index(), 1);
+    assert_eq!(TestEnum::NamedFields { a: 3 }.variant_index(), 2);
+    assert_eq!(TestEnum::TwoWordName.variant_index(), 3);
+}
+
+#[test]
+fn test_index_constants() {
+    assert_eq!(TestEnum::VARIANT_COUNT, 4);
+
+    assert_eq!(TestEnum::UNIT_INDEX, 0);
+    assert_eq!(TestEnum::TUPLE_INDEX, 1);
+    assert_eq!(TestEnum::NAMED_FIELDS_INDEX, 2);
+    assert_eq!(TestEnum::TWO_WORD_NAME_INDEX, 3);
+}
+
+#[test]
+fn test_kind_try_from() {
+    let values = [
+        TestEnum::Unit,
+        TestEnum::Tuple(1, 2),
+        TestEnum::NamedFields { a: 3 },
+        TestEnum::TwoWordName,
+    ];
+
+    for value in values {
+        assert_eq!(
+            TestEnumKind::try_from(value.variant_index()),
+            Ok(value.kind())
+        );
+    }
+
+    assert_eq!(
+        TestEnumKind::try_from(TestEnum::NAMED_FIELDS_INDEX),
+        Ok(TestEnumKind::NamedFields)
+    );
+    assert_eq!(
+        TestEnumKind::try_from(TestEnum::VARIANT_COUNT as u8),
+        Err(TestEnum::VARIANT_COUNT as u8)
+    );
+    assert_eq!(TestEnumKind::try_from(u8::MAX), Err(u8::MAX));
+}
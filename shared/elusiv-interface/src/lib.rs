@@ -0,0 +1,17 @@
+//! Elusiv program interface used by other programs (e.g. the Warden-Network)
+//!
+//! # Note
+//!
+//! The values are verified against the Elusiv program in it's unit-tests.
+
+/// Instruction-index of `ElusivInstruction::FinalizeBaseCommitmentHash`
+pub const FINALIZE_BASE_COMMITMENT_HASH_INDEX: u8 = 2;
+
+/// Instruction-index of `ElusivInstruction::FinalizeVerificationTransferLamports`
+pub const FINALIZE_VERIFICATION_TRANSFER_LAMPORTS_INDEX: u8 = 13;
+
+/// Instruction-index of `ElusivInstruction::FinalizeVerificationTransferToken`
+pub const FINALIZE_VERIFICATION_TRANSFER_TOKEN_INDEX: u8 = 14;
+
+/// Account-index of the (original) fee payer in all fee-reimbursing Elusiv instructions
+pub const FEE_PAYER_ACCOUNT_INDEX: usize = 0;
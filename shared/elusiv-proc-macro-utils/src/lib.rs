@@ -56,6 +56,12 @@ pub fn pda(pda_seed: &[u8]) -> (Pubkey, u8) {
     Pubkey::find_program_address(&[pda_seed], &program_id)
 }
 
+/// Computes the PDA of a seed and an offset for the program `program_name`
+pub fn program_pda(program_name: &str, pda_seed: &[u8], pda_offset: u32) -> Pubkey {
+    let program_id = Pubkey::from_str(&read_program_id(program_name)).unwrap();
+    Pubkey::find_program_address(&[pda_seed, &pda_offset.to_le_bytes()], &program_id).0
+}
+
 pub fn pubkey_bytes(pubkey: &str) -> TokenStream {
     format!("{:?}", Pubkey::from_str(pubkey).unwrap().to_bytes())
         .parse()
@@ -95,6 +95,20 @@ fn anonymize_type_lifetimes(ty: &mut Type) {
     }
 }
 
+/// Maps a deserialized field value to its human-readable representation (used by the `debug-display` feature)
+fn debug_display_value(ty: &Type, value: TokenStream) -> TokenStream {
+    let type_ident = match ty {
+        Type::Path(path) => path.path.segments.last().map(|s| s.ident.to_string()),
+        _ => None,
+    };
+
+    match type_ident.as_deref() {
+        Some("U256") => quote! { elusiv_types::display::Decimal(#value) },
+        Some("RawU256") => quote! { elusiv_types::display::Decimal((#value).skip_mr()) },
+        _ => value,
+    }
+}
+
 pub fn impl_elusiv_account(ast: &syn::DeriveInput, attrs: TokenStream) -> TokenStream {
     let ident = ast.ident.clone();
     let eager_ident: TokenStream = format!("{}Eager", ident).parse().unwrap();
@@ -128,6 +142,8 @@ pub fn impl_elusiv_account(ast: &syn::DeriveInput, attrs: TokenStream) -> TokenS
     let mut eager_defs = quote!();
     let mut eager_init = quote!();
     let mut use_eager_type = false;
+    let mut trailing_data = None;
+    let mut debug_fields = quote!();
 
     // 'a lifetime for the `ProgramAccount` impl
     let program_account_lifetime = quote!('a);
@@ -155,6 +171,34 @@ pub fn impl_elusiv_account(ast: &syn::DeriveInput, attrs: TokenStream) -> TokenS
                 let b_lifetime = lifetimes.lifetimes[1].clone();
                 let t_lifetime = lifetimes.lifetimes[2].clone();
 
+                // An optional `verified_children` bitmap allows for skipping the verification of child-accounts
+                let has_verified_children = s.fields.iter().any(|field| {
+                    field.ident.as_ref().map(|i| i.to_string())
+                        == Some(String::from("verified_children"))
+                });
+                let (verified_children_fns, invalidate_verified_child) = if has_verified_children {
+                    impls.extend(quote! {
+                        const _: () = assert!(#child_account_count <= u64::BITS as usize);
+                    });
+
+                    (
+                        quote! {
+                            fn verified_children_bitmap(&self) -> u64 {
+                                self.get_verified_children()
+                            }
+
+                            fn set_verified_children_bitmap(&mut self, bitmap: u64) {
+                                self.set_verified_children(&bitmap)
+                            }
+                        },
+                        quote! {
+                            self.set_verified_children(&(self.get_verified_children() & !(1 << index)));
+                        },
+                    )
+                } else {
+                    (quote! {}, quote! {})
+                };
+
                 impls.extend(quote!{
                     impl < #lifetimes > elusiv_types::accounts::ParentAccount < #program_account_lifetime, #b_lifetime, #t_lifetime > for #ident < #lifetimes > {
                         const COUNT: usize = #child_account_count;
@@ -165,9 +209,12 @@ pub fn impl_elusiv_account(ast: &syn::DeriveInput, attrs: TokenStream) -> TokenS
                         }
 
                         fn set_child_pubkey(&mut self, index: usize, pubkey: ElusivOption<solana_program::pubkey::Pubkey>) {
+                            #invalidate_verified_child
                             self.set_pubkeys(index, &pubkey)
                         }
 
+                        #verified_children_fns
+
                         fn get_child_pubkey(&self, index: usize) -> Option<solana_program::pubkey::Pubkey> {
                             self.get_pubkeys(index).option()
                         }
@@ -238,6 +285,11 @@ pub fn impl_elusiv_account(ast: &syn::DeriveInput, attrs: TokenStream) -> TokenS
                 use_eager_type = true;
             }
 
+            // Exposes all data following the sized fields as a raw byte slice (`SIZE` becomes the minimum account size)
+            "trailing_data" => {
+                trailing_data = Some(attr.value.clone());
+            }
+
             any => panic!("Invalid attribute '{}'", any),
         }
     }
@@ -348,6 +400,13 @@ pub fn impl_elusiv_account(ast: &syn::DeriveInput, attrs: TokenStream) -> TokenS
                 if custom_field {
                     sizes.push(quote! { <#ty as elusiv_types::bytes::SizedType>::SIZE });
 
+                    debug_fields.extend(quote! {
+                        debug.field(
+                            stringify!(#field_ident),
+                            &elusiv_types::display::Bytes(<#ty as elusiv_types::bytes::SizedType>::SIZE),
+                        );
+                    });
+
                     if is_type_lifetime_bound(ty) {
                         let mut ty2 = ty.clone();
                         anonymize_type_lifetimes(&mut ty2);
@@ -373,6 +432,14 @@ pub fn impl_elusiv_account(ast: &syn::DeriveInput, attrs: TokenStream) -> TokenS
                         let #field_ident = <#ty as borsh::BorshDeserialize>::try_from_slice(#field_ident)?;
                     });
 
+                    let value = debug_display_value(ty, quote! { #field_ident });
+                    debug_fields.extend(quote! {
+                        match <#ty as borsh::BorshDeserialize>::deserialize(&mut &self.#field_ident[..]) {
+                            Ok(#field_ident) => debug.field(stringify!(#field_ident), &#value),
+                            Err(_) => debug.field(stringify!(#field_ident), &elusiv_types::display::Invalid(self.#field_ident.len())),
+                        };
+                    });
+
                     if use_getter {
                         fns.extend(quote!{
                             #doc
@@ -398,6 +465,7 @@ pub fn impl_elusiv_account(ast: &syn::DeriveInput, attrs: TokenStream) -> TokenS
                     panic!("Custom fields are not allowed with Array-types");
                 }
 
+                let value = debug_display_value(&array.elem, quote! { v });
                 let ty = array.elem.clone().into_token_stream();
                 let len = array.len.clone();
                 let size = quote! { <#ty as elusiv_types::bytes::BorshSerDeSized>::SIZE * #len };
@@ -412,6 +480,17 @@ pub fn impl_elusiv_account(ast: &syn::DeriveInput, attrs: TokenStream) -> TokenS
                     let #field_ident = <[#ty; #len] as borsh::BorshDeserialize>::try_from_slice(#field_ident)?;
                 });
 
+                debug_fields.extend(quote! {
+                    match self.#field_ident
+                        .chunks(<#ty as elusiv_types::bytes::BorshSerDeSized>::SIZE)
+                        .map(|v| <#ty as borsh::BorshDeserialize>::deserialize(&mut &v[..]).map(|v| #value))
+                        .collect::<Result<Vec<_>, _>>()
+                    {
+                        Ok(#field_ident) => debug.field(stringify!(#field_ident), &#field_ident),
+                        Err(_) => debug.field(stringify!(#field_ident), &elusiv_types::display::Invalid(self.#field_ident.len())),
+                    };
+                });
+
                 if use_getter {
                     fns.extend(quote!{
                         #doc
@@ -437,6 +516,37 @@ pub fn impl_elusiv_account(ast: &syn::DeriveInput, attrs: TokenStream) -> TokenS
         }
     }
 
+    let invalid_data_len = if let Some(trailing_ident) = &trailing_data {
+        field_defs.extend(quote! {
+            #trailing_ident: &'a mut [u8],
+        });
+        field_idents.extend(quote! {
+            #trailing_ident,
+        });
+        fields_split.extend(quote! {
+            let #trailing_ident = data;
+        });
+
+        eager_defs.extend(quote! {
+            pub #trailing_ident: Vec<u8>,
+        });
+        eager_idents.extend(quote! { #trailing_ident, });
+        eager_init.extend(quote! {
+            let #trailing_ident = data.to_vec();
+        });
+
+        debug_fields.extend(quote! {
+            debug.field(
+                stringify!(#trailing_ident),
+                &elusiv_types::display::Bytes(self.#trailing_ident.len()),
+            );
+        });
+
+        quote! { data.len() < }
+    } else {
+        quote! { data.len() != }
+    };
+
     let account_size_test: TokenStream =
         format!("test_{}_account_size", ident.to_string().to_lowercase())
             .parse()
@@ -449,6 +559,13 @@ pub fn impl_elusiv_account(ast: &syn::DeriveInput, attrs: TokenStream) -> TokenS
         }
     });
     let anonymous_lifetimes = lifetimes.as_anonymous_lifetimes();
+    let dump_ident: TokenStream = format!("dump_{}", camel_to_snake(&ident.to_string()))
+        .parse()
+        .unwrap();
+    let dump_doc = format!(
+        "Pretty-prints the raw data of a [`{}`] (requires the `debug-display` feature)",
+        ident
+    );
 
     let eager_type = if use_eager_type {
         quote! {
@@ -467,7 +584,7 @@ pub fn impl_elusiv_account(ast: &syn::DeriveInput, attrs: TokenStream) -> TokenS
             #[cfg(feature = "elusiv-client")]
             impl elusiv_types::accounts::EagerAccountRepr for #eager_ident {
                 fn new(data: Vec<u8>) -> Result<Self, std::io::Error> {
-                    if data.len() != < #ident < #anonymous_lifetimes > as elusiv_types::accounts::SizedAccount>::SIZE {
+                    if #invalid_data_len < #ident < #anonymous_lifetimes > as elusiv_types::accounts::SizedAccount>::SIZE {
                         return Err(std::io::Error::new(std::io::ErrorKind::Other, "Invalid account data len"))
                     }
 
@@ -496,7 +613,7 @@ pub fn impl_elusiv_account(ast: &syn::DeriveInput, attrs: TokenStream) -> TokenS
 
         impl < #lifetimes > elusiv_types::accounts::ProgramAccount < #program_account_lifetime > for #ident < #lifetimes > {
             fn new(data: &'a mut [u8]) -> Result<Self, solana_program::program_error::ProgramError> {
-                if data.len() != <Self as elusiv_types::accounts::SizedAccount>::SIZE {
+                if #invalid_data_len <Self as elusiv_types::accounts::SizedAccount>::SIZE {
                     return Err(solana_program::program_error::ProgramError::InvalidAccountData)
                 }
 
@@ -522,7 +639,40 @@ pub fn impl_elusiv_account(ast: &syn::DeriveInput, attrs: TokenStream) -> TokenS
         }
 
         #eager_type
+
+        #[cfg(feature = "debug-display")]
+        impl < #lifetimes > std::fmt::Debug for #ident < #lifetimes > {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                let mut debug = f.debug_struct(stringify!(#ident));
+                #debug_fields
+                debug.finish()
+            }
+        }
+
+        #[cfg(feature = "debug-display")]
+        #[doc = #dump_doc]
+        #[allow(dead_code)]
+        #vis fn #dump_ident(data: &[u8]) -> String {
+            let len = data.len();
+            let mut data = data.to_vec();
+            match < #ident < #anonymous_lifetimes > as elusiv_types::accounts::ProgramAccount>::new(&mut data) {
+                Ok(account) => format!("{:#?}", account),
+                Err(_) => format!("Invalid {} data ({} bytes)", stringify!(#ident), len),
+            }
+        }
+    }
+}
+
+/// Converts an upper camel case identifier into snake case
+fn camel_to_snake(camel: &str) -> String {
+    let mut snake = String::new();
+    for (i, char) in camel.chars().enumerate() {
+        if char.is_uppercase() && i > 0 {
+            snake.push('_');
+        }
+        snake.push_str(&char.to_lowercase().to_string());
     }
+    snake
 }
 
 /// Matches attributes with the syntac `ident: value, ..` with value being a `TokenStream`
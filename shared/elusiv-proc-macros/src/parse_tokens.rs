@@ -1,4 +1,4 @@
-use elusiv_proc_macro_utils::pubkey_bytes;
+use elusiv_proc_macro_utils::{program_pda, pubkey_bytes};
 use proc_macro2::TokenStream;
 use quote::quote;
 use serde::{Deserialize, Serialize};
@@ -6,6 +6,10 @@ use std::fs;
 
 const TOKEN_TOML_PATH: &str = "/Token.toml";
 
+/// Seeds of the `TestMintAccount` and `TestPriceAccount` PDAs
+const TEST_MINT_SEED: &[u8] = b"TestMint";
+const TEST_PRICE_SEED: &[u8] = b"TestPrice";
+
 #[derive(Serialize, Deserialize, Debug)]
 struct Tokens {
     token: Vec<Token>,
@@ -43,7 +47,6 @@ pub fn impl_parse_tokens() -> TokenStream {
             .unwrap();
         let id = i as u16;
         symbols.extend(quote! {
-            #[cfg(feature = "elusiv-client")]
             pub const #sym: u16 = #id;
 
             #[cfg(feature = "elusiv-client")]
@@ -57,13 +60,18 @@ pub fn impl_parse_tokens() -> TokenStream {
         let min = token.min;
         let max = token.max;
 
-        let mint = if cfg!(feature = "devnet") {
+        // `devnet-tools` builds register the program's test-mints and test-price-accounts
+        let mint = if cfg!(feature = "devnet-tools") && i > 0 {
+            pubkey_bytes(&program_pda("elusiv", TEST_MINT_SEED, i as u32).to_string())
+        } else if cfg!(feature = "devnet") {
             pubkey_bytes(&token.mint_devnet)
         } else {
             pubkey_bytes(&token.mint)
         };
 
-        let pyth_usd_price_key = if cfg!(feature = "devnet") {
+        let pyth_usd_price_key = if cfg!(feature = "devnet-tools") {
+            pubkey_bytes(&program_pda("elusiv", TEST_PRICE_SEED, i as u32).to_string())
+        } else if cfg!(feature = "devnet") {
             pubkey_bytes(&token.pyth_usd_price_devnet)
         } else {
             pubkey_bytes(&token.pyth_usd_price_mainnet)
@@ -0,0 +1,127 @@
+//! Parsing of the `cu_checkpoint!` logs (`cu-trace` feature) and management of compute-unit baselines
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+const CHECKPOINT_PREFIX: &str = "Program log: cu-checkpoint: ";
+const CONSUMPTION_PREFIX: &str = "Program consumption: ";
+const CONSUMPTION_SUFFIX: &str = " units remaining";
+
+/// Setting this environment variable overwrites the baselines with the measured values
+pub const UPDATE_BASELINES_ENV: &str = "UPDATE_CU_BASELINES";
+
+/// Maps each checkpoint label to the compute units consumed since the previous checkpoint
+///
+/// # Note
+///
+/// - The first checkpoint of each top-level instruction only serves as a starting point.
+/// - If a label is passed multiple times, the maximum consumption is used.
+pub fn parse_cu_checkpoints(logs: &[String]) -> BTreeMap<String, u64> {
+    let mut table = BTreeMap::new();
+    let mut label: Option<&str> = None;
+    let mut previous_remaining: Option<u64> = None;
+
+    for log in logs {
+        // A new top-level instruction resets the previous checkpoint
+        if log.starts_with("Program ") && log.ends_with(" invoke [1]") {
+            previous_remaining = None;
+            label = None;
+        } else if let Some(l) = log.strip_prefix(CHECKPOINT_PREFIX) {
+            label = Some(l);
+        } else if let Some(remaining) = log
+            .strip_prefix(CONSUMPTION_PREFIX)
+            .and_then(|l| l.strip_suffix(CONSUMPTION_SUFFIX))
+            .and_then(|l| l.parse::<u64>().ok())
+        {
+            if let Some(l) = label.take() {
+                if let Some(previous) = previous_remaining {
+                    let consumed = previous.saturating_sub(remaining);
+                    let entry = table.entry(l.to_string()).or_insert(0);
+                    *entry = std::cmp::max(*entry, consumed);
+                }
+                previous_remaining = Some(remaining);
+            }
+        }
+    }
+
+    table
+}
+
+/// Reads baselines stored as `label = compute_units` lines (`#` starts a comment)
+pub fn read_cu_baselines(path: &Path) -> BTreeMap<String, u64> {
+    let content = std::fs::read_to_string(path).unwrap_or_default();
+
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let (label, units) = line
+                .rsplit_once('=')
+                .unwrap_or_else(|| panic!("Invalid baseline line: {}", line));
+            let units = units
+                .trim()
+                .parse()
+                .unwrap_or_else(|_| panic!("Invalid baseline line: {}", line));
+            (label.trim().to_string(), units)
+        })
+        .collect()
+}
+
+pub fn write_cu_baselines(path: &Path, baselines: &BTreeMap<String, u64>) {
+    let mut content = format!(
+        "# Compute-unit baselines per `cu_checkpoint!` label (set `{}` to re-record)\n",
+        UPDATE_BASELINES_ENV
+    );
+    for (label, units) in baselines {
+        content.push_str(&format!("{} = {}\n", label, units));
+    }
+
+    std::fs::write(path, content).unwrap();
+}
+
+/// Asserts that each measured label deviates from its baseline by at most `tolerance_percent`
+///
+/// # Note
+///
+/// Labels without a baseline are recorded. With [`UPDATE_BASELINES_ENV`] set, all baselines are overwritten.
+pub fn check_cu_baselines(path: &Path, table: &BTreeMap<String, u64>, tolerance_percent: u64) {
+    if std::env::var(UPDATE_BASELINES_ENV).is_ok() {
+        write_cu_baselines(path, table);
+        return;
+    }
+
+    let mut baselines = read_cu_baselines(path);
+    let mut violations = Vec::new();
+    let mut recorded = false;
+
+    for (label, units) in table {
+        match baselines.get(label) {
+            Some(baseline) => {
+                let deviation = if units > baseline {
+                    units - baseline
+                } else {
+                    baseline - units
+                };
+                if deviation * 100 > baseline * tolerance_percent {
+                    violations.push(format!("{}: {} (baseline {})", label, units, baseline));
+                }
+            }
+            None => {
+                baselines.insert(label.clone(), *units);
+                recorded = true;
+            }
+        }
+    }
+
+    if recorded {
+        write_cu_baselines(path, &baselines);
+    }
+
+    assert!(
+        violations.is_empty(),
+        "Compute units outside of the {}% tolerance:\n{}",
+        tolerance_percent,
+        violations.join("\n")
+    );
+}
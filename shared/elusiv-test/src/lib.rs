@@ -1,6 +1,8 @@
 #![allow(dead_code)]
 #![allow(unused_macros)]
 
+pub mod compute_units;
+
 use elusiv_types::tokens::{
     elusiv_token, pyth_price_account_data, Lamports, Price, SPLToken, Token, TOKENS,
 };
@@ -32,6 +34,9 @@ pub struct ElusivProgramTest {
     context: ProgramTestContext,
     spl_tokens: Vec<u16>,
     programs: Vec<Program>,
+
+    /// Logs of all simulated transactions since [`ElusivProgramTest::start_cu_trace`] (`None` if not tracing)
+    cu_trace: Option<Vec<String>>,
 }
 
 impl ElusivProgramTest {
@@ -46,6 +51,7 @@ impl ElusivProgramTest {
             context,
             spl_tokens: Vec::new(),
             programs: programs.to_vec(),
+            cu_trace: None,
         }
     }
 
@@ -107,6 +113,19 @@ impl ElusivProgramTest {
         tx.try_sign(&signing_keypairs, self.context.last_blockhash)
             .or(Err(BanksClientError::ClientError("Signature failure")))?;
 
+        if self.cu_trace.is_some() {
+            let simulation = self
+                .context
+                .banks_client
+                .simulate_transaction(tx.clone())
+                .await?;
+
+            if let (Some(trace), Some(details)) = (&mut self.cu_trace, simulation.simulation_details)
+            {
+                trace.extend(details.logs);
+            }
+        }
+
         self.context
             .banks_client
             .process_transaction_with_preflight(tx)
@@ -124,6 +143,16 @@ impl ElusivProgramTest {
             .await
     }
 
+    /// Starts recording the logs of all following transactions (used for parsing [`compute_units`] checkpoints)
+    pub fn start_cu_trace(&mut self) {
+        self.cu_trace = Some(Vec::new());
+    }
+
+    /// Stops recording and returns all logs recorded since [`Self::start_cu_trace`]
+    pub fn take_cu_trace(&mut self) -> Vec<String> {
+        self.cu_trace.take().unwrap_or_default()
+    }
+
     pub fn context(&mut self) -> &mut ProgramTestContext {
         &mut self.context
     }
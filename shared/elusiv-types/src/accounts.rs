@@ -112,6 +112,21 @@ pub trait ParentAccount<'a, 'b, 't>: ProgramAccount<'a> {
     /// - returns [`None`] if the child-account has not been set
     fn get_child_pubkey(&self, index: usize) -> Option<Pubkey>;
 
+    /// Bitmap of the child-accounts whose owner and size have been verified once
+    /// - parent-accounts without a `verified_children` field never skip any verification
+    fn verified_children_bitmap(&self) -> u64 {
+        0
+    }
+
+    /// Persistently sets the bitmap of verified child-accounts
+    fn set_verified_children_bitmap(&mut self, _bitmap: u64) {}
+
+    /// Returns `true` if the child-account at `child_index` has been verified once (and it's pubkey not changed since)
+    fn is_child_verified(&self, child_index: usize) -> bool {
+        child_index < u64::BITS as usize
+            && self.verified_children_bitmap() & (1 << child_index) != 0
+    }
+
     /// Returns the child-accounts [`AccountInfo`] for the specified index
     ///
     /// # Safety
@@ -131,6 +146,7 @@ pub trait ParentAccount<'a, 'b, 't>: ProgramAccount<'a> {
     /// - All matched accounts are consumed from the iterator.
     /// - The accounts need to match the order in which their pubkeys are stored.
     /// - Any account which pubkey has been previously set can be used.
+    /// - The owner check is skipped for verified child-accounts (see [`Self::is_child_verified`]).
     fn find_child_accounts<'c, 'd, I>(
         parent: &Self,
         program_id: &Pubkey,
@@ -161,7 +177,7 @@ pub trait ParentAccount<'a, 'b, 't>: ProgramAccount<'a> {
                             None => continue,
                         }
 
-                        if account.owner != program_id {
+                        if !parent.is_child_verified(child_index) && account.owner != program_id {
                             return Err(ProgramError::IllegalOwner);
                         }
 
@@ -417,10 +433,113 @@ impl<'a, 'b> UnverifiedAccountInfo<'a, 'b> {
     }
 }
 
+/// The expected state of an account passed to an instruction (emitted by the `ElusivInstruction` derive)
+#[cfg(feature = "elusiv-client")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountSlot {
+    pub pubkey: Pubkey,
+
+    /// `None` if the account can be owned by any program (or does not need to exist)
+    pub expected_owner: Option<Pubkey>,
+    pub min_size: usize,
+    pub must_be_writable: bool,
+}
+
+#[cfg(feature = "elusiv-client")]
+impl AccountSlot {
+    pub fn new(
+        pubkey: Pubkey,
+        expected_owner: Option<Pubkey>,
+        min_size: usize,
+        must_be_writable: bool,
+    ) -> Self {
+        Self {
+            pubkey,
+            expected_owner,
+            min_size,
+            must_be_writable,
+        }
+    }
+}
+
+/// The fetched on-chain state of an account
+#[cfg(feature = "elusiv-client")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountSnapshot {
+    pub owner: Pubkey,
+    pub data_len: usize,
+    pub executable: bool,
+}
+
+#[cfg(feature = "elusiv-client")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    MissingAccount(Pubkey),
+    InvalidOwner {
+        pubkey: Pubkey,
+        expected: Pubkey,
+        actual: Pubkey,
+    },
+    AccountTooSmall {
+        pubkey: Pubkey,
+        min_size: usize,
+        size: usize,
+    },
+    ReadonlyAccount(Pubkey),
+}
+
+/// Checks the fetched accounts against the [`AccountSlot`]s of an instruction
+///
+/// # Note
+///
+/// Slots without an expected owner and a minimum size of zero are not required to be part of `rpc_snapshot`.
+#[cfg(feature = "elusiv-client")]
+pub fn validate_accounts(
+    slots: &[AccountSlot],
+    rpc_snapshot: &std::collections::HashMap<Pubkey, AccountSnapshot>,
+) -> Result<(), ValidationError> {
+    for slot in slots {
+        let snapshot = match rpc_snapshot.get(&slot.pubkey) {
+            Some(snapshot) => snapshot,
+            None => {
+                if slot.expected_owner.is_none() && slot.min_size == 0 {
+                    continue;
+                }
+                return Err(ValidationError::MissingAccount(slot.pubkey));
+            }
+        };
+
+        if let Some(expected) = slot.expected_owner {
+            if snapshot.owner != expected {
+                return Err(ValidationError::InvalidOwner {
+                    pubkey: slot.pubkey,
+                    expected,
+                    actual: snapshot.owner,
+                });
+            }
+        }
+
+        if snapshot.data_len < slot.min_size {
+            return Err(ValidationError::AccountTooSmall {
+                pubkey: slot.pubkey,
+                min_size: slot.min_size,
+                size: snapshot.data_len,
+            });
+        }
+
+        // Executable accounts can never be written to
+        if slot.must_be_writable && snapshot.executable {
+            return Err(ValidationError::ReadonlyAccount(slot.pubkey));
+        }
+    }
+
+    Ok(())
+}
+
 macro_rules! impl_user_account {
     ($ty: ident) => {
         #[cfg(feature = "elusiv-client")]
-        #[derive(Debug)]
+        #[derive(Debug, Clone)]
         pub struct $ty(pub Pubkey);
 
         #[cfg(feature = "elusiv-client")]
@@ -51,6 +51,8 @@ impl_borsh_sized!(u16, 2);
 impl_borsh_sized!(u32, 4);
 impl_borsh_sized!(u64, 8);
 impl_borsh_sized!(u128, 16);
+impl_borsh_sized!(i32, 4);
+impl_borsh_sized!(i64, 8);
 
 impl_borsh_sized!(bool, 1);
 impl_borsh_sized!(std::net::Ipv4Addr, 4);
@@ -0,0 +1,93 @@
+//! Human-readable representations of account field values (used by the `Debug` impls of `elusiv_account`s)
+
+use std::fmt;
+
+/// Unsigned 256 bit little-endian integer displayed as a decimal number
+pub struct Decimal(pub [u8; 32]);
+
+impl fmt::Debug for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut value = self.0;
+        let mut digits = Vec::new();
+
+        loop {
+            // Long division by 10, starting with the most significant byte
+            let mut remainder = 0u16;
+            let mut is_zero = true;
+            for byte in value.iter_mut().rev() {
+                let current = (remainder << 8) | *byte as u16;
+                *byte = (current / 10) as u8;
+                remainder = current % 10;
+                is_zero &= *byte == 0;
+            }
+
+            digits.push(b'0' + remainder as u8);
+            if is_zero {
+                break;
+            }
+        }
+
+        digits.reverse();
+        f.write_str(std::str::from_utf8(&digits).map_err(|_| fmt::Error)?)
+    }
+}
+
+/// Lamports displayed as SOL
+pub struct Sol(pub u64);
+
+const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+
+impl fmt::Debug for Sol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}.{:09} SOL",
+            self.0 / LAMPORTS_PER_SOL,
+            self.0 % LAMPORTS_PER_SOL
+        )
+    }
+}
+
+/// Placeholder for fields that are not deserialized
+pub struct Bytes(pub usize);
+
+impl fmt::Debug for Bytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<{} bytes>", self.0)
+    }
+}
+
+/// Placeholder for fields that cannot be deserialized
+pub struct Invalid(pub usize);
+
+impl fmt::Debug for Invalid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<invalid: {} bytes>", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decimal() {
+        assert_eq!(format!("{:?}", Decimal([0; 32])), "0");
+
+        let mut v = [0; 32];
+        v[0] = 255;
+        v[1] = 1;
+        assert_eq!(format!("{:?}", Decimal(v)), "511");
+
+        assert_eq!(
+            format!("{:?}", Decimal([255; 32])),
+            "115792089237316195423570985008687907853269984665640564039457584007913129639935"
+        );
+    }
+
+    #[test]
+    fn test_sol() {
+        assert_eq!(format!("{:?}", Sol(0)), "0.000000000 SOL");
+        assert_eq!(format!("{:?}", Sol(1_500_000_001)), "1.500000001 SOL");
+    }
+}
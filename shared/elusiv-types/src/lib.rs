@@ -2,6 +2,10 @@
 pub mod accounts;
 #[cfg(feature = "bytes")]
 pub mod bytes;
+#[cfg(feature = "debug-display")]
+pub mod display;
+#[cfg(feature = "tokens")]
+pub mod rounding;
 #[cfg(feature = "tokens")]
 pub mod tokens;
 
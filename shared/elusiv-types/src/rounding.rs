@@ -0,0 +1,134 @@
+//! Rounding policy for all token/lamport conversions and fee calculations
+//!
+//! Every rounding favors the pool ([`RoundingDirection::FavorPool`]), so no conversion can leak value out of the pool:
+//! - fees (and rents charged to the user) are rounded up ([`FEE`])
+//! - payouts (e.g. subventions) are rounded down ([`PAYOUT`])
+//! - refunds are rounded down ([`REFUND`])
+
+/// The party that benefits from a rounding
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "elusiv-client"), derive(Debug))]
+pub enum RoundingDirection {
+    FavorPool,
+    FavorUser,
+}
+
+/// The flow of a rounded amount between the user and the pool
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "elusiv-client"), derive(Debug))]
+pub enum AmountKind {
+    /// Paid by the user
+    Fee,
+
+    /// Paid to the user
+    Payout,
+
+    /// Returned to the user
+    Refund,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "elusiv-client"), derive(Debug))]
+pub struct Rounding {
+    pub kind: AmountKind,
+    pub direction: RoundingDirection,
+}
+
+pub const FEE: Rounding = Rounding {
+    kind: AmountKind::Fee,
+    direction: RoundingDirection::FavorPool,
+};
+
+pub const PAYOUT: Rounding = Rounding {
+    kind: AmountKind::Payout,
+    direction: RoundingDirection::FavorPool,
+};
+
+pub const REFUND: Rounding = Rounding {
+    kind: AmountKind::Refund,
+    direction: RoundingDirection::FavorPool,
+};
+
+impl Rounding {
+    pub const fn rounds_up(&self) -> bool {
+        match self.kind {
+            AmountKind::Fee => matches!(self.direction, RoundingDirection::FavorPool),
+            AmountKind::Payout | AmountKind::Refund => {
+                matches!(self.direction, RoundingDirection::FavorUser)
+            }
+        }
+    }
+}
+
+/// Computes `numerator / denominator`
+pub fn div_rounded(numerator: u128, denominator: u128, rounding: Rounding) -> Option<u128> {
+    let quotient = numerator.checked_div(denominator)?;
+    let remainder = numerator - quotient * denominator;
+
+    if rounding.rounds_up() && remainder > 0 {
+        quotient.checked_add(1)
+    } else {
+        Some(quotient)
+    }
+}
+
+/// Computes `numerator / denominator * 10^exponent`
+pub fn scale_rounded(
+    numerator: u128,
+    denominator: u128,
+    exponent: i32,
+    rounding: Rounding,
+) -> Option<u128> {
+    let power = 10u128.checked_pow(exponent.unsigned_abs())?;
+
+    if exponent >= 0 {
+        div_rounded(numerator.checked_mul(power)?, denominator, rounding)
+    } else {
+        div_rounded(numerator, denominator.checked_mul(power)?, rounding)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rounds_up() {
+        assert!(FEE.rounds_up());
+        assert!(!PAYOUT.rounds_up());
+        assert!(!REFUND.rounds_up());
+
+        for kind in [AmountKind::Fee, AmountKind::Payout, AmountKind::Refund] {
+            let favor_pool = Rounding {
+                kind,
+                direction: RoundingDirection::FavorPool,
+            };
+            let favor_user = Rounding {
+                kind,
+                direction: RoundingDirection::FavorUser,
+            };
+            assert_ne!(favor_pool.rounds_up(), favor_user.rounds_up());
+        }
+    }
+
+    #[test]
+    fn test_div_rounded() {
+        assert_eq!(div_rounded(10, 5, FEE), Some(2));
+        assert_eq!(div_rounded(10, 5, PAYOUT), Some(2));
+        assert_eq!(div_rounded(11, 5, FEE), Some(3));
+        assert_eq!(div_rounded(11, 5, PAYOUT), Some(2));
+        assert_eq!(div_rounded(0, 5, FEE), Some(0));
+        assert_eq!(div_rounded(1, 0, FEE), None);
+        assert_eq!(div_rounded(u128::MAX, 2, FEE), Some(u128::MAX / 2 + 1));
+    }
+
+    #[test]
+    fn test_scale_rounded() {
+        assert_eq!(scale_rounded(1, 3, 2, FEE), Some(34));
+        assert_eq!(scale_rounded(1, 3, 2, REFUND), Some(33));
+        assert_eq!(scale_rounded(1_001, 1, -3, FEE), Some(2));
+        assert_eq!(scale_rounded(1_001, 1, -3, REFUND), Some(1));
+        assert_eq!(scale_rounded(1, 1, 39, FEE), None);
+        assert_eq!(scale_rounded(1, 1, -39, PAYOUT), None);
+    }
+}
@@ -1,4 +1,5 @@
 use crate as elusiv_types;
+use crate::rounding::{scale_rounded, Rounding};
 use borsh::{BorshDeserialize, BorshSerialize};
 use elusiv_derive::BorshSerDeSized;
 use solana_program::{
@@ -10,6 +11,7 @@ use std::{
     ops::{Add, Sub},
 };
 
+use pyth_sdk_solana::state::{load_price_account, PriceAccount};
 pub use pyth_sdk_solana::{load_price_feed_from_account_info, Price};
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -64,13 +66,16 @@ impl Token {
     }
 
     pub fn new_checked(token_id: TokenID, amount: u64) -> Result<Self, TokenError> {
-        let id = token_id as usize;
-
-        if id >= TOKENS.len() {
-            return Err(TokenError::InvalidTokenID);
-        }
+        Self::new_checked_with_token(token_id, amount, &elusiv_token(token_id)?)
+    }
 
-        if amount < TOKENS[id].min || amount > TOKENS[id].max {
+    /// Checks `amount` against the bounds of a token whose metadata is supplied by the caller (and not necessarily part of [`TOKENS`])
+    pub fn new_checked_with_token(
+        token_id: TokenID,
+        amount: u64,
+        token: &ElusivToken,
+    ) -> Result<Self, TokenError> {
+        if amount < token.min || amount > token.max {
             return Err(TokenError::InvalidAmount);
         }
 
@@ -81,18 +86,19 @@ impl Token {
         token_id: u16,
         price: Price,
         check_amount: bool,
+        rounding: Rounding,
     ) -> Result<Self, TokenError> {
         let target_expo = if token_id == 0 {
             0
         } else {
             -(elusiv_token(token_id)?.decimals as i32)
         };
-        let amount = price
-            .scale_to_exponent(target_expo)
-            .ok_or(TokenError::PriceError)?
-            .price
-            .try_into()
-            .or(Err(TokenError::PriceError))?;
+        let amount = scaled_amount(
+            price.price.try_into().or(Err(TokenError::PriceError))?,
+            1,
+            &[price.expo, -target_expo],
+            rounding,
+        )?;
 
         if check_amount {
             Self::new_checked(token_id, amount)
@@ -182,12 +188,27 @@ impl Sub for Token {
 #[derive(
     BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Eq, Clone, Copy, Default,
 )]
-#[cfg_attr(feature = "elusiv-client", derive(Debug))]
+#[cfg_attr(
+    all(feature = "elusiv-client", not(feature = "debug-display")),
+    derive(Debug)
+)]
 pub struct Lamports(pub u64);
 
+#[cfg(feature = "debug-display")]
+impl std::fmt::Debug for Lamports {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&crate::display::Sol(self.0), f)
+    }
+}
+
 impl Lamports {
-    pub fn into_token(&self, price: &TokenPrice, token_id: TokenID) -> Result<Token, TokenError> {
-        price.lamports_into_token(self, token_id)
+    pub fn into_token(
+        &self,
+        price: &TokenPrice,
+        token_id: TokenID,
+        rounding: Rounding,
+    ) -> Result<Token, TokenError> {
+        price.lamports_into_token(self, token_id, rounding)
     }
 
     pub fn into_token_strict(&self) -> Token {
@@ -224,6 +245,15 @@ impl SPLToken {
 pub fn verify_token_account(
     account: &AccountInfo,
     token_id: TokenID,
+) -> Result<bool, ProgramError> {
+    verify_token_account_with_mint(account, token_id, &elusiv_token(token_id)?.mint)
+}
+
+/// [`verify_token_account`] for a token whose `mint` is supplied by the caller (ignored for Lamports)
+pub fn verify_token_account_with_mint(
+    account: &AccountInfo,
+    token_id: TokenID,
+    mint: &Pubkey,
 ) -> Result<bool, ProgramError> {
     if token_id == 0 {
         Ok(*account.owner != spl_token::ID)
@@ -235,20 +265,66 @@ pub fn verify_token_account(
         let data = &account.data.borrow()[..];
         let account = spl_token::state::Account::unpack(data)?;
 
-        Ok(account.mint == elusiv_token(token_id)?.mint)
+        Ok(account.mint == *mint)
     }
 }
 
+/// Ensures that a given account is a token account for the specified token, owned by `owner`
+/// - `owner` can be any address (including a PDA)
+pub fn verify_token_account_owner(
+    account: &AccountInfo,
+    owner: &Pubkey,
+    token_id: TokenID,
+) -> Result<bool, ProgramError> {
+    verify_token_account_owner_with_mint(account, owner, token_id, &elusiv_token(token_id)?.mint)
+}
+
+/// [`verify_token_account_owner`] for a token whose `mint` is supplied by the caller (ignored for Lamports)
+pub fn verify_token_account_owner_with_mint(
+    account: &AccountInfo,
+    owner: &Pubkey,
+    token_id: TokenID,
+    mint: &Pubkey,
+) -> Result<bool, ProgramError> {
+    if token_id == 0 {
+        return Ok(*account.key == *owner);
+    }
+
+    if !verify_token_account_with_mint(account, token_id, mint)? {
+        return Ok(false);
+    }
+
+    let data = &account.data.borrow()[..];
+    let account = spl_token::state::Account::unpack(data)?;
+
+    Ok(account.owner == *owner)
+}
+
 /// Verifies an associated-token-account for a given token-id
 pub fn verify_associated_token_account(
     wallet_address: &Pubkey,
     token_account_address: &Pubkey,
     token_id: TokenID,
+) -> Result<bool, ProgramError> {
+    verify_associated_token_account_with_mint(
+        wallet_address,
+        token_account_address,
+        token_id,
+        &elusiv_token(token_id)?.mint,
+    )
+}
+
+/// [`verify_associated_token_account`] for a token whose `mint` is supplied by the caller (ignored for Lamports)
+pub fn verify_associated_token_account_with_mint(
+    wallet_address: &Pubkey,
+    token_account_address: &Pubkey,
+    token_id: TokenID,
+    mint: &Pubkey,
 ) -> Result<bool, ProgramError> {
     if token_id == 0 {
         Ok(*wallet_address == *token_account_address)
     } else {
-        let expected = get_associated_token_address(wallet_address, &elusiv_token(token_id)?.mint);
+        let expected = get_associated_token_address(wallet_address, mint);
 
         Ok(*token_account_address == expected)
     }
@@ -258,6 +334,15 @@ pub struct TokenPrice {
     pub lamports_usd: Price,
     pub token_usd: Price,
     pub token_id: TokenID,
+
+    /// The slot at which `lamports_usd` has been published (`0` if not loaded from a Pyth price account)
+    pub lamports_usd_publish_slot: u64,
+
+    /// The slot at which `token_usd` has been published (`0` if not loaded from a Pyth price account)
+    pub token_usd_publish_slot: u64,
+
+    /// The decimals of the token (`None` for unknown tokens)
+    token_decimals: Option<u8>,
 }
 
 impl TokenPrice {
@@ -269,27 +354,56 @@ impl TokenPrice {
         if token_id == 0 {
             Ok(Self::new_lamports())
         } else {
-            let lamports = TOKENS[0];
-            let token = TOKENS[token_id as usize];
+            Self::new_with_token(
+                sol_usd_price_account,
+                token_usd_price_account,
+                token_id,
+                &elusiv_token(token_id)?,
+            )
+        }
+    }
 
-            if lamports.pyth_usd_price_key != *sol_usd_price_account.key {
-                return Err(TokenError::InvalidPriceAccount.into());
-            }
+    /// Loads the price of a token whose metadata is supplied by the caller (and not necessarily part of [`TOKENS`])
+    pub fn new_with_token(
+        sol_usd_price_account: &AccountInfo,
+        token_usd_price_account: &AccountInfo,
+        token_id: TokenID,
+        token: &ElusivToken,
+    ) -> Result<Self, ProgramError> {
+        if token_id == 0 {
+            return Ok(Self::new_lamports());
+        }
 
-            if token.pyth_usd_price_key != *token_usd_price_account.key {
-                return Err(TokenError::InvalidPriceAccount.into());
-            }
+        let lamports = TOKENS[0];
 
-            let lamports_usd = Self::load_token_usd_price(sol_usd_price_account, 0)?;
-            let token_usd = Self::load_token_usd_price(token_usd_price_account, token_id)?;
+        if lamports.pyth_usd_price_key != *sol_usd_price_account.key {
+            return Err(TokenError::InvalidPriceAccount.into());
+        }
 
-            Ok(Self::new_from_price(lamports_usd, token_usd, token_id))
+        if token.pyth_usd_price_key != *token_usd_price_account.key {
+            return Err(TokenError::InvalidPriceAccount.into());
         }
+
+        let lamports_usd = Self::load_usd_price(sol_usd_price_account, &lamports)?;
+        let token_usd = Self::load_usd_price(token_usd_price_account, token)?;
+
+        Ok(Self {
+            lamports_usd_publish_slot: Self::load_publish_slot(sol_usd_price_account)?,
+            token_usd_publish_slot: Self::load_publish_slot(token_usd_price_account)?,
+            ..Self::new_from_token_price(lamports_usd, token_usd, token_id, token)
+        })
     }
 
     pub fn load_token_usd_price(
         token_usd_price_account: &AccountInfo,
         token_id: TokenID,
+    ) -> Result<Price, TokenError> {
+        Self::load_usd_price(token_usd_price_account, &elusiv_token(token_id)?)
+    }
+
+    fn load_usd_price(
+        token_usd_price_account: &AccountInfo,
+        token: &ElusivToken,
     ) -> Result<Price, TokenError> {
         let price_feed = load_price_feed_from_account_info(token_usd_price_account)
             .or(Err(TokenError::PriceError))?;
@@ -299,12 +413,21 @@ impl TokenPrice {
             .ok_or(TokenError::PriceError)?;
 
         let price = base_price
-            .cmul(1, -(elusiv_token(token_id)?.price_base_exp as i32))
+            .cmul(1, -(token.price_base_exp as i32))
             .ok_or(TokenError::PriceError)?;
 
         Ok(price)
     }
 
+    /// The slot at which the (aggregate) price of a Pyth price account has been published
+    fn load_publish_slot(price_account: &AccountInfo) -> Result<u64, TokenError> {
+        let data = price_account.data.borrow();
+        let account: &PriceAccount =
+            load_price_account(&data[..]).or(Err(TokenError::PriceError))?;
+
+        Ok(account.agg.pub_slot)
+    }
+
     pub fn new_from_price(lamports_usd: Price, token_usd: Price, token_id: TokenID) -> Self {
         if token_id == 0 {
             Self::new_lamports()
@@ -313,6 +436,29 @@ impl TokenPrice {
                 lamports_usd,
                 token_usd,
                 token_id,
+                lamports_usd_publish_slot: 0,
+                token_usd_publish_slot: 0,
+                token_decimals: elusiv_token(token_id).ok().map(|token| token.decimals),
+            }
+        }
+    }
+
+    pub fn new_from_token_price(
+        lamports_usd: Price,
+        token_usd: Price,
+        token_id: TokenID,
+        token: &ElusivToken,
+    ) -> Self {
+        if token_id == 0 {
+            Self::new_lamports()
+        } else {
+            Self {
+                lamports_usd,
+                token_usd,
+                token_id,
+                lamports_usd_publish_slot: 0,
+                token_usd_publish_slot: 0,
+                token_decimals: Some(token.decimals),
             }
         }
     }
@@ -333,6 +479,9 @@ impl TokenPrice {
                 lamports_usd,
                 token_usd,
                 token_id,
+                lamports_usd_publish_slot: 0,
+                token_usd_publish_slot: 0,
+                token_decimals: elusiv_token(token_id).ok().map(|token| token.decimals),
             })
         }
     }
@@ -350,10 +499,23 @@ impl TokenPrice {
                 expo: 0,
             },
             token_id: 0,
+            lamports_usd_publish_slot: 0,
+            token_usd_publish_slot: 0,
+            token_decimals: Some(TOKENS[0].decimals),
         }
     }
 
-    pub fn token_into_lamports(&self, token: Token) -> Result<Lamports, TokenError> {
+    fn token_decimals(&self) -> Result<i32, TokenError> {
+        self.token_decimals
+            .map(|decimals| decimals as i32)
+            .ok_or(TokenError::InvalidTokenID)
+    }
+
+    pub fn token_into_lamports(
+        &self,
+        token: Token,
+        rounding: Rounding,
+    ) -> Result<Lamports, TokenError> {
         if token.token_id() != self.token_id {
             return Err(TokenError::InvalidTokenID);
         }
@@ -362,24 +524,25 @@ impl TokenPrice {
             return Ok(Lamports(token.amount()));
         }
 
-        let usd = self
-            .token_usd
-            .mul(&Price {
-                price: token.amount().try_into().unwrap(),
-                conf: 0,
-                expo: -(elusiv_token(self.token_id)?.decimals as i32),
-            })
-            .ok_or(TokenError::PriceError)?;
-        let price = usd
-            .get_price_in_quote(&self.lamports_usd, 0)
-            .ok_or(TokenError::PriceError)?;
-        Token::new_from_price(0, price, false)?.into_lamports()
+        // amount * 10^{-decimals} * token_usd / lamports_usd
+        let amount = scaled_amount(
+            token.amount() as u128 * positive_price(self.token_usd.price)?,
+            positive_price(self.lamports_usd.price)?,
+            &[
+                self.token_usd.expo,
+                -self.token_decimals()?,
+                -self.lamports_usd.expo,
+            ],
+            rounding,
+        )?;
+        Ok(Lamports(amount))
     }
 
     pub fn lamports_into_token(
         &self,
         lamports: &Lamports,
         token_id: TokenID,
+        rounding: Rounding,
     ) -> Result<Token, TokenError> {
         if token_id != self.token_id {
             return Err(TokenError::InvalidTokenID);
@@ -389,26 +552,55 @@ impl TokenPrice {
             return Ok(lamports.into_token_strict());
         }
 
-        let usd = self
-            .lamports_usd
-            .mul(&Price {
-                price: lamports.0.try_into().unwrap(),
-                conf: 0,
-                expo: 0,
-            })
-            .ok_or(TokenError::PriceError)?;
-        let price = usd
-            .get_price_in_quote(
-                &self.token_usd,
-                -(elusiv_token(self.token_id)?.decimals as i32),
-            )
-            .ok_or(TokenError::PriceError)?;
-        Token::new_from_price(token_id, price, false)
+        // lamports * lamports_usd / token_usd * 10^{decimals}
+        let amount = scaled_amount(
+            lamports.0 as u128 * positive_price(self.lamports_usd.price)?,
+            positive_price(self.token_usd.price)?,
+            &[
+                self.lamports_usd.expo,
+                -self.token_usd.expo,
+                self.token_decimals()?,
+            ],
+            rounding,
+        )?;
+        Ok(Token::new(token_id, amount))
     }
 }
 
-#[cfg(feature = "test-elusiv")]
+fn positive_price(price: i64) -> Result<u128, TokenError> {
+    match price.try_into() {
+        Ok(price) if price > 0 => Ok(price),
+        _ => Err(TokenError::PriceError),
+    }
+}
+
+/// Computes `numerator / denominator * 10^{sum(exponents)}` as a token amount
+fn scaled_amount(
+    numerator: u128,
+    denominator: u128,
+    exponents: &[i32],
+    rounding: Rounding,
+) -> Result<u64, TokenError> {
+    let exponent = exponents
+        .iter()
+        .try_fold(0i32, |acc, e| acc.checked_add(*e))
+        .ok_or(TokenError::PriceError)?;
+
+    scale_rounded(numerator, denominator, exponent, rounding)
+        .and_then(|amount| amount.try_into().ok())
+        .ok_or(TokenError::PriceError)
+}
+
+#[cfg(any(feature = "test-elusiv", feature = "devnet-tools"))]
 pub fn pyth_price_account_data(price: &Price) -> Result<Vec<u8>, TokenError> {
+    pyth_price_account_data_with_publish_slot(price, 0)
+}
+
+#[cfg(any(feature = "test-elusiv", feature = "devnet-tools"))]
+pub fn pyth_price_account_data_with_publish_slot(
+    price: &Price,
+    publish_slot: u64,
+) -> Result<Vec<u8>, TokenError> {
     use bytemuck::bytes_of;
     use pyth_sdk_solana::{
         state::{AccountType, MAGIC, VERSION_2},
@@ -427,14 +619,21 @@ pub fn pyth_price_account_data(price: &Price) -> Result<Vec<u8>, TokenError> {
     account.agg.conf = price.conf;
     account.prev_conf = price.conf;
     account.agg.status = PriceStatus::Trading;
+    account.agg.pub_slot = publish_slot;
 
     Ok(bytes_of(&account).to_vec())
 }
 
 #[cfg(feature = "test-elusiv")]
 pub fn spl_token_account_data(token_id: TokenID) -> Vec<u8> {
+    spl_token_account_data_with_owner(token_id, &Pubkey::default())
+}
+
+#[cfg(feature = "test-elusiv")]
+pub fn spl_token_account_data_with_owner(token_id: TokenID, owner: &Pubkey) -> Vec<u8> {
     let account = spl_token::state::Account {
         mint: elusiv_token(token_id).unwrap().mint,
+        owner: *owner,
         state: spl_token::state::AccountState::Initialized,
         ..Default::default()
     };
@@ -122,22 +122,51 @@ pub fn create_pda_account<'a>(
 
     let lamports_required = Rent::get()?.minimum_balance(account_size);
     let space: u64 = account_size.try_into().unwrap();
-    guard!(
-        payer.lamports() >= lamports_required,
-        ProgramError::AccountNotRentExempt
-    );
 
-    invoke_signed(
-        &system_instruction::create_account(
-            payer.key,
-            pda_account.key,
-            lamports_required,
-            space,
-            program_id,
-        ),
-        &[payer.clone(), pda_account.clone()],
-        &[signers_seeds],
-    )?;
+    if pda_account.lamports() > 0 {
+        // A third party already funded the PDA (which prevents `create_account`), so we adopt the (system-owned) account instead
+        let lamports_missing = lamports_required.saturating_sub(pda_account.lamports());
+        guard!(
+            payer.lamports() >= lamports_missing,
+            ProgramError::AccountNotRentExempt
+        );
+
+        if lamports_missing > 0 {
+            solana_program::program::invoke(
+                &system_instruction::transfer(payer.key, pda_account.key, lamports_missing),
+                &[payer.clone(), pda_account.clone()],
+            )?;
+        }
+
+        invoke_signed(
+            &system_instruction::allocate(pda_account.key, space),
+            std::slice::from_ref(pda_account),
+            &[signers_seeds],
+        )?;
+
+        invoke_signed(
+            &system_instruction::assign(pda_account.key, program_id),
+            std::slice::from_ref(pda_account),
+            &[signers_seeds],
+        )?;
+    } else {
+        guard!(
+            payer.lamports() >= lamports_required,
+            ProgramError::AccountNotRentExempt
+        );
+
+        invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                pda_account.key,
+                lamports_required,
+                space,
+                program_id,
+            ),
+            &[payer.clone(), pda_account.clone()],
+            &[signers_seeds],
+        )?;
+    }
 
     // Assign default fields
     let mut data = &mut pda_account.data.borrow_mut()[..];